@@ -4,11 +4,16 @@ use tempfile::tempdir;
 use tokio::runtime::Runtime;
 use uuid::Uuid;
 
+use my_budget_server::categories::list_categories;
 use my_budget_server::database::{get_user_db, init_main_db};
+use my_budget_server::models::{GetCategoriesQuery, GetRecordsQuery};
+use my_budget_server::records::list_records;
 
 // Benchmark constants
 const BENCH_BASE_TIMESTAMP: i64 = 1700000000;
 const BENCH_RECORD_COUNT: usize = 1000;
+const BENCH_USAGE_RECORD_COUNT: usize = 10_000;
+const BENCH_USAGE_CATEGORY_COUNT: usize = 50;
 
 async fn setup_benchmark_environment() -> (String, String, tempfile::TempDir) {
     let temp_dir = tempdir().expect("Failed to create temporary directory");
@@ -47,6 +52,51 @@ async fn create_benchmark_records(data_path: &str, user_id: &str, count: usize)
     }
 }
 
+/// Categories with skewed usage: category `i` gets about twice the records of
+/// category `i + 1`, and the last ones none at all.
+async fn create_usage_benchmark_data(data_path: &str, user_id: &str) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.write().await;
+
+    for i in 0..BENCH_USAGE_CATEGORY_COUNT {
+        conn.execute(
+            "INSERT INTO categories (id, name, is_income) VALUES (?, ?, 0)",
+            (
+                format!("usage_category_{}", i),
+                format!("Category {:02}", i),
+            ),
+        )
+        .await
+        .unwrap();
+    }
+    for i in 0..BENCH_USAGE_RECORD_COUNT {
+        let category = format!("usage_category_{}", (i + 1).trailing_zeros());
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES (?, ?, ?, ?, ?)",
+            (
+                Uuid::new_v4().to_string(),
+                format!("Usage Record {}", i),
+                10.0,
+                category,
+                BENCH_BASE_TIMESTAMP + i as i64,
+            ),
+        )
+        .await
+        .unwrap();
+    }
+}
+
+async fn benchmark_list_categories_by_usage(data_path: &str, user_id: &str) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let query = GetCategoriesQuery {
+        sort: Some("usage".to_string()),
+        ..Default::default()
+    };
+
+    let response = list_categories(&user_db, &query).await.unwrap();
+    black_box(response);
+}
+
 async fn benchmark_get_all_records(data_path: &str, user_id: &str) {
     let user_db = get_user_db(data_path, user_id).await.unwrap();
     let conn = user_db.read().await;
@@ -111,6 +161,25 @@ async fn benchmark_count_query(data_path: &str, user_id: &str) {
     }
 }
 
+async fn benchmark_list_records(
+    data_path: &str,
+    user_id: &str,
+    include_total: bool,
+    count_only: bool,
+) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let query = GetRecordsQuery {
+        start_time: Some(BENCH_BASE_TIMESTAMP),
+        end_time: Some(BENCH_BASE_TIMESTAMP + 1000),
+        include_total: Some(include_total),
+        count_only: Some(count_only),
+        ..Default::default()
+    };
+
+    let response = list_records(&user_db, &query).await.unwrap();
+    black_box(response);
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
 
@@ -137,8 +206,37 @@ fn criterion_benchmark(c: &mut Criterion) {
             .iter(|| benchmark_count_query(&data_path, &user_id))
     });
 
+    // GET /records with both queries, then with the count or the row fetch skipped
+    c.bench_function("list_records_with_total", |b| {
+        b.to_async(&rt)
+            .iter(|| benchmark_list_records(&data_path, &user_id, true, false))
+    });
+
+    c.bench_function("list_records_without_total", |b| {
+        b.to_async(&rt)
+            .iter(|| benchmark_list_records(&data_path, &user_id, false, false))
+    });
+
+    c.bench_function("list_records_count_only", |b| {
+        b.to_async(&rt)
+            .iter(|| benchmark_list_records(&data_path, &user_id, true, true))
+    });
+
+    // GET /categories?sort=usage, counting records per category in one grouped join
+    let (usage_data_path, usage_user_id, _usage_temp_dir) =
+        rt.block_on(setup_benchmark_environment());
+    rt.block_on(create_usage_benchmark_data(
+        &usage_data_path,
+        &usage_user_id,
+    ));
+    c.bench_function("list_categories_by_usage", |b| {
+        b.to_async(&rt)
+            .iter(|| benchmark_list_categories_by_usage(&usage_data_path, &usage_user_id))
+    });
+
     // Keep temp_dir alive until the end
     std::mem::forget(_temp_dir);
+    std::mem::forget(_usage_temp_dir);
 }
 
 criterion_group!(benches, criterion_benchmark);
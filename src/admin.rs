@@ -0,0 +1,103 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use tower_sessions::Session;
+
+use crate::auth::{get_user_by_id, remove_account, require_admin};
+use crate::database::{Db, user_db_path};
+use crate::models::{AdminUser, UserRole};
+use crate::utils::{db_error, db_error_with_context, get_database_path, get_user_database};
+
+/// Makes the account named `username` an admin. Returns false if there is none.
+pub async fn grant_admin(main_db: &Db, username: &str) -> anyhow::Result<bool> {
+    let conn = main_db.write().await;
+    let updated = conn
+        .execute(
+            "UPDATE users SET role = ? WHERE name = ?",
+            (UserRole::Admin.as_str(), username),
+        )
+        .await?;
+    Ok(updated > 0)
+}
+
+/// Records in the database of `user_id`, without creating one for users who
+/// never stored anything.
+async fn count_user_records(user_id: &str) -> Result<u64, (StatusCode, String)> {
+    if !user_db_path(get_database_path(), user_id).exists() {
+        return Ok(0);
+    }
+
+    let user_db = get_user_database(user_id).await?;
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query("SELECT COUNT(*) FROM records", ())
+        .await
+        .map_err(|_| db_error_with_context("failed to count records"))?;
+    let count = match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => row.get::<i64>(0).map_err(|_| db_error())?,
+        None => 0,
+    };
+    Ok(count as u64)
+}
+
+/// Every account on the instance with its role and how many records it holds.
+pub async fn list_users(
+    State(main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<Vec<AdminUser>>), (StatusCode, String)> {
+    require_admin(&main_db, &session).await?;
+
+    let mut users = Vec::new();
+    {
+        let conn = main_db.read().await;
+        let mut rows = conn
+            .query(
+                "SELECT id, name, role, created_at FROM users ORDER BY created_at IS NULL, created_at ASC, rowid ASC",
+                (),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query users"))?;
+        while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            let role: String = row.get(2).map_err(|_| db_error())?;
+            users.push(AdminUser {
+                id: row.get(0).map_err(|_| db_error())?,
+                username: row.get(1).map_err(|_| db_error())?,
+                role: UserRole::parse(&role).unwrap_or_default(),
+                created_at: row.get(3).map_err(|_| db_error())?,
+                record_count: 0,
+            });
+        }
+    }
+
+    for user in &mut users {
+        user.record_count = count_user_records(&user.id).await?;
+    }
+
+    Ok((StatusCode::OK, Json(users)))
+}
+
+/// Deletes another account the way DELETE /auth/account would, without its
+/// password. Admins remove their own account through that route instead.
+pub async fn delete_user(
+    State(main_db): State<Db>,
+    session: Session,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let admin = require_admin(&main_db, &session).await?;
+    if admin.id == user_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Use DELETE /auth/account to delete your own account".to_string(),
+        ));
+    }
+
+    let user = get_user_by_id(&main_db, &user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    remove_account(&main_db, &user.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,193 @@
+use axum::{
+    extract::{Query, Request},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use serde::{
+    Deserialize, Deserializer, Serializer,
+    de::{self, Visitor},
+};
+
+use crate::constants::MAX_AMOUNT_STRING_LENGTH;
+
+/// How monetary amounts are rendered in JSON responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmountFormat {
+    /// Plain JSON numbers (the default)
+    #[default]
+    Number,
+    /// Exact two-decimal strings such as "12.50"
+    String,
+}
+
+tokio::task_local! {
+    static AMOUNT_FORMAT: AmountFormat;
+}
+
+/// The format selected for the request currently being served.
+pub fn current_amount_format() -> AmountFormat {
+    AMOUNT_FORMAT.try_with(|format| *format).unwrap_or_default()
+}
+
+/// Runs `future` with the given amount format, e.g. for work spawned off the request task.
+pub async fn with_amount_format<F: Future>(format: AmountFormat, future: F) -> F::Output {
+    AMOUNT_FORMAT.scope(format, future).await
+}
+
+/// Like [`with_amount_format`], for synchronous work such as building a JSON value.
+pub fn with_amount_format_sync<R>(format: AmountFormat, f: impl FnOnce() -> R) -> R {
+    AMOUNT_FORMAT.sync_scope(format, f)
+}
+
+pub fn parse_amount_format(value: &str) -> Result<AmountFormat, (StatusCode, String)> {
+    match value {
+        "number" => Ok(AmountFormat::Number),
+        "string" => Ok(AmountFormat::String),
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported amount format: {}", other),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct AmountFormatQuery {
+    amount_format: Option<String>,
+}
+
+/// Finds an `amount=` media type parameter, e.g. `Accept: application/json; amount=string`.
+fn amount_param_from_accept(accept: &str) -> Option<&str> {
+    accept
+        .split(',')
+        .flat_map(|media_range| media_range.split(';').skip(1))
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("amount"))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+}
+
+/// Middleware selecting the amount format from `?amount_format=` or the `Accept`
+/// header and making it visible to the amount serializers for this request.
+pub async fn amount_format_layer(
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let from_query = Query::<AmountFormatQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.amount_format);
+    let from_accept = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .and_then(amount_param_from_accept)
+        .map(str::to_string);
+
+    let format = match from_query.or(from_accept) {
+        Some(value) => parse_amount_format(&value)?,
+        None => AmountFormat::Number,
+    };
+
+    Ok(with_amount_format(format, next.run(request)).await)
+}
+
+pub fn format_amount(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+pub fn serialize_amount<S: Serializer>(amount: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    match current_amount_format() {
+        AmountFormat::Number => serializer.serialize_f64(*amount),
+        AmountFormat::String => serializer.serialize_str(&format_amount(*amount)),
+    }
+}
+
+pub fn serialize_optional_amount<S: Serializer>(
+    amount: &Option<f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match amount {
+        Some(amount) => serialize_amount(amount, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn serialize_amounts<S: Serializer>(amounts: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeSeq;
+
+    let mut seq = serializer.serialize_seq(Some(amounts.len()))?;
+    for amount in amounts {
+        match current_amount_format() {
+            AmountFormat::Number => seq.serialize_element(amount)?,
+            AmountFormat::String => seq.serialize_element(&format_amount(*amount))?,
+        }
+    }
+    seq.end()
+}
+
+/// Parses a decimal amount string such as "12.34" or "-5". Only an optional minus
+/// sign, digits and one decimal point are accepted; exponents, whitespace and
+/// anything longer than `MAX_AMOUNT_STRING_LENGTH` are rejected. The string is
+/// converted in one correctly rounded step, so it maps to the same f64 as the
+/// equivalent JSON number.
+pub fn parse_amount_str(value: &str) -> Result<f64, String> {
+    if value.len() > MAX_AMOUNT_STRING_LENGTH {
+        return Err(format!(
+            "amount string cannot be longer than {} characters",
+            MAX_AMOUNT_STRING_LENGTH
+        ));
+    }
+
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(whole) || !fraction.is_none_or(is_digits) {
+        return Err(format!("invalid amount: {:?}", value));
+    }
+
+    value
+        .parse()
+        .map_err(|_| format!("invalid amount: {:?}", value))
+}
+
+struct AmountVisitor;
+
+impl Visitor<'_> for AmountVisitor {
+    type Value = f64;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a number or a decimal string")
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<f64, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<f64, E> {
+        Ok(value as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<f64, E> {
+        Ok(value as f64)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<f64, E> {
+        parse_amount_str(value).map_err(E::custom)
+    }
+}
+
+/// Accepts an amount as a JSON number or as a decimal string (see `parse_amount_str`).
+pub fn deserialize_amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserializer.deserialize_any(AmountVisitor)
+}
+
+pub fn deserialize_optional_amount<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    #[derive(Deserialize)]
+    struct Amount(#[serde(deserialize_with = "deserialize_amount")] f64);
+
+    Ok(Option::<Amount>::deserialize(deserializer)?.map(|Amount(amount)| amount))
+}
@@ -0,0 +1,185 @@
+use axum::{
+    Json,
+    extract::{Path, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::auth::{get_current_user, with_token_user};
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::{ApiToken, CreateApiTokenPayload, CreateApiTokenResponse, PublicUser};
+use crate::utils::{db_error, db_error_with_context, validate_string_length};
+
+/// Tokens are random, so an unsalted hash is enough to keep a stolen users.db
+/// from handing them out, and it can be looked up directly.
+pub(crate) fn hash_token(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+/// `API_TOKEN_BYTES` random bytes, URL-safe encoded.
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; API_TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn generate_token() -> String {
+    format!("{}{}", API_TOKEN_PREFIX, random_token())
+}
+
+fn invalid_token() -> (StatusCode, String) {
+    (StatusCode::UNAUTHORIZED, "Invalid API token".to_string())
+}
+
+/// Resolves a token to its user and marks it used at `now`.
+pub async fn authenticate_token(
+    main_db: &Db,
+    token: &str,
+    now: i64,
+) -> Result<Option<PublicUser>, (StatusCode, String)> {
+    let token_hash = hash_token(token);
+    let conn = main_db.write().await;
+    let mut rows = conn
+        .query(
+            "SELECT u.id, u.name FROM api_tokens t JOIN users u ON u.id = t.user_id WHERE t.token_hash = ?",
+            [token_hash.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query API token"))?;
+    let Some(row) = rows.next().await.map_err(|_| db_error())? else {
+        return Ok(None);
+    };
+    let user = PublicUser {
+        id: row.get(0).map_err(|_| db_error())?,
+        username: row.get(1).map_err(|_| db_error())?,
+        email: None,
+    };
+
+    conn.execute(
+        "UPDATE api_tokens SET last_used_at = ? WHERE token_hash = ?",
+        (now, token_hash.as_str()),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to update API token"))?;
+
+    Ok(Some(user))
+}
+
+/// Middleware letting `Authorization: Bearer <token>` stand in for a session
+/// login. An unknown or revoked token is refused outright rather than falling
+/// back to the session.
+pub async fn bearer_auth_layer(
+    State(main_db): State<Db>,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let token = match request.headers().get(header::AUTHORIZATION) {
+        Some(value) => {
+            let value = value.to_str().map_err(|_| invalid_token())?;
+            match value.split_once(' ') {
+                Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => {
+                    token.trim().to_string()
+                }
+                _ => return Err(invalid_token()),
+            }
+        }
+        None => return Ok(next.run(request).await),
+    };
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let user = authenticate_token(&main_db, &token, now)
+        .await?
+        .ok_or_else(invalid_token)?;
+    Ok(with_token_user(user, next.run(request)).await)
+}
+
+pub async fn create_api_token(
+    State(main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<CreateApiTokenPayload>,
+) -> Result<(StatusCode, Json<CreateApiTokenResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+    validate_string_length(&payload.name, "Token name", MAX_API_TOKEN_NAME_LENGTH)?;
+
+    let token = generate_token();
+    let response = CreateApiTokenResponse {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name.trim().to_string(),
+        created_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+        token,
+    };
+
+    let conn = main_db.write().await;
+    conn.execute(
+        "INSERT INTO api_tokens (id, user_id, name, token_hash, created_at) VALUES (?, ?, ?, ?, ?)",
+        (
+            response.id.as_str(),
+            user.id.as_str(),
+            response.name.as_str(),
+            hash_token(&response.token),
+            response.created_at,
+        ),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to create API token"))?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub async fn list_api_tokens(
+    State(main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<Vec<ApiToken>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let conn = main_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT id, name, created_at, last_used_at FROM api_tokens WHERE user_id = ? ORDER BY created_at ASC, id ASC",
+            [user.id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query API tokens"))?;
+
+    let mut tokens = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        tokens.push(ApiToken {
+            id: row.get(0).map_err(|_| db_error())?,
+            name: row.get(1).map_err(|_| db_error())?,
+            created_at: row.get(2).map_err(|_| db_error())?,
+            last_used_at: row.get(3).map_err(|_| db_error())?,
+        });
+    }
+
+    Ok((StatusCode::OK, Json(tokens)))
+}
+
+/// Revokes a token; requests carrying it are refused from then on.
+pub async fn delete_api_token(
+    State(main_db): State<Db>,
+    session: Session,
+    Path(token_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let conn = main_db.write().await;
+    let deleted = conn
+        .execute(
+            "DELETE FROM api_tokens WHERE id = ? AND user_id = ?",
+            (token_id.as_str(), user.id.as_str()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to delete API token"))?;
+    if deleted == 0 {
+        return Err((StatusCode::NOT_FOUND, "API token not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,222 @@
+use axum::{
+    Extension, Router, middleware,
+    response::Html,
+    routing::{delete, get, patch, post, put},
+};
+use std::sync::Arc;
+use tower_sessions::Session;
+
+use crate::amount_format::amount_format_layer;
+use crate::database::Db;
+use crate::password_reset::{self, ResetTokenSender};
+use crate::rate_limit::{RateLimit, RateLimiter, rate_limit_layer};
+use crate::{
+    admin, api_tokens, archive, auth, budgets, categories, category_csv, category_rules, closing,
+    export_jobs, import, onboarding, orphans, record_history, records, recurring, settings, sync,
+    user_sessions,
+};
+
+/// Builds the application router with every API route mounted.
+///
+/// Session and CORS layers are left to the caller so the binary and the test
+/// harness can each supply their own. Login, registration and password resets
+/// share one limiter allowing `auth_rate_limit` attempts per client IP, and
+/// `reset_sender` delivers password reset tokens.
+pub fn build_app(
+    main_db: Db,
+    auth_rate_limit: RateLimit,
+    reset_sender: Arc<dyn ResetTokenSender>,
+) -> Router {
+    let auth_limiter = middleware::from_fn_with_state(
+        Arc::new(RateLimiter::new(auth_rate_limit)),
+        rate_limit_layer,
+    );
+
+    Router::new()
+        .route("/", get(root))
+        .route(
+            "/auth/register",
+            post(auth::register).route_layer(auth_limiter.clone()),
+        )
+        .route(
+            "/auth/login",
+            post(auth::login).route_layer(auth_limiter.clone()),
+        )
+        .route(
+            "/auth/forgot-password",
+            post(password_reset::forgot_password).route_layer(auth_limiter.clone()),
+        )
+        .route(
+            "/auth/reset-password",
+            post(password_reset::reset_password).route_layer(auth_limiter),
+        )
+        .route("/auth/me", get(auth::me))
+        .route("/auth/logout", post(auth::logout))
+        .route("/auth/change-username", post(auth::change_username))
+        .route("/auth/profile", patch(auth::update_profile))
+        .route("/auth/sessions", get(user_sessions::list_sessions))
+        .route("/auth/sessions/{id}", delete(user_sessions::revoke_session))
+        .route("/auth/account", delete(auth::delete_account))
+        .route(
+            "/auth/tokens",
+            post(api_tokens::create_api_token).get(api_tokens::list_api_tokens),
+        )
+        .route("/auth/tokens/{id}", delete(api_tokens::delete_api_token))
+        .route("/admin/users", get(admin::list_users))
+        .route("/admin/users/{id}", delete(admin::delete_user))
+        .route(
+            "/records",
+            post(records::create_record)
+                .get(records::get_records)
+                .head(records::head_records)
+                .delete(records::purge_records)
+                .patch(records::patch_records),
+        )
+        .route("/records/split", post(records::create_split_record))
+        .route(
+            "/records/categorize",
+            post(category_rules::categorize_record),
+        )
+        .route("/records/changes", get(sync::get_record_changes))
+        .route("/records/names", get(records::get_record_names))
+        .route("/records/compare", get(records::get_comparison))
+        .route("/records/pivot", get(records::get_pivot))
+        .route("/records/export", get(records::export_records))
+        .route("/records/stats", get(records::get_stats))
+        .route("/records/summary", get(records::get_summary))
+        .route("/records/timeseries", get(records::get_timeseries))
+        .route("/records/top", get(records::get_top_records))
+        .route(
+            "/records/summary/by-category",
+            get(records::get_category_summary),
+        )
+        .route("/records/summary/by-tag", get(records::get_tag_summary))
+        .route("/records/orphans", get(orphans::get_orphans))
+        .route("/records/orphans/repair", post(orphans::repair_orphans))
+        .route(
+            "/records/archive",
+            get(archive::get_archives).post(archive::post_archive),
+        )
+        .route("/records/unarchive", post(archive::post_unarchive))
+        .route("/records/close", post(closing::post_close))
+        .route("/records/reopen", post(closing::post_reopen))
+        .route(
+            "/records/{id}",
+            get(records::get_record)
+                .put(records::update_record)
+                .delete(records::delete_record),
+        )
+        .route("/records/{id}/history", get(record_history::get_history))
+        .route("/records/{id}/duplicate", post(records::duplicate_record))
+        .route(
+            "/records/{id}/star",
+            post(records::star_record).delete(records::unstar_record),
+        )
+        .route(
+            "/categories",
+            post(categories::create_category).get(categories::get_categories),
+        )
+        .route("/categories/bulk", post(categories::bulk_create_categories))
+        .route("/categories/export", get(category_csv::export_categories))
+        .route("/categories/import", post(category_csv::import_categories))
+        .route("/categories/order", put(categories::reorder_categories))
+        .route(
+            "/categories/unused",
+            delete(categories::delete_unused_categories),
+        )
+        .route(
+            "/categories/duplicates",
+            get(categories::get_duplicate_categories),
+        )
+        .route(
+            "/categories/{id}",
+            get(categories::get_category)
+                .put(categories::update_category)
+                .delete(categories::delete_category),
+        )
+        .route(
+            "/categories/{id}/defaults",
+            get(categories::get_category_defaults),
+        )
+        .route(
+            "/categories/{id}/stats",
+            get(categories::get_category_stats),
+        )
+        .route(
+            "/categories/{id}/rules",
+            get(category_rules::list_category_rules).post(category_rules::create_category_rule),
+        )
+        .route(
+            "/categories/{id}/rules/{rule_id}",
+            put(category_rules::update_category_rule).delete(category_rules::delete_category_rule),
+        )
+        .route("/budgets/status", get(budgets::get_budget_status))
+        .route("/exports", post(export_jobs::create_export))
+        .route(
+            "/exports/{id}",
+            get(export_jobs::get_export).delete(export_jobs::delete_export),
+        )
+        .route("/exports/{id}/download", get(export_jobs::download_export))
+        .route("/import", post(import::import_backup))
+        .route("/import/csv", post(import::import_csv))
+        .route("/onboarding/status", get(onboarding::get_onboarding_status))
+        .route(
+            "/onboarding/complete",
+            post(onboarding::complete_onboarding),
+        )
+        .route(
+            "/recurring",
+            post(recurring::create_recurring_rule).get(recurring::list_recurring_rules),
+        )
+        .route(
+            "/recurring/{id}",
+            get(recurring::get_recurring)
+                .put(recurring::update_recurring_rule)
+                .delete(recurring::delete_recurring_rule),
+        )
+        .route(
+            "/recurring/{id}/pause",
+            post(recurring::pause_recurring_rule),
+        )
+        .route(
+            "/recurring/{id}/resume",
+            post(recurring::resume_recurring_rule),
+        )
+        .route(
+            "/settings/preferences",
+            get(settings::get_preferences).put(settings::update_preferences),
+        )
+        .route(
+            "/settings/rates",
+            get(settings::get_exchange_rates).put(settings::update_exchange_rates),
+        )
+        .route("/sync", get(sync::sync))
+        .layer(middleware::from_fn(amount_format_layer))
+        .layer(Extension(reset_sender))
+        .layer(middleware::from_fn_with_state(
+            main_db.clone(),
+            user_sessions::session_activity_layer,
+        ))
+        .layer(middleware::from_fn_with_state(
+            main_db.clone(),
+            api_tokens::bearer_auth_layer,
+        ))
+        .with_state(main_db)
+}
+
+async fn root(session: Session) -> Html<String> {
+    let count: usize = session
+        .get("visitor_count")
+        .await
+        .unwrap_or(Some(0))
+        .unwrap_or(0);
+    let new_count = count + 1;
+
+    // Ignore session update errors for this simple endpoint
+    let _ = session.insert("visitor_count", new_count).await;
+
+    Html(format!(
+        "<h1>My Budget Server</h1><p>API Ready - Visit count: {}</p>",
+        new_count
+    ))
+}
@@ -0,0 +1,331 @@
+use std::borrow::Cow;
+
+use axum::{Json, extract::State, http::StatusCode};
+use tower_sessions::Session;
+
+use crate::auth::get_current_user;
+use crate::database::{Db, RECORD_TABLE_COLUMNS, RECORD_TABLE_DEFINITION};
+use crate::models::{
+    ArchiveRecordsPayload, RecordArchive, RecordArchiveResponse, UnarchiveRecordsPayload,
+};
+use crate::sync::advance_data_horizon;
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+/// UTC calendar year of a record timestamp in SQL.
+const RECORD_YEAR_EXPR: &str = "CAST(strftime('%Y', timestamp, 'unixepoch') AS INTEGER)";
+
+/// Table holding the archived records of `year`.
+pub fn archive_table_name(year: i32) -> String {
+    format!("records_{}", year)
+}
+
+fn create_archive_table(table: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} ({})",
+        table, RECORD_TABLE_DEFINITION
+    )
+}
+
+/// UTC year of a timestamp, saturating for timestamps outside the supported range.
+fn utc_year(timestamp: i64) -> i32 {
+    match time::OffsetDateTime::from_unix_timestamp(timestamp) {
+        Ok(datetime) => datetime.year(),
+        Err(_) if timestamp < 0 => i32::MIN,
+        Err(_) => i32::MAX,
+    }
+}
+
+/// Archived years, oldest first.
+pub async fn archived_years(conn: &libsql::Connection) -> Result<Vec<i32>, (StatusCode, String)> {
+    let mut rows = conn
+        .query("SELECT year FROM record_archives ORDER BY year ASC", ())
+        .await
+        .map_err(|_| db_error_with_context("failed to query record archives"))?;
+
+    let mut years = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        years.push(row.get(0).map_err(|_| db_error())?);
+    }
+    Ok(years)
+}
+
+/// Table expression to read records dated between `start_time` and `end_time`
+/// from: plain `records`, or a UNION ALL of it with the archive tables of every
+/// year the range touches. Callers alias it, e.g. `FROM {} AS records`.
+pub async fn records_source(
+    conn: &libsql::Connection,
+    start_time: i64,
+    end_time: i64,
+) -> Result<Cow<'static, str>, (StatusCode, String)> {
+    let (first_year, last_year) = (utc_year(start_time), utc_year(end_time));
+    let years: Vec<i32> = archived_years(conn)
+        .await?
+        .into_iter()
+        .filter(|year| (first_year..=last_year).contains(year))
+        .collect();
+    if years.is_empty() {
+        return Ok("records".into());
+    }
+
+    let mut selects = vec![format!("SELECT {} FROM records", RECORD_TABLE_COLUMNS)];
+    selects.extend(years.into_iter().map(|year| {
+        format!(
+            "SELECT {} FROM {}",
+            RECORD_TABLE_COLUMNS,
+            archive_table_name(year)
+        )
+    }));
+    Ok(format!("({})", selects.join(" UNION ALL ")).into())
+}
+
+/// Archived years with the number of records in each, oldest first.
+pub async fn list_record_archives(
+    conn: &libsql::Connection,
+) -> Result<Vec<RecordArchive>, (StatusCode, String)> {
+    let mut archives = Vec::new();
+    for year in archived_years(conn).await? {
+        let mut rows = conn
+            .query(
+                &format!("SELECT COUNT(*) FROM {}", archive_table_name(year)),
+                (),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to count archived records"))?;
+        let record_count = match rows.next().await.map_err(|_| db_error())? {
+            Some(row) => row.get(0).map_err(|_| db_error())?,
+            None => 0,
+        };
+        archives.push(RecordArchive { year, record_count });
+    }
+    Ok(archives)
+}
+
+async fn move_records_to_archives(
+    conn: &libsql::Connection,
+    before: i64,
+    archived_at: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT DISTINCT {} FROM records WHERE timestamp < ?",
+                RECORD_YEAR_EXPR
+            ),
+            [before],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to archive records"))?;
+    let mut years = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        years.push(row.get::<i32>(0).map_err(|_| db_error())?);
+    }
+
+    let mut moved = 0;
+    for &year in &years {
+        let table = archive_table_name(year);
+        conn.execute(&create_archive_table(&table), ())
+            .await
+            .map_err(|_| db_error_with_context("failed to create archive table"))?;
+        // A record id already in the archive fails the insert instead of duplicating it
+        moved += conn
+            .execute(
+                &format!(
+                    "INSERT INTO {} ({}) SELECT {} FROM records WHERE timestamp < ? AND {} = ?",
+                    table, RECORD_TABLE_COLUMNS, RECORD_TABLE_COLUMNS, RECORD_YEAR_EXPR
+                ),
+                (before, year),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to archive records"))?;
+        conn.execute(
+            "INSERT INTO record_archives (year, archived_at) VALUES (?, ?) ON CONFLICT(year) DO UPDATE SET archived_at = excluded.archived_at",
+            (year, archived_at),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to archive records"))?;
+    }
+
+    let deleted = conn
+        .execute("DELETE FROM records WHERE timestamp < ?", [before])
+        .await
+        .map_err(|_| db_error_with_context("failed to archive records"))?;
+    if deleted != moved {
+        return Err(db_error_with_context(
+            "archived record count does not match removed records",
+        ));
+    }
+
+    // Archived records still exist, so the deletion trigger's tombstones must not reach sync
+    for &year in &years {
+        conn.execute(
+            &format!(
+                "DELETE FROM record_deletions WHERE record_id IN (SELECT id FROM {})",
+                archive_table_name(year)
+            ),
+            (),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to archive records"))?;
+    }
+
+    if moved > 0 {
+        advance_data_horizon(conn).await?;
+    }
+    Ok(moved as u32)
+}
+
+/// Moves every record dated before `before` out of `records` into the archive
+/// table of its UTC year, all in one transaction. Archived records keep their
+/// tags and splits and still show up in range queries over their year, but can
+/// no longer be fetched, edited or deleted by id. Returns the number moved.
+pub async fn archive_records(
+    user_db: &Db,
+    before: i64,
+    archived_at: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+
+    match move_records_to_archives(&tx, before, archived_at).await {
+        Ok(moved) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+            Ok(moved)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+async fn restore_archived_records(
+    conn: &libsql::Connection,
+    years: &[i32],
+) -> Result<u32, (StatusCode, String)> {
+    let mut moved = 0;
+    for &year in years {
+        let table = archive_table_name(year);
+        // Ids already back in `records` fail the insert instead of duplicating it
+        moved += conn
+            .execute(
+                &format!(
+                    "INSERT INTO records ({}) SELECT {} FROM {}",
+                    RECORD_TABLE_COLUMNS, RECORD_TABLE_COLUMNS, table
+                ),
+                (),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to restore archived records"))?;
+        conn.execute(&format!("DROP TABLE {}", table), ())
+            .await
+            .map_err(|_| db_error_with_context("failed to restore archived records"))?;
+        conn.execute("DELETE FROM record_archives WHERE year = ?", [year])
+            .await
+            .map_err(|_| db_error_with_context("failed to restore archived records"))?;
+    }
+    if moved > 0 {
+        advance_data_horizon(conn).await?;
+    }
+    Ok(moved as u32)
+}
+
+/// Moves the archived records of `year`, or of every archived year, back into
+/// `records` and drops their archive tables, all in one transaction. Returns the
+/// number of records restored.
+pub async fn unarchive_records(
+    user_db: &Db,
+    year: Option<i32>,
+) -> Result<u32, (StatusCode, String)> {
+    let conn = user_db.write().await;
+    let mut years = archived_years(&conn).await?;
+    if let Some(year) = year {
+        if !years.contains(&year) {
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("No archive for year {}", year),
+            ));
+        }
+        years = vec![year];
+    }
+
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+
+    match restore_archived_records(&tx, &years).await {
+        Ok(moved) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+            Ok(moved)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+pub async fn get_archives(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<Vec<RecordArchive>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let archives = list_record_archives(&conn).await?;
+
+    Ok((StatusCode::OK, Json(archives)))
+}
+
+pub async fn post_archive(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<ArchiveRecordsPayload>,
+) -> Result<(StatusCode, Json<RecordArchiveResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    if payload.before > now {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "before cannot be in the future".to_string(),
+        ));
+    }
+
+    let user_db = get_user_database(&user.id).await?;
+    let moved = archive_records(&user_db, payload.before, now).await?;
+    let conn = user_db.read().await;
+    let archives = list_record_archives(&conn).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(RecordArchiveResponse { moved, archives }),
+    ))
+}
+
+pub async fn post_unarchive(
+    State(_main_db): State<Db>,
+    session: Session,
+    payload: Option<Json<UnarchiveRecordsPayload>>,
+) -> Result<(StatusCode, Json<RecordArchiveResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let user_db = get_user_database(&user.id).await?;
+    let moved = unarchive_records(&user_db, payload.year).await?;
+    let conn = user_db.read().await;
+    let archives = list_record_archives(&conn).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(RecordArchiveResponse { moved, archives }),
+    ))
+}
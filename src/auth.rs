@@ -2,48 +2,68 @@ use argon2::{
     Argon2,
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
-use axum::{Json, extract::State, http::StatusCode};
+use axum::{
+    Json,
+    extract::State,
+    http::{Extensions, HeaderMap, StatusCode},
+};
+use std::sync::LazyLock;
 use tower_sessions::Session;
 use uuid::Uuid;
 
+use crate::api_tokens::random_token;
 use crate::constants::*;
-use crate::database::Db;
-use crate::models::{LoginPayload, PublicUser, RegisterPayload, User};
+use crate::database::{Db, remove_user_db};
+use crate::export_jobs::user_exports_dir;
+use crate::models::{
+    ChangeUsernamePayload, DeleteAccountPayload, LoginPayload, PublicUser, RegisterPayload,
+    UpdateProfilePayload, User, UserRole,
+};
+use crate::user_sessions::{forget_session, record_login};
+use crate::utils::get_database_path;
 
-async fn create_user(db: &Db, username: &str, password: &str) -> anyhow::Result<PublicUser> {
+pub(crate) fn hash_password(password: &str) -> anyhow::Result<String> {
     let salt = SaltString::generate(&mut OsRng);
-    let hash = Argon2::default()
+    Ok(Argon2::default()
         .hash_password(password.as_bytes(), &salt)
-        .unwrap()
-        .to_string();
+        .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?
+        .to_string())
+}
+
+async fn create_user(
+    db: &Db,
+    username: &str,
+    password: &str,
+    email: Option<&str>,
+) -> anyhow::Result<PublicUser> {
+    let hash = hash_password(password)?;
     let id = Uuid::new_v4().to_string();
+    let created_at = time::OffsetDateTime::now_utc().unix_timestamp();
     let conn = db.write().await;
 
+    // The first account of an instance is its admin
     conn.execute(
-        "INSERT INTO users (id, name, password_hash) VALUES (?, ?, ?)",
-        (id.as_str(), username, hash.as_str()),
+        "INSERT INTO users (id, name, password_hash, role, created_at, email) SELECT ?, ?, ?, CASE WHEN EXISTS (SELECT 1 FROM users) THEN 'user' ELSE 'admin' END, ?, ?",
+        (id.as_str(), username, hash.as_str(), created_at, email),
     )
     .await?;
 
     Ok(PublicUser {
         id,
         username: username.to_string(),
+        email: email.map(str::to_string),
     })
 }
 
-pub async fn register(
-    State(db): State<Db>,
-    Json(payload): Json<RegisterPayload>,
-) -> Result<(StatusCode, Json<PublicUser>), (StatusCode, String)> {
-    // Input validation
-    if payload.username.trim().is_empty() {
+/// Checks a new username against the rules `register` applies.
+fn validate_username(username: &str) -> Result<(), (StatusCode, String)> {
+    if username.trim().is_empty() {
         return Err((
             StatusCode::BAD_REQUEST,
             "Username cannot be empty".to_string(),
         ));
     }
-    if payload.username.len() < MIN_USERNAME_LENGTH || payload.username.len() > MAX_USERNAME_LENGTH
-    {
+    if username.len() < MIN_USERNAME_LENGTH || username.len() > MAX_USERNAME_LENGTH {
         return Err((
             StatusCode::BAD_REQUEST,
             format!(
@@ -52,7 +72,37 @@ pub async fn register(
             ),
         ));
     }
-    if payload.password.len() < MIN_PASSWORD_LENGTH {
+    if !username
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Username can only contain alphanumeric characters, underscores, and hyphens"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+fn is_common_password(password: &str) -> bool {
+    let password = password.to_lowercase();
+    COMMON_PASSWORDS
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .any(|common| common == password)
+}
+
+/// Checks a new password for `username` against the length, username and
+/// common-password rules in `constants`.
+pub fn validate_password_strength(
+    password: &str,
+    username: &str,
+) -> Result<(), (StatusCode, String)> {
+    if password.len() < MIN_PASSWORD_LENGTH {
         return Err((
             StatusCode::BAD_REQUEST,
             format!(
@@ -61,27 +111,72 @@ pub async fn register(
             ),
         ));
     }
-    if !payload
-        .username
-        .chars()
-        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
-    {
+    if REJECT_PASSWORD_MATCHING_USERNAME && password.eq_ignore_ascii_case(username.trim()) {
         return Err((
             StatusCode::BAD_REQUEST,
-            "Username can only contain alphanumeric characters, underscores, and hyphens"
-                .to_string(),
+            "Password cannot be the same as the username".to_string(),
         ));
     }
+    if REJECT_COMMON_PASSWORDS && is_common_password(password) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Password is too common, choose a less guessable one".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Trims and lowercases an email address after a basic format check: one `@`
+/// between a local part and a dotted domain, no whitespace, within SMTP limits.
+pub(crate) fn normalize_email(email: &str) -> Result<String, (StatusCode, String)> {
+    let email = email.trim().to_lowercase();
+    let invalid = || (StatusCode::BAD_REQUEST, "Invalid email address".to_string());
+
+    if email.len() > MAX_EMAIL_LENGTH || email.chars().any(char::is_whitespace) {
+        return Err(invalid());
+    }
+    let (local, domain) = email.split_once('@').ok_or_else(invalid)?;
+    if local.is_empty() || local.len() > MAX_EMAIL_LOCAL_PART_LENGTH || domain.contains('@') {
+        return Err(invalid());
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2
+        || labels.iter().any(|label| {
+            label.is_empty()
+                || label.starts_with('-')
+                || label.ends_with('-')
+                || !label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+    {
+        return Err(invalid());
+    }
+    Ok(email)
+}
+
+/// Maps a failed users write to 409 when the name or email is taken.
+fn user_write_error(e: impl std::fmt::Display) -> (StatusCode, String) {
+    let message = e.to_string();
+    if message.contains("UNIQUE constraint failed: users.email") {
+        (StatusCode::CONFLICT, "Email already in use".to_string())
+    } else if message.contains("UNIQUE constraint failed") {
+        (StatusCode::CONFLICT, "Username already exists".to_string())
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+pub async fn register(
+    State(db): State<Db>,
+    Json(payload): Json<RegisterPayload>,
+) -> Result<(StatusCode, Json<PublicUser>), (StatusCode, String)> {
+    // Input validation
+    validate_username(&payload.username)?;
+    validate_password_strength(&payload.password, &payload.username)?;
+    let email = payload.email.as_deref().map(normalize_email).transpose()?;
 
-    let user = create_user(&db, &payload.username, &payload.password)
+    let user = create_user(&db, &payload.username, &payload.password, email.as_deref())
         .await
-        .map_err(|e| {
-            if e.to_string().contains("UNIQUE constraint failed") {
-                (StatusCode::CONFLICT, "Username already exists".to_string())
-            } else {
-                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-            }
-        })?;
+        .map_err(user_write_error)?;
 
     Ok((StatusCode::CREATED, Json(user)))
 }
@@ -90,7 +185,7 @@ async fn get_user_by_username(db: &Db, username: &str) -> anyhow::Result<Option<
     let conn = db.read().await;
     let mut rows = conn
         .query(
-            "SELECT id, name, password_hash FROM users WHERE name = ?",
+            "SELECT id, name, password_hash, email FROM users WHERE name = ?",
             [username],
         )
         .await?;
@@ -99,10 +194,37 @@ async fn get_user_by_username(db: &Db, username: &str) -> anyhow::Result<Option<
         let id: String = row.get(0)?;
         let username: String = row.get(1)?;
         let password_hash: String = row.get(2)?;
+        let email: Option<String> = row.get(3)?;
+        Ok(Some(User {
+            id,
+            username,
+            password_hash,
+            email,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
+pub(crate) async fn get_user_by_id(db: &Db, user_id: &str) -> anyhow::Result<Option<User>> {
+    let conn = db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT id, name, password_hash, email FROM users WHERE id = ?",
+            [user_id],
+        )
+        .await?;
+
+    if let Some(row) = rows.next().await? {
+        let id: String = row.get(0)?;
+        let username: String = row.get(1)?;
+        let password_hash: String = row.get(2)?;
+        let email: Option<String> = row.get(3)?;
         Ok(Some(User {
             id,
             username,
             password_hash,
+            email,
         }))
     } else {
         Ok(None)
@@ -117,9 +239,20 @@ fn verify_password(password: &str, hash: &str) -> anyhow::Result<bool> {
         .is_ok())
 }
 
+static DUMMY_PASSWORD_HASH: LazyLock<String> =
+    LazyLock::new(|| hash_password(&random_token()).expect("Failed to hash the dummy password"));
+
+/// Hash of a random password nobody knows, made with the same Argon2 parameters
+/// as real ones, for logins naming no account to be checked against.
+pub fn dummy_password_hash() -> &'static str {
+    DUMMY_PASSWORD_HASH.as_str()
+}
+
 pub async fn login(
     State(db): State<Db>,
     session: Session,
+    headers: HeaderMap,
+    extensions: Extensions,
     Json(payload): Json<LoginPayload>,
 ) -> Result<(StatusCode, Json<PublicUser>), (StatusCode, String)> {
     // Input validation
@@ -140,18 +273,28 @@ pub async fn login(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    // Verify even when no account has the name, against the dummy hash, so an
+    // unknown name takes as long as a wrong password. Any other reason to refuse
+    // an account belongs in the match below, after verification, with the same
+    // answer.
+    let password_hash = user_data
+        .as_ref()
+        .map_or(dummy_password_hash(), |user| user.password_hash.as_str());
+    let is_valid = verify_password(&payload.password, password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
     let user = match user_data {
-        Some(data) => data,
-        None => return Err((StatusCode::UNAUTHORIZED, "Invalid credentials".to_string())),
+        Some(user) if is_valid => user,
+        _ => return Err((StatusCode::UNAUTHORIZED, "Invalid credentials".to_string())),
     };
 
-    let is_valid = verify_password(&payload.password, &user.password_hash)
+    // A new id for the logged-in session, so one handed out before login
+    // cannot be used to ride along (session fixation)
+    session
+        .cycle_id()
+        .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    if !is_valid {
-        return Err((StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()));
-    }
-
     // Set user session
     session
         .insert("user_id", &user.id)
@@ -161,17 +304,36 @@ pub async fn login(
         .insert("username", &user.username.clone())
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let remember_me = payload.remember_me.unwrap_or(DEFAULT_REMEMBER_ME);
+    record_login(&db, &session, &user.id, remember_me, &headers, &extensions).await?;
 
     Ok((
         StatusCode::OK,
         Json(PublicUser {
             id: user.id,
             username: user.username,
+            email: user.email,
         }),
     ))
 }
 
+tokio::task_local! {
+    /// The user an API token authenticated for the request being served.
+    static TOKEN_USER: PublicUser;
+}
+
+/// Runs `future` with `user` as the current user, whatever the session says.
+pub async fn with_token_user<F: Future>(user: PublicUser, future: F) -> F::Output {
+    TOKEN_USER.scope(user, future).await
+}
+
+/// The user behind the request: the one its API token belongs to, otherwise the
+/// one logged in to the session.
 pub async fn get_current_user(session: &Session) -> Result<PublicUser, (StatusCode, String)> {
+    if let Ok(user) = TOKEN_USER.try_with(PublicUser::clone) {
+        return Ok(user);
+    }
+
     let user_id: Option<String> = session
         .get("user_id")
         .await
@@ -183,18 +345,241 @@ pub async fn get_current_user(session: &Session) -> Result<PublicUser, (StatusCo
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     match (user_id, username) {
-        (Some(id), Some(name)) => Ok(PublicUser { id, username: name }),
+        (Some(id), Some(name)) => Ok(PublicUser {
+            id,
+            username: name,
+            email: None,
+        }),
         _ => Err((StatusCode::UNAUTHORIZED, "Not logged in".to_string())),
     }
 }
 
-pub async fn me(session: Session) -> Result<(StatusCode, Json<PublicUser>), (StatusCode, String)> {
-    let user = get_current_user(&session).await?;
-    Ok((StatusCode::OK, Json(user)))
+/// The current user if their account is an admin one. Anyone else logged in
+/// gets 403, so admin routes do not pass for missing ones.
+pub async fn require_admin(db: &Db, session: &Session) -> Result<PublicUser, (StatusCode, String)> {
+    let user = get_current_user(session).await?;
+
+    let conn = db.read().await;
+    let mut rows = conn
+        .query("SELECT role FROM users WHERE id = ?", [user.id.as_str()])
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let role = match rows
+        .next()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        Some(row) => row
+            .get::<String>(0)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+        None => return Err((StatusCode::UNAUTHORIZED, ERR_INVALID_SESSION.to_string())),
+    };
+
+    if UserRole::parse(&role) != Some(UserRole::Admin) {
+        return Err((StatusCode::FORBIDDEN, ERR_ADMIN_REQUIRED.to_string()));
+    }
+    Ok(user)
+}
+
+/// The logged-in user, with the details only they get to see.
+pub async fn me(
+    State(db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<PublicUser>), (StatusCode, String)> {
+    let current = get_current_user(&session).await?;
+    let user = get_user_by_id(&db, &current.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(PublicUser {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+        }),
+    ))
 }
 
-pub async fn logout(session: Session) -> Result<StatusCode, (StatusCode, String)> {
+/// Sets or, with an explicit null, removes the logged-in user's email address
+/// after checking their password.
+pub async fn update_profile(
+    State(db): State<Db>,
+    session: Session,
+    Json(payload): Json<UpdateProfilePayload>,
+) -> Result<(StatusCode, Json<PublicUser>), (StatusCode, String)> {
+    let current = get_current_user(&session).await?;
+    let email = match &payload.email {
+        Some(Some(email)) => Some(Some(normalize_email(email)?)),
+        Some(None) => Some(None),
+        None => None,
+    };
+
+    let user = get_user_by_id(&db, &current.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+
+    let is_valid = verify_password(&payload.password, &user.password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !is_valid {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()));
+    }
+
+    let email = match email {
+        Some(email) => {
+            db.write()
+                .await
+                .execute(
+                    "UPDATE users SET email = ? WHERE id = ?",
+                    (email.as_deref(), user.id.as_str()),
+                )
+                .await
+                .map_err(user_write_error)?;
+            email
+        }
+        None => user.email,
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(PublicUser {
+            id: user.id,
+            username: user.username,
+            email,
+        }),
+    ))
+}
+
+/// Logs the session out: its data is dropped and it continues under a new id,
+/// so the cookie used so far no longer names any session.
+pub(crate) async fn end_session(session: &Session) -> Result<(), (StatusCode, String)> {
     session.clear().await;
+    session
+        .cycle_id()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+pub async fn logout(
+    State(db): State<Db>,
+    session: Session,
+) -> Result<StatusCode, (StatusCode, String)> {
+    forget_session(&db, &session).await?;
+    end_session(&session).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Renames the logged-in user after checking their password, and updates the
+/// session so the new name shows right away. Other sessions of the account pick
+/// it up on their next request, in `session_activity_layer`.
+pub async fn change_username(
+    State(db): State<Db>,
+    session: Session,
+    Json(payload): Json<ChangeUsernamePayload>,
+) -> Result<(StatusCode, Json<PublicUser>), (StatusCode, String)> {
+    let current = get_current_user(&session).await?;
+    validate_username(&payload.username)?;
+
+    let user = get_user_by_id(&db, &current.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+
+    let is_valid = verify_password(&payload.password, &user.password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !is_valid {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()));
+    }
+
+    db.write()
+        .await
+        .execute(
+            "UPDATE users SET name = ? WHERE id = ?",
+            (payload.username.as_str(), user.id.as_str()),
+        )
+        .await
+        .map_err(user_write_error)?;
+    session
+        .insert("username", &payload.username)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(PublicUser {
+            id: user.id,
+            username: payload.username,
+            email: user.email,
+        }),
+    ))
+}
+
+/// Deletes the logged-in user after checking their password. The users row goes
+/// first, so a crash before the files are removed leaves an orphaned database
+/// nobody can log in to rather than a login without its data.
+pub async fn delete_account(
+    State(db): State<Db>,
+    session: Session,
+    Json(payload): Json<DeleteAccountPayload>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let current = get_current_user(&session).await?;
+
+    let user = get_user_by_id(&db, &current.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Account not found".to_string()))?;
+
+    let is_valid = verify_password(&payload.password, &user.password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !is_valid {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()));
+    }
+
+    end_session(&session).await?;
+    remove_account(&db, &user.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Removes an account: its users row, API and reset tokens and stored sessions go
+/// first so nobody can sign in as it, then its database and export files.
+pub(crate) async fn remove_account(db: &Db, user_id: &str) -> Result<(), (StatusCode, String)> {
+    {
+        let conn = db.write().await;
+        conn.execute("DELETE FROM users WHERE id = ?", [user_id])
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        conn.execute("DELETE FROM api_tokens WHERE user_id = ?", [user_id])
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        conn.execute("DELETE FROM user_sessions WHERE user_id = ?", [user_id])
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        conn.execute(
+            "DELETE FROM password_reset_tokens WHERE user_id = ?",
+            [user_id],
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        conn.execute(
+            "DELETE FROM sessions WHERE json_extract(data, '$.data.user_id') = ?",
+            [user_id],
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    let data_path = get_database_path();
+    remove_user_db(data_path, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    match tokio::fs::remove_dir_all(user_exports_dir(data_path, user_id)).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
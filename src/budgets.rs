@@ -0,0 +1,272 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use tower_sessions::Session;
+
+use crate::amount_format::format_amount;
+use crate::archive::records_source;
+use crate::auth::get_current_user;
+use crate::database::Db;
+use crate::models::{
+    BudgetStatus, BudgetStatusResponse, BudgetWarning, GetBudgetStatusQuery, Record, RecordKind,
+};
+use crate::settings::get_default_currency;
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+fn invalid_month() -> (StatusCode, String) {
+    (
+        StatusCode::BAD_REQUEST,
+        "month must be formatted as YYYY-MM".to_string(),
+    )
+}
+
+/// Parses a `YYYY-MM` month into its year and month.
+pub fn parse_budget_month(month: &str) -> Result<(i32, time::Month), (StatusCode, String)> {
+    let (year, month) = month.split_once('-').ok_or_else(invalid_month)?;
+    if year.len() != 4
+        || month.len() != 2
+        || !year
+            .bytes()
+            .chain(month.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid_month());
+    }
+    let year: i32 = year.parse().map_err(|_| invalid_month())?;
+    let month: u8 = month.parse().map_err(|_| invalid_month())?;
+    let month = time::Month::try_from(month).map_err(|_| invalid_month())?;
+    Ok((year, month))
+}
+
+/// First and last second of a UTC calendar month.
+fn month_bounds(year: i32, month: time::Month) -> Result<(i64, i64), (StatusCode, String)> {
+    let start = time::Date::from_calendar_date(year, month, 1).map_err(|_| invalid_month())?;
+    let next = match month {
+        time::Month::December => time::Date::from_calendar_date(year + 1, time::Month::January, 1),
+        month => time::Date::from_calendar_date(year, month.next(), 1),
+    }
+    .map_err(|_| invalid_month())?;
+    Ok((
+        start.midnight().assume_utc().unix_timestamp(),
+        next.midnight().assume_utc().unix_timestamp() - 1,
+    ))
+}
+
+/// Spending per budgeted category between `start_time` and `end_time`, ordered by
+/// category name. Only expenses in `currency` count; split records count each
+/// portion under its own category. Categories without a budget are left out.
+pub async fn budget_status(
+    conn: &libsql::Connection,
+    start_time: i64,
+    end_time: i64,
+    currency: &str,
+) -> Result<Vec<BudgetStatus>, (StatusCode, String)> {
+    let source = records_source(conn, start_time, end_time).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "WITH portions AS (SELECT r.category_id, r.amount FROM {} r WHERE r.timestamp BETWEEN ?1 AND ?2 AND r.kind = 'expense' AND r.currency = ?3 AND NOT EXISTS (SELECT 1 FROM record_splits s WHERE s.record_id = r.id) UNION ALL SELECT s.category_id, s.amount FROM record_splits s JOIN {} r ON r.id = s.record_id WHERE r.timestamp BETWEEN ?1 AND ?2 AND r.kind = 'expense' AND r.currency = ?3) SELECT c.id, c.name, c.monthly_budget, TOTAL(p.amount) FROM categories c LEFT JOIN portions p ON p.category_id = c.id WHERE c.monthly_budget IS NOT NULL GROUP BY c.id ORDER BY c.name ASC",
+                source, source
+            ),
+            (start_time, end_time, currency),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to compute budget status"))?;
+
+    let mut statuses = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let budget: f64 = row.get(2).map_err(|_| db_error())?;
+        let spent: f64 = row.get(3).map_err(|_| db_error())?;
+        statuses.push(BudgetStatus {
+            category_id: row.get(0).map_err(|_| db_error())?,
+            category_name: row.get(1).map_err(|_| db_error())?,
+            budget,
+            spent,
+            remaining: budget - spent,
+            percent_used: (budget > 0.0).then(|| spent / budget * 100.0),
+            over_budget: spent > budget,
+        });
+    }
+
+    Ok(statuses)
+}
+
+/// What `record` adds to the spending of each category it is filed under, as
+/// counted by [`budget_status`]: nothing unless it is an expense in `currency`.
+fn budget_portions<'a>(record: &'a Record, currency: &str) -> Vec<(&'a str, f64)> {
+    if record.kind != RecordKind::Expense || record.currency != currency {
+        return Vec::new();
+    }
+    if record.splits.is_empty() {
+        record
+            .category_id
+            .as_deref()
+            .map(|category_id| (category_id, record.amount))
+            .into_iter()
+            .collect()
+    } else {
+        record
+            .splits
+            .iter()
+            .map(|split| (split.category_id.as_str(), split.amount))
+            .collect()
+    }
+}
+
+/// Rejects a written `record` with 422 when it takes a category with
+/// `enforce_budget` over its budget for the record's UTC month. Runs on the
+/// transaction that wrote the record, which callers open as IMMEDIATE so it holds
+/// the database's write lock from the start and concurrent writes cannot both
+/// slip under the limit. `previous` is the record before an update;
+/// an update only fails when it adds to a category that ends up over budget, so
+/// editing anything else on a record in an overspent category still works.
+pub async fn enforce_budgets(
+    conn: &libsql::Connection,
+    record: &Record,
+    previous: Option<&Record>,
+    currency: &str,
+) -> Result<(), (StatusCode, String)> {
+    let portions = budget_portions(record, currency);
+    let mut enforced = Vec::new();
+    for (category_id, amount) in portions {
+        let mut rows = conn
+            .query(
+                "SELECT 1 FROM categories WHERE id = ? AND enforce_budget = 1 AND monthly_budget IS NOT NULL",
+                [category_id],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to check category budget"))?;
+        if rows.next().await.map_err(|_| db_error())?.is_some() {
+            enforced.push((category_id, amount));
+        }
+    }
+    if enforced.is_empty() {
+        return Ok(());
+    }
+
+    let date = time::OffsetDateTime::from_unix_timestamp(record.timestamp)
+        .map_err(|_| db_error())?
+        .date();
+    let (start_time, end_time) = month_bounds(date.year(), date.month())?;
+    // What the record counted for before the update, if in the same month
+    let previous_portions = previous
+        .filter(|previous| (start_time..=end_time).contains(&previous.timestamp))
+        .map(|previous| budget_portions(previous, currency))
+        .unwrap_or_default();
+    let statuses = budget_status(conn, start_time, end_time, currency).await?;
+
+    let to_cents = |amount: f64| (amount * 100.0).round() as i64;
+    for (category_id, attempted) in enforced {
+        let Some(status) = statuses
+            .iter()
+            .find(|status| status.category_id == category_id)
+        else {
+            continue;
+        };
+        let previous: f64 = previous_portions
+            .iter()
+            .filter(|(id, _)| *id == category_id)
+            .map(|(_, amount)| amount)
+            .sum();
+        if to_cents(status.spent) > to_cents(status.budget)
+            && to_cents(attempted) > to_cents(previous)
+        {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "Record would exceed the monthly budget of {}: budget {}, spent {}, attempted {}",
+                    status.category_name,
+                    format_amount(status.budget),
+                    format_amount(status.spent - attempted + previous),
+                    format_amount(attempted)
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Budgets exceeded in the UTC month of `record` by the categories it is filed
+/// under, counted the same way as [`budget_status`]. Only an expense in the
+/// default currency adds to spending, so other records never warn.
+pub async fn budget_warnings(
+    user_db: &Db,
+    record: &Record,
+) -> Result<Vec<BudgetWarning>, (StatusCode, String)> {
+    if record.kind != RecordKind::Expense {
+        return Ok(Vec::new());
+    }
+    let currency = get_default_currency(user_db).await?;
+    if record.currency != currency {
+        return Ok(Vec::new());
+    }
+    let category_ids: Vec<&str> = if record.splits.is_empty() {
+        record.category_id.as_deref().into_iter().collect()
+    } else {
+        record
+            .splits
+            .iter()
+            .map(|split| split.category_id.as_str())
+            .collect()
+    };
+    if category_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let date = time::OffsetDateTime::from_unix_timestamp(record.timestamp)
+        .map_err(|_| db_error())?
+        .date();
+    let (start_time, end_time) = month_bounds(date.year(), date.month())?;
+    let statuses = {
+        let conn = user_db.read().await;
+        budget_status(&conn, start_time, end_time, &currency).await?
+    };
+
+    Ok(statuses
+        .into_iter()
+        .filter(|status| status.over_budget && category_ids.contains(&status.category_id.as_str()))
+        .map(|status| BudgetWarning {
+            over_by: status.spent - status.budget,
+            category_id: status.category_id,
+            category_name: status.category_name,
+            budget: status.budget,
+            spent: status.spent,
+        })
+        .collect())
+}
+
+pub async fn get_budget_status(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetBudgetStatusQuery>,
+) -> Result<(StatusCode, Json<BudgetStatusResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let (year, month) = match query.month.as_deref() {
+        Some(month) => parse_budget_month(month)?,
+        None => {
+            let today = time::OffsetDateTime::now_utc().date();
+            (today.year(), today.month())
+        }
+    };
+    let (start_time, end_time) = month_bounds(year, month)?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let currency = get_default_currency(&user_db).await?;
+    let categories = {
+        let conn = user_db.read().await;
+        budget_status(&conn, start_time, end_time, &currency).await?
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(BudgetStatusResponse {
+            month: format!("{:04}-{:02}", year, month as u8),
+            currency,
+            categories,
+        }),
+    ))
+}
@@ -2,26 +2,95 @@ use axum::{
     Json,
     extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
 };
+use std::collections::BTreeMap;
+
 use tower_sessions::Session;
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
+use crate::archive::{archive_table_name, archived_years, records_source};
 use crate::auth::get_current_user;
+use crate::closing::ensure_period_open;
 use crate::constants::*;
 use crate::database::Db;
 use crate::models::{
-    Category, CreateCategoryPayload, GetCategoriesQuery, GetCategoriesResponse,
-    UpdateCategoryPayload,
+    BulkCreateCategoriesPayload, BulkCreateCategoriesResponse, CascadeDeleteCategoryResponse,
+    Category, CategoryDefaultsResponse, CategoryDuplicateGroup, CategoryMonthTotal,
+    CategoryStatsResponse, CreateCategoryPayload, DeleteCategoryQuery, DeleteUnusedCategoriesQuery,
+    DeleteUnusedCategoriesResponse, GetCategoriesQuery, GetCategoriesResponse,
+    GetCategoryStatsQuery, NameSuggestion, RecordHistoryAction, ReorderCategoriesPayload,
+    UnusedCategory, UpdateCategoryPayload,
 };
+use crate::record_history::{append_record_history, record_changes};
+use crate::records::{RECORD_COLUMNS, extract_record_from_row, summary_period_expr};
 use crate::utils::{
     db_error, db_error_with_context, get_user_database, validate_categories_limit, validate_offset,
     validate_string_length,
 };
 
+/// Columns `extract_category_from_row` expects, in order.
+pub const CATEGORY_COLUMNS: &str =
+    "id, name, is_income, monthly_budget, parent_id, archived, sort_order, enforce_budget";
+/// Number of columns in [`CATEGORY_COLUMNS`]; extra selected columns follow them.
+pub(crate) const CATEGORY_COLUMN_COUNT: i32 = 8;
+
+/// Manual order: reordered categories by position, then the rest by name.
+const MANUAL_CATEGORY_ORDER: &str = "sort_order IS NULL, sort_order ASC, name ASC";
+
+/// Most used first, counting archived records too; unused categories come last.
+const USAGE_CATEGORY_ORDER: &str = "COALESCE(counts.record_count, 0) DESC, name ASC";
+
 pub fn validate_category_name(name: &str) -> Result<(), (StatusCode, String)> {
     validate_string_length(name, "Category name", MAX_CATEGORY_NAME_LENGTH)
 }
 
+/// Trims a category name and composes it to NFC, so the same name typed as
+/// precomposed or combining characters is stored and compared alike.
+pub fn normalize_category_name(name: &str) -> String {
+    name.trim().nfc().collect()
+}
+
+/// Key two names collide on: normalized, then folded like SQLite's NOCASE,
+/// which only folds ASCII.
+pub fn category_name_key(name: &str) -> String {
+    normalize_category_name(name).to_ascii_lowercase()
+}
+
+pub fn validate_monthly_budget(budget: f64) -> Result<(), (StatusCode, String)> {
+    if !budget.is_finite() || budget < 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Monthly budget must be a non-negative number".to_string(),
+        ));
+    }
+    if budget > MAX_RECORD_AMOUNT {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Monthly budget cannot exceed {}", MAX_RECORD_AMOUNT),
+        ));
+    }
+    Ok(())
+}
+
+fn duplicate_category_name() -> (StatusCode, String) {
+    (
+        StatusCode::CONFLICT,
+        "Category name already exists (case-insensitive)".to_string(),
+    )
+}
+
+/// Maps a failed category insert or rename to 409 when it hit the unique name
+/// index, which is what a concurrent write with the same name runs into.
+fn category_write_error(error: libsql::Error, context: &str) -> (StatusCode, String) {
+    if error.to_string().contains("UNIQUE constraint failed") {
+        duplicate_category_name()
+    } else {
+        db_error_with_context(context)
+    }
+}
+
 pub fn extract_category_from_row(row: libsql::Row) -> Result<Category, (StatusCode, String)> {
     let id: String = row
         .get(0)
@@ -32,24 +101,86 @@ pub fn extract_category_from_row(row: libsql::Row) -> Result<Category, (StatusCo
     let is_income: bool = row
         .get(2)
         .map_err(|_| db_error_with_context("invalid category data"))?;
+    let monthly_budget: Option<f64> = row
+        .get(3)
+        .map_err(|_| db_error_with_context("invalid category data"))?;
+    let parent_id: Option<String> = row
+        .get(4)
+        .map_err(|_| db_error_with_context("invalid category data"))?;
+    let archived: bool = row
+        .get(5)
+        .map_err(|_| db_error_with_context("invalid category data"))?;
+    let sort_order: Option<i64> = row
+        .get(6)
+        .map_err(|_| db_error_with_context("invalid category data"))?;
+    let enforce_budget: bool = row
+        .get(7)
+        .map_err(|_| db_error_with_context("invalid category data"))?;
 
     Ok(Category {
         id,
         name,
         is_income,
+        monthly_budget,
+        parent_id,
+        archived,
+        sort_order,
+        enforce_budget,
+        record_count: None,
+        total_amount: None,
+        last_used_at: None,
     })
 }
 
+/// Checks that `parent_id` names an existing category and, when re-parenting
+/// `category_id`, that the category would not end up among its own ancestors.
+pub async fn validate_parent_category(
+    conn: &libsql::Connection,
+    category_id: Option<&str>,
+    parent_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let mut rows = conn
+        .query("SELECT id FROM categories WHERE id = ?", [parent_id])
+        .await
+        .map_err(|_| db_error_with_context("failed to check parent category"))?;
+    if rows.next().await.map_err(|_| db_error())?.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Parent category does not exist".to_string(),
+        ));
+    }
+
+    let Some(category_id) = category_id else {
+        return Ok(());
+    };
+    // UNION rather than UNION ALL stops the walk on a loop left by older data
+    let mut rows = conn
+        .query(
+            "WITH RECURSIVE ancestors(id) AS (SELECT ?1 UNION SELECT categories.parent_id FROM categories JOIN ancestors ON categories.id = ancestors.id WHERE categories.parent_id IS NOT NULL) SELECT 1 FROM ancestors WHERE id = ?2",
+            (parent_id, category_id),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to check parent category"))?;
+    if rows.next().await.map_err(|_| db_error())?.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A category cannot be its own ancestor".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
 pub async fn validate_category_not_in_use(
     user_db: &std::sync::Arc<tokio::sync::RwLock<libsql::Connection>>,
     category_id: &str,
 ) -> Result<(), (StatusCode, String)> {
     let conn = user_db.read().await;
 
-    // Check if any records use this category
+    // Check if any records or split portions use this category
     let mut rows = conn
         .query(
-            "SELECT COUNT(*) FROM records WHERE category_id = ?",
+            "SELECT (SELECT COUNT(*) FROM records WHERE category_id = ?1) + (SELECT COUNT(*) FROM record_splits WHERE category_id = ?1)",
             [category_id],
         )
         .await
@@ -65,6 +196,183 @@ pub async fn validate_category_not_in_use(
         }
     }
 
+    // Recurring rules would keep posting records into a missing category
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM recurring_rules WHERE category_id = ?",
+            [category_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to check category usage"))?;
+
+    if let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let count: u32 = row.get(0).map_err(|_| db_error())?;
+        if count > 0 {
+            return Err((
+                StatusCode::CONFLICT,
+                "Cannot delete category: it has associated recurring rules".to_string(),
+            ));
+        }
+    }
+
+    ensure_no_child_categories(&conn, category_id).await
+}
+
+/// Children would be left pointing at a missing parent, so a category that
+/// still has any cannot be deleted, with or without reassignment.
+async fn ensure_no_child_categories(
+    conn: &libsql::Connection,
+    category_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM categories WHERE parent_id = ?",
+            [category_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to check category usage"))?;
+
+    if let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let count: u32 = row.get(0).map_err(|_| db_error())?;
+        if count > 0 {
+            return Err((
+                StatusCode::CONFLICT,
+                "Cannot delete category: it has child categories; move or delete them first"
+                    .to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves everything filed under `category_id` to `target_id` and deletes the
+/// category. Records, including archived ones, and recurring rules are pointed
+/// at the target; a split portion is merged into the record's existing target
+/// portion when it has one. Each live record that changes gets a new version
+/// and a history entry, like any other category change. Fails with 423 when any
+/// of the records lies in the closed period.
+async fn reassign_and_delete_category(
+    conn: &libsql::Connection,
+    category_id: &str,
+    target_id: &str,
+    changed_at: i64,
+) -> Result<(), (StatusCode, String)> {
+    ensure_no_child_categories(conn, category_id).await?;
+
+    let reassign_error = || db_error_with_context("failed to reassign category");
+
+    let affected = {
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM records WHERE category_id = ?1 OR id IN (SELECT record_id FROM record_splits WHERE category_id = ?1)",
+                    RECORD_COLUMNS
+                ),
+                [category_id],
+            )
+            .await
+            .map_err(|_| reassign_error())?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            records.push(extract_record_from_row(row)?);
+        }
+        records
+    };
+
+    // Archived records are rewritten too, so the earliest one counts
+    let source = records_source(conn, i64::MIN, i64::MAX).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT MIN(timestamp) FROM {} AS records WHERE category_id = ?1 OR id IN (SELECT record_id FROM record_splits WHERE category_id = ?1)",
+                source
+            ),
+            [category_id],
+        )
+        .await
+        .map_err(|_| reassign_error())?;
+    if let Some(row) = rows.next().await.map_err(|_| db_error())?
+        && let Some(earliest) = row.get::<Option<i64>>(0).map_err(|_| db_error())?
+    {
+        ensure_period_open(conn, earliest).await?;
+    }
+
+    // (record_id, category_id) is unique, so fold portions into an existing
+    // target portion before moving the rest over
+    conn.execute(
+        "UPDATE record_splits SET amount = amount + (SELECT s.amount FROM record_splits s WHERE s.record_id = record_splits.record_id AND s.category_id = ?1) WHERE category_id = ?2 AND record_id IN (SELECT record_id FROM record_splits WHERE category_id = ?1)",
+        (category_id, target_id),
+    )
+    .await
+    .map_err(|_| reassign_error())?;
+    conn.execute(
+        "DELETE FROM record_splits WHERE category_id = ?1 AND record_id IN (SELECT record_id FROM record_splits WHERE category_id = ?2)",
+        (category_id, target_id),
+    )
+    .await
+    .map_err(|_| reassign_error())?;
+    conn.execute(
+        "UPDATE record_splits SET category_id = ?2 WHERE category_id = ?1",
+        (category_id, target_id),
+    )
+    .await
+    .map_err(|_| reassign_error())?;
+
+    for record in &affected {
+        conn.execute(
+            "UPDATE records SET category_id = CASE WHEN category_id = ?1 THEN ?2 ELSE category_id END, updated_at = ?3, version = version + 1 WHERE id = ?4",
+            (category_id, target_id, changed_at, record.id.as_str()),
+        )
+        .await
+        .map_err(|_| reassign_error())?;
+
+        let mut rows = conn
+            .query(
+                &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
+                [record.id.as_str()],
+            )
+            .await
+            .map_err(|_| reassign_error())?;
+        let row = rows
+            .next()
+            .await
+            .map_err(|_| db_error())?
+            .ok_or_else(reassign_error)?;
+        let reassigned = extract_record_from_row(row)?;
+        append_record_history(
+            conn,
+            &record.id,
+            RecordHistoryAction::Update,
+            &record_changes(record, Some(&reassigned)),
+            changed_at,
+        )
+        .await?;
+    }
+
+    for year in archived_years(conn).await? {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET category_id = ?2 WHERE category_id = ?1",
+                archive_table_name(year)
+            ),
+            (category_id, target_id),
+        )
+        .await
+        .map_err(|_| reassign_error())?;
+    }
+
+    conn.execute(
+        "UPDATE recurring_rules SET category_id = ?2 WHERE category_id = ?1",
+        (category_id, target_id),
+    )
+    .await
+    .map_err(|_| reassign_error())?;
+
+    conn.execute("DELETE FROM categories WHERE id = ?", [category_id])
+        .await
+        .map_err(|_| db_error_with_context("failed to delete category"))?;
+
     Ok(())
 }
 
@@ -78,7 +386,10 @@ pub async fn create_category(
 
     // Input validation and sanitization
     validate_category_name(&payload.name)?;
-    let category_name = payload.name.trim().to_string();
+    let category_name = normalize_category_name(&payload.name);
+    if let Some(budget) = payload.monthly_budget {
+        validate_monthly_budget(budget)?;
+    }
 
     // Get user's database
     let user_db = get_user_database(&user.id).await?;
@@ -86,57 +397,195 @@ pub async fn create_category(
     // Use a single write connection for the entire transaction
     let conn = user_db.write().await;
 
-    // Check if category name already exists (case-insensitive)
-    let mut existing_rows = conn
-        .query(
-            "SELECT id FROM categories WHERE LOWER(name) = LOWER(?)",
-            [category_name.as_str()],
-        )
+    if let Some(ref parent_id) = payload.parent_id {
+        validate_parent_category(&conn, None, parent_id).await?;
+    }
+
+    // Check and insert in one transaction; the case-insensitive unique index
+    // catches a concurrent create that slips past the check
+    let category_id = Uuid::new_v4().to_string();
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
         .await
-        .map_err(|_| db_error_with_context("failed to check existing category"))?;
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+    let result = async {
+        let mut existing_rows = tx
+            .query(
+                "SELECT id FROM categories WHERE LOWER(name) = LOWER(?)",
+                [category_name.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to check existing category"))?;
+        if existing_rows
+            .next()
+            .await
+            .map_err(|_| db_error())?
+            .is_some()
+        {
+            return Err(duplicate_category_name());
+        }
 
-    if existing_rows
-        .next()
+        tx.execute(
+            "INSERT INTO categories (id, name, is_income, monthly_budget, parent_id, enforce_budget, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (
+                category_id.as_str(),
+                category_name.as_str(),
+                payload.is_income,
+                payload.monthly_budget,
+                payload.parent_id.as_deref(),
+                payload.enforce_budget,
+                now,
+            ),
+        )
         .await
-        .map_err(|_| db_error())?
-        .is_some()
-    {
-        return Err((
-            StatusCode::CONFLICT,
-            "Category name already exists (case-insensitive)".to_string(),
-        ));
+        .map_err(|e| category_write_error(e, "category creation failed"))?;
+        Ok(())
     }
+    .await;
 
-    // Create category
-    let category_id = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT INTO categories (id, name, is_income) VALUES (?, ?, ?)",
-        (
-            category_id.as_str(),
-            category_name.as_str(),
-            payload.is_income,
-        ),
-    )
-    .await
-    .map_err(|_| db_error_with_context("category creation failed"))?;
+    match result {
+        Ok(()) => tx
+            .commit()
+            .await
+            .map_err(|_| db_error_with_context("failed to commit transaction"))?,
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err(err);
+        }
+    }
 
     let category = Category {
         id: category_id,
         name: category_name,
         is_income: payload.is_income,
+        monthly_budget: payload.monthly_budget,
+        parent_id: payload.parent_id,
+        archived: false,
+        sort_order: None,
+        enforce_budget: payload.enforce_budget,
+        record_count: None,
+        total_amount: None,
+        last_used_at: None,
     };
 
     Ok((StatusCode::CREATED, Json(category)))
 }
 
-pub async fn get_categories(
+/// Creates every name not yet taken in one transaction. Names matching an
+/// existing category or an earlier name in the batch, ignoring case, are skipped.
+pub async fn create_category_batch(
+    user_db: &Db,
+    names: &[String],
+    is_income: bool,
+) -> Result<BulkCreateCategoriesResponse, (StatusCode, String)> {
+    if names.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "At least one category name must be provided".to_string(),
+        ));
+    }
+    if names.len() > MAX_BULK_CATEGORIES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "A batch cannot create more than {} categories",
+                MAX_BULK_CATEGORIES
+            ),
+        ));
+    }
+    for (index, name) in names.iter().enumerate() {
+        validate_category_name(name)
+            .map_err(|(status, msg)| (status, format!("Name {}: {}", index, msg)))?;
+    }
+
+    let conn = user_db.write().await;
+    let mut taken = std::collections::HashSet::new();
+    let mut rows = conn
+        .query("SELECT name FROM categories", ())
+        .await
+        .map_err(|_| db_error_with_context("failed to check existing category"))?;
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        taken.insert(category_name_key(
+            &row.get::<String>(0).map_err(|_| db_error())?,
+        ));
+    }
+
+    let mut created = Vec::new();
+    let mut skipped = Vec::new();
+    for name in names {
+        let name = normalize_category_name(name);
+        if taken.insert(name.to_ascii_lowercase()) {
+            created.push(Category {
+                id: Uuid::new_v4().to_string(),
+                name,
+                is_income,
+                monthly_budget: None,
+                parent_id: None,
+                archived: false,
+                sort_order: None,
+                enforce_budget: false,
+                record_count: None,
+                total_amount: None,
+                last_used_at: None,
+            });
+        } else {
+            skipped.push(name);
+        }
+    }
+
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let result = async {
+        for category in &created {
+            tx.execute(
+                "INSERT INTO categories (id, name, is_income, updated_at) VALUES (?, ?, ?, ?)",
+                (category.id.as_str(), category.name.as_str(), is_income, now),
+            )
+            .await
+            .map_err(|e| category_write_error(e, "category creation failed"))?;
+        }
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+            Ok(BulkCreateCategoriesResponse { created, skipped })
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+pub async fn bulk_create_categories(
     State(_main_db): State<Db>,
     session: Session,
-    Query(query): Query<GetCategoriesQuery>,
-) -> Result<(StatusCode, Json<GetCategoriesResponse>), (StatusCode, String)> {
-    // Get current user from session
+    Json(payload): Json<BulkCreateCategoriesPayload>,
+) -> Result<(StatusCode, Json<BulkCreateCategoriesResponse>), (StatusCode, String)> {
     let user = get_current_user(&session).await?;
 
+    let user_db = get_user_database(&user.id).await?;
+    let response = create_category_batch(&user_db, &payload.names, payload.is_income).await?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Lists one page of categories for GET /categories. Record counts behind
+/// `include_counts` and `sort=usage` come from one grouped join rather than a
+/// count per category.
+pub async fn list_categories(
+    user_db: &Db,
+    query: &GetCategoriesQuery,
+) -> Result<GetCategoriesResponse, (StatusCode, String)> {
     // Input validation
     let limit = validate_categories_limit(query.limit)?;
     let offset = validate_offset(query.offset)?;
@@ -144,79 +593,363 @@ pub async fn get_categories(
     // Validate and sanitize search term
     let search_term = query
         .search
-        .as_ref()
-        .map(|s| s.trim())
+        .as_deref()
+        .map(normalize_category_name)
         .filter(|s| !s.is_empty());
     if let Some(search) = &search_term {
         validate_string_length(search, "Search term", MAX_SEARCH_TERM_LENGTH)?;
     }
 
-    // Get user's database
-    let user_db = get_user_database(&user.id).await?;
-    let conn = user_db.read().await;
-
-    // Get total count with search filter
-    let total_count: u32 = if let Some(search) = &search_term {
-        let search_pattern = format!("%{}%", search);
-        let mut count_rows = conn
-            .query(
-                "SELECT COUNT(*) FROM categories WHERE name LIKE ? COLLATE NOCASE",
-                [search_pattern.as_str()],
-            )
-            .await
-            .map_err(|_| db_error_with_context("failed to count categories"))?;
-
-        if let Some(row) = count_rows.next().await.map_err(|_| db_error())? {
-            row.get(0).map_err(|_| db_error())?
-        } else {
-            0
+    let order_by = match query.sort.as_deref() {
+        None | Some("name") => "name ASC",
+        Some("manual") => MANUAL_CATEGORY_ORDER,
+        Some("usage") => USAGE_CATEGORY_ORDER,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("sort must be 'name', 'manual' or 'usage', got '{}'", other),
+            ));
         }
-    } else {
-        let mut count_rows = conn
-            .query("SELECT COUNT(*) FROM categories", ())
-            .await
-            .map_err(|_| db_error_with_context("failed to count categories"))?;
-
-        if let Some(row) = count_rows.next().await.map_err(|_| db_error())? {
-            row.get(0).map_err(|_| db_error())?
-        } else {
-            0
+    };
+    let include_counts = query.include_counts.unwrap_or(false);
+    let include_usage = query.include_usage.unwrap_or(false);
+    // Totals cover every record unless bounded by start_time/end_time
+    let totals_window = if query.include_totals.unwrap_or(false) {
+        let start_time = query.start_time.unwrap_or(i64::MIN);
+        let end_time = query.end_time.unwrap_or(i64::MAX);
+        if start_time > end_time {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "start_time cannot be after end_time".to_string(),
+            ));
         }
+        Some((start_time, end_time))
+    } else {
+        None
     };
 
-    // Get categories with search filter, pagination, and ordering (utilizing the index)
-    let mut rows = if let Some(search) = &search_term {
-        let search_pattern = format!("%{}%", search);
-        conn.query(
-            "SELECT id, name, is_income FROM categories WHERE name LIKE ? COLLATE NOCASE ORDER BY name ASC LIMIT ? OFFSET ?",
-            (search_pattern.as_str(), limit, offset)
-        )
-        .await
-        .map_err(|_| db_error_with_context("failed to query categories"))?
+    let conn = user_db.read().await;
+
+    // Archived categories are hidden unless asked for; search narrows further
+    let mut conditions = Vec::new();
+    let mut filter_params: Vec<libsql::Value> = Vec::new();
+    if !query.include_archived.unwrap_or(false) {
+        conditions.push("archived = 0");
+    }
+    if let Some(search) = &search_term {
+        conditions.push("name LIKE ? COLLATE NOCASE");
+        filter_params.push(format!("%{}%", search).into());
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
     } else {
-        conn.query(
-            "SELECT id, name, is_income FROM categories ORDER BY name ASC LIMIT ? OFFSET ?",
-            (limit, offset),
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    // Get total count with the same filters
+    let mut count_rows = conn
+        .query(
+            &format!("SELECT COUNT(*) FROM categories{}", where_clause),
+            libsql::params_from_iter(filter_params.clone()),
         )
         .await
-        .map_err(|_| db_error_with_context("failed to query categories"))?
+        .map_err(|_| db_error_with_context("failed to count categories"))?;
+    let total_count: u32 = match count_rows.next().await.map_err(|_| db_error())? {
+        Some(row) => row.get(0).map_err(|_| db_error())?,
+        None => 0,
     };
 
-    let mut categories = Vec::new();
-    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
-        categories.push(extract_category_from_row(row)?);
+    // Counts, totals and last use come from grouped joins, so they never change
+    // which categories are listed
+    let mut columns = CATEGORY_COLUMNS.to_string();
+    let mut joins = String::new();
+    let mut params: Vec<libsql::Value> = Vec::new();
+    if include_counts || include_usage || order_by == USAGE_CATEGORY_ORDER {
+        let source = records_source(&conn, i64::MIN, i64::MAX).await?;
+        if include_counts {
+            columns.push_str(", COALESCE(counts.record_count, 0)");
+        }
+        joins.push_str(&format!(
+            " LEFT JOIN (SELECT category_id, COUNT(*) AS record_count, MAX(r.timestamp) AS last_used_at FROM {} r GROUP BY category_id) counts ON counts.category_id = categories.id",
+            source
+        ));
+    }
+    if let Some((start_time, end_time)) = totals_window {
+        let source = records_source(&conn, start_time, end_time).await?;
+        columns.push_str(", COALESCE(totals.total_amount, 0.0)");
+        joins.push_str(&format!(
+            " LEFT JOIN (SELECT category_id, TOTAL(amount) AS total_amount FROM {} r WHERE r.timestamp BETWEEN ? AND ? GROUP BY category_id) totals ON totals.category_id = categories.id",
+            source
+        ));
+        params.push(start_time.into());
+        params.push(end_time.into());
+    }
+    if include_usage {
+        columns.push_str(", counts.last_used_at");
     }
 
-    Ok((
-        StatusCode::OK,
-        Json(GetCategoriesResponse {
-            categories,
-            total_count,
-            limit,
-            offset,
-        }),
-    ))
-}
+    // Get categories with filters, pagination, and ordering (utilizing the index)
+    params.extend(filter_params);
+    params.push(limit.into());
+    params.push(offset.into());
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM categories{}{} ORDER BY {} LIMIT ? OFFSET ?",
+                columns, joins, where_clause, order_by
+            ),
+            libsql::params_from_iter(params),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query categories"))?;
+
+    let mut categories = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let record_count = if include_counts {
+            Some(
+                row.get::<u32>(CATEGORY_COLUMN_COUNT)
+                    .map_err(|_| db_error_with_context("invalid category data"))?,
+            )
+        } else {
+            None
+        };
+        let total_amount = if totals_window.is_some() {
+            Some(
+                row.get::<f64>(CATEGORY_COLUMN_COUNT + i32::from(include_counts))
+                    .map_err(|_| db_error_with_context("invalid category data"))?,
+            )
+        } else {
+            None
+        };
+        let last_used_at = if include_usage {
+            let index = CATEGORY_COLUMN_COUNT
+                + i32::from(include_counts)
+                + i32::from(totals_window.is_some());
+            Some(
+                row.get::<Option<i64>>(index)
+                    .map_err(|_| db_error_with_context("invalid category data"))?,
+            )
+        } else {
+            None
+        };
+        let mut category = extract_category_from_row(row)?;
+        category.record_count = record_count;
+        category.total_amount = total_amount;
+        category.last_used_at = last_used_at;
+        categories.push(category);
+    }
+
+    Ok(GetCategoriesResponse {
+        categories,
+        total_count,
+        limit,
+        offset,
+    })
+}
+
+pub async fn get_categories(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetCategoriesQuery>,
+) -> Result<(StatusCode, Json<GetCategoriesResponse>), (StatusCode, String)> {
+    // Get current user from session
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let response = list_categories(&user_db, &query).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Rewrites `sort_order` so the categories in `category_ids` come first, in that
+/// order, followed by the rest in their previous manual order. Returns every
+/// category in the new order.
+pub async fn reorder_category_list(
+    user_db: &Db,
+    category_ids: &[String],
+) -> Result<Vec<Category>, (StatusCode, String)> {
+    let mut seen = std::collections::HashSet::new();
+    for category_id in category_ids {
+        if !seen.insert(category_id.as_str()) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Category {} is listed more than once", category_id),
+            ));
+        }
+    }
+
+    let conn = user_db.write().await;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM categories ORDER BY {}",
+                CATEGORY_COLUMNS, MANUAL_CATEGORY_ORDER
+            ),
+            (),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query categories"))?;
+    let mut current = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        current.push(extract_category_from_row(row)?);
+    }
+
+    let mut ordered = Vec::with_capacity(current.len());
+    for category_id in category_ids {
+        let position = current
+            .iter()
+            .position(|category| &category.id == category_id)
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Category {} does not exist", category_id),
+                )
+            })?;
+        ordered.push(current.remove(position));
+    }
+    ordered.append(&mut current);
+
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let result = async {
+        for (position, category) in ordered.iter_mut().enumerate() {
+            let sort_order = position as i64 + 1;
+            tx.execute(
+                "UPDATE categories SET sort_order = ?, updated_at = ? WHERE id = ?",
+                (sort_order, now, category.id.as_str()),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to reorder categories"))?;
+            category.sort_order = Some(sort_order);
+        }
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+            Ok(ordered)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+pub async fn reorder_categories(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<ReorderCategoriesPayload>,
+) -> Result<(StatusCode, Json<Vec<Category>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let categories = reorder_category_list(&user_db, &payload.category_ids).await?;
+
+    Ok((StatusCode::OK, Json(categories)))
+}
+
+pub async fn get_category(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(category_id): Path<String>,
+) -> Result<(StatusCode, Json<Category>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            &format!("SELECT {} FROM categories WHERE id = ?", CATEGORY_COLUMNS),
+            [category_id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query category"))?;
+
+    let mut category = match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => extract_category_from_row(row)?,
+        None => return Err((StatusCode::NOT_FOUND, "Category not found".to_string())),
+    };
+    category.last_used_at = Some(category_last_used_at(&conn, &category.id).await?);
+
+    Ok((StatusCode::OK, Json(category)))
+}
+
+/// Groups of categories whose names collide under `category_name_key`, oldest
+/// first within each group. Names stored before normalization existed are the
+/// only way such a group can come about, so they are reported, not merged.
+pub async fn find_duplicate_categories(
+    conn: &libsql::Connection,
+) -> Result<Vec<CategoryDuplicateGroup>, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!("SELECT {} FROM categories ORDER BY rowid", CATEGORY_COLUMNS),
+            (),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query categories"))?;
+
+    let mut by_key: BTreeMap<String, Vec<Category>> = BTreeMap::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let category = extract_category_from_row(row)?;
+        by_key
+            .entry(category_name_key(&category.name))
+            .or_default()
+            .push(category);
+    }
+
+    Ok(by_key
+        .into_values()
+        .filter(|categories| categories.len() > 1)
+        .map(|categories| CategoryDuplicateGroup {
+            normalized_name: normalize_category_name(&categories[0].name),
+            categories,
+        })
+        .collect())
+}
+
+pub async fn get_duplicate_categories(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<Vec<CategoryDuplicateGroup>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let groups = find_duplicate_categories(&conn).await?;
+
+    Ok((StatusCode::OK, Json(groups)))
+}
+
+/// Timestamp of the latest record filed under the category, archived ones
+/// included.
+async fn category_last_used_at(
+    conn: &libsql::Connection,
+    category_id: &str,
+) -> Result<Option<i64>, (StatusCode, String)> {
+    let source = records_source(conn, i64::MIN, i64::MAX).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT MAX(r.timestamp) FROM {} r WHERE r.category_id = ?",
+                source
+            ),
+            [category_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query category usage"))?;
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => row.get(0).map_err(|_| db_error()),
+        None => Ok(None),
+    }
+}
 
 pub async fn update_category(
     State(_main_db): State<Db>,
@@ -227,16 +960,25 @@ pub async fn update_category(
     // Get current user from session
     let user = get_current_user(&session).await?;
 
-    // Input validation - only update if name is provided
-    let category_name = if let Some(ref name) = payload.name {
-        validate_category_name(name)?;
-        name.trim().to_string()
-    } else {
+    // Input validation - at least one field has to change
+    if payload.name.is_none()
+        && payload.monthly_budget.is_none()
+        && payload.parent_id.is_none()
+        && payload.archived.is_none()
+        && payload.enforce_budget.is_none()
+    {
         return Err((
             StatusCode::BAD_REQUEST,
-            "Category name is required for update".to_string(),
+            "Category name, monthly_budget, parent_id, archived or enforce_budget is required for update"
+                .to_string(),
         ));
-    };
+    }
+    if let Some(ref name) = payload.name {
+        validate_category_name(name)?;
+    }
+    if let Some(Some(budget)) = payload.monthly_budget {
+        validate_monthly_budget(budget)?;
+    }
 
     // Get user's database
     let user_db = get_user_database(&user.id).await?;
@@ -245,7 +987,7 @@ pub async fn update_category(
     // First, check if the category exists and belongs to the user
     let mut existing_rows = conn
         .query(
-            "SELECT id, name, is_income FROM categories WHERE id = ?",
+            &format!("SELECT {} FROM categories WHERE id = ?", CATEGORY_COLUMNS),
             [category_id.as_str()],
         )
         .await
@@ -256,6 +998,21 @@ pub async fn update_category(
     } else {
         return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
     };
+    let category_name = payload
+        .name
+        .map(|name| normalize_category_name(&name))
+        .unwrap_or(existing_category.name);
+    let monthly_budget = payload
+        .monthly_budget
+        .unwrap_or(existing_category.monthly_budget);
+    if let Some(Some(ref parent_id)) = payload.parent_id {
+        validate_parent_category(&conn, Some(&category_id), parent_id).await?;
+    }
+    let parent_id = payload.parent_id.unwrap_or(existing_category.parent_id);
+    let archived = payload.archived.unwrap_or(existing_category.archived);
+    let enforce_budget = payload
+        .enforce_budget
+        .unwrap_or(existing_category.enforce_budget);
 
     // Check if the new name conflicts with existing categories (excluding current one)
     let mut conflict_rows = conn
@@ -266,26 +1023,65 @@ pub async fn update_category(
         .await
         .map_err(|_| db_error_with_context("failed to check name conflict"))?;
 
-    if conflict_rows
-        .next()
-        .await
-        .map_err(|_| db_error())?
-        .is_some()
-    {
-        return Err((
-            StatusCode::CONFLICT,
-            "Category name already exists (case-insensitive)".to_string(),
-        ));
+    if let Some(row) = conflict_rows.next().await.map_err(|_| db_error())? {
+        if !payload.merge_on_conflict.unwrap_or(false) {
+            return Err(duplicate_category_name());
+        }
+
+        // Renaming onto an existing name merges into that category, which keeps
+        // its own settings
+        let target_id: String = row.get(0).map_err(|_| db_error())?;
+        let changed_at = time::OffsetDateTime::now_utc().unix_timestamp();
+        let tx = conn
+            .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+            .await
+            .map_err(|_| db_error_with_context("failed to start transaction"))?;
+        let result = async {
+            reassign_and_delete_category(&tx, &category_id, &target_id, changed_at).await?;
+            let mut rows = tx
+                .query(
+                    &format!("SELECT {} FROM categories WHERE id = ?", CATEGORY_COLUMNS),
+                    [target_id.as_str()],
+                )
+                .await
+                .map_err(|_| db_error_with_context("failed to query merged category"))?;
+            match rows.next().await.map_err(|_| db_error())? {
+                Some(row) => extract_category_from_row(row),
+                None => Err(db_error()),
+            }
+        }
+        .await;
+        return match result {
+            Ok(merged_category) => {
+                tx.commit()
+                    .await
+                    .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+                Ok((StatusCode::OK, Json(merged_category)))
+            }
+            Err(err) => {
+                let _ = tx.rollback().await;
+                Err(err)
+            }
+        };
     }
 
     // Update the category
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
     let affected_rows = conn
         .execute(
-            "UPDATE categories SET name = ? WHERE id = ?",
-            (category_name.as_str(), category_id.as_str()),
+            "UPDATE categories SET name = ?, monthly_budget = ?, parent_id = ?, archived = ?, enforce_budget = ?, updated_at = ? WHERE id = ?",
+            (
+                category_name.as_str(),
+                monthly_budget,
+                parent_id.as_deref(),
+                archived,
+                enforce_budget,
+                now,
+                category_id.as_str(),
+            ),
         )
         .await
-        .map_err(|_| db_error_with_context("failed to update category"))?;
+        .map_err(|e| category_write_error(e, "failed to update category"))?;
 
     // Verify the update actually modified a record
     if affected_rows == 0 {
@@ -299,16 +1095,112 @@ pub async fn update_category(
         id: category_id,
         name: category_name,
         is_income: existing_category.is_income,
+        monthly_budget,
+        parent_id,
+        archived,
+        sort_order: existing_category.sort_order,
+        enforce_budget,
+        record_count: None,
+        total_amount: None,
+        last_used_at: None,
     };
 
     Ok((StatusCode::OK, Json(updated_category)))
 }
 
+/// Deletes the category together with every record filed under it, archived
+/// ones included, their tags and splits, and its recurring rules. Like a purge no
+/// history snapshot is kept. Records of other categories with a split portion
+/// here would be left unbalanced, so they block the delete. Returns the number of
+/// records deleted.
+async fn cascade_delete_category(
+    conn: &libsql::Connection,
+    category_id: &str,
+) -> Result<u32, (StatusCode, String)> {
+    ensure_no_child_categories(conn, category_id).await?;
+
+    let cascade_error = || db_error_with_context("failed to delete category records");
+
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM record_splits s JOIN records r ON r.id = s.record_id WHERE s.category_id = ?1 AND (r.category_id IS NULL OR r.category_id != ?1)",
+            [category_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to check category usage"))?;
+    if let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let count: u32 = row.get(0).map_err(|_| db_error())?;
+        if count > 0 {
+            return Err((
+                StatusCode::CONFLICT,
+                "Cannot delete category: split portions of records in other categories use it"
+                    .to_string(),
+            ));
+        }
+    }
+
+    let mut tables = vec!["records".to_string()];
+    tables.extend(
+        archived_years(conn)
+            .await?
+            .into_iter()
+            .map(archive_table_name),
+    );
+
+    let mut deleted = 0;
+    for table in &tables {
+        let mut rows = conn
+            .query(
+                &format!("SELECT MIN(timestamp) FROM {} WHERE category_id = ?", table),
+                [category_id],
+            )
+            .await
+            .map_err(|_| cascade_error())?;
+        if let Some(row) = rows.next().await.map_err(|_| db_error())?
+            && let Some(earliest) = row.get::<Option<i64>>(0).map_err(|_| db_error())?
+        {
+            ensure_period_open(conn, earliest).await?;
+        }
+
+        for child in ["record_tags", "record_splits"] {
+            conn.execute(
+                &format!(
+                    "DELETE FROM {} WHERE record_id IN (SELECT id FROM {} WHERE category_id = ?)",
+                    child, table
+                ),
+                [category_id],
+            )
+            .await
+            .map_err(|_| cascade_error())?;
+        }
+        deleted += conn
+            .execute(
+                &format!("DELETE FROM {} WHERE category_id = ?", table),
+                [category_id],
+            )
+            .await
+            .map_err(|_| cascade_error())?;
+    }
+
+    conn.execute(
+        "DELETE FROM recurring_rules WHERE category_id = ?",
+        [category_id],
+    )
+    .await
+    .map_err(|_| cascade_error())?;
+    conn.execute("DELETE FROM categories WHERE id = ?", [category_id])
+        .await
+        .map_err(|_| db_error_with_context("failed to delete category"))?;
+
+    Ok(deleted as u32)
+}
+
 pub async fn delete_category(
     State(_main_db): State<Db>,
     session: Session,
     Path(category_id): Path<String>,
-) -> Result<StatusCode, (StatusCode, String)> {
+    Query(query): Query<DeleteCategoryQuery>,
+) -> Result<Response, (StatusCode, String)> {
     // Get current user from session
     let user = get_current_user(&session).await?;
 
@@ -334,11 +1226,86 @@ pub async fn delete_category(
         {
             return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
         }
-
-        // Check if category is in use by any records
-        validate_category_not_in_use(&user_db, &category_id).await?;
     } // Read lock is dropped here
 
+    let cascade = query.cascade.unwrap_or(false);
+    if cascade && query.reassign_to.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "reassign_to and cascade cannot be combined".to_string(),
+        ));
+    }
+
+    if cascade {
+        let conn = user_db.write().await;
+        let tx = conn
+            .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+            .await
+            .map_err(|_| db_error_with_context("failed to start transaction"))?;
+        return match cascade_delete_category(&tx, &category_id).await {
+            Ok(deleted_records) => {
+                tx.commit()
+                    .await
+                    .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+                Ok((
+                    StatusCode::OK,
+                    Json(CascadeDeleteCategoryResponse { deleted_records }),
+                )
+                    .into_response())
+            }
+            Err(err) => {
+                let _ = tx.rollback().await;
+                Err(err)
+            }
+        };
+    }
+
+    // Usage does not block a delete that moves everything elsewhere first
+    if let Some(target_id) = query.reassign_to {
+        if target_id == category_id {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Cannot reassign records to the category being deleted".to_string(),
+            ));
+        }
+
+        let conn = user_db.write().await;
+        let mut target_rows = conn
+            .query(
+                "SELECT id FROM categories WHERE id = ?",
+                [target_id.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query reassignment target"))?;
+        if target_rows.next().await.map_err(|_| db_error())?.is_none() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Reassignment target category does not exist".to_string(),
+            ));
+        }
+
+        let changed_at = time::OffsetDateTime::now_utc().unix_timestamp();
+        let tx = conn
+            .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+            .await
+            .map_err(|_| db_error_with_context("failed to start transaction"))?;
+        return match reassign_and_delete_category(&tx, &category_id, &target_id, changed_at).await {
+            Ok(()) => {
+                tx.commit()
+                    .await
+                    .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+                Ok(StatusCode::NO_CONTENT.into_response())
+            }
+            Err(err) => {
+                let _ = tx.rollback().await;
+                Err(err)
+            }
+        };
+    }
+
+    // Check if category is in use by any records
+    validate_category_not_in_use(&user_db, &category_id).await?;
+
     // Now delete the category
     let conn = user_db.write().await;
     let affected_rows = conn
@@ -354,5 +1321,396 @@ pub async fn delete_category(
         return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Categories nothing refers to, by name: no records, archived ones included,
+/// no split portions, recurring rules or child categories. Archived or budgeted
+/// categories are left out on request.
+async fn find_unused_categories(
+    conn: &libsql::Connection,
+    keep_archived: bool,
+    keep_budgeted: bool,
+) -> Result<Vec<UnusedCategory>, (StatusCode, String)> {
+    let source = records_source(conn, i64::MIN, i64::MAX).await?;
+    let mut sql = format!(
+        "SELECT c.id, c.name FROM categories c WHERE NOT EXISTS (SELECT 1 FROM {} r WHERE r.category_id = c.id) AND NOT EXISTS (SELECT 1 FROM record_splits s WHERE s.category_id = c.id) AND NOT EXISTS (SELECT 1 FROM recurring_rules rr WHERE rr.category_id = c.id) AND NOT EXISTS (SELECT 1 FROM categories k WHERE k.parent_id = c.id)",
+        source
+    );
+    if keep_archived {
+        sql.push_str(" AND c.archived = 0");
+    }
+    if keep_budgeted {
+        sql.push_str(" AND c.monthly_budget IS NULL");
+    }
+    sql.push_str(" ORDER BY c.name ASC");
+
+    let mut rows = conn
+        .query(&sql, ())
+        .await
+        .map_err(|_| db_error_with_context("failed to query unused categories"))?;
+    let mut categories = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        categories.push(UnusedCategory {
+            id: row.get(0).map_err(|_| db_error())?,
+            name: row.get(1).map_err(|_| db_error())?,
+        });
+    }
+    Ok(categories)
+}
+
+/// Lists the unused categories, and with `confirm` deletes them all in one
+/// transaction.
+pub async fn delete_unused_categories(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<DeleteUnusedCategoriesQuery>,
+) -> Result<(StatusCode, Json<DeleteUnusedCategoriesResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+
+    if !query.confirm {
+        let conn = user_db.read().await;
+        let categories =
+            find_unused_categories(&conn, query.keep_archived, query.keep_budgeted).await?;
+        return Ok((
+            StatusCode::OK,
+            Json(DeleteUnusedCategoriesResponse {
+                deleted: false,
+                categories,
+            }),
+        ));
+    }
+
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+    let result = async {
+        let categories =
+            find_unused_categories(&tx, query.keep_archived, query.keep_budgeted).await?;
+        for category in &categories {
+            tx.execute(
+                "DELETE FROM categories WHERE id = ?",
+                [category.id.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to delete category"))?;
+        }
+        Ok(categories)
+    }
+    .await;
+
+    match result {
+        Ok(categories) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+            Ok((
+                StatusCode::OK,
+                Json(DeleteUnusedCategoriesResponse {
+                    deleted: true,
+                    categories,
+                }),
+            ))
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+/// Suggests quick-entry defaults for a category from records at or after `since`:
+/// the most frequent amount(s), the median amount, and the most frequent names.
+pub async fn compute_category_defaults(
+    user_db: &std::sync::Arc<tokio::sync::RwLock<libsql::Connection>>,
+    category_id: &str,
+    since: i64,
+) -> Result<CategoryDefaultsResponse, (StatusCode, String)> {
+    let conn = user_db.read().await;
+
+    // Amount frequencies, ordered by amount so the median can be located by cumulative count
+    let mut amount_rows = conn
+        .query(
+            "SELECT amount, COUNT(*) FROM records WHERE category_id = ? AND timestamp >= ? GROUP BY amount ORDER BY amount ASC",
+            (category_id, since),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to analyze category amounts"))?;
+
+    let mut amount_counts: Vec<(f64, u32)> = Vec::new();
+    while let Some(row) = amount_rows.next().await.map_err(|_| db_error())? {
+        let amount: f64 = row.get(0).map_err(|_| db_error())?;
+        let count: u32 = row.get(1).map_err(|_| db_error())?;
+        amount_counts.push((amount, count));
+    }
+
+    let sample_size: u32 = amount_counts.iter().map(|(_, count)| count).sum();
+
+    // Every amount sharing the highest frequency is a mode; ties resolve to the smaller amounts
+    let max_count = amount_counts
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0);
+    let common_amounts: Vec<f64> = amount_counts
+        .iter()
+        .filter(|(_, count)| *count == max_count)
+        .map(|(amount, _)| *amount)
+        .take(CATEGORY_DEFAULTS_MAX_AMOUNTS)
+        .collect();
+
+    let median_amount = if sample_size == 0 {
+        None
+    } else {
+        let amount_at = |position: u32| {
+            let mut seen = 0;
+            for (amount, count) in &amount_counts {
+                seen += count;
+                if position < seen {
+                    return *amount;
+                }
+            }
+            amount_counts
+                .last()
+                .map(|(amount, _)| *amount)
+                .unwrap_or(0.0)
+        };
+        if sample_size % 2 == 1 {
+            Some(amount_at(sample_size / 2))
+        } else {
+            Some((amount_at(sample_size / 2 - 1) + amount_at(sample_size / 2)) / 2.0)
+        }
+    };
+
+    // Most frequent names, most recently used first on ties
+    let mut name_rows = conn
+        .query(
+            "SELECT name, COUNT(*) AS uses FROM records WHERE category_id = ? AND timestamp >= ? GROUP BY name ORDER BY uses DESC, MAX(timestamp) DESC LIMIT ?",
+            (category_id, since, CATEGORY_DEFAULTS_MAX_NAMES),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to analyze category names"))?;
+
+    let mut common_names = Vec::new();
+    while let Some(row) = name_rows.next().await.map_err(|_| db_error())? {
+        let name: String = row.get(0).map_err(|_| db_error())?;
+        let count: u32 = row.get(1).map_err(|_| db_error())?;
+        common_names.push(NameSuggestion { name, count });
+    }
+
+    Ok(CategoryDefaultsResponse {
+        category_id: category_id.to_string(),
+        sample_size,
+        common_amounts,
+        median_amount,
+        common_names,
+    })
+}
+
+pub async fn get_category_defaults(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(category_id): Path<String>,
+) -> Result<(StatusCode, Json<CategoryDefaultsResponse>), (StatusCode, String)> {
+    // Get current user from session
+    let user = get_current_user(&session).await?;
+
+    // Get user's database
+    let user_db = get_user_database(&user.id).await?;
+
+    // Check if category exists and belongs to user first
+    {
+        let conn = user_db.read().await;
+        let mut existing_rows = conn
+            .query(
+                "SELECT id FROM categories WHERE id = ?",
+                [category_id.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query existing category"))?;
+
+        if existing_rows
+            .next()
+            .await
+            .map_err(|_| db_error())?
+            .is_none()
+        {
+            return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
+        }
+    } // Read lock is dropped here
+
+    let since = time::OffsetDateTime::now_utc().unix_timestamp()
+        - CATEGORY_DEFAULTS_WINDOW_DAYS * 24 * 60 * 60;
+    let defaults = compute_category_defaults(&user_db, &category_id, since).await?;
+
+    Ok((StatusCode::OK, Json(defaults)))
+}
+
+/// UTC months counted from year 0, so windows can step month by month.
+fn month_index(timestamp: i64) -> Result<i32, (StatusCode, String)> {
+    let date = time::OffsetDateTime::from_unix_timestamp(timestamp)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid time range".to_string()))?
+        .date();
+    Ok(date.year() * 12 + i32::from(date.month() as u8) - 1)
+}
+
+/// `YYYY-MM` label of a month index, matching the `month` buckets of
+/// [`summary_period_expr`].
+fn month_label(index: i32) -> String {
+    format!(
+        "{:04}-{:02}",
+        index.div_euclid(12),
+        index.rem_euclid(12) + 1
+    )
+}
+
+/// Resolves the stats window: `end_time` defaults to now and `start_time` to the
+/// start of the UTC month `CATEGORY_STATS_DEFAULT_MONTHS - 1` months before it.
+/// Returns the bounds together with the label of every month they touch.
+fn resolve_category_stats_window(
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> Result<(i64, i64, Vec<String>), (StatusCode, String)> {
+    let end_time = end_time.unwrap_or_else(|| time::OffsetDateTime::now_utc().unix_timestamp());
+    let end_month = month_index(end_time)?;
+    let start_time = match start_time {
+        Some(start_time) => start_time,
+        None => {
+            let start_month = end_month - (CATEGORY_STATS_DEFAULT_MONTHS as i32 - 1);
+            let month = time::Month::try_from((start_month.rem_euclid(12) + 1) as u8)
+                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid time range".to_string()))?;
+            time::Date::from_calendar_date(start_month.div_euclid(12), month, 1)
+                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid time range".to_string()))?
+                .midnight()
+                .assume_utc()
+                .unix_timestamp()
+        }
+    };
+
+    if start_time > end_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "start_time cannot be after end_time".to_string(),
+        ));
+    }
+    let start_month = month_index(start_time)?;
+    if end_month - start_month >= MAX_CATEGORY_STATS_MONTHS as i32 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Time range cannot exceed {} months",
+                MAX_CATEGORY_STATS_MONTHS
+            ),
+        ));
+    }
+    let months = (start_month..=end_month).map(month_label).collect();
+    Ok((start_time, end_time, months))
+}
+
+/// Usage of a category over all its records, archived ones included, plus its
+/// totals for each of `months` within `start_time..=end_time`.
+pub async fn compute_category_stats(
+    conn: &libsql::Connection,
+    category_id: &str,
+    start_time: i64,
+    end_time: i64,
+    months: &[String],
+) -> Result<CategoryStatsResponse, (StatusCode, String)> {
+    let source = records_source(conn, i64::MIN, i64::MAX).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT COUNT(*), TOTAL(amount), MIN(timestamp), MAX(timestamp) FROM {} r WHERE r.category_id = ?",
+                source
+            ),
+            [category_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to compute category stats"))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(|_| db_error())?
+        .ok_or_else(db_error)?;
+    let total_records: u32 = row.get(0).map_err(|_| db_error())?;
+    let total_amount: f64 = row.get(1).map_err(|_| db_error())?;
+    let first_used_at: Option<i64> = row.get(2).map_err(|_| db_error())?;
+    let last_used_at: Option<i64> = row.get(3).map_err(|_| db_error())?;
+
+    let source = records_source(conn, start_time, end_time).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} AS month, TOTAL(amount), COUNT(*) FROM {} AS records WHERE category_id = ? AND timestamp BETWEEN ? AND ? GROUP BY month",
+                summary_period_expr("month", 0)?,
+                source
+            ),
+            (category_id, start_time, end_time),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to compute category stats"))?;
+    let mut totals = std::collections::HashMap::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let month: String = row.get(0).map_err(|_| db_error())?;
+        let total_amount: f64 = row.get(1).map_err(|_| db_error())?;
+        let record_count: u32 = row.get(2).map_err(|_| db_error())?;
+        totals.insert(month, (total_amount, record_count));
+    }
+
+    let monthly = months
+        .iter()
+        .map(|month| {
+            let (total_amount, record_count) = totals.get(month).copied().unwrap_or((0.0, 0));
+            CategoryMonthTotal {
+                month: month.clone(),
+                total_amount,
+                record_count,
+            }
+        })
+        .collect();
+
+    Ok(CategoryStatsResponse {
+        category_id: category_id.to_string(),
+        total_records,
+        total_amount,
+        first_used_at,
+        last_used_at,
+        monthly,
+    })
+}
+
+pub async fn get_category_stats(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(category_id): Path<String>,
+    Query(query): Query<GetCategoryStatsQuery>,
+) -> Result<(StatusCode, Json<CategoryStatsResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+    let (start_time, end_time, months) =
+        resolve_category_stats_window(query.start_time, query.end_time)?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let mut existing_rows = conn
+        .query(
+            "SELECT id FROM categories WHERE id = ?",
+            [category_id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query existing category"))?;
+    if existing_rows
+        .next()
+        .await
+        .map_err(|_| db_error())?
+        .is_none()
+    {
+        return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
+    }
+
+    let stats = compute_category_stats(&conn, &category_id, start_time, end_time, &months).await?;
+    Ok((StatusCode::OK, Json(stats)))
 }
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use axum::{
+    Json,
+    extract::State,
+    http::{StatusCode, header},
+};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::auth::get_current_user;
+use crate::categories::{
+    CATEGORY_COLUMNS, category_name_key, extract_category_from_row, normalize_category_name,
+    validate_category_name, validate_monthly_budget, validate_parent_category,
+};
+use crate::database::Db;
+use crate::import::{CsvRow, parse_csv};
+use crate::models::{Category, CategoryCsvImportPayload, CategoryCsvImportResponse};
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+/// Header of the category CSV. Parents are referenced by name, since ids differ
+/// between instances.
+const CATEGORY_CSV_HEADER: &str = "name,is_income,monthly_budget,parent,archived,sort_order";
+
+/// Quotes a CSV field when it would otherwise be split, or lose surrounding
+/// whitespace, on the way back in.
+fn csv_field(value: &str) -> String {
+    let needs_quotes = value.contains([',', '"', '\n', '\r']) || value.trim() != value;
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Every category as CSV, one row per category ordered by name.
+pub fn write_category_csv(categories: &[Category]) -> String {
+    let names: HashMap<&str, &str> = categories
+        .iter()
+        .map(|category| (category.id.as_str(), category.name.as_str()))
+        .collect();
+
+    let mut csv = format!("{}\n", CATEGORY_CSV_HEADER);
+    for category in categories {
+        let parent = category
+            .parent_id
+            .as_deref()
+            .and_then(|parent_id| names.get(parent_id).copied())
+            .unwrap_or_default();
+        let fields = [
+            csv_field(&category.name),
+            category.is_income.to_string(),
+            category
+                .monthly_budget
+                .map(|budget| budget.to_string())
+                .unwrap_or_default(),
+            csv_field(parent),
+            category.archived.to_string(),
+            category
+                .sort_order
+                .map(|sort_order| sort_order.to_string())
+                .unwrap_or_default(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// A category read from one CSV row.
+#[derive(Debug, Clone, PartialEq)]
+struct CsvCategory {
+    line: u32,
+    name: String,
+    is_income: bool,
+    monthly_budget: Option<f64>,
+    parent: Option<String>,
+    archived: bool,
+    sort_order: Option<i64>,
+}
+
+fn parse_bool_field(value: &str, column: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "false" | "0" => Ok(false),
+        "true" | "1" => Ok(true),
+        _ => Err(format!("{} must be true or false", column)),
+    }
+}
+
+/// Reads the rows after the header, picking columns by their header name. Only
+/// `name` is required; missing columns take their defaults.
+fn parse_category_rows(rows: &[CsvRow]) -> Result<Vec<CsvCategory>, (StatusCode, String)> {
+    let invalid = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let (header, rows) = rows
+        .split_first()
+        .ok_or_else(|| invalid("CSV is empty".to_string()))?;
+    let column = |name: &str| {
+        header
+            .fields
+            .iter()
+            .position(|field| field.trim().eq_ignore_ascii_case(name))
+    };
+    let name_column =
+        column("name").ok_or_else(|| invalid("CSV header needs a name column".to_string()))?;
+    let is_income_column = column("is_income");
+    let budget_column = column("monthly_budget");
+    let parent_column = column("parent");
+    let archived_column = column("archived");
+    let sort_order_column = column("sort_order");
+
+    rows.iter()
+        .map(|row| {
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|index| row.fields.get(index))
+                    .map(|value| value.trim())
+                    .unwrap_or_default()
+            };
+            let parse = || -> Result<CsvCategory, String> {
+                let name = field(Some(name_column));
+                validate_category_name(name).map_err(|(_, msg)| msg)?;
+                let monthly_budget = match field(budget_column) {
+                    "" => None,
+                    value => {
+                        let budget: f64 = value
+                            .parse()
+                            .map_err(|_| "monthly_budget must be a number".to_string())?;
+                        validate_monthly_budget(budget).map_err(|(_, msg)| msg)?;
+                        Some(budget)
+                    }
+                };
+                let sort_order = match field(sort_order_column) {
+                    "" => None,
+                    value => Some(
+                        value
+                            .parse()
+                            .map_err(|_| "sort_order must be an integer".to_string())?,
+                    ),
+                };
+                Ok(CsvCategory {
+                    line: row.line,
+                    name: normalize_category_name(name),
+                    is_income: parse_bool_field(field(is_income_column), "is_income")?,
+                    monthly_budget,
+                    parent: Some(field(parent_column))
+                        .filter(|parent| !parent.is_empty())
+                        .map(str::to_string),
+                    archived: parse_bool_field(field(archived_column), "archived")?,
+                    sort_order,
+                })
+            };
+            parse().map_err(|msg| invalid(format!("Line {}: {}", row.line, msg)))
+        })
+        .collect()
+}
+
+/// Creates the categories in `rows` whose names are not taken yet, ignoring case,
+/// then links them to their parents by name. Later rows repeating an earlier name
+/// are skipped too. Returns how many were created and skipped.
+async fn insert_csv_categories(
+    conn: &libsql::Connection,
+    rows: &[CsvCategory],
+) -> Result<CategoryCsvImportResponse, (StatusCode, String)> {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    // Keyed by category_name_key, so ASCII case and Unicode composition are ignored
+    let mut ids_by_name = HashMap::new();
+    let mut existing = conn
+        .query("SELECT id, name FROM categories", ())
+        .await
+        .map_err(|_| db_error_with_context("failed to query categories"))?;
+    while let Some(row) = existing.next().await.map_err(|_| db_error())? {
+        let id: String = row.get(0).map_err(|_| db_error())?;
+        let name: String = row.get(1).map_err(|_| db_error())?;
+        ids_by_name.insert(category_name_key(&name), id);
+    }
+
+    let mut summary = CategoryCsvImportResponse::default();
+    let mut created = Vec::new();
+    for row in rows {
+        let key = category_name_key(&row.name);
+        if ids_by_name.contains_key(&key) {
+            summary.categories_skipped += 1;
+            continue;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO categories (id, name, is_income, monthly_budget, archived, sort_order, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (
+                id.as_str(),
+                row.name.as_str(),
+                row.is_income,
+                row.monthly_budget,
+                row.archived,
+                row.sort_order,
+                now,
+            ),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to import category"))?;
+        ids_by_name.insert(key, id.clone());
+        created.push((row, id));
+        summary.categories_created += 1;
+    }
+
+    // Parents may come later in the file, so they are linked once all rows exist
+    for (row, id) in created {
+        let Some(parent) = &row.parent else {
+            continue;
+        };
+        let with_line =
+            |(status, msg): (StatusCode, String)| (status, format!("Line {}: {}", row.line, msg));
+        let parent_id = ids_by_name.get(&category_name_key(parent)).ok_or_else(|| {
+            with_line((
+                StatusCode::BAD_REQUEST,
+                "Parent category does not exist".to_string(),
+            ))
+        })?;
+        validate_parent_category(conn, Some(&id), parent_id)
+            .await
+            .map_err(with_line)?;
+        conn.execute(
+            "UPDATE categories SET parent_id = ?, updated_at = ? WHERE id = ?",
+            (parent_id.as_str(), now, id.as_str()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to import category"))?;
+    }
+
+    Ok(summary)
+}
+
+/// Imports categories from CSV in the format of [`write_category_csv`], all in one
+/// transaction: an invalid row rejects the whole file.
+pub async fn import_category_csv(
+    user_db: &Db,
+    csv: &str,
+) -> Result<CategoryCsvImportResponse, (StatusCode, String)> {
+    let rows = parse_csv(csv, ',').map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    let rows = parse_category_rows(&rows)?;
+
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+    match insert_csv_categories(&tx, &rows).await {
+        Ok(summary) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+            Ok(summary)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+pub async fn export_categories(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, [(header::HeaderName, &'static str); 2], String), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM categories ORDER BY name ASC",
+                CATEGORY_COLUMNS
+            ),
+            (),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query categories"))?;
+    let mut categories = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        categories.push(extract_category_from_row(row)?);
+    }
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"categories.csv\"",
+            ),
+        ],
+        write_category_csv(&categories),
+    ))
+}
+
+pub async fn import_categories(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<CategoryCsvImportPayload>,
+) -> Result<(StatusCode, Json<CategoryCsvImportResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let summary = import_category_csv(&user_db, &payload.csv).await?;
+
+    Ok((StatusCode::OK, Json(summary)))
+}
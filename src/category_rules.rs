@@ -0,0 +1,312 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::auth::get_current_user;
+use crate::categories::{CATEGORY_COLUMNS, extract_category_from_row};
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::{
+    CategorizeRecordPayload, CategorizeRecordResponse, CategoryRule, CreateCategoryRulePayload,
+    UpdateCategoryRulePayload,
+};
+use crate::records::{find_record_by_id, validate_record_name};
+use crate::utils::{db_error, db_error_with_context, get_user_database, validate_string_length};
+
+const CATEGORY_RULE_COLUMNS: &str = "id, category_id, keyword, priority";
+
+pub fn validate_rule_keyword(keyword: &str) -> Result<(), (StatusCode, String)> {
+    validate_string_length(keyword, "Keyword", MAX_RULE_KEYWORD_LENGTH)
+}
+
+fn extract_category_rule_from_row(row: &libsql::Row) -> Result<CategoryRule, (StatusCode, String)> {
+    Ok(CategoryRule {
+        id: row.get(0).map_err(|_| db_error())?,
+        category_id: row.get(1).map_err(|_| db_error())?,
+        keyword: row.get(2).map_err(|_| db_error())?,
+        priority: row.get(3).map_err(|_| db_error())?,
+    })
+}
+
+fn category_rule_not_found() -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, "Category rule not found".to_string())
+}
+
+/// Maps a failed rule insert or update to 409 when it hit the unique keyword
+/// index of the category.
+fn rule_write_error(error: libsql::Error, context: &str) -> (StatusCode, String) {
+    if error.to_string().contains("UNIQUE constraint failed") {
+        (
+            StatusCode::CONFLICT,
+            "Keyword already exists for this category (case-insensitive)".to_string(),
+        )
+    } else {
+        db_error_with_context(context)
+    }
+}
+
+async fn ensure_category_found(
+    conn: &libsql::Connection,
+    category_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let mut rows = conn
+        .query("SELECT id FROM categories WHERE id = ?", [category_id])
+        .await
+        .map_err(|_| db_error_with_context("failed to query existing category"))?;
+    if rows.next().await.map_err(|_| db_error())?.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Category not found".to_string()));
+    }
+    Ok(())
+}
+
+async fn find_category_rule(
+    conn: &libsql::Connection,
+    category_id: &str,
+    rule_id: &str,
+) -> Result<CategoryRule, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM category_rules WHERE id = ? AND category_id = ?",
+                CATEGORY_RULE_COLUMNS
+            ),
+            (rule_id, category_id),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query category rule"))?;
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => extract_category_rule_from_row(&row),
+        None => Err(category_rule_not_found()),
+    }
+}
+
+/// The keyword rules of a user, loaded once so many names can be matched without
+/// a query per name. Rules are kept in the order they win in: highest priority
+/// first, then the longest keyword, then by category name.
+pub struct CategoryRuleMatcher {
+    /// Each rule with its keyword lowercased for matching
+    rules: Vec<(String, CategoryRule)>,
+}
+
+impl CategoryRuleMatcher {
+    /// Archived categories take no new records, so their rules are left out.
+    pub async fn load(conn: &libsql::Connection) -> Result<Self, (StatusCode, String)> {
+        let mut rows = conn
+            .query(
+                "SELECT r.id, r.category_id, r.keyword, r.priority FROM category_rules r JOIN categories c ON c.id = r.category_id WHERE c.archived = 0 ORDER BY r.priority DESC, LENGTH(r.keyword) DESC, c.name ASC",
+                (),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to load category rules"))?;
+
+        let mut rules = Vec::new();
+        while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            let rule = extract_category_rule_from_row(&row)?;
+            rules.push((rule.keyword.to_ascii_lowercase(), rule));
+        }
+        Ok(Self { rules })
+    }
+
+    /// The winning rule whose keyword occurs in `name`, ignoring ASCII case like
+    /// the other name checks.
+    pub fn find(&self, name: &str) -> Option<&CategoryRule> {
+        let name = name.to_ascii_lowercase();
+        self.rules
+            .iter()
+            .find(|(keyword, _)| name.contains(keyword.as_str()))
+            .map(|(_, rule)| rule)
+    }
+}
+
+pub async fn list_category_rules(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(category_id): Path<String>,
+) -> Result<(StatusCode, Json<Vec<CategoryRule>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    ensure_category_found(&conn, &category_id).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM category_rules WHERE category_id = ? ORDER BY priority DESC, keyword ASC",
+                CATEGORY_RULE_COLUMNS
+            ),
+            [category_id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query category rules"))?;
+
+    let mut rules = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        rules.push(extract_category_rule_from_row(&row)?);
+    }
+
+    Ok((StatusCode::OK, Json(rules)))
+}
+
+pub async fn create_category_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(category_id): Path<String>,
+    Json(payload): Json<CreateCategoryRulePayload>,
+) -> Result<(StatusCode, Json<CategoryRule>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    validate_rule_keyword(&payload.keyword)?;
+
+    let rule = CategoryRule {
+        id: Uuid::new_v4().to_string(),
+        category_id,
+        keyword: payload.keyword.trim().to_string(),
+        priority: payload.priority,
+    };
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.write().await;
+    ensure_category_found(&conn, &rule.category_id).await?;
+    conn.execute(
+        &format!(
+            "INSERT INTO category_rules ({}) VALUES (?, ?, ?, ?)",
+            CATEGORY_RULE_COLUMNS
+        ),
+        (
+            rule.id.as_str(),
+            rule.category_id.as_str(),
+            rule.keyword.as_str(),
+            rule.priority,
+        ),
+    )
+    .await
+    .map_err(|e| rule_write_error(e, "category rule creation failed"))?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn update_category_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path((category_id, rule_id)): Path<(String, String)>,
+    Json(payload): Json<UpdateCategoryRulePayload>,
+) -> Result<(StatusCode, Json<CategoryRule>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    if payload.keyword.is_none() && payload.priority.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Keyword or priority is required for update".to_string(),
+        ));
+    }
+    if let Some(ref keyword) = payload.keyword {
+        validate_rule_keyword(keyword)?;
+    }
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.write().await;
+    let mut rule = find_category_rule(&conn, &category_id, &rule_id).await?;
+    if let Some(keyword) = payload.keyword {
+        rule.keyword = keyword.trim().to_string();
+    }
+    rule.priority = payload.priority.unwrap_or(rule.priority);
+
+    conn.execute(
+        "UPDATE category_rules SET keyword = ?, priority = ? WHERE id = ?",
+        (rule.keyword.as_str(), rule.priority, rule.id.as_str()),
+    )
+    .await
+    .map_err(|e| rule_write_error(e, "failed to update category rule"))?;
+
+    Ok((StatusCode::OK, Json(rule)))
+}
+
+pub async fn delete_category_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path((category_id, rule_id)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.write().await;
+    let affected_rows = conn
+        .execute(
+            "DELETE FROM category_rules WHERE id = ? AND category_id = ?",
+            (rule_id.as_str(), category_id.as_str()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to delete category rule"))?;
+
+    if affected_rows == 0 {
+        return Err(category_rule_not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Suggests a category for a record name, or for the name of an existing record,
+/// from the keyword rules. Nothing is written, not even to the record.
+pub async fn categorize_record(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<CategorizeRecordPayload>,
+) -> Result<(StatusCode, Json<CategorizeRecordResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let name = match (payload.name, payload.record_id) {
+        (Some(name), None) => {
+            validate_record_name(&name)?;
+            name
+        }
+        (None, Some(record_id)) => {
+            find_record_by_id(&conn, &record_id)
+                .await?
+                .ok_or_else(|| (StatusCode::NOT_FOUND, "Record not found".to_string()))?
+                .name
+        }
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Exactly one of name or record_id is required".to_string(),
+            ));
+        }
+    };
+
+    let matcher = CategoryRuleMatcher::load(&conn).await?;
+    let Some(rule) = matcher.find(&name).cloned() else {
+        return Ok((
+            StatusCode::OK,
+            Json(CategorizeRecordResponse {
+                category: None,
+                rule: None,
+            }),
+        ));
+    };
+    let mut rows = conn
+        .query(
+            &format!("SELECT {} FROM categories WHERE id = ?", CATEGORY_COLUMNS),
+            [rule.category_id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query category"))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(|_| db_error())?
+        .ok_or_else(db_error)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(CategorizeRecordResponse {
+            category: Some(extract_category_from_row(row)?),
+            rule: Some(rule),
+        }),
+    ))
+}
@@ -0,0 +1,93 @@
+use axum::{Json, extract::State, http::StatusCode};
+use tower_sessions::Session;
+
+use crate::auth::get_current_user;
+use crate::constants::SETTING_CLOSED_BEFORE;
+use crate::database::Db;
+use crate::models::{ClosePeriodPayload, ClosedPeriod};
+use crate::settings::{delete_setting, set_setting};
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+/// The close cutoff: records dated before it are read-only. Read through `conn`
+/// so callers can check it inside the transaction doing the write.
+pub async fn closed_before(conn: &libsql::Connection) -> Result<Option<i64>, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            "SELECT value FROM settings WHERE key = ?",
+            [SETTING_CLOSED_BEFORE],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query settings"))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => {
+            let value: String = row.get(0).map_err(|_| db_error())?;
+            let closed_before = value
+                .parse()
+                .map_err(|_| db_error_with_context("invalid closed_before setting"))?;
+            Ok(Some(closed_before))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Rejects with 423 a write touching a record dated `timestamp` when that falls in
+/// the closed period.
+pub async fn ensure_period_open(
+    conn: &libsql::Connection,
+    timestamp: i64,
+) -> Result<(), (StatusCode, String)> {
+    match closed_before(conn).await? {
+        Some(closed_before) if timestamp < closed_before => Err((
+            StatusCode::LOCKED,
+            format!(
+                "Records dated before {} are closed; reopen them to make changes",
+                closed_before
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub async fn post_close(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<ClosePeriodPayload>,
+) -> Result<(StatusCode, Json<ClosedPeriod>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    if payload.before > now {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "before cannot be in the future".to_string(),
+        ));
+    }
+
+    let user_db = get_user_database(&user.id).await?;
+    set_setting(&user_db, SETTING_CLOSED_BEFORE, &payload.before.to_string()).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ClosedPeriod {
+            closed_before: Some(payload.before),
+        }),
+    ))
+}
+
+pub async fn post_reopen(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<ClosedPeriod>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    delete_setting(&user_db, SETTING_CLOSED_BEFORE).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ClosedPeriod {
+            closed_before: None,
+        }),
+    ))
+}
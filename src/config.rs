@@ -1,5 +1,17 @@
 use crate::constants::*;
+use crate::rate_limit::RateLimit;
 use std::env;
+use std::time::Duration;
+
+/// Where sessions are kept, chosen with `SESSION_STORE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SessionStoreKind {
+    /// In process memory; every restart logs everyone out (the default)
+    #[default]
+    Memory,
+    /// In the `sessions` table of users.db
+    Sqlite,
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -7,6 +19,13 @@ pub struct Config {
     pub port: String,
     pub data_path: String,
     pub session_secret: String,
+    /// Records dated further back are purged by the maintenance task; None keeps everything
+    pub record_retention_days: Option<u32>,
+    /// Attempts allowed per client IP on login and registration
+    pub auth_rate_limit: RateLimit,
+    pub session_store: SessionStoreKind,
+    /// Account made admin at startup, in addition to the first one registered
+    pub admin_username: Option<String>,
 }
 
 #[derive(Debug)]
@@ -14,6 +33,9 @@ pub enum ConfigError {
     MissingSessionSecret,
     InvalidSessionSecret(String),
     InvalidPort(String),
+    InvalidRetentionDays(String),
+    InvalidRateLimit(&'static str, String),
+    InvalidSessionStore(String),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -28,6 +50,19 @@ impl std::fmt::Display for ConfigError {
             ConfigError::InvalidPort(port) => {
                 write!(f, "Invalid port number: {}", port)
             }
+            ConfigError::InvalidRetentionDays(days) => {
+                write!(f, "Invalid {}: {}", RECORD_RETENTION_DAYS_VAR, days)
+            }
+            ConfigError::InvalidRateLimit(var, value) => {
+                write!(f, "Invalid {}: {}", var, value)
+            }
+            ConfigError::InvalidSessionStore(store) => {
+                write!(
+                    f,
+                    "Invalid {}: {} (expected memory or sqlite)",
+                    SESSION_STORE_VAR, store
+                )
+            }
         }
     }
 }
@@ -62,11 +97,48 @@ impl Config {
             ));
         }
 
+        // Retention is off unless set to a positive number of days
+        let record_retention_days = match env::var(RECORD_RETENTION_DAYS_VAR) {
+            Ok(days) => match days.trim().parse::<u32>() {
+                Ok(0) => None,
+                Ok(days) => Some(days),
+                Err(_) => return Err(ConfigError::InvalidRetentionDays(days)),
+            },
+            Err(_) => None,
+        };
+
+        let defaults = RateLimit::default();
+        let auth_rate_limit = RateLimit {
+            max_attempts: positive_from_env(AUTH_RATE_LIMIT_ATTEMPTS_VAR)?
+                .unwrap_or(defaults.max_attempts),
+            window: positive_from_env(AUTH_RATE_LIMIT_WINDOW_SECS_VAR)?
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.window),
+        };
+
+        let session_store = match env::var(SESSION_STORE_VAR) {
+            Ok(store) => match store.trim().to_ascii_lowercase().as_str() {
+                "memory" => SessionStoreKind::Memory,
+                "sqlite" => SessionStoreKind::Sqlite,
+                _ => return Err(ConfigError::InvalidSessionStore(store)),
+            },
+            Err(_) => SessionStoreKind::default(),
+        };
+
+        let admin_username = env::var(ADMIN_USERNAME_VAR)
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+
         Ok(Config {
             host,
             port,
             data_path,
             session_secret,
+            record_retention_days,
+            auth_rate_limit,
+            session_store,
+            admin_username,
         })
     }
 
@@ -74,3 +146,17 @@ impl Config {
         format!("{}:{}", self.host, self.port)
     }
 }
+
+/// A positive number from the environment variable `var`, or None when unset.
+fn positive_from_env<T>(var: &'static str) -> Result<Option<T>, ConfigError>
+where
+    T: std::str::FromStr + PartialOrd + Default,
+{
+    match env::var(var) {
+        Ok(value) => match value.trim().parse::<T>() {
+            Ok(parsed) if parsed > T::default() => Ok(Some(parsed)),
+            _ => Err(ConfigError::InvalidRateLimit(var, value)),
+        },
+        Err(_) => Ok(None),
+    }
+}
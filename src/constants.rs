@@ -6,24 +6,160 @@ pub const DEFAULT_DATA_PATH: &str = "data";
 // Session configuration
 pub const SESSION_NAME: &str = "axum_session";
 pub const SESSION_EXPIRY_DAYS: i64 = 30;
+/// Inactivity after which a session logged in without remember-me ends
+pub const SHORT_SESSION_EXPIRY_HOURS: i64 = 8;
+/// Whether logins that leave out `remember_me` are remembered
+pub const DEFAULT_REMEMBER_ME: bool = false;
 pub const MIN_SESSION_SECRET_LENGTH: usize = 64;
+/// Environment variable choosing the session store, `memory` (default) or `sqlite`
+pub const SESSION_STORE_VAR: &str = "SESSION_STORE";
+/// Session key holding the id of the session's `user_sessions` row
+pub const SESSION_DEVICE_ID_KEY: &str = "device_id";
+/// How stale `last_seen_at` may get before a request refreshes it
+pub const SESSION_LAST_SEEN_INTERVAL_SECS: i64 = 60;
+pub const MAX_USER_AGENT_LENGTH: usize = 255;
+
+// Login and registration rate limiting, per client IP
+pub const DEFAULT_AUTH_RATE_LIMIT_ATTEMPTS: u32 = 10;
+pub const DEFAULT_AUTH_RATE_LIMIT_WINDOW_SECS: u64 = 60;
+/// Environment variable holding how many attempts are allowed per window
+pub const AUTH_RATE_LIMIT_ATTEMPTS_VAR: &str = "AUTH_RATE_LIMIT_ATTEMPTS";
+/// Environment variable holding the length of the window in seconds
+pub const AUTH_RATE_LIMIT_WINDOW_SECS_VAR: &str = "AUTH_RATE_LIMIT_WINDOW_SECS";
+/// Environment variable naming an existing account to make admin at startup
+pub const ADMIN_USERNAME_VAR: &str = "ADMIN_USERNAME";
 
 // Database limits and defaults
 pub const DEFAULT_CATEGORIES_LIMIT: u32 = 100;
 pub const DEFAULT_RECORDS_LIMIT: u32 = 500;
 pub const MAX_LIMIT: u32 = 1000;
 pub const MAX_OFFSET: u32 = 1_000_000;
+pub const DEFAULT_TOP_RECORDS_LIMIT: u32 = 10;
+
+// Export streaming
+pub const EXPORT_FLUSH_ROWS: usize = 500;
+pub const EXPORT_CHANNEL_CAPACITY: usize = 4;
+
+// Asynchronous export jobs
+pub const EXPORT_JOB_PROGRESS_ROWS: u32 = 500;
+pub const EXPORT_JOB_RETENTION_SECS: i64 = 24 * 60 * 60;
+pub const EXPORTS_DIR_NAME: &str = "exports";
+
+// Background maintenance
+pub const MAINTENANCE_INTERVAL_SECS: u64 = 60 * 60;
+/// Environment variable holding how many days of records to keep; unset or 0 keeps everything
+pub const RECORD_RETENTION_DAYS_VAR: &str = "RECORD_RETENTION_DAYS";
+
+// Daily time series
+pub const TIMESERIES_DEFAULT_DAYS: i64 = 30;
+pub const MAX_TIMESERIES_DAYS: i64 = 2 * 366;
+
+// Duplicate detection
+pub const DUPLICATE_WINDOW_SECS: i64 = 120;
+/// Record columns that must match exactly, besides a timestamp within the window
+pub const DUPLICATE_MATCH_FIELDS: &[&str] = &["name", "amount", "category_id"];
+
+// Idempotent record creation
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENCY_KEY_TTL_SECS: i64 = 24 * 60 * 60;
+
+// Record counts
+/// Carries `total_count` on GET /records and is the whole answer of HEAD /records
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+// Recurring rules
+pub const RECURRING_SCHEDULER_INTERVAL_SECS: u64 = 60;
+/// Occurrences posted per rule in one scheduler pass; a longer backlog continues on the next pass
+pub const RECURRING_MAX_CATCH_UP: u32 = 100;
+
+// Backup documents
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+// Bank statement CSV import
+pub const DEFAULT_CSV_DATE_FORMAT: &str = "%Y-%m-%d";
+pub const MAX_CSV_IMPORT_ROWS: usize = 10_000;
+
+// Chunked sync
+pub const DEFAULT_SYNC_CHUNK_SIZE: u32 = 1000;
+pub const MAX_SYNC_CHUNK_SIZE: u32 = 5000;
+pub const SYNC_TOKEN_TTL_SECS: i64 = 60 * 60;
+
+// Category quick-entry defaults
+pub const CATEGORY_DEFAULTS_WINDOW_DAYS: i64 = 90;
+pub const CATEGORY_DEFAULTS_MAX_AMOUNTS: usize = 3;
+pub const CATEGORY_DEFAULTS_MAX_NAMES: u32 = 5;
+
+// Category usage stats
+pub const CATEGORY_STATS_DEFAULT_MONTHS: u32 = 12;
+pub const MAX_CATEGORY_STATS_MONTHS: u32 = 120;
+
+// Record name autocomplete
+pub const DEFAULT_NAME_SUGGESTIONS_LIMIT: u32 = 10;
+pub const MAX_NAME_SUGGESTIONS_LIMIT: u32 = 50;
+
+// Summaries
+pub const UNKNOWN_CATEGORY_NAME: &str = "unknown";
+pub const UNCATEGORIZED_CATEGORY_NAME: &str = "uncategorized";
+/// `category_id` filter value matching records without a category
+pub const UNCATEGORIZED_FILTER: &str = "none";
+/// UTC offsets range from -12:00 to +14:00
+pub const MAX_TZ_OFFSET_MINUTES: i32 = 840;
+
+// Per-user settings keys
+pub const SETTING_ONBOARDING_DISMISSED: &str = "onboarding_dismissed";
+pub const SETTING_DEFAULT_CURRENCY: &str = "default_currency";
+/// Unix seconds; records dated before it are read-only
+pub const SETTING_CLOSED_BEFORE: &str = "closed_before";
+/// Counter bumped whenever data is removed outside the normal deletion tracking
+pub const SETTING_DATA_HORIZON: &str = "data_horizon";
+
+// Currencies
+pub const DEFAULT_CURRENCY: &str = "USD";
 
 // Validation limits
 pub const MAX_CATEGORY_NAME_LENGTH: usize = 100;
+pub const MAX_CATEGORY_FILTER_IDS: usize = 20;
 pub const MAX_RECORD_NAME_LENGTH: usize = 255;
+pub const MAX_RECORD_FILTER_IDS: usize = 100;
+pub const MAX_BATCH_UPDATE_SIZE: usize = 100;
+pub const MAX_BULK_CATEGORIES: usize = 100;
+pub const MAX_RULE_KEYWORD_LENGTH: usize = 100;
+pub const MAX_TAG_LENGTH: usize = 50;
+pub const MAX_TAGS_PER_RECORD: usize = 20;
+pub const MAX_SPLITS_PER_RECORD: usize = 20;
+/// Largest absolute record amount, far above any real transaction but safe to SUM()
+pub const MAX_RECORD_AMOUNT: f64 = 1_000_000_000.0;
+pub const MAX_AMOUNT_DECIMAL_PLACES: i32 = 2;
+/// Longest amount accepted as a JSON string, e.g. "-1000000000.00"
+pub const MAX_AMOUNT_STRING_LENGTH: usize = 32;
+pub const MIN_RECORD_TIMESTAMP: i64 = 0;
+/// How far a record timestamp may run ahead of the server clock, to allow for drift
+pub const MAX_RECORD_TIMESTAMP_SKEW_SECS: i64 = 24 * 60 * 60;
+pub const MAX_PAYMENT_METHOD_LENGTH: usize = 50;
 pub const MAX_SEARCH_TERM_LENGTH: usize = 100;
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
 pub const MAX_USERNAME_LENGTH: usize = 50;
 pub const MIN_USERNAME_LENGTH: usize = 4;
-pub const MIN_PASSWORD_LENGTH: usize = 6;
+/// Longest address SMTP can deliver to
+pub const MAX_EMAIL_LENGTH: usize = 254;
+pub const MAX_EMAIL_LOCAL_PART_LENGTH: usize = 64;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+/// Refuse passwords found in `common_passwords.txt`
+pub const REJECT_COMMON_PASSWORDS: bool = true;
+/// Refuse passwords equal to the username, ignoring case
+pub const REJECT_PASSWORD_MATCHING_USERNAME: bool = true;
+pub const MAX_API_TOKEN_NAME_LENGTH: usize = 100;
+/// Random bytes in a personal API token
+pub const API_TOKEN_BYTES: usize = 32;
+/// Marks personal API tokens, e.g. for secret scanners
+pub const API_TOKEN_PREFIX: &str = "mbt_";
+/// How long a password reset token stays usable
+pub const PASSWORD_RESET_TOKEN_TTL_SECS: i64 = 60 * 60;
 
 // Error messages
 pub const ERR_DATABASE_ACCESS: &str = "Database access error";
 pub const ERR_DATABASE_OPERATION: &str = "Database operation failed";
 pub const ERR_INVALID_SESSION: &str = "Invalid session";
 pub const ERR_UNAUTHORIZED: &str = "Not logged in";
+pub const ERR_ADMIN_REQUIRED: &str = "Admin access required";
+pub const ERR_EXPORT_INTERRUPTED: &str = "Export was interrupted by a server restart";
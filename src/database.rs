@@ -1,34 +1,170 @@
 use anyhow::Result;
 use libsql::{Builder, Connection};
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::RwLock;
 
+use crate::archive::archive_table_name;
+
 const CREATE_USERS_TABLE: &str = r#"
 CREATE TABLE IF NOT EXISTS users (
     id             TEXT    PRIMARY KEY,
     name           TEXT    UNIQUE NOT NULL,
-    password_hash  TEXT    NOT NULL
+    password_hash  TEXT    NOT NULL,
+    role           TEXT    NOT NULL DEFAULT 'user',
+    created_at     INTEGER,
+    email          TEXT
+);
+"#;
+
+/// Emails are optional, so the index leaves any number of NULLs alone
+const CREATE_USERS_EMAIL_INDEX: &str =
+    "CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email ON users(email)";
+
+/// Sessions of the SQLite session store, the serialized record with its expiry
+const CREATE_SESSIONS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS sessions (
+    id         TEXT    PRIMARY KEY,
+    data       TEXT    NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+"#;
+
+/// Personal API tokens; only a hash of each token is kept
+const CREATE_API_TOKENS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id           TEXT    PRIMARY KEY,
+    user_id      TEXT    NOT NULL,
+    name         TEXT    NOT NULL,
+    token_hash   TEXT    UNIQUE NOT NULL,
+    created_at   INTEGER NOT NULL,
+    last_used_at INTEGER
+);
+"#;
+
+const CREATE_API_TOKENS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_api_tokens_user_id ON api_tokens(user_id);
+"#;
+
+/// Logins, one row per session, for listing and revoking them. The id is our
+/// own, kept in the session, so the session cookie's id is never shown.
+const CREATE_USER_SESSIONS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id            TEXT    PRIMARY KEY,
+    user_id       TEXT    NOT NULL,
+    created_at    INTEGER NOT NULL,
+    last_seen_at  INTEGER NOT NULL,
+    user_agent    TEXT,
+    ip            TEXT,
+    remember_me   INTEGER NOT NULL DEFAULT 1
+);
+"#;
+
+const CREATE_USER_SESSIONS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user_id ON user_sessions(user_id);
+"#;
+
+/// Outstanding password reset tokens by hash, each usable once before it expires
+const CREATE_PASSWORD_RESET_TOKENS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    token_hash  TEXT    PRIMARY KEY,
+    user_id     TEXT    NOT NULL,
+    expires_at  INTEGER NOT NULL
 );
 "#;
 
+const CREATE_PASSWORD_RESET_TOKENS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user_id ON password_reset_tokens(user_id);
+"#;
+
 const CREATE_RECORDS_TABLE: &str = r#"
 CREATE TABLE IF NOT EXISTS records (
     id          TEXT    PRIMARY KEY,
     name        TEXT    NOT NULL,
     amount      REAL    NOT NULL,
-    category_id TEXT    NOT NULL,
+    category_id TEXT,
     timestamp   INTEGER NOT NULL
 );
 "#;
 
+/// Every column of `records` in table order, including those added by migrations.
+pub const RECORD_TABLE_COLUMNS: &str = "id, name, amount, category_id, timestamp, currency, kind, created_at, updated_at, payment_method, version, starred";
+
+/// Column definitions matching [`RECORD_TABLE_COLUMNS`], for tables rebuilt or
+/// created with the full `records` schema.
+pub const RECORD_TABLE_DEFINITION: &str = "id TEXT PRIMARY KEY, name TEXT NOT NULL, amount REAL NOT NULL, category_id TEXT, timestamp INTEGER NOT NULL, currency TEXT NOT NULL DEFAULT 'USD', kind TEXT NOT NULL DEFAULT 'expense', created_at INTEGER NOT NULL DEFAULT 0, updated_at INTEGER NOT NULL DEFAULT 0, payment_method TEXT, version INTEGER NOT NULL DEFAULT 1, starred INTEGER NOT NULL DEFAULT 0";
+
+/// Full-text index over record names, kept in sync by the triggers below. It is an
+/// external content table keyed by the records rowid, so names are not stored twice.
+const CREATE_RECORDS_FTS_TABLE: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS records_fts USING fts5(
+    name,
+    content = 'records',
+    content_rowid = 'rowid'
+);
+"#;
+
+const CREATE_RECORDS_FTS_TRIGGERS: [&str; 3] = [
+    r#"
+CREATE TRIGGER IF NOT EXISTS records_fts_insert AFTER INSERT ON records BEGIN
+    INSERT INTO records_fts (rowid, name) VALUES (new.rowid, new.name);
+END;
+"#,
+    r#"
+CREATE TRIGGER IF NOT EXISTS records_fts_update AFTER UPDATE OF name ON records BEGIN
+    INSERT INTO records_fts (records_fts, rowid, name) VALUES ('delete', old.rowid, old.name);
+    INSERT INTO records_fts (rowid, name) VALUES (new.rowid, new.name);
+END;
+"#,
+    r#"
+CREATE TRIGGER IF NOT EXISTS records_fts_delete AFTER DELETE ON records BEGIN
+    INSERT INTO records_fts (records_fts, rowid, name) VALUES ('delete', old.rowid, old.name);
+END;
+"#,
+];
+
 const CREATE_CATEGORIES_TABLE: &str = r#"
 CREATE TABLE IF NOT EXISTS categories (
-    id        TEXT    PRIMARY KEY,
-    name      TEXT    UNIQUE NOT NULL,
-    is_income BOOLEAN NOT NULL DEFAULT FALSE
+    id             TEXT    PRIMARY KEY,
+    name           TEXT    UNIQUE NOT NULL,
+    is_income      BOOLEAN NOT NULL DEFAULT FALSE,
+    monthly_budget REAL,
+    parent_id      TEXT,
+    archived       INTEGER NOT NULL DEFAULT 0,
+    sort_order     INTEGER,
+    enforce_budget INTEGER NOT NULL DEFAULT 0,
+    updated_at     INTEGER NOT NULL DEFAULT 0
+);
+"#;
+
+const CREATE_RECORD_TAGS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS record_tags (
+    record_id TEXT NOT NULL,
+    tag       TEXT NOT NULL,
+    PRIMARY KEY (record_id, tag)
+);
+"#;
+
+const CREATE_RECORD_TAGS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_record_tags_tag ON record_tags(tag);
+"#;
+
+const CREATE_RECORD_SPLITS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS record_splits (
+    record_id   TEXT NOT NULL,
+    category_id TEXT NOT NULL,
+    amount      REAL NOT NULL,
+    PRIMARY KEY (record_id, category_id)
 );
 "#;
 
+const CREATE_RECORD_SPLITS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_record_splits_category_id ON record_splits(category_id);
+"#;
+
 const CREATE_RECORDS_INDEX: &str = r#"
 CREATE INDEX IF NOT EXISTS idx_records_timestamp ON records(timestamp);
 "#;
@@ -37,8 +173,300 @@ const CREATE_CATEGORIES_INDEX: &str = r#"
 CREATE INDEX IF NOT EXISTS idx_categories_name ON categories(name);
 "#;
 
+/// Category names are unique ignoring ASCII case, like the LOWER() checks in the
+/// handlers; the plain UNIQUE on `name` is case-sensitive.
+const CREATE_CATEGORIES_NAME_NOCASE_INDEX: &str = r#"
+CREATE UNIQUE INDEX IF NOT EXISTS idx_categories_name_nocase ON categories(name COLLATE NOCASE);
+"#;
+
+const CREATE_EXPORT_JOBS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS export_jobs (
+    id           TEXT    PRIMARY KEY,
+    format       TEXT    NOT NULL,
+    status       TEXT    NOT NULL,
+    start_time   INTEGER NOT NULL,
+    end_time     INTEGER NOT NULL,
+    progress     INTEGER NOT NULL DEFAULT 0,
+    total_rows   INTEGER NOT NULL DEFAULT 0,
+    file_path    TEXT    NOT NULL,
+    error        TEXT,
+    created_at   INTEGER NOT NULL,
+    completed_at INTEGER
+);
+"#;
+
+const CREATE_EXPORT_JOBS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_export_jobs_created_at ON export_jobs(created_at);
+"#;
+
+const CREATE_RECURRING_RULES_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS recurring_rules (
+    id          TEXT    PRIMARY KEY,
+    name        TEXT    NOT NULL,
+    amount      REAL    NOT NULL,
+    category_id TEXT    NOT NULL,
+    currency    TEXT    NOT NULL,
+    kind        TEXT    NOT NULL,
+    interval    TEXT    NOT NULL,
+    start_time  INTEGER NOT NULL,
+    next_run    INTEGER NOT NULL,
+    active      BOOLEAN NOT NULL DEFAULT 1
+);
+"#;
+
+const CREATE_RECURRING_RULES_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_recurring_rules_next_run ON recurring_rules(active, next_run);
+"#;
+
+/// Keyword rules suggesting a category for a record name. A higher priority wins
+/// when keywords of several categories match.
+const CREATE_CATEGORY_RULES_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS category_rules (
+    id          TEXT    PRIMARY KEY,
+    category_id TEXT    NOT NULL,
+    keyword     TEXT    NOT NULL,
+    priority    INTEGER NOT NULL DEFAULT 0
+);
+"#;
+
+const CREATE_CATEGORY_RULES_INDEX: &str = r#"
+CREATE UNIQUE INDEX IF NOT EXISTS idx_category_rules_keyword ON category_rules(category_id, keyword COLLATE NOCASE);
+"#;
+
+/// Rules go with their category, however it is deleted (plain delete, reassigning
+/// or cascading delete, replacing import).
+const CREATE_CATEGORY_RULES_TRIGGER: &str = r#"
+CREATE TRIGGER IF NOT EXISTS categories_delete_rules AFTER DELETE ON categories BEGIN
+    DELETE FROM category_rules WHERE category_id = old.id;
+END;
+"#;
+
+const CREATE_RECORD_HISTORY_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS record_history (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    record_id  TEXT    NOT NULL,
+    action     TEXT    NOT NULL,
+    changed_at INTEGER NOT NULL,
+    changes    TEXT    NOT NULL
+);
+"#;
+
+const CREATE_RECORD_HISTORY_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_record_history_record_id ON record_history(record_id, id);
+"#;
+
+/// Tombstones of deleted records for delta sync. Filled by a trigger, so every
+/// way of deleting records (single delete, purge, replacing import) leaves one.
+const CREATE_RECORD_DELETIONS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS record_deletions (
+    record_id  TEXT    PRIMARY KEY,
+    deleted_at INTEGER NOT NULL
+);
+"#;
+
+const CREATE_RECORD_DELETIONS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_record_deletions_deleted_at ON record_deletions(deleted_at);
+"#;
+
+const CREATE_RECORD_DELETIONS_TRIGGER: &str = r#"
+CREATE TRIGGER IF NOT EXISTS records_deletion_log AFTER DELETE ON records BEGIN
+    INSERT OR REPLACE INTO record_deletions (record_id, deleted_at)
+    VALUES (old.id, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+"#;
+
+const CREATE_IDEMPOTENCY_KEYS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key        TEXT    PRIMARY KEY,
+    request    TEXT    NOT NULL,
+    record_id  TEXT    NOT NULL,
+    response   TEXT    NOT NULL,
+    created_at INTEGER NOT NULL
+);
+"#;
+
+const CREATE_IDEMPOTENCY_KEYS_INDEX: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys(created_at);
+"#;
+
+const CREATE_SETTINGS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS settings (
+    key   TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+"#;
+
+/// Units of the user's default currency one unit of `currency` is worth.
+const CREATE_EXCHANGE_RATES_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    currency   TEXT    PRIMARY KEY,
+    rate       REAL    NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+"#;
+
+/// Years whose records were moved out of `records` into a `records_<year>` table.
+const CREATE_RECORD_ARCHIVES_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS record_archives (
+    year        INTEGER PRIMARY KEY,
+    archived_at INTEGER NOT NULL
+);
+"#;
+
+/// Background export jobs write to the user DB through their own connection,
+/// so wait for short-lived locks instead of failing with SQLITE_BUSY.
+const USER_DB_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Stored in each user DB's `PRAGMA user_version` once its schema is set up, so
+/// later opens skip the setup. Bump it whenever `migrate_user_db` changes.
+const USER_DB_SCHEMA_VERSION: i64 = 2;
+
 pub type Db = Arc<RwLock<Connection>>;
 
+/// Adds a column to an existing table unless it is already there. Columns added
+/// after a table's original CREATE statement go through here, so fresh and
+/// existing user databases end up with the same schema. Returns whether the
+/// column was added.
+async fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool> {
+    let mut rows = conn
+        .query(&format!("PRAGMA table_info({})", table), ())
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let name: String = row.get(1)?;
+        if name == column {
+            return Ok(false);
+        }
+    }
+
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        (),
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Whether `column` of `table` is declared NOT NULL.
+async fn column_is_not_null(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut rows = conn
+        .query(&format!("PRAGMA table_info({})", table), ())
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let name: String = row.get(1)?;
+        if name == column {
+            return Ok(row.get::<i64>(3)? != 0);
+        }
+    }
+    Ok(false)
+}
+
+/// Rebuilds a table holding records (`records` or a yearly archive) so that
+/// `category_id` is nullable, which SQLite cannot change in place. Rows keep their
+/// rowids, so the search index stays valid; indexes and triggers on the table are
+/// dropped with it and have to be recreated. Returns whether it was rebuilt.
+pub async fn make_record_category_nullable(conn: &Connection, table: &str) -> Result<bool> {
+    if !column_is_not_null(conn, table, "category_id").await? {
+        return Ok(false);
+    }
+
+    let rebuilt = format!("{}_rebuild", table);
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await?;
+    tx.execute(
+        &format!("CREATE TABLE {} ({})", rebuilt, RECORD_TABLE_DEFINITION),
+        (),
+    )
+    .await?;
+    tx.execute(
+        &format!(
+            "INSERT INTO {} (rowid, {}) SELECT rowid, {} FROM {}",
+            rebuilt, RECORD_TABLE_COLUMNS, RECORD_TABLE_COLUMNS, table
+        ),
+        (),
+    )
+    .await?;
+    tx.execute(&format!("DROP TABLE {}", table), ()).await?;
+    tx.execute(&format!("ALTER TABLE {} RENAME TO {}", rebuilt, table), ())
+        .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    let mut rows = conn
+        .query(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?",
+            [table],
+        )
+        .await?;
+    Ok(rows.next().await?.is_some())
+}
+
+/// Renames categories whose name repeats an older one ignoring case, to "Food (2)"
+/// and so on, so the case-insensitive unique index can be built.
+async fn rename_duplicate_category_names(conn: &Connection) -> Result<()> {
+    let mut rows = conn
+        .query(
+            "SELECT id, name FROM categories c WHERE EXISTS (SELECT 1 FROM categories o WHERE o.name = c.name COLLATE NOCASE AND o.rowid < c.rowid) ORDER BY rowid",
+            (),
+        )
+        .await?;
+    let mut duplicates = Vec::new();
+    while let Some(row) = rows.next().await? {
+        duplicates.push((row.get::<String>(0)?, row.get::<String>(1)?));
+    }
+
+    for (id, name) in &duplicates {
+        let mut suffix = 2;
+        let renamed = loop {
+            let candidate = format!("{} ({})", name, suffix);
+            let mut taken = conn
+                .query(
+                    "SELECT 1 FROM categories WHERE name = ? COLLATE NOCASE",
+                    [candidate.as_str()],
+                )
+                .await?;
+            if taken.next().await?.is_none() {
+                break candidate;
+            }
+            suffix += 1;
+        };
+        conn.execute(
+            "UPDATE categories SET name = ? WHERE id = ?",
+            (renamed.as_str(), id.as_str()),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Sets up the record name search index, filling it from existing records the
+/// first time. SQLite builds without FTS5 are left without it and record search
+/// falls back to LIKE.
+async fn init_records_fts(conn: &Connection) -> Result<()> {
+    let existed = table_exists(conn, "records_fts").await?;
+    if conn.execute(CREATE_RECORDS_FTS_TABLE, ()).await.is_err() {
+        return Ok(());
+    }
+    for trigger in CREATE_RECORDS_FTS_TRIGGERS {
+        conn.execute(trigger, ()).await?;
+    }
+    if !existed {
+        conn.execute(
+            "INSERT INTO records_fts (records_fts) VALUES ('rebuild')",
+            (),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
 /// Main users registry DB (users.db)
 pub async fn init_main_db(data_dir: &str) -> Result<Db> {
     tokio::fs::create_dir_all(data_dir).await?;
@@ -47,20 +475,167 @@ pub async fn init_main_db(data_dir: &str) -> Result<Db> {
     let conn = db.connect()?;
 
     conn.execute(CREATE_USERS_TABLE, ()).await?;
+    add_column_if_missing(&conn, "users", "role", "TEXT NOT NULL DEFAULT 'user'").await?;
+    add_column_if_missing(&conn, "users", "created_at", "INTEGER").await?;
+    add_column_if_missing(&conn, "users", "email", "TEXT").await?;
+    conn.execute(CREATE_USERS_EMAIL_INDEX, ()).await?;
+    conn.execute(CREATE_SESSIONS_TABLE, ()).await?;
+    conn.execute(CREATE_API_TOKENS_TABLE, ()).await?;
+    conn.execute(CREATE_API_TOKENS_INDEX, ()).await?;
+    conn.execute(CREATE_USER_SESSIONS_TABLE, ()).await?;
+    add_column_if_missing(
+        &conn,
+        "user_sessions",
+        "remember_me",
+        "INTEGER NOT NULL DEFAULT 1",
+    )
+    .await?;
+    conn.execute(CREATE_USER_SESSIONS_INDEX, ()).await?;
+    conn.execute(CREATE_PASSWORD_RESET_TOKENS_TABLE, ()).await?;
+    conn.execute(CREATE_PASSWORD_RESET_TOKENS_INDEX, ()).await?;
     Ok(Arc::new(RwLock::new(conn)))
 }
 
+/// Where the per-user DB of `user_id` lives.
+pub fn user_db_path(data_dir: &str, user_id: &str) -> PathBuf {
+    Path::new(data_dir).join(format!("user_{}.db", user_id))
+}
+
+/// Removes a per-user DB together with SQLite's journal files next to it.
+/// Files already gone are skipped, so an interrupted removal can be repeated.
+pub async fn remove_user_db(data_dir: &str, user_id: &str) -> Result<()> {
+    let path = user_db_path(data_dir, user_id);
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let mut file = path.clone().into_os_string();
+        file.push(suffix);
+        match tokio::fs::remove_file(&file).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 /// Per-user isolated DB (user_{id}.db)
 pub async fn get_user_db(data_dir: &str, user_id: &str) -> Result<Db> {
-    let path = Path::new(data_dir).join(format!("user_{}.db", user_id));
+    let path = user_db_path(data_dir, user_id);
     let db = Builder::new_local(path).build().await?;
     let conn = db.connect()?;
+    conn.busy_timeout(USER_DB_BUSY_TIMEOUT)?;
+
+    if user_db_schema_version(&conn).await? < USER_DB_SCHEMA_VERSION {
+        migrate_user_db(&conn).await?;
+        conn.execute(
+            &format!("PRAGMA user_version = {}", USER_DB_SCHEMA_VERSION),
+            (),
+        )
+        .await?;
+    }
 
+    Ok(Arc::new(RwLock::new(conn)))
+}
+
+async fn user_db_schema_version(conn: &Connection) -> Result<i64> {
+    let mut rows = conn.query("PRAGMA user_version", ()).await?;
+    match rows.next().await? {
+        Some(row) => Ok(row.get(0)?),
+        None => Ok(0),
+    }
+}
+
+/// Creates the user DB's tables and brings older databases up to date. Every
+/// step is idempotent, so a database left between versions can run it again.
+async fn migrate_user_db(conn: &Connection) -> Result<()> {
     // Create tables for user's expense data
     conn.execute(CREATE_RECORDS_TABLE, ()).await?;
     conn.execute(CREATE_CATEGORIES_TABLE, ()).await?;
     conn.execute(CREATE_RECORDS_INDEX, ()).await?;
     conn.execute(CREATE_CATEGORIES_INDEX, ()).await?;
+    conn.execute(CREATE_RECORD_TAGS_TABLE, ()).await?;
+    conn.execute(CREATE_RECORD_TAGS_INDEX, ()).await?;
+    conn.execute(CREATE_RECORD_SPLITS_TABLE, ()).await?;
+    conn.execute(CREATE_RECORD_SPLITS_INDEX, ()).await?;
+    conn.execute(CREATE_SETTINGS_TABLE, ()).await?;
+    conn.execute(CREATE_EXCHANGE_RATES_TABLE, ()).await?;
+    conn.execute(CREATE_RECORD_ARCHIVES_TABLE, ()).await?;
+    conn.execute(CREATE_EXPORT_JOBS_TABLE, ()).await?;
+    conn.execute(CREATE_EXPORT_JOBS_INDEX, ()).await?;
+    conn.execute(CREATE_RECURRING_RULES_TABLE, ()).await?;
+    conn.execute(CREATE_RECURRING_RULES_INDEX, ()).await?;
+    conn.execute(CREATE_CATEGORY_RULES_TABLE, ()).await?;
+    conn.execute(CREATE_CATEGORY_RULES_INDEX, ()).await?;
+    conn.execute(CREATE_CATEGORY_RULES_TRIGGER, ()).await?;
+    conn.execute(CREATE_RECORD_HISTORY_TABLE, ()).await?;
+    conn.execute(CREATE_RECORD_HISTORY_INDEX, ()).await?;
+    conn.execute(CREATE_RECORD_DELETIONS_TABLE, ()).await?;
+    conn.execute(CREATE_RECORD_DELETIONS_INDEX, ()).await?;
+    conn.execute(CREATE_RECORD_DELETIONS_TRIGGER, ()).await?;
+    conn.execute(CREATE_IDEMPOTENCY_KEYS_TABLE, ()).await?;
+    conn.execute(CREATE_IDEMPOTENCY_KEYS_INDEX, ()).await?;
 
-    Ok(Arc::new(RwLock::new(conn)))
+    // Migrations for columns added after the original schema
+    add_column_if_missing(conn, "records", "currency", "TEXT NOT NULL DEFAULT 'USD'").await?;
+    add_column_if_missing(conn, "records", "kind", "TEXT NOT NULL DEFAULT 'expense'").await?;
+    let added_created_at =
+        add_column_if_missing(conn, "records", "created_at", "INTEGER NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(conn, "records", "updated_at", "INTEGER NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(conn, "records", "payment_method", "TEXT").await?;
+    add_column_if_missing(conn, "records", "version", "INTEGER NOT NULL DEFAULT 1").await?;
+    add_column_if_missing(conn, "records", "starred", "INTEGER NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(conn, "categories", "monthly_budget", "REAL").await?;
+    add_column_if_missing(conn, "categories", "parent_id", "TEXT").await?;
+    add_column_if_missing(conn, "categories", "archived", "INTEGER NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(conn, "categories", "sort_order", "INTEGER").await?;
+    add_column_if_missing(
+        conn,
+        "categories",
+        "enforce_budget",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    add_column_if_missing(
+        conn,
+        "categories",
+        "updated_at",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    // Older databases may hold names differing only in case
+    rename_duplicate_category_names(conn).await?;
+    conn.execute(CREATE_CATEGORIES_NAME_NOCASE_INDEX, ())
+        .await?;
+    if added_created_at {
+        // Entry times of existing rows are unknown, their transaction time is the best guess
+        conn.execute(
+            "UPDATE records SET created_at = timestamp, updated_at = timestamp",
+            (),
+        )
+        .await?;
+    }
+    // Records may be left uncategorized
+    if make_record_category_nullable(conn, "records").await? {
+        conn.execute(CREATE_RECORDS_INDEX, ()).await?;
+        conn.execute(CREATE_RECORD_DELETIONS_TRIGGER, ()).await?;
+    }
+    let mut archives = conn.query("SELECT year FROM record_archives", ()).await?;
+    let mut archive_years = Vec::new();
+    while let Some(row) = archives.next().await? {
+        archive_years.push(row.get::<i32>(0)?);
+    }
+    for year in archive_years {
+        make_record_category_nullable(conn, &archive_table_name(year)).await?;
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_records_updated_at ON records(updated_at)",
+        (),
+    )
+    .await?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_categories_updated_at ON categories(updated_at)",
+        (),
+    )
+    .await?;
+    init_records_fts(conn).await?;
+
+    Ok(())
 }
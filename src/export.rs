@@ -0,0 +1,218 @@
+use axum::http::StatusCode;
+use std::fmt::Write;
+use time::OffsetDateTime;
+
+use crate::archive::records_source;
+use crate::database::Db;
+use crate::models::{Record, RecordKind};
+use crate::records::{RECORD_COLUMNS, extract_record_from_row};
+use crate::utils::{db_error, db_error_with_context};
+
+/// OFX caps NAME at 32 characters; the full name still goes into MEMO.
+const OFX_NAME_MAX_CHARS: usize = 32;
+
+/// Bank statement formats understood by accounting tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementFormat {
+    Qif,
+    Ofx,
+}
+
+impl StatementFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "qif" => Some(StatementFormat::Qif),
+            "ofx" => Some(StatementFormat::Ofx),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StatementFormat::Qif => "application/qif",
+            StatementFormat::Ofx => "application/x-ofx",
+        }
+    }
+}
+
+/// A record together with the name of its category, if the category still exists.
+pub struct StatementEntry {
+    pub record: Record,
+    pub category_name: Option<String>,
+}
+
+/// Loads the records in a time range, oldest first, with their category names.
+pub async fn load_statement_entries(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+) -> Result<Vec<StatementEntry>, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let source = records_source(&conn, start_time, end_time).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {}, (SELECT name FROM categories WHERE categories.id = records.category_id) FROM {} AS records WHERE timestamp BETWEEN ? AND ? ORDER BY timestamp ASC, id ASC",
+                RECORD_COLUMNS, source
+            ),
+            (start_time, end_time),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query records"))?;
+
+    let mut entries = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let category_name: Option<String> = row
+            .get(14)
+            .map_err(|_| db_error_with_context("invalid record data"))?;
+        entries.push(StatementEntry {
+            record: extract_record_from_row(row)?,
+            category_name,
+        });
+    }
+    Ok(entries)
+}
+
+/// Expenses leave the account and are written as negative amounts, income as positive.
+fn signed_amount(record: &Record) -> String {
+    let amount = match record.kind {
+        RecordKind::Expense => -record.amount,
+        RecordKind::Income => record.amount,
+    };
+    format!("{:.2}", amount)
+}
+
+fn utc_datetime(timestamp: i64) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(timestamp).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
+/// Both formats are line based, so line breaks inside a value would start a new field.
+fn single_line(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in single_line(value).chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a QIF bank register. Dates are US style `MM/DD/YYYY` in UTC, the record
+/// name is the payee and the category name the `L` field.
+pub fn write_qif(entries: &[StatementEntry]) -> String {
+    let mut qif = String::from("!Type:Bank\n");
+    for entry in entries {
+        let record = &entry.record;
+        let date = utc_datetime(record.timestamp);
+        let _ = writeln!(
+            qif,
+            "D{:02}/{:02}/{}",
+            u8::from(date.month()),
+            date.day(),
+            date.year()
+        );
+        let _ = writeln!(qif, "T{}", signed_amount(record));
+        let _ = writeln!(qif, "P{}", single_line(&record.name));
+        if let Some(category_name) = &entry.category_name {
+            let _ = writeln!(qif, "L{}", single_line(category_name));
+        }
+        qif.push_str("^\n");
+    }
+    qif
+}
+
+/// Renders `entries` in a statement format; `currency` and `generated_at` only
+/// matter for OFX.
+pub fn write_statement(
+    format: StatementFormat,
+    entries: &[StatementEntry],
+    currency: &str,
+    generated_at: i64,
+) -> String {
+    match format {
+        StatementFormat::Qif => write_qif(entries),
+        StatementFormat::Ofx => write_ofx(entries, currency, generated_at),
+    }
+}
+
+fn ofx_datetime(timestamp: i64) -> String {
+    let datetime = utc_datetime(timestamp);
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        datetime.year(),
+        u8::from(datetime.month()),
+        datetime.day(),
+        datetime.hour(),
+        datetime.minute(),
+        datetime.second()
+    )
+}
+
+/// Writes an OFX 2 bank statement in `currency`. Every record becomes a
+/// transaction keyed by its id, with the name as NAME (truncated) and MEMO.
+/// OFX has no per-transaction category, so category names are left out.
+pub fn write_ofx(entries: &[StatementEntry], currency: &str, generated_at: i64) -> String {
+    let generated_at = ofx_datetime(generated_at);
+    let (start, end) = match (entries.first(), entries.last()) {
+        (Some(first), Some(last)) => (
+            ofx_datetime(first.record.timestamp),
+            ofx_datetime(last.record.timestamp),
+        ),
+        _ => (generated_at.clone(), generated_at.clone()),
+    };
+
+    let mut ofx = String::new();
+    ofx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+    ofx.push_str("<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>\n");
+    ofx.push_str("<OFX>\n<SIGNONMSGSRSV1>\n<SONRS>\n");
+    ofx.push_str("<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>\n");
+    let _ = writeln!(ofx, "<DTSERVER>{}</DTSERVER>", generated_at);
+    ofx.push_str("<LANGUAGE>ENG</LANGUAGE>\n</SONRS>\n</SIGNONMSGSRSV1>\n");
+    ofx.push_str("<BANKMSGSRSV1>\n<STMTTRNRS>\n<TRNUID>0</TRNUID>\n");
+    ofx.push_str("<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>\n");
+    ofx.push_str("<STMTRS>\n");
+    let _ = writeln!(ofx, "<CURDEF>{}</CURDEF>", escape_xml(currency));
+    ofx.push_str("<BANKACCTFROM><BANKID>0</BANKID><ACCTID>budget</ACCTID><ACCTTYPE>CHECKING</ACCTTYPE></BANKACCTFROM>\n");
+    ofx.push_str("<BANKTRANLIST>\n");
+    let _ = writeln!(ofx, "<DTSTART>{}</DTSTART>", start);
+    let _ = writeln!(ofx, "<DTEND>{}</DTEND>", end);
+    for entry in entries {
+        let record = &entry.record;
+        let trntype = match record.kind {
+            RecordKind::Expense => "DEBIT",
+            RecordKind::Income => "CREDIT",
+        };
+        let short_name: String = record.name.chars().take(OFX_NAME_MAX_CHARS).collect();
+        ofx.push_str("<STMTTRN>\n");
+        let _ = writeln!(ofx, "<TRNTYPE>{}</TRNTYPE>", trntype);
+        let _ = writeln!(
+            ofx,
+            "<DTPOSTED>{}</DTPOSTED>",
+            ofx_datetime(record.timestamp)
+        );
+        let _ = writeln!(ofx, "<TRNAMT>{}</TRNAMT>", signed_amount(record));
+        let _ = writeln!(ofx, "<FITID>{}</FITID>", escape_xml(&record.id));
+        let _ = writeln!(ofx, "<NAME>{}</NAME>", escape_xml(&short_name));
+        let _ = writeln!(ofx, "<MEMO>{}</MEMO>", escape_xml(&record.name));
+        ofx.push_str("</STMTTRN>\n");
+    }
+    ofx.push_str("</BANKTRANLIST>\n");
+    // OFX requires a ledger balance, but records do not track one
+    let _ = writeln!(
+        ofx,
+        "<LEDGERBAL><BALAMT>0.00</BALAMT><DTASOF>{}</DTASOF></LEDGERBAL>",
+        end
+    );
+    ofx.push_str("</STMTRS>\n</STMTTRNRS>\n</BANKMSGSRSV1>\n</OFX>\n");
+    ofx
+}
@@ -0,0 +1,519 @@
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{StatusCode, header},
+};
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::amount_format::{current_amount_format, with_amount_format};
+use crate::archive::records_source;
+use crate::auth::get_current_user;
+use crate::constants::*;
+use crate::database::Db;
+use crate::export::{StatementFormat, load_statement_entries, write_statement};
+use crate::models::{CreateExportJobPayload, ExportJob, ExportJobStatus};
+use crate::records::{load_record_page, resolve_time_window, validate_export_format};
+use crate::settings::get_default_currency;
+use crate::utils::{db_error, db_error_with_context, get_database_path, get_user_database};
+
+const EXPORT_DOWNLOAD_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Directory holding every export file of a user.
+pub fn user_exports_dir(data_path: &str, user_id: &str) -> PathBuf {
+    PathBuf::from(data_path)
+        .join(EXPORTS_DIR_NAME)
+        .join(format!("user_{}", user_id))
+}
+
+/// Finished export files live in `{data_path}/exports/user_{id}/{job_id}.{format}`.
+pub fn export_file_path(data_path: &str, user_id: &str, job_id: &str, format: &str) -> PathBuf {
+    user_exports_dir(data_path, user_id).join(format!("{}.{}", job_id, format))
+}
+
+fn extract_export_job_from_row(row: &libsql::Row) -> Result<ExportJob, (StatusCode, String)> {
+    let status: String = row.get(2).map_err(|_| db_error())?;
+
+    Ok(ExportJob {
+        id: row.get(0).map_err(|_| db_error())?,
+        format: row.get(1).map_err(|_| db_error())?,
+        status: ExportJobStatus::parse(&status)
+            .ok_or_else(|| db_error_with_context("invalid export job status"))?,
+        start_time: row.get(3).map_err(|_| db_error())?,
+        end_time: row.get(4).map_err(|_| db_error())?,
+        progress: row.get(5).map_err(|_| db_error())?,
+        total_rows: row.get(6).map_err(|_| db_error())?,
+        error: row.get(7).map_err(|_| db_error())?,
+        created_at: row.get(8).map_err(|_| db_error())?,
+        completed_at: row.get(9).map_err(|_| db_error())?,
+    })
+}
+
+/// Loads a job together with the path of its output file.
+async fn load_export_job(
+    user_db: &Db,
+    job_id: &str,
+) -> Result<Option<(ExportJob, String)>, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT id, format, status, start_time, end_time, progress, total_rows, error, created_at, completed_at, file_path FROM export_jobs WHERE id = ?",
+            [job_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query export job"))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => {
+            let job = extract_export_job_from_row(&row)?;
+            let file_path: String = row.get(10).map_err(|_| db_error())?;
+            Ok(Some((job, file_path)))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn get_export_job(
+    user_db: &Db,
+    job_id: &str,
+) -> Result<Option<ExportJob>, (StatusCode, String)> {
+    Ok(load_export_job(user_db, job_id).await?.map(|(job, _)| job))
+}
+
+/// Inserts a pending job row and returns it with the path its file will be written
+/// to; the caller is expected to start it with `spawn_export_job`.
+pub async fn create_export_job(
+    user_db: &Db,
+    data_path: &str,
+    user_id: &str,
+    format: &str,
+    start_time: i64,
+    end_time: i64,
+    now: i64,
+) -> Result<(ExportJob, PathBuf), (StatusCode, String)> {
+    let job = ExportJob {
+        id: Uuid::new_v4().to_string(),
+        format: format.to_string(),
+        status: ExportJobStatus::Pending,
+        start_time,
+        end_time,
+        progress: 0,
+        total_rows: 0,
+        error: None,
+        created_at: now,
+        completed_at: None,
+    };
+
+    let file_path = export_file_path(data_path, user_id, &job.id, format);
+
+    let conn = user_db.write().await;
+    conn.execute(
+        "INSERT INTO export_jobs (id, format, status, start_time, end_time, file_path, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        (
+            job.id.as_str(),
+            job.format.as_str(),
+            job.status.as_str(),
+            start_time,
+            end_time,
+            file_path.to_string_lossy().as_ref(),
+            now,
+        ),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to create export job"))?;
+
+    Ok((job, file_path))
+}
+
+fn export_file_error() -> (StatusCode, String) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to write export file".to_string(),
+    )
+}
+
+/// Writes the job's records to `file_path` in `format`.
+///
+/// NDJSON records are read in keyset-paginated batches of
+/// `EXPORT_JOB_PROGRESS_ROWS`, and the progress counter is bumped after each
+/// batch. Statement formats go through the writers of GET /records/export and are
+/// rendered in one go. No lock on the user DB is held while the file is being
+/// written.
+pub async fn run_export_job(
+    user_db: &Db,
+    job_id: &str,
+    format: &str,
+    start_time: i64,
+    end_time: i64,
+    file_path: &std::path::Path,
+) -> Result<(), (StatusCode, String)> {
+    let (source, total_rows): (String, u32) = {
+        let conn = user_db.read().await;
+        let source = records_source(&conn, start_time, end_time).await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT COUNT(*) FROM {} AS records WHERE timestamp BETWEEN ? AND ?",
+                    source
+                ),
+                (start_time, end_time),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to count records"))?;
+        let total_rows = match rows.next().await.map_err(|_| db_error())? {
+            Some(row) => row.get(0).map_err(|_| db_error())?,
+            None => 0,
+        };
+        (source.into_owned(), total_rows)
+    };
+
+    {
+        let conn = user_db.write().await;
+        conn.execute(
+            "UPDATE export_jobs SET status = ?, total_rows = ? WHERE id = ?",
+            (ExportJobStatus::Running.as_str(), total_rows, job_id),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to update export job"))?;
+    }
+
+    if let Some(parent) = file_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|_| export_file_error())?;
+    }
+    let mut file = tokio::io::BufWriter::new(
+        tokio::fs::File::create(file_path)
+            .await
+            .map_err(|_| export_file_error())?,
+    );
+
+    let progress = match StatementFormat::parse(format) {
+        Some(format) => {
+            let entries = load_statement_entries(user_db, start_time, end_time).await?;
+            let body = write_statement(
+                format,
+                &entries,
+                &get_default_currency(user_db).await?,
+                time::OffsetDateTime::now_utc().unix_timestamp(),
+            );
+            file.write_all(body.as_bytes())
+                .await
+                .map_err(|_| export_file_error())?;
+            entries.len() as u32
+        }
+        None => {
+            write_ndjson_pages(user_db, job_id, &source, start_time, end_time, &mut file).await?
+        }
+    };
+
+    file.flush().await.map_err(|_| export_file_error())?;
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let conn = user_db.write().await;
+    conn.execute(
+        "UPDATE export_jobs SET status = ?, progress = ?, completed_at = ? WHERE id = ?",
+        (ExportJobStatus::Completed.as_str(), progress, now, job_id),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to complete export job"))?;
+
+    Ok(())
+}
+
+/// Appends the records of `source` in the job's range to `file` as NDJSON, page
+/// by page, bumping the job's progress after each page. Returns how many records
+/// were written.
+async fn write_ndjson_pages(
+    user_db: &Db,
+    job_id: &str,
+    source: &str,
+    start_time: i64,
+    end_time: i64,
+    file: &mut tokio::io::BufWriter<tokio::fs::File>,
+) -> Result<u32, (StatusCode, String)> {
+    // Resume after (timestamp, id); every real id sorts after the empty string
+    let mut cursor = (start_time, String::new());
+    let mut progress: u32 = 0;
+    loop {
+        let batch = load_record_page(
+            &*user_db.read().await,
+            source,
+            end_time,
+            &cursor,
+            EXPORT_JOB_PROGRESS_ROWS,
+        )
+        .await?;
+
+        let Some(last) = batch.last() else {
+            break;
+        };
+        cursor = (last.timestamp, last.id.clone());
+
+        let mut buffer = Vec::new();
+        for record in &batch {
+            serde_json::to_writer(&mut buffer, record).map_err(|_| export_file_error())?;
+            buffer.push(b'\n');
+        }
+        file.write_all(&buffer)
+            .await
+            .map_err(|_| export_file_error())?;
+
+        progress += batch.len() as u32;
+        let conn = user_db.write().await;
+        conn.execute(
+            "UPDATE export_jobs SET progress = ? WHERE id = ?",
+            (progress, job_id),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to update export progress"))?;
+    }
+
+    Ok(progress)
+}
+
+/// Runs a job in the background, recording a failure on the job row if it errors.
+pub fn spawn_export_job(user_db: Db, job: ExportJob, file_path: PathBuf) {
+    // The job outlives the request, so carry the request's amount format along
+    let amount_format = current_amount_format();
+    tokio::spawn(with_amount_format(amount_format, async move {
+        let result = run_export_job(
+            &user_db,
+            &job.id,
+            &job.format,
+            job.start_time,
+            job.end_time,
+            &file_path,
+        )
+        .await;
+
+        if let Err((_, message)) = result {
+            let now = time::OffsetDateTime::now_utc().unix_timestamp();
+            let conn = user_db.write().await;
+            let _ = conn
+                .execute(
+                    "UPDATE export_jobs SET status = ?, error = ?, completed_at = ? WHERE id = ?",
+                    (
+                        ExportJobStatus::Failed.as_str(),
+                        message,
+                        now,
+                        job.id.as_str(),
+                    ),
+                )
+                .await;
+        }
+    }));
+}
+
+async fn remove_export_file(file_path: &str) {
+    // A file that was never written (or already removed) is fine
+    let _ = tokio::fs::remove_file(file_path).await;
+}
+
+/// Deletes a finished job and its file. Returns false if the job does not exist.
+pub async fn delete_export_job(user_db: &Db, job_id: &str) -> Result<bool, (StatusCode, String)> {
+    let Some((job, file_path)) = load_export_job(user_db, job_id).await? else {
+        return Ok(false);
+    };
+
+    if matches!(
+        job.status,
+        ExportJobStatus::Pending | ExportJobStatus::Running
+    ) {
+        return Err((StatusCode::CONFLICT, "Export is still running".to_string()));
+    }
+
+    remove_export_file(&file_path).await;
+
+    let conn = user_db.write().await;
+    conn.execute("DELETE FROM export_jobs WHERE id = ?", [job_id])
+        .await
+        .map_err(|_| db_error_with_context("failed to delete export job"))?;
+
+    Ok(true)
+}
+
+/// Marks jobs left pending or running as failed. Their tasks died with the
+/// previous process, so at startup no unfinished job is really in progress.
+/// Returns how many jobs were marked.
+pub async fn fail_interrupted_export_jobs(
+    user_db: &Db,
+    now: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let conn = user_db.write().await;
+    let failed = conn
+        .execute(
+            "UPDATE export_jobs SET status = ?, error = ?, completed_at = ? WHERE status IN (?, ?)",
+            (
+                ExportJobStatus::Failed.as_str(),
+                ERR_EXPORT_INTERRUPTED,
+                now,
+                ExportJobStatus::Pending.as_str(),
+                ExportJobStatus::Running.as_str(),
+            ),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to update export jobs"))?;
+    Ok(failed as u32)
+}
+
+/// Removes jobs created before `cutoff` along with their files, leaving jobs
+/// still pending or running to finish. Returns how many jobs were purged.
+pub async fn purge_expired_export_jobs(
+    user_db: &Db,
+    cutoff: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let mut expired = Vec::new();
+    {
+        let conn = user_db.read().await;
+        let mut rows = conn
+            .query(
+                "SELECT id, file_path FROM export_jobs WHERE created_at < ? AND status NOT IN (?, ?)",
+                (
+                    cutoff,
+                    ExportJobStatus::Pending.as_str(),
+                    ExportJobStatus::Running.as_str(),
+                ),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query export jobs"))?;
+        while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            let id: String = row.get(0).map_err(|_| db_error())?;
+            let file_path: String = row.get(1).map_err(|_| db_error())?;
+            expired.push((id, file_path));
+        }
+    }
+
+    for (id, file_path) in &expired {
+        remove_export_file(file_path).await;
+
+        let conn = user_db.write().await;
+        conn.execute("DELETE FROM export_jobs WHERE id = ?", [id.as_str()])
+            .await
+            .map_err(|_| db_error_with_context("failed to delete export job"))?;
+    }
+
+    Ok(expired.len() as u32)
+}
+
+fn export_not_found() -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, "Export not found".to_string())
+}
+
+pub async fn create_export(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<CreateExportJobPayload>,
+) -> Result<(StatusCode, Json<ExportJob>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let format = validate_export_format(payload.format.as_deref())?;
+
+    let user_db = get_user_database(&user.id).await?;
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let (start_time, end_time) = resolve_time_window(payload.start_time, payload.end_time);
+
+    let (job, file_path) = create_export_job(
+        &user_db,
+        get_database_path(),
+        &user.id,
+        format,
+        start_time,
+        end_time,
+        now,
+    )
+    .await?;
+
+    spawn_export_job(user_db, job.clone(), file_path);
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+pub async fn get_export(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(job_id): Path<String>,
+) -> Result<(StatusCode, Json<ExportJob>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let job = get_export_job(&user_db, &job_id)
+        .await?
+        .ok_or_else(export_not_found)?;
+
+    Ok((StatusCode::OK, Json(job)))
+}
+
+pub async fn download_export(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(job_id): Path<String>,
+) -> Result<(StatusCode, [(header::HeaderName, String); 2], Body), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let (job, file_path) = load_export_job(&user_db, &job_id)
+        .await?
+        .ok_or_else(export_not_found)?;
+
+    if job.status != ExportJobStatus::Completed {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Export is not ready (status: {})", job.status.as_str()),
+        ));
+    }
+
+    let file = tokio::fs::File::open(&file_path).await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Export file is missing".to_string(),
+        )
+    })?;
+
+    let content_type = StatementFormat::parse(&job.format)
+        .map(|format| format.content_type())
+        .unwrap_or("application/x-ndjson");
+    let stream = futures_util::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut chunk = vec![0u8; EXPORT_DOWNLOAD_CHUNK_BYTES];
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok(Bytes::from(chunk)), Some(file)))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"export-{}.{}\"", job.id, job.format),
+            ),
+        ],
+        Body::from_stream(stream),
+    ))
+}
+
+pub async fn delete_export(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(job_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    if !delete_export_job(&user_db, &job_id).await? {
+        return Err(export_not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
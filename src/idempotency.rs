@@ -0,0 +1,107 @@
+use axum::http::{HeaderMap, StatusCode};
+
+use crate::amount_format::{AmountFormat, with_amount_format};
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::Record;
+use crate::utils::{db_error, db_error_with_context};
+
+/// The response stored for an idempotency key, together with the request it answered.
+pub struct IdempotentResponse {
+    pub request: String,
+    pub record: Record,
+}
+
+/// Reads the `Idempotency-Key` header. Keys are opaque to the server but must be
+/// non-empty visible ASCII of at most `MAX_IDEMPOTENCY_KEY_LENGTH` characters.
+pub fn idempotency_key_from_headers(
+    headers: &HeaderMap,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    let key = value.to_str().unwrap_or_default().trim();
+    if key.is_empty()
+        || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH
+        || !key.chars().all(|c| c.is_ascii_graphic())
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_IDEMPOTENCY_KEY_LENGTH
+            ),
+        ));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Looks up a key stored at or after `not_before`; older keys count as unused
+/// even before the maintenance task purges them.
+pub async fn find_idempotent_response(
+    conn: &libsql::Connection,
+    key: &str,
+    not_before: i64,
+) -> Result<Option<IdempotentResponse>, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            "SELECT request, response FROM idempotency_keys WHERE key = ? AND created_at >= ?",
+            (key, not_before),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query idempotency keys"))?;
+
+    let Some(row) = rows.next().await.map_err(|_| db_error())? else {
+        return Ok(None);
+    };
+    let request: String = row.get(0).map_err(|_| db_error())?;
+    let response: String = row.get(1).map_err(|_| db_error())?;
+
+    Ok(Some(IdempotentResponse {
+        request,
+        record: serde_json::from_str(&response).map_err(|_| db_error())?,
+    }))
+}
+
+/// Stores the record created for `key`. Called on the insert's own transaction
+/// so a key is never stored without its record, or the other way round.
+pub async fn store_idempotent_response(
+    conn: &libsql::Connection,
+    key: &str,
+    request: &str,
+    record: &Record,
+    now: i64,
+) -> Result<(), (StatusCode, String)> {
+    // Amounts are stored as plain numbers whatever format this request asked for
+    let response = with_amount_format(AmountFormat::Number, async {
+        serde_json::to_string(record)
+    })
+    .await
+    .map_err(|_| db_error())?;
+
+    // Replaces an expired key the maintenance task has not purged yet
+    conn.execute(
+        "INSERT OR REPLACE INTO idempotency_keys (key, request, record_id, response, created_at) VALUES (?, ?, ?, ?, ?)",
+        (key, request, record.id.as_str(), response, now),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to store idempotency key"))?;
+    Ok(())
+}
+
+/// Removes keys stored before `cutoff`. Returns how many keys were purged.
+pub async fn purge_expired_idempotency_keys(
+    user_db: &Db,
+    cutoff: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let conn = user_db.write().await;
+    let purged = conn
+        .execute(
+            "DELETE FROM idempotency_keys WHERE created_at < ?",
+            [cutoff],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to purge idempotency keys"))?;
+    Ok(purged as u32)
+}
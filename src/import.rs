@@ -0,0 +1,669 @@
+use std::collections::HashMap;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::amount_format::parse_amount_str;
+use crate::auth::get_current_user;
+use crate::categories::{normalize_category_name, validate_category_name, validate_monthly_budget};
+use crate::category_rules::CategoryRuleMatcher;
+use crate::closing::ensure_period_open;
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::{
+    BackupDocument, CsvImportPayload, CsvImportResponse, CsvRowError, ImportQuery, ImportResponse,
+    Record, RecordKind, RecordSplit,
+};
+use crate::records::{
+    insert_record, normalize_splits, normalize_tags, replace_record_splits, replace_record_tags,
+    validate_category_id, validate_currency, validate_payment_method, validate_record_amount,
+    validate_record_name, validate_record_timestamp, validate_split_total,
+};
+use crate::settings::get_default_currency;
+use crate::sync::advance_data_horizon;
+use crate::utils::{db_error, db_error_with_context, get_user_database, validate_category_exists};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Keep existing data and skip incoming ids that already exist
+    Merge,
+    /// Wipe categories and records before restoring
+    Replace,
+}
+
+pub fn parse_import_mode(mode: Option<&str>) -> Result<ImportMode, (StatusCode, String)> {
+    match mode {
+        Some("merge") => Ok(ImportMode::Merge),
+        Some("replace") => Ok(ImportMode::Replace),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            "Import mode must be 'merge' or 'replace'".to_string(),
+        )),
+    }
+}
+
+async fn apply_backup(
+    conn: &libsql::Connection,
+    document: &BackupDocument,
+    mode: ImportMode,
+) -> Result<ImportResponse, (StatusCode, String)> {
+    let mut summary = ImportResponse::default();
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+
+    if mode == ImportMode::Replace {
+        // Wiping records is a change to the closed period when any are dated in it
+        let mut rows = conn
+            .query("SELECT MIN(timestamp) FROM records", ())
+            .await
+            .map_err(|_| db_error_with_context("failed to query records"))?;
+        if let Some(row) = rows.next().await.map_err(|_| db_error())?
+            && let Some(earliest) = row.get::<Option<i64>>(0).map_err(|_| db_error())?
+        {
+            ensure_period_open(conn, earliest).await?;
+        }
+
+        advance_data_horizon(conn).await?;
+        conn.execute("DELETE FROM records", ())
+            .await
+            .map_err(|_| db_error_with_context("failed to clear records"))?;
+        conn.execute("DELETE FROM record_tags", ())
+            .await
+            .map_err(|_| db_error_with_context("failed to clear record tags"))?;
+        conn.execute("DELETE FROM record_splits", ())
+            .await
+            .map_err(|_| db_error_with_context("failed to clear record splits"))?;
+        conn.execute("DELETE FROM categories", ())
+            .await
+            .map_err(|_| db_error_with_context("failed to clear categories"))?;
+    }
+
+    // Backup category ids resolved to an existing category of the same name
+    let mut category_id_map: HashMap<&str, String> = HashMap::new();
+    let resolve = |category_id_map: &HashMap<&str, String>, id: &str| -> String {
+        category_id_map
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    };
+
+    // Categories go first so record category_id references resolve
+    for (index, category) in document.categories.iter().enumerate() {
+        let with_index =
+            |(status, msg): (StatusCode, String)| (status, format!("Category {}: {}", index, msg));
+        validate_category_name(&category.name).map_err(with_index)?;
+        let name = normalize_category_name(&category.name);
+        if let Some(budget) = category.monthly_budget {
+            validate_monthly_budget(budget).map_err(with_index)?;
+        }
+
+        // A category already there under the same id, or else under the same
+        // name in another case, is kept and the backup's one skipped
+        let mut rows = conn
+            .query(
+                "SELECT id FROM categories WHERE id = ?1 OR LOWER(name) = LOWER(?2) ORDER BY id = ?1 DESC LIMIT 1",
+                [category.id.as_str(), name.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to check existing category"))?;
+        if let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            let existing_id: String = row.get(0).map_err(|_| db_error())?;
+            if existing_id != category.id {
+                category_id_map.insert(category.id.as_str(), existing_id);
+            }
+            summary.categories_skipped += 1;
+            continue;
+        }
+
+        conn.execute(
+            "INSERT INTO categories (id, name, is_income, monthly_budget, parent_id, archived, sort_order, enforce_budget, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            (
+                category.id.as_str(),
+                name,
+                category.is_income,
+                category.monthly_budget,
+                category
+                    .parent_id
+                    .as_deref()
+                    .map(|id| resolve(&category_id_map, id)),
+                category.archived,
+                category.sort_order,
+                category.enforce_budget,
+                now,
+            ),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to import category"))?;
+        summary.categories_created += 1;
+    }
+
+    for (index, record) in document.records.iter().enumerate() {
+        let with_index =
+            |(status, msg): (StatusCode, String)| (status, format!("Record {}: {}", index, msg));
+        validate_record_name(&record.name).map_err(with_index)?;
+        validate_record_amount(record.amount).map_err(with_index)?;
+        if let Some(ref category_id) = record.category_id {
+            validate_category_id(category_id).map_err(with_index)?;
+        }
+        let record_category_id = record
+            .category_id
+            .as_deref()
+            .map(|id| resolve(&category_id_map, id));
+        validate_currency(&record.currency).map_err(with_index)?;
+        if let Some(ref payment_method) = record.payment_method {
+            validate_payment_method(payment_method).map_err(with_index)?;
+        }
+        let tags = normalize_tags(&record.tags).map_err(with_index)?;
+        let splits: Vec<RecordSplit> = record
+            .splits
+            .iter()
+            .map(|split| RecordSplit {
+                category_id: resolve(&category_id_map, split.category_id.trim()),
+                amount: split.amount,
+            })
+            .collect();
+        let splits = normalize_splits(&splits).map_err(with_index)?;
+        validate_split_total(record.amount, &splits).map_err(with_index)?;
+
+        // The record's own category and every split category must exist
+        let category_ids = record_category_id
+            .as_deref()
+            .into_iter()
+            .chain(splits.iter().map(|split| split.category_id.as_str()));
+        for category_id in category_ids {
+            let mut category_rows = conn
+                .query("SELECT id FROM categories WHERE id = ?", [category_id])
+                .await
+                .map_err(|_| db_error_with_context("failed to check category existence"))?;
+            if category_rows
+                .next()
+                .await
+                .map_err(|_| db_error())?
+                .is_none()
+            {
+                return Err(with_index((
+                    StatusCode::BAD_REQUEST,
+                    "Category does not exist".to_string(),
+                )));
+            }
+        }
+
+        // Backups written before entry times were tracked are stamped with the import time
+        let created_at = if record.created_at > 0 {
+            record.created_at
+        } else {
+            now
+        };
+        let updated_at = record.updated_at.max(created_at);
+
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO records (id, name, amount, category_id, timestamp, currency, kind, payment_method, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                (
+                    record.id.as_str(),
+                    record.name.trim(),
+                    record.amount,
+                    record_category_id.as_deref(),
+                    record.timestamp,
+                    record.currency.as_str(),
+                    record.kind.as_str(),
+                    record.payment_method.as_deref().map(str::trim),
+                    created_at,
+                    updated_at,
+                ),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to import record"))?;
+
+        if inserted == 0 {
+            summary.records_skipped += 1;
+        } else {
+            // Only records actually added count as a change to the closed period
+            ensure_period_open(conn, record.timestamp)
+                .await
+                .map_err(with_index)?;
+            replace_record_tags(conn, &record.id, &tags).await?;
+            replace_record_splits(conn, &record.id, &splits).await?;
+            summary.records_created += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Restores a backup document into the user's database inside a single
+/// transaction; any invalid entry rolls back the whole import.
+pub async fn restore_backup(
+    user_db: &Db,
+    document: &BackupDocument,
+    mode: ImportMode,
+) -> Result<ImportResponse, (StatusCode, String)> {
+    if document.version != BACKUP_FORMAT_VERSION {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported backup version: {}", document.version),
+        ));
+    }
+
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start import"))?;
+
+    match apply_backup(&tx, document, mode).await {
+        Ok(summary) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit import"))?;
+            Ok(summary)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+pub async fn import_backup(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<ImportQuery>,
+    Json(document): Json<BackupDocument>,
+) -> Result<(StatusCode, Json<ImportResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let mode = parse_import_mode(query.mode.as_deref())?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let summary = restore_backup(&user_db, &document, mode).await?;
+
+    Ok((StatusCode::OK, Json(summary)))
+}
+
+/// A CSV row together with the line it starts on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRow {
+    pub line: u32,
+    pub fields: Vec<String>,
+}
+
+/// Splits CSV text into rows. Fields may be quoted, with `""` for a literal quote,
+/// and quoted fields may span lines. Blank lines are skipped.
+pub fn parse_csv(text: &str, delimiter: char) -> Result<Vec<CsvRow>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+
+    let mut end_row = |fields: &mut Vec<String>, row_line: u32| {
+        let fields = std::mem::take(fields);
+        if !(fields.len() == 1 && fields[0].trim().is_empty()) {
+            rows.push(CsvRow {
+                line: row_line,
+                fields,
+            });
+        }
+    };
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                fields.push(std::mem::take(&mut field));
+                end_row(&mut fields, row_line);
+                line += 1;
+                row_line = line;
+            }
+            c if c == delimiter => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("Line {}: unterminated quoted field", row_line));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        end_row(&mut fields, row_line);
+    }
+    Ok(rows)
+}
+
+/// Checks a `date_format` once up front, so a typo fails the request instead of every row.
+pub fn validate_date_format(format: &str) -> Result<(), (StatusCode, String)> {
+    let invalid = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let mut seen = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some(spec @ ('d' | 'm' | 'Y' | 'y')) => seen.push(spec),
+            Some('%') => {}
+            Some(spec) => {
+                return Err(invalid(format!(
+                    "Unsupported date format directive: %{}",
+                    spec
+                )));
+            }
+            None => return Err(invalid("Date format cannot end with %".to_string())),
+        }
+    }
+
+    let has = |spec: char| seen.matches(spec).count();
+    let years = has('Y') + has('y');
+    if has('d') != 1 || has('m') != 1 || years != 1 {
+        return Err(invalid(
+            "Date format needs exactly one each of %d, %m and %Y or %y".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Parses a statement date with a format accepted by `validate_date_format` and
+/// returns midnight UTC of that day. `%y` years are taken to be in 2000-2099.
+pub fn parse_statement_date(value: &str, format: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid date {:?}, expected {}", value, format);
+    let mut input = value.trim().chars().peekable();
+    let (mut day, mut month, mut year) = (0u8, 0u8, 0i32);
+
+    let mut format_chars = format.chars();
+    while let Some(c) = format_chars.next() {
+        let spec = match c {
+            '%' => format_chars.next().ok_or_else(invalid)?,
+            literal => {
+                if input.next() != Some(literal) {
+                    return Err(invalid());
+                }
+                continue;
+            }
+        };
+        // Day and month may drop their leading zero; years have a fixed width
+        let (min_digits, max_digits) = match spec {
+            'd' | 'm' => (1, 2),
+            'y' => (2, 2),
+            'Y' => (4, 4),
+            _ => {
+                if input.next() != Some(spec) {
+                    return Err(invalid());
+                }
+                continue;
+            }
+        };
+        let mut digits = String::new();
+        while digits.len() < max_digits
+            && let Some(digit) = input.next_if(char::is_ascii_digit)
+        {
+            digits.push(digit);
+        }
+        if digits.len() < min_digits {
+            return Err(invalid());
+        }
+        let number: i32 = digits.parse().map_err(|_| invalid())?;
+        match spec {
+            'd' => day = number as u8,
+            'm' => month = number as u8,
+            'y' => year = 2000 + number,
+            _ => year = number,
+        }
+    }
+    if input.next().is_some() {
+        return Err(invalid());
+    }
+
+    let month = time::Month::try_from(month).map_err(|_| invalid())?;
+    let date = time::Date::from_calendar_date(year, month, day).map_err(|_| invalid())?;
+    Ok(date.midnight().assume_utc().unix_timestamp())
+}
+
+/// Parses a statement amount such as "-1.234,56" with `,` as the decimal separator.
+/// The other separator is dropped as a thousands separator, along with spaces and a
+/// leading plus sign.
+pub fn parse_statement_amount(value: &str, decimal_separator: char) -> Result<f64, String> {
+    let thousands_separator = if decimal_separator == ',' { '.' } else { ',' };
+    let normalized: String = value
+        .chars()
+        .filter(|c| *c != thousands_separator && !c.is_whitespace())
+        .map(|c| if c == decimal_separator { '.' } else { c })
+        .collect();
+    let normalized = normalized.strip_prefix('+').unwrap_or(&normalized);
+    parse_amount_str(normalized).map_err(|_| format!("invalid amount {:?}", value.trim()))
+}
+
+fn single_char_option(
+    value: Option<&str>,
+    default: char,
+    name: &str,
+    allowed: &[char],
+) -> Result<char, (StatusCode, String)> {
+    let Some(value) = value else {
+        return Ok(default);
+    };
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if allowed.is_empty() || allowed.contains(&c) => Ok(c),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid {}: {:?}", name, value),
+        )),
+    }
+}
+
+/// Turns one mapped CSV row into a record filed under `category_id`. Negative
+/// amounts are money going out and become expenses, positive amounts become income.
+fn record_from_csv_row(
+    row: &CsvRow,
+    payload: &CsvImportPayload,
+    category_id: Option<&str>,
+    date_format: &str,
+    decimal_separator: char,
+    currency: &str,
+    now: i64,
+) -> Result<Record, String> {
+    let field = |index: usize| {
+        row.fields
+            .get(index)
+            .map(|value| value.trim())
+            .ok_or_else(|| format!("missing column {}", index))
+    };
+    let message = |(_, msg): (StatusCode, String)| msg;
+
+    let name = field(payload.columns.description)?;
+    validate_record_name(name).map_err(message)?;
+    let signed_amount = parse_statement_amount(field(payload.columns.amount)?, decimal_separator)?;
+    validate_record_amount(signed_amount).map_err(message)?;
+    let timestamp = parse_statement_date(field(payload.columns.date)?, date_format)?;
+    validate_record_timestamp(timestamp, now).map_err(message)?;
+
+    let kind = if signed_amount < 0.0 {
+        RecordKind::Expense
+    } else {
+        RecordKind::Income
+    };
+    Ok(Record {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        amount: signed_amount.abs(),
+        category_id: category_id.map(str::to_string),
+        timestamp,
+        currency: currency.to_string(),
+        kind,
+        payment_method: None,
+        tags: Vec::new(),
+        splits: Vec::new(),
+        created_at: now,
+        updated_at: now,
+        version: 1,
+        starred: false,
+        balance: None,
+        category: None,
+        budget_warnings: Vec::new(),
+        matched_rule: None,
+    })
+}
+
+/// Creates records from a bank statement CSV. Rows that do not parse are reported
+/// by line and skipped, or fail the whole import in strict mode; the valid rows are
+/// inserted in a single transaction.
+pub async fn import_statement_csv(
+    user_db: &Db,
+    payload: &CsvImportPayload,
+    now: i64,
+) -> Result<CsvImportResponse, (StatusCode, String)> {
+    let date_format = payload
+        .date_format
+        .as_deref()
+        .unwrap_or(DEFAULT_CSV_DATE_FORMAT);
+    validate_date_format(date_format)?;
+    let decimal_separator = single_char_option(
+        payload.decimal_separator.as_deref(),
+        '.',
+        "decimal separator",
+        &['.', ','],
+    )?;
+    let delimiter = single_char_option(payload.delimiter.as_deref(), ',', "delimiter", &[])?;
+    if delimiter == '"' || delimiter == '\n' || delimiter == '\r' {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid delimiter: {:?}", delimiter),
+        ));
+    }
+    let fallback_category_id = payload.category_id.as_deref().map(str::trim);
+    match fallback_category_id {
+        Some(category_id) => validate_category_exists(user_db, category_id).await?,
+        None if payload.auto_categorize => {}
+        None => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "category_id is required unless auto_categorize is set".to_string(),
+            ));
+        }
+    }
+
+    let mut rows =
+        parse_csv(&payload.csv, delimiter).map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    if payload.has_header.unwrap_or(true) && !rows.is_empty() {
+        rows.remove(0);
+    }
+    if rows.len() > MAX_CSV_IMPORT_ROWS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("CSV cannot have more than {} rows", MAX_CSV_IMPORT_ROWS),
+        ));
+    }
+
+    let currency = get_default_currency(user_db).await?;
+    let matcher = if payload.auto_categorize {
+        Some(CategoryRuleMatcher::load(&*user_db.read().await).await?)
+    } else {
+        None
+    };
+    let mut records = Vec::with_capacity(rows.len());
+    let mut auto_categorized = 0;
+    let mut summary = CsvImportResponse::default();
+    for row in &rows {
+        let matched_category_id = matcher.as_ref().and_then(|matcher| {
+            let name = row.fields.get(payload.columns.description)?;
+            matcher
+                .find(name.trim())
+                .map(|rule| rule.category_id.as_str())
+        });
+        match record_from_csv_row(
+            row,
+            payload,
+            matched_category_id.or(fallback_category_id),
+            date_format,
+            decimal_separator,
+            &currency,
+            now,
+        ) {
+            Ok(record) => {
+                if matched_category_id.is_some() {
+                    auto_categorized += 1;
+                }
+                records.push(record);
+            }
+            Err(message) if payload.strict => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Line {}: {}", row.line, message),
+                ));
+            }
+            Err(message) => summary.errors.push(CsvRowError {
+                line: row.line,
+                message,
+            }),
+        }
+    }
+
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start import"))?;
+    let result = async {
+        for record in &records {
+            ensure_period_open(&tx, record.timestamp).await?;
+            insert_record(&tx, record).await?;
+        }
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to commit import"))?;
+            summary.records_created = records.len() as u32;
+            summary.records_auto_categorized = auto_categorized;
+            Ok(summary)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+pub async fn import_csv(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<CsvImportPayload>,
+) -> Result<(StatusCode, Json<CsvImportResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let summary = import_statement_csv(&user_db, &payload, now).await?;
+
+    Ok((StatusCode::OK, Json(summary)))
+}
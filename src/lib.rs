@@ -1,8 +1,35 @@
+pub mod admin;
+pub mod amount_format;
+pub mod api_tokens;
+pub mod app;
+pub mod archive;
 pub mod auth;
+pub mod budgets;
 pub mod categories;
+pub mod category_csv;
+pub mod category_rules;
+pub mod closing;
 pub mod config;
 pub mod constants;
 pub mod database;
+pub mod export;
+pub mod export_jobs;
+pub mod idempotency;
+pub mod import;
+pub mod maintenance;
 pub mod models;
+pub mod onboarding;
+pub mod orphans;
+pub mod password_reset;
+pub mod rate_limit;
+pub mod record_history;
 pub mod records;
+pub mod recurring;
+pub mod session_store;
+pub mod settings;
+pub mod sync;
+#[cfg(feature = "test-utils")]
+pub mod test_support;
+pub mod timestamp_format;
+pub mod user_sessions;
 pub mod utils;
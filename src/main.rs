@@ -1,26 +1,37 @@
-use axum::{
-    Router,
-    response::Html,
-    routing::{get, post, put},
-};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use time::Duration;
 use tower_http::cors::CorsLayer;
-use tower_sessions::{Expiry, MemoryStore, Session, SessionManagerLayer, cookie::Key};
-
-pub mod auth;
-pub mod categories;
-pub mod config;
-pub mod constants;
-pub mod database;
-pub mod models;
-pub mod records;
-pub mod utils;
-
-use config::Config;
-use constants::*;
+use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer, SessionStore, cookie::Key};
+
+use my_budget_server::admin;
+use my_budget_server::app::build_app;
+use my_budget_server::auth;
+use my_budget_server::config::{Config, SessionStoreKind};
+use my_budget_server::constants::*;
+use my_budget_server::database;
+use my_budget_server::maintenance::{recover_interrupted_export_jobs, spawn_maintenance_scheduler};
+use my_budget_server::password_reset::LogResetTokenSender;
+use my_budget_server::recurring::spawn_recurring_scheduler;
+use my_budget_server::session_store::SqliteStore;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
+/// Wraps the app in a session layer over `store` with signed cookies.
+fn with_sessions<S: SessionStore + Clone>(
+    app: axum::Router,
+    store: S,
+    key: Key,
+    secure: bool,
+) -> axum::Router {
+    let session_layer = SessionManagerLayer::new(store)
+        .with_secure(secure) // Only secure in production
+        .with_name(SESSION_NAME)
+        .with_expiry(Expiry::OnInactivity(Duration::days(SESSION_EXPIRY_DAYS)))
+        .with_signed(key);
+    app.layer(session_layer)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load environment variables
@@ -29,15 +40,42 @@ async fn main() -> Result<()> {
     // Load and validate configuration
     let config = Config::from_env().map_err(|e| format!("Configuration error: {}", e))?;
 
+    // Hash the dummy password for unknown login names up front, so the first
+    // such login is not slower than the rest
+    auth::dummy_password_hash();
+
     // Initialize main database
     let main_db = database::init_main_db(&config.data_path)
         .await
         .map_err(|e| format!("Failed to initialize main database: {}", e))?;
 
-    // Create session store
-    let store = MemoryStore::default();
-    // TODO: Consider adding periodic session cleanup for long-running deployments
-    // to prevent memory growth with accumulated expired sessions
+    if let Some(username) = &config.admin_username {
+        let granted = admin::grant_admin(&main_db, username)
+            .await
+            .map_err(|e| format!("Failed to grant admin role: {}", e))?;
+        if !granted {
+            eprintln!(
+                "{} names no existing account: {}",
+                ADMIN_USERNAME_VAR, username
+            );
+        }
+    }
+
+    // Exports still marked in progress died with the previous process
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let interrupted = recover_interrupted_export_jobs(&main_db, &config.data_path, now)
+        .await
+        .map_err(|(_, message)| format!("Failed to recover export jobs: {}", message))?;
+    if interrupted > 0 {
+        println!("Marked {} interrupted export jobs as failed", interrupted);
+    }
+
+    // Purge expired export jobs, other stale per-user data and, with a retention
+    // period configured, old records in the background
+    spawn_maintenance_scheduler(main_db.clone(), config.record_retention_days);
+
+    // Post due occurrences of recurring rules as records
+    spawn_recurring_scheduler(main_db.clone());
 
     // Create session key with proper error handling
     let session_key = Key::try_from(config.session_secret.as_bytes())
@@ -49,12 +87,6 @@ async fn main() -> Result<()> {
         .map(|val| val.to_lowercase() == "true")
         .unwrap_or(false);
 
-    let session_layer = SessionManagerLayer::new(store)
-        .with_secure(is_production) // Only secure in production
-        .with_name(SESSION_NAME)
-        .with_expiry(Expiry::OnInactivity(Duration::days(SESSION_EXPIRY_DAYS)))
-        .with_signed(session_key);
-
     // Configure CORS to allow frontend requests
     let frontend_origin =
         std::env::var("FRONTEND_ORIGIN").unwrap_or_else(|_| "http://localhost:5173".to_string());
@@ -65,41 +97,42 @@ async fn main() -> Result<()> {
             axum::http::Method::GET,
             axum::http::Method::POST,
             axum::http::Method::PUT,
+            axum::http::Method::PATCH,
             axum::http::Method::DELETE,
         ])
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             axum::http::header::ACCEPT,
             axum::http::header::COOKIE,
+            axum::http::header::AUTHORIZATION,
+            axum::http::header::IF_MATCH,
+            axum::http::HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+        ])
+        // Response headers the frontend reads besides the CORS-safelisted ones
+        .expose_headers([
+            axum::http::HeaderName::from_static(TOTAL_COUNT_HEADER),
+            axum::http::header::ETAG,
         ])
         .allow_credentials(true);
 
-    // Build application router
-    let app = Router::new()
-        .route("/", get(root))
-        .route("/auth/register", post(auth::register))
-        .route("/auth/login", post(auth::login))
-        .route("/auth/me", get(auth::me))
-        .route("/auth/logout", post(auth::logout))
-        .route(
-            "/records",
-            post(records::create_record).get(records::get_records),
-        )
-        .route(
-            "/records/{id}",
-            put(records::update_record).delete(records::delete_record),
-        )
-        .route(
-            "/categories",
-            post(categories::create_category).get(categories::get_categories),
-        )
-        .route(
-            "/categories/{id}",
-            put(categories::update_category).delete(categories::delete_category),
-        )
-        .layer(cors)
-        .layer(session_layer)
-        .with_state(main_db);
+    // Build application router. Sessions live in memory, which every restart
+    // clears, or in users.db, where maintenance purges the expired ones
+    let app = build_app(
+        main_db.clone(),
+        config.auth_rate_limit,
+        Arc::new(LogResetTokenSender),
+    )
+    .layer(cors);
+    let app = match config.session_store {
+        SessionStoreKind::Memory => {
+            // TODO: Consider adding periodic session cleanup for long-running deployments
+            // to prevent memory growth with accumulated expired sessions
+            with_sessions(app, MemoryStore::default(), session_key, is_production)
+        }
+        SessionStoreKind::Sqlite => {
+            with_sessions(app, SqliteStore::new(main_db), session_key, is_production)
+        }
+    };
 
     // Create TCP listener with proper error handling
     let bind_address = config.bind_address();
@@ -110,26 +143,13 @@ async fn main() -> Result<()> {
     println!("Server running on http://{}", bind_address);
 
     // Start server with proper error handling
-    axum::serve(listener, app)
-        .await
-        .map_err(|e| format!("Server error: {}", e))?;
+    // Connect info gives the rate limiter the client address
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .map_err(|e| format!("Server error: {}", e))?;
 
     Ok(())
 }
-
-async fn root(session: Session) -> Html<String> {
-    let count: usize = session
-        .get("visitor_count")
-        .await
-        .unwrap_or(Some(0))
-        .unwrap_or(0);
-    let new_count = count + 1;
-
-    // Ignore session update errors for this simple endpoint
-    let _ = session.insert("visitor_count", new_count).await;
-
-    Html(format!(
-        "<h1>My Budget Server</h1><p>API Ready - Visit count: {}</p>",
-        new_count
-    ))
-}
@@ -0,0 +1,154 @@
+use axum::http::StatusCode;
+use std::time::Duration;
+
+use crate::categories::find_duplicate_categories;
+use crate::constants::*;
+use crate::database::{Db, get_user_db};
+use crate::export_jobs::{fail_interrupted_export_jobs, purge_expired_export_jobs};
+use crate::idempotency::purge_expired_idempotency_keys;
+use crate::password_reset::purge_expired_reset_tokens;
+use crate::records::purge_expired_records;
+use crate::session_store::purge_expired_sessions;
+use crate::user_sessions::purge_stale_user_sessions;
+use crate::utils::{get_database_path, list_user_ids};
+
+async fn open_user_db(data_path: &str, user_id: &str) -> Result<Db, (StatusCode, String)> {
+    get_user_db(data_path, user_id).await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ERR_DATABASE_ACCESS.to_string(),
+        )
+    })
+}
+
+/// Runs one maintenance pass, purging expired sessions and reset tokens and,
+/// over every user's database, expired export jobs and idempotency keys and
+/// reporting category names that collide once normalized. A user whose database
+/// fails is reported and skipped. Returns the number of export jobs that were
+/// purged.
+pub async fn run_maintenance(
+    main_db: &Db,
+    data_path: &str,
+    now: i64,
+) -> Result<u32, (StatusCode, String)> {
+    purge_expired_sessions(main_db, now).await?;
+    purge_expired_reset_tokens(main_db, now).await?;
+    purge_stale_user_sessions(main_db, now - SESSION_EXPIRY_DAYS * 24 * 60 * 60).await?;
+    let user_ids = list_user_ids(main_db).await?;
+
+    let mut purged = 0;
+    for user_id in user_ids {
+        let result = async {
+            let user_db = open_user_db(data_path, &user_id).await?;
+            let purged =
+                purge_expired_export_jobs(&user_db, now - EXPORT_JOB_RETENTION_SECS).await?;
+            purge_expired_idempotency_keys(&user_db, now - IDEMPOTENCY_KEY_TTL_SECS).await?;
+
+            let duplicates = find_duplicate_categories(&*user_db.read().await).await?;
+            for group in duplicates {
+                eprintln!(
+                    "User {} has {} categories named '{}' once normalized; see GET /categories/duplicates",
+                    user_id,
+                    group.categories.len(),
+                    group.normalized_name
+                );
+            }
+            Ok::<_, (StatusCode, String)>(purged)
+        }
+        .await;
+        match result {
+            Ok(count) => purged += count,
+            Err((_, message)) => {
+                eprintln!("Maintenance of user {} failed: {}", user_id, message)
+            }
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Marks export jobs the previous process left unfinished as failed, over every
+/// user's database, so they can be purged like any other finished job. A user
+/// whose database cannot be updated is reported and skipped. Returns the number
+/// of jobs marked.
+pub async fn recover_interrupted_export_jobs(
+    main_db: &Db,
+    data_path: &str,
+    now: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let user_ids = list_user_ids(main_db).await?;
+
+    let mut failed = 0;
+    for user_id in user_ids {
+        let result = async {
+            let user_db = open_user_db(data_path, &user_id).await?;
+            fail_interrupted_export_jobs(&user_db, now).await
+        }
+        .await;
+        match result {
+            Ok(count) => failed += count,
+            Err((_, message)) => eprintln!(
+                "Recovering export jobs of user {} failed: {}",
+                user_id, message
+            ),
+        }
+    }
+
+    Ok(failed)
+}
+
+/// Deletes every user's records dated more than `retention_days` before `now`.
+/// `now` doubles as the snapshot cutoff: records created at or after it are kept
+/// even if backdated. A user whose database fails is reported and skipped.
+/// Returns the number of records deleted.
+pub async fn run_retention_purge(
+    main_db: &Db,
+    data_path: &str,
+    retention_days: u32,
+    now: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let user_ids = list_user_ids(main_db).await?;
+    let cutoff = now - i64::from(retention_days) * 24 * 60 * 60;
+
+    let mut purged = 0;
+    for user_id in user_ids {
+        let result = async {
+            let user_db = open_user_db(data_path, &user_id).await?;
+            purge_expired_records(&user_db, cutoff, now).await
+        }
+        .await;
+        match result {
+            Ok(count) => purged += count,
+            Err((_, message)) => {
+                eprintln!("Retention purge of user {} failed: {}", user_id, message)
+            }
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Starts the background scheduler that runs `run_maintenance`, and the retention
+/// purge when `record_retention_days` is set, every `MAINTENANCE_INTERVAL_SECS`.
+pub fn spawn_maintenance_scheduler(main_db: Db, record_retention_days: Option<u32>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(MAINTENANCE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let now = time::OffsetDateTime::now_utc().unix_timestamp();
+            if let Err((_, message)) = run_maintenance(&main_db, get_database_path(), now).await {
+                eprintln!("Maintenance run failed: {}", message);
+            }
+            if let Some(retention_days) = record_retention_days {
+                match run_retention_purge(&main_db, get_database_path(), retention_days, now).await
+                {
+                    Ok(purged) => println!(
+                        "Retention purge removed {} records older than {} days",
+                        purged, retention_days
+                    ),
+                    Err((_, message)) => eprintln!("Retention purge failed: {}", message),
+                }
+            }
+        }
+    });
+}
@@ -1,67 +1,874 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::amount_format::{
+    deserialize_amount, deserialize_optional_amount, serialize_amount, serialize_amounts,
+    serialize_optional_amount,
+};
+use crate::constants::DEFAULT_CURRENCY;
+use crate::timestamp_format::{deserialize_optional_timestamp, deserialize_timestamp};
+
+fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+fn default_record_version() -> i64 {
+    1
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
     pub id: String,
     pub username: String,
     #[serde(skip_serializing)]
     pub password_hash: String,
+    pub email: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct RegisterPayload {
     pub username: String,
     pub password: String,
+    #[serde(default)]
+    pub email: Option<String>,
 }
 
-#[derive(Serialize)]
+/// What an account may do; admins also manage the other accounts.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    #[default]
+    User,
+    Admin,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::User => "user",
+            UserRole::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "user" => Some(UserRole::User),
+            "admin" => Some(UserRole::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// An account as listed to admins by GET /admin/users.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AdminUser {
+    pub id: String,
+    pub username: String,
+    pub role: UserRole,
+    /// None for accounts registered before creation times were kept
+    pub created_at: Option<i64>,
+    pub record_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PublicUser {
     pub id: String,
     pub username: String,
+    /// Only filled in on responses to the account's owner
+    #[serde(default)]
+    pub email: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct LoginPayload {
     pub username: String,
     pub password: String,
+    /// Keep the session for `SESSION_EXPIRY_DAYS` of inactivity rather than
+    /// `SHORT_SESSION_EXPIRY_HOURS`; `DEFAULT_REMEMBER_ME` when absent
+    pub remember_me: Option<bool>,
+}
+
+/// A personal API token as listed; the token itself is only shown on creation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    /// Null until the token is first used
+    pub last_used_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateApiTokenPayload {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateApiTokenResponse {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    /// Sent as `Authorization: Bearer <token>`; not retrievable again
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateProfilePayload {
+    /// An explicit null removes the email address
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub email: Option<Option<String>>,
+    /// The current password, re-entered to confirm
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChangeUsernamePayload {
+    pub username: String,
+    /// The current password, re-entered to confirm
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAccountPayload {
+    /// The current password, re-entered to confirm
+    pub password: String,
+}
+
+/// A login of the current user, as listed by GET /auth/sessions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionInfo {
+    pub id: String,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub user_agent: Option<String>,
+    /// The address logged in from with its host part zeroed
+    pub ip: Option<String>,
+    /// Whether the session lasts `SESSION_EXPIRY_DAYS` rather than a few hours
+    pub remember_me: bool,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ForgotPasswordPayload {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordPayload {
+    /// The token delivered by POST /auth/forgot-password
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    #[default]
+    Expense,
+    Income,
+}
+
+impl RecordKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordKind::Expense => "expense",
+            RecordKind::Income => "income",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "expense" => Some(RecordKind::Expense),
+            "income" => Some(RecordKind::Income),
+            _ => None,
+        }
+    }
+}
+
+/// The part of a split record's amount booked under one category.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordSplit {
+    pub category_id: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub amount: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Record {
     pub id: String,
     pub name: String,
+    #[serde(serialize_with = "serialize_amount")]
     pub amount: f64,
-    pub category_id: String,
+    /// None for records not filed under a category yet
+    pub category_id: Option<String>,
     pub timestamp: i64,
+    /// ISO 4217 code
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub kind: RecordKind,
+    /// Free-form, e.g. "cash" or a card name
+    #[serde(default)]
+    pub payment_method: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Empty unless the amount is split across categories
+    #[serde(default)]
+    pub splits: Vec<RecordSplit>,
+    /// When the row was entered, as opposed to the editable `timestamp`
+    #[serde(default)]
+    pub created_at: i64,
+    /// When the row was last modified
+    #[serde(default)]
+    pub updated_at: i64,
+    /// Starts at 1 and goes up with every change; sent back in `If-Match` to
+    /// detect a concurrent edit
+    #[serde(default = "default_record_version")]
+    pub version: i64,
+    /// Marked by the user to find it again quickly
+    #[serde(default)]
+    pub starred: bool,
+    /// Running balance up to and including this record, only set when listing
+    /// records with `include_balance=true`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_amount"
+    )]
+    pub balance: Option<f64>,
+    /// The record's category, only set with `include=category`. Null when the
+    /// category no longer exists
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_present"
+    )]
+    pub category: Option<Option<RecordCategory>>,
+    /// Budgets this record leaves exceeded for its month, only set in the response
+    /// to a create or to an update of its amount or category
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub budget_warnings: Vec<BudgetWarning>,
+    /// The keyword rule that picked the category, only set in the response to a
+    /// create with `auto_categorize=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_rule: Option<CategoryRule>,
 }
 
-#[derive(Deserialize)]
+/// Category embedded in a record by `include=category`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordCategory {
+    pub id: String,
+    pub name: String,
+}
+
+/// Maps a present field, even an explicit null, to `Some`, so a missing field
+/// (`None`) stays distinguishable from a null one (`Some(None)`).
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// `deserialize_present` for amounts, which may also be decimal strings.
+fn deserialize_present_amount<'de, D>(deserializer: D) -> Result<Option<Option<f64>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_optional_amount(deserializer).map(Some)
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct CreateRecordPayload {
+    /// Client-generated id (a lowercase hyphenated UUID) for records created
+    /// offline. If a record with this id already exists it is returned as is.
+    pub id: Option<String>,
     pub name: String,
+    /// A JSON number or a decimal string such as "12.34"
+    #[serde(deserialize_with = "deserialize_amount")]
     pub amount: f64,
-    pub category_id: String,
-    pub timestamp: i64,
+    /// Null or omitted leaves the record uncategorized
+    #[serde(default)]
+    pub category_id: Option<String>,
+    /// Transaction time, defaulting to now
+    pub timestamp: Option<i64>,
+    /// Falls back to the user's default currency
+    pub currency: Option<String>,
+    /// "expense" (default) or "income"
+    pub kind: Option<String>,
+    pub payment_method: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Must add up to `amount`; required by POST /records/split
+    #[serde(default)]
+    pub splits: Vec<RecordSplit>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateRecordQuery {
+    /// Skips duplicate detection
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// Files a record sent without category_id or splits under the category of
+    /// the keyword rule matching its name
+    #[serde(default)]
+    pub auto_categorize: bool,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateRecordPayload {
     pub name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_amount")]
     pub amount: Option<f64>,
     pub category_id: Option<String>,
     pub timestamp: Option<i64>,
+    pub currency: Option<String>,
+    pub kind: Option<String>,
+    /// An empty string clears the payment method
+    pub payment_method: Option<String>,
+    /// Replaces the record's tags when present
+    pub tags: Option<Vec<String>>,
+    /// Replaces the record's splits when present; an empty list un-splits it
+    pub splits: Option<Vec<RecordSplit>>,
+    pub starred: Option<bool>,
+}
+
+/// One entry of PATCH /records: the record to change and the fields to set.
+#[derive(Deserialize)]
+pub struct BatchUpdateEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub changes: UpdateRecordPayload,
 }
 
 #[derive(Deserialize)]
+pub struct BatchUpdateQuery {
+    /// Abort the whole batch on the first failing entry; true unless given
+    pub fail_fast: Option<bool>,
+}
+
+/// Outcome of one batch entry, either the updated record or an error.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchUpdateResult {
+    /// Position of the entry in the request
+    pub index: usize,
+    pub id: String,
+    /// Status a single PUT /records/{id} with the same changes would have had
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record: Option<Record>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchUpdateResponse {
+    pub updated: u32,
+    pub failed: u32,
+    /// One result per entry, in request order
+    pub results: Vec<BatchUpdateResult>,
+}
+
+#[derive(Deserialize)]
+pub struct PurgeRecordsQuery {
+    /// Both bounds are required and inclusive
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    pub category_id: Option<String>,
+    /// Without true nothing is deleted and only the matching records are counted
+    pub confirm: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PurgeRecordsResponse {
+    /// Records deleted, or on a dry run the records that would be
+    pub count: u32,
+    pub dry_run: bool,
+}
+
+/// Optional changes applied to the copy made by POST /records/{id}/duplicate.
+#[derive(Deserialize, Default)]
+pub struct DuplicateRecordPayload {
+    #[serde(default, deserialize_with = "deserialize_optional_amount")]
+    pub amount: Option<f64>,
+    /// Defaults to now
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
 pub struct GetRecordsQuery {
+    /// Unix seconds or an RFC3339 date such as 2024-05-01T00:00:00Z
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    pub limit: Option<u32>,
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub tag: Option<String>,
+    pub kind: Option<String>,
+    pub payment_method: Option<String>,
+    /// Only starred records with true, only unstarred ones with false
+    pub starred: Option<bool>,
+    /// Comma-separated list of record ids; replaces the time range, so it cannot
+    /// be combined with `start_time` or `end_time`
+    pub ids: Option<String>,
+    /// A single category id, or "none" for records without a category
+    pub category_id: Option<String>,
+    /// Comma-separated list of category ids
+    pub category_ids: Option<String>,
+    /// Comma-separated list of category ids to leave out
+    pub exclude_category_ids: Option<String>,
+    /// Inclusive bounds; negative values match refunds
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    /// Set to false to skip counting; total_count is then null
+    pub include_total: Option<bool>,
+    /// Set to true to add `total_amount`, the sum over every matching record
+    pub include_sum: Option<bool>,
+    /// Set to true to only count; records is then empty
+    pub count_only: Option<bool>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Words to look for in record names; each matches as a prefix
+    pub q: Option<String>,
+    /// Set to true to also list records dated after now when `end_time` is absent
+    pub include_future: Option<bool>,
+    /// Shortcut for the time bounds, e.g. "this_month" or "last_30d"; cannot be
+    /// combined with `start_time` or `end_time`
+    pub range: Option<String>,
+    /// Minutes east of UTC used to find local day, week and month boundaries of
+    /// `range`, default 0
+    pub tz_offset_minutes: Option<i32>,
+    /// Set to true to add each record's running `balance`. The balance runs over
+    /// every record matching the filters, oldest first, so it does not depend on
+    /// `limit` or `cursor`
+    pub include_balance: Option<bool>,
+    /// Starting point of the running balance, default 0
+    pub opening_balance: Option<f64>,
+    /// "category" embeds each record's category as `category: { id, name }`
+    pub include: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GetRecordQuery {
+    /// Same as for GET /records
+    pub include: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ExportRecordsQuery {
+    pub format: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
     pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
     pub end_time: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct GetRecordNamesQuery {
+    /// Matched case-insensitively against the start of record names
+    pub prefix: Option<String>,
     pub limit: Option<u32>,
 }
 
+#[derive(Deserialize)]
+pub struct GetSummaryQuery {
+    pub group_by: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    /// Minutes east of UTC used to find local day and month boundaries, default 0
+    pub tz_offset_minutes: Option<i32>,
+    /// Comma-separated list of category ids to leave out
+    pub exclude_category_ids: Option<String>,
+    /// Convert amounts into the default currency using the stored exchange rates
+    pub convert: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SummaryBucket {
+    pub period: String,
+    pub currency: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total: f64,
+    #[serde(serialize_with = "serialize_amount")]
+    pub income_total: f64,
+    #[serde(serialize_with = "serialize_amount")]
+    pub expense_total: f64,
+    pub count: u32,
+    /// Set on converted summaries for records whose currency has no exchange rate;
+    /// their totals stay in `currency`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unconverted: bool,
+}
+
+#[derive(Deserialize)]
+pub struct GetTopRecordsQuery {
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    /// Number of records to return
+    pub n: Option<u32>,
+    pub category_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GetTimeseriesQuery {
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    /// Include days without records as zero entries
+    pub fill: Option<bool>,
+    /// Defaults to the user's default currency
+    pub currency: Option<String>,
+    /// Minutes east of UTC used to find local day and month boundaries, default 0
+    pub tz_offset_minutes: Option<i32>,
+}
+
+/// Expense total of one UTC day.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimeseriesPoint {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total: f64,
+    pub count: u32,
+}
+
+#[derive(Deserialize)]
+pub struct GetPivotQuery {
+    pub year: i32,
+    /// Include categories without expenses in the year as all-zero rows
+    pub include_empty: Option<bool>,
+    /// Defaults to the user's default currency
+    pub currency: Option<String>,
+    /// Minutes east of UTC used to find local day and month boundaries, default 0
+    pub tz_offset_minutes: Option<i32>,
+}
+
+/// Expenses of one category in each UTC month of a year.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PivotRow {
+    /// None for records without a category or whose category no longer exists
+    pub category_id: Option<String>,
+    pub category_name: String,
+    /// Twelve totals, January first
+    #[serde(serialize_with = "serialize_amounts")]
+    pub months: Vec<f64>,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PivotTable {
+    pub year: i32,
+    pub currency: String,
+    pub rows: Vec<PivotRow>,
+}
+
+#[derive(Deserialize)]
+pub struct GetComparisonQuery {
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    pub category_id: Option<String>,
+    /// Defaults to the user's default currency
+    pub currency: Option<String>,
+}
+
+/// Expense total of one inclusive time window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PeriodTotal {
+    pub start_time: i64,
+    pub end_time: i64,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total: f64,
+    pub count: u32,
+}
+
+/// A window compared with the window of equal length right before it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PeriodComparison {
+    pub currency: String,
+    pub current: PeriodTotal,
+    pub previous: PeriodTotal,
+    /// `current.total - previous.total`
+    #[serde(serialize_with = "serialize_amount")]
+    pub difference: f64,
+    /// Change relative to the previous total in percent, rounded to two decimals;
+    /// null when the previous total is zero
+    pub percent_change: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateExportJobPayload {
+    pub format: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportJobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl ExportJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportJobStatus::Pending => "pending",
+            ExportJobStatus::Running => "running",
+            ExportJobStatus::Completed => "completed",
+            ExportJobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(ExportJobStatus::Pending),
+            "running" => Some(ExportJobStatus::Running),
+            "completed" => Some(ExportJobStatus::Completed),
+            "failed" => Some(ExportJobStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExportJob {
+    pub id: String,
+    pub format: String,
+    pub status: ExportJobStatus,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// Rows written so far
+    pub progress: u32,
+    pub total_rows: u32,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct GetCategorySummaryQuery {
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    /// "payment_method" additionally splits each category by payment method
+    pub group_by: Option<String>,
+    /// Comma-separated list of category ids to leave out
+    pub exclude_category_ids: Option<String>,
+    /// Set to true to total subcategories under their top-level category
+    pub rollup: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryTotal {
+    /// None for uncategorized records and for records whose category no longer
+    /// exists; `category_name` tells the two apart
+    pub category_id: Option<String>,
+    pub category_name: String,
+    /// Set when grouped by payment method; omitted for the group of records without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_method: Option<String>,
+    pub currency: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total_amount: f64,
+    pub record_count: u32,
+}
+
+#[derive(Deserialize)]
+pub struct GetTagSummaryQuery {
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+}
+
+/// Records carrying several tags count fully toward each of them, so totals
+/// across tags can exceed the overall spend.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TagTotal {
+    /// None for the bucket of untagged records
+    pub tag: Option<String>,
+    pub currency: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total_amount: f64,
+    pub record_count: u32,
+}
+
+#[derive(Deserialize)]
+pub struct GetStatsQuery {
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+    pub category_id: Option<String>,
+    pub currency: Option<String>,
+    pub kind: Option<String>,
+    /// Convert amounts into the default currency using the stored exchange rates
+    pub convert: Option<bool>,
+}
+
+/// Aggregates over a set of records; average/min/max are null when it is empty.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordStats {
+    pub count: u32,
+    #[serde(serialize_with = "serialize_amount")]
+    pub sum: f64,
+    #[serde(serialize_with = "serialize_amount")]
+    pub income_total: f64,
+    #[serde(serialize_with = "serialize_amount")]
+    pub expense_total: f64,
+    #[serde(serialize_with = "serialize_optional_amount")]
+    pub average: Option<f64>,
+    #[serde(serialize_with = "serialize_optional_amount")]
+    pub min: Option<f64>,
+    #[serde(serialize_with = "serialize_optional_amount")]
+    pub max: Option<f64>,
+    /// Currency the figures are in, set when amounts were converted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Records left out of a conversion for lack of an exchange rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unconverted: Option<Vec<UnconvertedTotal>>,
+}
+
 #[derive(Serialize)]
 pub struct GetRecordsResponse {
     pub records: Vec<Record>,
-    pub total_count: u32,
+    pub total_count: Option<u32>,
+    /// Sum of `amount` over all matching records regardless of paging, only with
+    /// `include_sum=true`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_amount"
+    )]
+    pub total_amount: Option<f64>,
+    /// The page size actually applied, after defaulting
+    pub limit: u32,
+    /// Whether more records match beyond this page
+    pub has_more: bool,
+    /// Continues after this page; null once the records are exhausted
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RepairOrphansPayload {
+    /// Existing category that orphaned records are moved to
+    pub category_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OrphanRepairResponse {
+    pub orphans_before: u32,
+    pub repaired: u32,
+    pub orphans_after: u32,
+}
+
+#[derive(Deserialize)]
+pub struct ArchiveRecordsPayload {
+    /// Records dated before this are moved into their year's archive table
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub before: i64,
+}
+
+#[derive(Deserialize, Default)]
+pub struct UnarchiveRecordsPayload {
+    /// Year to restore; every archived year when omitted
+    pub year: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordArchive {
+    pub year: i32,
+    pub record_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordArchiveResponse {
+    /// Records moved by the request
+    pub moved: u32,
+    /// Archived years after the request, oldest first
+    pub archives: Vec<RecordArchive>,
+}
+
+#[derive(Deserialize)]
+pub struct ClosePeriodPayload {
+    /// Records dated before this can no longer be created, edited or deleted
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub before: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClosedPeriod {
+    /// None when no period is closed
+    pub closed_before: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UserPreferences {
+    pub default_currency: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePreferencesPayload {
+    pub default_currency: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExchangeRate {
+    pub currency: String,
+    /// Units of the default currency one unit of `currency` is worth
+    pub rate: f64,
+    pub updated_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExchangeRatesResponse {
+    pub base_currency: String,
+    pub rates: Vec<ExchangeRate>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateExchangeRatesPayload {
+    /// Rates to insert or replace, keyed by currency code
+    pub rates: BTreeMap<String, f64>,
+}
+
+/// Records a converted aggregate could not include for lack of an exchange rate.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnconvertedTotal {
+    pub currency: String,
+    pub count: u32,
+    #[serde(serialize_with = "serialize_amount")]
+    pub sum: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OnboardingStatus {
+    pub has_categories: bool,
+    /// Whether any category has a monthly budget
+    pub has_budgets: bool,
+    pub has_records: bool,
+    pub preferences_set: bool,
+    pub dismissed: bool,
+    pub onboarding_complete: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -69,30 +876,547 @@ pub struct Category {
     pub id: String,
     pub name: String,
     pub is_income: bool,
+    /// Spending allowed per month; null when the category is not budgeted
+    #[serde(
+        default,
+        serialize_with = "serialize_optional_amount",
+        deserialize_with = "deserialize_optional_amount"
+    )]
+    pub monthly_budget: Option<f64>,
+    /// The category this one is nested under; null for a top-level category
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Hidden from the default listing and closed to new records
+    #[serde(default)]
+    pub archived: bool,
+    /// Position under `sort=manual`; null until the categories are reordered
+    #[serde(default)]
+    pub sort_order: Option<i64>,
+    /// Refuse expenses that would take the month's spending over
+    /// `monthly_budget` instead of only warning
+    #[serde(default)]
+    pub enforce_budget: bool,
+    /// Records filed under the category, archived ones included; only listed
+    /// with `include_counts=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_count: Option<u32>,
+    /// Sum of the amounts of records filed under the category within the
+    /// requested window; only listed with `include_totals=true`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_amount",
+        deserialize_with = "deserialize_optional_amount"
+    )]
+    pub total_amount: Option<f64>,
+    /// Timestamp of the latest record filed under the category, null when it
+    /// was never used; listed with `include_usage=true` and by the single GET
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_present"
+    )]
+    pub last_used_at: Option<Option<i64>>,
+}
+
+/// Categories whose names collide once normalized, which can only be stored
+/// names that predate normalization; listed by GET /categories/duplicates
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryDuplicateGroup {
+    pub normalized_name: String,
+    pub categories: Vec<Category>,
 }
 
 #[derive(Deserialize)]
 pub struct CreateCategoryPayload {
     pub name: String,
     pub is_income: bool,
+    #[serde(default, deserialize_with = "deserialize_optional_amount")]
+    pub monthly_budget: Option<f64>,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub enforce_budget: bool,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateCategoryPayload {
     pub name: Option<String>,
+    /// An explicit null removes the budget
+    #[serde(default, deserialize_with = "deserialize_present_amount")]
+    pub monthly_budget: Option<Option<f64>>,
+    /// An explicit null makes the category top-level
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub parent_id: Option<Option<String>>,
+    pub archived: Option<bool>,
+    pub enforce_budget: Option<bool>,
+    /// When the new name is taken, move this category's records into the category
+    /// holding it and delete this one instead of failing with 409
+    pub merge_on_conflict: Option<bool>,
 }
 
 #[derive(Deserialize)]
+pub struct BulkCreateCategoriesPayload {
+    pub names: Vec<String>,
+    /// Applies to every created category
+    #[serde(default)]
+    pub is_income: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BulkCreateCategoriesResponse {
+    pub created: Vec<Category>,
+    /// Names already taken, or repeated earlier in the batch, ignoring case
+    pub skipped: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReorderCategoriesPayload {
+    /// Categories in their new order; the ones left out follow in their
+    /// previous relative order
+    pub category_ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GetBudgetStatusQuery {
+    /// `YYYY-MM`, defaults to the current UTC month
+    pub month: Option<String>,
+}
+
+/// Expenses of one budgeted category against its monthly budget.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BudgetStatus {
+    pub category_id: String,
+    pub category_name: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub budget: f64,
+    #[serde(serialize_with = "serialize_amount")]
+    pub spent: f64,
+    /// Negative once the budget is exceeded
+    #[serde(serialize_with = "serialize_amount")]
+    pub remaining: f64,
+    /// Share of the budget spent, in percent; null for a zero budget
+    pub percent_used: Option<f64>,
+    pub over_budget: bool,
+}
+
+/// A budgeted category that is over its monthly budget after a record write.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BudgetWarning {
+    pub category_id: String,
+    pub category_name: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub budget: f64,
+    /// Spent in the record's month, the record included
+    #[serde(serialize_with = "serialize_amount")]
+    pub spent: f64,
+    /// How far `spent` goes beyond `budget`
+    #[serde(serialize_with = "serialize_amount")]
+    pub over_by: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BudgetStatusResponse {
+    /// `YYYY-MM`
+    pub month: String,
+    /// Only expenses in this currency, the user's default, count as spent
+    pub currency: String,
+    pub categories: Vec<BudgetStatus>,
+}
+
+#[derive(Deserialize, Default)]
 pub struct GetCategoriesQuery {
     pub limit: Option<u32>,
     pub offset: Option<u32>,
     pub search: Option<String>,
+    /// Also list archived categories
+    pub include_archived: Option<bool>,
+    /// "name" (the default), "manual" for the order set through
+    /// PUT /categories/order, or "usage" for the most used first
+    pub sort: Option<String>,
+    /// Add each category's `record_count`
+    pub include_counts: Option<bool>,
+    /// Add each category's `total_amount` between the optional, inclusive
+    /// `start_time` and `end_time`
+    pub include_totals: Option<bool>,
+    /// Add each category's `last_used_at`
+    pub include_usage: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct DeleteCategoryQuery {
+    /// Category to move the deleted category's records, splits and recurring
+    /// rules to before deleting it.
+    pub reassign_to: Option<String>,
+    /// Delete the category's records and recurring rules along with it
+    pub cascade: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteUnusedCategoriesQuery {
+    /// Delete the unused categories; without it they are only listed
+    #[serde(default)]
+    pub confirm: bool,
+    /// Leave archived categories alone
+    #[serde(default)]
+    pub keep_archived: bool,
+    /// Leave categories with a monthly budget alone
+    #[serde(default)]
+    pub keep_budgeted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UnusedCategory {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeleteUnusedCategoriesResponse {
+    /// False for a dry run, which only lists the categories
+    pub deleted: bool,
+    pub categories: Vec<UnusedCategory>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CascadeDeleteCategoryResponse {
+    /// Records deleted together with the category, archived ones included
+    pub deleted_records: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct GetCategoriesResponse {
     pub categories: Vec<Category>,
     pub total_count: u32,
     pub limit: u32,
     pub offset: u32,
 }
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NameSuggestion {
+    pub name: String,
+    pub count: u32,
+}
+
+#[derive(Deserialize)]
+pub struct GetCategoryStatsQuery {
+    /// Defaults to the start of the UTC month 11 months before `end_time`
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub start_time: Option<i64>,
+    /// Defaults to now
+    #[serde(default, deserialize_with = "deserialize_optional_timestamp")]
+    pub end_time: Option<i64>,
+}
+
+/// Records filed under a category in one UTC month.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CategoryMonthTotal {
+    /// `YYYY-MM`
+    pub month: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total_amount: f64,
+    pub record_count: u32,
+}
+
+/// How a category has been used: all-time figures plus monthly totals for the
+/// requested window.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryStatsResponse {
+    pub category_id: String,
+    pub total_records: u32,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total_amount: f64,
+    /// Timestamp of the oldest record, null when the category was never used
+    pub first_used_at: Option<i64>,
+    /// Timestamp of the newest record, null when the category was never used
+    pub last_used_at: Option<i64>,
+    /// Every month touched by the window, oldest first, zero for months without
+    /// records
+    pub monthly: Vec<CategoryMonthTotal>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CategoryDefaultsResponse {
+    pub category_id: String,
+    pub sample_size: u32,
+    #[serde(serialize_with = "serialize_amounts")]
+    pub common_amounts: Vec<f64>,
+    #[serde(serialize_with = "serialize_optional_amount")]
+    pub median_amount: Option<f64>,
+    pub common_names: Vec<NameSuggestion>,
+}
+
+#[derive(Deserialize)]
+pub struct SyncQuery {
+    pub limit: Option<u32>,
+    pub continuation: Option<String>,
+}
+
+/// One chunk of a sync. Clients must keep requesting with `continuation` until
+/// `complete` is true, and only then adopt `server_time` as their watermark.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SyncResponse {
+    pub categories: Vec<Category>,
+    pub records: Vec<Record>,
+    pub continuation: Option<String>,
+    pub complete: bool,
+    pub server_time: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct RecordChangesQuery {
+    /// `server_time` of the previous delta; unix seconds or an RFC3339 date
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub since: i64,
+}
+
+/// Records changed since a watermark. Changes made in the second of `since` are
+/// included again, so clients must apply deltas idempotently.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecordChangesResponse {
+    /// Records created or updated at or after `since`, oldest change first
+    pub records: Vec<Record>,
+    /// Records deleted at or after `since`
+    pub deleted_ids: Vec<String>,
+    /// Pass as `since` on the next request
+    pub server_time: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupDocument {
+    pub version: u32,
+    pub categories: Vec<Category>,
+    pub records: Vec<Record>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    pub mode: Option<String>,
+}
+
+/// Zero-based positions of the fields a bank statement CSV row is read from.
+#[derive(Deserialize)]
+pub struct CsvColumnMapping {
+    pub date: usize,
+    pub amount: usize,
+    pub description: usize,
+}
+
+#[derive(Deserialize)]
+pub struct CsvImportPayload {
+    pub csv: String,
+    pub columns: CsvColumnMapping,
+    /// `%d`, `%m`, `%Y` and `%y` with literal separators, e.g. "%d.%m.%Y"
+    pub date_format: Option<String>,
+    /// "." (default) or ","; the other one is treated as a thousands separator
+    pub decimal_separator: Option<String>,
+    /// Field separator, defaulting to ","
+    pub delimiter: Option<String>,
+    /// Category every imported record is filed under. With `auto_categorize` only
+    /// rows no rule matches go here, and it may be left out to keep them
+    /// uncategorized
+    pub category_id: Option<String>,
+    /// Files each row under the category of the keyword rule matching its
+    /// description
+    #[serde(default)]
+    pub auto_categorize: bool,
+    /// Whether the first row holds column names; defaults to true
+    pub has_header: Option<bool>,
+    /// Rejects the whole file on the first bad row instead of skipping it
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CsvRowError {
+    /// 1-based line in the posted CSV
+    pub line: u32,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct CsvImportResponse {
+    pub records_created: u32,
+    /// Created records whose category came from a keyword rule
+    #[serde(default)]
+    pub records_auto_categorized: u32,
+    /// Rows that were skipped; always empty in strict mode
+    pub errors: Vec<CsvRowError>,
+}
+
+#[derive(Deserialize)]
+pub struct CategoryCsvImportPayload {
+    /// CSV as produced by GET /categories/export; only the name column is required
+    pub csv: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct CategoryCsvImportResponse {
+    pub categories_created: u32,
+    /// Rows whose name is already taken, ignoring case
+    pub categories_skipped: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ImportResponse {
+    pub categories_created: u32,
+    pub categories_skipped: u32,
+    pub records_created: u32,
+    pub records_skipped: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurringInterval {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl RecurringInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecurringInterval::Daily => "daily",
+            RecurringInterval::Weekly => "weekly",
+            RecurringInterval::Monthly => "monthly",
+            RecurringInterval::Yearly => "yearly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(RecurringInterval::Daily),
+            "weekly" => Some(RecurringInterval::Weekly),
+            "monthly" => Some(RecurringInterval::Monthly),
+            "yearly" => Some(RecurringInterval::Yearly),
+            _ => None,
+        }
+    }
+}
+
+/// Template for records posted automatically every `interval`, starting at `start_time`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecurringRule {
+    pub id: String,
+    pub name: String,
+    #[serde(serialize_with = "serialize_amount")]
+    pub amount: f64,
+    pub category_id: String,
+    pub currency: String,
+    pub kind: RecordKind,
+    pub interval: RecurringInterval,
+    /// First occurrence; monthly and yearly rules keep its day of month and time of day
+    pub start_time: i64,
+    /// Timestamp of the next occurrence to be posted
+    pub next_run: i64,
+    pub active: bool,
+}
+
+#[derive(Deserialize)]
+pub struct CreateRecurringRulePayload {
+    pub name: String,
+    pub amount: f64,
+    pub category_id: String,
+    /// "daily", "weekly", "monthly" or "yearly"
+    pub interval: String,
+    pub start_time: i64,
+    /// Falls back to the user's default currency
+    pub currency: Option<String>,
+    /// "expense" (default) or "income"
+    pub kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateRecurringRulePayload {
+    pub name: Option<String>,
+    pub amount: Option<f64>,
+    pub category_id: Option<String>,
+    pub currency: Option<String>,
+    pub kind: Option<String>,
+    /// Changing the interval keeps the next occurrence already scheduled
+    pub interval: Option<String>,
+}
+
+/// Suggests `category_id` for records whose name contains `keyword`, ignoring case.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CategoryRule {
+    pub id: String,
+    pub category_id: String,
+    pub keyword: String,
+    /// The highest priority wins when several rules match
+    pub priority: i64,
+}
+
+#[derive(Deserialize)]
+pub struct CreateCategoryRulePayload {
+    pub keyword: String,
+    #[serde(default)]
+    pub priority: i64,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCategoryRulePayload {
+    pub keyword: Option<String>,
+    pub priority: Option<i64>,
+}
+
+/// Either a record name or the id of an existing record whose name is used.
+#[derive(Deserialize)]
+pub struct CategorizeRecordPayload {
+    pub name: Option<String>,
+    pub record_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CategorizeRecordResponse {
+    /// Null when no rule matches
+    pub category: Option<Category>,
+    /// The rule that picked `category`
+    pub rule: Option<CategoryRule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordHistoryAction {
+    Update,
+    Delete,
+}
+
+impl RecordHistoryAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordHistoryAction::Update => "update",
+            RecordHistoryAction::Delete => "delete",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "update" => Some(RecordHistoryAction::Update),
+            "delete" => Some(RecordHistoryAction::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Old and new value of one record field; `new` is null when the record was deleted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+/// One mutation of a record, listing only the fields it changed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordHistoryEntry {
+    pub id: i64,
+    pub record_id: String,
+    pub action: RecordHistoryAction,
+    pub changed_at: i64,
+    pub changes: std::collections::BTreeMap<String, FieldChange>,
+}
@@ -0,0 +1,112 @@
+use axum::{Json, extract::State, http::StatusCode};
+use tower_sessions::Session;
+
+use crate::archive::records_source;
+use crate::auth::get_current_user;
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::OnboardingStatus;
+use crate::settings::set_setting;
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+/// Evaluates an `EXISTS` query, which stops at the first matching row.
+async fn exists(
+    conn: &libsql::Connection,
+    sql: &str,
+    params: impl libsql::params::IntoParams,
+    context: &str,
+) -> Result<bool, (StatusCode, String)> {
+    let mut rows = conn
+        .query(sql, params)
+        .await
+        .map_err(|_| db_error_with_context(context))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => Ok(row.get::<i64>(0).map_err(|_| db_error())? != 0),
+        None => Ok(false),
+    }
+}
+
+/// Reports how far the user got through first-run setup.
+///
+/// Onboarding counts as complete once the user has categories, a budget and
+/// records (archived years included), or as soon as the wizard has been
+/// explicitly dismissed.
+pub async fn compute_onboarding_status(
+    user_db: &Db,
+) -> Result<OnboardingStatus, (StatusCode, String)> {
+    let conn = user_db.read().await;
+
+    let has_categories = exists(
+        &conn,
+        "SELECT EXISTS(SELECT 1 FROM categories LIMIT 1)",
+        (),
+        "failed to check categories",
+    )
+    .await?;
+    let has_budgets = exists(
+        &conn,
+        "SELECT EXISTS(SELECT 1 FROM categories WHERE monthly_budget IS NOT NULL LIMIT 1)",
+        (),
+        "failed to check budgets",
+    )
+    .await?;
+    let source = records_source(&conn, i64::MIN, i64::MAX).await?;
+    let has_records = exists(
+        &conn,
+        &format!("SELECT EXISTS(SELECT 1 FROM {} AS records LIMIT 1)", source),
+        (),
+        "failed to check records",
+    )
+    .await?;
+    let dismissed = exists(
+        &conn,
+        "SELECT EXISTS(SELECT 1 FROM settings WHERE key = ? AND value = 'true')",
+        [SETTING_ONBOARDING_DISMISSED],
+        "failed to check onboarding state",
+    )
+    .await?;
+    // Any setting other than the wizard's own flag and the data horizon is a
+    // user preference
+    let preferences_set = exists(
+        &conn,
+        "SELECT EXISTS(SELECT 1 FROM settings WHERE key NOT IN (?, ?) LIMIT 1)",
+        [SETTING_ONBOARDING_DISMISSED, SETTING_DATA_HORIZON],
+        "failed to check preferences",
+    )
+    .await?;
+
+    Ok(OnboardingStatus {
+        has_categories,
+        has_budgets,
+        has_records,
+        preferences_set,
+        dismissed,
+        onboarding_complete: dismissed || (has_categories && has_budgets && has_records),
+    })
+}
+
+pub async fn get_onboarding_status(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<OnboardingStatus>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let status = compute_onboarding_status(&user_db).await?;
+
+    Ok((StatusCode::OK, Json(status)))
+}
+
+pub async fn complete_onboarding(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<OnboardingStatus>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    set_setting(&user_db, SETTING_ONBOARDING_DISMISSED, "true").await?;
+    let status = compute_onboarding_status(&user_db).await?;
+
+    Ok((StatusCode::OK, Json(status)))
+}
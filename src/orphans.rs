@@ -0,0 +1,149 @@
+use axum::{Json, extract::State, http::StatusCode};
+use tower_sessions::Session;
+
+use crate::auth::get_current_user;
+use crate::closing::ensure_period_open;
+use crate::database::Db;
+use crate::models::{OrphanRepairResponse, Record, RecordHistoryAction, RepairOrphansPayload};
+use crate::record_history::{append_record_history, record_changes};
+use crate::records::{RECORD_COLUMNS, extract_record_from_row, validate_category_id};
+use crate::utils::{db_error, db_error_with_context, get_user_database, validate_category_exists};
+
+/// Matches records whose category row is gone. There is no foreign key, so older
+/// builds or manual SQL could delete a category that records still point at.
+/// Uncategorized records point at nothing and are not orphans.
+const ORPHAN_CONDITION: &str = "records.category_id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM categories WHERE categories.id = records.category_id)";
+
+/// Records pointing at a category that no longer exists, newest first.
+pub async fn find_orphaned_records(
+    conn: &libsql::Connection,
+) -> Result<Vec<Record>, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM records WHERE {} ORDER BY timestamp DESC, id DESC",
+                RECORD_COLUMNS, ORPHAN_CONDITION
+            ),
+            (),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query orphaned records"))?;
+
+    let mut records = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        records.push(extract_record_from_row(row)?);
+    }
+    Ok(records)
+}
+
+async fn count_orphaned_records(conn: &libsql::Connection) -> Result<u32, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!("SELECT COUNT(*) FROM records WHERE {}", ORPHAN_CONDITION),
+            (),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to count orphaned records"))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => row.get(0).map_err(|_| db_error()),
+        None => Ok(0),
+    }
+}
+
+/// Moves every orphaned record to `category_id` in one transaction. Each record
+/// gets a new version and a history entry, like any other category change. An
+/// orphan in the closed period fails the whole repair with 423.
+pub async fn repair_orphaned_records(
+    user_db: &Db,
+    category_id: &str,
+) -> Result<OrphanRepairResponse, (StatusCode, String)> {
+    let conn = user_db.write().await;
+    let orphans = find_orphaned_records(&conn).await?;
+    let changed_at = time::OffsetDateTime::now_utc().unix_timestamp();
+
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to repair orphaned records"))?;
+    let result = async {
+        for orphan in &orphans {
+            ensure_period_open(&tx, orphan.timestamp).await?;
+            let mut repaired = orphan.clone();
+            repaired.category_id = Some(category_id.to_string());
+            repaired.updated_at = changed_at;
+            repaired.version += 1;
+
+            tx.execute(
+                "UPDATE records SET category_id = ?, updated_at = ?, version = ? WHERE id = ?",
+                (
+                    repaired.category_id.as_deref(),
+                    repaired.updated_at,
+                    repaired.version,
+                    repaired.id.as_str(),
+                ),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to repair orphaned records"))?;
+            append_record_history(
+                &tx,
+                &repaired.id,
+                RecordHistoryAction::Update,
+                &record_changes(orphan, Some(&repaired)),
+                changed_at,
+            )
+            .await?;
+        }
+        count_orphaned_records(&tx).await
+    }
+    .await;
+
+    let orphans_after = match result {
+        Ok(orphans_after) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to repair orphaned records"))?;
+            orphans_after
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err(err);
+        }
+    };
+
+    Ok(OrphanRepairResponse {
+        orphans_before: orphans.len() as u32,
+        repaired: orphans.len() as u32,
+        orphans_after,
+    })
+}
+
+pub async fn get_orphans(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<Vec<Record>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let records = find_orphaned_records(&conn).await?;
+
+    Ok((StatusCode::OK, Json(records)))
+}
+
+pub async fn repair_orphans(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<RepairOrphansPayload>,
+) -> Result<(StatusCode, Json<OrphanRepairResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    validate_category_id(&payload.category_id)?;
+
+    let user_db = get_user_database(&user.id).await?;
+    validate_category_exists(&user_db, &payload.category_id).await?;
+
+    let response = repair_orphaned_records(&user_db, &payload.category_id).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
@@ -0,0 +1,182 @@
+use async_trait::async_trait;
+use axum::{Extension, Json, extract::State, http::StatusCode};
+use std::sync::Arc;
+
+use crate::api_tokens::{hash_token, random_token};
+use crate::auth::{hash_password, normalize_email, validate_password_strength};
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::{ForgotPasswordPayload, ResetPasswordPayload};
+use crate::utils::{db_error, db_error_with_context};
+
+/// Delivers password reset tokens to the email address of their account.
+#[async_trait]
+pub trait ResetTokenSender: Send + Sync {
+    async fn send(&self, email: &str, token: &str) -> anyhow::Result<()>;
+}
+
+/// Prints reset tokens to stderr, for development and until mail delivery is set up.
+#[derive(Debug, Default)]
+pub struct LogResetTokenSender;
+
+#[async_trait]
+impl ResetTokenSender for LogResetTokenSender {
+    async fn send(&self, email: &str, token: &str) -> anyhow::Result<()> {
+        eprintln!("Password reset token for {}: {}", email, token);
+        Ok(())
+    }
+}
+
+fn invalid_reset_token() -> (StatusCode, String) {
+    (
+        StatusCode::BAD_REQUEST,
+        "Invalid or expired reset token".to_string(),
+    )
+}
+
+/// Stores a new reset token for the account with `email`, valid until
+/// `PASSWORD_RESET_TOKEN_TTL_SECS` after `now`. Returns None if no account has
+/// that address.
+pub async fn issue_reset_token(
+    main_db: &Db,
+    email: &str,
+    now: i64,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let conn = main_db.write().await;
+    let mut rows = conn
+        .query("SELECT id FROM users WHERE email = ?", [email])
+        .await
+        .map_err(|_| db_error_with_context("failed to query users"))?;
+    let Some(row) = rows.next().await.map_err(|_| db_error())? else {
+        return Ok(None);
+    };
+    let user_id: String = row.get(0).map_err(|_| db_error())?;
+
+    let token = random_token();
+    conn.execute(
+        "INSERT INTO password_reset_tokens (token_hash, user_id, expires_at) VALUES (?, ?, ?)",
+        (
+            hash_token(&token),
+            user_id,
+            now + PASSWORD_RESET_TOKEN_TTL_SECS,
+        ),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to create reset token"))?;
+    Ok(Some(token))
+}
+
+/// Deletes reset tokens that expired before `now`.
+pub async fn purge_expired_reset_tokens(
+    main_db: &Db,
+    now: i64,
+) -> Result<u64, (StatusCode, String)> {
+    let conn = main_db.write().await;
+    conn.execute(
+        "DELETE FROM password_reset_tokens WHERE expires_at <= ?",
+        [now],
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to purge reset tokens"))
+}
+
+/// Sends a reset token to the account with the given email, if there is one.
+/// The answer is 202 either way so the endpoint cannot be used to find accounts.
+pub async fn forgot_password(
+    State(main_db): State<Db>,
+    Extension(sender): Extension<Arc<dyn ResetTokenSender>>,
+    Json(payload): Json<ForgotPasswordPayload>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let email = normalize_email(&payload.email)?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+
+    if let Some(token) = issue_reset_token(&main_db, &email, now).await?
+        && let Err(e) = sender.send(&email, &token).await
+    {
+        eprintln!("Failed to send password reset token: {}", e);
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Sets a new password with a reset token. The token is used up, along with
+/// every other token outstanding for the account.
+pub async fn reset_password(
+    State(main_db): State<Db>,
+    Json(payload): Json<ResetPasswordPayload>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let token_hash = hash_token(payload.token.trim());
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+
+    let (user_id, username) = {
+        let conn = main_db.read().await;
+        let mut rows = conn
+            .query(
+                "SELECT u.id, u.name FROM password_reset_tokens t JOIN users u ON u.id = t.user_id WHERE t.token_hash = ? AND t.expires_at > ?",
+                (token_hash.as_str(), now),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query reset token"))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(|_| db_error())?
+            .ok_or_else(invalid_reset_token)?;
+        let user_id: String = row.get(0).map_err(|_| db_error())?;
+        let username: String = row.get(1).map_err(|_| db_error())?;
+        (user_id, username)
+    };
+
+    // A weak password leaves the token in place for another try
+    validate_password_strength(&payload.password, &username)?;
+    let password_hash = hash_password(&payload.password)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let conn = main_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("password reset failed"))?;
+
+    let result = async {
+        // Claiming the token here keeps a concurrent reset from using it too
+        let claimed = tx
+            .execute(
+                "DELETE FROM password_reset_tokens WHERE token_hash = ? AND expires_at > ?",
+                (token_hash.as_str(), now),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to claim reset token"))?;
+        if claimed == 0 {
+            return Err(invalid_reset_token());
+        }
+
+        tx.execute(
+            "UPDATE users SET password_hash = ? WHERE id = ?",
+            (password_hash.as_str(), user_id.as_str()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to update password"))?;
+        tx.execute(
+            "DELETE FROM password_reset_tokens WHERE user_id = ?",
+            [user_id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to delete reset tokens"))?;
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => tx
+            .commit()
+            .await
+            .map_err(|_| db_error_with_context("password reset failed"))?,
+        Err(e) => {
+            let _ = tx.rollback().await;
+            return Err(e);
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{Extensions, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::constants::*;
+
+/// How many attempts a client may make within a sliding window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_attempts: u32,
+    pub window: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            max_attempts: DEFAULT_AUTH_RATE_LIMIT_ATTEMPTS,
+            window: Duration::from_secs(DEFAULT_AUTH_RATE_LIMIT_WINDOW_SECS),
+        }
+    }
+}
+
+/// In-process sliding-window limiter keyed by client IP. Every allowed attempt
+/// counts toward the window whatever its outcome; refused ones do not, so a
+/// client that backs off gets in again once its oldest attempt expires.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    attempts: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            limit,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records an attempt by `client` now; see [`RateLimiter::check_at`].
+    pub fn check(&self, client: IpAddr) -> Result<(), Duration> {
+        self.check_at(client, Instant::now())
+    }
+
+    /// Records an attempt by `client` at `now`, or refuses it with how long the
+    /// client has to wait before the next one is allowed.
+    pub fn check_at(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        let window = self.limit.window;
+        let mut attempts = self.attempts.lock().unwrap();
+        // Forget clients whose attempts have all expired, so the map stays small
+        attempts.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|time| now.saturating_duration_since(*time) >= window)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = attempts.entry(client).or_default();
+        if times.len() >= self.limit.max_attempts as usize {
+            let oldest = times.front().copied().unwrap_or(now);
+            return Err((oldest + window).saturating_duration_since(now));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+/// The peer address the server was told about, or the unspecified address when
+/// the app is served without connect info.
+pub(crate) fn client_ip(extensions: &Extensions) -> IpAddr {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+/// Middleware answering 429 with `Retry-After` once the client is over the limit.
+pub async fn rate_limit_layer(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limiter.check(client_ip(request.extensions())) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            // Round up so a client waiting the advertised time is let in
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many attempts, try again later".to_string(),
+            )
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs.max(1)));
+            response
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use tower_sessions::Session;
+
+use crate::amount_format::{AmountFormat, with_amount_format_sync};
+use crate::auth::get_current_user;
+use crate::database::Db;
+use crate::models::{FieldChange, Record, RecordHistoryAction, RecordHistoryEntry};
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+fn record_fields(record: &Record) -> [(&'static str, Value); 10] {
+    // Amounts are stored as plain numbers whatever format this request asked for
+    with_amount_format_sync(AmountFormat::Number, || {
+        [
+            ("name", json!(record.name)),
+            ("amount", json!(record.amount)),
+            ("category_id", json!(record.category_id)),
+            ("timestamp", json!(record.timestamp)),
+            ("currency", json!(record.currency)),
+            ("kind", json!(record.kind)),
+            ("payment_method", json!(record.payment_method)),
+            ("tags", json!(record.tags)),
+            ("starred", json!(record.starred)),
+            // Unsplit records report null so their history does not mention splits
+            (
+                "splits",
+                if record.splits.is_empty() {
+                    Value::Null
+                } else {
+                    json!(record.splits)
+                },
+            ),
+        ]
+    })
+}
+
+/// Fields that differ between `before` and `after`. A deleted record (`after` is
+/// None) reports every field, so its entry doubles as a snapshot.
+pub fn record_changes(before: &Record, after: Option<&Record>) -> BTreeMap<String, FieldChange> {
+    let old_fields = record_fields(before);
+    let new_fields = after.map(record_fields);
+
+    old_fields
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, (field, old))| {
+            let new = match &new_fields {
+                Some(fields) => fields[i].1.clone(),
+                None => Value::Null,
+            };
+            (old != new).then(|| (field.to_string(), FieldChange { old, new }))
+        })
+        .collect()
+}
+
+/// Appends one history entry. Called on the mutation's own transaction so the
+/// entry and the change it describes are committed together.
+pub async fn append_record_history(
+    conn: &libsql::Connection,
+    record_id: &str,
+    action: RecordHistoryAction,
+    changes: &BTreeMap<String, FieldChange>,
+    changed_at: i64,
+) -> Result<(), (StatusCode, String)> {
+    // Serializing a map of JSON values cannot fail
+    let changes = serde_json::to_string(changes).unwrap_or_default();
+
+    conn.execute(
+        "INSERT INTO record_history (record_id, action, changed_at, changes) VALUES (?, ?, ?, ?)",
+        (record_id, action.as_str(), changed_at, changes),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to write record history"))?;
+    Ok(())
+}
+
+fn extract_history_entry_from_row(
+    row: &libsql::Row,
+) -> Result<RecordHistoryEntry, (StatusCode, String)> {
+    let action: String = row.get(2).map_err(|_| db_error())?;
+    let changes: String = row.get(4).map_err(|_| db_error())?;
+
+    Ok(RecordHistoryEntry {
+        id: row.get(0).map_err(|_| db_error())?,
+        record_id: row.get(1).map_err(|_| db_error())?,
+        action: RecordHistoryAction::parse(&action).ok_or_else(db_error)?,
+        changed_at: row.get(3).map_err(|_| db_error())?,
+        changes: serde_json::from_str(&changes).map_err(|_| db_error())?,
+    })
+}
+
+/// History of a record, newest first. Returns None when the record neither
+/// exists nor has any history; a deleted record keeps its history.
+pub async fn get_record_history(
+    user_db: &Db,
+    record_id: &str,
+) -> Result<Option<Vec<RecordHistoryEntry>>, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT id, record_id, action, changed_at, changes FROM record_history WHERE record_id = ? ORDER BY id DESC",
+            [record_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query record history"))?;
+
+    let mut entries = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        entries.push(extract_history_entry_from_row(&row)?);
+    }
+
+    if entries.is_empty() {
+        let mut rows = conn
+            .query("SELECT 1 FROM records WHERE id = ?", [record_id])
+            .await
+            .map_err(|_| db_error_with_context("failed to query record"))?;
+        if rows.next().await.map_err(|_| db_error())?.is_none() {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(entries))
+}
+
+pub async fn get_history(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(record_id): Path<String>,
+) -> Result<(StatusCode, Json<Vec<RecordHistoryEntry>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let entries = get_record_history(&user_db, &record_id)
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Record not found".to_string()))?;
+
+    Ok((StatusCode::OK, Json(entries)))
+}
@@ -1,20 +1,45 @@
 use axum::{
     Json,
+    body::{Body, Bytes},
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
 };
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use tower_sessions::Session;
 use uuid::Uuid;
 
+use crate::amount_format::{AmountFormat, current_amount_format, with_amount_format};
+use crate::archive::{archive_table_name, archived_years, records_source};
 use crate::auth::get_current_user;
+use crate::budgets::{budget_warnings, enforce_budgets};
+use crate::category_rules::CategoryRuleMatcher;
+use crate::closing::ensure_period_open;
 use crate::constants::*;
-use crate::database::Db;
+use crate::database::{Db, table_exists};
+use crate::export::{StatementFormat, load_statement_entries, write_statement};
+use crate::idempotency::{
+    find_idempotent_response, idempotency_key_from_headers, store_idempotent_response,
+};
 use crate::models::{
-    CreateRecordPayload, GetRecordsQuery, GetRecordsResponse, Record, UpdateRecordPayload,
+    BatchUpdateEntry, BatchUpdateQuery, BatchUpdateResponse, BatchUpdateResult, CategoryTotal,
+    CreateRecordPayload, CreateRecordQuery, DuplicateRecordPayload, ExportRecordsQuery,
+    GetCategorySummaryQuery, GetComparisonQuery, GetPivotQuery, GetRecordNamesQuery,
+    GetRecordQuery, GetRecordsQuery, GetRecordsResponse, GetStatsQuery, GetSummaryQuery,
+    GetTagSummaryQuery, GetTimeseriesQuery, GetTopRecordsQuery, PeriodComparison, PeriodTotal,
+    PivotRow, PivotTable, PurgeRecordsQuery, PurgeRecordsResponse, Record, RecordCategory,
+    RecordHistoryAction, RecordKind, RecordSplit, RecordStats, SummaryBucket, TagTotal,
+    TimeseriesPoint, UnconvertedTotal, UpdateRecordPayload,
 };
+use crate::record_history::{append_record_history, record_changes};
+use crate::settings::get_default_currency;
+use crate::sync::advance_data_horizon;
 use crate::utils::{
-    db_error, db_error_with_context, get_user_database, validate_category_exists,
-    validate_records_limit, validate_string_length,
+    db_error, db_error_with_context, ensure_category_exists, get_user_database,
+    validate_category_active, validate_category_exists, validate_limit, validate_records_limit,
+    validate_string_length,
 };
 
 pub fn validate_record_name(name: &str) -> Result<(), (StatusCode, String)> {
@@ -22,12 +47,34 @@ pub fn validate_record_name(name: &str) -> Result<(), (StatusCode, String)> {
 }
 
 pub fn validate_record_amount(amount: f64) -> Result<(), (StatusCode, String)> {
+    if !amount.is_finite() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Record amount must be a finite number".to_string(),
+        ));
+    }
     if amount == 0.0 {
         return Err((
             StatusCode::BAD_REQUEST,
             "Record amount cannot be zero".to_string(),
         ));
     }
+    if amount.abs() > MAX_RECORD_AMOUNT {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Record amount cannot exceed {}", MAX_RECORD_AMOUNT),
+        ));
+    }
+    let scale = 10f64.powi(MAX_AMOUNT_DECIMAL_PLACES);
+    if (amount * scale).round() / scale != amount {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Record amount cannot have more than {} decimal places",
+                MAX_AMOUNT_DECIMAL_PLACES
+            ),
+        ));
+    }
     Ok(())
 }
 
@@ -35,6 +82,79 @@ pub fn validate_category_id(category_id: &str) -> Result<(), (StatusCode, String
     validate_string_length(category_id, "Category ID", MAX_CATEGORY_NAME_LENGTH)
 }
 
+/// Client-generated record ids must be UUIDs in the form the server generates
+/// itself, so each record has exactly one spelling of its id.
+pub fn validate_record_id(record_id: &str) -> Result<(), (StatusCode, String)> {
+    match Uuid::try_parse(record_id) {
+        Ok(parsed) if parsed.hyphenated().to_string() == record_id => Ok(()),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            "Record ID must be a lowercase hyphenated UUID".to_string(),
+        )),
+    }
+}
+
+pub fn validate_payment_method(payment_method: &str) -> Result<(), (StatusCode, String)> {
+    validate_string_length(payment_method, "Payment method", MAX_PAYMENT_METHOD_LENGTH)
+}
+
+/// Currencies are ISO 4217 codes: exactly three uppercase ASCII letters.
+pub fn validate_currency(currency: &str) -> Result<(), (StatusCode, String)> {
+    if currency.len() != 3 || !currency.bytes().all(|b| b.is_ascii_uppercase()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Currency must be a three-letter uppercase ISO 4217 code".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn parse_record_kind(kind: &str) -> Result<RecordKind, (StatusCode, String)> {
+    RecordKind::parse(kind).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Record kind must be 'expense' or 'income'".to_string(),
+        )
+    })
+}
+
+/// Sanity bounds for any record timestamp: not before `MIN_RECORD_TIMESTAMP` and
+/// at most `MAX_RECORD_TIMESTAMP_SKEW_SECS` after `now`.
+pub fn validate_record_timestamp(timestamp: i64, now: i64) -> Result<(), (StatusCode, String)> {
+    if timestamp < MIN_RECORD_TIMESTAMP {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Timestamp cannot be earlier than {}", MIN_RECORD_TIMESTAMP),
+        ));
+    }
+    if timestamp > now.saturating_add(MAX_RECORD_TIMESTAMP_SKEW_SECS) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Timestamp cannot be more than {} seconds ahead of the server time",
+                MAX_RECORD_TIMESTAMP_SKEW_SECS
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Validates a UTC offset in minutes, defaulting to UTC.
+pub fn validate_tz_offset(tz_offset_minutes: Option<i32>) -> Result<i32, (StatusCode, String)> {
+    let tz_offset_minutes = tz_offset_minutes.unwrap_or(0);
+    if !(-MAX_TZ_OFFSET_MINUTES..=MAX_TZ_OFFSET_MINUTES).contains(&tz_offset_minutes) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "tz_offset_minutes must be between -{} and {}",
+                MAX_TZ_OFFSET_MINUTES, MAX_TZ_OFFSET_MINUTES
+            ),
+        ));
+    }
+    Ok(tz_offset_minutes)
+}
+
+/// Window for new records, tighter than `validate_record_timestamp` on both ends.
 pub fn validate_timestamp(timestamp: i64) -> Result<(), (StatusCode, String)> {
     let current_time = time::OffsetDateTime::now_utc().unix_timestamp();
 
@@ -61,6 +181,384 @@ pub fn validate_timestamp(timestamp: i64) -> Result<(), (StatusCode, String)> {
     Ok(())
 }
 
+/// Maps the `sort_by`/`order` query parameters onto a fixed ORDER BY clause.
+/// Defaults to newest first.
+/// Newest first, the only order cursor pagination walks.
+const DEFAULT_RECORDS_ORDER: &str = "timestamp DESC, id DESC";
+
+/// Position after the last record of a page in the default order. Clients treat
+/// the encoded form as opaque.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordCursor {
+    pub timestamp: i64,
+    pub id: String,
+}
+
+pub fn encode_record_cursor(cursor: &RecordCursor) -> String {
+    // Serializing a plain struct of a string and an integer cannot fail
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+pub fn decode_record_cursor(encoded: &str) -> Result<RecordCursor, (StatusCode, String)> {
+    URL_SAFE_NO_PAD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))
+}
+
+pub fn records_order_clause(
+    sort_by: Option<&str>,
+    order: Option<&str>,
+) -> Result<&'static str, (StatusCode, String)> {
+    let descending = match order.unwrap_or("desc") {
+        "asc" => false,
+        "desc" => true,
+        other => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("order must be 'asc' or 'desc', got '{}'", other),
+            ));
+        }
+    };
+
+    // Ties break on id so pages never shuffle records with equal sort keys
+    match (sort_by.unwrap_or("timestamp"), descending) {
+        ("timestamp", true) => Ok(DEFAULT_RECORDS_ORDER),
+        ("timestamp", false) => Ok("timestamp ASC, id ASC"),
+        ("amount", true) => Ok("amount DESC, id DESC"),
+        ("amount", false) => Ok("amount ASC, id ASC"),
+        ("name", true) => Ok("name DESC, id DESC"),
+        ("name", false) => Ok("name ASC, id ASC"),
+        (other, _) => Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "sort_by must be 'timestamp', 'amount' or 'name', got '{}'",
+                other
+            ),
+        )),
+    }
+}
+
+/// Splits the comma-separated `ids` list of record ids, dropping blanks and repeats.
+pub fn parse_record_ids(ids: &str) -> Result<Vec<String>, (StatusCode, String)> {
+    let mut record_ids: Vec<String> = Vec::new();
+    for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        if !record_ids.iter().any(|existing| existing == id) {
+            record_ids.push(id.to_string());
+        }
+    }
+
+    if record_ids.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "ids must contain at least one id".to_string(),
+        ));
+    }
+    if record_ids.len() > MAX_RECORD_FILTER_IDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("ids cannot contain more than {} ids", MAX_RECORD_FILTER_IDS),
+        ));
+    }
+    Ok(record_ids)
+}
+
+/// Reads the comma-separated `include` parameter and returns whether the
+/// category should be embedded, the only supported option so far.
+pub fn parse_record_includes(include: Option<&str>) -> Result<bool, (StatusCode, String)> {
+    let mut include_category = false;
+    for option in include
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+    {
+        match option {
+            "category" => include_category = true,
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown include option '{}'", option),
+                ));
+            }
+        }
+    }
+    Ok(include_category)
+}
+
+/// Splits a comma-separated list of category ids given as query parameter `param`,
+/// dropping blanks.
+pub fn parse_category_ids(
+    category_ids: &str,
+    param: &str,
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let mut ids = Vec::new();
+    for id in category_ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+    {
+        validate_category_id(id)?;
+        if !ids.iter().any(|existing| existing == id) {
+            ids.push(id.to_string());
+        }
+    }
+
+    if ids.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} must contain at least one id", param),
+        ));
+    }
+    if ids.len() > MAX_CATEGORY_FILTER_IDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "{} cannot contain more than {} ids",
+                param, MAX_CATEGORY_FILTER_IDS
+            ),
+        ));
+    }
+    Ok(ids)
+}
+
+/// Checks optional inclusive amount bounds; both must be finite and in order.
+pub fn validate_amount_range(
+    min_amount: Option<f64>,
+    max_amount: Option<f64>,
+) -> Result<(), (StatusCode, String)> {
+    for (name, bound) in [("min_amount", min_amount), ("max_amount", max_amount)] {
+        if bound.is_some_and(|value| !value.is_finite()) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("{} must be a finite number", name),
+            ));
+        }
+    }
+
+    if let (Some(min), Some(max)) = (min_amount, max_amount)
+        && min > max
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "min_amount cannot be greater than max_amount".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Applies the default record time window: from the epoch until now.
+pub fn resolve_time_window(start_time: Option<i64>, end_time: Option<i64>) -> (i64, i64) {
+    let start_time = start_time.unwrap_or(0);
+    let end_time = end_time.unwrap_or_else(|| time::OffsetDateTime::now_utc().unix_timestamp());
+    (start_time, end_time)
+}
+
+/// Resolves a `range` shortcut into inclusive `(start_time, end_time)` bounds as
+/// of `now`, with days, weeks (starting Monday) and months taken in local time
+/// `tz_offset_minutes` east of UTC. `today`, `this_week`, `this_month` and
+/// `last_month` cover the whole period; `last_30d` and `year_to_date` end at `now`.
+pub fn resolve_relative_range(
+    range: &str,
+    now: i64,
+    tz_offset_minutes: i32,
+) -> Result<(i64, i64), (StatusCode, String)> {
+    let offset_secs = i64::from(tz_offset_minutes) * 60;
+    let invalid_date = || {
+        (
+            StatusCode::BAD_REQUEST,
+            "Time range is out of bounds".to_string(),
+        )
+    };
+    let midnight = |date: time::Date| date.midnight().assume_utc().unix_timestamp() - offset_secs;
+    let month_start = |year: i32, month: time::Month| {
+        time::Date::from_calendar_date(year, month, 1).map_err(|_| invalid_date())
+    };
+
+    let today = time::OffsetDateTime::from_unix_timestamp(now + offset_secs)
+        .map_err(|_| invalid_date())?
+        .date();
+    let this_month = month_start(today.year(), today.month())?;
+    let next_month = match today.month() {
+        time::Month::December => month_start(today.year() + 1, time::Month::January)?,
+        month => month_start(today.year(), month.next())?,
+    };
+
+    match range {
+        "today" => {
+            let tomorrow = today.next_day().ok_or_else(invalid_date)?;
+            Ok((midnight(today), midnight(tomorrow) - 1))
+        }
+        "this_week" => {
+            let monday =
+                today - time::Duration::days(i64::from(today.weekday().number_days_from_monday()));
+            Ok((
+                midnight(monday),
+                midnight(monday + time::Duration::days(7)) - 1,
+            ))
+        }
+        "this_month" => Ok((midnight(this_month), midnight(next_month) - 1)),
+        "last_month" => {
+            let last_month = match today.month() {
+                time::Month::January => month_start(today.year() - 1, time::Month::December)?,
+                month => month_start(today.year(), month.previous())?,
+            };
+            Ok((midnight(last_month), midnight(this_month) - 1))
+        }
+        "last_30d" => Ok((now - 30 * 24 * 60 * 60, now)),
+        "year_to_date" => Ok((
+            midnight(month_start(today.year(), time::Month::January)?),
+            now,
+        )),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            "range must be one of today, this_week, this_month, last_month, last_30d, year_to_date"
+                .to_string(),
+        )),
+    }
+}
+
+/// Column list matching `extract_record_from_row`. Tags and splits are aggregated
+/// into JSON arrays so every record query returns them without a second round trip.
+pub const RECORD_COLUMNS: &str = "id, name, amount, category_id, timestamp, currency, kind, created_at, updated_at, payment_method, version, starred, (SELECT json_group_array(tag) FROM record_tags WHERE record_tags.record_id = records.id), (SELECT json_group_array(json_object('category_id', category_id, 'amount', amount)) FROM record_splits WHERE record_splits.record_id = records.id)";
+
+/// Trims and lowercases tags, dropping duplicates. The result is sorted.
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, (StatusCode, String)> {
+    let mut normalized = Vec::with_capacity(tags.len());
+    for tag in tags {
+        validate_string_length(tag, "Tag", MAX_TAG_LENGTH)?;
+        normalized.push(tag.trim().to_lowercase());
+    }
+    normalized.sort();
+    normalized.dedup();
+
+    if normalized.len() > MAX_TAGS_PER_RECORD {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "A record cannot have more than {} tags",
+                MAX_TAGS_PER_RECORD
+            ),
+        ));
+    }
+
+    Ok(normalized)
+}
+
+fn too_few_splits() -> (StatusCode, String) {
+    (
+        StatusCode::BAD_REQUEST,
+        "A split record needs at least two splits".to_string(),
+    )
+}
+
+/// Trims split category ids and checks each amount, rejecting repeated categories.
+/// The result is sorted by category id; an empty list means the record is not split.
+pub fn normalize_splits(splits: &[RecordSplit]) -> Result<Vec<RecordSplit>, (StatusCode, String)> {
+    if splits.len() == 1 {
+        return Err(too_few_splits());
+    }
+    if splits.len() > MAX_SPLITS_PER_RECORD {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "A record cannot have more than {} splits",
+                MAX_SPLITS_PER_RECORD
+            ),
+        ));
+    }
+
+    let mut normalized = Vec::with_capacity(splits.len());
+    for split in splits {
+        validate_category_id(&split.category_id)?;
+        validate_record_amount(split.amount)?;
+        normalized.push(RecordSplit {
+            category_id: split.category_id.trim().to_string(),
+            amount: split.amount,
+        });
+    }
+    normalized.sort_by(|a, b| a.category_id.cmp(&b.category_id));
+
+    if normalized
+        .windows(2)
+        .any(|pair| pair[0].category_id == pair[1].category_id)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A category can only appear once in a record's splits".to_string(),
+        ));
+    }
+
+    Ok(normalized)
+}
+
+/// Checks that splits add up to the record amount, compared in cents so float
+/// rounding in the parts does not matter.
+pub fn validate_split_total(
+    amount: f64,
+    splits: &[RecordSplit],
+) -> Result<(), (StatusCode, String)> {
+    if splits.is_empty() {
+        return Ok(());
+    }
+
+    let to_cents = |amount: f64| (amount * 100.0).round() as i64;
+    let total: i64 = splits.iter().map(|split| to_cents(split.amount)).sum();
+    if total != to_cents(amount) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Split amounts must add up to the record amount".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces every split of a record; an empty slice leaves it unsplit. Splits are
+/// expected to be validated already.
+pub async fn replace_record_splits(
+    conn: &libsql::Connection,
+    record_id: &str,
+    splits: &[RecordSplit],
+) -> Result<(), (StatusCode, String)> {
+    conn.execute("DELETE FROM record_splits WHERE record_id = ?", [record_id])
+        .await
+        .map_err(|_| db_error_with_context("failed to clear record splits"))?;
+
+    for split in splits {
+        conn.execute(
+            "INSERT INTO record_splits (record_id, category_id, amount) VALUES (?, ?, ?)",
+            (record_id, split.category_id.as_str(), split.amount),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to save record splits"))?;
+    }
+
+    Ok(())
+}
+
+/// Replaces every tag of a record. Tags are expected to be normalized already.
+pub async fn replace_record_tags(
+    conn: &libsql::Connection,
+    record_id: &str,
+    tags: &[String],
+) -> Result<(), (StatusCode, String)> {
+    conn.execute("DELETE FROM record_tags WHERE record_id = ?", [record_id])
+        .await
+        .map_err(|_| db_error_with_context("failed to clear record tags"))?;
+
+    for tag in tags {
+        conn.execute(
+            "INSERT INTO record_tags (record_id, tag) VALUES (?, ?)",
+            (record_id, tag.as_str()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to save record tags"))?;
+    }
+
+    Ok(())
+}
+
 pub fn extract_record_from_row(row: libsql::Row) -> Result<Record, (StatusCode, String)> {
     let id: String = row
         .get(0)
@@ -71,12 +569,47 @@ pub fn extract_record_from_row(row: libsql::Row) -> Result<Record, (StatusCode,
     let amount: f64 = row
         .get(2)
         .map_err(|_| db_error_with_context("invalid record data"))?;
-    let category_id: String = row
+    let category_id: Option<String> = row
         .get(3)
         .map_err(|_| db_error_with_context("invalid record data"))?;
     let timestamp: i64 = row
         .get(4)
         .map_err(|_| db_error_with_context("invalid record data"))?;
+    let currency: String = row
+        .get(5)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let kind: String = row
+        .get(6)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let kind =
+        RecordKind::parse(&kind).ok_or_else(|| db_error_with_context("invalid record kind"))?;
+    let created_at: i64 = row
+        .get(7)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let updated_at: i64 = row
+        .get(8)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let payment_method: Option<String> = row
+        .get(9)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let version: i64 = row
+        .get(10)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let starred: bool = row
+        .get(11)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let tags_json: String = row
+        .get(12)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let mut tags: Vec<String> = serde_json::from_str(&tags_json)
+        .map_err(|_| db_error_with_context("invalid record tags"))?;
+    tags.sort();
+    let splits_json: String = row
+        .get(13)
+        .map_err(|_| db_error_with_context("invalid record data"))?;
+    let mut splits: Vec<RecordSplit> = serde_json::from_str(&splits_json)
+        .map_err(|_| db_error_with_context("invalid record splits"))?;
+    splits.sort_by(|a, b| a.category_id.cmp(&b.category_id));
 
     Ok(Record {
         id,
@@ -84,159 +617,2119 @@ pub fn extract_record_from_row(row: libsql::Row) -> Result<Record, (StatusCode,
         amount,
         category_id,
         timestamp,
+        currency,
+        kind,
+        payment_method,
+        tags,
+        splits,
+        created_at,
+        updated_at,
+        version,
+        starred,
+        balance: None,
+        category: None,
+        budget_warnings: Vec::new(),
+        matched_rule: None,
     })
 }
 
+pub async fn find_record_by_id(
+    conn: &libsql::Connection,
+    record_id: &str,
+) -> Result<Option<Record>, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
+            [record_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query existing record"))?;
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => Ok(Some(extract_record_from_row(row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Inserts a record and its tags on `conn`, typically inside a transaction.
+pub async fn insert_record(
+    conn: &libsql::Connection,
+    record: &Record,
+) -> Result<(), (StatusCode, String)> {
+    conn.execute(
+        "INSERT INTO records (id, name, amount, category_id, timestamp, currency, kind, payment_method, created_at, updated_at, version, starred) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        (
+            record.id.as_str(),
+            record.name.as_str(),
+            record.amount,
+            record.category_id.as_deref(),
+            record.timestamp,
+            record.currency.as_str(),
+            record.kind.as_str(),
+            record.payment_method.as_deref(),
+            record.created_at,
+            record.updated_at,
+            record.version,
+            record.starred,
+        ),
+    )
+    .await
+    .map_err(|_| db_error_with_context("record creation failed"))?;
+
+    replace_record_tags(conn, &record.id, &record.tags).await?;
+    replace_record_splits(conn, &record.id, &record.splits).await
+}
+
+/// Looks for an existing record matching `record` on every `DUPLICATE_MATCH_FIELDS`
+/// column with a timestamp within `DUPLICATE_WINDOW_SECS`, returning the closest one's id.
+pub async fn find_duplicate_record(
+    conn: &libsql::Connection,
+    record: &Record,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let mut conditions = Vec::with_capacity(DUPLICATE_MATCH_FIELDS.len() + 1);
+    let mut params: Vec<libsql::Value> = Vec::with_capacity(DUPLICATE_MATCH_FIELDS.len() + 3);
+    for field in DUPLICATE_MATCH_FIELDS {
+        let value = match *field {
+            "name" => record.name.clone().into(),
+            "amount" => record.amount.into(),
+            "category_id" => record.category_id.clone().into(),
+            "currency" => record.currency.clone().into(),
+            "kind" => record.kind.as_str().into(),
+            _ => return Err(db_error_with_context("unsupported duplicate match field")),
+        };
+        // IS rather than = so uncategorized records match each other
+        conditions.push(format!("{} IS ?", field));
+        params.push(value);
+    }
+    conditions.push("timestamp BETWEEN ? AND ?".to_string());
+    params.push((record.timestamp - DUPLICATE_WINDOW_SECS).into());
+    params.push((record.timestamp + DUPLICATE_WINDOW_SECS).into());
+    params.push(record.timestamp.into());
+
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT id FROM records WHERE {} ORDER BY ABS(timestamp - ?) ASC LIMIT 1",
+                conditions.join(" AND ")
+            ),
+            libsql::params_from_iter(params),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to check for duplicate records"))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => Ok(Some(row.get(0).map_err(|_| db_error())?)),
+        None => Ok(None),
+    }
+}
+
 pub async fn create_record(
     State(_main_db): State<Db>,
     session: Session,
+    Query(query): Query<CreateRecordQuery>,
+    headers: HeaderMap,
     Json(payload): Json<CreateRecordPayload>,
 ) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
     // Get current user from session
     let user = get_current_user(&session).await?;
 
     // Input validation
+    let idempotency_key = idempotency_key_from_headers(&headers)?;
+    if let Some(ref record_id) = payload.id {
+        validate_record_id(record_id)?;
+    }
     validate_record_name(&payload.name)?;
     validate_record_amount(payload.amount)?;
-    validate_category_id(&payload.category_id)?;
-    validate_timestamp(payload.timestamp)?;
+    if let Some(ref category_id) = payload.category_id {
+        validate_category_id(category_id)?;
+    }
+    if let Some(timestamp) = payload.timestamp {
+        validate_timestamp(timestamp)?;
+    }
+    if let Some(ref currency) = payload.currency {
+        validate_currency(currency)?;
+    }
+    if let Some(ref payment_method) = payload.payment_method {
+        validate_payment_method(payment_method)?;
+    }
+    let kind = payload
+        .kind
+        .as_deref()
+        .map(parse_record_kind)
+        .transpose()?
+        .unwrap_or_default();
+    let tags = normalize_tags(&payload.tags)?;
+    let splits = normalize_splits(&payload.splits)?;
+    validate_split_total(payload.amount, &splits)?;
 
     // Get user's database
     let user_db = get_user_database(&user.id).await?;
 
-    // Validate that the category exists
-    validate_category_exists(&user_db, &payload.category_id).await?;
+    // Validate that the category exists and still takes new records
+    if let Some(ref category_id) = payload.category_id {
+        validate_category_active(&user_db, category_id).await?;
+    }
+    for split in &splits {
+        validate_category_active(&user_db, &split.category_id).await?;
+    }
 
-    // Create record
-    let record_id = Uuid::new_v4().to_string();
+    // A record without any category may get one from the keyword rules
+    let matched_rule =
+        if query.auto_categorize && payload.category_id.is_none() && splits.is_empty() {
+            let conn = user_db.read().await;
+            CategoryRuleMatcher::load(&conn)
+                .await?
+                .find(payload.name.trim())
+                .cloned()
+        } else {
+            None
+        };
 
-    let conn = user_db.write().await;
-    conn.execute(
-        "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES (?, ?, ?, ?, ?)",
-        (
-            record_id.as_str(),
-            payload.name.trim(),
-            payload.amount,
-            payload.category_id.trim(),
-            payload.timestamp,
-        ),
-    )
-    .await
-    .map_err(|_| db_error_with_context("record creation failed"))?;
+    // Serializing the deserialized payload cannot fail. Amounts are fingerprinted
+    // as plain numbers, so a replay asking for another amount format still matches
+    let request = with_amount_format(AmountFormat::Number, async {
+        serde_json::to_string(&payload).unwrap_or_default()
+    })
+    .await;
+    // Read before the write lock is taken, for the budget check under it
+    let default_currency = get_default_currency(&user_db).await?;
+    let currency = payload.currency.unwrap_or_else(|| default_currency.clone());
 
+    // Create record
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
     let record = Record {
-        id: record_id,
+        id: payload.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
         name: payload.name.trim().to_string(),
         amount: payload.amount,
-        category_id: payload.category_id.trim().to_string(),
-        timestamp: payload.timestamp,
+        category_id: payload
+            .category_id
+            .map(|category_id| category_id.trim().to_string())
+            .or_else(|| matched_rule.as_ref().map(|rule| rule.category_id.clone())),
+        timestamp: payload.timestamp.unwrap_or(now),
+        currency,
+        kind,
+        payment_method: payload
+            .payment_method
+            .map(|payment_method| payment_method.trim().to_string()),
+        tags,
+        splits,
+        created_at: now,
+        updated_at: now,
+        version: 1,
+        starred: false,
+        balance: None,
+        category: None,
+        budget_warnings: Vec::new(),
+        matched_rule: None,
     };
 
-    Ok((StatusCode::CREATED, Json(record)))
-}
-
-pub async fn get_records(
-    State(_main_db): State<Db>,
-    session: Session,
-    Query(query): Query<GetRecordsQuery>,
-) -> Result<(StatusCode, Json<GetRecordsResponse>), (StatusCode, String)> {
-    let user = get_current_user(&session).await?;
-
-    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("record creation failed"))?;
 
-    let limit = validate_records_limit(query.limit)?;
+    // Checked on the insert's transaction so a double submit cannot slip between check and insert.
+    // A replayed idempotency key yields the originally created record instead.
+    let result = async {
+        if let Some(ref key) = idempotency_key
+            && let Some(previous) =
+                find_idempotent_response(&tx, key, now - IDEMPOTENCY_KEY_TTL_SECS).await?
+        {
+            if previous.request != request {
+                return Err((
+                    StatusCode::CONFLICT,
+                    "Idempotency-Key was already used for a different request".to_string(),
+                ));
+            }
+            return Ok((StatusCode::CREATED, previous.record));
+        }
 
-    let conn = user_db.read().await;
+        // A client-generated id that is already taken means the create was replayed,
+        // e.g. by an offline client retrying its queue
+        if let Some(existing) = find_record_by_id(&tx, &record.id).await? {
+            return Ok((StatusCode::OK, existing));
+        }
 
-    // Use default values: start_time defaults to 0, end_time defaults to current timestamp
-    let start_time = query.start_time.unwrap_or(0);
-    let end_time = query
-        .end_time
-        .unwrap_or_else(|| time::OffsetDateTime::now_utc().unix_timestamp());
+        ensure_period_open(&tx, record.timestamp).await?;
 
-    // Get total count
-    let count_query = "SELECT COUNT(*) FROM records WHERE timestamp BETWEEN ? AND ?";
-    let mut count_rows = conn
-        .query(count_query, (start_time, end_time))
-        .await
-        .map_err(|_| db_error_with_context("failed to count records"))?;
+        if !query.allow_duplicate
+            && let Some(existing_id) = find_duplicate_record(&tx, &record).await?
+        {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Duplicate of existing record {}", existing_id),
+            ));
+        }
+        insert_record(&tx, &record).await?;
+        enforce_budgets(&tx, &record, None, &default_currency).await?;
+        if let Some(ref key) = idempotency_key {
+            store_idempotent_response(&tx, key, &request, &record, now).await?;
+        }
+        Ok((StatusCode::CREATED, record))
+    }
+    .await;
 
-    let total_count: u32 = if let Some(row) = count_rows.next().await.map_err(|_| db_error())? {
-        row.get(0).map_err(|_| db_error())?
-    } else {
-        0
+    let (status, mut record) = match result {
+        Ok((status, record)) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("record creation failed"))?;
+            (status, record)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err(err);
+        }
     };
+    drop(conn);
 
-    // Get records
-    let records_query = "SELECT id, name, amount, category_id, timestamp FROM records WHERE timestamp BETWEEN ? AND ? ORDER BY timestamp DESC LIMIT ?";
-    let mut rows = conn
-        .query(records_query, (start_time, end_time, limit))
+    // The record is in either way, so a failed check only drops the warnings
+    record.budget_warnings = budget_warnings(&user_db, &record).await.unwrap_or_default();
+    record.matched_rule = matched_rule;
+    Ok((status, Json(record)))
+}
+
+/// Creates a record split across categories. Same as `create_record` except that
+/// the splits are required.
+pub async fn create_split_record(
+    state: State<Db>,
+    session: Session,
+    query: Query<CreateRecordQuery>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateRecordPayload>,
+) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
+    if payload.splits.is_empty() {
+        return Err(too_few_splits());
+    }
+    create_record(state, session, query, headers, Json(payload)).await
+}
+
+/// Copies a record under a new id, dated now unless `overrides` says otherwise.
+/// The copy keeps the name, amount, category, currency, kind, payment method,
+/// tags and splits, but starts unstarred at version 1.
+pub async fn duplicate_existing_record(
+    user_db: &Db,
+    record_id: &str,
+    overrides: &DuplicateRecordPayload,
+) -> Result<Record, (StatusCode, String)> {
+    if let Some(amount) = overrides.amount {
+        validate_record_amount(amount)?;
+    }
+    if let Some(timestamp) = overrides.timestamp {
+        validate_timestamp(timestamp)?;
+    }
+    // Read before the write lock is taken, for the budget check under it
+    let default_currency = get_default_currency(user_db).await?;
+
+    let conn = user_db.write().await;
+    let mut rows = conn
+        .query(
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
+            [record_id],
+        )
         .await
-        .map_err(|_| db_error_with_context("failed to query records"))?;
+        .map_err(|_| db_error_with_context("failed to query existing record"))?;
+    let original = match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => extract_record_from_row(row)?,
+        None => return Err((StatusCode::NOT_FOUND, "Record not found".to_string())),
+    };
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let copy = Record {
+        id: Uuid::new_v4().to_string(),
+        amount: overrides.amount.unwrap_or(original.amount),
+        timestamp: overrides.timestamp.unwrap_or(now),
+        created_at: now,
+        updated_at: now,
+        version: 1,
+        starred: false,
+        balance: None,
+        category: None,
+        budget_warnings: Vec::new(),
+        matched_rule: None,
+        ..original
+    };
+    // A new amount on a split record needs splits that still add up to it
+    validate_split_total(copy.amount, &copy.splits)?;
+
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("record creation failed"))?;
+    let result = async {
+        ensure_period_open(&tx, copy.timestamp).await?;
+        insert_record(&tx, &copy).await?;
+        enforce_budgets(&tx, &copy, None, &default_currency).await
+    }
+    .await;
+    match result {
+        Ok(()) => tx
+            .commit()
+            .await
+            .map_err(|_| db_error_with_context("record creation failed"))?,
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err(err);
+        }
+    }
+
+    Ok(copy)
+}
+
+pub async fn duplicate_record(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(record_id): Path<String>,
+    payload: Option<Json<DuplicateRecordPayload>>,
+) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let overrides = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let user_db = get_user_database(&user.id).await?;
+    let record = duplicate_existing_record(&user_db, &record_id, &overrides).await?;
+
+    Ok((StatusCode::CREATED, Json(record)))
+}
+
+/// Turns free text into an FTS5 query that requires every word as a prefix. Words
+/// are quoted, so FTS5 operators and punctuation in the input are searched literally.
+pub fn fts_match_query(term: &str) -> String {
+    term.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// WHERE clause for record queries built from optional filters. Conditions are
+/// SQL fragments made of column names and placeholders only; user input only
+/// ever travels as bound parameters.
+struct RecordFilter {
+    conditions: Vec<Cow<'static, str>>,
+    params: Vec<libsql::Value>,
+    /// Time range the filter is bounded by, which decides the archives it reads
+    range: Option<(i64, i64)>,
+}
+
+impl RecordFilter {
+    fn time_range(start_time: i64, end_time: i64) -> Self {
+        RecordFilter {
+            conditions: vec!["timestamp BETWEEN ? AND ?".into()],
+            params: vec![start_time.into(), end_time.into()],
+            range: Some((start_time, end_time)),
+        }
+    }
+
+    /// Matches exactly the given records, whatever their timestamp. Archived
+    /// records are not looked up by id.
+    fn by_ids(ids: Vec<String>) -> Self {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        RecordFilter {
+            conditions: vec![format!("id IN ({})", placeholders).into()],
+            params: ids.into_iter().map(libsql::Value::from).collect(),
+            range: None,
+        }
+    }
+
+    /// Table expression the filter reads from, see [`records_source`].
+    async fn source(
+        &self,
+        conn: &libsql::Connection,
+    ) -> Result<Cow<'static, str>, (StatusCode, String)> {
+        match self.range {
+            Some((start_time, end_time)) => records_source(conn, start_time, end_time).await,
+            None => Ok("records".into()),
+        }
+    }
+
+    fn with_tag(&mut self, tag: String) {
+        self.conditions.push(
+            "EXISTS (SELECT 1 FROM record_tags WHERE record_tags.record_id = records.id AND record_tags.tag = ?)".into(),
+        );
+        self.params.push(tag.into());
+    }
+
+    /// Matches `term` against record names through the full-text index when there
+    /// is one, otherwise with a substring match.
+    fn with_search(&mut self, term: &str, full_text: bool) {
+        if full_text {
+            self.conditions.push(
+                "records.rowid IN (SELECT rowid FROM records_fts WHERE records_fts MATCH ?)".into(),
+            );
+            self.params.push(fts_match_query(term).into());
+        } else {
+            self.conditions.push("name LIKE ? COLLATE NOCASE".into());
+            self.params.push(format!("%{}%", term.trim()).into());
+        }
+    }
+
+    fn with_category(&mut self, category_id: String) {
+        self.conditions.push("category_id = ?".into());
+        self.params.push(category_id.into());
+    }
+
+    fn with_categories(&mut self, category_ids: Vec<String>) {
+        let placeholders = vec!["?"; category_ids.len()].join(", ");
+        self.conditions
+            .push(format!("category_id IN ({})", placeholders).into());
+        self.params
+            .extend(category_ids.into_iter().map(libsql::Value::from));
+    }
+
+    fn without_category(&mut self) {
+        self.conditions.push("category_id IS NULL".into());
+    }
+
+    /// Uncategorized records are kept, since they are not filed under any of `category_ids`.
+    fn without_categories(&mut self, category_ids: Vec<String>) {
+        let placeholders = vec!["?"; category_ids.len()].join(", ");
+        self.conditions.push(
+            format!(
+                "(category_id IS NULL OR category_id NOT IN ({}))",
+                placeholders
+            )
+            .into(),
+        );
+        self.params
+            .extend(category_ids.into_iter().map(libsql::Value::from));
+    }
+
+    fn with_starred(&mut self, starred: bool) {
+        self.conditions.push("starred = ?".into());
+        self.params.push(starred.into());
+    }
+
+    fn with_payment_method(&mut self, payment_method: String) {
+        self.conditions.push("payment_method = ?".into());
+        self.params.push(payment_method.into());
+    }
+
+    fn with_kind(&mut self, kind: RecordKind) {
+        self.conditions.push("kind = ?".into());
+        self.params.push(kind.as_str().into());
+    }
+
+    fn after_cursor(&mut self, cursor: RecordCursor) {
+        self.conditions.push("(timestamp, id) < (?, ?)".into());
+        self.params.push(cursor.timestamp.into());
+        self.params.push(cursor.id.into());
+    }
+
+    fn with_min_amount(&mut self, min_amount: f64) {
+        self.conditions.push("amount >= ?".into());
+        self.params.push(min_amount.into());
+    }
+
+    fn with_max_amount(&mut self, max_amount: f64) {
+        self.conditions.push("amount <= ?".into());
+        self.params.push(max_amount.into());
+    }
+
+    fn with_currency(&mut self, currency: String) {
+        self.conditions.push("currency = ?".into());
+        self.params.push(currency.into());
+    }
+
+    fn clause(&self) -> String {
+        self.conditions.join(" AND ")
+    }
+
+    fn params(&self) -> Vec<libsql::Value> {
+        self.params.clone()
+    }
+}
+
+/// Builds the WHERE clause of GET /records from the query's filter parameters;
+/// shared by the listing and the count-only HEAD /records.
+async fn records_filter(
+    conn: &libsql::Connection,
+    query: &GetRecordsQuery,
+) -> Result<RecordFilter, (StatusCode, String)> {
+    // Planned records lie after the default end of now, so they need the flag or an explicit end_time
+    let end_time = match query.end_time {
+        None if query.include_future.unwrap_or(false) => Some(i64::MAX),
+        end_time => end_time,
+    };
+    let tz_offset_minutes = validate_tz_offset(query.tz_offset_minutes)?;
+    if query.range.is_some() && (query.start_time.is_some() || query.end_time.is_some()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "range cannot be combined with start_time or end_time".to_string(),
+        ));
+    }
+    let mut filter = match query.ids.as_deref() {
+        Some(_) if query.start_time.is_some() || query.end_time.is_some() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "ids cannot be combined with start_time or end_time".to_string(),
+            ));
+        }
+        Some(_) if query.range.is_some() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "ids cannot be combined with range".to_string(),
+            ));
+        }
+        Some(ids) => RecordFilter::by_ids(parse_record_ids(ids)?),
+        None => {
+            let (start_time, end_time) = match query.range.as_deref() {
+                Some(range) => {
+                    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+                    resolve_relative_range(range, now, tz_offset_minutes)?
+                }
+                None => resolve_time_window(query.start_time, end_time),
+            };
+            RecordFilter::time_range(start_time, end_time)
+        }
+    };
+    if let Some(tag) = query.tag.as_deref() {
+        validate_string_length(tag, "Tag", MAX_TAG_LENGTH)?;
+        filter.with_tag(tag.trim().to_lowercase());
+    }
+    if let Some(kind) = query.kind.as_deref() {
+        filter.with_kind(parse_record_kind(kind)?);
+    }
+    if let Some(payment_method) = query.payment_method.as_deref() {
+        validate_payment_method(payment_method)?;
+        filter.with_payment_method(payment_method.trim().to_string());
+    }
+    if let Some(starred) = query.starred {
+        filter.with_starred(starred);
+    }
+    match query.category_id.as_deref() {
+        Some(UNCATEGORIZED_FILTER) => filter.without_category(),
+        Some(category_id) => {
+            validate_category_id(category_id)?;
+            filter.with_category(category_id.trim().to_string());
+        }
+        None => {}
+    }
+    let category_ids = query
+        .category_ids
+        .as_deref()
+        .map(|ids| parse_category_ids(ids, "category_ids"))
+        .transpose()?;
+    let excluded_ids = query
+        .exclude_category_ids
+        .as_deref()
+        .map(|ids| parse_category_ids(ids, "exclude_category_ids"))
+        .transpose()?;
+    if let (Some(included), Some(excluded)) = (&category_ids, &excluded_ids)
+        && let Some(id) = included.iter().find(|id| excluded.contains(id))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Category {} cannot be both included and excluded", id),
+        ));
+    }
+    if let Some(category_ids) = category_ids {
+        filter.with_categories(category_ids);
+    }
+    if let Some(excluded_ids) = excluded_ids {
+        filter.without_categories(excluded_ids);
+    }
+    validate_amount_range(query.min_amount, query.max_amount)?;
+    if let Some(min_amount) = query.min_amount {
+        filter.with_min_amount(min_amount);
+    }
+    if let Some(max_amount) = query.max_amount {
+        filter.with_max_amount(max_amount);
+    }
+
+    if let Some(q) = query.q.as_deref() {
+        validate_string_length(q, "Search term", MAX_SEARCH_TERM_LENGTH)?;
+        // The full-text index only covers records that are not archived
+        let full_text = table_exists(conn, "records_fts")
+            .await
+            .map_err(|_| db_error_with_context("failed to check search index"))?
+            && filter.source(conn).await? == "records";
+        filter.with_search(q, full_text);
+    }
+    Ok(filter)
+}
+
+/// Counts the records matching the filter parameters of `query`; paging,
+/// ordering and response options are ignored.
+pub async fn count_records(
+    user_db: &Db,
+    query: &GetRecordsQuery,
+) -> Result<u32, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let filter = records_filter(&conn, query).await?;
+    let source = filter.source(&conn).await?;
+
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT COUNT(*) FROM {} AS records WHERE {}",
+                source,
+                filter.clause()
+            ),
+            libsql::params_from_iter(filter.params()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to count records"))?;
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => row.get(0).map_err(|_| db_error()),
+        None => Ok(0),
+    }
+}
+
+fn total_count_headers(total_count: u32) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total_count));
+    headers
+}
+
+/// Lists records matching `query`. The count and the row fetch each run only
+/// when the response needs them (see `include_total` and `count_only`). Pages
+/// in the default order carry a `next_cursor` while more records follow; the
+/// count ignores the cursor and covers every matching record. `has_more` is
+/// worked out from one row past the page, so it also holds for cursor pages,
+/// custom orders and uncounted queries.
+pub async fn list_records(
+    user_db: &Db,
+    query: &GetRecordsQuery,
+) -> Result<GetRecordsResponse, (StatusCode, String)> {
+    let limit = validate_records_limit(query.limit)?;
+    let order_by = records_order_clause(query.sort_by.as_deref(), query.order.as_deref())?;
+
+    let include_category = parse_record_includes(query.include.as_deref())?;
+    let include_total = query.include_total.unwrap_or(true);
+    let include_sum = query.include_sum.unwrap_or(false);
+    let count_only = query.count_only.unwrap_or(false);
+    if count_only && !include_total {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "count_only cannot be combined with include_total=false".to_string(),
+        ));
+    }
+
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(decode_record_cursor)
+        .transpose()?;
+    let keyset_order = order_by == DEFAULT_RECORDS_ORDER;
+    if cursor.is_some() && !keyset_order {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "cursor can only be used with the default timestamp order".to_string(),
+        ));
+    }
+
+    let include_balance = query.include_balance.unwrap_or(false);
+    if let Some(opening_balance) = query.opening_balance {
+        if !include_balance {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "opening_balance requires include_balance=true".to_string(),
+            ));
+        }
+        if !opening_balance.is_finite() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "opening_balance must be a finite number".to_string(),
+            ));
+        }
+    }
+
+    let conn = user_db.read().await;
+    let mut filter = records_filter(&conn, query).await?;
+    let source = filter.source(&conn).await?;
+
+    // Get total count and sum, both over the full filter
+    let (total_count, total_amount) = if include_total || include_sum {
+        let count_query = format!(
+            "SELECT COUNT(*), COALESCE(SUM(amount), 0.0) FROM {} AS records WHERE {}",
+            source,
+            filter.clause()
+        );
+        let mut count_rows = conn
+            .query(&count_query, libsql::params_from_iter(filter.params()))
+            .await
+            .map_err(|_| db_error_with_context("failed to count records"))?;
+
+        let (count, sum) = match count_rows.next().await.map_err(|_| db_error())? {
+            Some(row) => (
+                row.get(0).map_err(|_| db_error())?,
+                row.get(1).map_err(|_| db_error())?,
+            ),
+            None => (0, 0.0),
+        };
+        (include_total.then_some(count), include_sum.then_some(sum))
+    } else {
+        (None, None)
+    };
+
+    // Balances cover the whole filtered range, so capture it before paging
+    let balance_filter = include_balance.then(|| (filter.clause(), filter.params()));
+
+    // Get records
+    let mut records = Vec::new();
+    if let Some(cursor) = cursor {
+        filter.after_cursor(cursor);
+    }
+    if !count_only {
+        let records_query = format!(
+            "SELECT {} FROM {} AS records WHERE {} ORDER BY {} LIMIT ?",
+            RECORD_COLUMNS,
+            source,
+            filter.clause(),
+            order_by
+        );
+        // One extra row tells whether another page follows
+        let mut params = filter.params();
+        params.push((limit + 1).into());
+        let mut rows = conn
+            .query(&records_query, libsql::params_from_iter(params))
+            .await
+            .map_err(|_| db_error_with_context("failed to query records"))?;
+
+        while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            records.push(extract_record_from_row(row)?);
+        }
+    }
+    let has_more = if count_only {
+        total_count.is_some_and(|count| count > 0)
+    } else {
+        records.len() as u32 > limit
+    };
+    records.truncate(limit as usize);
+
+    if let Some((clause, params)) = balance_filter
+        && !records.is_empty()
+    {
+        let opening_balance = query.opening_balance.unwrap_or(0.0);
+        let balances = running_balances(&conn, &source, &clause, params, &records).await?;
+        for record in &mut records {
+            record.balance = balances
+                .get(&record.id)
+                .map(|balance| opening_balance + balance);
+        }
+    }
+
+    if include_category {
+        attach_record_categories(&conn, &mut records).await?;
+    }
+
+    let next_cursor = match records.last() {
+        Some(last) if keyset_order && has_more => Some(encode_record_cursor(&RecordCursor {
+            timestamp: last.timestamp,
+            id: last.id.clone(),
+        })),
+        _ => None,
+    };
+
+    Ok(GetRecordsResponse {
+        records,
+        total_count,
+        total_amount,
+        limit,
+        has_more,
+        next_cursor,
+    })
+}
+
+/// Running totals of the records matching `clause`, oldest first with ties broken
+/// by id, looked up for the records of the current page. Income adds to the
+/// balance and expenses subtract from it; amounts in different currencies are
+/// summed as they are.
+async fn running_balances(
+    conn: &libsql::Connection,
+    source: &str,
+    clause: &str,
+    mut params: Vec<libsql::Value>,
+    page: &[Record],
+) -> Result<std::collections::HashMap<String, f64>, (StatusCode, String)> {
+    let placeholders = vec!["?"; page.len()].join(", ");
+    let balance_query = format!(
+        "SELECT id, balance FROM (SELECT id, SUM(CASE WHEN kind = 'income' THEN amount ELSE -amount END) OVER (ORDER BY timestamp ASC, id ASC ROWS UNBOUNDED PRECEDING) AS balance FROM {} AS records WHERE {}) WHERE id IN ({})",
+        source, clause, placeholders
+    );
+    params.extend(
+        page.iter()
+            .map(|record| libsql::Value::from(record.id.clone())),
+    );
+
+    let mut rows = conn
+        .query(&balance_query, libsql::params_from_iter(params))
+        .await
+        .map_err(|_| db_error_with_context("failed to compute balances"))?;
+
+    let mut balances = std::collections::HashMap::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        balances.insert(
+            row.get::<String>(0).map_err(|_| db_error())?,
+            row.get::<f64>(1).map_err(|_| db_error())?,
+        );
+    }
+    Ok(balances)
+}
+
+/// Sets `category` on each record from a LEFT JOIN to categories, leaving it
+/// null for records whose category no longer exists.
+pub async fn attach_record_categories(
+    conn: &libsql::Connection,
+    records: &mut [Record],
+) -> Result<(), (StatusCode, String)> {
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let placeholders = vec!["?"; records.len()].join(", ");
+    let ids: Vec<libsql::Value> = records
+        .iter()
+        .map(|record| libsql::Value::from(record.id.clone()))
+        .collect();
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT records.id, categories.id, categories.name FROM records LEFT JOIN categories ON categories.id = records.category_id WHERE records.id IN ({})",
+                placeholders
+            ),
+            libsql::params_from_iter(ids),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query record categories"))?;
+
+    let mut categories = std::collections::HashMap::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let record_id: String = row.get(0).map_err(|_| db_error())?;
+        let category = match row.get::<Option<String>>(1).map_err(|_| db_error())? {
+            Some(id) => Some(RecordCategory {
+                id,
+                name: row.get(2).map_err(|_| db_error())?,
+            }),
+            None => None,
+        };
+        categories.insert(record_id, category);
+    }
+
+    for record in records {
+        record.category = Some(categories.remove(&record.id).flatten());
+    }
+    Ok(())
+}
+
+/// GET /records. Without `end_time` the window ends now, so future-dated records
+/// such as next week's rent only show up with `include_future=true` or an explicit
+/// `end_time` after their timestamp.
+pub async fn get_records(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetRecordsQuery>,
+) -> Result<(StatusCode, HeaderMap, Json<GetRecordsResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let response = list_records(&user_db, &query).await?;
+    let headers = response
+        .total_count
+        .map(total_count_headers)
+        .unwrap_or_default();
+
+    Ok((StatusCode::OK, headers, Json(response)))
+}
+
+/// HEAD /records. Takes the same filters as GET /records but only runs the count,
+/// returned in the `X-Total-Count` header.
+pub async fn head_records(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetRecordsQuery>,
+) -> Result<(StatusCode, HeaderMap), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let total_count = count_records(&user_db, &query).await?;
+
+    Ok((StatusCode::OK, total_count_headers(total_count)))
+}
+
+/// GET /records/{id}, optionally with the category embedded like GET /records.
+pub async fn get_record(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(record_id): Path<String>,
+    Query(query): Query<GetRecordQuery>,
+) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let include_category = parse_record_includes(query.include.as_deref())?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let Some(mut record) = find_record_by_id(&conn, &record_id).await? else {
+        return Err((StatusCode::NOT_FOUND, "Record not found".to_string()));
+    };
+    if include_category {
+        attach_record_categories(&conn, std::slice::from_mut(&mut record)).await?;
+    }
+
+    Ok((StatusCode::OK, Json(record)))
+}
+
+/// Distinct record names starting with `prefix`, ignoring case, most recently
+/// used first. Names differing only in case count once, spelled as on the
+/// latest record that uses them.
+pub async fn suggest_record_names(
+    user_db: &Db,
+    prefix: &str,
+    limit: u32,
+) -> Result<Vec<String>, (StatusCode, String)> {
+    // The prefix is matched literally, so LIKE wildcards in it are escaped
+    let pattern = format!(
+        "{}%",
+        prefix
+            .trim()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT name, MAX(timestamp) AS last_used FROM records WHERE name LIKE ? ESCAPE '\\' GROUP BY name COLLATE NOCASE ORDER BY last_used DESC, name ASC LIMIT ?",
+            (pattern, limit),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query record names"))?;
+
+    let mut names = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        names.push(row.get(0).map_err(|_| db_error())?);
+    }
+    Ok(names)
+}
+
+/// GET /records/names, for autocompleting the name of a new record.
+pub async fn get_record_names(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetRecordNamesQuery>,
+) -> Result<(StatusCode, Json<Vec<String>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let prefix = query.prefix.unwrap_or_default();
+    validate_string_length(&prefix, "Prefix", MAX_RECORD_NAME_LENGTH)?;
+    let limit = match query.limit {
+        Some(0) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Limit must be greater than 0".to_string(),
+            ));
+        }
+        Some(limit) if limit > MAX_NAME_SUGGESTIONS_LIMIT => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Limit cannot exceed {}", MAX_NAME_SUGGESTIONS_LIMIT),
+            ));
+        }
+        limit => limit.unwrap_or(DEFAULT_NAME_SUGGESTIONS_LIMIT),
+    };
+
+    let user_db = get_user_database(&user.id).await?;
+    let names = suggest_record_names(&user_db, &prefix, limit).await?;
+
+    Ok((StatusCode::OK, Json(names)))
+}
+
+/// The `n` records in a time range with the largest absolute amount, largest
+/// first. Equal amounts fall back to the newest timestamp, then id, so repeated
+/// calls return the same records in the same order.
+pub async fn top_records(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+    n: u32,
+    category_id: Option<&str>,
+) -> Result<Vec<Record>, (StatusCode, String)> {
+    let mut filter = RecordFilter::time_range(start_time, end_time);
+    if let Some(category_id) = category_id {
+        filter.with_category(category_id.to_string());
+    }
+
+    let conn = user_db.read().await;
+    let mut params = filter.params();
+    params.push(n.into());
+    let top_query = format!(
+        "SELECT {} FROM {} AS records WHERE {} ORDER BY ABS(amount) DESC, timestamp DESC, id DESC LIMIT ?",
+        RECORD_COLUMNS,
+        filter.source(&conn).await?,
+        filter.clause()
+    );
+
+    let mut rows = conn
+        .query(&top_query, libsql::params_from_iter(params))
+        .await
+        .map_err(|_| db_error_with_context("failed to query top records"))?;
 
     let mut records = Vec::new();
     while let Some(row) = rows.next().await.map_err(|_| db_error())? {
         records.push(extract_record_from_row(row)?);
     }
 
-    Ok((
-        StatusCode::OK,
-        Json(GetRecordsResponse {
-            records,
-            total_count,
-        }),
-    ))
+    Ok(records)
+}
+
+pub async fn get_top_records(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetTopRecordsQuery>,
+) -> Result<(StatusCode, Json<Vec<Record>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let n = validate_limit(query.n, DEFAULT_TOP_RECORDS_LIMIT)?;
+    if let Some(ref category_id) = query.category_id {
+        validate_category_id(category_id)?;
+    }
+    let (start_time, end_time) = resolve_time_window(query.start_time, query.end_time);
+
+    let user_db = get_user_database(&user.id).await?;
+    let records = top_records(
+        &user_db,
+        start_time,
+        end_time,
+        n,
+        query.category_id.as_deref(),
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(records)))
+}
+
+/// SQLite date modifiers turning a unix timestamp into local time at
+/// `tz_offset_minutes` east of UTC. The offset must already be validated.
+fn local_time_modifiers(tz_offset_minutes: i32) -> String {
+    format!("'unixepoch', '{:+} minutes'", tz_offset_minutes)
+}
+
+/// SQL expression bucketing a record's timestamp, shifted to local time by
+/// `tz_offset_minutes`, into a sortable period label: `YYYY-MM-DD` per day, the
+/// Monday starting the week per week, `YYYY-MM` per month.
+pub fn summary_period_expr(
+    group_by: &str,
+    tz_offset_minutes: i32,
+) -> Result<String, (StatusCode, String)> {
+    let local = local_time_modifiers(tz_offset_minutes);
+    match group_by {
+        "day" => Ok(format!("strftime('%Y-%m-%d', timestamp, {})", local)),
+        "week" => Ok(format!(
+            "date(timestamp, {}, 'weekday 0', '-6 days')",
+            local
+        )),
+        "month" => Ok(format!("strftime('%Y-%m', timestamp, {})", local)),
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            format!("group_by must be 'day', 'week' or 'month', got '{}'", other),
+        )),
+    }
+}
+
+/// SQL expression for the factor turning a record's amount into the currency bound
+/// to its placeholder: 1 for that currency itself, the stored exchange rate for
+/// others, NULL when a currency has no rate.
+const CONVERSION_RATE_EXPR: &str = "CASE WHEN currency = ? THEN 1.0 ELSE (SELECT rate FROM exchange_rates WHERE exchange_rates.currency = records.currency) END";
+
+/// Totals and counts the records in a time range per period and currency, oldest
+/// period first. Periods without records are omitted, and so are records filed
+/// under one of `excluded_category_ids`.
+pub async fn summarize_records(
+    user_db: &Db,
+    group_by: &str,
+    start_time: i64,
+    end_time: i64,
+    excluded_category_ids: &[String],
+    tz_offset_minutes: i32,
+) -> Result<Vec<SummaryBucket>, (StatusCode, String)> {
+    summarize(
+        user_db,
+        group_by,
+        start_time,
+        end_time,
+        excluded_category_ids,
+        tz_offset_minutes,
+        None,
+    )
+    .await
+}
+
+/// Like [`summarize_records`], but every amount is converted into `base_currency`
+/// at the stored exchange rates before it is totalled, giving one bucket per
+/// period. Records in a currency without a rate stay in per-currency buckets
+/// flagged `unconverted`, listed after the converted bucket of their period.
+pub async fn summarize_records_converted(
+    user_db: &Db,
+    group_by: &str,
+    start_time: i64,
+    end_time: i64,
+    excluded_category_ids: &[String],
+    tz_offset_minutes: i32,
+    base_currency: &str,
+) -> Result<Vec<SummaryBucket>, (StatusCode, String)> {
+    summarize(
+        user_db,
+        group_by,
+        start_time,
+        end_time,
+        excluded_category_ids,
+        tz_offset_minutes,
+        Some(base_currency),
+    )
+    .await
+}
+
+async fn summarize(
+    user_db: &Db,
+    group_by: &str,
+    start_time: i64,
+    end_time: i64,
+    excluded_category_ids: &[String],
+    tz_offset_minutes: i32,
+    convert_to: Option<&str>,
+) -> Result<Vec<SummaryBucket>, (StatusCode, String)> {
+    let period = summary_period_expr(group_by, tz_offset_minutes)?;
+
+    let mut filter = RecordFilter::time_range(start_time, end_time);
+    if !excluded_category_ids.is_empty() {
+        filter.without_categories(excluded_category_ids.to_vec());
+    }
+
+    // Without conversion every rate is 1, so buckets fall back to the record currency
+    let (bucket_currency, rate) = match convert_to {
+        Some(_) => ("?", CONVERSION_RATE_EXPR),
+        None => ("currency", "1.0"),
+    };
+    let mut params = Vec::new();
+    if let Some(base_currency) = convert_to {
+        params.push(libsql::Value::from(base_currency.to_string()));
+        params.push(libsql::Value::from(base_currency.to_string()));
+    }
+    params.extend(filter.params());
+
+    let conn = user_db.read().await;
+    let summary_query = format!(
+        "SELECT period, CASE WHEN rate IS NULL THEN currency ELSE {} END AS bucket_currency, rate IS NULL AS unconverted, SUM(amount * COALESCE(rate, 1.0)), TOTAL(CASE WHEN kind = 'income' THEN amount * COALESCE(rate, 1.0) END), TOTAL(CASE WHEN kind = 'expense' THEN amount * COALESCE(rate, 1.0) END), COUNT(*) FROM (SELECT {} AS period, currency, kind, amount, {} AS rate FROM {} AS records WHERE {}) GROUP BY period, bucket_currency, unconverted ORDER BY period ASC, unconverted ASC, bucket_currency ASC",
+        bucket_currency,
+        period,
+        rate,
+        filter.source(&conn).await?,
+        filter.clause()
+    );
+    let mut rows = conn
+        .query(&summary_query, libsql::params_from_iter(params))
+        .await
+        .map_err(|_| db_error_with_context("failed to summarize records"))?;
+
+    let mut buckets = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        buckets.push(SummaryBucket {
+            period: row.get(0).map_err(|_| db_error())?,
+            currency: row.get(1).map_err(|_| db_error())?,
+            unconverted: row.get::<i64>(2).map_err(|_| db_error())? != 0,
+            total: row.get(3).map_err(|_| db_error())?,
+            income_total: row.get(4).map_err(|_| db_error())?,
+            expense_total: row.get(5).map_err(|_| db_error())?,
+            count: row.get(6).map_err(|_| db_error())?,
+        });
+    }
+
+    Ok(buckets)
+}
+
+pub async fn get_summary(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetSummaryQuery>,
+) -> Result<(StatusCode, Json<Vec<SummaryBucket>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+
+    let (start_time, end_time) = resolve_time_window(query.start_time, query.end_time);
+    let excluded_ids = query
+        .exclude_category_ids
+        .as_deref()
+        .map(|ids| parse_category_ids(ids, "exclude_category_ids"))
+        .transpose()?
+        .unwrap_or_default();
+    let tz_offset_minutes = validate_tz_offset(query.tz_offset_minutes)?;
+
+    let group_by = query.group_by.as_deref().unwrap_or("month");
+    let buckets = if query.convert.unwrap_or(false) {
+        summarize_records_converted(
+            &user_db,
+            group_by,
+            start_time,
+            end_time,
+            &excluded_ids,
+            tz_offset_minutes,
+            &get_default_currency(&user_db).await?,
+        )
+        .await?
+    } else {
+        summarize_records(
+            &user_db,
+            group_by,
+            start_time,
+            end_time,
+            &excluded_ids,
+            tz_offset_minutes,
+        )
+        .await?
+    };
+
+    Ok((StatusCode::OK, Json(buckets)))
+}
+
+/// Resolves the time series window: `end_time` defaults to now and `start_time`
+/// to `TIMESERIES_DEFAULT_DAYS` before it. Windows longer than
+/// `MAX_TIMESERIES_DAYS` are rejected to keep the response bounded.
+pub fn resolve_timeseries_window(
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> Result<(i64, i64), (StatusCode, String)> {
+    let end_time = end_time.unwrap_or_else(|| time::OffsetDateTime::now_utc().unix_timestamp());
+    let start_time = start_time.unwrap_or(end_time - TIMESERIES_DEFAULT_DAYS * 24 * 60 * 60);
+
+    if start_time > end_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "start_time cannot be after end_time".to_string(),
+        ));
+    }
+    if end_time - start_time > MAX_TIMESERIES_DAYS * 24 * 60 * 60 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Time range cannot exceed {} days", MAX_TIMESERIES_DAYS),
+        ));
+    }
+    Ok((start_time, end_time))
+}
+
+/// Expense totals per local day at `tz_offset_minutes` east of UTC in `currency`,
+/// oldest first. With `fill`, every day touched by the window is listed, including
+/// days without records.
+pub async fn daily_timeseries(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+    currency: &str,
+    fill: bool,
+    tz_offset_minutes: i32,
+) -> Result<Vec<TimeseriesPoint>, (StatusCode, String)> {
+    let day = summary_period_expr("day", tz_offset_minutes)?;
+
+    let conn = user_db.read().await;
+    let source = records_source(&conn, start_time, end_time).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} AS day, SUM(amount), COUNT(*) FROM {} AS records WHERE timestamp BETWEEN ? AND ? AND kind = ? AND currency = ? GROUP BY day ORDER BY day ASC",
+                day, source
+            ),
+            (
+                start_time,
+                end_time,
+                RecordKind::Expense.as_str(),
+                currency,
+            ),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to build time series"))?;
+
+    let mut points = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        points.push(TimeseriesPoint {
+            date: row.get(0).map_err(|_| db_error())?,
+            total: row.get(1).map_err(|_| db_error())?,
+            count: row.get(2).map_err(|_| db_error())?,
+        });
+    }
+
+    if !fill {
+        return Ok(points);
+    }
+
+    let local_date = |timestamp: i64| {
+        time::OffsetDateTime::from_unix_timestamp(timestamp + i64::from(tz_offset_minutes) * 60)
+            .map(|datetime| datetime.date())
+            .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid time range".to_string()))
+    };
+    let last_date = local_date(end_time)?;
+    let mut date = local_date(start_time)?;
+    let mut points = points.into_iter().peekable();
+    let mut filled = Vec::new();
+    loop {
+        let label = date.to_string();
+        match points.next_if(|point| point.date == label) {
+            Some(point) => filled.push(point),
+            None => filled.push(TimeseriesPoint {
+                date: label,
+                total: 0.0,
+                count: 0,
+            }),
+        }
+        match date.next_day() {
+            Some(next) if next <= last_date => date = next,
+            _ => break,
+        }
+    }
+    Ok(filled)
+}
+
+pub async fn get_timeseries(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetTimeseriesQuery>,
+) -> Result<(StatusCode, Json<Vec<TimeseriesPoint>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let (start_time, end_time) = resolve_timeseries_window(query.start_time, query.end_time)?;
+    if let Some(ref currency) = query.currency {
+        validate_currency(currency)?;
+    }
+    let tz_offset_minutes = validate_tz_offset(query.tz_offset_minutes)?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let currency = match query.currency {
+        Some(currency) => currency,
+        None => get_default_currency(&user_db).await?,
+    };
+
+    let points = daily_timeseries(
+        &user_db,
+        start_time,
+        end_time,
+        &currency,
+        query.fill.unwrap_or(false),
+        tz_offset_minutes,
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(points)))
+}
+
+/// Totals the records in a time range per category and currency, largest absolute
+/// total first. Records without a category are grouped under
+/// `UNCATEGORIZED_CATEGORY_NAME`, and records pointing at a deleted category under
+/// `UNKNOWN_CATEGORY_NAME`. Split records count each portion under its own
+/// category. With `by_payment_method`, each category is further split by payment
+/// method. With `rollup`, subcategories are totalled under their top-level
+/// category. Portions filed under one of `excluded_category_ids` are left out.
+pub async fn summarize_by_category(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+    by_payment_method: bool,
+    rollup: bool,
+    excluded_category_ids: &[String],
+) -> Result<Vec<CategoryTotal>, (StatusCode, String)> {
+    // Without the split every group shares a NULL payment method
+    let payment_method = if by_payment_method {
+        "p.payment_method"
+    } else {
+        "NULL"
+    };
+    // Each category's top-level ancestor; categories caught in a parent loop have none
+    // and stay on their own
+    let (roots, category_key) = if rollup {
+        (
+            "RECURSIVE category_roots(id, root_id) AS (SELECT id, id FROM categories WHERE parent_id IS NULL UNION ALL SELECT categories.id, category_roots.root_id FROM categories JOIN category_roots ON categories.parent_id = category_roots.id), ",
+            "COALESCE((SELECT root_id FROM category_roots WHERE category_roots.id = p.category_id), p.category_id)",
+        )
+    } else {
+        ("", "p.category_id")
+    };
+
+    // ?1 and ?2 are the time range, excluded ids are numbered after them
+    let exclusion = if excluded_category_ids.is_empty() {
+        String::new()
+    } else {
+        let placeholders: Vec<String> = (0..excluded_category_ids.len())
+            .map(|i| format!("?{}", i + 3))
+            .collect();
+        format!(
+            " WHERE (p.category_id IS NULL OR p.category_id NOT IN ({}))",
+            placeholders.join(", ")
+        )
+    };
+    let mut params: Vec<libsql::Value> = vec![start_time.into(), end_time.into()];
+    params.extend(
+        excluded_category_ids
+            .iter()
+            .cloned()
+            .map(libsql::Value::from),
+    );
+
+    let conn = user_db.read().await;
+    let source = records_source(&conn, start_time, end_time).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "WITH {}portions AS (SELECT r.category_id, r.currency, r.payment_method, r.amount FROM {} r WHERE r.timestamp BETWEEN ?1 AND ?2 AND NOT EXISTS (SELECT 1 FROM record_splits s WHERE s.record_id = r.id) UNION ALL SELECT s.category_id, r.currency, r.payment_method, s.amount FROM record_splits s JOIN {} r ON r.id = s.record_id WHERE r.timestamp BETWEEN ?1 AND ?2) SELECT c.id, c.name, {} AS method, p.currency, SUM(p.amount) AS total, COUNT(*), p.category_id IS NULL AS uncategorized FROM portions p LEFT JOIN categories c ON c.id = {}{} GROUP BY c.id, uncategorized, method, p.currency ORDER BY ABS(total) DESC, c.name ASC, method ASC",
+                roots, source, source, payment_method, category_key, exclusion
+            ),
+            libsql::params_from_iter(params),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to summarize records by category"))?;
+
+    let mut totals = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let category_name: Option<String> = row.get(1).map_err(|_| db_error())?;
+        let uncategorized: bool = row.get(6).map_err(|_| db_error())?;
+        let fallback_name = if uncategorized {
+            UNCATEGORIZED_CATEGORY_NAME
+        } else {
+            UNKNOWN_CATEGORY_NAME
+        };
+        totals.push(CategoryTotal {
+            category_id: row.get(0).map_err(|_| db_error())?,
+            category_name: category_name.unwrap_or_else(|| fallback_name.to_string()),
+            payment_method: row.get(2).map_err(|_| db_error())?,
+            currency: row.get(3).map_err(|_| db_error())?,
+            total_amount: row.get(4).map_err(|_| db_error())?,
+            record_count: row.get(5).map_err(|_| db_error())?,
+        });
+    }
+
+    Ok(totals)
+}
+
+pub async fn get_category_summary(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetCategorySummaryQuery>,
+) -> Result<(StatusCode, Json<Vec<CategoryTotal>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+
+    let (start_time, end_time) = resolve_time_window(query.start_time, query.end_time);
+    let by_payment_method = match query.group_by.as_deref() {
+        None => false,
+        Some("payment_method") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("group_by must be 'payment_method', got '{}'", other),
+            ));
+        }
+    };
+
+    let excluded_ids = query
+        .exclude_category_ids
+        .as_deref()
+        .map(|ids| parse_category_ids(ids, "exclude_category_ids"))
+        .transpose()?
+        .unwrap_or_default();
+
+    let totals = summarize_by_category(
+        &user_db,
+        start_time,
+        end_time,
+        by_payment_method,
+        query.rollup.unwrap_or(false),
+        &excluded_ids,
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(totals)))
+}
+
+/// Totals and counts the records in a time range per tag and currency, largest
+/// absolute total first. A record with several tags counts fully toward each one, and
+/// untagged records are totalled in a bucket without a tag.
+pub async fn summarize_by_tag(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+) -> Result<Vec<TagTotal>, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let source = records_source(&conn, start_time, end_time).await?;
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT t.tag, r.currency, SUM(r.amount) AS total, COUNT(*) FROM {} r LEFT JOIN record_tags t ON t.record_id = r.id WHERE r.timestamp BETWEEN ? AND ? GROUP BY t.tag, r.currency ORDER BY ABS(total) DESC, t.tag ASC, r.currency ASC",
+                source
+            ),
+            (start_time, end_time),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to summarize records by tag"))?;
+
+    let mut totals = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        totals.push(TagTotal {
+            tag: row.get(0).map_err(|_| db_error())?,
+            currency: row.get(1).map_err(|_| db_error())?,
+            total_amount: row.get(2).map_err(|_| db_error())?,
+            record_count: row.get(3).map_err(|_| db_error())?,
+        });
+    }
+
+    Ok(totals)
+}
+
+pub async fn get_tag_summary(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetTagSummaryQuery>,
+) -> Result<(StatusCode, Json<Vec<TagTotal>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+
+    let (start_time, end_time) = resolve_time_window(query.start_time, query.end_time);
+    let totals = summarize_by_tag(&user_db, start_time, end_time).await?;
+
+    Ok((StatusCode::OK, Json(totals)))
+}
+
+fn stats_filter(
+    start_time: i64,
+    end_time: i64,
+    category_id: Option<&str>,
+    currency: Option<&str>,
+    kind: Option<RecordKind>,
+) -> RecordFilter {
+    let mut filter = RecordFilter::time_range(start_time, end_time);
+    if let Some(kind) = kind {
+        filter.with_kind(kind);
+    }
+    if let Some(category_id) = category_id {
+        filter.with_category(category_id.to_string());
+    }
+    if let Some(currency) = currency {
+        filter.with_currency(currency.to_string());
+    }
+    filter
+}
+
+/// Reads the single row of a stats query selecting count, sum, income total,
+/// expense total, average, min and max in that order.
+async fn read_record_stats(
+    conn: &libsql::Connection,
+    stats_query: &str,
+    params: Vec<libsql::Value>,
+) -> Result<RecordStats, (StatusCode, String)> {
+    let mut rows = conn
+        .query(stats_query, libsql::params_from_iter(params))
+        .await
+        .map_err(|_| db_error_with_context("failed to compute record stats"))?;
+
+    let row = rows
+        .next()
+        .await
+        .map_err(|_| db_error())?
+        .ok_or_else(db_error)?;
+
+    Ok(RecordStats {
+        count: row.get(0).map_err(|_| db_error())?,
+        sum: row.get(1).map_err(|_| db_error())?,
+        income_total: row.get(2).map_err(|_| db_error())?,
+        expense_total: row.get(3).map_err(|_| db_error())?,
+        average: row.get(4).map_err(|_| db_error())?,
+        min: row.get(5).map_err(|_| db_error())?,
+        max: row.get(6).map_err(|_| db_error())?,
+        currency: None,
+        unconverted: None,
+    })
+}
+
+/// Computes count, sum, average, min and max of the records in a time range,
+/// optionally restricted to one category, currency and/or kind. Income and expense
+/// totals are reported separately alongside the overall sum.
+pub async fn compute_record_stats(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+    category_id: Option<&str>,
+    currency: Option<&str>,
+    kind: Option<RecordKind>,
+) -> Result<RecordStats, (StatusCode, String)> {
+    let filter = stats_filter(start_time, end_time, category_id, currency, kind);
+
+    let conn = user_db.read().await;
+    // TOTAL() is 0.0 for an empty set, unlike SUM() which is NULL
+    let stats_query = format!(
+        "SELECT COUNT(*), TOTAL(amount), TOTAL(CASE WHEN kind = 'income' THEN amount END), TOTAL(CASE WHEN kind = 'expense' THEN amount END), AVG(amount), MIN(amount), MAX(amount) FROM {} AS records WHERE {}",
+        filter.source(&conn).await?,
+        filter.clause()
+    );
+
+    read_record_stats(&conn, &stats_query, filter.params()).await
+}
+
+/// Like [`compute_record_stats`], but each amount is converted into
+/// `base_currency` at the stored exchange rates before aggregating. Records in a
+/// currency without a rate are left out of the figures and totalled per currency
+/// under `unconverted` instead.
+pub async fn compute_converted_record_stats(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+    category_id: Option<&str>,
+    currency: Option<&str>,
+    kind: Option<RecordKind>,
+    base_currency: &str,
+) -> Result<RecordStats, (StatusCode, String)> {
+    let filter = stats_filter(start_time, end_time, category_id, currency, kind);
+    let conn = user_db.read().await;
+    let rated = format!(
+        "SELECT currency, kind, amount, {} AS rate FROM {} AS records WHERE {}",
+        CONVERSION_RATE_EXPR,
+        filter.source(&conn).await?,
+        filter.clause()
+    );
+    let mut params = vec![libsql::Value::from(base_currency.to_string())];
+    params.extend(filter.params());
+
+    let stats_query = format!(
+        "SELECT COUNT(*), TOTAL(amount * rate), TOTAL(CASE WHEN kind = 'income' THEN amount * rate END), TOTAL(CASE WHEN kind = 'expense' THEN amount * rate END), AVG(amount * rate), MIN(amount * rate), MAX(amount * rate) FROM ({}) WHERE rate IS NOT NULL",
+        rated
+    );
+    let mut stats = read_record_stats(&conn, &stats_query, params.clone()).await?;
+
+    let unconverted_query = format!(
+        "SELECT currency, COUNT(*), TOTAL(amount) FROM ({}) WHERE rate IS NULL GROUP BY currency ORDER BY currency ASC",
+        rated
+    );
+    let mut rows = conn
+        .query(&unconverted_query, libsql::params_from_iter(params))
+        .await
+        .map_err(|_| db_error_with_context("failed to compute record stats"))?;
+
+    let mut unconverted = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        unconverted.push(UnconvertedTotal {
+            currency: row.get(0).map_err(|_| db_error())?,
+            count: row.get(1).map_err(|_| db_error())?,
+            sum: row.get(2).map_err(|_| db_error())?,
+        });
+    }
+
+    stats.currency = Some(base_currency.to_string());
+    stats.unconverted = Some(unconverted);
+    Ok(stats)
+}
+
+async fn period_expense_total(
+    conn: &libsql::Connection,
+    start_time: i64,
+    end_time: i64,
+    category_id: Option<&str>,
+    currency: &str,
+) -> Result<PeriodTotal, (StatusCode, String)> {
+    let mut filter = RecordFilter::time_range(start_time, end_time);
+    filter.with_kind(RecordKind::Expense);
+    filter.with_currency(currency.to_string());
+    if let Some(category_id) = category_id {
+        filter.with_category(category_id.to_string());
+    }
+
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT TOTAL(amount), COUNT(*) FROM {} AS records WHERE {}",
+                filter.source(conn).await?,
+                filter.clause()
+            ),
+            libsql::params_from_iter(filter.params()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to total records"))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(|_| db_error())?
+        .ok_or_else(db_error)?;
+
+    Ok(PeriodTotal {
+        start_time,
+        end_time,
+        total: row.get(0).map_err(|_| db_error())?,
+        count: row.get(1).map_err(|_| db_error())?,
+    })
+}
+
+/// Compares expenses in `currency` over `[start_time, end_time]` with the window
+/// of the same length that ends right before `start_time`.
+pub async fn compare_periods(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+    category_id: Option<&str>,
+    currency: &str,
+) -> Result<PeriodComparison, (StatusCode, String)> {
+    // Both bounds are inclusive, so a window covers end - start + 1 seconds
+    let length = end_time - start_time + 1;
+
+    let conn = user_db.read().await;
+    let current = period_expense_total(&conn, start_time, end_time, category_id, currency).await?;
+    let previous = period_expense_total(
+        &conn,
+        start_time - length,
+        start_time - 1,
+        category_id,
+        currency,
+    )
+    .await?;
+
+    let difference = current.total - previous.total;
+    let percent_change = (previous.total != 0.0)
+        .then(|| (difference / previous.total.abs() * 10_000.0).round() / 100.0);
+
+    Ok(PeriodComparison {
+        currency: currency.to_string(),
+        current,
+        previous,
+        difference,
+        percent_change,
+    })
+}
+
+pub async fn get_comparison(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetComparisonQuery>,
+) -> Result<(StatusCode, Json<PeriodComparison>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let (start_time, end_time) = resolve_time_window(query.start_time, query.end_time);
+    if start_time > end_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "start_time cannot be after end_time".to_string(),
+        ));
+    }
+    if let Some(ref category_id) = query.category_id {
+        validate_category_id(category_id)?;
+    }
+    if let Some(ref currency) = query.currency {
+        validate_currency(currency)?;
+    }
+
+    let user_db = get_user_database(&user.id).await?;
+    let currency = match query.currency {
+        Some(currency) => currency,
+        None => get_default_currency(&user_db).await?,
+    };
+
+    let comparison = compare_periods(
+        &user_db,
+        start_time,
+        end_time,
+        query.category_id.as_deref(),
+        &currency,
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(comparison)))
+}
+
+/// Unix timestamp of January 1st, 00:00 local time of `year` at
+/// `tz_offset_minutes` east of UTC.
+fn year_start(year: i32, tz_offset_minutes: i32) -> Result<i64, (StatusCode, String)> {
+    time::Date::from_calendar_date(year, time::Month::January, 1)
+        .map(|date| {
+            date.midnight().assume_utc().unix_timestamp() - i64::from(tz_offset_minutes) * 60
+        })
+        .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid year {}", year)))
+}
+
+fn empty_pivot_row(category_id: Option<String>, category_name: String) -> PivotRow {
+    PivotRow {
+        category_id,
+        category_name,
+        months: vec![0.0; 12],
+        total: 0.0,
+    }
+}
+
+/// Totals the expenses in `currency` per category and local month of `year`. Split
+/// records count each portion under its own category, and records without a
+/// category or pointing at a deleted one are grouped under `UNKNOWN_CATEGORY_NAME`,
+/// listed last. With
+/// `include_empty`, every existing category gets a row even without expenses.
+pub async fn pivot_by_category_month(
+    user_db: &Db,
+    year: i32,
+    currency: &str,
+    include_empty: bool,
+    tz_offset_minutes: i32,
+) -> Result<PivotTable, (StatusCode, String)> {
+    let start_time = year_start(year, tz_offset_minutes)?;
+    let end_time = year_start(year + 1, tz_offset_minutes)? - 1;
+
+    let conn = user_db.read().await;
+    let mut rows: Vec<PivotRow> = Vec::new();
+
+    if include_empty {
+        let mut categories = conn
+            .query("SELECT id, name FROM categories ORDER BY name ASC", ())
+            .await
+            .map_err(|_| db_error_with_context("failed to query categories"))?;
+        while let Some(row) = categories.next().await.map_err(|_| db_error())? {
+            rows.push(empty_pivot_row(
+                Some(row.get(0).map_err(|_| db_error())?),
+                row.get(1).map_err(|_| db_error())?,
+            ));
+        }
+    }
+
+    let source = records_source(&conn, start_time, end_time).await?;
+    let mut totals = conn
+        .query(
+            &format!("WITH portions AS (SELECT r.category_id, r.timestamp, r.amount FROM {} r WHERE r.timestamp BETWEEN ?1 AND ?2 AND r.kind = 'expense' AND r.currency = ?3 AND NOT EXISTS (SELECT 1 FROM record_splits s WHERE s.record_id = r.id) UNION ALL SELECT s.category_id, r.timestamp, s.amount FROM record_splits s JOIN {} r ON r.id = s.record_id WHERE r.timestamp BETWEEN ?1 AND ?2 AND r.kind = 'expense' AND r.currency = ?3) SELECT c.id, c.name, CAST(strftime('%m', p.timestamp, {}) AS INTEGER) AS month, TOTAL(p.amount) FROM portions p LEFT JOIN categories c ON c.id = p.category_id GROUP BY c.id, month ORDER BY c.id IS NULL, c.name ASC, month ASC", source, source, local_time_modifiers(tz_offset_minutes)),
+            (start_time, end_time, currency),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to pivot records"))?;
+
+    while let Some(row) = totals.next().await.map_err(|_| db_error())? {
+        let category_id: Option<String> = row.get(0).map_err(|_| db_error())?;
+        let month: u32 = row.get(2).map_err(|_| db_error())?;
+        let total: f64 = row.get(3).map_err(|_| db_error())?;
+        let index = match rows.iter().position(|r| r.category_id == category_id) {
+            Some(index) => index,
+            None => {
+                let name: Option<String> = row.get(1).map_err(|_| db_error())?;
+                rows.push(empty_pivot_row(
+                    category_id,
+                    name.unwrap_or_else(|| UNKNOWN_CATEGORY_NAME.to_string()),
+                ));
+                rows.len() - 1
+            }
+        };
+        rows[index].months[month as usize - 1] = total;
+        rows[index].total += total;
+    }
+
+    Ok(PivotTable {
+        year,
+        currency: currency.to_string(),
+        rows,
+    })
+}
+
+pub async fn get_pivot(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<GetPivotQuery>,
+) -> Result<(StatusCode, Json<PivotTable>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    if let Some(ref currency) = query.currency {
+        validate_currency(currency)?;
+    }
+    let tz_offset_minutes = validate_tz_offset(query.tz_offset_minutes)?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let currency = match query.currency {
+        Some(currency) => currency,
+        None => get_default_currency(&user_db).await?,
+    };
+
+    let table = pivot_by_category_month(
+        &user_db,
+        query.year,
+        &currency,
+        query.include_empty.unwrap_or(false),
+        tz_offset_minutes,
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(table)))
 }
 
-pub async fn update_record(
+pub async fn get_stats(
     State(_main_db): State<Db>,
     session: Session,
-    Path(record_id): Path<String>,
-    Json(payload): Json<UpdateRecordPayload>,
-) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
-    // Get current user from session
+    Query(query): Query<GetStatsQuery>,
+) -> Result<(StatusCode, Json<RecordStats>), (StatusCode, String)> {
     let user = get_current_user(&session).await?;
 
-    // Validate that at least one field is being updated
-    if payload.name.is_none()
-        && payload.amount.is_none()
-        && payload.category_id.is_none()
-        && payload.timestamp.is_none()
-    {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "At least one field must be provided for update".to_string(),
-        ));
-    }
+    let user_db = get_user_database(&user.id).await?;
 
-    // Input validation for provided fields
-    if let Some(ref name) = payload.name {
-        validate_record_name(name)?;
+    let (start_time, end_time) = resolve_time_window(query.start_time, query.end_time);
+    if let Some(ref currency) = query.currency {
+        validate_currency(currency)?;
     }
+    let kind = query.kind.as_deref().map(parse_record_kind).transpose()?;
+    let stats = if query.convert.unwrap_or(false) {
+        compute_converted_record_stats(
+            &user_db,
+            start_time,
+            end_time,
+            query.category_id.as_deref(),
+            query.currency.as_deref(),
+            kind,
+            &get_default_currency(&user_db).await?,
+        )
+        .await?
+    } else {
+        compute_record_stats(
+            &user_db,
+            start_time,
+            end_time,
+            query.category_id.as_deref(),
+            query.currency.as_deref(),
+            kind,
+        )
+        .await?
+    };
 
-    if let Some(amount) = payload.amount {
-        validate_record_amount(amount)?;
-    }
+    Ok((StatusCode::OK, Json(stats)))
+}
 
-    if let Some(ref category_id) = payload.category_id {
-        validate_category_id(category_id)?;
+/// Reads the record version a client expects from `If-Match`, quoted like an
+/// entity tag or bare. `*` matches any version, as does a missing header.
+pub fn expected_version_from_headers(
+    headers: &HeaderMap,
+) -> Result<Option<i64>, (StatusCode, String)> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
     }
+    let version = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    version.parse().map(Some).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "If-Match must be a record version".to_string(),
+        )
+    })
+}
 
-    // Get user's database
-    let user_db = get_user_database(&user.id).await?;
+/// An `UpdateRecordPayload` that passed the checks needing no database, with its
+/// fields parsed and normalized.
+struct RecordUpdate {
+    name: Option<String>,
+    amount: Option<f64>,
+    category_id: Option<String>,
+    timestamp: Option<i64>,
+    currency: Option<String>,
+    kind: Option<RecordKind>,
+    /// `Some(None)` clears the payment method
+    payment_method: Option<Option<String>>,
+    tags: Option<Vec<String>>,
+    splits: Option<Vec<RecordSplit>>,
+    starred: Option<bool>,
+}
 
-    // Validate that the category exists if being updated
-    if let Some(ref category_id) = payload.category_id {
-        validate_category_exists(&user_db, category_id).await?;
+impl RecordUpdate {
+    fn validate(payload: UpdateRecordPayload, now: i64) -> Result<Self, (StatusCode, String)> {
+        // Validate that at least one field is being updated
+        if payload.name.is_none()
+            && payload.amount.is_none()
+            && payload.category_id.is_none()
+            && payload.timestamp.is_none()
+            && payload.currency.is_none()
+            && payload.kind.is_none()
+            && payload.payment_method.is_none()
+            && payload.tags.is_none()
+            && payload.splits.is_none()
+            && payload.starred.is_none()
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "At least one field must be provided for update".to_string(),
+            ));
+        }
+
+        // Input validation for provided fields
+        if let Some(ref name) = payload.name {
+            validate_record_name(name)?;
+        }
+
+        if let Some(amount) = payload.amount {
+            validate_record_amount(amount)?;
+        }
+
+        if let Some(timestamp) = payload.timestamp {
+            validate_record_timestamp(timestamp, now)?;
+        }
+
+        if let Some(ref category_id) = payload.category_id {
+            validate_category_id(category_id)?;
+        }
+
+        if let Some(ref currency) = payload.currency {
+            validate_currency(currency)?;
+        }
+
+        // An empty payment method clears it
+        let payment_method = match payload.payment_method.as_deref().map(str::trim) {
+            Some("") => Some(None),
+            Some(payment_method) => {
+                validate_payment_method(payment_method)?;
+                Some(Some(payment_method.to_string()))
+            }
+            None => None,
+        };
+
+        let kind = payload.kind.as_deref().map(parse_record_kind).transpose()?;
+        let tags = payload.tags.as_deref().map(normalize_tags).transpose()?;
+        let splits = payload
+            .splits
+            .as_deref()
+            .map(normalize_splits)
+            .transpose()?;
+
+        Ok(RecordUpdate {
+            name: payload.name,
+            amount: payload.amount,
+            category_id: payload.category_id,
+            timestamp: payload.timestamp,
+            currency: payload.currency,
+            kind,
+            payment_method,
+            tags,
+            splits,
+            starred: payload.starred,
+        })
     }
 
-    let conn = user_db.write().await;
+    /// Categories the update points the record or its splits at, which must exist.
+    /// Whether the update can move spending between budgets.
+    fn changes_spending(&self) -> bool {
+        self.amount.is_some() || self.category_id.is_some() || self.splits.is_some()
+    }
+
+    fn category_ids(&self) -> impl Iterator<Item = &str> {
+        self.category_id.as_deref().into_iter().chain(
+            self.splits
+                .iter()
+                .flatten()
+                .map(|split| split.category_id.as_str()),
+        )
+    }
+}
 
+/// Applies a validated update to a record through `conn`, which the caller has in
+/// an immediate transaction. With an `expected_version` that is no longer current nothing is
+/// written and the latest copy comes back with 409 instead.
+async fn apply_record_update(
+    conn: &libsql::Connection,
+    record_id: &str,
+    update: &RecordUpdate,
+    expected_version: Option<i64>,
+    now: i64,
+) -> Result<(StatusCode, Record), (StatusCode, String)> {
     // First, check if the record exists and belongs to the user
     let mut existing_rows = conn
         .query(
-            "SELECT id, name, amount, category_id, timestamp FROM records WHERE id = ?",
-            [record_id.as_str()],
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
+            [record_id],
         )
         .await
         .map_err(|_| db_error_with_context("failed to query existing record"))?;
@@ -246,31 +2739,91 @@ pub async fn update_record(
     } else {
         return Err((StatusCode::NOT_FOUND, "Record not found".to_string()));
     };
+    ensure_period_open(conn, existing_record.timestamp).await?;
+
+    // The immediate transaction holds the database's write lock, so the version
+    // cannot move before the update
+    if expected_version.is_some_and(|version| version != existing_record.version) {
+        return Ok((StatusCode::CONFLICT, existing_record));
+    }
 
     // Build the updated record with new values or keep existing ones
-    let updated_name = payload.name.as_deref().unwrap_or(&existing_record.name);
-    let updated_amount = payload.amount.unwrap_or(existing_record.amount);
-    let updated_category_id = payload
-        .category_id
-        .as_deref()
-        .unwrap_or(&existing_record.category_id);
-    let updated_timestamp = payload.timestamp.unwrap_or(existing_record.timestamp);
+    let mut updated_record = Record {
+        id: record_id.to_string(),
+        name: update
+            .name
+            .clone()
+            .unwrap_or_else(|| existing_record.name.clone()),
+        amount: update.amount.unwrap_or(existing_record.amount),
+        category_id: update
+            .category_id
+            .clone()
+            .or_else(|| existing_record.category_id.clone()),
+        timestamp: update.timestamp.unwrap_or(existing_record.timestamp),
+        currency: update
+            .currency
+            .clone()
+            .unwrap_or_else(|| existing_record.currency.clone()),
+        kind: update.kind.unwrap_or(existing_record.kind),
+        payment_method: update
+            .payment_method
+            .clone()
+            .unwrap_or_else(|| existing_record.payment_method.clone()),
+        tags: update
+            .tags
+            .clone()
+            .unwrap_or_else(|| existing_record.tags.clone()),
+        splits: update
+            .splits
+            .clone()
+            .unwrap_or_else(|| existing_record.splits.clone()),
+        created_at: existing_record.created_at,
+        updated_at: existing_record.updated_at,
+        version: existing_record.version,
+        starred: update.starred.unwrap_or(existing_record.starred),
+        balance: None,
+        category: None,
+        budget_warnings: Vec::new(),
+        matched_rule: None,
+    };
+    // Nor can a record be moved into the closed period
+    ensure_period_open(conn, updated_record.timestamp).await?;
+    // A new amount on a split record needs splits that still add up to it
+    validate_split_total(updated_record.amount, &updated_record.splits)?;
+    let changes = record_changes(&existing_record, Some(&updated_record));
+    if !changes.is_empty() {
+        updated_record.updated_at = now;
+        updated_record.version += 1;
+    }
 
-    // Update the record and verify it was actually modified
+    // Update the record, its tags and its history together, then verify it was actually modified
     let affected_rows = conn
         .execute(
-            "UPDATE records SET name = ?, amount = ?, category_id = ?, timestamp = ? WHERE id = ?",
+            "UPDATE records SET name = ?, amount = ?, category_id = ?, timestamp = ?, currency = ?, kind = ?, payment_method = ?, starred = ?, updated_at = ?, version = ? WHERE id = ?",
             (
-                updated_name,
-                updated_amount,
-                updated_category_id,
-                updated_timestamp,
-                record_id.as_str(),
+                updated_record.name.as_str(),
+                updated_record.amount,
+                updated_record.category_id.as_deref(),
+                updated_record.timestamp,
+                updated_record.currency.as_str(),
+                updated_record.kind.as_str(),
+                updated_record.payment_method.as_deref(),
+                updated_record.starred,
+                updated_record.updated_at,
+                updated_record.version,
+                updated_record.id.as_str(),
             ),
         )
         .await
         .map_err(|_| db_error_with_context("failed to update record"))?;
 
+    if changes.contains_key("tags") {
+        replace_record_tags(conn, &updated_record.id, &updated_record.tags).await?;
+    }
+    if changes.contains_key("splits") {
+        replace_record_splits(conn, &updated_record.id, &updated_record.splits).await?;
+    }
+
     // Verify the update actually modified a record
     if affected_rows == 0 {
         return Err((
@@ -279,15 +2832,307 @@ pub async fn update_record(
         ));
     }
 
-    let updated_record = Record {
-        id: record_id,
-        name: updated_name.to_string(),
-        amount: updated_amount,
-        category_id: updated_category_id.to_string(),
-        timestamp: updated_timestamp,
+    // An update that leaves every field as it was adds no history
+    if !changes.is_empty() {
+        append_record_history(
+            conn,
+            &updated_record.id,
+            RecordHistoryAction::Update,
+            &changes,
+            now,
+        )
+        .await?;
+    }
+
+    Ok((StatusCode::OK, updated_record))
+}
+
+/// Applies a partial update. With an `If-Match` version that is no longer current
+/// the record is left alone and the latest copy comes back with 409, so the
+/// client can merge and retry; without one the last write wins.
+pub async fn update_record(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(record_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateRecordPayload>,
+) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
+    // Get current user from session
+    let user = get_current_user(&session).await?;
+    let expected_version = expected_version_from_headers(&headers)?;
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let update = RecordUpdate::validate(payload, now)?;
+
+    // Get user's database
+    let user_db = get_user_database(&user.id).await?;
+
+    // Validate that the categories exist if being updated
+    for category_id in update.category_ids() {
+        validate_category_exists(&user_db, category_id).await?;
+    }
+    let default_currency = get_default_currency(&user_db).await?;
+
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to update record"))?;
+
+    let result = async {
+        let previous = find_record_by_id(&tx, &record_id).await?;
+        let (status, record) =
+            apply_record_update(&tx, &record_id, &update, expected_version, now).await?;
+        if status == StatusCode::OK {
+            enforce_budgets(&tx, &record, previous.as_ref(), &default_currency).await?;
+        }
+        Ok((status, record))
+    }
+    .await;
+
+    let (status, mut record) = match result {
+        Ok((status, record)) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to update record"))?;
+            (status, record)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err(err);
+        }
+    };
+    drop(conn);
+
+    // A version conflict wrote nothing, so there is nothing to warn about
+    if status == StatusCode::OK && update.changes_spending() {
+        record.budget_warnings = budget_warnings(&user_db, &record).await.unwrap_or_default();
+    }
+    Ok((status, Json(record)))
+}
+
+/// Validates and applies one entry of a batch update inside a savepoint, so a
+/// failing entry leaves no partial writes behind.
+async fn apply_batch_update_entry(
+    conn: &libsql::Connection,
+    entry: BatchUpdateEntry,
+    currency: &str,
+    now: i64,
+) -> Result<Record, (StatusCode, String)> {
+    let update = RecordUpdate::validate(entry.changes, now)?;
+    for category_id in update.category_ids() {
+        ensure_category_exists(conn, category_id).await?;
+    }
+
+    conn.execute("SAVEPOINT batch_entry", ())
+        .await
+        .map_err(|_| db_error_with_context("failed to update record"))?;
+    let result = async {
+        let previous = find_record_by_id(conn, &entry.id).await?;
+        let (_, record) = apply_record_update(conn, &entry.id, &update, None, now).await?;
+        enforce_budgets(conn, &record, previous.as_ref(), currency).await?;
+        Ok(record)
+    }
+    .await;
+    match result {
+        Ok(record) => {
+            conn.execute("RELEASE batch_entry", ())
+                .await
+                .map_err(|_| db_error_with_context("failed to update record"))?;
+            Ok(record)
+        }
+        Err(err) => {
+            let _ = conn.execute("ROLLBACK TO batch_entry", ()).await;
+            let _ = conn.execute("RELEASE batch_entry", ()).await;
+            Err(err)
+        }
+    }
+}
+
+/// Applies each entry like `update_record` would, in order and in one
+/// transaction. With `fail_fast` the first failing entry rolls the whole batch
+/// back and its error is returned, prefixed with its index; otherwise failing
+/// entries are skipped and reported alongside the updated records.
+pub async fn batch_update_records(
+    user_db: &Db,
+    entries: Vec<BatchUpdateEntry>,
+    fail_fast: bool,
+    now: i64,
+) -> Result<BatchUpdateResponse, (StatusCode, String)> {
+    // Read before the write lock is taken, for the budget checks under it
+    let default_currency = get_default_currency(user_db).await?;
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to update records"))?;
+
+    let mut results = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.into_iter().enumerate() {
+        let id = entry.id.clone();
+        match apply_batch_update_entry(&tx, entry, &default_currency, now).await {
+            Ok(record) => results.push(BatchUpdateResult {
+                index,
+                id,
+                status: StatusCode::OK.as_u16(),
+                record: Some(record),
+                error: None,
+            }),
+            Err((status, message)) if fail_fast => {
+                let _ = tx.rollback().await;
+                return Err((status, format!("Entry {}: {}", index, message)));
+            }
+            Err((status, message)) => results.push(BatchUpdateResult {
+                index,
+                id,
+                status: status.as_u16(),
+                record: None,
+                error: Some(message),
+            }),
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| db_error_with_context("failed to update records"))?;
+
+    let updated = results.iter().filter(|r| r.record.is_some()).count() as u32;
+    Ok(BatchUpdateResponse {
+        updated,
+        failed: results.len() as u32 - updated,
+        results,
+    })
+}
+
+/// PATCH /records: updates several records at once, see [`batch_update_records`].
+/// `fail_fast` defaults to true.
+pub async fn patch_records(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<BatchUpdateQuery>,
+    Json(entries): Json<Vec<BatchUpdateEntry>>,
+) -> Result<(StatusCode, Json<BatchUpdateResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    if entries.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "At least one record update must be provided".to_string(),
+        ));
+    }
+    if entries.len() > MAX_BATCH_UPDATE_SIZE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "A batch cannot update more than {} records",
+                MAX_BATCH_UPDATE_SIZE
+            ),
+        ));
+    }
+
+    let user_db = get_user_database(&user.id).await?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let response =
+        batch_update_records(&user_db, entries, query.fail_fast.unwrap_or(true), now).await?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Stars or unstars a record. Like any other change this bumps the version and is
+/// recorded in the history; setting the flag it already has changes nothing.
+pub async fn set_record_starred(
+    user_db: &Db,
+    record_id: &str,
+    starred: bool,
+) -> Result<Record, (StatusCode, String)> {
+    let conn = user_db.write().await;
+
+    let mut existing_rows = conn
+        .query(
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
+            [record_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query existing record"))?;
+    let existing_record = match existing_rows.next().await.map_err(|_| db_error())? {
+        Some(row) => extract_record_from_row(row)?,
+        None => return Err((StatusCode::NOT_FOUND, "Record not found".to_string())),
     };
+    if existing_record.starred == starred {
+        return Ok(existing_record);
+    }
+
+    let mut updated_record = existing_record.clone();
+    updated_record.starred = starred;
+    updated_record.updated_at = time::OffsetDateTime::now_utc().unix_timestamp();
+    updated_record.version += 1;
+    let changes = record_changes(&existing_record, Some(&updated_record));
+
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to update record"))?;
+    let result = async {
+        tx.execute(
+            "UPDATE records SET starred = ?, updated_at = ?, version = ? WHERE id = ?",
+            (
+                updated_record.starred,
+                updated_record.updated_at,
+                updated_record.version,
+                record_id,
+            ),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to update record"))?;
+        append_record_history(
+            &tx,
+            record_id,
+            RecordHistoryAction::Update,
+            &changes,
+            updated_record.updated_at,
+        )
+        .await
+    }
+    .await;
+
+    match result {
+        Ok(()) => tx
+            .commit()
+            .await
+            .map_err(|_| db_error_with_context("failed to update record"))?,
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err(err);
+        }
+    }
+
+    Ok(updated_record)
+}
+
+pub async fn star_record(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(record_id): Path<String>,
+) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let record = set_record_starred(&user_db, &record_id, true).await?;
+
+    Ok((StatusCode::OK, Json(record)))
+}
 
-    Ok((StatusCode::OK, Json(updated_record)))
+pub async fn unstar_record(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(record_id): Path<String>,
+) -> Result<(StatusCode, Json<Record>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let record = set_record_starred(&user_db, &record_id, false).await?;
+
+    Ok((StatusCode::OK, Json(record)))
 }
 
 pub async fn delete_record(
@@ -303,11 +3148,55 @@ pub async fn delete_record(
 
     let conn = user_db.write().await;
 
-    // Delete the record and verify it was actually deleted
-    let affected_rows = conn
-        .execute("DELETE FROM records WHERE id = ?", [record_id.as_str()])
+    // Delete the record together with its tags and splits, keeping a final snapshot in its history
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
         .await
         .map_err(|_| db_error_with_context("failed to delete record"))?;
+    let result = async {
+        let mut rows = tx
+            .query(
+                &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
+                [record_id.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query existing record"))?;
+        let Some(row) = rows.next().await.map_err(|_| db_error())? else {
+            return Ok(0);
+        };
+        let existing_record = extract_record_from_row(row)?;
+        ensure_period_open(&tx, existing_record.timestamp).await?;
+
+        let affected_rows = tx
+            .execute("DELETE FROM records WHERE id = ?", [record_id.as_str()])
+            .await
+            .map_err(|_| db_error_with_context("failed to delete record"))?;
+        replace_record_tags(&tx, &record_id, &[]).await?;
+        replace_record_splits(&tx, &record_id, &[]).await?;
+        append_record_history(
+            &tx,
+            &record_id,
+            RecordHistoryAction::Delete,
+            &record_changes(&existing_record, None),
+            time::OffsetDateTime::now_utc().unix_timestamp(),
+        )
+        .await?;
+        Ok(affected_rows)
+    }
+    .await;
+
+    let affected_rows = match result {
+        Ok(affected_rows) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to delete record"))?;
+            affected_rows
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            return Err(err);
+        }
+    };
 
     // Verify the delete actually removed a record
     if affected_rows == 0 {
@@ -316,3 +3205,337 @@ pub async fn delete_record(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Deletes every record in `[start_time, end_time]`, optionally only those in one
+/// category, together with their tags and splits. Unlike a single delete no history
+/// snapshot is kept. With `dry_run` the records are only counted.
+pub async fn purge_record_range(
+    user_db: &Db,
+    start_time: i64,
+    end_time: i64,
+    category_id: Option<&str>,
+    dry_run: bool,
+) -> Result<u32, (StatusCode, String)> {
+    let mut filter = RecordFilter::time_range(start_time, end_time);
+    if let Some(category_id) = category_id {
+        filter.with_category(category_id.to_string());
+    }
+
+    let conn = user_db.write().await;
+    // Checked for dry runs too, so a preview does not promise a purge that would fail
+    ensure_period_open(&conn, start_time).await?;
+    if dry_run {
+        let mut rows = conn
+            .query(
+                &format!("SELECT COUNT(*) FROM records WHERE {}", filter.clause()),
+                libsql::params_from_iter(filter.params()),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to count records"))?;
+        return match rows.next().await.map_err(|_| db_error())? {
+            Some(row) => row.get(0).map_err(|_| db_error()),
+            None => Ok(0),
+        };
+    }
+
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to purge records"))?;
+    let result = async {
+        for table in ["record_tags", "record_splits"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE record_id IN (SELECT id FROM records WHERE {})",
+                    table,
+                    filter.clause()
+                ),
+                libsql::params_from_iter(filter.params()),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to purge records"))?;
+        }
+        let deleted = tx
+            .execute(
+                &format!("DELETE FROM records WHERE {}", filter.clause()),
+                libsql::params_from_iter(filter.params()),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to purge records"))?;
+        if deleted > 0 {
+            advance_data_horizon(&tx).await?;
+        }
+        Ok(deleted)
+    }
+    .await;
+
+    match result {
+        Ok(deleted) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to purge records"))?;
+            Ok(deleted as u32)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+/// Deletes records dated before `cutoff` together with their tags, splits and
+/// history, for the retention purge. Archived years are purged like live records.
+/// Only records created before `created_before` qualify, so records entered while
+/// the purge runs are left alone. Returns how many were deleted.
+pub async fn purge_expired_records(
+    user_db: &Db,
+    cutoff: i64,
+    created_before: i64,
+) -> Result<u32, (StatusCode, String)> {
+    const EXPIRED: &str = "timestamp < ? AND created_at < ?";
+
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to purge expired records"))?;
+    let result = async {
+        let mut record_tables = vec!["records".to_string()];
+        record_tables.extend(
+            archived_years(&tx)
+                .await?
+                .into_iter()
+                .map(archive_table_name),
+        );
+
+        let mut deleted = 0;
+        for record_table in record_tables {
+            for table in ["record_tags", "record_splits", "record_history"] {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE record_id IN (SELECT id FROM {} WHERE {})",
+                        table, record_table, EXPIRED
+                    ),
+                    (cutoff, created_before),
+                )
+                .await
+                .map_err(|_| db_error_with_context("failed to purge expired records"))?;
+            }
+            deleted += tx
+                .execute(
+                    &format!("DELETE FROM {} WHERE {}", record_table, EXPIRED),
+                    (cutoff, created_before),
+                )
+                .await
+                .map_err(|_| db_error_with_context("failed to purge expired records"))?;
+        }
+        if deleted > 0 {
+            advance_data_horizon(&tx).await?;
+        }
+        Ok(deleted)
+    }
+    .await;
+
+    match result {
+        Ok(deleted) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to purge expired records"))?;
+            Ok(deleted as u32)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+/// DELETE /records. Needs both time bounds so a missing parameter cannot wipe
+/// everything, and only deletes with `confirm=true`; otherwise it is a dry run.
+pub async fn purge_records(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<PurgeRecordsQuery>,
+) -> Result<(StatusCode, Json<PurgeRecordsResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let (Some(start_time), Some(end_time)) = (query.start_time, query.end_time) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "start_time and end_time are required".to_string(),
+        ));
+    };
+    if start_time > end_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "start_time cannot be after end_time".to_string(),
+        ));
+    }
+    if let Some(ref category_id) = query.category_id {
+        validate_category_id(category_id)?;
+    }
+
+    let dry_run = !query.confirm.unwrap_or(false);
+    let user_db = get_user_database(&user.id).await?;
+    let count = purge_record_range(
+        &user_db,
+        start_time,
+        end_time,
+        query.category_id.as_deref(),
+        dry_run,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(PurgeRecordsResponse { count, dry_run }),
+    ))
+}
+
+/// Resolves the requested export format, defaulting to NDJSON. The statement
+/// formats of [`StatementFormat`] are accepted too.
+pub fn validate_export_format(format: Option<&str>) -> Result<&'static str, (StatusCode, String)> {
+    match format.unwrap_or("ndjson") {
+        "ndjson" => Ok("ndjson"),
+        "qif" => Ok("qif"),
+        "ofx" => Ok("ofx"),
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported export format: {}", other),
+        )),
+    }
+}
+
+fn export_error(context: &str) -> std::io::Error {
+    std::io::Error::other(format!("Database error: {}", context))
+}
+
+/// The next page of at most `limit` records from `source` dated up to `end_time`,
+/// resuming after the `(timestamp, id)` cursor. Every real id sorts after the
+/// empty string, so `(start_time, "")` starts at the beginning of a range.
+pub(crate) async fn load_record_page(
+    conn: &libsql::Connection,
+    source: &str,
+    end_time: i64,
+    cursor: &(i64, String),
+    limit: u32,
+) -> Result<Vec<Record>, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM {} AS records WHERE timestamp <= ? AND (timestamp > ? OR (timestamp = ? AND id > ?)) ORDER BY timestamp ASC, id ASC LIMIT ?",
+                RECORD_COLUMNS, source
+            ),
+            (end_time, cursor.0, cursor.0, cursor.1.as_str(), limit),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query records"))?;
+    let mut page = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        page.push(extract_record_from_row(row)?);
+    }
+    Ok(page)
+}
+
+/// Streams the records in a time range as NDJSON, one `Record` per line.
+///
+/// A background task reads keyset-paginated pages of `EXPORT_FLUSH_ROWS` records
+/// and hands each over as one chunk through a bounded channel, so at most a few
+/// chunks are ever held in memory. The user DB is only locked while a page is
+/// read, never while a slow client is being sent to. The task stops as soon as
+/// the receiving side is dropped, e.g. when the client disconnects mid-stream.
+pub fn stream_records_ndjson(
+    user_db: Db,
+    start_time: i64,
+    end_time: i64,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+    let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+
+    // The reader runs off the request task, so carry the request's amount format along
+    let amount_format = current_amount_format();
+    tokio::spawn(with_amount_format(amount_format, async move {
+        let source = match records_source(&*user_db.read().await, start_time, end_time).await {
+            Ok(source) => source.into_owned(),
+            Err(_) => {
+                let _ = tx.send(Err(export_error("failed to query archives"))).await;
+                return;
+            }
+        };
+
+        let mut cursor = (start_time, String::new());
+        loop {
+            let page = load_record_page(
+                &*user_db.read().await,
+                &source,
+                end_time,
+                &cursor,
+                EXPORT_FLUSH_ROWS as u32,
+            )
+            .await;
+            let page = match page {
+                Ok(page) => page,
+                Err(_) => {
+                    let _ = tx.send(Err(export_error("failed to read records"))).await;
+                    return;
+                }
+            };
+            let Some(last) = page.last() else {
+                break;
+            };
+            cursor = (last.timestamp, last.id.clone());
+
+            let mut buffer = Vec::new();
+            for record in &page {
+                if serde_json::to_writer(&mut buffer, record).is_err() {
+                    let _ = tx.send(Err(export_error("failed to encode record"))).await;
+                    return;
+                }
+                buffer.push(b'\n');
+            }
+            // A failed send means the client went away
+            if tx.send(Ok(Bytes::from(buffer))).await.is_err() {
+                return;
+            }
+        }
+    }));
+
+    futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    })
+}
+
+pub async fn export_records(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<ExportRecordsQuery>,
+) -> Result<(StatusCode, [(header::HeaderName, &'static str); 1], Body), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let format = validate_export_format(query.format.as_deref())?;
+
+    let user_db = get_user_database(&user.id).await?;
+
+    let (start_time, end_time) = resolve_time_window(query.start_time, query.end_time);
+
+    // Statement formats are small enough to render in one go
+    if let Some(format) = StatementFormat::parse(format) {
+        let entries = load_statement_entries(&user_db, start_time, end_time).await?;
+        let body = write_statement(
+            format,
+            &entries,
+            &get_default_currency(&user_db).await?,
+            time::OffsetDateTime::now_utc().unix_timestamp(),
+        );
+        return Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, format.content_type())],
+            Body::from(body),
+        ));
+    }
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(stream_records_ndjson(user_db, start_time, end_time)),
+    ))
+}
@@ -0,0 +1,619 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use std::time::Duration;
+use time::{Date, OffsetDateTime};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::auth::get_current_user;
+use crate::budgets::enforce_budgets;
+use crate::closing::ensure_period_open;
+use crate::constants::*;
+use crate::database::{Db, get_user_db};
+use crate::models::{
+    CreateRecurringRulePayload, Record, RecordKind, RecurringInterval, RecurringRule,
+    UpdateRecurringRulePayload,
+};
+use crate::records::{
+    insert_record, parse_record_kind, validate_category_id, validate_currency,
+    validate_record_amount, validate_record_name,
+};
+use crate::settings::get_default_currency;
+use crate::utils::{
+    db_error, db_error_with_context, ensure_category_active, get_database_path, get_user_database,
+    list_user_ids, validate_category_exists,
+};
+
+const RECURRING_RULE_COLUMNS: &str =
+    "id, name, amount, category_id, currency, kind, interval, start_time, next_run, active";
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub fn parse_recurring_interval(interval: &str) -> Result<RecurringInterval, (StatusCode, String)> {
+    RecurringInterval::parse(interval).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Unsupported interval: {}", interval),
+        )
+    })
+}
+
+pub fn validate_start_time(start_time: i64, now: i64) -> Result<(), (StatusCode, String)> {
+    let ten_years = 10 * 365 * SECONDS_PER_DAY;
+
+    if start_time < now - ten_years {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Start time cannot be more than 10 years in the past".to_string(),
+        ));
+    }
+
+    if start_time > now + ten_years {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Start time cannot be more than 10 years in the future".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Moves `date` forward by `months`, clamping the day to `day` or the end of the
+/// target month, whichever comes first (rent on the 31st is posted on Feb 28).
+fn add_months(date: Date, months: u32, day: u8) -> Option<Date> {
+    let month_index = date.month() as u32 - 1 + months;
+    let year = date.year() + (month_index / 12) as i32;
+    let month = time::Month::try_from((month_index % 12 + 1) as u8).ok()?;
+    Date::from_calendar_date(year, month, day.min(month.length(year))).ok()
+}
+
+/// The occurrence after `current` for a rule anchored at `start_time`. Monthly and
+/// yearly rules always return to the anchor's day of month, so a short month does
+/// not shift every later occurrence.
+pub fn next_occurrence(start_time: i64, current: i64, interval: RecurringInterval) -> Option<i64> {
+    let months = match interval {
+        RecurringInterval::Daily => return current.checked_add(SECONDS_PER_DAY),
+        RecurringInterval::Weekly => return current.checked_add(7 * SECONDS_PER_DAY),
+        RecurringInterval::Monthly => 1,
+        RecurringInterval::Yearly => 12,
+    };
+
+    let anchor = OffsetDateTime::from_unix_timestamp(start_time).ok()?;
+    let current = OffsetDateTime::from_unix_timestamp(current).ok()?;
+    let date = add_months(current.date(), months, anchor.day())?;
+    Some(date.with_time(anchor.time()).assume_utc().unix_timestamp())
+}
+
+/// The first occurrence after `now`, skipping any that were missed.
+fn first_occurrence_after(rule: &RecurringRule, now: i64) -> Option<i64> {
+    let mut next_run = rule.next_run;
+    while next_run <= now {
+        next_run = next_occurrence(rule.start_time, next_run, rule.interval)?;
+    }
+    Some(next_run)
+}
+
+fn extract_recurring_rule_from_row(
+    row: &libsql::Row,
+) -> Result<RecurringRule, (StatusCode, String)> {
+    let kind: String = row.get(5).map_err(|_| db_error())?;
+    let interval: String = row.get(6).map_err(|_| db_error())?;
+
+    Ok(RecurringRule {
+        id: row.get(0).map_err(|_| db_error())?,
+        name: row.get(1).map_err(|_| db_error())?,
+        amount: row.get(2).map_err(|_| db_error())?,
+        category_id: row.get(3).map_err(|_| db_error())?,
+        currency: row.get(4).map_err(|_| db_error())?,
+        kind: RecordKind::parse(&kind).ok_or_else(db_error)?,
+        interval: RecurringInterval::parse(&interval).ok_or_else(db_error)?,
+        start_time: row.get(7).map_err(|_| db_error())?,
+        next_run: row.get(8).map_err(|_| db_error())?,
+        active: row.get(9).map_err(|_| db_error())?,
+    })
+}
+
+async fn query_recurring_rules(
+    conn: &libsql::Connection,
+    filter: &str,
+    params: Vec<libsql::Value>,
+) -> Result<Vec<RecurringRule>, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM recurring_rules {}",
+                RECURRING_RULE_COLUMNS, filter
+            ),
+            libsql::params_from_iter(params),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query recurring rules"))?;
+
+    let mut rules = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        rules.push(extract_recurring_rule_from_row(&row)?);
+    }
+    Ok(rules)
+}
+
+pub async fn get_recurring_rule(
+    user_db: &Db,
+    rule_id: &str,
+) -> Result<Option<RecurringRule>, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let rules = query_recurring_rules(&conn, "WHERE id = ?", vec![rule_id.into()]).await?;
+    Ok(rules.into_iter().next())
+}
+
+/// What became of an occurrence [`post_occurrence`] was asked to post.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Occurrence {
+    Posted,
+    /// Claimed but refused, e.g. by an enforced budget; the rule moves past it
+    Skipped,
+    /// Another pass advanced the rule first
+    AlreadyTaken,
+}
+
+/// Posts one occurrence of `rule` and advances its `next_run` in a single
+/// transaction. The update only matches while `next_run` still holds the
+/// occurrence being posted, so a pass that raced with another one (or was
+/// retried after a crash) posts nothing instead of a duplicate. A record the
+/// checks of `create_record` would refuse is skipped rather than retried on
+/// every pass.
+async fn post_occurrence(
+    conn: &libsql::Connection,
+    rule: &RecurringRule,
+    next_run: i64,
+    currency: &str,
+    now: i64,
+) -> Result<Occurrence, (StatusCode, String)> {
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to post recurring record"))?;
+
+    let result = async {
+        let claimed = tx
+            .execute(
+                "UPDATE recurring_rules SET next_run = ? WHERE id = ? AND next_run = ? AND active = 1",
+                (next_run, rule.id.as_str(), rule.next_run),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to advance recurring rule"))?;
+
+        if claimed == 0 {
+            return Ok(Occurrence::AlreadyTaken);
+        }
+
+        let record = Record {
+            id: Uuid::new_v4().to_string(),
+            name: rule.name.clone(),
+            amount: rule.amount,
+            category_id: Some(rule.category_id.clone()),
+            timestamp: rule.next_run,
+            currency: rule.currency.clone(),
+            kind: rule.kind,
+            payment_method: None,
+            tags: Vec::new(),
+            splits: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            version: 1,
+            starred: false,
+            balance: None,
+            category: None,
+            budget_warnings: Vec::new(),
+            matched_rule: None,
+        };
+
+        tx.execute("SAVEPOINT occurrence", ())
+            .await
+            .map_err(|_| db_error_with_context("failed to post recurring record"))?;
+        let posted = async {
+            ensure_period_open(&tx, record.timestamp).await?;
+            ensure_category_active(&tx, &rule.category_id).await?;
+            insert_record(&tx, &record).await?;
+            enforce_budgets(&tx, &record, None, currency).await
+        }
+        .await;
+        match posted {
+            Ok(()) => {
+                tx.execute("RELEASE occurrence", ())
+                    .await
+                    .map_err(|_| db_error_with_context("failed to post recurring record"))?;
+                Ok(Occurrence::Posted)
+            }
+            Err((status, message)) if status.is_client_error() => {
+                let _ = tx.execute("ROLLBACK TO occurrence", ()).await;
+                let _ = tx.execute("RELEASE occurrence", ()).await;
+                eprintln!(
+                    "Skipped the occurrence of recurring rule {} at {}: {}",
+                    rule.id, rule.next_run, message
+                );
+                Ok(Occurrence::Skipped)
+            }
+            Err(err) => Err(err),
+        }
+    }
+    .await;
+
+    match result {
+        Ok(occurrence) => {
+            tx.commit()
+                .await
+                .map_err(|_| db_error_with_context("failed to post recurring record"))?;
+            Ok(occurrence)
+        }
+        Err(err) => {
+            let _ = tx.rollback().await;
+            Err(err)
+        }
+    }
+}
+
+/// Posts every occurrence of the user's active rules that is due at `now`, each
+/// timestamped with its scheduled time. Returns the number of records created.
+pub async fn materialize_due_rules(user_db: &Db, now: i64) -> Result<u32, (StatusCode, String)> {
+    // Read before the write lock is taken, for the budget checks under it
+    let default_currency = get_default_currency(user_db).await?;
+    let conn = user_db.write().await;
+    let due_rules = query_recurring_rules(
+        &conn,
+        "WHERE active = 1 AND next_run <= ? ORDER BY next_run ASC",
+        vec![now.into()],
+    )
+    .await?;
+
+    let mut posted = 0;
+    for mut rule in due_rules {
+        let mut catch_up = 0;
+        while rule.next_run <= now && catch_up < RECURRING_MAX_CATCH_UP {
+            let Some(next_run) = next_occurrence(rule.start_time, rule.next_run, rule.interval)
+            else {
+                break;
+            };
+
+            match post_occurrence(&conn, &rule, next_run, &default_currency, now).await? {
+                Occurrence::Posted => posted += 1,
+                Occurrence::Skipped => {}
+                Occurrence::AlreadyTaken => break,
+            }
+            rule.next_run = next_run;
+            catch_up += 1;
+        }
+    }
+
+    Ok(posted)
+}
+
+/// Runs one scheduler pass over every user's database. Returns the number of
+/// records created.
+pub async fn run_recurring_rules(
+    main_db: &Db,
+    data_path: &str,
+    now: i64,
+) -> Result<u32, (StatusCode, String)> {
+    let user_ids = list_user_ids(main_db).await?;
+
+    // A user whose database cannot be read must not hold up everyone after them
+    let mut posted = 0;
+    for user_id in user_ids {
+        let result = async {
+            let user_db = get_user_db(data_path, &user_id).await.map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ERR_DATABASE_ACCESS.to_string(),
+                )
+            })?;
+            materialize_due_rules(&user_db, now).await
+        }
+        .await;
+        match result {
+            Ok(count) => posted += count,
+            Err((_, message)) => {
+                eprintln!("Recurring rules of user {} failed: {}", user_id, message)
+            }
+        }
+    }
+
+    Ok(posted)
+}
+
+/// Starts the background scheduler that runs `run_recurring_rules` every
+/// `RECURRING_SCHEDULER_INTERVAL_SECS`.
+pub fn spawn_recurring_scheduler(main_db: Db) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(RECURRING_SCHEDULER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let now = OffsetDateTime::now_utc().unix_timestamp();
+            if let Err((_, message)) = run_recurring_rules(&main_db, get_database_path(), now).await
+            {
+                eprintln!("Recurring rules pass failed: {}", message);
+            }
+        }
+    });
+}
+
+/// Pauses or resumes a rule. Resuming skips occurrences missed while paused
+/// rather than backfilling them.
+pub async fn set_recurring_rule_active(
+    user_db: &Db,
+    rule_id: &str,
+    active: bool,
+    now: i64,
+) -> Result<Option<RecurringRule>, (StatusCode, String)> {
+    let Some(mut rule) = get_recurring_rule(user_db, rule_id).await? else {
+        return Ok(None);
+    };
+
+    if active && !rule.active {
+        rule.next_run = first_occurrence_after(&rule, now)
+            .ok_or_else(|| db_error_with_context("failed to schedule recurring rule"))?;
+    }
+    rule.active = active;
+
+    let conn = user_db.write().await;
+    conn.execute(
+        "UPDATE recurring_rules SET active = ?, next_run = ? WHERE id = ?",
+        (rule.active, rule.next_run, rule_id),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to update recurring rule"))?;
+
+    Ok(Some(rule))
+}
+
+fn recurring_rule_not_found() -> (StatusCode, String) {
+    (
+        StatusCode::NOT_FOUND,
+        "Recurring rule not found".to_string(),
+    )
+}
+
+pub async fn create_recurring_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<CreateRecurringRulePayload>,
+) -> Result<(StatusCode, Json<RecurringRule>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    validate_record_name(&payload.name)?;
+    validate_record_amount(payload.amount)?;
+    validate_category_id(&payload.category_id)?;
+    let interval = parse_recurring_interval(&payload.interval)?;
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    validate_start_time(payload.start_time, now)?;
+    if let Some(ref currency) = payload.currency {
+        validate_currency(currency)?;
+    }
+    let kind = payload
+        .kind
+        .as_deref()
+        .map(parse_record_kind)
+        .transpose()?
+        .unwrap_or_default();
+
+    let user_db = get_user_database(&user.id).await?;
+    validate_category_exists(&user_db, &payload.category_id).await?;
+
+    let currency = match payload.currency {
+        Some(currency) => currency,
+        None => get_default_currency(&user_db).await?,
+    };
+
+    let rule = RecurringRule {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name.trim().to_string(),
+        amount: payload.amount,
+        category_id: payload.category_id.trim().to_string(),
+        currency,
+        kind,
+        interval,
+        start_time: payload.start_time,
+        next_run: payload.start_time,
+        active: true,
+    };
+
+    let conn = user_db.write().await;
+    conn.execute(
+        &format!(
+            "INSERT INTO recurring_rules ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            RECURRING_RULE_COLUMNS
+        ),
+        (
+            rule.id.as_str(),
+            rule.name.as_str(),
+            rule.amount,
+            rule.category_id.as_str(),
+            rule.currency.as_str(),
+            rule.kind.as_str(),
+            rule.interval.as_str(),
+            rule.start_time,
+            rule.next_run,
+            rule.active,
+        ),
+    )
+    .await
+    .map_err(|_| db_error_with_context("recurring rule creation failed"))?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn list_recurring_rules(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<Vec<RecurringRule>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.read().await;
+    let rules = query_recurring_rules(&conn, "ORDER BY next_run ASC, id ASC", Vec::new()).await?;
+
+    Ok((StatusCode::OK, Json(rules)))
+}
+
+pub async fn get_recurring(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(rule_id): Path<String>,
+) -> Result<(StatusCode, Json<RecurringRule>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let rule = get_recurring_rule(&user_db, &rule_id)
+        .await?
+        .ok_or_else(recurring_rule_not_found)?;
+
+    Ok((StatusCode::OK, Json(rule)))
+}
+
+pub async fn update_recurring_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(rule_id): Path<String>,
+    Json(payload): Json<UpdateRecurringRulePayload>,
+) -> Result<(StatusCode, Json<RecurringRule>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    if payload.name.is_none()
+        && payload.amount.is_none()
+        && payload.category_id.is_none()
+        && payload.currency.is_none()
+        && payload.kind.is_none()
+        && payload.interval.is_none()
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "At least one field must be provided for update".to_string(),
+        ));
+    }
+
+    if let Some(ref name) = payload.name {
+        validate_record_name(name)?;
+    }
+    if let Some(amount) = payload.amount {
+        validate_record_amount(amount)?;
+    }
+    if let Some(ref category_id) = payload.category_id {
+        validate_category_id(category_id)?;
+    }
+    if let Some(ref currency) = payload.currency {
+        validate_currency(currency)?;
+    }
+    let kind = payload.kind.as_deref().map(parse_record_kind).transpose()?;
+    let interval = payload
+        .interval
+        .as_deref()
+        .map(parse_recurring_interval)
+        .transpose()?;
+
+    let user_db = get_user_database(&user.id).await?;
+    if let Some(ref category_id) = payload.category_id {
+        validate_category_exists(&user_db, category_id).await?;
+    }
+
+    let mut rule = get_recurring_rule(&user_db, &rule_id)
+        .await?
+        .ok_or_else(recurring_rule_not_found)?;
+
+    if let Some(name) = payload.name {
+        rule.name = name.trim().to_string();
+    }
+    if let Some(amount) = payload.amount {
+        rule.amount = amount;
+    }
+    if let Some(category_id) = payload.category_id {
+        rule.category_id = category_id.trim().to_string();
+    }
+    if let Some(currency) = payload.currency {
+        rule.currency = currency;
+    }
+    rule.kind = kind.unwrap_or(rule.kind);
+    rule.interval = interval.unwrap_or(rule.interval);
+
+    let conn = user_db.write().await;
+    let affected_rows = conn
+        .execute(
+            "UPDATE recurring_rules SET name = ?, amount = ?, category_id = ?, currency = ?, kind = ?, interval = ? WHERE id = ?",
+            (
+                rule.name.as_str(),
+                rule.amount,
+                rule.category_id.as_str(),
+                rule.currency.as_str(),
+                rule.kind.as_str(),
+                rule.interval.as_str(),
+                rule_id.as_str(),
+            ),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to update recurring rule"))?;
+
+    if affected_rows == 0 {
+        return Err(recurring_rule_not_found());
+    }
+
+    Ok((StatusCode::OK, Json(rule)))
+}
+
+pub async fn delete_recurring_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(rule_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let conn = user_db.write().await;
+    let affected_rows = conn
+        .execute(
+            "DELETE FROM recurring_rules WHERE id = ?",
+            [rule_id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to delete recurring rule"))?;
+
+    if affected_rows == 0 {
+        return Err(recurring_rule_not_found());
+    }
+
+    // Records already posted by the rule are kept
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn pause_recurring_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(rule_id): Path<String>,
+) -> Result<(StatusCode, Json<RecurringRule>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let rule = set_recurring_rule_active(&user_db, &rule_id, false, now)
+        .await?
+        .ok_or_else(recurring_rule_not_found)?;
+
+    Ok((StatusCode::OK, Json(rule)))
+}
+
+pub async fn resume_recurring_rule(
+    State(_main_db): State<Db>,
+    session: Session,
+    Path(rule_id): Path<String>,
+) -> Result<(StatusCode, Json<RecurringRule>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let rule = set_recurring_rule_active(&user_db, &rule_id, true, now)
+        .await?
+        .ok_or_else(recurring_rule_not_found)?;
+
+    Ok((StatusCode::OK, Json(rule)))
+}
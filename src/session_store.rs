@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use time::OffsetDateTime;
+use tower_sessions::{
+    SessionStore,
+    session::{Id, Record},
+    session_store,
+};
+
+use crate::database::Db;
+use crate::utils::db_error_with_context;
+
+/// Session store keeping sessions in the `sessions` table of users.db, so logins
+/// survive a restart and every process sharing the data directory sees them.
+#[derive(Clone)]
+pub struct SqliteStore {
+    db: Db,
+}
+
+impl std::fmt::Debug for SqliteStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteStore").finish_non_exhaustive()
+    }
+}
+
+impl SqliteStore {
+    pub fn new(db: Db) -> Self {
+        SqliteStore { db }
+    }
+}
+
+fn backend_error(error: libsql::Error) -> session_store::Error {
+    session_store::Error::Backend(error.to_string())
+}
+
+/// The record is stored whole as JSON; the expiry is kept beside it in seconds so
+/// expired rows can be purged in SQL.
+async fn upsert_session(conn: &libsql::Connection, record: &Record) -> session_store::Result<()> {
+    let data =
+        serde_json::to_string(record).map_err(|e| session_store::Error::Encode(e.to_string()))?;
+    conn.execute(
+        "INSERT INTO sessions (id, data, expires_at) VALUES (?, ?, ?) ON CONFLICT(id) DO UPDATE SET data = excluded.data, expires_at = excluded.expires_at",
+        (
+            record.id.to_string(),
+            data,
+            record.expiry_date.unix_timestamp(),
+        ),
+    )
+    .await
+    .map_err(backend_error)?;
+    Ok(())
+}
+
+#[async_trait]
+impl SessionStore for SqliteStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        let conn = self.db.write().await;
+        // Session ID collision mitigation, checked under the write lock
+        loop {
+            let mut rows = conn
+                .query(
+                    "SELECT 1 FROM sessions WHERE id = ?",
+                    [record.id.to_string()],
+                )
+                .await
+                .map_err(backend_error)?;
+            if rows.next().await.map_err(backend_error)?.is_none() {
+                break;
+            }
+            record.id = Id::default();
+        }
+        upsert_session(&conn, record).await
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        let conn = self.db.write().await;
+        upsert_session(&conn, record).await
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        let conn = self.db.read().await;
+        let mut rows = conn
+            .query(
+                "SELECT data FROM sessions WHERE id = ?",
+                [session_id.to_string()],
+            )
+            .await
+            .map_err(backend_error)?;
+        let Some(row) = rows.next().await.map_err(backend_error)? else {
+            return Ok(None);
+        };
+        let data: String = row.get(0).map_err(backend_error)?;
+        let record: Record =
+            serde_json::from_str(&data).map_err(|e| session_store::Error::Decode(e.to_string()))?;
+        // Expired rows linger until the maintenance task purges them
+        Ok((record.expiry_date > OffsetDateTime::now_utc()).then_some(record))
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        let conn = self.db.write().await;
+        conn.execute(
+            "DELETE FROM sessions WHERE id = ?",
+            [session_id.to_string()],
+        )
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+/// Deletes sessions that expired before `now`. Returns how many were removed.
+pub async fn purge_expired_sessions(db: &Db, now: i64) -> Result<u32, (StatusCode, String)> {
+    let conn = db.write().await;
+    let purged = conn
+        .execute("DELETE FROM sessions WHERE expires_at < ?", [now])
+        .await
+        .map_err(|_| db_error_with_context("failed to purge expired sessions"))?;
+    Ok(purged as u32)
+}
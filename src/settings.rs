@@ -0,0 +1,202 @@
+use axum::{Json, extract::State, http::StatusCode};
+use tower_sessions::Session;
+
+use crate::auth::get_current_user;
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::{
+    ExchangeRate, ExchangeRatesResponse, UpdateExchangeRatesPayload, UpdatePreferencesPayload,
+    UserPreferences,
+};
+use crate::records::validate_currency;
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+/// Reads a per-user setting from the `settings` key-value table.
+pub async fn get_setting(user_db: &Db, key: &str) -> Result<Option<String>, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query("SELECT value FROM settings WHERE key = ?", [key])
+        .await
+        .map_err(|_| db_error_with_context("failed to query settings"))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => Ok(Some(row.get(0).map_err(|_| db_error())?)),
+        None => Ok(None),
+    }
+}
+
+/// Inserts or replaces a per-user setting.
+pub async fn set_setting(user_db: &Db, key: &str, value: &str) -> Result<(), (StatusCode, String)> {
+    let conn = user_db.write().await;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        (key, value),
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to save setting"))?;
+
+    Ok(())
+}
+
+/// Removes a per-user setting, if set.
+pub async fn delete_setting(user_db: &Db, key: &str) -> Result<(), (StatusCode, String)> {
+    let conn = user_db.write().await;
+    conn.execute("DELETE FROM settings WHERE key = ?", [key])
+        .await
+        .map_err(|_| db_error_with_context("failed to delete setting"))?;
+
+    Ok(())
+}
+
+/// The currency new records get when none is given.
+pub async fn get_default_currency(user_db: &Db) -> Result<String, (StatusCode, String)> {
+    Ok(get_setting(user_db, SETTING_DEFAULT_CURRENCY)
+        .await?
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string()))
+}
+
+pub async fn get_preferences(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<UserPreferences>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let preferences = UserPreferences {
+        default_currency: get_default_currency(&user_db).await?,
+    };
+
+    Ok((StatusCode::OK, Json(preferences)))
+}
+
+pub async fn update_preferences(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<UpdatePreferencesPayload>,
+) -> Result<(StatusCode, Json<UserPreferences>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    if let Some(ref currency) = payload.default_currency {
+        validate_currency(currency)?;
+    }
+
+    let user_db = get_user_database(&user.id).await?;
+    if let Some(ref currency) = payload.default_currency {
+        set_setting(&user_db, SETTING_DEFAULT_CURRENCY, currency).await?;
+    }
+
+    let preferences = UserPreferences {
+        default_currency: get_default_currency(&user_db).await?,
+    };
+
+    Ok((StatusCode::OK, Json(preferences)))
+}
+
+/// Stored exchange rates into the default currency, ordered by currency code.
+pub async fn list_exchange_rates(user_db: &Db) -> Result<Vec<ExchangeRate>, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT currency, rate, updated_at FROM exchange_rates ORDER BY currency ASC",
+            (),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query exchange rates"))?;
+
+    let mut rates = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        rates.push(ExchangeRate {
+            currency: row.get(0).map_err(|_| db_error())?,
+            rate: row.get(1).map_err(|_| db_error())?,
+            updated_at: row.get(2).map_err(|_| db_error())?,
+        });
+    }
+
+    Ok(rates)
+}
+
+/// Inserts or replaces exchange rates, all stamped with `updated_at`.
+pub async fn upsert_exchange_rates(
+    user_db: &Db,
+    rates: &[(String, f64)],
+    updated_at: i64,
+) -> Result<(), (StatusCode, String)> {
+    let conn = user_db.write().await;
+    let tx = conn
+        .transaction_with_behavior(libsql::TransactionBehavior::Immediate)
+        .await
+        .map_err(|_| db_error_with_context("failed to start transaction"))?;
+
+    for (currency, rate) in rates {
+        tx.execute(
+            "INSERT INTO exchange_rates (currency, rate, updated_at) VALUES (?, ?, ?) ON CONFLICT(currency) DO UPDATE SET rate = excluded.rate, updated_at = excluded.updated_at",
+            (currency.as_str(), *rate, updated_at),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to save exchange rate"))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| db_error_with_context("failed to commit transaction"))?;
+
+    Ok(())
+}
+
+pub fn validate_exchange_rate(rate: f64) -> Result<(), (StatusCode, String)> {
+    if !rate.is_finite() || rate <= 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Exchange rate must be a positive number".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn get_exchange_rates(
+    State(_main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<ExchangeRatesResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let response = ExchangeRatesResponse {
+        base_currency: get_default_currency(&user_db).await?,
+        rates: list_exchange_rates(&user_db).await?,
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+pub async fn update_exchange_rates(
+    State(_main_db): State<Db>,
+    session: Session,
+    Json(payload): Json<UpdateExchangeRatesPayload>,
+) -> Result<(StatusCode, Json<ExchangeRatesResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    for (currency, rate) in &payload.rates {
+        validate_currency(currency)?;
+        validate_exchange_rate(*rate)?;
+    }
+
+    let user_db = get_user_database(&user.id).await?;
+    let base_currency = get_default_currency(&user_db).await?;
+    if payload.rates.contains_key(&base_currency) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Cannot set an exchange rate for the default currency".to_string(),
+        ));
+    }
+
+    let rates: Vec<(String, f64)> = payload.rates.into_iter().collect();
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    upsert_exchange_rates(&user_db, &rates, now).await?;
+
+    let response = ExchangeRatesResponse {
+        base_currency,
+        rates: list_exchange_rates(&user_db).await?,
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
@@ -0,0 +1,306 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+
+use crate::archive::records_source;
+use crate::auth::get_current_user;
+use crate::categories::{CATEGORY_COLUMN_COUNT, CATEGORY_COLUMNS, extract_category_from_row};
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::{RecordChangesQuery, RecordChangesResponse, SyncQuery, SyncResponse};
+use crate::records::{RECORD_COLUMNS, extract_record_from_row};
+use crate::utils::{db_error, db_error_with_context, get_user_database};
+
+/// Entities are synced in this order. Both are walked by `(updated_at, id)` so an
+/// item written while a sync is under way lands after the cursor instead of
+/// behind it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncEntity {
+    Categories,
+    Records,
+}
+
+/// Resume point carried between chunks. Clients treat the encoded form as opaque.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SyncToken {
+    pub entity: SyncEntity,
+    pub last_id: String,
+    /// `updated_at` of the last item handed out
+    #[serde(default)]
+    pub last_updated_at: i64,
+    pub server_time: i64,
+    /// The account's data horizon when the sync started; a sync cannot resume
+    /// once archiving, a purge or a replacing import has moved it on
+    #[serde(default)]
+    pub data_horizon: i64,
+}
+
+/// The account's data horizon: a counter bumped by every operation that removes
+/// data without leaving deletions behind for a sync in progress to notice.
+async fn data_horizon(conn: &libsql::Connection) -> Result<i64, (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            "SELECT CAST(value AS INTEGER) FROM settings WHERE key = ?",
+            [SETTING_DATA_HORIZON],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query settings"))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        Some(row) => row.get(0).map_err(|_| db_error()),
+        None => Ok(0),
+    }
+}
+
+/// Moves the data horizon on, so syncs started before now have to restart. Runs
+/// on the transaction removing the data.
+pub async fn advance_data_horizon(conn: &libsql::Connection) -> Result<(), (StatusCode, String)> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?, '1') ON CONFLICT(key) DO UPDATE SET value = CAST(value AS INTEGER) + 1",
+        [SETTING_DATA_HORIZON],
+    )
+    .await
+    .map_err(|_| db_error_with_context("failed to save setting"))?;
+
+    Ok(())
+}
+
+pub fn encode_sync_token(token: &SyncToken) -> String {
+    // Serializing a plain struct of strings and integers cannot fail
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(token).unwrap_or_default())
+}
+
+pub fn decode_sync_token(encoded: &str, now: i64) -> Result<SyncToken, (StatusCode, String)> {
+    let invalid = || {
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid continuation token".to_string(),
+        )
+    };
+
+    let bytes = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| invalid())?;
+    let token: SyncToken = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+    if token.server_time > now {
+        return Err(invalid());
+    }
+
+    // The snapshot the token belongs to is too old to resume safely
+    if now - token.server_time > SYNC_TOKEN_TTL_SECS {
+        return Err((
+            StatusCode::GONE,
+            "Continuation token expired, restart sync".to_string(),
+        ));
+    }
+
+    Ok(token)
+}
+
+pub fn validate_sync_chunk_size(limit: Option<u32>) -> Result<u32, (StatusCode, String)> {
+    match limit {
+        Some(0) => Err((
+            StatusCode::BAD_REQUEST,
+            "Limit must be greater than 0".to_string(),
+        )),
+        Some(l) if l > MAX_SYNC_CHUNK_SIZE => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Limit cannot exceed {}", MAX_SYNC_CHUNK_SIZE),
+        )),
+        Some(l) => Ok(l),
+        None => Ok(DEFAULT_SYNC_CHUNK_SIZE),
+    }
+}
+
+/// Builds the next sync chunk of at most `limit` items, starting after `token`
+/// (or from the beginning when no token is given). A token from before the data
+/// horizon last moved is refused with 410, as the data it walked may be gone.
+pub async fn build_sync_chunk(
+    user_db: &Db,
+    token: Option<SyncToken>,
+    limit: u32,
+    now: i64,
+) -> Result<SyncResponse, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let horizon = data_horizon(&conn).await?;
+
+    let mut cursor = match token {
+        Some(token) if token.data_horizon != horizon => {
+            return Err((
+                StatusCode::GONE,
+                "Data was archived or purged since the sync started, restart sync".to_string(),
+            ));
+        }
+        Some(token) => token,
+        None => SyncToken {
+            entity: SyncEntity::Categories,
+            last_id: String::new(),
+            last_updated_at: 0,
+            server_time: now,
+            data_horizon: horizon,
+        },
+    };
+    let mut remaining = limit;
+    let mut categories = Vec::new();
+    let mut records = Vec::new();
+
+    if cursor.entity == SyncEntity::Categories {
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {}, updated_at FROM categories WHERE (updated_at, id) > (?, ?) ORDER BY updated_at ASC, id ASC LIMIT ?",
+                    CATEGORY_COLUMNS
+                ),
+                (cursor.last_updated_at, cursor.last_id.as_str(), remaining),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query categories"))?;
+
+        let mut last_updated_at = 0;
+        while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            last_updated_at = row
+                .get(CATEGORY_COLUMN_COUNT)
+                .map_err(|_| db_error_with_context("invalid category data"))?;
+            categories.push(extract_category_from_row(row)?);
+        }
+        remaining -= categories.len() as u32;
+
+        match categories.last() {
+            Some(last) if remaining == 0 => {
+                cursor.last_updated_at = last_updated_at;
+                cursor.last_id = last.id.clone();
+            }
+            _ => {
+                // Categories exhausted, carry on with records
+                cursor.entity = SyncEntity::Records;
+                cursor.last_updated_at = 0;
+                cursor.last_id = String::new();
+            }
+        }
+    }
+
+    let mut complete = false;
+    if cursor.entity == SyncEntity::Records && remaining > 0 {
+        // Archived years are part of the account's data and sync like live records
+        let source = records_source(&conn, i64::MIN, i64::MAX).await?;
+        let mut rows = conn
+            .query(
+                &format!(
+                    "SELECT {} FROM {} AS records WHERE (updated_at, id) > (?, ?) ORDER BY updated_at ASC, id ASC LIMIT ?",
+                    RECORD_COLUMNS, source
+                ),
+                (cursor.last_updated_at, cursor.last_id.as_str(), remaining),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query records"))?;
+
+        while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+            records.push(extract_record_from_row(row)?);
+        }
+
+        match records.last() {
+            Some(last) if records.len() as u32 == remaining => {
+                cursor.last_updated_at = last.updated_at;
+                cursor.last_id = last.id.clone();
+            }
+            _ => complete = true,
+        }
+    }
+
+    Ok(SyncResponse {
+        categories,
+        records,
+        continuation: (!complete).then(|| encode_sync_token(&cursor)),
+        complete,
+        // Only the final chunk hands out the watermark
+        server_time: complete.then_some(cursor.server_time),
+    })
+}
+
+/// Records created or updated and ids of records deleted at or after `since`.
+/// A record deleted and re-created under the same id counts as changed only.
+pub async fn collect_record_changes(
+    user_db: &Db,
+    since: i64,
+    now: i64,
+) -> Result<RecordChangesResponse, (StatusCode, String)> {
+    let conn = user_db.read().await;
+    let source = records_source(&conn, i64::MIN, i64::MAX).await?;
+
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT {} FROM {} AS records WHERE updated_at >= ? ORDER BY updated_at ASC, id ASC",
+                RECORD_COLUMNS, source
+            ),
+            [since],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query changed records"))?;
+    let mut records = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        records.push(extract_record_from_row(row)?);
+    }
+
+    let mut rows = conn
+        .query(
+            &format!(
+                "SELECT record_id FROM record_deletions WHERE deleted_at >= ? AND record_id NOT IN (SELECT id FROM {} AS records) ORDER BY deleted_at ASC, record_id ASC",
+                source
+            ),
+            [since],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query deleted records"))?;
+    let mut deleted_ids = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        deleted_ids.push(row.get(0).map_err(|_| db_error())?);
+    }
+
+    Ok(RecordChangesResponse {
+        records,
+        deleted_ids,
+        server_time: now,
+    })
+}
+
+/// GET /records/changes: the delta since the `server_time` of the previous call.
+pub async fn get_record_changes(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<RecordChangesQuery>,
+) -> Result<(StatusCode, Json<RecordChangesResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let user_db = get_user_database(&user.id).await?;
+    let changes = collect_record_changes(&user_db, query.since, now).await?;
+
+    Ok((StatusCode::OK, Json(changes)))
+}
+
+pub async fn sync(
+    State(_main_db): State<Db>,
+    session: Session,
+    Query(query): Query<SyncQuery>,
+) -> Result<(StatusCode, Json<SyncResponse>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let limit = validate_sync_chunk_size(query.limit)?;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let token = query
+        .continuation
+        .as_deref()
+        .map(|encoded| decode_sync_token(encoded, now))
+        .transpose()?;
+
+    let user_db = get_user_database(&user.id).await?;
+    let chunk = build_sync_chunk(&user_db, token, limit, now).await?;
+
+    Ok((StatusCode::OK, Json(chunk)))
+}
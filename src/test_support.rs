@@ -0,0 +1,320 @@
+//! HTTP-level test harness, enabled with the `test-utils` feature.
+//!
+//! `TestApp` drives the real router (handlers, session auth and routing) through
+//! `tower::ServiceExt::oneshot`, carrying the session cookie between requests the
+//! way a browser would.
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    http::{HeaderMap, Method, Request, StatusCode, header},
+};
+use http_body_util::BodyExt;
+use serde::{Serialize, de::DeserializeOwned};
+use std::sync::{Arc, Mutex, OnceLock};
+use tempfile::{TempDir, tempdir};
+use tower::ServiceExt;
+use tower_sessions::{
+    MemoryStore, SessionManagerLayer, SessionStore, cookie::Key, service::SignedCookie,
+};
+
+use crate::app::build_app;
+use crate::constants::*;
+use crate::database::{Db, init_main_db};
+use crate::models::PublicUser;
+use crate::password_reset::ResetTokenSender;
+use crate::rate_limit::RateLimit;
+use crate::session_store::SqliteStore;
+use crate::utils::{get_database_path, set_database_path};
+
+pub const TEST_USERNAME: &str = "test_user";
+pub const TEST_PASSWORD: &str = "test-password-123";
+
+/// Per-user databases live in one directory per test process; user ids are
+/// UUIDs, so every `TestApp` user still gets an isolated database file.
+fn shared_data_path() -> &'static str {
+    static DATA_DIR: OnceLock<TempDir> = OnceLock::new();
+    let dir = DATA_DIR.get_or_init(|| tempdir().expect("Failed to create test data directory"));
+    set_database_path(
+        dir.path()
+            .to_str()
+            .expect("Failed to convert path to string"),
+    );
+    get_database_path()
+}
+
+fn fresh_temp_dir() -> TempDir {
+    tempdir().expect("Failed to create temporary directory")
+}
+
+fn session_layer<S: SessionStore + Clone>(store: S) -> SessionManagerLayer<S, SignedCookie> {
+    SessionManagerLayer::new(store)
+        .with_secure(false)
+        .with_name(SESSION_NAME)
+        .with_signed(Key::from(&[42u8; 64]))
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!(
+                "Failed to decode response body as JSON ({}): {}",
+                e,
+                self.text()
+            )
+        })
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+/// Keeps password reset tokens instead of delivering them, for tests to use.
+#[derive(Default)]
+pub struct RecordingResetSender {
+    sent: Mutex<Vec<(String, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ResetTokenSender for RecordingResetSender {
+    async fn send(&self, email: &str, token: &str) -> anyhow::Result<()> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((email.to_string(), token.to_string()));
+        Ok(())
+    }
+}
+
+pub struct TestApp {
+    router: Router,
+    main_db: Db,
+    cookie: Mutex<Option<String>>,
+    auth_rate_limit: RateLimit,
+    sqlite_sessions: bool,
+    temp_dir: Arc<TempDir>,
+    reset_sender: Arc<RecordingResetSender>,
+}
+
+impl TestApp {
+    /// Builds the full router against a fresh users database and an in-memory
+    /// session store owned by this app.
+    pub async fn new() -> Self {
+        Self::with_auth_rate_limit(RateLimit::default()).await
+    }
+
+    /// Same as [`TestApp::new`] with its own limit on login and registration.
+    pub async fn with_auth_rate_limit(auth_rate_limit: RateLimit) -> Self {
+        Self::build(
+            Arc::new(fresh_temp_dir()),
+            auth_rate_limit,
+            false,
+            Arc::default(),
+        )
+        .await
+    }
+
+    /// Same as [`TestApp::new`] with sessions kept in the users database.
+    pub async fn with_sqlite_sessions() -> Self {
+        Self::build(
+            Arc::new(fresh_temp_dir()),
+            RateLimit::default(),
+            true,
+            Arc::default(),
+        )
+        .await
+    }
+
+    /// A new app over the same users database, as after a server restart. It
+    /// starts without a cookie; in-memory sessions of this app are not carried over.
+    pub async fn restart(&self) -> Self {
+        Self::build(
+            self.temp_dir.clone(),
+            self.auth_rate_limit,
+            self.sqlite_sessions,
+            self.reset_sender.clone(),
+        )
+        .await
+    }
+
+    async fn build(
+        temp_dir: Arc<TempDir>,
+        auth_rate_limit: RateLimit,
+        sqlite_sessions: bool,
+        reset_sender: Arc<RecordingResetSender>,
+    ) -> Self {
+        let data_path = shared_data_path();
+        let main_db = init_main_db(
+            temp_dir
+                .path()
+                .to_str()
+                .expect("Failed to convert path to string"),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("Failed to initialize main database: {}", e));
+
+        let app = build_app(main_db.clone(), auth_rate_limit, reset_sender.clone());
+        let router = if sqlite_sessions {
+            app.layer(session_layer(SqliteStore::new(main_db.clone())))
+        } else {
+            app.layer(session_layer(MemoryStore::default()))
+        };
+
+        // Make sure the shared directory exists before any handler opens a user database
+        std::fs::create_dir_all(data_path).expect("Failed to create data directory");
+
+        TestApp {
+            router,
+            main_db,
+            cookie: Mutex::new(None),
+            auth_rate_limit,
+            sqlite_sessions,
+            temp_dir,
+            reset_sender,
+        }
+    }
+
+    /// Directory holding the per-user databases, for white-box checks with `get_user_db`.
+    pub fn data_path(&self) -> &'static str {
+        get_database_path()
+    }
+
+    /// Password reset tokens sent so far, as (email, token) in sending order.
+    pub fn sent_reset_tokens(&self) -> Vec<(String, String)> {
+        self.reset_sender.sent.lock().unwrap().clone()
+    }
+
+    pub fn main_db(&self) -> &Db {
+        &self.main_db
+    }
+
+    /// The session cookie (`name=value`) currently held by the client, if any.
+    pub fn cookie(&self) -> Option<String> {
+        self.cookie.lock().unwrap().clone()
+    }
+
+    pub fn set_cookie(&self, cookie: Option<String>) {
+        *self.cookie.lock().unwrap() = cookie;
+    }
+
+    pub async fn register_and_login(&self) -> PublicUser {
+        self.register_and_login_as(TEST_USERNAME, TEST_PASSWORD)
+            .await
+    }
+
+    pub async fn register_and_login_as(&self, username: &str, password: &str) -> PublicUser {
+        let credentials = serde_json::json!({ "username": username, "password": password });
+
+        let response = self.post_json("/auth/register", &credentials).await;
+        assert_eq!(
+            response.status,
+            StatusCode::CREATED,
+            "registration failed: {}",
+            response.text()
+        );
+
+        let response = self.post_json("/auth/login", &credentials).await;
+        assert_eq!(
+            response.status,
+            StatusCode::OK,
+            "login failed: {}",
+            response.text()
+        );
+        response.json()
+    }
+
+    pub async fn get(&self, path: &str) -> TestResponse {
+        self.send(Method::GET, path, None).await
+    }
+
+    pub async fn delete(&self, path: &str) -> TestResponse {
+        self.send(Method::DELETE, path, None).await
+    }
+
+    pub async fn delete_json<T: Serialize>(&self, path: &str, body: &T) -> TestResponse {
+        self.send(
+            Method::DELETE,
+            path,
+            Some(serde_json::to_vec(body).unwrap()),
+        )
+        .await
+    }
+
+    pub async fn post_json<T: Serialize>(&self, path: &str, body: &T) -> TestResponse {
+        self.send(Method::POST, path, Some(serde_json::to_vec(body).unwrap()))
+            .await
+    }
+
+    pub async fn put_json<T: Serialize>(&self, path: &str, body: &T) -> TestResponse {
+        self.send(Method::PUT, path, Some(serde_json::to_vec(body).unwrap()))
+            .await
+    }
+
+    pub async fn patch_json<T: Serialize>(&self, path: &str, body: &T) -> TestResponse {
+        self.send(Method::PATCH, path, Some(serde_json::to_vec(body).unwrap()))
+            .await
+    }
+
+    async fn send(&self, method: Method, path: &str, json: Option<Vec<u8>>) -> TestResponse {
+        let builder = Request::builder().method(method).uri(path);
+        let request = match json {
+            Some(bytes) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(bytes)),
+            None => builder.body(Body::empty()),
+        }
+        .expect("Failed to build request");
+
+        self.request(request).await
+    }
+
+    /// Sends an arbitrary request, attaching the current session cookie and
+    /// remembering any cookie the server sets.
+    pub async fn request(&self, mut request: Request<Body>) -> TestResponse {
+        if let Some(cookie) = self.cookie() {
+            request
+                .headers_mut()
+                .insert(header::COOKIE, cookie.parse().unwrap());
+        }
+
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Router is infallible");
+
+        if let Some(set_cookie) = response
+            .headers()
+            .get(header::SET_COOKIE)
+            .and_then(|value| value.to_str().ok())
+        {
+            let pair = set_cookie.split(';').next().unwrap_or_default().to_string();
+            self.set_cookie(Some(pair));
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = body
+            .collect()
+            .await
+            .expect("Failed to read response body")
+            .to_bytes();
+
+        TestResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body,
+        }
+    }
+}
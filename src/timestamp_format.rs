@@ -0,0 +1,61 @@
+use serde::{
+    Deserialize, Deserializer,
+    de::{self, Visitor},
+};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+/// Parses a time bound given either as unix seconds ("1714521600") or as an
+/// RFC3339 date such as "2024-05-01T00:00:00Z". Offsets other than `Z` are
+/// honoured, and dates before 1970 give negative timestamps.
+pub fn parse_timestamp_str(value: &str) -> Result<i64, String> {
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return Ok(timestamp);
+    }
+
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map(OffsetDateTime::unix_timestamp)
+        .map_err(|_| {
+            format!(
+                "expected a unix timestamp or an RFC3339 date like 2024-05-01T00:00:00Z, got {:?}",
+                value
+            )
+        })
+}
+
+struct TimestampVisitor;
+
+impl Visitor<'_> for TimestampVisitor {
+    type Value = i64;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a unix timestamp or an RFC3339 date")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<i64, E> {
+        Ok(value)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<i64, E> {
+        i64::try_from(value).map_err(|_| E::custom("timestamp is out of range"))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<i64, E> {
+        parse_timestamp_str(value).map_err(E::custom)
+    }
+}
+
+/// Accepts a time bound as an integer or a string (see `parse_timestamp_str`).
+pub fn deserialize_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    deserializer.deserialize_any(TimestampVisitor)
+}
+
+/// Optional variant of `deserialize_timestamp`. Query extraction reports failures
+/// together with the parameter name, e.g. "start_time: expected a unix timestamp ...".
+pub fn deserialize_optional_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<i64>, D::Error> {
+    #[derive(Deserialize)]
+    struct Timestamp(#[serde(deserialize_with = "deserialize_timestamp")] i64);
+
+    Ok(Option::<Timestamp>::deserialize(deserializer)?.map(|Timestamp(timestamp)| timestamp))
+}
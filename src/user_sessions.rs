@@ -0,0 +1,305 @@
+use axum::{
+    Json,
+    extract::{Path, Request, State},
+    http::{Extensions, HeaderMap, StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use std::net::IpAddr;
+use tower_sessions::{Expiry, Session};
+use uuid::Uuid;
+
+use crate::auth::{end_session, get_current_user};
+use crate::constants::*;
+use crate::database::Db;
+use crate::models::SessionInfo;
+use crate::rate_limit::client_ip;
+use crate::utils::{db_error, db_error_with_context};
+
+/// Keeps the network part of an address only: a /24 for IPv4, a /48 for IPv6.
+pub fn truncate_ip(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{}.{}.{}.0", a, b, c)
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            std::net::Ipv6Addr::new(segments[0], segments[1], segments[2], 0, 0, 0, 0, 0)
+                .to_string()
+        }
+    }
+}
+
+/// How long a session may sit idle before it ends.
+pub fn session_lifetime(remember_me: bool) -> time::Duration {
+    if remember_me {
+        time::Duration::days(SESSION_EXPIRY_DAYS)
+    } else {
+        time::Duration::hours(SHORT_SESSION_EXPIRY_HOURS)
+    }
+}
+
+/// What a request finds out about the session it comes with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionActivity {
+    /// Revoked, its account deleted, or idle past its lifetime
+    Ended,
+    /// Still valid; `refreshed` when `last_seen_at` was moved up to now.
+    /// `username` is the account's current name, which another session may
+    /// have changed since this one logged in.
+    Active {
+        remember_me: bool,
+        refreshed: bool,
+        username: String,
+    },
+}
+
+fn session_error(e: tower_sessions::session::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn current_device_id(session: &Session) -> Result<Option<String>, (StatusCode, String)> {
+    session
+        .get(SESSION_DEVICE_ID_KEY)
+        .await
+        .map_err(session_error)
+}
+
+/// Registers the session `user_id` just logged in to, replacing the row of an
+/// earlier login on the same session, and gives it the lifetime `remember_me`
+/// asks for.
+pub async fn record_login(
+    main_db: &Db,
+    session: &Session,
+    user_id: &str,
+    remember_me: bool,
+    headers: &HeaderMap,
+    extensions: &Extensions,
+) -> Result<(), (StatusCode, String)> {
+    let previous = current_device_id(session).await?;
+    let device_id = Uuid::new_v4().to_string();
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|agent| {
+            agent
+                .chars()
+                .take(MAX_USER_AGENT_LENGTH)
+                .collect::<String>()
+        });
+    let ip = truncate_ip(client_ip(extensions));
+
+    {
+        let conn = main_db.write().await;
+        if let Some(previous) = &previous {
+            conn.execute(
+                "DELETE FROM user_sessions WHERE id = ?",
+                [previous.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to replace session"))?;
+        }
+        conn.execute(
+            "INSERT INTO user_sessions (id, user_id, created_at, last_seen_at, user_agent, ip, remember_me) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            (device_id.as_str(), user_id, now, now, user_agent, ip, remember_me),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to record session"))?;
+    }
+
+    session.set_expiry(Some(Expiry::OnInactivity(session_lifetime(remember_me))));
+    session
+        .insert(SESSION_DEVICE_ID_KEY, device_id)
+        .await
+        .map_err(session_error)
+}
+
+/// Drops the row of the session being logged out of.
+pub async fn forget_session(main_db: &Db, session: &Session) -> Result<(), (StatusCode, String)> {
+    if let Some(device_id) = current_device_id(session).await? {
+        main_db
+            .write()
+            .await
+            .execute(
+                "DELETE FROM user_sessions WHERE id = ?",
+                [device_id.as_str()],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to delete session"))?;
+    }
+    Ok(())
+}
+
+/// Checks the session with row `device_id` at `now`, ending it once idle past
+/// its lifetime, and refreshes `last_seen_at` at most once per
+/// `SESSION_LAST_SEEN_INTERVAL_SECS`.
+pub async fn check_session_activity(
+    main_db: &Db,
+    device_id: &str,
+    now: i64,
+) -> Result<SessionActivity, (StatusCode, String)> {
+    let (last_seen_at, remember_me, username) = {
+        let conn = main_db.read().await;
+        let mut rows = conn
+            .query(
+                "SELECT s.last_seen_at, s.remember_me, u.name FROM user_sessions s JOIN users u ON u.id = s.user_id WHERE s.id = ?",
+                [device_id],
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to query session"))?;
+        match rows.next().await.map_err(|_| db_error())? {
+            Some(row) => (
+                row.get::<i64>(0).map_err(|_| db_error())?,
+                row.get::<bool>(1).map_err(|_| db_error())?,
+                row.get::<String>(2).map_err(|_| db_error())?,
+            ),
+            None => return Ok(SessionActivity::Ended),
+        }
+    };
+
+    let idle = now - last_seen_at;
+    if idle >= session_lifetime(remember_me).whole_seconds() {
+        main_db
+            .write()
+            .await
+            .execute("DELETE FROM user_sessions WHERE id = ?", [device_id])
+            .await
+            .map_err(|_| db_error_with_context("failed to delete session"))?;
+        return Ok(SessionActivity::Ended);
+    }
+
+    let refreshed = idle >= SESSION_LAST_SEEN_INTERVAL_SECS;
+    if refreshed {
+        main_db
+            .write()
+            .await
+            .execute(
+                "UPDATE user_sessions SET last_seen_at = ? WHERE id = ?",
+                (now, device_id),
+            )
+            .await
+            .map_err(|_| db_error_with_context("failed to update session"))?;
+    }
+    Ok(SessionActivity::Active {
+        remember_me,
+        refreshed,
+        username,
+    })
+}
+
+/// Middleware logging out sessions that ended, because they were revoked, their
+/// account deleted or they sat idle too long, and keeping `last_seen_at` of the
+/// others current. The session layer's expiry is the long one, so short
+/// sessions have theirs set again whenever they are seen, and a session whose
+/// account was renamed elsewhere takes the new name. Sessions from before rows
+/// were kept carry no id and pass untouched.
+pub async fn session_activity_layer(
+    State(main_db): State<Db>,
+    session: Session,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    if let Some(device_id) = current_device_id(&session).await? {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        match check_session_activity(&main_db, &device_id, now).await? {
+            SessionActivity::Ended => session.flush().await.map_err(session_error)?,
+            SessionActivity::Active {
+                remember_me,
+                refreshed,
+                username,
+            } => {
+                if refreshed && !remember_me {
+                    session.set_expiry(Some(Expiry::OnInactivity(session_lifetime(false))));
+                }
+                let stored: Option<String> =
+                    session.get("username").await.map_err(session_error)?;
+                if stored.as_deref() != Some(username.as_str()) {
+                    session
+                        .insert("username", username)
+                        .await
+                        .map_err(session_error)?;
+                }
+            }
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+/// Deletes rows of sessions inactive since before `before`, which the session
+/// store has expired by then.
+pub async fn purge_stale_user_sessions(
+    main_db: &Db,
+    before: i64,
+) -> Result<u64, (StatusCode, String)> {
+    main_db
+        .write()
+        .await
+        .execute("DELETE FROM user_sessions WHERE last_seen_at < ?", [before])
+        .await
+        .map_err(|_| db_error_with_context("failed to purge sessions"))
+}
+
+/// The current user's sessions, most recently active first.
+pub async fn list_sessions(
+    State(main_db): State<Db>,
+    session: Session,
+) -> Result<(StatusCode, Json<Vec<SessionInfo>>), (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+    let current = current_device_id(&session).await?;
+
+    let conn = main_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT id, created_at, last_seen_at, user_agent, ip, remember_me FROM user_sessions WHERE user_id = ? ORDER BY last_seen_at DESC, created_at DESC, rowid DESC",
+            [user.id.as_str()],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to query sessions"))?;
+
+    let mut sessions = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        let id: String = row.get(0).map_err(|_| db_error())?;
+        sessions.push(SessionInfo {
+            current: current.as_deref() == Some(id.as_str()),
+            id,
+            created_at: row.get(1).map_err(|_| db_error())?,
+            last_seen_at: row.get(2).map_err(|_| db_error())?,
+            user_agent: row.get(3).map_err(|_| db_error())?,
+            ip: row.get(4).map_err(|_| db_error())?,
+            remember_me: row.get(5).map_err(|_| db_error())?,
+        });
+    }
+
+    Ok((StatusCode::OK, Json(sessions)))
+}
+
+/// Revokes one of the current user's sessions; it is logged out on its next
+/// request. Revoking the current session logs out right away.
+pub async fn revoke_session(
+    State(main_db): State<Db>,
+    session: Session,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user = get_current_user(&session).await?;
+
+    let deleted = main_db
+        .write()
+        .await
+        .execute(
+            "DELETE FROM user_sessions WHERE id = ? AND user_id = ?",
+            (session_id.as_str(), user.id.as_str()),
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to delete session"))?;
+    if deleted == 0 {
+        return Err((StatusCode::NOT_FOUND, "Session not found".to_string()));
+    }
+
+    if current_device_id(&session).await?.as_deref() == Some(session_id.as_str()) {
+        end_session(&session).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -13,6 +13,12 @@ pub fn get_database_path() -> &'static str {
     })
 }
 
+/// Pins the directory used for per-user databases before it is first read from the
+/// environment. Returns false if the path has already been initialized.
+pub fn set_database_path(path: &str) -> bool {
+    CACHED_DATABASE_PATH.set(path.to_string()).is_ok()
+}
+
 pub async fn get_user_database(
     user_id: &str,
 ) -> Result<Arc<RwLock<libsql::Connection>>, (StatusCode, String)> {
@@ -25,6 +31,23 @@ pub async fn get_user_database(
     })
 }
 
+/// Ids of every registered user, for background jobs that visit each user database.
+pub async fn list_user_ids(
+    main_db: &Arc<RwLock<libsql::Connection>>,
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let conn = main_db.read().await;
+    let mut rows = conn
+        .query("SELECT id FROM users", ())
+        .await
+        .map_err(|_| db_error_with_context("failed to query users"))?;
+
+    let mut user_ids = Vec::new();
+    while let Some(row) = rows.next().await.map_err(|_| db_error())? {
+        user_ids.push(row.get::<String>(0).map_err(|_| db_error())?);
+    }
+    Ok(user_ids)
+}
+
 pub fn db_error() -> (StatusCode, String) {
     (
         StatusCode::INTERNAL_SERVER_ERROR,
@@ -64,6 +87,14 @@ pub async fn validate_category_exists(
     category_id: &str,
 ) -> Result<(), (StatusCode, String)> {
     let conn = user_db.read().await;
+    ensure_category_exists(&conn, category_id).await
+}
+
+/// Like [`validate_category_exists`], for callers that already hold a connection.
+pub async fn ensure_category_exists(
+    conn: &libsql::Connection,
+    category_id: &str,
+) -> Result<(), (StatusCode, String)> {
     let mut rows = conn
         .query("SELECT id FROM categories WHERE id = ?", [category_id])
         .await
@@ -78,6 +109,41 @@ pub async fn validate_category_exists(
     Ok(())
 }
 
+/// Like [`validate_category_exists`], but also rejects archived categories, which
+/// accept no new records.
+pub async fn validate_category_active(
+    user_db: &Arc<RwLock<libsql::Connection>>,
+    category_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let conn = user_db.read().await;
+    ensure_category_active(&conn, category_id).await
+}
+
+/// Like [`validate_category_active`], for callers that already hold a connection.
+pub async fn ensure_category_active(
+    conn: &libsql::Connection,
+    category_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let mut rows = conn
+        .query(
+            "SELECT archived FROM categories WHERE id = ?",
+            [category_id],
+        )
+        .await
+        .map_err(|_| db_error_with_context("failed to check category existence"))?;
+
+    match rows.next().await.map_err(|_| db_error())? {
+        None => Err((
+            StatusCode::BAD_REQUEST,
+            "Category does not exist".to_string(),
+        )),
+        Some(row) if row.get::<bool>(0).map_err(|_| db_error())? => {
+            Err((StatusCode::BAD_REQUEST, "Category is archived".to_string()))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
 pub fn validate_limit(limit: Option<u32>, default: u32) -> Result<u32, (StatusCode, String)> {
     match limit {
         Some(l) => {
@@ -0,0 +1,89 @@
+/*!
+ * Account Deletion Tests
+ *
+ * Covers DELETE /auth/account: the current password is required, the user can no
+ * longer log in afterwards, their database file is removed, and neither the
+ * cleared session nor another session of the deleted user can delete again.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::user_db_path;
+use my_budget_server::test_support::{TEST_PASSWORD, TEST_USERNAME};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_wrong_password_keeps_account() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_category_via_api(&app, "Food").await;
+
+    let response = app
+        .delete_json("/auth/account", &json!({ "password": "not-the-password" }))
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    assert!(user_db_path(&data_path, &user_id).exists());
+    let response = app.get("/auth/me").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_deleted_account_is_gone() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, 1705276800).await;
+    assert!(user_db_path(&data_path, &user_id).exists());
+
+    let password = json!({ "password": TEST_PASSWORD });
+    let response = app.delete_json("/auth/account", &password).await;
+    assert_eq!(
+        response.status,
+        StatusCode::NO_CONTENT,
+        "{}",
+        response.text()
+    );
+    assert!(!user_db_path(&data_path, &user_id).exists());
+
+    // The session is cleared, so neither the user nor a second deletion gets in
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+    let response = app.delete_json("/auth/account", &password).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": TEST_USERNAME, "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_other_session_of_deleted_account_logged_out() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let other_device = app.cookie();
+
+    // A second login gets a session of its own
+    app.set_cookie(None);
+    let credentials = json!({ "username": TEST_USERNAME, "password": TEST_PASSWORD });
+    let response = app.post_json("/auth/login", &credentials).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_ne!(app.cookie(), other_device);
+
+    let password = json!({ "password": TEST_PASSWORD });
+    let response = app.delete_json("/auth/account", &password).await;
+    assert_eq!(
+        response.status,
+        StatusCode::NO_CONTENT,
+        "{}",
+        response.text()
+    );
+
+    // Its sessions went with the account
+    app.set_cookie(other_device);
+    let response = app.delete_json("/auth/account", &password).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,170 @@
+/*!
+ * Account Email Tests
+ *
+ * Covers the optional email address: registration with and without one, the
+ * format check, 409 for an address already in use, PATCH /auth/profile setting
+ * and clearing it, and that only the owner ever sees it.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::models::{AdminUser, PublicUser};
+use my_budget_server::test_support::{TEST_PASSWORD, TestApp};
+use serde_json::json;
+
+async fn register(app: &TestApp, username: &str, email: Option<&str>) -> (StatusCode, String) {
+    let mut body = json!({ "username": username, "password": TEST_PASSWORD });
+    if let Some(email) = email {
+        body["email"] = json!(email);
+    }
+    let response = app.post_json("/auth/register", &body).await;
+    (response.status, response.text())
+}
+
+#[tokio::test]
+async fn test_register_with_and_without_email() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_json(
+            "/auth/register",
+            &json!({ "username": "with_email", "password": TEST_PASSWORD, "email": " Someone@Example.COM " }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let user: PublicUser = response.json();
+    assert_eq!(user.email.as_deref(), Some("someone@example.com"));
+
+    let (status, _) = register(&app, "without_email", None).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = register(&app, "also_without", None).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let user = app.register_and_login_as("logged_in", TEST_PASSWORD).await;
+    assert_eq!(user.email, None);
+    let me: PublicUser = app.get("/auth/me").await.json();
+    assert_eq!(me.email, None);
+
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": "with_email", "password": TEST_PASSWORD }),
+        )
+        .await;
+    let user: PublicUser = response.json();
+    assert_eq!(user.email.as_deref(), Some("someone@example.com"));
+    let me: PublicUser = app.get("/auth/me").await.json();
+    assert_eq!(me.email.as_deref(), Some("someone@example.com"));
+}
+
+#[tokio::test]
+async fn test_invalid_email_rejected() {
+    let app = TestApp::new().await;
+
+    for email in [
+        "",
+        "plainaddress",
+        "@example.com",
+        "someone@",
+        "someone@localhost",
+        "some one@example.com",
+        "someone@@example.com",
+        "someone@example..com",
+        "someone@-example.com",
+    ] {
+        let (status, message) = register(&app, "bad_email", Some(email)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{:?}", email);
+        assert_eq!(message, "Invalid email address");
+    }
+
+    let too_long = format!("{}@example.com", "a".repeat(65));
+    let (status, _) = register(&app, "bad_email", Some(&too_long)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_duplicate_email_conflicts() {
+    let app = TestApp::new().await;
+    let (status, _) = register(&app, "first_user", Some("shared@example.com")).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, message) = register(&app, "second_user", Some("SHARED@example.com")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(message, "Email already in use");
+
+    app.register_and_login_as("third_user", TEST_PASSWORD).await;
+    let response = app
+        .patch_json(
+            "/auth/profile",
+            &json!({ "email": "shared@example.com", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.text(), "Email already in use");
+}
+
+#[tokio::test]
+async fn test_profile_update_sets_and_clears_email() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+
+    let response = app
+        .patch_json(
+            "/auth/profile",
+            &json!({ "email": "me@example.com", "password": "not-the-password" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .patch_json(
+            "/auth/profile",
+            &json!({ "email": "not-an-email", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .patch_json(
+            "/auth/profile",
+            &json!({ "email": "me@example.com", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(
+        response.json::<PublicUser>().email.as_deref(),
+        Some("me@example.com")
+    );
+
+    // Leaving email out changes nothing
+    let response = app
+        .patch_json("/auth/profile", &json!({ "password": TEST_PASSWORD }))
+        .await;
+    assert_eq!(
+        response.json::<PublicUser>().email.as_deref(),
+        Some("me@example.com")
+    );
+
+    let response = app
+        .patch_json(
+            "/auth/profile",
+            &json!({ "email": null, "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json::<PublicUser>().email, None);
+    assert_eq!(app.get("/auth/me").await.json::<PublicUser>().email, None);
+}
+
+#[tokio::test]
+async fn test_email_not_shown_to_other_users() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    let (status, _) = register(&app, "second_user", Some("private@example.com")).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Not even the admin listing carries it
+    let response = app.get("/admin/users").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(!response.text().contains("private@example.com"));
+    let users: Vec<AdminUser> = response.json();
+    assert_eq!(users.len(), 2);
+}
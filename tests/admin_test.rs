@@ -0,0 +1,125 @@
+/*!
+ * Admin Tests
+ *
+ * Covers the admin role: the first account registered is the admin, others get
+ * 403 from /admin routes, GET /admin/users lists every account with its record
+ * count, DELETE /admin/users/{id} removes an account like self-deletion does,
+ * and grant_admin promotes an existing account.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::admin::grant_admin;
+use my_budget_server::database::user_db_path;
+use my_budget_server::models::{AdminUser, UserRole};
+use my_budget_server::test_support::{TEST_PASSWORD, TestApp};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_admin_lists_users_and_others_are_forbidden() {
+    let (app, data_path, admin_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &admin_id, "Lunch", 12.0, &food, 1705276800).await;
+    create_test_record(&data_path, &admin_id, "Dinner", 20.0, &food, 1705280400).await;
+    let admin_cookie = app.cookie();
+
+    app.set_cookie(None);
+    let member = app
+        .register_and_login_as("family_member", TEST_PASSWORD)
+        .await;
+    let response = app.get("/admin/users").await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.text(), "Admin access required");
+    let response = app.delete(&format!("/admin/users/{}", admin_id)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    // Forbidden as well for ids that do not exist
+    let response = app.delete("/admin/users/no-such-user").await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    app.set_cookie(None);
+    assert_eq!(
+        app.get("/admin/users").await.status,
+        StatusCode::UNAUTHORIZED
+    );
+
+    app.set_cookie(admin_cookie);
+    let response = app.get("/admin/users").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let users: Vec<AdminUser> = response.json();
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0].id, admin_id);
+    assert_eq!(users[0].role, UserRole::Admin);
+    assert_eq!(users[0].record_count, 2);
+    assert!(users[0].created_at.is_some());
+    assert_eq!(users[1].id, member.id);
+    assert_eq!(users[1].username, "family_member");
+    assert_eq!(users[1].role, UserRole::User);
+    assert_eq!(users[1].record_count, 0);
+
+    // Listing does not create databases for users who never stored anything
+    assert!(!user_db_path(&data_path, &member.id).exists());
+}
+
+#[tokio::test]
+async fn test_admin_deletes_user() {
+    let app = TestApp::with_sqlite_sessions().await;
+    let admin = app.register_and_login().await;
+    let admin_cookie = app.cookie();
+
+    app.set_cookie(None);
+    let member = app
+        .register_and_login_as("family_member", TEST_PASSWORD)
+        .await;
+    create_test_category_via_api(&app, "Food").await;
+    assert!(user_db_path(app.data_path(), &member.id).exists());
+    let member_cookie = app.cookie();
+
+    app.set_cookie(admin_cookie);
+    let response = app.delete(&format!("/admin/users/{}", member.id)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::NO_CONTENT,
+        "{}",
+        response.text()
+    );
+    assert!(!user_db_path(app.data_path(), &member.id).exists());
+    let response = app.delete(&format!("/admin/users/{}", member.id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.delete(&format!("/admin/users/{}", admin.id)).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let users: Vec<AdminUser> = app.get("/admin/users").await.json();
+    assert_eq!(users.len(), 1);
+
+    // The deleted user's stored session is gone along with the account
+    app.set_cookie(member_cookie);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": "family_member", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_grant_admin() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    app.set_cookie(None);
+    app.register_and_login_as("family_member", TEST_PASSWORD)
+        .await;
+    assert_eq!(app.get("/admin/users").await.status, StatusCode::FORBIDDEN);
+
+    assert!(grant_admin(app.main_db(), "family_member").await.unwrap());
+    assert!(!grant_admin(app.main_db(), "nobody_here").await.unwrap());
+
+    let response = app.get("/admin/users").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let users: Vec<AdminUser> = response.json();
+    assert!(users.iter().all(|user| user.role == UserRole::Admin));
+}
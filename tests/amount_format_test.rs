@@ -0,0 +1,123 @@
+/*!
+ * Amount Format Contract Tests
+ *
+ * Pins the byte-exact JSON rendering of amounts in the default (number) mode and
+ * the opt-in string mode selected via `?amount_format=` or the Accept header.
+ */
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use common::*;
+use my_budget_server::amount_format::{AmountFormat, format_amount, parse_amount_format};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+#[test]
+fn format_amount_renders_two_decimals() {
+    assert_eq!(format_amount(0.1 + 0.2), "0.30");
+    assert_eq!(format_amount(12.5), "12.50");
+    assert_eq!(format_amount(-3.0), "-3.00");
+    assert_eq!(format_amount(1234.567), "1234.57");
+}
+
+#[test]
+fn parse_amount_format_values() {
+    assert_eq!(parse_amount_format("number").unwrap(), AmountFormat::Number);
+    assert_eq!(parse_amount_format("string").unwrap(), AmountFormat::String);
+    let (status, message) = parse_amount_format("decimal").unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(message, "Unsupported amount format: decimal");
+}
+
+#[tokio::test]
+async fn default_mode_renders_json_numbers() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Sum",
+        0.1 + 0.2,
+        "misc",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let response = app.get("/records").await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains(r#""amount":0.30000000000000004"#));
+}
+
+#[tokio::test]
+async fn query_flag_renders_amount_strings() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Sum",
+        0.1 + 0.2,
+        "misc",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let response = app.get("/records?amount_format=string").await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains(r#""amount":"0.30""#));
+}
+
+#[tokio::test]
+async fn accept_parameter_renders_amount_strings() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.5,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let request = Request::builder()
+        .uri("/records")
+        .header(header::ACCEPT, "application/json; amount=string")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(request).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains(r#""amount":"12.50""#));
+}
+
+#[tokio::test]
+async fn export_stream_honors_string_mode() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.5,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let response = app.get("/records/export?amount_format=string").await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains(r#""amount":"12.50""#));
+}
+
+#[tokio::test]
+async fn unknown_amount_format_is_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/records?amount_format=cents").await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Unsupported amount format: cents");
+}
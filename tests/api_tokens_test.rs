@@ -0,0 +1,118 @@
+/*!
+ * Personal API Token Tests
+ *
+ * Covers /auth/tokens: a token is shown once on creation and listed without it,
+ * `Authorization: Bearer` works in place of a session and marks the token used,
+ * revoked or unknown tokens get 401, and tokens of other users cannot be revoked.
+ */
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use my_budget_server::models::{ApiToken, CreateApiTokenResponse, PublicUser};
+use my_budget_server::test_support::{TEST_PASSWORD, TestApp, TestResponse};
+use serde_json::json;
+
+async fn create_token(app: &TestApp, name: &str) -> CreateApiTokenResponse {
+    let response = app
+        .post_json("/auth/tokens", &json!({ "name": name }))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    response.json()
+}
+
+async fn with_bearer(app: &TestApp, method: Method, path: &str, token: &str) -> TestResponse {
+    let request = Request::builder()
+        .method(method)
+        .uri(path)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    app.request(request).await
+}
+
+#[tokio::test]
+async fn test_token_authenticates_until_revoked() {
+    let app = TestApp::new().await;
+    let user = app.register_and_login().await;
+    let created = create_token(&app, "cron").await;
+    assert!(created.token.starts_with("mbt_"));
+
+    let tokens: Vec<ApiToken> = app.get("/auth/tokens").await.json();
+    assert_eq!(
+        tokens,
+        vec![ApiToken {
+            id: created.id.clone(),
+            name: "cron".to_string(),
+            created_at: created.created_at,
+            last_used_at: None,
+        }]
+    );
+    assert!(
+        !app.get("/auth/tokens")
+            .await
+            .text()
+            .contains(&created.token)
+    );
+
+    // A client without the session cookie
+    let cookie = app.cookie();
+    app.set_cookie(None);
+    let response = with_bearer(&app, Method::GET, "/auth/me", &created.token).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(response.json::<PublicUser>().id, user.id);
+    let response = with_bearer(&app, Method::GET, "/records", &created.token).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(response.json::<serde_json::Value>()["records"], json!([]));
+
+    app.set_cookie(cookie);
+    let tokens: Vec<ApiToken> = app.get("/auth/tokens").await.json();
+    assert!(tokens[0].last_used_at.is_some());
+
+    let response = app.delete(&format!("/auth/tokens/{}", created.id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    app.set_cookie(None);
+    let response = with_bearer(&app, Method::GET, "/records", &created.token).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_unknown_or_malformed_token_refused() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+
+    // Even with a valid session alongside it
+    let response = with_bearer(&app, Method::GET, "/records", "mbt_not-a-token").await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let request = Request::builder()
+        .uri("/records")
+        .header(header::AUTHORIZATION, "Basic dXNlcjpwYXNz")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.request(request).await.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post_json("/auth/tokens", &json!({ "name": "  " }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_tokens_are_per_user() {
+    let app = TestApp::new().await;
+    app.register_and_login_as("first_user", TEST_PASSWORD).await;
+    let first = create_token(&app, "first").await;
+
+    app.register_and_login_as("second_user", TEST_PASSWORD)
+        .await;
+    let tokens: Vec<ApiToken> = app.get("/auth/tokens").await.json();
+    assert!(tokens.is_empty());
+    let response = app.delete(&format!("/auth/tokens/{}", first.id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    app.set_cookie(None);
+    let response = with_bearer(&app, Method::GET, "/auth/me", &first.token).await;
+    assert_eq!(response.json::<PublicUser>().username, "first_user");
+}
@@ -0,0 +1,202 @@
+/*!
+ * Record Archive Tests
+ *
+ * Covers moving old records into per-year archive tables and back through
+ * POST /records/archive and POST /records/unarchive: no record is lost or
+ * duplicated, and range queries, exports and the pivot still see archived years.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{
+    PivotTable, Record, RecordArchive, RecordArchiveResponse, RecordChangesResponse,
+};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+// 2019-06-01 00:00:00 UTC
+const JUN_2019: i64 = 1559347200;
+// 2020-03-01 00:00:00 UTC
+const MAR_2020: i64 = 1583020800;
+// 2024-01-01 00:00:00 UTC
+const JAN_2024: i64 = 1704067200;
+// 2024-02-01 00:00:00 UTC
+const FEB_2024: i64 = 1706745600;
+
+/// Two records in 2019, one in 2020 and one in 2024, returned by id order.
+async fn create_records_across_years(app: &TestApp, data_path: &str, user_id: &str) -> Vec<String> {
+    let food = create_test_category_via_api(app, "Food").await;
+    let mut ids = vec![
+        create_test_record(data_path, user_id, "Old lunch", 10.0, &food, JUN_2019).await,
+        create_test_record(data_path, user_id, "Old dinner", 20.0, &food, JUN_2019 + 60).await,
+        create_test_record(data_path, user_id, "Snack", 5.0, &food, MAR_2020).await,
+        create_test_record(data_path, user_id, "Coffee", 7.0, &food, FEB_2024).await,
+    ];
+    ids.sort();
+    ids
+}
+
+async fn exported_ids(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app.get(&format!("/records/export?{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let mut ids: Vec<String> = response
+        .text()
+        .lines()
+        .map(|line| serde_json::from_str::<Record>(line).unwrap().id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+async fn live_record_count(data_path: &str, user_id: &str) -> u32 {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query("SELECT COUNT(*) FROM records", ())
+        .await
+        .unwrap();
+    rows.next().await.unwrap().unwrap().get(0).unwrap()
+}
+
+#[tokio::test]
+async fn test_archive_and_unarchive_round_trip() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let ids = create_records_across_years(&app, &data_path, &user_id).await;
+
+    let response = app
+        .post_json("/records/archive", &json!({ "before": JAN_2024 }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let archived: RecordArchiveResponse = response.json();
+    assert_eq!(archived.moved, 3);
+    assert_eq!(
+        archived.archives,
+        vec![
+            RecordArchive {
+                year: 2019,
+                record_count: 2
+            },
+            RecordArchive {
+                year: 2020,
+                record_count: 1
+            },
+        ]
+    );
+    assert_eq!(live_record_count(&data_path, &user_id).await, 1);
+
+    // Every record is exported exactly once, archived or not
+    assert_eq!(exported_ids(&app, "start_time=0").await, ids);
+
+    let response = app.get("/records?start_time=0&limit=10").await;
+    let listed: serde_json::Value = response.json();
+    assert_eq!(listed["total_count"], 4);
+    assert_eq!(listed["records"].as_array().unwrap().len(), 4);
+
+    // Archived records are out of reach of by-id operations
+    let mut not_found = 0;
+    for id in &ids {
+        if app.get(&format!("/records/{}", id)).await.status == StatusCode::NOT_FOUND {
+            not_found += 1;
+        }
+    }
+    assert_eq!(not_found, 3);
+
+    // Archiving is not a deletion as far as sync clients are concerned
+    let response = app.get("/records/changes?since=0").await;
+    let changes: RecordChangesResponse = response.json();
+    assert!(changes.deleted_ids.is_empty());
+
+    let response = app
+        .post_json("/records/unarchive", &json!({ "year": 2019 }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let restored: RecordArchiveResponse = response.json();
+    assert_eq!(restored.moved, 2);
+    assert_eq!(
+        restored.archives,
+        vec![RecordArchive {
+            year: 2020,
+            record_count: 1
+        }]
+    );
+    assert_eq!(live_record_count(&data_path, &user_id).await, 3);
+    assert_eq!(exported_ids(&app, "start_time=0").await, ids);
+
+    // Without a year everything comes back
+    let response = app.post_json("/records/unarchive", &json!({})).await;
+    let restored: RecordArchiveResponse = response.json();
+    assert_eq!(restored.moved, 1);
+    assert!(restored.archives.is_empty());
+    assert_eq!(live_record_count(&data_path, &user_id).await, 4);
+    assert_eq!(exported_ids(&app, "start_time=0").await, ids);
+
+    let response = app.get("/records/archive").await;
+    assert_eq!(response.text(), "[]");
+}
+
+#[tokio::test]
+async fn test_range_queries_read_archived_years() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_records_across_years(&app, &data_path, &user_id).await;
+    app.post_json("/records/archive", &json!({ "before": JAN_2024 }))
+        .await;
+
+    let response = app.get("/records/pivot?year=2019").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let table: PivotTable = response.json();
+    assert_eq!(table.rows.len(), 1);
+    assert_eq!(table.rows[0].category_name, "Food");
+    assert_eq!(table.rows[0].months[5], 30.0);
+
+    let response = app
+        .get(&format!(
+            "/records/stats?start_time={}&end_time={}",
+            JUN_2019, FEB_2024
+        ))
+        .await;
+    let stats: serde_json::Value = response.json();
+    assert_eq!(stats["count"], 4);
+    assert_eq!(stats["sum"], 42.0);
+
+    // Name search covers archived years too
+    let response = app.get("/records?start_time=0&q=old").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let listed: serde_json::Value = response.json();
+    assert_eq!(listed["total_count"], 2);
+
+    // A range that stays within the live year only sees live records
+    assert_eq!(
+        exported_ids(&app, &format!("start_time={}", JAN_2024))
+            .await
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_archive_validation() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let future = time::OffsetDateTime::now_utc().unix_timestamp() + 86400;
+    let response = app
+        .post_json("/records/archive", &json!({ "before": future }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post_json("/records/unarchive", &json!({ "year": 2019 }))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.text(), "No archive for year 2019");
+
+    // Archiving with nothing old enough is a no-op
+    let response = app
+        .post_json("/records/archive", &json!({ "before": JAN_2024 }))
+        .await;
+    let archived: RecordArchiveResponse = response.json();
+    assert_eq!(archived.moved, 0);
+    assert!(archived.archives.is_empty());
+}
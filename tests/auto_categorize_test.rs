@@ -0,0 +1,188 @@
+/*!
+ * Auto-Categorization Tests
+ *
+ * Covers auto_categorize on POST /import/csv, where rows matching a keyword rule
+ * are filed under its category and the rest under the fallback category_id, and
+ * on POST /records, which files a record sent without a category by its rule and
+ * reports the rule that matched.
+ */
+
+mod common;
+
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{CsvImportResponse, Record};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+async fn create_rule(app: &TestApp, category_id: &str, keyword: &str, priority: i64) {
+    let response = app
+        .post_json(
+            &format!("/categories/{}/rules", category_id),
+            &json!({ "keyword": keyword, "priority": priority }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+}
+
+/// 50 statement rows: every other one mentions Uber or Shell, the rest match no rule.
+fn statement_csv() -> String {
+    let mut csv = String::from("Date,Description,Amount\n");
+    for i in 0..50 {
+        let description = match i % 4 {
+            0 => format!("UBER *TRIP {}", i),
+            2 => format!("Shell station {}", i),
+            _ => format!("Card payment {}", i),
+        };
+        csv.push_str(&format!(
+            "2024-05-{:02},{},-{}.50\n",
+            i % 28 + 1,
+            description,
+            i
+        ));
+    }
+    csv
+}
+
+#[tokio::test]
+async fn test_import_auto_categorizes_matching_rows() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let transport = create_test_category_via_api(&app, "Transport").await;
+    let fuel = create_test_category_via_api(&app, "Fuel").await;
+    let uncategorized = create_test_category_via_api(&app, "Uncategorized").await;
+    create_rule(&app, &transport, "uber", 0).await;
+    create_rule(&app, &fuel, "shell", 0).await;
+
+    let response = app
+        .post_json(
+            "/import/csv",
+            &json!({
+                "csv": statement_csv(),
+                "columns": { "date": 0, "amount": 2, "description": 1 },
+                "category_id": uncategorized,
+                "auto_categorize": true,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let summary: CsvImportResponse = response.json();
+    assert_eq!(summary.records_created, 50);
+    assert_eq!(summary.records_auto_categorized, 25);
+    assert!(summary.errors.is_empty());
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let mut per_category: HashMap<String, Vec<&Record>> = HashMap::new();
+    for record in &records {
+        per_category
+            .entry(record.category_id.clone().unwrap())
+            .or_default()
+            .push(record);
+    }
+    assert_eq!(per_category[&transport].len(), 13);
+    assert!(
+        per_category[&transport]
+            .iter()
+            .all(|r| r.name.starts_with("UBER"))
+    );
+    assert_eq!(per_category[&fuel].len(), 12);
+    assert_eq!(per_category[&uncategorized].len(), 25);
+    assert!(
+        per_category[&uncategorized]
+            .iter()
+            .all(|r| r.name.starts_with("Card payment"))
+    );
+}
+
+#[tokio::test]
+async fn test_import_fallback_category() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let transport = create_test_category_via_api(&app, "Transport").await;
+    create_rule(&app, &transport, "uber", 0).await;
+    let csv = "Date,Description,Amount\n2024-05-01,Uber ride,-12\n2024-05-02,Bakery,-3\n";
+
+    // Without a fallback, rows no rule matches stay uncategorized
+    let response = app
+        .post_json(
+            "/import/csv",
+            &json!({
+                "csv": csv,
+                "columns": { "date": 0, "amount": 2, "description": 1 },
+                "auto_categorize": true,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let bakery = records.iter().find(|r| r.name == "Bakery").unwrap();
+    assert_eq!(bakery.category_id, None);
+    let uber = records.iter().find(|r| r.name == "Uber ride").unwrap();
+    assert_eq!(uber.category_id.as_deref(), Some(transport.as_str()));
+
+    // Without auto_categorize every row needs the category
+    let response = app
+        .post_json(
+            "/import/csv",
+            &json!({
+                "csv": csv,
+                "columns": { "date": 0, "amount": 2, "description": 1 },
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "category_id is required unless auto_categorize is set"
+    );
+}
+
+#[tokio::test]
+async fn test_create_record_auto_categorized() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let transport = create_test_category_via_api(&app, "Transport").await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_rule(&app, &transport, "uber", 0).await;
+    let timestamp = recent_timestamp();
+
+    let response = app
+        .post_json(
+            "/records?auto_categorize=true",
+            &json!({ "name": "Uber to airport", "amount": 30.0, "timestamp": timestamp }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let record: Record = response.json();
+    assert_eq!(record.category_id.as_deref(), Some(transport.as_str()));
+    let rule = record.matched_rule.unwrap();
+    assert_eq!(rule.keyword, "uber");
+    assert_eq!(rule.category_id, transport);
+
+    // An explicit category is kept
+    let response = app
+        .post_json(
+            "/records?auto_categorize=true",
+            &json!({ "name": "Uber Eats", "amount": 20.0, "category_id": food, "timestamp": timestamp }),
+        )
+        .await;
+    let record: Record = response.json();
+    assert_eq!(record.category_id.as_deref(), Some(food.as_str()));
+    assert!(record.matched_rule.is_none());
+
+    // No match, or no flag, leaves the record uncategorized
+    for (path, name) in [
+        ("/records?auto_categorize=true", "Bakery"),
+        ("/records", "Uber home"),
+    ] {
+        let response = app
+            .post_json(
+                path,
+                &json!({ "name": name, "amount": 5.0, "timestamp": timestamp }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+        assert!(!response.text().contains("matched_rule"));
+        let record: Record = response.json();
+        assert_eq!(record.category_id, None);
+    }
+}
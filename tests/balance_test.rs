@@ -0,0 +1,140 @@
+/*!
+ * Running Balance Tests
+ *
+ * Covers `include_balance` and `opening_balance` on GET /records: income adds to
+ * and expenses subtract from the balance, pages still come newest first, and each
+ * balance covers the whole filtered range regardless of `limit` and `cursor`.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+const ONE_DAY: i64 = 24 * 60 * 60;
+
+/// Salary 1000, rent 200, lunch 50 and a refund of 20: balances 1000, 800, 750, 770.
+async fn setup_ledger() -> TestApp {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "General").await;
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Salary", "amount": 1000.0, "category_id": category_id, "kind": "income", "timestamp": TEST_BASE_TIMESTAMP }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let expenses = [("Rent", 200.0), ("Lunch", 50.0), ("Refund", -20.0)];
+    for (day, (name, amount)) in expenses.into_iter().enumerate() {
+        create_test_record(
+            &data_path,
+            &user_id,
+            name,
+            amount,
+            &category_id,
+            TEST_BASE_TIMESTAMP + (day as i64 + 1) * ONE_DAY,
+        )
+        .await;
+    }
+    app
+}
+
+async fn balances(app: &TestApp, query: &str) -> (Vec<(String, f64)>, Value) {
+    let response = app.get(&format!("/records?{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: Value = response.json();
+    let rows = body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["name"].as_str().unwrap().to_string(),
+                r["balance"].as_f64().unwrap(),
+            )
+        })
+        .collect();
+    (rows, body)
+}
+
+fn rows(expected: &[(&str, f64)]) -> Vec<(String, f64)> {
+    expected
+        .iter()
+        .map(|(name, balance)| (name.to_string(), *balance))
+        .collect()
+}
+
+#[tokio::test]
+async fn balance_runs_oldest_first_but_records_stay_newest_first() {
+    let app = setup_ledger().await;
+
+    let (page, _) = balances(&app, "include_balance=true").await;
+    assert_eq!(
+        page,
+        rows(&[
+            ("Refund", 770.0),
+            ("Lunch", 750.0),
+            ("Rent", 800.0),
+            ("Salary", 1000.0)
+        ])
+    );
+
+    let (page, _) = balances(&app, "include_balance=true&opening_balance=100.5").await;
+    assert_eq!(page[0], ("Refund".to_string(), 870.5));
+    assert_eq!(page[3], ("Salary".to_string(), 1100.5));
+
+    // Without the option records carry no balance at all
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    assert!(body["records"][0].get("balance").is_none());
+}
+
+#[tokio::test]
+async fn balance_is_not_limited_to_the_returned_page() {
+    let app = setup_ledger().await;
+
+    let (page, body) = balances(&app, "include_balance=true&limit=2").await;
+    assert_eq!(page, rows(&[("Refund", 770.0), ("Lunch", 750.0)]));
+
+    let cursor = body["next_cursor"].as_str().unwrap();
+    let (page, _) = balances(
+        &app,
+        &format!("include_balance=true&limit=2&cursor={}", cursor),
+    )
+    .await;
+    assert_eq!(page, rows(&[("Rent", 800.0), ("Salary", 1000.0)]));
+
+    // Filters do narrow the range the balance runs over
+    let (page, _) = balances(
+        &app,
+        &format!(
+            "include_balance=true&kind=expense&start_time={}",
+            TEST_BASE_TIMESTAMP + ONE_DAY
+        ),
+    )
+    .await;
+    assert_eq!(
+        page,
+        rows(&[("Refund", -230.0), ("Lunch", -250.0), ("Rent", -200.0)])
+    );
+}
+
+#[tokio::test]
+async fn opening_balance_needs_include_balance() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/records?opening_balance=10").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .get("/records?include_balance=true&opening_balance=inf")
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.get("/records?include_balance=true").await;
+    assert_eq!(response.status, StatusCode::OK);
+}
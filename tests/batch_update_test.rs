@@ -0,0 +1,145 @@
+/*!
+ * Batch Record Update Tests
+ *
+ * Covers PATCH /records: per-entry results, the same validation as a single
+ * PUT, rolling everything back with `fail_fast` versus skipping bad entries
+ * without it, and the batch size limit.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::constants::MAX_BATCH_UPDATE_SIZE;
+use my_budget_server::models::{BatchUpdateResponse, Record};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+const TEST_TIMESTAMP: i64 = 1704067200; // Jan 1, 2024 00:00:00 UTC
+
+async fn get_record(app: &TestApp, id: &str) -> Record {
+    let response = app.get(&format!("/records/{}", id)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+#[tokio::test]
+async fn test_batch_update_applies_each_entry() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+    let lunch =
+        create_test_record(&data_path, &user_id, "Lnuch", 12.0, &food, TEST_TIMESTAMP).await;
+    let taxi = create_test_record(&data_path, &user_id, "Taxi", 30.0, &food, TEST_TIMESTAMP).await;
+
+    let response = app
+        .patch_json(
+            "/records",
+            &json!([
+                { "id": lunch, "name": "Lunch" },
+                { "id": taxi, "amount": 25.5, "category_id": travel, "tags": ["Work"] },
+            ]),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let batch: BatchUpdateResponse = response.json();
+    assert_eq!(batch.updated, 2);
+    assert_eq!(batch.failed, 0);
+    assert_eq!(batch.results[0].index, 0);
+    assert_eq!(batch.results[0].record.as_ref().unwrap().name, "Lunch");
+    assert_eq!(batch.results[1].record.as_ref().unwrap().version, 2);
+
+    let updated = get_record(&app, &taxi).await;
+    assert_eq!(updated.amount, 25.5);
+    assert_eq!(updated.category_id.as_deref(), Some(travel.as_str()));
+    assert_eq!(updated.tags, vec!["work"]);
+    assert_eq!(get_record(&app, &lunch).await.name, "Lunch");
+}
+
+#[tokio::test]
+async fn test_batch_update_fail_fast_rolls_back() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let lunch =
+        create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, TEST_TIMESTAMP).await;
+
+    let entries = json!([
+        { "id": lunch, "name": "Brunch" },
+        { "id": lunch, "currency": "usd" },
+        { "id": "missing", "name": "Ghost" },
+    ]);
+
+    let response = app.patch_json("/records", &entries).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(
+        response.text().starts_with("Entry 1: "),
+        "{}",
+        response.text()
+    );
+
+    // The valid first entry was rolled back with the rest
+    let record = get_record(&app, &lunch).await;
+    assert_eq!(record.name, "Lunch");
+    assert_eq!(record.version, 1);
+}
+
+#[tokio::test]
+async fn test_batch_update_without_fail_fast_skips_bad_entries() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let lunch =
+        create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, TEST_TIMESTAMP).await;
+    let taxi = create_test_record(&data_path, &user_id, "Taxi", 30.0, &food, TEST_TIMESTAMP).await;
+
+    let response = app
+        .patch_json(
+            "/records?fail_fast=false",
+            &json!([
+                { "id": lunch, "name": "Brunch" },
+                { "id": taxi, "category_id": "no-such-category" },
+                { "id": "missing", "name": "Ghost" },
+                { "id": taxi },
+                { "id": taxi, "starred": true },
+            ]),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let batch: BatchUpdateResponse = response.json();
+    assert_eq!(batch.updated, 2);
+    assert_eq!(batch.failed, 3);
+
+    let statuses: Vec<u16> = batch.results.iter().map(|r| r.status).collect();
+    assert_eq!(statuses, vec![200, 400, 404, 400, 200]);
+    assert_eq!(
+        batch.results[1].error.as_deref(),
+        Some("Category does not exist")
+    );
+    assert_eq!(batch.results[2].error.as_deref(), Some("Record not found"));
+    assert!(batch.results[2].record.is_none());
+
+    assert_eq!(get_record(&app, &lunch).await.name, "Brunch");
+    let taxi = get_record(&app, &taxi).await;
+    assert_eq!(taxi.category_id.as_deref(), Some(food.as_str()));
+    assert!(taxi.starred);
+}
+
+#[tokio::test]
+async fn test_batch_update_size_limits() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.patch_json("/records", &json!([])).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let entries: Vec<serde_json::Value> = (0..=MAX_BATCH_UPDATE_SIZE)
+        .map(|i| json!({ "id": format!("record-{}", i), "name": "Renamed" }))
+        .collect();
+    let response = app.patch_json("/records", &entries).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        format!(
+            "A batch cannot update more than {} records",
+            MAX_BATCH_UPDATE_SIZE
+        )
+    );
+}
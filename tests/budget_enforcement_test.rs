@@ -0,0 +1,353 @@
+/*!
+ * Budget Enforcement Tests
+ *
+ * Covers categories with enforce_budget: POST /records and PUT /records/{id}
+ * refuse with 422 an expense that would take the month's spending over the
+ * budget, spending exactly up to it is allowed, updates that do not add to an
+ * overspent category still go through, PATCH /records and
+ * POST /records/{id}/duplicate are held to the same limit, concurrent creates
+ * racing the limit cannot both get in, and recurring occurrences that would
+ * overspend are skipped.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{BatchUpdateResponse, Category, Record, RecurringRule};
+use my_budget_server::recurring::materialize_due_rules;
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+use std::sync::Arc;
+
+const JAN_10_2024: i64 = 1704844800;
+const JAN_20_2024: i64 = 1705708800;
+const FEB_10_2024: i64 = 1707523200;
+
+async fn create_enforced_category(app: &TestApp, name: &str, budget: f64) -> String {
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({
+                "name": name,
+                "is_income": false,
+                "monthly_budget": budget,
+                "enforce_budget": true,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let category: Category = response.json();
+    assert!(category.enforce_budget);
+    category.id
+}
+
+async fn post_expense(
+    app: &TestApp,
+    name: &str,
+    amount: f64,
+    category_id: &str,
+) -> (StatusCode, String) {
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": name,
+                "amount": amount,
+                "category_id": category_id,
+                "timestamp": JAN_10_2024,
+            }),
+        )
+        .await;
+    (response.status, response.text())
+}
+
+#[tokio::test]
+async fn test_spending_up_to_budget_allowed_and_past_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let dining = create_enforced_category(&app, "Dining", 100.0).await;
+
+    let (status, body) = post_expense(&app, "Pizza", 60.0, &dining).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let (status, body) = post_expense(&app, "Sushi", 40.01, &dining).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body,
+        "Record would exceed the monthly budget of Dining: budget 100.00, spent 60.00, attempted 40.01"
+    );
+    let (status, body) = post_expense(&app, "Sushi", 40.0, &dining).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let (status, _) = post_expense(&app, "Gum", 0.01, &dining).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 2);
+
+    // Another month has its own budget
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Gum", "amount": 0.01, "category_id": dining, "timestamp": FEB_10_2024 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_unenforced_and_income_records_not_blocked() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "Dining", "is_income": false, "monthly_budget": 10.0 }),
+        )
+        .await;
+    let dining: Category = response.json();
+    assert!(!dining.enforce_budget);
+    let (status, body) = post_expense(&app, "Feast", 50.0, &dining.id).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    // Switching enforcement on later blocks further spending only
+    let response = app
+        .put_json(
+            &format!("/categories/{}", dining.id),
+            &json!({ "enforce_budget": true }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert!(response.json::<Category>().enforce_budget);
+    let (status, _) = post_expense(&app, "Snack", 1.0, &dining.id).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Refund",
+                "amount": 5.0,
+                "category_id": dining.id,
+                "timestamp": JAN_10_2024,
+                "kind": "income",
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_update_rejected_only_when_adding_spending() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let dining = create_enforced_category(&app, "Dining", 100.0).await;
+    let other = create_test_category_via_api(&app, "Other").await;
+
+    let (_, body) = post_expense(&app, "Pizza", 70.0, &dining).await;
+    let pizza: Record = serde_json::from_str(&body).unwrap();
+    let coffee =
+        create_test_record(&data_path, &user_id, "Coffee", 40.0, &other, JAN_20_2024).await;
+
+    // Moving a record into the category
+    let response = app
+        .put_json(
+            &format!("/records/{}", coffee),
+            &json!({ "category_id": dining }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.text(),
+        "Record would exceed the monthly budget of Dining: budget 100.00, spent 70.00, attempted 40.00"
+    );
+
+    // Raising the amount
+    let response = app
+        .put_json(
+            &format!("/records/{}", pizza.id),
+            &json!({ "amount": 100.01 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.text(),
+        "Record would exceed the monthly budget of Dining: budget 100.00, spent 70.00, attempted 100.01"
+    );
+    let response = app
+        .put_json(
+            &format!("/records/{}", pizza.id),
+            &json!({ "amount": 100.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    // The rejected update left the record where it was
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let coffee = records.iter().find(|r| r.id == coffee).unwrap();
+    assert_eq!(coffee.category_id.as_deref(), Some(other.as_str()));
+
+    // Lowering the budget leaves the category overspent, yet a rename or a
+    // smaller amount still goes through
+    app.put_json(
+        &format!("/categories/{}", dining),
+        &json!({ "monthly_budget": 50.0 }),
+    )
+    .await;
+    let response = app
+        .put_json(
+            &format!("/records/{}", pizza.id),
+            &json!({ "name": "Pasta" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let response = app
+        .put_json(
+            &format!("/records/{}", pizza.id),
+            &json!({ "amount": 80.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_batch_update_rejected_past_budget() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let dining = create_enforced_category(&app, "Dining", 100.0).await;
+    let other = create_test_category_via_api(&app, "Other").await;
+
+    let (_, body) = post_expense(&app, "Pizza", 70.0, &dining).await;
+    let pizza: Record = serde_json::from_str(&body).unwrap();
+    let coffee =
+        create_test_record(&data_path, &user_id, "Coffee", 40.0, &other, JAN_20_2024).await;
+
+    let response = app
+        .patch_json(
+            "/records",
+            &json!([
+                { "id": pizza.id, "name": "Pasta" },
+                { "id": coffee, "category_id": dining },
+            ]),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.text(),
+        "Entry 1: Record would exceed the monthly budget of Dining: budget 100.00, spent 70.00, attempted 40.00"
+    );
+
+    let response = app
+        .patch_json(
+            "/records?fail_fast=false",
+            &json!([
+                { "id": coffee, "category_id": dining },
+                { "id": pizza.id, "amount": 100.0 },
+            ]),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let batch: BatchUpdateResponse = response.json();
+    let statuses: Vec<u16> = batch.results.iter().map(|r| r.status).collect();
+    assert_eq!(statuses, vec![422, 200]);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let coffee = records.iter().find(|r| r.id == coffee).unwrap();
+    assert_eq!(coffee.category_id.as_deref(), Some(other.as_str()));
+}
+
+#[tokio::test]
+async fn test_duplicate_rejected_past_budget() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let dining = create_enforced_category(&app, "Dining", 100.0).await;
+
+    let (_, body) = post_expense(&app, "Pizza", 60.0, &dining).await;
+    let pizza: Record = serde_json::from_str(&body).unwrap();
+
+    let response = app
+        .post_json(
+            &format!("/records/{}/duplicate", pizza.id),
+            &json!({ "timestamp": JAN_20_2024 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.text(),
+        "Record would exceed the monthly budget of Dining: budget 100.00, spent 60.00, attempted 60.00"
+    );
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 1);
+
+    // A smaller copy still fits
+    let response = app
+        .post_json(
+            &format!("/records/{}/duplicate", pizza.id),
+            &json!({ "timestamp": JAN_20_2024, "amount": 40.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_creates_cannot_both_fit() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let dining = create_enforced_category(&app, "Dining", 100.0).await;
+    let app = Arc::new(app);
+
+    // Spawned onto the worker threads so the requests really overlap
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let app = Arc::clone(&app);
+            let dining = dining.clone();
+            tokio::spawn(async move {
+                post_expense(&app, &format!("Dinner {}", i), 60.0, &dining).await
+            })
+        })
+        .collect();
+    let mut statuses = Vec::new();
+    for handle in handles {
+        let (status, body) = handle.await.unwrap();
+        assert!(!status.is_server_error(), "{}: {}", status, body);
+        statuses.push(status);
+    }
+
+    statuses.sort();
+    let mut expected = vec![StatusCode::UNPROCESSABLE_ENTITY; 7];
+    expected.insert(0, StatusCode::CREATED);
+    assert_eq!(statuses, expected);
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 1);
+}
+
+#[tokio::test]
+async fn test_recurring_occurrence_past_budget_skipped() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let dining = create_enforced_category(&app, "Dining", 100.0).await;
+    let response = app
+        .post_json(
+            "/recurring",
+            &json!({
+                "name": "Meal plan",
+                "amount": 60.0,
+                "category_id": dining,
+                "interval": "weekly",
+                "start_time": JAN_10_2024,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let rule: RecurringRule = response.json();
+
+    // The second January occurrence would overspend; the rule moves past it
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    assert_eq!(
+        materialize_due_rules(&user_db, JAN_20_2024).await.unwrap(),
+        1
+    );
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 1);
+    let rule: RecurringRule = app.get(&format!("/recurring/{}", rule.id)).await.json();
+    assert!(rule.next_run > JAN_20_2024);
+
+    // A later pass does not retry it
+    assert_eq!(
+        materialize_due_rules(&user_db, JAN_20_2024).await.unwrap(),
+        0
+    );
+}
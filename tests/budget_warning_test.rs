@@ -0,0 +1,155 @@
+/*!
+ * Budget Warning Tests
+ *
+ * Covers the budget_warnings returned by POST /records and by PUT /records/{id}
+ * when the amount or category changes: a record pushing its category past the
+ * monthly budget is still saved but comes back with a warning, while records
+ * under budget, in unbudgeted categories or not counted as spending do not.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{Category, Record};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+async fn create_budgeted_category(app: &TestApp, name: &str, budget: Option<f64>) -> String {
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": name, "is_income": false, "monthly_budget": budget }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    response.json::<Category>().id
+}
+
+#[tokio::test]
+async fn test_crossing_budget_warns() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let dining = create_budgeted_category(&app, "Dining", Some(100.0)).await;
+    let timestamp = recent_timestamp();
+
+    let record = create_record_via_api(
+        &app,
+        &json!({ "name": "Pizza", "amount": 60.0, "category_id": dining, "timestamp": timestamp }),
+    )
+    .await;
+    assert!(record.budget_warnings.is_empty());
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Sushi", "amount": 50.5, "category_id": dining, "timestamp": timestamp }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let body: serde_json::Value = serde_json::from_str(&response.text()).unwrap();
+    assert_eq!(
+        body["budget_warnings"],
+        json!([{
+            "category_id": dining,
+            "category_name": "Dining",
+            "budget": 100.0,
+            "spent": 110.5,
+            "over_by": 10.5,
+        }])
+    );
+
+    // The warning does not stop the record from being saved
+    let (records, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 2);
+    assert!(records.iter().any(|r| r.name == "Sushi"));
+}
+
+#[tokio::test]
+async fn test_no_warning_without_budget_or_spending() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let misc = create_budgeted_category(&app, "Misc", None).await;
+    let dining = create_budgeted_category(&app, "Dining", Some(10.0)).await;
+    let timestamp = recent_timestamp();
+
+    let record = create_record_via_api(
+        &app,
+        &json!({ "name": "Laptop", "amount": 2000.0, "category_id": misc, "timestamp": timestamp }),
+    )
+    .await;
+    assert!(record.budget_warnings.is_empty());
+
+    // Income and foreign currency expenses do not count as spending
+    let record = create_record_via_api(
+        &app,
+        &json!({ "name": "Refund", "amount": 50.0, "category_id": dining, "timestamp": timestamp, "kind": "income" }),
+    )
+    .await;
+    assert!(record.budget_warnings.is_empty());
+    let record = create_record_via_api(
+        &app,
+        &json!({ "name": "Tapas", "amount": 50.0, "category_id": dining, "timestamp": timestamp, "currency": "EUR" }),
+    )
+    .await;
+    assert!(record.budget_warnings.is_empty());
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Ramen", "amount": 5.0, "category_id": dining, "timestamp": timestamp }),
+        )
+        .await;
+    assert!(!response.text().contains("budget_warnings"));
+}
+
+#[tokio::test]
+async fn test_update_warns_when_amount_or_category_changes() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let groceries = create_budgeted_category(&app, "Groceries", Some(100.0)).await;
+    let dining = create_budgeted_category(&app, "Dining", Some(50.0)).await;
+    let timestamp = recent_timestamp();
+
+    create_record_via_api(
+        &app,
+        &json!({ "name": "Pizza", "amount": 40.0, "category_id": dining, "timestamp": timestamp }),
+    )
+    .await;
+    let market = create_record_via_api(
+        &app,
+        &json!({ "name": "Market", "amount": 30.0, "category_id": groceries, "timestamp": timestamp }),
+    )
+    .await;
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", market.id),
+            &json!({ "amount": 120.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let record: Record = response.json();
+    assert_eq!(record.budget_warnings.len(), 1);
+    assert_eq!(record.budget_warnings[0].category_id, groceries);
+    assert_eq!(record.budget_warnings[0].over_by, 20.0);
+
+    // Renaming leaves spending alone, so it does not warn again
+    let response = app
+        .put_json(
+            &format!("/records/{}", market.id),
+            &json!({ "name": "Supermarket" }),
+        )
+        .await;
+    let record: Record = response.json();
+    assert!(record.budget_warnings.is_empty());
+
+    // Moving it to Dining puts that category over instead
+    let response = app
+        .put_json(
+            &format!("/records/{}", market.id),
+            &json!({ "amount": 30.0, "category_id": dining }),
+        )
+        .await;
+    let record: Record = response.json();
+    assert_eq!(record.budget_warnings.len(), 1);
+    assert_eq!(record.budget_warnings[0].category_name, "Dining");
+    assert_eq!(record.budget_warnings[0].spent, 70.0);
+}
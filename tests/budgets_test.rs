@@ -0,0 +1,129 @@
+/*!
+ * Budget Status Tests
+ *
+ * Covers GET /budgets/status: spending per budgeted category within one calendar
+ * month, remaining amounts, percentage used, over-budget flags and month
+ * validation.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{BudgetStatusResponse, Category};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+// 2024-05-01 00:00:00 UTC
+const MAY_START: i64 = 1714521600;
+// 2024-06-01 00:00:00 UTC
+const JUNE_START: i64 = 1717200000;
+
+async fn create_budgeted_category(app: &TestApp, name: &str, budget: Option<f64>) -> String {
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": name, "is_income": false, "monthly_budget": budget }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    response.json::<Category>().id
+}
+
+/// Groceries: 300 budget, 200 spent in May. Dining: 100 budget, 120 spent in May.
+/// Misc has no budget. June spending and income must not count towards May.
+async fn create_budget_fixture(app: &TestApp, data_path: &str, user_id: &str) {
+    let groceries = create_budgeted_category(app, "Groceries", Some(300.0)).await;
+    let dining = create_budgeted_category(app, "Dining", Some(100.0)).await;
+    let misc = create_budgeted_category(app, "Misc", None).await;
+
+    let fixtures = [
+        ("Market", 120.0, &groceries, MAY_START),
+        ("Bakery", 80.0, &groceries, MAY_START + 86400),
+        ("Market", 50.0, &groceries, JUNE_START),
+        ("Pizza", 90.0, &dining, MAY_START + 3600),
+        ("Sushi", 30.0, &dining, JUNE_START - 1),
+        ("Gift", 40.0, &misc, MAY_START),
+    ];
+    for (name, amount, category_id, timestamp) in fixtures {
+        create_test_record(data_path, user_id, name, amount, category_id, timestamp).await;
+    }
+
+    let refund = create_test_record(data_path, user_id, "Refund", 25.0, &dining, MAY_START).await;
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    user_db
+        .write()
+        .await
+        .execute(
+            "UPDATE records SET kind = 'income' WHERE id = ?",
+            [refund.as_str()],
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_budget_status_under_and_over_budget() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_budget_fixture(&app, &data_path, &user_id).await;
+
+    let response = app.get("/budgets/status?month=2024-05").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let status: BudgetStatusResponse = response.json();
+
+    assert_eq!(status.month, "2024-05");
+    assert_eq!(status.currency, "USD");
+    assert_eq!(status.categories.len(), 2);
+
+    let dining = &status.categories[0];
+    assert_eq!(dining.category_name, "Dining");
+    assert_eq!(dining.budget, 100.0);
+    assert_eq!(dining.spent, 120.0);
+    assert_eq!(dining.remaining, -20.0);
+    assert_eq!(dining.percent_used, Some(120.0));
+    assert!(dining.over_budget);
+
+    let groceries = &status.categories[1];
+    assert_eq!(groceries.category_name, "Groceries");
+    assert_eq!(groceries.budget, 300.0);
+    assert_eq!(groceries.spent, 200.0);
+    assert_eq!(groceries.remaining, 100.0);
+    assert!(!groceries.over_budget);
+    assert!((groceries.percent_used.unwrap() - 66.666).abs() < 0.01);
+}
+
+#[tokio::test]
+async fn test_budget_status_month_without_spending() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_budget_fixture(&app, &data_path, &user_id).await;
+
+    let response = app.get("/budgets/status?month=2023-12").await;
+    let status: BudgetStatusResponse = response.json();
+    assert_eq!(status.categories.len(), 2);
+    assert!(status.categories.iter().all(|c| c.spent == 0.0));
+    assert!(status.categories.iter().all(|c| c.remaining == c.budget));
+
+    // Without a month the current one is reported
+    let response = app.get("/budgets/status").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let status: BudgetStatusResponse = response.json();
+    let today = time::OffsetDateTime::now_utc().date();
+    assert_eq!(
+        status.month,
+        format!("{:04}-{:02}", today.year(), today.month() as u8)
+    );
+}
+
+#[tokio::test]
+async fn test_budget_status_invalid_month_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    for month in [
+        "2024-13", "2024-5", "24-05", "2024/05", "May-2024", "2024-00",
+    ] {
+        let response = app.get(&format!("/budgets/status?month={}", month)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", month);
+        assert_eq!(response.text(), "month must be formatted as YYYY-MM");
+    }
+}
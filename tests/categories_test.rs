@@ -1,9 +1,15 @@
 use axum::http::StatusCode;
 use my_budget_server::categories::{
-    extract_category_from_row, validate_category_name, validate_category_not_in_use,
+    compute_category_defaults, extract_category_from_row, validate_category_name,
+    validate_category_not_in_use,
 };
 use my_budget_server::database::get_user_db;
-use my_budget_server::models::Category;
+use my_budget_server::models::{
+    BulkCreateCategoriesResponse, CascadeDeleteCategoryResponse, Category, GetCategoriesResponse,
+    Record,
+};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
 use uuid::Uuid;
 
 mod common;
@@ -44,7 +50,7 @@ async fn get_category_from_db(
 
     let mut rows = conn
         .query(
-            "SELECT id, name, is_income FROM categories WHERE id = ?",
+            "SELECT id, name, is_income, monthly_budget, parent_id, archived, sort_order, enforce_budget FROM categories WHERE id = ?",
             [category_id],
         )
         .await
@@ -54,10 +60,23 @@ async fn get_category_from_db(
         let id: String = row.get(0).expect("Failed to get category id");
         let name: String = row.get(1).expect("Failed to get category name");
         let is_income: bool = row.get(2).expect("Failed to get category is_income");
+        let monthly_budget: Option<f64> = row.get(3).expect("Failed to get category budget");
+        let parent_id: Option<String> = row.get(4).expect("Failed to get category parent_id");
+        let archived: bool = row.get(5).expect("Failed to get category archived");
+        let sort_order: Option<i64> = row.get(6).expect("Failed to get category sort_order");
+        let enforce_budget: bool = row.get(7).expect("Failed to get category enforce_budget");
         Some(Category {
             id,
             name,
             is_income,
+            monthly_budget,
+            parent_id,
+            archived,
+            sort_order,
+            enforce_budget,
+            record_count: None,
+            total_amount: None,
+            last_used_at: None,
         })
     } else {
         None
@@ -72,7 +91,7 @@ async fn get_all_categories_from_db(data_path: &str, user_id: &str) -> Vec<Categ
 
     let mut rows = conn
         .query(
-            "SELECT id, name, is_income FROM categories ORDER BY name ASC",
+            "SELECT id, name, is_income, monthly_budget, parent_id, archived, sort_order, enforce_budget FROM categories ORDER BY name ASC",
             (),
         )
         .await
@@ -83,10 +102,23 @@ async fn get_all_categories_from_db(data_path: &str, user_id: &str) -> Vec<Categ
         let id: String = row.get(0).expect("Failed to get category id");
         let name: String = row.get(1).expect("Failed to get category name");
         let is_income: bool = row.get(2).expect("Failed to get category is_income");
+        let monthly_budget: Option<f64> = row.get(3).expect("Failed to get category budget");
+        let parent_id: Option<String> = row.get(4).expect("Failed to get category parent_id");
+        let archived: bool = row.get(5).expect("Failed to get category archived");
+        let sort_order: Option<i64> = row.get(6).expect("Failed to get category sort_order");
+        let enforce_budget: bool = row.get(7).expect("Failed to get category enforce_budget");
         categories.push(Category {
             id,
             name,
             is_income,
+            monthly_budget,
+            parent_id,
+            archived,
+            sort_order,
+            enforce_budget,
+            record_count: None,
+            total_amount: None,
+            last_used_at: None,
         });
     }
 
@@ -161,7 +193,7 @@ async fn test_extract_category_from_row() {
 
     let mut rows = conn
         .query(
-            "SELECT id, name, is_income FROM categories WHERE id = ?",
+            "SELECT id, name, is_income, monthly_budget, parent_id, archived, sort_order, enforce_budget FROM categories WHERE id = ?",
             [category_id.as_str()],
         )
         .await
@@ -218,26 +250,24 @@ async fn test_validate_category_not_in_use_without_records() {
 
 #[tokio::test]
 async fn test_category_update_database_operations() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create a test category
-    let category_id = create_test_category(&data_path, &user_id, "Original Name").await;
-
-    // Update the category directly in the database (simulating the update operation)
-    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
-    let conn = user_db.write().await;
+    let category_id = create_test_category_via_api(&app, "Original Name").await;
 
+    // Rename it through the API
     let new_name = "Updated Name";
-    let affected_rows = conn
-        .execute(
-            "UPDATE categories SET name = ? WHERE id = ?",
-            (new_name, category_id.as_str()),
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "name": new_name }),
         )
-        .await
-        .expect("Failed to update category");
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
 
-    assert_eq!(affected_rows, 1);
-    drop(conn);
+    let category: Category = response.json();
+    assert_eq!(category.id, category_id);
+    assert_eq!(category.name, new_name);
 
     // Verify the update worked
     let updated_category = get_category_from_db(&data_path, &user_id, &category_id).await;
@@ -247,95 +277,363 @@ async fn test_category_update_database_operations() {
 
 #[tokio::test]
 async fn test_category_update_nonexistent() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     let non_existent_id = Uuid::new_v4().to_string();
 
-    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
-    let conn = user_db.write().await;
-
-    let affected_rows = conn
-        .execute(
-            "UPDATE categories SET name = ? WHERE id = ?",
-            ("New Name", non_existent_id.as_str()),
+    let response = app
+        .put_json(
+            &format!("/categories/{}", non_existent_id),
+            &json!({ "name": "New Name" }),
         )
-        .await
-        .expect("Failed to execute update");
+        .await;
 
-    // Should affect 0 rows since category doesn't exist
-    assert_eq!(affected_rows, 0);
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.text(), "Category not found");
 }
 
 #[tokio::test]
 async fn test_category_duplicate_name_detection() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     // Create two categories
-    let _category1_id = create_test_category(&data_path, &user_id, "Category One").await;
-    let category2_id = create_test_category(&data_path, &user_id, "Category Two").await;
+    let _category1_id = create_test_category_via_api(&app, "Category One").await;
+    let category2_id = create_test_category_via_api(&app, "Category Two").await;
+
+    // Creating a case-insensitive duplicate is rejected
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "CATEGORY ONE", "is_income": false }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    // So is renaming another category onto it
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category2_id),
+            &json!({ "name": "CATEGORY ONE" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.text(),
+        "Category name already exists (case-insensitive)"
+    );
+}
 
-    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
-    let conn = user_db.read().await;
+#[tokio::test]
+async fn test_category_same_name_update_allowed() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
-    // Check for case-insensitive duplicate (excluding current category)
-    let mut rows = conn
-        .query(
-            "SELECT id FROM categories WHERE LOWER(name) = LOWER(?) AND id != ?",
-            ("CATEGORY ONE", category2_id.as_str()),
+    let category_id = create_test_category_via_api(&app, "Category Name").await;
+
+    // Updating to the same name (even with different casing) is not a conflict
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "name": "category name" }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json::<Category>().name, "category name");
+}
+
+#[tokio::test]
+async fn test_category_rename_merges_on_conflict() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "food").await;
+    let food_and_drink = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "Food & Drink", "is_income": false, "monthly_budget": 250.0 }),
         )
         .await
-        .expect("Failed to query for duplicates");
+        .json::<Category>()
+        .id;
+    let snack = create_test_record(&data_path, &user_id, "Snack", 5.0, &food, 1706745600).await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Wine",
+        20.0,
+        &food_and_drink,
+        1706745600,
+    )
+    .await;
 
-    // Should find the existing "Category One"
-    assert!(rows.next().await.expect("Failed to read row").is_some());
+    let response = app
+        .put_json(
+            &format!("/categories/{}", food),
+            &json!({ "name": "food & drink", "merge_on_conflict": true }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let merged: Category = response.json();
+    assert_eq!(merged.id, food_and_drink);
+    assert_eq!(merged.name, "Food & Drink");
+    assert_eq!(merged.monthly_budget, Some(250.0));
+
+    // The renamed category is gone and its records moved over
+    let response = app.get(&format!("/categories/{}", food)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let (records, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 2);
+    assert!(
+        records
+            .iter()
+            .all(|r| r.category_id.as_deref() == Some(food_and_drink.as_str()))
+    );
+    let moved: Record = app.get(&format!("/records/{}", snack)).await.json();
+    assert_eq!(moved.version, 2);
 }
 
 #[tokio::test]
-async fn test_category_same_name_update_allowed() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+async fn test_category_rename_conflict_without_merge() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "food").await;
+    create_test_category_via_api(&app, "Food & Drink").await;
+    create_test_record(&data_path, &user_id, "Snack", 5.0, &food, 1706745600).await;
+
+    for body in [
+        json!({ "name": "Food & Drink" }),
+        json!({ "name": "Food & Drink", "merge_on_conflict": false }),
+    ] {
+        let response = app.put_json(&format!("/categories/{}", food), &body).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+    }
+    let response = app.get(&format!("/categories/{}", food)).await;
+    assert_eq!(response.json::<Category>().name, "food");
+
+    // Without a conflict the flag is a plain rename
+    let response = app
+        .put_json(
+            &format!("/categories/{}", food),
+            &json!({ "name": "Groceries", "merge_on_conflict": true }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let renamed: Category = response.json();
+    assert_eq!(renamed.id, food);
+    assert_eq!(renamed.name, "Groceries");
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records[0].category_id.as_deref(), Some(food.as_str()));
+}
+
+#[tokio::test]
+async fn test_category_monthly_budget_set_and_cleared() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "Groceries", "is_income": false, "monthly_budget": 500 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let category: Category = response.json();
+    assert_eq!(category.monthly_budget, Some(500.0));
+
+    // Changing only the budget keeps the name
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category.id),
+            &json!({ "monthly_budget": "650.50" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let updated: Category = response.json();
+    assert_eq!(updated.name, "Groceries");
+    assert_eq!(updated.monthly_budget, Some(650.5));
+
+    // Renaming leaves the budget alone
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category.id),
+            &json!({ "name": "Food" }),
+        )
+        .await;
+    assert_eq!(response.json::<Category>().monthly_budget, Some(650.5));
+
+    // An explicit null stops tracking the category
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category.id),
+            &json!({ "monthly_budget": null }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let response_body: serde_json::Value = response.json();
+    assert!(response_body["monthly_budget"].is_null());
+
+    let stored = get_category_from_db(&data_path, &user_id, &category.id)
+        .await
+        .unwrap();
+    assert_eq!(stored.name, "Food");
+    assert_eq!(stored.monthly_budget, None);
+}
 
-    let category_id = create_test_category(&data_path, &user_id, "Category Name").await;
+#[tokio::test]
+async fn test_category_invalid_monthly_budget_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "Groceries", "is_income": false, "monthly_budget": -1 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "Monthly budget must be a non-negative number"
+    );
+
+    let category_id = create_test_category_via_api(&app, "Groceries").await;
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "monthly_budget": -0.01 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // A zero budget is valid: nothing may be spent
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "monthly_budget": 0 }),
+        )
+        .await;
+    assert_eq!(response.json::<Category>().monthly_budget, Some(0.0));
+
+    let response = app
+        .put_json(&format!("/categories/{}", category_id), &json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_existing_categories_get_budget_column() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let user_id = Uuid::new_v4().to_string();
+
+    // A user database created before categories had budgets
+    {
+        let path = temp_dir.path().join(format!("user_{}.db", user_id));
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "CREATE TABLE categories (id TEXT PRIMARY KEY, name TEXT UNIQUE NOT NULL, is_income BOOLEAN NOT NULL DEFAULT FALSE)",
+            (),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO categories (id, name, is_income) VALUES ('old', 'Rent', 0)",
+            (),
+        )
+        .await
+        .unwrap();
+    }
 
+    let category = get_category_from_db(data_path, &user_id, "old")
+        .await
+        .unwrap();
+    assert_eq!(category.name, "Rent");
+    assert_eq!(category.monthly_budget, None);
+}
+
+#[tokio::test]
+async fn test_concurrent_creates_differing_in_case() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let food = json!({ "name": "Food", "is_income": false });
+    let lowercase = json!({ "name": "food", "is_income": false });
+    let (first, second) = tokio::join!(
+        app.post_json("/categories", &food),
+        app.post_json("/categories", &lowercase)
+    );
+
+    let mut statuses = vec![first.status, second.status];
+    statuses.sort();
+    assert_eq!(statuses, vec![StatusCode::CREATED, StatusCode::CONFLICT]);
+    assert_eq!(
+        get_all_categories_from_db(&data_path, &user_id).await.len(),
+        1
+    );
+
+    // The database itself refuses a second spelling
     let user_db = get_user_db(&data_path, &user_id).await.unwrap();
-    let conn = user_db.read().await;
+    let result = user_db
+        .write()
+        .await
+        .execute(
+            "INSERT INTO categories (id, name, is_income) VALUES ('other', 'FOOD', 0)",
+            (),
+        )
+        .await;
+    assert!(result.is_err());
+}
 
-    // Check for duplicates excluding the same category (should find none)
-    let mut rows = conn
-        .query(
-            "SELECT id FROM categories WHERE LOWER(name) = LOWER(?) AND id != ?",
-            ("Category Name", category_id.as_str()),
+#[tokio::test]
+async fn test_existing_case_duplicates_renamed() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let user_id = Uuid::new_v4().to_string();
+
+    // A user database created before names were unique ignoring case
+    {
+        let path = temp_dir.path().join(format!("user_{}.db", user_id));
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "CREATE TABLE categories (id TEXT PRIMARY KEY, name TEXT UNIQUE NOT NULL, is_income BOOLEAN NOT NULL DEFAULT FALSE)",
+            (),
         )
         .await
-        .expect("Failed to query for duplicates");
+        .unwrap();
+        conn.execute(
+            "INSERT INTO categories (id, name, is_income) VALUES ('a', 'Food', 0), ('b', 'food', 0), ('c', 'FOOD', 0), ('d', 'Rent', 0)",
+            (),
+        )
+        .await
+        .unwrap();
+    }
 
-    // Should find no duplicates when updating to the same name
-    assert!(rows.next().await.expect("Failed to read row").is_none());
+    let mut names: Vec<(String, String)> = get_all_categories_from_db(data_path, &user_id)
+        .await
+        .into_iter()
+        .map(|c| (c.id, c.name))
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            ("a".to_string(), "Food".to_string()),
+            ("b".to_string(), "food (2)".to_string()),
+            ("c".to_string(), "FOOD (3)".to_string()),
+            ("d".to_string(), "Rent".to_string()),
+        ]
+    );
 }
 
 #[tokio::test]
 async fn test_category_delete_database_operations() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
-    let category_id = create_test_category(&data_path, &user_id, "Test Category").await;
+    let category_id = create_test_category_via_api(&app, "Test Category").await;
 
     // Verify category exists
     let category = get_category_from_db(&data_path, &user_id, &category_id).await;
     assert!(category.is_some());
 
     // Delete the category
-    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
-    let conn = user_db.write().await;
-
-    let affected_rows = conn
-        .execute(
-            "DELETE FROM categories WHERE id = ?",
-            [category_id.as_str()],
-        )
-        .await
-        .expect("Failed to delete category");
-
-    assert_eq!(affected_rows, 1);
-    drop(conn);
+    let response = app.delete(&format!("/categories/{}", category_id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
 
     // Verify category was deleted
     let deleted_category = get_category_from_db(&data_path, &user_id, &category_id).await;
@@ -344,47 +642,29 @@ async fn test_category_delete_database_operations() {
 
 #[tokio::test]
 async fn test_category_delete_nonexistent() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     let non_existent_id = Uuid::new_v4().to_string();
 
-    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
-    let conn = user_db.write().await;
-
-    let affected_rows = conn
-        .execute(
-            "DELETE FROM categories WHERE id = ?",
-            [non_existent_id.as_str()],
-        )
-        .await
-        .expect("Failed to execute delete");
+    let response = app
+        .delete(&format!("/categories/{}", non_existent_id))
+        .await;
 
-    // Should affect 0 rows since category doesn't exist
-    assert_eq!(affected_rows, 0);
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.text(), "Category not found");
 }
 
 #[tokio::test]
 async fn test_category_delete_preserves_others() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
-    let category1_id = create_test_category(&data_path, &user_id, "Category 1").await;
-    let category2_id = create_test_category(&data_path, &user_id, "Category 2").await;
-    let category3_id = create_test_category(&data_path, &user_id, "Category 3").await;
+    let category1_id = create_test_category_via_api(&app, "Category 1").await;
+    let category2_id = create_test_category_via_api(&app, "Category 2").await;
+    let category3_id = create_test_category_via_api(&app, "Category 3").await;
 
     // Delete category 2
-    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
-    let conn = user_db.write().await;
-
-    let affected_rows = conn
-        .execute(
-            "DELETE FROM categories WHERE id = ?",
-            [category2_id.as_str()],
-        )
-        .await
-        .expect("Failed to delete category");
-
-    assert_eq!(affected_rows, 1);
-    drop(conn);
+    let response = app.delete(&format!("/categories/{}", category2_id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
 
     // Verify category 2 is gone but others remain
     let all_categories = get_all_categories_from_db(&data_path, &user_id).await;
@@ -396,6 +676,461 @@ async fn test_category_delete_preserves_others() {
     assert!(remaining_ids.contains(&category3_id));
 }
 
+#[tokio::test]
+async fn test_category_delete_in_use_conflict() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let category_id = create_test_category_via_api(&app, "Busy Category").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Test Record",
+        50.0,
+        &category_id,
+        1234567890,
+    )
+    .await;
+
+    let response = app.delete(&format!("/categories/{}", category_id)).await;
+
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert!(
+        get_category_from_db(&data_path, &user_id, &category_id)
+            .await
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn test_category_delete_with_reassignment() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let old_id = create_test_category_via_api(&app, "Eating Out").await;
+    let target_id = create_test_category_via_api(&app, "Food").await;
+    let other_id = create_test_category_via_api(&app, "Household").await;
+    let record_id =
+        create_test_record(&data_path, &user_id, "Dinner", 50.0, &old_id, 1234567890).await;
+
+    // One split overlaps the target and is merged into it, the other just moves
+    let response = app
+        .post_json(
+            "/records/split",
+            &json!({
+                "name": "Supermarket",
+                "amount": 100.0,
+                "category_id": target_id,
+                "timestamp": recent_timestamp(),
+                "splits": [
+                    { "category_id": old_id, "amount": 30.0 },
+                    { "category_id": target_id, "amount": 50.0 },
+                    { "category_id": other_id, "amount": 20.0 },
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let split_id = response.json::<Record>().id;
+
+    let response = app
+        .post_json(
+            "/recurring",
+            &json!({
+                "name": "Lunch plan",
+                "amount": 80.0,
+                "category_id": old_id,
+                "interval": "monthly",
+                "start_time": time::OffsetDateTime::now_utc().unix_timestamp() + 86400,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+
+    // Usage that would otherwise conflict does not block a reassigning delete
+    let response = app
+        .delete(&format!("/categories/{}?reassign_to={}", old_id, target_id))
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::NO_CONTENT,
+        "{}",
+        response.text()
+    );
+    assert!(
+        get_category_from_db(&data_path, &user_id, &old_id)
+            .await
+            .is_none()
+    );
+
+    let record: Record = app.get(&format!("/records/{}", record_id)).await.json();
+    assert_eq!(record.category_id.as_deref(), Some(target_id.as_str()));
+    assert_eq!(record.version, 2);
+
+    let split: Record = app.get(&format!("/records/{}", split_id)).await.json();
+    assert_eq!(split.splits.len(), 2);
+    let target_portion = split
+        .splits
+        .iter()
+        .find(|portion| portion.category_id == target_id)
+        .unwrap();
+    assert_eq!(target_portion.amount, 80.0);
+    assert!(
+        split
+            .splits
+            .iter()
+            .all(|portion| portion.category_id != old_id)
+    );
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM recurring_rules WHERE category_id = ?",
+            [target_id.as_str()],
+        )
+        .await
+        .unwrap();
+    let rules: u32 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(rules, 1);
+}
+
+#[tokio::test]
+async fn test_category_delete_invalid_reassignment() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let category_id = create_test_category_via_api(&app, "Busy Category").await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Test Record",
+        50.0,
+        &category_id,
+        1234567890,
+    )
+    .await;
+
+    let response = app
+        .delete(&format!(
+            "/categories/{}?reassign_to={}",
+            category_id, category_id
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "Cannot reassign records to the category being deleted"
+    );
+
+    let response = app
+        .delete(&format!(
+            "/categories/{}?reassign_to={}",
+            category_id,
+            Uuid::new_v4()
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "Reassignment target category does not exist"
+    );
+
+    // Nothing moved and the category is still there
+    assert!(
+        get_category_from_db(&data_path, &user_id, &category_id)
+            .await
+            .is_some()
+    );
+    let record: Record = app.get(&format!("/records/{}", record_id)).await.json();
+    assert_eq!(record.category_id.as_deref(), Some(category_id.as_str()));
+    assert_eq!(record.version, 1);
+}
+
+#[tokio::test]
+async fn test_category_cascade_delete() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let junk_id = create_test_category_via_api(&app, "Junk").await;
+    let keep_id = create_test_category_via_api(&app, "Keep").await;
+    for name in ["Test 1", "Test 2", "Test 3"] {
+        create_test_record(&data_path, &user_id, name, 1.0, &junk_id, 1234567890).await;
+    }
+    let kept_record =
+        create_test_record(&data_path, &user_id, "Real", 20.0, &keep_id, 1234567890).await;
+
+    // Without the flag the category in use still conflicts
+    let response = app.delete(&format!("/categories/{}", junk_id)).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    let response = app
+        .delete(&format!("/categories/{}?cascade=false", junk_id))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 4);
+
+    let response = app
+        .delete(&format!("/categories/{}?cascade=true", junk_id))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: CascadeDeleteCategoryResponse = response.json();
+    assert_eq!(body.deleted_records, 3);
+
+    assert!(
+        get_category_from_db(&data_path, &user_id, &junk_id)
+            .await
+            .is_none()
+    );
+    let (remaining, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, kept_record);
+}
+
+#[tokio::test]
+async fn test_category_cascade_delete_unused_and_invalid() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let empty_id = create_test_category_via_api(&app, "Empty").await;
+    let other_id = create_test_category_via_api(&app, "Other").await;
+
+    let response = app
+        .delete(&format!(
+            "/categories/{}?cascade=true&reassign_to={}",
+            empty_id, other_id
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "reassign_to and cascade cannot be combined"
+    );
+
+    let response = app
+        .delete(&format!("/categories/{}?cascade=true", empty_id))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: CascadeDeleteCategoryResponse = response.json();
+    assert_eq!(body.deleted_records, 0);
+}
+
+#[tokio::test]
+async fn test_categories_include_counts() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let food_id = create_test_category_via_api(&app, "Food").await;
+    let fuel_id = create_test_category_via_api(&app, "Fuel").await;
+    create_test_category_via_api(&app, "Gifts").await;
+    for (name, category_id, timestamp) in [
+        ("Lunch", &food_id, 1234567890),
+        ("Dinner", &food_id, 1334567890),
+        ("Snack", &food_id, 1434567890),
+        ("Gas", &fuel_id, 1234567890),
+    ] {
+        create_test_record(&data_path, &user_id, name, 10.0, category_id, timestamp).await;
+    }
+
+    let response = app.get("/categories?include_counts=true").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    let counts: Vec<(&str, Option<u32>)> = body
+        .categories
+        .iter()
+        .map(|c| (c.name.as_str(), c.record_count))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![("Food", Some(3)), ("Fuel", Some(1)), ("Gifts", Some(0))]
+    );
+
+    // Search and pagination apply as usual
+    let response = app
+        .get("/categories?include_counts=true&search=f&limit=1&offset=1")
+        .await;
+    let body: GetCategoriesResponse = response.json();
+    assert_eq!(body.total_count, 3);
+    assert_eq!(body.categories.len(), 1);
+    assert_eq!(body.categories[0].name, "Fuel");
+    assert_eq!(body.categories[0].record_count, Some(1));
+
+    // The cheap path leaves counts out entirely
+    let response = app.get("/categories").await;
+    let body: serde_json::Value = response.json();
+    assert!(body["categories"][0].get("record_count").is_none());
+}
+
+#[tokio::test]
+async fn test_categories_include_totals() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let food_id = create_test_category_via_api(&app, "Food").await;
+    let fuel_id = create_test_category_via_api(&app, "Fuel").await;
+    create_test_category_via_api(&app, "Gifts").await;
+    create_test_category_via_api(&app, "Rent").await;
+    for (name, amount, category_id, timestamp) in [
+        ("Lunch", 12.5, &food_id, 1000),
+        ("Dinner", 30.0, &food_id, 2000),
+        ("Snack", 4.0, &food_id, 5000),
+        ("Gas", 60.0, &fuel_id, 1500),
+    ] {
+        create_test_record(&data_path, &user_id, name, amount, category_id, timestamp).await;
+    }
+
+    // Search narrows the listing, totals only cover the window
+    let response = app
+        .get("/categories?search=f&include_totals=true&include_counts=true&start_time=1000&end_time=2000")
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    assert_eq!(body.total_count, 3);
+    let totals: Vec<(&str, Option<f64>, Option<u32>)> = body
+        .categories
+        .iter()
+        .map(|c| (c.name.as_str(), c.total_amount, c.record_count))
+        .collect();
+    assert_eq!(
+        totals,
+        vec![
+            ("Food", Some(42.5), Some(3)),
+            ("Fuel", Some(60.0), Some(1)),
+            ("Gifts", Some(0.0), Some(0)),
+        ]
+    );
+
+    let response = app
+        .get("/categories?include_totals=true&limit=2&offset=1")
+        .await;
+    let body: GetCategoriesResponse = response.json();
+    assert_eq!(body.categories.len(), 2);
+    assert_eq!(body.categories[0].name, "Fuel");
+    assert_eq!(body.categories[0].total_amount, Some(60.0));
+    assert_eq!(body.categories[0].record_count, None);
+}
+
+#[tokio::test]
+async fn test_categories_totals_empty_range() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let food_id = create_test_category_via_api(&app, "Food").await;
+    create_test_category_via_api(&app, "Fuel").await;
+    create_test_record(&data_path, &user_id, "Lunch", 12.5, &food_id, 1000).await;
+
+    let response = app
+        .get("/categories?include_totals=true&start_time=3000&end_time=4000")
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    assert_eq!(body.categories.len(), 2);
+    assert!(body.categories.iter().all(|c| c.total_amount == Some(0.0)));
+
+    let response = app
+        .get("/categories?include_totals=true&start_time=4000&end_time=3000")
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "start_time cannot be after end_time");
+}
+
+#[tokio::test]
+async fn test_bulk_create_categories() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_category_via_api(&app, "Rent").await;
+
+    let response = app
+        .post_json(
+            "/categories/bulk",
+            &json!({ "names": ["Food", " Travel ", "rent", "food", "Utilities", "TRAVEL"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let body: BulkCreateCategoriesResponse = response.json();
+    let created: Vec<&str> = body.created.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(created, vec!["Food", "Travel", "Utilities"]);
+    assert_eq!(body.skipped, vec!["rent", "food", "TRAVEL"]);
+    assert!(body.created.iter().all(|c| !c.is_income));
+
+    let names: Vec<String> = get_all_categories_from_db(&data_path, &user_id)
+        .await
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    assert_eq!(names, vec!["Food", "Rent", "Travel", "Utilities"]);
+}
+
+#[tokio::test]
+async fn test_bulk_create_categories_invalid_batch() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    // One bad name rejects the whole batch
+    let response = app
+        .post_json("/categories/bulk", &json!({ "names": ["Food", "  "] }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(
+        response.text().starts_with("Name 1: "),
+        "{}",
+        response.text()
+    );
+    assert!(
+        get_all_categories_from_db(&data_path, &user_id)
+            .await
+            .is_empty()
+    );
+
+    let response = app
+        .post_json("/categories/bulk", &json!({ "names": [] }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let names: Vec<String> = (0..101).map(|i| format!("Category {}", i)).collect();
+    let response = app
+        .post_json("/categories/bulk", &json!({ "names": names }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "A batch cannot create more than 100 categories"
+    );
+}
+
+#[tokio::test]
+async fn test_get_single_category() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "Groceries", "is_income": false, "monthly_budget": 250.0 }),
+        )
+        .await;
+    let created: Category = response.json();
+
+    let response = app.get(&format!("/categories/{}", created.id)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let category: Category = response.json();
+    assert_eq!(category.id, created.id);
+    assert_eq!(category.name, "Groceries");
+    assert_eq!(category.monthly_budget, Some(250.0));
+    assert!(!category.archived);
+
+    let response = app.get(&format!("/categories/{}", Uuid::new_v4())).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.text(), "Category not found");
+}
+
+#[tokio::test]
+async fn test_get_single_category_requires_login() {
+    let app = TestApp::new().await;
+
+    let response = app.get(&format!("/categories/{}", Uuid::new_v4())).await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_categories_require_login() {
+    let app = TestApp::new().await;
+
+    let response = app.get("/categories").await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
 #[tokio::test]
 async fn test_category_existence_check() {
     let (data_path, user_id, _temp_dir) = setup_test_environment().await;
@@ -509,3 +1244,167 @@ async fn test_category_after_record_deletion() {
         .expect("Failed to delete category");
     assert_eq!(affected_rows, 1);
 }
+
+#[tokio::test]
+async fn test_category_defaults_skewed_distribution() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+
+    let category_id = create_test_category(&data_path, &user_id, "Transit").await;
+    let base_time = 1700000000;
+
+    // Heavily skewed towards 2.75 with a few outliers
+    for i in 0..7 {
+        create_test_record(
+            &data_path,
+            &user_id,
+            "Subway",
+            2.75,
+            &category_id,
+            base_time + i,
+        )
+        .await;
+    }
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Bus",
+        2.50,
+        &category_id,
+        base_time + 10,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Bus",
+        2.50,
+        &category_id,
+        base_time + 11,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Taxi",
+        30.0,
+        &category_id,
+        base_time + 12,
+    )
+    .await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let defaults = compute_category_defaults(&user_db, &category_id, base_time)
+        .await
+        .expect("Failed to compute category defaults");
+
+    assert_eq!(defaults.sample_size, 10);
+    assert_eq!(defaults.common_amounts, vec![2.75]);
+    assert_eq!(defaults.median_amount, Some(2.75));
+
+    let names: Vec<&str> = defaults
+        .common_names
+        .iter()
+        .map(|n| n.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Subway", "Bus", "Taxi"]);
+    assert_eq!(defaults.common_names[0].count, 7);
+    assert_eq!(defaults.common_names[1].count, 2);
+}
+
+#[tokio::test]
+async fn test_category_defaults_tied_modes_and_even_median() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+
+    let category_id = create_test_category(&data_path, &user_id, "Coffee").await;
+    let base_time = 1700000000;
+
+    for (i, amount) in [3.0, 3.0, 4.5, 4.5, 5.0, 6.0].iter().enumerate() {
+        create_test_record(
+            &data_path,
+            &user_id,
+            "Latte",
+            *amount,
+            &category_id,
+            base_time + i as i64,
+        )
+        .await;
+    }
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let defaults = compute_category_defaults(&user_db, &category_id, base_time)
+        .await
+        .unwrap();
+
+    // Both 3.0 and 4.5 appear twice
+    assert_eq!(defaults.common_amounts, vec![3.0, 4.5]);
+    // Sorted: 3.0 3.0 4.5 4.5 5.0 6.0 -> (4.5 + 4.5) / 2
+    assert_eq!(defaults.median_amount, Some(4.5));
+}
+
+#[tokio::test]
+async fn test_category_defaults_ignores_old_and_other_records() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+
+    let category_id = create_test_category(&data_path, &user_id, "Groceries").await;
+    let other_category_id = create_test_category(&data_path, &user_id, "Rent").await;
+    let base_time = 1700000000;
+
+    // Outside the analysis window
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Old Shop",
+        99.0,
+        &category_id,
+        base_time - 1,
+    )
+    .await;
+    // Different category
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Rent",
+        1200.0,
+        &other_category_id,
+        base_time,
+    )
+    .await;
+    // In window
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Market",
+        20.0,
+        &category_id,
+        base_time + 1,
+    )
+    .await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let defaults = compute_category_defaults(&user_db, &category_id, base_time)
+        .await
+        .unwrap();
+
+    assert_eq!(defaults.sample_size, 1);
+    assert_eq!(defaults.common_amounts, vec![20.0]);
+    assert_eq!(defaults.median_amount, Some(20.0));
+    assert_eq!(defaults.common_names.len(), 1);
+    assert_eq!(defaults.common_names[0].name, "Market");
+}
+
+#[tokio::test]
+async fn test_category_defaults_without_history() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+
+    let category_id = create_test_category(&data_path, &user_id, "Unused").await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let defaults = compute_category_defaults(&user_db, &category_id, 0)
+        .await
+        .expect("Empty history should not be an error");
+
+    assert_eq!(defaults.sample_size, 0);
+    assert!(defaults.common_amounts.is_empty());
+    assert_eq!(defaults.median_amount, None);
+    assert!(defaults.common_names.is_empty());
+}
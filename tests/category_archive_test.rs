@@ -0,0 +1,143 @@
+/*!
+ * Category Archive Tests
+ *
+ * Covers archiving categories through PUT /categories/{id}: archived categories
+ * are hidden from GET /categories unless include_archived=true, refuse new
+ * records, keep their existing records and reports working, and unarchiving
+ * restores normal behavior.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{Category, CategoryTotal, GetCategoriesResponse, Record};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+
+async fn set_archived(app: &TestApp, category_id: &str, archived: bool) -> Category {
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "archived": archived }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let category: Category = response.json();
+    assert_eq!(category.archived, archived);
+    category
+}
+
+async fn listed_names(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app.get(&format!("/categories{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    assert_eq!(body.total_count as usize, body.categories.len());
+    body.categories.into_iter().map(|c| c.name).collect()
+}
+
+async fn post_record(app: &TestApp, category_id: &str) -> (StatusCode, String) {
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Pickup fee",
+                "amount": 15.0,
+                "category_id": category_id,
+                "timestamp": recent_timestamp(),
+            }),
+        )
+        .await;
+    (response.status, response.text())
+}
+
+#[tokio::test]
+async fn test_archived_category_hidden_from_default_list() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let daycare = create_test_category_via_api(&app, "Daycare").await;
+    create_test_category_via_api(&app, "Food").await;
+
+    let category = set_archived(&app, &daycare, true).await;
+    assert_eq!(category.name, "Daycare");
+
+    assert_eq!(listed_names(&app, "").await, vec!["Food"]);
+    assert_eq!(
+        listed_names(&app, "?search=day").await,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        listed_names(&app, "?include_archived=true").await,
+        vec!["Daycare", "Food"]
+    );
+}
+
+#[tokio::test]
+async fn test_archived_category_rejects_new_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let daycare = create_test_category_via_api(&app, "Daycare").await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let existing =
+        create_test_record(&data_path, &user_id, "Tuition", 400.0, &daycare, FEB_START).await;
+    set_archived(&app, &daycare, true).await;
+
+    let (status, message) = post_record(&app, &daycare).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(message, "Category is archived");
+
+    // Split portions cannot land in it either
+    let response = app
+        .post_json(
+            "/records/split",
+            &json!({
+                "name": "Mixed",
+                "amount": 30.0,
+                "category_id": food,
+                "timestamp": recent_timestamp(),
+                "splits": [
+                    { "category_id": food, "amount": 20.0 },
+                    { "category_id": daycare, "amount": 10.0 },
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Category is archived");
+
+    // Existing records stay editable and keep showing up in reports
+    let response = app
+        .put_json(
+            &format!("/records/{}", existing),
+            &json!({ "amount": 410.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let record: Record = response.json();
+    assert_eq!(record.category_id.as_deref(), Some(daycare.as_str()));
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-category?start_time={}&end_time={}",
+            FEB_START,
+            FEB_START + 3600
+        ))
+        .await;
+    let totals: Vec<CategoryTotal> = response.json();
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].category_name, "Daycare");
+    assert_eq!(totals[0].total_amount, 410.0);
+}
+
+#[tokio::test]
+async fn test_unarchive_restores_category() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let daycare = create_test_category_via_api(&app, "Daycare").await;
+    set_archived(&app, &daycare, true).await;
+
+    set_archived(&app, &daycare, false).await;
+    assert_eq!(listed_names(&app, "").await, vec!["Daycare"]);
+    let (status, message) = post_record(&app, &daycare).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", message);
+}
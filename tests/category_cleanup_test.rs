@@ -0,0 +1,166 @@
+/*!
+ * Unused Category Cleanup Tests
+ *
+ * Covers DELETE /categories/unused: categories no record refers to are listed on
+ * a dry run and deleted with confirm=true, while used ones, parents and, on
+ * request, archived or budgeted categories are left alone.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{DeleteUnusedCategoriesResponse, GetCategoriesResponse};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+const JAN_15_2024: i64 = 1705276800;
+
+async fn listed_names(app: &TestApp) -> Vec<String> {
+    let response = app.get("/categories?include_archived=true").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let mut names: Vec<String> = response
+        .json::<GetCategoriesResponse>()
+        .categories
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    names.sort();
+    names
+}
+
+fn removed_names(body: &DeleteUnusedCategoriesResponse) -> Vec<&str> {
+    body.categories.iter().map(|c| c.name.as_str()).collect()
+}
+
+/// Food has a record, Home a child, Archived is archived and Budgeted has a
+/// monthly budget; Travel, Garden and the Home child are plain unused ones.
+async fn seed_categories(app: &TestApp, data_path: &str, user_id: &str) {
+    let food = create_test_category_via_api(app, "Food").await;
+    create_test_record(data_path, user_id, "Lunch", 12.0, &food, JAN_15_2024).await;
+    create_test_category_via_api(app, "Travel").await;
+    create_test_category_via_api(app, "Garden").await;
+
+    let home = create_test_category_via_api(app, "Home").await;
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "Repairs", "is_income": false, "parent_id": home }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+
+    let archived = create_test_category_via_api(app, "Archived").await;
+    let response = app
+        .put_json(
+            &format!("/categories/{}", archived),
+            &json!({ "archived": true }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let budgeted = create_test_category_via_api(app, "Budgeted").await;
+    let response = app
+        .put_json(
+            &format!("/categories/{}", budgeted),
+            &json!({ "monthly_budget": 200.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_dry_run_lists_without_deleting() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    seed_categories(&app, &data_path, &user_id).await;
+    let before = listed_names(&app).await;
+
+    let response = app.delete("/categories/unused").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: DeleteUnusedCategoriesResponse = response.json();
+    assert!(!body.deleted);
+    assert_eq!(
+        removed_names(&body),
+        vec!["Archived", "Budgeted", "Garden", "Repairs", "Travel"]
+    );
+
+    assert_eq!(listed_names(&app).await, before);
+}
+
+#[tokio::test]
+async fn test_confirm_deletes_only_unused() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    seed_categories(&app, &data_path, &user_id).await;
+
+    let response = app.delete("/categories/unused?confirm=true").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: DeleteUnusedCategoriesResponse = response.json();
+    assert!(body.deleted);
+    assert_eq!(
+        removed_names(&body),
+        vec!["Archived", "Budgeted", "Garden", "Repairs", "Travel"]
+    );
+
+    assert_eq!(listed_names(&app).await, vec!["Food", "Home"]);
+
+    // Home lost its only child, so it is unused now
+    let body: DeleteUnusedCategoriesResponse = app.delete("/categories/unused").await.json();
+    assert_eq!(removed_names(&body), vec!["Home"]);
+}
+
+#[tokio::test]
+async fn test_keep_archived_and_budgeted() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    seed_categories(&app, &data_path, &user_id).await;
+
+    let body: DeleteUnusedCategoriesResponse = app
+        .delete("/categories/unused?keep_archived=true")
+        .await
+        .json();
+    assert_eq!(
+        removed_names(&body),
+        vec!["Budgeted", "Garden", "Repairs", "Travel"]
+    );
+
+    let response = app
+        .delete("/categories/unused?confirm=true&keep_archived=true&keep_budgeted=true")
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: DeleteUnusedCategoriesResponse = response.json();
+    assert_eq!(removed_names(&body), vec!["Garden", "Repairs", "Travel"]);
+
+    assert_eq!(
+        listed_names(&app).await,
+        vec!["Archived", "Budgeted", "Food", "Home"]
+    );
+}
+
+#[tokio::test]
+async fn test_split_portions_and_recurring_rules_count_as_use() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let drinks = create_test_category_via_api(&app, "Drinks").await;
+    let rent = create_test_category_via_api(&app, "Rent").await;
+    let record = create_test_record(&data_path, &user_id, "Dinner", 30.0, &food, JAN_15_2024).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    {
+        let conn = user_db.write().await;
+        conn.execute(
+            "INSERT INTO record_splits (record_id, category_id, amount) VALUES (?1, ?2, 20.0), (?1, ?3, 10.0)",
+            (record.as_str(), food.as_str(), drinks.as_str()),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO recurring_rules (id, name, amount, category_id, currency, kind, interval, start_time, next_run) VALUES ('rule', 'Rent', 900.0, ?1, 'USD', 'expense', 'monthly', ?2, ?2)",
+            (rent.as_str(), JAN_15_2024),
+        )
+        .await
+        .unwrap();
+    }
+
+    let body: DeleteUnusedCategoriesResponse = app.delete("/categories/unused").await.json();
+    assert!(body.categories.is_empty(), "{:?}", body.categories);
+}
@@ -0,0 +1,164 @@
+/*!
+ * Category CSV Tests
+ *
+ * Covers carrying a category setup between users: GET /categories/export
+ * writes one CSV row per category and POST /categories/import reads it back,
+ * skipping names that already exist and rejecting the whole file on a bad row.
+ */
+
+mod common;
+
+use axum::http::{StatusCode, header};
+use common::*;
+use my_budget_server::models::{Category, CategoryCsvImportResponse, GetCategoriesResponse};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+/// Every category, archived ones included, with its parent resolved to a name.
+async fn category_setup(app: &TestApp) -> Vec<(Category, Option<String>)> {
+    let response = app.get("/categories?include_archived=true").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let categories = response.json::<GetCategoriesResponse>().categories;
+    categories
+        .iter()
+        .map(|category| {
+            let parent = category.parent_id.as_ref().map(|parent_id| {
+                categories
+                    .iter()
+                    .find(|c| &c.id == parent_id)
+                    .unwrap()
+                    .name
+                    .clone()
+            });
+            (category.clone(), parent)
+        })
+        .collect()
+}
+
+async fn import(app: &TestApp, csv: &str) -> (StatusCode, String) {
+    let response = app
+        .post_json("/categories/import", &json!({ "csv": csv }))
+        .await;
+    (response.status, response.text())
+}
+
+#[tokio::test]
+async fn test_export_import_round_trip() {
+    let (source, _data_path, _user_id) = setup_test_app().await;
+    let food = source
+        .post_json(
+            "/categories",
+            &json!({ "name": "Food", "is_income": false, "monthly_budget": 400.5 }),
+        )
+        .await
+        .json::<Category>()
+        .id;
+    source
+        .post_json(
+            "/categories",
+            &json!({ "name": "Eating out, bars", "is_income": false, "parent_id": food }),
+        )
+        .await;
+    source
+        .post_json(
+            "/categories",
+            &json!({ "name": "Salary", "is_income": true }),
+        )
+        .await;
+    let daycare = create_test_category_via_api(&source, "Daycare").await;
+    source
+        .put_json(
+            &format!("/categories/{}", daycare),
+            &json!({ "archived": true }),
+        )
+        .await;
+    source
+        .put_json("/categories/order", &json!({ "category_ids": [food] }))
+        .await;
+
+    let response = source.get("/categories/export").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(
+        response.headers[header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let csv = response.text();
+    assert!(csv.starts_with("name,is_income,monthly_budget,parent,archived,sort_order\n"));
+    assert!(csv.contains("\"Eating out, bars\""));
+
+    let (target, _data_path, _user_id) = setup_test_app().await;
+    let (status, body) = import(&target, &csv).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let summary: CategoryCsvImportResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(summary.categories_created, 4);
+    assert_eq!(summary.categories_skipped, 0);
+
+    let expected = category_setup(&source).await;
+    let imported = category_setup(&target).await;
+    assert_eq!(imported.len(), expected.len());
+    for ((imported, imported_parent), (expected, expected_parent)) in imported.iter().zip(&expected)
+    {
+        assert_ne!(imported.id, expected.id);
+        assert_eq!(imported.name, expected.name);
+        assert_eq!(imported.is_income, expected.is_income);
+        assert_eq!(imported.monthly_budget, expected.monthly_budget);
+        assert_eq!(imported_parent, expected_parent);
+        assert_eq!(imported.archived, expected.archived);
+        assert_eq!(imported.sort_order, expected.sort_order);
+    }
+
+    // Importing the same file again only finds duplicates
+    let (status, body) = import(&target, &csv).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let summary: CategoryCsvImportResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(summary.categories_created, 0);
+    assert_eq!(summary.categories_skipped, 4);
+}
+
+#[tokio::test]
+async fn test_import_skips_case_insensitive_duplicates() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    create_test_category_via_api(&app, "Rent").await;
+
+    // Parents may be listed after their children
+    let csv = "name,parent\nGroceries,Household\nrent,\nHousehold,\nGROCERIES,\n";
+    let (status, body) = import(&app, csv).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let summary: CategoryCsvImportResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(summary.categories_created, 2);
+    assert_eq!(summary.categories_skipped, 2);
+
+    let setup = category_setup(&app).await;
+    let names: Vec<(&str, Option<&str>)> = setup
+        .iter()
+        .map(|(category, parent)| (category.name.as_str(), parent.as_deref()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("Groceries", Some("Household")),
+            ("Household", None),
+            ("Rent", None)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_import_invalid_row_rejects_file() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let csv = "name,monthly_budget\nFood,100\nTravel,lots\n";
+    let (status, body) = import(&app, csv).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body, "Line 3: monthly_budget must be a number");
+
+    let (status, body) = import(&app, "name,parent\nFood,Missing\n").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body, "Line 2: Parent category does not exist");
+
+    let (status, body) = import(&app, "title\nFood\n").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body, "CSV header needs a name column");
+
+    assert!(category_setup(&app).await.is_empty());
+}
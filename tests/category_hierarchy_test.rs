@@ -0,0 +1,164 @@
+/*!
+ * Category Hierarchy Tests
+ *
+ * Covers nesting categories through parent_id: validation of parents and
+ * cycles on create and update, blocking deletion of categories that still
+ * have children and the rollup=true mode of the per-category summary.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{Category, CategoryTotal};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+
+async fn create_child_category(app: &TestApp, name: &str, parent_id: &str) -> String {
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": name, "is_income": false, "parent_id": parent_id }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let category: Category = response.json();
+    assert_eq!(category.parent_id.as_deref(), Some(parent_id));
+    category.id
+}
+
+async fn set_parent(
+    app: &TestApp,
+    category_id: &str,
+    parent_id: serde_json::Value,
+) -> (StatusCode, String) {
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "parent_id": parent_id }),
+        )
+        .await;
+    (response.status, response.text())
+}
+
+#[tokio::test]
+async fn test_cycles_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let restaurants = create_child_category(&app, "Restaurants", &food).await;
+    let fast_food = create_child_category(&app, "Fast food", &restaurants).await;
+
+    let (status, message) = set_parent(&app, &food, json!(food)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(message, "A category cannot be its own ancestor");
+
+    // Food -> Restaurants -> Fast food -> Food would loop
+    let (status, message) = set_parent(&app, &food, json!(fast_food)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(message, "A category cannot be its own ancestor");
+
+    let (status, message) = set_parent(&app, &food, json!("missing")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(message, "Parent category does not exist");
+
+    // Moving a subtree elsewhere and back to the top level is fine
+    let travel = create_test_category_via_api(&app, "Travel").await;
+    let (status, _) = set_parent(&app, &restaurants, json!(travel)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = set_parent(&app, &restaurants, json!(null)).await;
+    assert_eq!(status, StatusCode::OK);
+    let category: Category = serde_json::from_str(&body).unwrap();
+    assert_eq!(category.parent_id, None);
+    assert_eq!(category.name, "Restaurants");
+}
+
+#[tokio::test]
+async fn test_create_with_missing_parent_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": "Groceries", "is_income": false, "parent_id": "missing" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Parent category does not exist");
+}
+
+#[tokio::test]
+async fn test_delete_with_children_blocked() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let groceries = create_child_category(&app, "Groceries", &food).await;
+
+    let response = app.delete(&format!("/categories/{}", food)).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.text(),
+        "Cannot delete category: it has child categories; move or delete them first"
+    );
+
+    // Once the child is moved out the parent can go
+    let (status, _) = set_parent(&app, &groceries, json!(null)).await;
+    assert_eq!(status, StatusCode::OK);
+    let response = app.delete(&format!("/categories/{}", food)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_category_summary_rollup() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let groceries = create_child_category(&app, "Groceries", &food).await;
+    let restaurants = create_child_category(&app, "Restaurants", &food).await;
+    let fast_food = create_child_category(&app, "Fast food", &restaurants).await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+
+    let fixtures = [
+        ("Snack", 5.0, &food),
+        ("Market", 40.0, &groceries),
+        ("Bistro", 30.0, &restaurants),
+        ("Burger", 12.0, &fast_food),
+        ("Train", 60.0, &travel),
+    ];
+    for (offset, (name, amount, category_id)) in fixtures.into_iter().enumerate() {
+        create_test_record(
+            &data_path,
+            &user_id,
+            name,
+            amount,
+            category_id,
+            FEB_START + offset as i64 * 60,
+        )
+        .await;
+    }
+    let window = format!("start_time={}&end_time={}", FEB_START, FEB_START + 3600);
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-category?{}&rollup=true",
+            window
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let totals: Vec<CategoryTotal> = response.json();
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[0].category_id.as_deref(), Some(food.as_str()));
+    assert_eq!(totals[0].total_amount, 87.0);
+    assert_eq!(totals[0].record_count, 4);
+    assert_eq!(totals[1].category_id.as_deref(), Some(travel.as_str()));
+    assert_eq!(totals[1].total_amount, 60.0);
+
+    // Without rollup every category stands alone
+    let response = app
+        .get(&format!("/records/summary/by-category?{}", window))
+        .await;
+    let totals: Vec<CategoryTotal> = response.json();
+    assert_eq!(totals.len(), 5);
+    assert_eq!(totals[0].category_name, "Travel");
+    assert_eq!(totals[1].category_name, "Groceries");
+}
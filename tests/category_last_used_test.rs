@@ -0,0 +1,116 @@
+/*!
+ * Category Last Used Tests
+ *
+ * Covers last_used_at on GET /categories?include_usage=true and on the single
+ * category GET: the latest record timestamp for used categories, null for
+ * unused ones, following new records, and left out of the default listing.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{Category, GetCategoriesResponse};
+use my_budget_server::test_support::TestApp;
+
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+
+async fn last_used(app: &TestApp) -> Vec<(String, Option<i64>)> {
+    let response = app.get("/categories?include_usage=true").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    body.categories
+        .into_iter()
+        .map(|c| (c.name, c.last_used_at.expect("last_used_at listed")))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_list_last_used_at() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+
+    create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, FEB_START).await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Dinner",
+        30.0,
+        &food,
+        FEB_START + 7200,
+    )
+    .await;
+    assert_eq!(
+        last_used(&app).await,
+        vec![
+            ("Food".to_string(), Some(FEB_START + 7200)),
+            ("Travel".to_string(), None),
+        ]
+    );
+
+    // New records move the timestamp, older ones leave it alone
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Breakfast",
+        8.0,
+        &food,
+        FEB_START - 60,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Train",
+        40.0,
+        &travel,
+        FEB_START + 86400,
+    )
+    .await;
+    assert_eq!(
+        last_used(&app).await,
+        vec![
+            ("Food".to_string(), Some(FEB_START + 7200)),
+            ("Travel".to_string(), Some(FEB_START + 86400)),
+        ]
+    );
+
+    // Unused categories report an explicit null, and the default listing none
+    let unused_id = create_test_category_via_api(&app, "Unused").await;
+    let response = app.get("/categories?include_usage=true").await;
+    assert!(response.text().contains("\"last_used_at\":null"));
+    let response = app.get("/categories").await;
+    assert!(!response.text().contains("last_used_at"));
+    let body: GetCategoriesResponse = response.json();
+    assert!(body.categories.iter().all(|c| c.last_used_at.is_none()));
+
+    // Usage combines with counts and totals
+    let response = app
+        .get("/categories?include_usage=true&include_counts=true&include_totals=true")
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    let food_listed = body.categories.iter().find(|c| c.id == food).unwrap();
+    assert_eq!(food_listed.record_count, Some(3));
+    assert_eq!(food_listed.total_amount, Some(50.0));
+    assert_eq!(food_listed.last_used_at, Some(Some(FEB_START + 7200)));
+    let unused = body.categories.iter().find(|c| c.id == unused_id).unwrap();
+    assert_eq!(unused.last_used_at, Some(None));
+}
+
+#[tokio::test]
+async fn test_single_category_last_used_at() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+
+    let response = app.get(&format!("/categories/{}", food)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert!(response.text().contains("\"last_used_at\":null"));
+    assert_eq!(response.json::<Category>().last_used_at, Some(None));
+
+    create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, FEB_START).await;
+    let category: Category = app.get(&format!("/categories/{}", food)).await.json();
+    assert_eq!(category.last_used_at, Some(Some(FEB_START)));
+}
@@ -0,0 +1,124 @@
+/*!
+ * Category Name Normalization Tests
+ *
+ * Covers NFC normalization of category names: precomposed and combining forms
+ * colliding on create and rename, names stored composed, full-width characters
+ * left distinct, search matching either form, and legacy duplicates reported by
+ * GET /categories/duplicates.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{Category, CategoryDuplicateGroup, GetCategoriesResponse};
+use serde_json::json;
+
+const CAFE_NFC: &str = "Caf\u{e9}";
+const CAFE_NFD: &str = "Cafe\u{301}";
+const CAFE_FULL_WIDTH: &str = "\u{ff23}\u{ff41}\u{ff46}\u{ff45}";
+
+#[tokio::test]
+async fn test_create_and_rename_normalize_names() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    // Combining input is stored composed
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": format!("  {}  ", CAFE_NFD), "is_income": false }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let cafe: Category = response.json();
+    assert_eq!(cafe.name, CAFE_NFC);
+
+    for name in [CAFE_NFC, CAFE_NFD, " cafe\u{301}"] {
+        let response = app
+            .post_json("/categories", &json!({ "name": name, "is_income": false }))
+            .await;
+        assert_eq!(response.status, StatusCode::CONFLICT, "{}", name);
+        assert_eq!(
+            response.text(),
+            "Category name already exists (case-insensitive)"
+        );
+    }
+
+    let food = create_test_category_via_api(&app, "Food").await;
+    let response = app
+        .put_json(
+            &format!("/categories/{}", food),
+            &json!({ "name": CAFE_NFD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    // Full-width letters are not folded by NFC and stay a separate name
+    let response = app
+        .post_json(
+            "/categories",
+            &json!({ "name": CAFE_FULL_WIDTH, "is_income": false }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let response = app
+        .put_json(
+            &format!("/categories/{}", food),
+            &json!({ "name": format!("{}\u{301}", CAFE_FULL_WIDTH) }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_search_matches_either_form() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    create_test_category_via_api(&app, CAFE_NFC).await;
+    create_test_category_via_api(&app, CAFE_FULL_WIDTH).await;
+    create_test_category_via_api(&app, "Cafeteria").await;
+
+    let response = app
+        .get(&format!("/categories?search={}", "afe%CC%81"))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    let names: Vec<&str> = body.categories.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec![CAFE_NFC]);
+
+    let response = app
+        .get(&format!("/categories?search={}", "%EF%BD%86%EF%BD%85"))
+        .await;
+    let body: GetCategoriesResponse = response.json();
+    let names: Vec<&str> = body.categories.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec![CAFE_FULL_WIDTH]);
+}
+
+#[tokio::test]
+async fn test_legacy_duplicates_reported() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let composed = create_test_category_via_api(&app, CAFE_NFC).await;
+    create_test_category_via_api(&app, "Food").await;
+
+    let response = app.get("/categories/duplicates").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert!(response.json::<Vec<CategoryDuplicateGroup>>().is_empty());
+
+    // A name stored before normalization, which the unique index lets through
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    user_db
+        .write()
+        .await
+        .execute(
+            "INSERT INTO categories (id, name, is_income) VALUES ('legacy', ?, 0)",
+            [CAFE_NFD],
+        )
+        .await
+        .unwrap();
+
+    let groups: Vec<CategoryDuplicateGroup> = app.get("/categories/duplicates").await.json();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].normalized_name, CAFE_NFC);
+    let ids: Vec<&str> = groups[0].categories.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, vec![composed.as_str(), "legacy"]);
+}
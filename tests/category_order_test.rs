@@ -0,0 +1,118 @@
+/*!
+ * Category Order Tests
+ *
+ * Covers manual category ordering: PUT /categories/order rewrites the order,
+ * keeping categories left out of the list after the listed ones, and
+ * GET /categories?sort=manual returns it. Unknown and repeated ids are rejected
+ * without changing anything.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{Category, GetCategoriesResponse};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+async fn manual_order(app: &TestApp) -> Vec<String> {
+    let response = app.get("/categories?sort=manual").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: GetCategoriesResponse = response.json();
+    body.categories.into_iter().map(|c| c.name).collect()
+}
+
+#[tokio::test]
+async fn test_reorder_round_trip() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let dining = create_test_category_via_api(&app, "Dining").await;
+    let groceries = create_test_category_via_api(&app, "Groceries").await;
+    let rent = create_test_category_via_api(&app, "Rent").await;
+
+    // Before any reordering manual order falls back to names
+    assert_eq!(
+        manual_order(&app).await,
+        vec!["Dining", "Groceries", "Rent"]
+    );
+
+    let response = app
+        .put_json(
+            "/categories/order",
+            &json!({ "category_ids": [groceries, rent, dining] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let categories: Vec<Category> = response.json();
+    let order: Vec<(&str, Option<i64>)> = categories
+        .iter()
+        .map(|c| (c.name.as_str(), c.sort_order))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            ("Groceries", Some(1)),
+            ("Rent", Some(2)),
+            ("Dining", Some(3))
+        ]
+    );
+    assert_eq!(
+        manual_order(&app).await,
+        vec!["Groceries", "Rent", "Dining"]
+    );
+
+    // The default listing stays alphabetical
+    let response = app.get("/categories").await;
+    let body: GetCategoriesResponse = response.json();
+    assert_eq!(body.categories[0].name, "Dining");
+
+    // Categories left out keep their relative order after the listed ones
+    let response = app
+        .put_json("/categories/order", &json!({ "category_ids": [dining] }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(
+        manual_order(&app).await,
+        vec!["Dining", "Groceries", "Rent"]
+    );
+
+    // New categories come after the ordered ones
+    create_test_category_via_api(&app, "Books").await;
+    assert_eq!(
+        manual_order(&app).await,
+        vec!["Dining", "Groceries", "Rent", "Books"]
+    );
+}
+
+#[tokio::test]
+async fn test_reorder_rejects_unknown_and_repeated_ids() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let dining = create_test_category_via_api(&app, "Dining").await;
+    let groceries = create_test_category_via_api(&app, "Groceries").await;
+
+    let response = app
+        .put_json(
+            "/categories/order",
+            &json!({ "category_ids": [groceries, "missing", dining] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Category missing does not exist");
+
+    let response = app
+        .put_json(
+            "/categories/order",
+            &json!({ "category_ids": [groceries, groceries] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // Nothing was reordered
+    assert_eq!(manual_order(&app).await, vec!["Dining", "Groceries"]);
+
+    let response = app.get("/categories?sort=popularity").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "sort must be 'name', 'manual' or 'usage', got 'popularity'"
+    );
+}
@@ -0,0 +1,192 @@
+/*!
+ * Category Rules Tests
+ *
+ * Covers keyword rules under /categories/{id}/rules (validation, per-category
+ * uniqueness, update and delete) and POST /records/categorize: overlapping
+ * keywords resolved by priority, no match, lookups by record id, and rules
+ * going away with their category.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{CategorizeRecordResponse, CategoryRule};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+async fn create_rule(app: &TestApp, category_id: &str, keyword: &str, priority: i64) -> String {
+    let response = app
+        .post_json(
+            &format!("/categories/{}/rules", category_id),
+            &json!({ "keyword": keyword, "priority": priority }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    response.json::<CategoryRule>().id
+}
+
+async fn categorize(app: &TestApp, name: &str) -> CategorizeRecordResponse {
+    let response = app
+        .post_json("/records/categorize", &json!({ "name": name }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+#[tokio::test]
+async fn test_rule_crud_and_validation() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let transport = create_test_category_via_api(&app, "Transport").await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let rules_path = format!("/categories/{}/rules", transport);
+
+    let uber = create_rule(&app, &transport, "  Uber ", 5).await;
+    create_rule(&app, &transport, "taxi", 10).await;
+    // The same keyword may point at another category
+    create_rule(&app, &food, "uber", 0).await;
+
+    let response = app.get(&rules_path).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let rules: Vec<CategoryRule> = response.json();
+    let keywords: Vec<(&str, i64)> = rules
+        .iter()
+        .map(|r| (r.keyword.as_str(), r.priority))
+        .collect();
+    assert_eq!(keywords, vec![("taxi", 10), ("Uber", 5)]);
+
+    let response = app
+        .post_json(&rules_path, &json!({ "keyword": "UBER" }))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(
+        response.text(),
+        "Keyword already exists for this category (case-insensitive)"
+    );
+    let response = app
+        .post_json(&rules_path, &json!({ "keyword": "  " }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Keyword cannot be empty");
+    let response = app
+        .post_json(&rules_path, &json!({ "keyword": "x".repeat(101) }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let response = app
+        .post_json("/categories/missing/rules", &json!({ "keyword": "bus" }))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .put_json(
+            &format!("{}/{}", rules_path, uber),
+            &json!({ "keyword": "uber ride", "priority": 20 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let rule: CategoryRule = response.json();
+    assert_eq!(rule.keyword, "uber ride");
+    assert_eq!(rule.priority, 20);
+    let response = app
+        .put_json(
+            &format!("{}/{}", rules_path, uber),
+            &json!({ "keyword": "Taxi" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    // A rule is only reachable under its own category
+    let response = app
+        .delete(&format!("/categories/{}/rules/{}", food, uber))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.delete(&format!("{}/{}", rules_path, uber)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    let rules: Vec<CategoryRule> = app.get(&rules_path).await.json();
+    assert_eq!(rules.len(), 1);
+}
+
+#[tokio::test]
+async fn test_categorize_by_priority() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let transport = create_test_category_via_api(&app, "Transport").await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_rule(&app, &transport, "uber", 10).await;
+    let uber_eats = create_rule(&app, &food, "uber eats", 20).await;
+
+    let suggestion = categorize(&app, "UBER EATS *Pizza").await;
+    assert_eq!(suggestion.category.unwrap().id, food);
+    assert_eq!(suggestion.rule.unwrap().id, uber_eats);
+
+    let suggestion = categorize(&app, "Uber trip downtown").await;
+    assert_eq!(suggestion.category.unwrap().name, "Transport");
+
+    // Lowering the priority lets the broader keyword win
+    app.put_json(
+        &format!("/categories/{}/rules/{}", food, uber_eats),
+        &json!({ "priority": 1 }),
+    )
+    .await;
+    let suggestion = categorize(&app, "UBER EATS *Pizza").await;
+    assert_eq!(suggestion.category.unwrap().id, transport);
+}
+
+#[tokio::test]
+async fn test_categorize_without_match() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let transport = create_test_category_via_api(&app, "Transport").await;
+    create_rule(&app, &transport, "uber", 0).await;
+
+    let response = app
+        .post_json("/records/categorize", &json!({ "name": "Groceries" }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_str(&response.text()).unwrap();
+    assert_eq!(body, json!({ "category": null, "rule": null }));
+
+    // A record id uses that record's name, without changing the record
+    let record_id =
+        create_test_record(&data_path, &user_id, "Uber", 12.0, &transport, 1706745600).await;
+    let response = app
+        .post_json("/records/categorize", &json!({ "record_id": record_id }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let suggestion: CategorizeRecordResponse = response.json();
+    assert_eq!(suggestion.category.unwrap().id, transport);
+
+    let response = app
+        .post_json("/records/categorize", &json!({ "record_id": "missing" }))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    for body in [json!({}), json!({ "name": "Uber", "record_id": record_id })] {
+        let response = app.post_json("/records/categorize", &body).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.text(),
+            "Exactly one of name or record_id is required"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_deleting_category_removes_rules() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let transport = create_test_category_via_api(&app, "Transport").await;
+    create_rule(&app, &transport, "uber", 0).await;
+    create_rule(&app, &transport, "taxi", 0).await;
+
+    let response = app.delete(&format!("/categories/{}", transport)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let suggestion = categorize(&app, "Uber").await;
+    assert!(suggestion.category.is_none());
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query("SELECT COUNT(*) FROM category_rules", ())
+        .await
+        .unwrap();
+    let count: u32 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(count, 0);
+}
@@ -0,0 +1,135 @@
+/*!
+ * Category Stats Tests
+ *
+ * Covers GET /categories/{id}/stats: all-time record count, total and first and
+ * last use, UTC monthly buckets across a year boundary with zero-filled gaps,
+ * the default 12 month window, unused and unknown categories and window
+ * validation.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{CategoryMonthTotal, CategoryStatsResponse};
+
+// 2023-11-01 00:00:00 UTC
+const NOV_2023_START: i64 = 1698796800;
+// 2023-12-01 00:00:00 UTC
+const DEC_2023_START: i64 = 1701388800;
+// 2024-01-01 00:00:00 UTC
+const JAN_2024_START: i64 = 1704067200;
+// 2024-03-01 00:00:00 UTC
+const MAR_2024_START: i64 = 1709251200;
+
+fn month(month: &str, total_amount: f64, record_count: u32) -> CategoryMonthTotal {
+    CategoryMonthTotal {
+        month: month.to_string(),
+        total_amount,
+        record_count,
+    }
+}
+
+#[tokio::test]
+async fn test_category_stats_across_year_boundary() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+
+    let fixtures = [
+        ("Market", 20.0, &food, DEC_2023_START + 86400),
+        ("Party", 30.0, &food, JAN_2024_START - 1),
+        ("Brunch", 15.0, &food, JAN_2024_START),
+        ("Bakery", 5.0, &food, JAN_2024_START + 10 * 86400),
+        ("Dinner", 100.0, &food, MAR_2024_START + 3600),
+        ("Train", 60.0, &travel, DEC_2023_START + 86400),
+    ];
+    for (name, amount, category_id, timestamp) in fixtures {
+        create_test_record(&data_path, &user_id, name, amount, category_id, timestamp).await;
+    }
+
+    let response = app
+        .get(&format!(
+            "/categories/{}/stats?start_time={}&end_time={}",
+            food,
+            NOV_2023_START,
+            MAR_2024_START - 1
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let stats: CategoryStatsResponse = response.json();
+    assert_eq!(stats.category_id, food);
+
+    // All-time figures ignore the window
+    assert_eq!(stats.total_records, 5);
+    assert_eq!(stats.total_amount, 170.0);
+    assert_eq!(stats.first_used_at, Some(DEC_2023_START + 86400));
+    assert_eq!(stats.last_used_at, Some(MAR_2024_START + 3600));
+
+    // The last second of December and midnight of January land in their own months
+    assert_eq!(
+        stats.monthly,
+        vec![
+            month("2023-11", 0.0, 0),
+            month("2023-12", 50.0, 2),
+            month("2024-01", 20.0, 2),
+            month("2024-02", 0.0, 0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_category_stats_unused_category() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+
+    let response = app.get(&format!("/categories/{}/stats", food)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let stats: CategoryStatsResponse = response.json();
+    assert_eq!(stats.total_records, 0);
+    assert_eq!(stats.total_amount, 0.0);
+    assert_eq!(stats.first_used_at, None);
+    assert_eq!(stats.last_used_at, None);
+
+    // By default the last 12 months are listed, ending with the current one
+    assert_eq!(stats.monthly.len(), 12);
+    assert!(
+        stats
+            .monthly
+            .iter()
+            .all(|m| m.total_amount == 0.0 && m.record_count == 0)
+    );
+    let today = time::OffsetDateTime::now_utc().date();
+    assert_eq!(
+        stats.monthly[11].month,
+        format!("{:04}-{:02}", today.year(), today.month() as u8)
+    );
+}
+
+#[tokio::test]
+async fn test_category_stats_errors() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+
+    let response = app.get("/categories/missing/stats").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.text(), "Category not found");
+
+    let response = app
+        .get(&format!(
+            "/categories/{}/stats?start_time={}&end_time={}",
+            food, JAN_2024_START, DEC_2023_START
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "start_time cannot be after end_time");
+
+    let response = app
+        .get(&format!(
+            "/categories/{}/stats?start_time=0&end_time={}",
+            food, JAN_2024_START
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Time range cannot exceed 120 months");
+}
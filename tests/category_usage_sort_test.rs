@@ -0,0 +1,88 @@
+/*!
+ * Category Usage Sort Tests
+ *
+ * Covers GET /categories?sort=usage: most used categories first by record count,
+ * ties by name, unused categories last, and the order holding together with
+ * search, include_counts and limit/offset paging.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::GetCategoriesResponse;
+use my_budget_server::test_support::TestApp;
+
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+
+async fn listed(app: &TestApp, query: &str) -> GetCategoriesResponse {
+    let response = app.get(&format!("/categories?sort=usage{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+fn names(body: &GetCategoriesResponse) -> Vec<&str> {
+    body.categories.iter().map(|c| c.name.as_str()).collect()
+}
+
+/// Bakery and Groceries with 3 records each, Bars with 1, Books and Bus unused.
+async fn create_usage_fixture(app: &TestApp, data_path: &str, user_id: &str) {
+    let bakery = create_test_category_via_api(app, "Bakery").await;
+    let groceries = create_test_category_via_api(app, "Groceries").await;
+    let bars = create_test_category_via_api(app, "Bars").await;
+    create_test_category_via_api(app, "Books").await;
+    create_test_category_via_api(app, "Bus").await;
+
+    let fixtures = [
+        &groceries, &bakery, &groceries, &bars, &bakery, &groceries, &bakery,
+    ];
+    for (offset, category_id) in fixtures.into_iter().enumerate() {
+        create_test_record(
+            data_path,
+            user_id,
+            "Purchase",
+            10.0,
+            category_id,
+            FEB_START + offset as i64 * 60,
+        )
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn test_sort_by_usage() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_usage_fixture(&app, &data_path, &user_id).await;
+
+    let body = listed(&app, "&include_counts=true").await;
+    assert_eq!(
+        names(&body),
+        vec!["Bakery", "Groceries", "Bars", "Books", "Bus"]
+    );
+    let counts: Vec<Option<u32>> = body.categories.iter().map(|c| c.record_count).collect();
+    assert_eq!(counts, vec![Some(3), Some(3), Some(1), Some(0), Some(0)]);
+
+    // Counts only drive the order unless asked for
+    let body = listed(&app, "").await;
+    assert_eq!(names(&body)[0], "Bakery");
+    assert!(body.categories.iter().all(|c| c.record_count.is_none()));
+}
+
+#[tokio::test]
+async fn test_sort_by_usage_with_search_and_paging() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_usage_fixture(&app, &data_path, &user_id).await;
+
+    let body = listed(&app, "&search=b").await;
+    assert_eq!(names(&body), vec!["Bakery", "Bars", "Books", "Bus"]);
+    assert_eq!(body.total_count, 4);
+
+    let first_page = listed(&app, "&limit=2&offset=0").await;
+    let second_page = listed(&app, "&limit=2&offset=2").await;
+    let last_page = listed(&app, "&limit=2&offset=4").await;
+    assert_eq!(names(&first_page), vec!["Bakery", "Groceries"]);
+    assert_eq!(names(&second_page), vec!["Bars", "Books"]);
+    assert_eq!(names(&last_page), vec!["Bus"]);
+    assert_eq!(last_page.total_count, 5);
+}
@@ -0,0 +1,157 @@
+/*!
+ * Change Username Tests
+ *
+ * Covers POST /auth/change-username: the password is required, the new name
+ * follows the registration rules and must be free, and the session reports the
+ * new name right away, other sessions of the account on their next request,
+ * while logging in works only under the new one.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::models::PublicUser;
+use my_budget_server::test_support::{TEST_PASSWORD, TEST_USERNAME, TestApp};
+use serde_json::json;
+
+async fn stored_session_usernames(app: &TestApp) -> Vec<String> {
+    let conn = app.main_db().read().await;
+    let mut rows = conn
+        .query(
+            "SELECT json_extract(data, '$.data.username') FROM sessions ORDER BY id",
+            (),
+        )
+        .await
+        .unwrap();
+    let mut names = Vec::new();
+    while let Some(row) = rows.next().await.unwrap() {
+        names.push(row.get(0).unwrap());
+    }
+    names
+}
+
+#[tokio::test]
+async fn test_rename_reaches_other_sessions() {
+    let app = TestApp::with_sqlite_sessions().await;
+    app.register_and_login().await;
+    let first_cookie = app.cookie();
+
+    app.set_cookie(None);
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": TEST_USERNAME, "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let response = app
+        .post_json(
+            "/auth/change-username",
+            &json!({ "username": "renamed_user", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    // The first session still holds the old name until it is next used
+    let mut names = stored_session_usernames(&app).await;
+    names.sort();
+    assert_eq!(names, vec!["renamed_user", TEST_USERNAME]);
+
+    app.set_cookie(first_cookie);
+    let response = app.get("/auth/me").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(
+        stored_session_usernames(&app).await,
+        vec!["renamed_user", "renamed_user"]
+    );
+}
+
+#[tokio::test]
+async fn test_rename_refreshes_session() {
+    let app = TestApp::new().await;
+    let user = app.register_and_login().await;
+
+    let response = app
+        .post_json(
+            "/auth/change-username",
+            &json!({ "username": "renamed_user", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let renamed: PublicUser = response.json();
+    assert_eq!(renamed.id, user.id);
+    assert_eq!(renamed.username, "renamed_user");
+
+    let me: PublicUser = app.get("/auth/me").await.json();
+    assert_eq!(me.username, "renamed_user");
+
+    app.post_json("/auth/logout", &json!({})).await;
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": TEST_USERNAME, "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": "renamed_user", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(response.json::<PublicUser>().id, user.id);
+}
+
+#[tokio::test]
+async fn test_taken_username_conflicts() {
+    let app = TestApp::new().await;
+    app.register_and_login_as("other_user", TEST_PASSWORD).await;
+    app.register_and_login().await;
+
+    let response = app
+        .post_json(
+            "/auth/change-username",
+            &json!({ "username": "other_user", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.text(), "Username already exists");
+
+    let me: PublicUser = app.get("/auth/me").await.json();
+    assert_eq!(me.username, TEST_USERNAME);
+}
+
+#[tokio::test]
+async fn test_password_and_name_rules_required() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+
+    let response = app
+        .post_json(
+            "/auth/change-username",
+            &json!({ "username": "renamed_user", "password": "not-the-password" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    for username in ["", "a", "has space", "semi;colon"] {
+        let response = app
+            .post_json(
+                "/auth/change-username",
+                &json!({ "username": username, "password": TEST_PASSWORD }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", username);
+    }
+
+    let me: PublicUser = app.get("/auth/me").await.json();
+    assert_eq!(me.username, TEST_USERNAME);
+
+    app.post_json("/auth/logout", &json!({})).await;
+    let response = app
+        .post_json(
+            "/auth/change-username",
+            &json!({ "username": "renamed_user", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
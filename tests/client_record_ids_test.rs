@@ -0,0 +1,110 @@
+/*!
+ * Client-Generated Record Id Tests
+ *
+ * Covers the optional `id` of POST /records used by offline clients: the record
+ * is stored under that id, replaying the create returns the existing record
+ * with 200 instead of inserting another, and malformed ids are rejected.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::Record;
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+const CLIENT_ID: &str = "0b6f3c52-4f6e-4a4c-9a51-2f7d8e1c9b30";
+
+#[tokio::test]
+async fn create_with_client_id_and_replay() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let payload = json!({
+        "id": CLIENT_ID,
+        "name": "Lunch",
+        "amount": 12.5,
+        "category_id": category_id,
+        "timestamp": TEST_BASE_TIMESTAMP,
+    });
+
+    let response = app.post_json("/records", &payload).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let created: Record = response.json();
+    assert_eq!(created.id, CLIENT_ID);
+
+    // The replay is neither a duplicate conflict nor a second record
+    let response = app.post_json("/records", &payload).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let replayed: Record = response.json();
+    assert_eq!(replayed.id, created.id);
+    assert_eq!(replayed.version, created.version);
+    assert_eq!(replayed.created_at, created.created_at);
+
+    // A replay with different content still returns the stored record
+    let mut changed = payload.clone();
+    changed["amount"] = json!(99.0);
+    let response = app.post_json("/records", &changed).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let replayed: Record = response.json();
+    assert_eq!(replayed.amount, 12.5);
+
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    assert_eq!(body["total_count"], 1);
+}
+
+#[tokio::test]
+async fn records_without_id_get_a_server_id() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Lunch",
+                "amount": 12.5,
+                "category_id": category_id,
+                "timestamp": TEST_BASE_TIMESTAMP,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let created: Record = response.json();
+    assert!(uuid::Uuid::try_parse(&created.id).is_ok());
+    assert_ne!(created.id, CLIENT_ID);
+}
+
+#[tokio::test]
+async fn malformed_client_ids_are_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    for id in [
+        "",
+        "not-a-uuid",
+        "0b6f3c524f6e4a4c9a512f7d8e1c9b30",
+        "0B6F3C52-4F6E-4A4C-9A51-2F7D8E1C9B30",
+        "{0b6f3c52-4f6e-4a4c-9a51-2f7d8e1c9b30}",
+    ] {
+        let response = app
+            .post_json(
+                "/records",
+                &json!({
+                    "id": id,
+                    "name": "Lunch",
+                    "amount": 12.5,
+                    "category_id": category_id,
+                    "timestamp": TEST_BASE_TIMESTAMP,
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{:?}", id);
+        assert!(response.text().contains("UUID"), "{:?}", id);
+    }
+
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    assert_eq!(body["total_count"], 0);
+}
@@ -0,0 +1,277 @@
+/*!
+ * Month Close Tests
+ *
+ * Covers closing past records through POST /records/close: creating, editing
+ * and deleting records dated before the cutoff is refused with 423 Locked, as
+ * are duplicating into it, range purges reaching it, backup imports adding to
+ * or wiping it, and category reassignments, merges and orphan repairs moving
+ * its records. Later records stay editable and POST /records/reopen lifts the
+ * lock.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{ClosedPeriod, Record};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+// 2024-01-15 00:00:00 UTC
+const JAN_15: i64 = 1705276800;
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+// 2024-02-10 00:00:00 UTC
+const FEB_10: i64 = 1707523200;
+
+async fn close_before(app: &TestApp, before: i64) {
+    let response = app
+        .post_json("/records/close", &json!({ "before": before }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let closed: ClosedPeriod = response.json();
+    assert_eq!(closed.closed_before, Some(before));
+}
+
+#[tokio::test]
+async fn test_closed_month_rejects_changes() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let january = create_test_record(&data_path, &user_id, "Groceries", 30.0, &food, JAN_15).await;
+    close_before(&app, FEB_START).await;
+
+    let response = app
+        .put_json(&format!("/records/{}", january), &json!({ "amount": 31.0 }))
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+    assert_eq!(
+        response.text(),
+        format!(
+            "Records dated before {} are closed; reopen them to make changes",
+            FEB_START
+        )
+    );
+
+    let response = app.delete(&format!("/records/{}", january)).await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+
+    // Backdating a new record into the closed month is refused too
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Late receipt", "amount": 4.0, "category_id": food, "timestamp": JAN_15 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+
+    let response = app.get(&format!("/records/{}", january)).await;
+    let record: Record = response.json();
+    assert_eq!(record.amount, 30.0);
+}
+
+#[tokio::test]
+async fn test_records_after_cutoff_stay_editable() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let february = create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, FEB_10).await;
+    close_before(&app, FEB_START).await;
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", february),
+            &json!({ "amount": 13.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    // Moving an open record into the closed month is a change to that month
+    let response = app
+        .put_json(
+            &format!("/records/{}", february),
+            &json!({ "timestamp": JAN_15 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Dinner", "amount": 20.0, "category_id": food, "timestamp": FEB_START }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+
+    let response = app.delete(&format!("/records/{}", february)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_reopen_restores_editability() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let january = create_test_record(&data_path, &user_id, "Groceries", 30.0, &food, JAN_15).await;
+    close_before(&app, FEB_START).await;
+
+    let response = app.post_json("/records/reopen", &json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let reopened: ClosedPeriod = response.json();
+    assert_eq!(reopened.closed_before, None);
+
+    let response = app
+        .put_json(&format!("/records/{}", january), &json!({ "amount": 31.0 }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let response = app.delete(&format!("/records/{}", january)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_duplicate_into_closed_month_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let february = create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, FEB_10).await;
+    close_before(&app, FEB_START).await;
+
+    let response = app
+        .post_json(
+            &format!("/records/{}/duplicate", february),
+            &json!({ "timestamp": JAN_15 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 1);
+
+    let response = app
+        .post_json(&format!("/records/{}/duplicate", february), &json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_purge_reaching_closed_month_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &user_id, "Groceries", 30.0, &food, JAN_15).await;
+    create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, FEB_10).await;
+    close_before(&app, FEB_START).await;
+
+    for confirm in [false, true] {
+        let response = app
+            .delete(&format!(
+                "/records?start_time={}&end_time={}&confirm={}",
+                JAN_15, FEB_10, confirm
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::LOCKED);
+    }
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 2);
+
+    let response = app
+        .delete(&format!(
+            "/records?start_time={}&end_time={}&confirm=true",
+            FEB_START, FEB_10
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 1);
+}
+
+#[tokio::test]
+async fn test_import_into_closed_month_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let january = create_test_record(&data_path, &user_id, "Groceries", 30.0, &food, JAN_15).await;
+    close_before(&app, FEB_START).await;
+
+    let record = |id: &str, timestamp: i64| json!({ "id": id, "name": "Imported", "amount": 5.0, "category_id": food, "timestamp": timestamp });
+    let backup = |records: Vec<serde_json::Value>| json!({ "version": 1, "categories": [], "records": records });
+
+    // A new record dated in the closed month
+    let response = app
+        .post_json(
+            "/import?mode=merge",
+            &backup(vec![record("rec-feb", FEB_10), record("rec-jan", JAN_15)]),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+    assert!(
+        response.text().starts_with("Record 1: "),
+        "{}",
+        response.text()
+    );
+
+    // Replacing would wipe the closed month's records
+    let response = app.post_json("/import?mode=replace", &backup(vec![])).await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].id, january);
+
+    // A record already there is skipped, not a change
+    let response = app
+        .post_json(
+            "/import?mode=merge",
+            &backup(vec![record(&january, JAN_15), record("rec-feb", FEB_10)]),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+}
+
+#[tokio::test]
+async fn test_category_moves_reaching_closed_month_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let dining = create_test_category_via_api(&app, "Dining").await;
+    create_test_record(&data_path, &user_id, "Groceries", 30.0, &food, JAN_15).await;
+    close_before(&app, FEB_START).await;
+
+    let response = app
+        .delete(&format!("/categories/{}?reassign_to={}", food, dining))
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+
+    // Renaming onto an existing name would merge the closed record across
+    let response = app
+        .put_json(
+            &format!("/categories/{}", food),
+            &json!({ "name": "Dining", "merge_on_conflict": true }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records[0].category_id.as_deref(), Some(food.as_str()));
+}
+
+#[tokio::test]
+async fn test_orphan_repair_reaching_closed_month_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let misc = create_test_category_via_api(&app, "Misc").await;
+    // Points at a category that does not exist
+    create_test_record(&data_path, &user_id, "Groceries", 30.0, "gone", JAN_15).await;
+    close_before(&app, FEB_START).await;
+
+    let response = app
+        .post_json("/records/orphans/repair", &json!({ "category_id": misc }))
+        .await;
+    assert_eq!(response.status, StatusCode::LOCKED);
+
+    let response = app.get("/records/orphans").await;
+    let orphans: Vec<Record> = response.json();
+    assert_eq!(orphans.len(), 1);
+}
+
+#[tokio::test]
+async fn test_close_in_future_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let future = time::OffsetDateTime::now_utc().unix_timestamp() + 86400;
+    let response = app
+        .post_json("/records/close", &json!({ "before": future }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
@@ -1,9 +1,11 @@
 use my_budget_server::database::{get_user_db, init_main_db};
 use my_budget_server::models::Record;
+use my_budget_server::test_support::TestApp;
 use std::fs;
 use tempfile::{TempDir, tempdir};
 use uuid::Uuid;
 
+#[allow(dead_code)]
 pub async fn setup_test_environment() -> (String, String, TempDir) {
     let temp_dir = tempdir().expect("Failed to create temporary directory");
     let data_path = temp_dir
@@ -34,6 +36,55 @@ pub async fn setup_test_environment() -> (String, String, TempDir) {
     (data_path, user_id, temp_dir)
 }
 
+/// Spins up the full HTTP app with a logged-in user. Returns the app together with
+/// the data path and user id so DB-level helpers can be used for white-box checks.
+#[allow(dead_code)]
+pub async fn setup_test_app() -> (TestApp, String, String) {
+    let app = TestApp::new().await;
+    let user = app.register_and_login().await;
+    let data_path = app.data_path().to_string();
+    (app, data_path, user.id)
+}
+
+#[allow(dead_code)]
+pub async fn create_test_category_via_api(app: &TestApp, name: &str) -> String {
+    let response = app
+        .post_json(
+            "/categories",
+            &serde_json::json!({ "name": name, "is_income": false }),
+        )
+        .await;
+    assert_eq!(
+        response.status,
+        axum::http::StatusCode::CREATED,
+        "category creation failed: {}",
+        response.text()
+    );
+    response.json::<serde_json::Value>()["id"]
+        .as_str()
+        .expect("Category id missing from response")
+        .to_string()
+}
+
+/// An hour in the past, recent enough to fall in the current month's summaries
+#[allow(dead_code)]
+pub fn recent_timestamp() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp() - 3600
+}
+
+#[allow(dead_code)]
+pub async fn create_record_via_api(app: &TestApp, payload: &serde_json::Value) -> Record {
+    let response = app.post_json("/records", payload).await;
+    assert_eq!(
+        response.status,
+        axum::http::StatusCode::CREATED,
+        "record creation failed: {}",
+        response.text()
+    );
+    response.json()
+}
+
+#[allow(dead_code)]
 pub async fn create_test_record(
     data_path: &str,
     user_id: &str,
@@ -100,7 +151,7 @@ pub async fn get_records_from_db(
     // Get records
     let mut rows = conn
         .query(
-            "SELECT id, name, amount, category_id, timestamp FROM records WHERE timestamp BETWEEN ? AND ? ORDER BY timestamp DESC LIMIT ?",
+            "SELECT id, name, amount, category_id, timestamp, created_at, updated_at FROM records WHERE timestamp BETWEEN ? AND ? ORDER BY timestamp DESC LIMIT ?",
             (start, end, lim),
         )
         .await
@@ -111,8 +162,10 @@ pub async fn get_records_from_db(
         let id: String = row.get(0).expect("Failed to get record id");
         let name: String = row.get(1).expect("Failed to get record name");
         let amount: f64 = row.get(2).expect("Failed to get record amount");
-        let category_id: String = row.get(3).expect("Failed to get record category_id");
+        let category_id: Option<String> = row.get(3).expect("Failed to get record category_id");
         let timestamp: i64 = row.get(4).expect("Failed to get record timestamp");
+        let created_at: i64 = row.get(5).expect("Failed to get record created_at");
+        let updated_at: i64 = row.get(6).expect("Failed to get record updated_at");
 
         records.push(Record {
             id,
@@ -120,6 +173,19 @@ pub async fn get_records_from_db(
             amount,
             category_id,
             timestamp,
+            currency: "USD".to_string(),
+            kind: Default::default(),
+            payment_method: None,
+            tags: Vec::new(),
+            splits: Vec::new(),
+            created_at,
+            updated_at,
+            version: 1,
+            starred: false,
+            balance: None,
+            category: None,
+            budget_warnings: Vec::new(),
+            matched_rule: None,
         });
     }
 
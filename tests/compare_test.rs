@@ -0,0 +1,118 @@
+/*!
+ * Period Comparison Tests
+ *
+ * Covers GET /records/compare: expense totals of a window and of the equally long
+ * window right before it, the difference between them, the category filter and
+ * the null percentage when the previous period has no spending.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::PeriodComparison;
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+
+const MAY_1_2024: i64 = 1714521600;
+const MAY_15_2024: i64 = 1715731200;
+const JUNE_1_2024: i64 = 1717200000;
+const JUNE_15_2024: i64 = 1718409600;
+const JULY_1_2024: i64 = 1719792000;
+
+fn june_query() -> String {
+    format!(
+        "/records/compare?start_time={}&end_time={}",
+        JUNE_1_2024,
+        JULY_1_2024 - 1
+    )
+}
+
+async fn create_record(app: &TestApp, body: Value) {
+    let response = app.post_json("/records", &body).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+}
+
+#[tokio::test]
+async fn compare_month_with_previous_month() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+
+    create_test_record(&data_path, &user_id, "May", 160.0, &food, MAY_15_2024).await;
+    create_test_record(&data_path, &user_id, "June", 150.0, &food, JUNE_1_2024).await;
+    create_test_record(&data_path, &user_id, "June", 50.0, &food, JULY_1_2024 - 1).await;
+    // June is 30 days long, so May 1 falls outside the previous window
+    create_test_record(&data_path, &user_id, "Too early", 999.0, &food, MAY_1_2024).await;
+    create_test_record(&data_path, &user_id, "July", 999.0, &food, JULY_1_2024).await;
+    // Income and other currencies are not spending in the compared currency
+    create_record(
+        &app,
+        json!({ "name": "Salary", "amount": 3000.0, "category_id": food, "kind": "income", "timestamp": JUNE_15_2024 }),
+    )
+    .await;
+    create_record(
+        &app,
+        json!({ "name": "Abroad", "amount": 70.0, "category_id": food, "currency": "EUR", "timestamp": JUNE_15_2024 }),
+    )
+    .await;
+
+    let response = app.get(&june_query()).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let comparison: PeriodComparison = response.json();
+    assert_eq!(comparison.currency, "USD");
+    assert_eq!(comparison.current.total, 200.0);
+    assert_eq!(comparison.current.count, 2);
+    assert_eq!(comparison.previous.start_time, MAY_1_2024 + 24 * 60 * 60);
+    assert_eq!(comparison.previous.end_time, JUNE_1_2024 - 1);
+    assert_eq!(comparison.previous.total, 160.0);
+    assert_eq!(comparison.previous.count, 1);
+    assert_eq!(comparison.difference, 40.0);
+    assert_eq!(comparison.percent_change, Some(25.0));
+
+    let response = app.get(&format!("{}&currency=EUR", june_query())).await;
+    let comparison: PeriodComparison = response.json();
+    assert_eq!(comparison.current.total, 70.0);
+}
+
+#[tokio::test]
+async fn compare_filters_by_category() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+
+    create_test_record(&data_path, &user_id, "Lunch", 80.0, &food, MAY_15_2024).await;
+    create_test_record(&data_path, &user_id, "Lunch", 60.0, &food, JUNE_15_2024).await;
+    create_test_record(&data_path, &user_id, "Train", 500.0, &travel, JUNE_15_2024).await;
+
+    let response = app
+        .get(&format!("{}&category_id={}", june_query(), food))
+        .await;
+    let comparison: PeriodComparison = response.json();
+    assert_eq!(comparison.current.total, 60.0);
+    assert_eq!(comparison.previous.total, 80.0);
+    assert_eq!(comparison.difference, -20.0);
+    assert_eq!(comparison.percent_change, Some(-25.0));
+}
+
+#[tokio::test]
+async fn compare_with_empty_previous_period() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &user_id, "Lunch", 12.5, &food, JUNE_15_2024).await;
+
+    let response = app.get(&june_query()).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["previous"]["total"], 0.0);
+    assert_eq!(body["previous"]["count"], 0);
+    assert_eq!(body["difference"], 12.5);
+    assert_eq!(body["percent_change"], Value::Null);
+
+    let response = app
+        .get(&format!(
+            "/records/compare?start_time={}&end_time={}",
+            JULY_1_2024, JUNE_1_2024
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
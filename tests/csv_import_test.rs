@@ -0,0 +1,258 @@
+/*!
+ * Bank Statement CSV Import Tests
+ *
+ * Covers POST /import/csv with differently shaped bank exports (column order,
+ * delimiters, date formats, comma-as-decimal amounts), per-line error reporting,
+ * strict mode, and the CSV, date and amount parsers on their own.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::import::{
+    CsvRow, parse_csv, parse_statement_amount, parse_statement_date, validate_date_format,
+};
+use my_budget_server::models::{CsvImportResponse, Record, RecordKind};
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+
+const MAY_3_2024: i64 = 1714694400;
+const MAY_4_2024: i64 = 1714780800;
+const MAY_10_2024: i64 = 1715299200;
+
+const GERMAN_CSV: &str = "\
+Buchungstag;Verwendungszweck;Empfänger;Betrag
+03.05.2024;Miete Mai;Hausverwaltung;-1.234,56
+04.05.2024;Gehalt;Arbeitgeber GmbH;+2.500,00
+10.05.2024;\"Bäckerei; Filiale 2\";Bäcker;-4,5
+";
+
+const US_CSV: &str = "\
+Amount,Date,Description
+-12.34,05/03/2024,Coffee shop
+\"1,200.00\",05/04/2024,\"Refund \"\"Laptop\"\"\"
+";
+
+async fn import_csv(app: &TestApp, payload: Value) -> CsvImportResponse {
+    let response = app.post_json("/import/csv", &payload).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+async fn records_oldest_first(app: &TestApp) -> Vec<Record> {
+    let response = app.get("/records?sort_by=timestamp&order=asc").await;
+    let body: Value = response.json();
+    serde_json::from_value(body["records"].clone()).unwrap()
+}
+
+#[tokio::test]
+async fn import_semicolon_csv_with_comma_decimals() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Bank").await;
+
+    let summary = import_csv(
+        &app,
+        json!({
+            "csv": GERMAN_CSV,
+            "columns": { "date": 0, "amount": 3, "description": 1 },
+            "date_format": "%d.%m.%Y",
+            "decimal_separator": ",",
+            "delimiter": ";",
+            "category_id": category_id,
+        }),
+    )
+    .await;
+    assert_eq!(summary.records_created, 3);
+    assert!(summary.errors.is_empty());
+
+    let records = records_oldest_first(&app).await;
+    let imported: Vec<(&str, f64, RecordKind, i64)> = records
+        .iter()
+        .map(|r| (r.name.as_str(), r.amount, r.kind, r.timestamp))
+        .collect();
+    assert_eq!(
+        imported,
+        vec![
+            ("Miete Mai", 1234.56, RecordKind::Expense, MAY_3_2024),
+            ("Gehalt", 2500.0, RecordKind::Income, MAY_4_2024),
+            ("Bäckerei; Filiale 2", 4.5, RecordKind::Expense, MAY_10_2024),
+        ]
+    );
+    assert!(
+        records
+            .iter()
+            .all(|r| r.category_id.as_deref() == Some(category_id.as_str()))
+    );
+    assert!(records.iter().all(|r| r.currency == "USD"));
+}
+
+#[tokio::test]
+async fn import_comma_csv_with_other_column_order() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Bank").await;
+
+    let summary = import_csv(
+        &app,
+        json!({
+            "csv": US_CSV,
+            "columns": { "date": 1, "amount": 0, "description": 2 },
+            "date_format": "%m/%d/%Y",
+            "category_id": category_id,
+        }),
+    )
+    .await;
+    assert_eq!(summary.records_created, 2);
+
+    let records = records_oldest_first(&app).await;
+    assert_eq!(records[0].name, "Coffee shop");
+    assert_eq!(records[0].amount, 12.34);
+    assert_eq!(records[0].timestamp, MAY_3_2024);
+    assert_eq!(records[1].name, "Refund \"Laptop\"");
+    assert_eq!(records[1].amount, 1200.0);
+    assert_eq!(records[1].kind, RecordKind::Income);
+}
+
+#[tokio::test]
+async fn bad_rows_are_reported_by_line() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Bank").await;
+    let csv = "\
+date,description,amount
+2024-05-03,Lunch,-12.50
+2024-13-01,Bad month,-1.00
+
+2024-05-04,,-3.00
+2024-05-04,Too precise,-3.001
+2024-05-04,Short row
+2024-05-10,Dinner,-30.00
+";
+    let payload = json!({
+        "csv": csv,
+        "columns": { "date": 0, "amount": 2, "description": 1 },
+        "category_id": category_id,
+    });
+
+    let summary = import_csv(&app, payload.clone()).await;
+    assert_eq!(summary.records_created, 2);
+    let lines: Vec<u32> = summary.errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![3, 5, 6, 7]);
+    assert!(summary.errors[0].message.contains("invalid date"));
+    assert!(summary.errors[2].message.contains("decimal places"));
+    assert!(summary.errors[3].message.contains("missing column 2"));
+
+    // Strict mode rejects the file on the first bad row and creates nothing
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Bank").await;
+    let mut strict = payload;
+    strict["category_id"] = json!(category_id);
+    strict["strict"] = json!(true);
+    let response = app.post_json("/import/csv", &strict).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(
+        response.text().starts_with("Line 3:"),
+        "{}",
+        response.text()
+    );
+    assert!(records_oldest_first(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn invalid_import_options_are_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Bank").await;
+    let base = json!({
+        "csv": "date,description,amount\n2024-05-03,Lunch,-12.50\n",
+        "columns": { "date": 0, "amount": 2, "description": 1 },
+        "category_id": category_id,
+    });
+
+    let overrides = [
+        ("date_format", json!("%d.%m")),
+        ("date_format", json!("%Y-%m-%d %H")),
+        ("decimal_separator", json!(";")),
+        ("delimiter", json!("\"")),
+        ("category_id", json!("missing")),
+    ];
+    for (field, value) in overrides {
+        let mut payload = base.clone();
+        payload[field] = value.clone();
+        let response = app.post_json("/import/csv", &payload).await;
+        assert_eq!(
+            response.status,
+            StatusCode::BAD_REQUEST,
+            "{}: {}",
+            field,
+            value
+        );
+    }
+    assert!(records_oldest_first(&app).await.is_empty());
+}
+
+#[test]
+fn statement_amounts_follow_the_decimal_separator() {
+    assert_eq!(parse_statement_amount("-1.234,56", ','), Ok(-1234.56));
+    assert_eq!(parse_statement_amount("+2.500,00", ','), Ok(2500.0));
+    assert_eq!(parse_statement_amount("4,5", ','), Ok(4.5));
+    assert_eq!(parse_statement_amount("1,200.00", '.'), Ok(1200.0));
+    assert_eq!(parse_statement_amount(" -12.34 ", '.'), Ok(-12.34));
+
+    for value in ["", "abc", "12,34,56.7.8", "1e3", "--5"] {
+        assert!(parse_statement_amount(value, '.').is_err(), "{:?}", value);
+    }
+}
+
+#[test]
+fn statement_dates_follow_the_format() {
+    assert_eq!(
+        parse_statement_date("03.05.2024", "%d.%m.%Y"),
+        Ok(MAY_3_2024)
+    );
+    assert_eq!(parse_statement_date("3.5.24", "%d.%m.%y"), Ok(MAY_3_2024));
+    assert_eq!(
+        parse_statement_date("05/03/2024", "%m/%d/%Y"),
+        Ok(MAY_3_2024)
+    );
+    assert_eq!(
+        parse_statement_date("2024-05-03", "%Y-%m-%d"),
+        Ok(MAY_3_2024)
+    );
+
+    for value in [
+        "2024-02-30",
+        "2024-05",
+        "2024-05-03x",
+        "24-05-03",
+        "03.05.2024",
+    ] {
+        assert!(
+            parse_statement_date(value, "%Y-%m-%d").is_err(),
+            "{:?}",
+            value
+        );
+    }
+
+    assert!(validate_date_format("%d.%m.%Y").is_ok());
+    assert!(validate_date_format("%d.%d.%Y").is_err());
+    assert!(validate_date_format("%Y-%m-%d%").is_err());
+}
+
+#[test]
+fn csv_quoted_fields_may_span_lines() {
+    let rows = parse_csv("a,\"b\nc\",\"d \"\"e\"\"\"\r\n\r\nf,g\n", ',').unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            CsvRow {
+                line: 1,
+                fields: vec!["a".into(), "b\nc".into(), "d \"e\"".into()],
+            },
+            CsvRow {
+                line: 4,
+                fields: vec!["f".into(), "g".into()],
+            },
+        ]
+    );
+
+    assert!(parse_csv("a,\"b\n", ',').is_err());
+}
@@ -0,0 +1,191 @@
+/*!
+ * Record Currency Tests
+ *
+ * Covers the per-record currency: explicit and default currencies on create,
+ * validation on update, the user's default currency preference, the schema
+ * migration for existing databases and per-currency summary buckets.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{Record, SummaryBucket, UserPreferences};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+use tempfile::tempdir;
+
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+
+async fn create_coffee(app: &TestApp, category_id: &str, currency: Option<&str>) -> Record {
+    let mut payload = json!({
+        "name": "Coffee",
+        "amount": 4.5,
+        "category_id": category_id,
+        "timestamp": recent_timestamp(),
+    });
+    if let Some(currency) = currency {
+        payload["currency"] = json!(currency);
+    }
+    create_record_via_api(app, &payload).await
+}
+
+#[tokio::test]
+async fn test_create_record_with_explicit_currency() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let record = create_coffee(&app, &category_id, Some("EUR")).await;
+    assert_eq!(record.currency, "EUR");
+
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"][0]["currency"], "EUR");
+}
+
+#[tokio::test]
+async fn test_create_record_with_default_currency() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let record = create_coffee(&app, &category_id, None).await;
+    assert_eq!(record.currency, "USD");
+
+    // The default is configurable per user
+    let response = app
+        .put_json(
+            "/settings/preferences",
+            &json!({ "default_currency": "JPY" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let preferences: UserPreferences = response.json();
+    assert_eq!(preferences.default_currency, "JPY");
+
+    let record = create_record_via_api(
+        &app,
+        &json!({
+            "name": "Tea",
+            "amount": 3.0,
+            "category_id": category_id,
+            "timestamp": recent_timestamp(),
+        }),
+    )
+    .await;
+    assert_eq!(record.currency, "JPY");
+}
+
+#[tokio::test]
+async fn test_invalid_currency_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_coffee(&app, &category_id, Some("EUR")).await;
+
+    for invalid in ["usd", "EURO", "U$D", ""] {
+        let response = app
+            .put_json(
+                &format!("/records/{}", record.id),
+                &json!({ "currency": invalid }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", invalid);
+        assert_eq!(
+            response.text(),
+            "Currency must be a three-letter uppercase ISO 4217 code"
+        );
+    }
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "currency": "GBP" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let updated: Record = response.json();
+    assert_eq!(updated.currency, "GBP");
+
+    let response = app
+        .put_json(
+            "/settings/preferences",
+            &json!({ "default_currency": "dollars" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_existing_database_is_migrated() {
+    let temp_dir = tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let user_id = uuid::Uuid::new_v4().to_string();
+
+    // A user database created before records had a currency column
+    {
+        let path = temp_dir.path().join(format!("user_{}.db", user_id));
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "CREATE TABLE records (id TEXT PRIMARY KEY, name TEXT NOT NULL, amount REAL NOT NULL, category_id TEXT NOT NULL, timestamp INTEGER NOT NULL)",
+            (),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES ('old', 'Legacy', 1.0, 'c', 1700000000)",
+            (),
+        )
+        .await
+        .unwrap();
+    }
+
+    // Opening it twice must be idempotent
+    get_user_db(data_path, &user_id).await.unwrap();
+    let user_db = get_user_db(data_path, &user_id).await.unwrap();
+
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query("SELECT currency FROM records WHERE id = 'old'", ())
+        .await
+        .unwrap();
+    let currency: String = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(currency, "USD");
+}
+
+#[tokio::test]
+async fn test_summary_groups_totals_per_currency() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(&data_path, &user_id, "A", 10.0, "c", FEB_START).await;
+    create_test_record(&data_path, &user_id, "B", 5.0, "c", FEB_START + 60).await;
+    let euro_id = create_test_record(&data_path, &user_id, "C", 7.0, "c", FEB_START + 120).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    user_db
+        .write()
+        .await
+        .execute(
+            "UPDATE records SET currency = 'EUR' WHERE id = ?",
+            [euro_id.as_str()],
+        )
+        .await
+        .unwrap();
+
+    let response = app.get("/records/summary?group_by=month").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let buckets: Vec<SummaryBucket> = response.json();
+
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].period, "2024-02");
+    assert_eq!(buckets[0].currency, "EUR");
+    assert_eq!(buckets[0].total, 7.0);
+    assert_eq!(buckets[1].currency, "USD");
+    assert_eq!(buckets[1].total, 15.0);
+    assert_eq!(buckets[1].count, 2);
+
+    let response = app.get("/records/stats?currency=EUR").await;
+    let stats: serde_json::Value = response.json();
+    assert_eq!(stats["count"], 1);
+    assert_eq!(stats["sum"], 7.0);
+}
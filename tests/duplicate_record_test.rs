@@ -0,0 +1,118 @@
+/*!
+ * Duplicate Record Tests
+ *
+ * Covers POST /records/{id}/duplicate: the copy gets a new id and the current
+ * time, keeps the original's other fields unless `amount` or `timestamp` are
+ * overridden, and the original record is left untouched.
+ */
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use common::*;
+use my_budget_server::models::{Record, RecordKind};
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+async fn create_original(app: &TestApp) -> Record {
+    let category_id = create_test_category_via_api(app, "Food").await;
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Groceries",
+                "amount": 54.3,
+                "category_id": category_id,
+                "timestamp": TEST_BASE_TIMESTAMP,
+                "payment_method": "card",
+                "tags": ["weekly"],
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    response.json()
+}
+
+#[tokio::test]
+async fn duplicate_without_body_copies_the_record_dated_now() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let original = create_original(&app).await;
+
+    let before = time::OffsetDateTime::now_utc().unix_timestamp();
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/records/{}/duplicate", original.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(request).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let copy: Record = response.json();
+
+    assert_ne!(copy.id, original.id);
+    assert!(copy.timestamp >= before);
+    assert_eq!(copy.name, original.name);
+    assert_eq!(copy.amount, original.amount);
+    assert_eq!(copy.category_id, original.category_id);
+    assert_eq!(copy.kind, RecordKind::Expense);
+    assert_eq!(copy.payment_method.as_deref(), Some("card"));
+    assert_eq!(copy.tags, vec!["weekly"]);
+    assert_eq!(copy.version, 1);
+
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    assert_eq!(body["total_count"], 2);
+}
+
+#[tokio::test]
+async fn duplicate_applies_overrides_and_leaves_the_original_alone() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let original = create_original(&app).await;
+
+    let response = app
+        .post_json(
+            &format!("/records/{}/duplicate", original.id),
+            &json!({ "amount": "61.20", "timestamp": TEST_BASE_TIMESTAMP + 3600 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let copy: Record = response.json();
+    assert_eq!(copy.amount, 61.2);
+    assert_eq!(copy.timestamp, TEST_BASE_TIMESTAMP + 3600);
+    assert_eq!(copy.name, "Groceries");
+
+    let response = app.get("/records?sort_by=timestamp&order=asc").await;
+    let body: Value = response.json();
+    let records: Vec<Record> = serde_json::from_value(body["records"].clone()).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].id, original.id);
+    assert_eq!(records[0].amount, 54.3);
+    assert_eq!(records[0].timestamp, TEST_BASE_TIMESTAMP);
+    assert_eq!(records[0].version, 1);
+
+    let response = app
+        .post_json(
+            &format!("/records/{}/duplicate", original.id),
+            &json!({ "amount": 0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn duplicate_of_missing_record_is_not_found() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json("/records/missing/duplicate", &json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    assert_eq!(body["total_count"], 0);
+}
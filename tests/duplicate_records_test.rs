@@ -0,0 +1,217 @@
+/*!
+ * Duplicate Record Tests
+ *
+ * Covers duplicate detection in POST /records: exact and near-timestamp repeats
+ * are rejected with 409 naming the existing record, anything outside the window
+ * or differing in a matched field is accepted, and `allow_duplicate=true` opts out.
+ * Creates running at the same time neither fail on the database lock nor let a
+ * double submit through.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::constants::DUPLICATE_WINDOW_SECS;
+use my_budget_server::models::Record;
+use my_budget_server::test_support::{TestApp, TestResponse};
+use serde_json::json;
+use std::sync::Arc;
+
+fn base_timestamp() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp() - 24 * 60 * 60
+}
+
+async fn post_record(
+    app: &TestApp,
+    path: &str,
+    name: &str,
+    amount: f64,
+    category_id: &str,
+    timestamp: i64,
+) -> TestResponse {
+    app.post_json(
+        path,
+        &json!({
+            "name": name,
+            "amount": amount,
+            "category_id": category_id,
+            "timestamp": timestamp,
+        }),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_exact_duplicate_is_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let timestamp = base_timestamp();
+
+    let response = post_record(&app, "/records", "Lunch", 12.5, &category_id, timestamp).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let original: Record = response.json();
+
+    // Surrounding whitespace does not make a new record
+    let response = post_record(&app, "/records", " Lunch ", 12.5, &category_id, timestamp).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert!(response.text().contains(&original.id));
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 1);
+}
+
+#[tokio::test]
+async fn test_near_timestamp_duplicate_is_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let timestamp = base_timestamp();
+
+    let response = post_record(&app, "/records", "Lunch", 12.5, &category_id, timestamp).await;
+    let original: Record = response.json();
+
+    for offset in [-DUPLICATE_WINDOW_SECS, 30, DUPLICATE_WINDOW_SECS] {
+        let response = post_record(
+            &app,
+            "/records",
+            "Lunch",
+            12.5,
+            &category_id,
+            timestamp + offset,
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::CONFLICT, "offset {}", offset);
+        assert!(response.text().contains(&original.id));
+    }
+
+    // Just outside the window is a separate purchase
+    let response = post_record(
+        &app,
+        "/records",
+        "Lunch",
+        12.5,
+        &category_id,
+        timestamp + DUPLICATE_WINDOW_SECS + 1,
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_differing_fields_are_not_duplicates() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let other_category_id = create_test_category_via_api(&app, "Work").await;
+    let timestamp = base_timestamp();
+
+    post_record(&app, "/records", "Lunch", 12.5, &category_id, timestamp).await;
+
+    let cases = [
+        ("Dinner", 12.5, category_id.as_str()),
+        ("Lunch", 12.0, category_id.as_str()),
+        ("Lunch", 12.5, other_category_id.as_str()),
+    ];
+    for (name, amount, category) in cases {
+        let response = post_record(&app, "/records", name, amount, category, timestamp).await;
+        assert_eq!(
+            response.status,
+            StatusCode::CREATED,
+            "{} {} {}",
+            name,
+            amount,
+            category
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_allow_duplicate_overrides_detection() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let timestamp = base_timestamp();
+
+    post_record(&app, "/records", "Coffee", 3.0, &category_id, timestamp).await;
+
+    let response = post_record(
+        &app,
+        "/records?allow_duplicate=true",
+        "Coffee",
+        3.0,
+        &category_id,
+        timestamp,
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+
+    let response = post_record(
+        &app,
+        "/records?allow_duplicate=false",
+        "Coffee",
+        3.0,
+        &category_id,
+        timestamp,
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_creates_all_succeed() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let timestamp = base_timestamp();
+
+    let app = Arc::new(app);
+    let tasks: Vec<_> = (0..8)
+        .map(|i| {
+            let app = app.clone();
+            let category_id = category_id.clone();
+            tokio::spawn(async move {
+                let name = format!("Lunch {}", i);
+                post_record(&app, "/records", &name, 12.5, &category_id, timestamp)
+                    .await
+                    .status
+            })
+        })
+        .collect();
+    for task in tasks {
+        assert_eq!(task.await.unwrap(), StatusCode::CREATED);
+    }
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 8);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_double_submit_creates_one_record() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let timestamp = base_timestamp();
+
+    let app = Arc::new(app);
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let app = app.clone();
+            let category_id = category_id.clone();
+            tokio::spawn(async move {
+                post_record(&app, "/records", "Lunch", 12.5, &category_id, timestamp)
+                    .await
+                    .status
+            })
+        })
+        .collect();
+    let mut statuses = Vec::new();
+    for task in tasks {
+        statuses.push(task.await.unwrap());
+    }
+    statuses.sort();
+
+    let mut expected = vec![StatusCode::CONFLICT; 7];
+    expected.insert(0, StatusCode::CREATED);
+    assert_eq!(statuses, expected);
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 1);
+}
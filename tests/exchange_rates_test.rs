@@ -0,0 +1,190 @@
+/*!
+ * Exchange Rate Tests
+ *
+ * Covers storing exchange rates under /settings/rates and the `convert` flag on
+ * the stats and summary endpoints, including records whose currency has no rate.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{ExchangeRatesResponse, RecordStats, SummaryBucket};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+
+/// USD 10 (the default currency), EUR 20, GBP 4 and CHF 8, all in February 2024.
+async fn create_mixed_currency_records(data_path: &str, user_id: &str) {
+    let fixtures = [
+        ("Lunch", 10.0, "USD"),
+        ("Train", 20.0, "EUR"),
+        ("Tea", 4.0, "GBP"),
+        ("Chocolate", 8.0, "CHF"),
+    ];
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    for (offset, (name, amount, currency)) in fixtures.into_iter().enumerate() {
+        let id = create_test_record(
+            data_path,
+            user_id,
+            name,
+            amount,
+            "c",
+            FEB_START + offset as i64 * 60,
+        )
+        .await;
+        user_db
+            .write()
+            .await
+            .execute(
+                "UPDATE records SET currency = ? WHERE id = ?",
+                (currency, id.as_str()),
+            )
+            .await
+            .unwrap();
+    }
+}
+
+async fn set_rates(app: &TestApp, rates: serde_json::Value) -> ExchangeRatesResponse {
+    let response = app
+        .put_json("/settings/rates", &json!({ "rates": rates }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+#[tokio::test]
+async fn test_put_and_get_exchange_rates() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/settings/rates").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let rates: ExchangeRatesResponse = response.json();
+    assert_eq!(rates.base_currency, "USD");
+    assert!(rates.rates.is_empty());
+
+    set_rates(&app, json!({ "EUR": 1.5, "GBP": 1.2 })).await;
+    // Later writes replace a currency's rate and leave the others alone
+    let rates = set_rates(&app, json!({ "GBP": 1.25 })).await;
+
+    assert_eq!(rates.rates.len(), 2);
+    assert_eq!(rates.rates[0].currency, "EUR");
+    assert_eq!(rates.rates[0].rate, 1.5);
+    assert_eq!(rates.rates[1].currency, "GBP");
+    assert_eq!(rates.rates[1].rate, 1.25);
+    assert!(rates.rates[1].updated_at >= rates.rates[0].updated_at);
+
+    let response = app.get("/settings/rates").await;
+    let fetched: ExchangeRatesResponse = response.json();
+    assert_eq!(fetched, rates);
+}
+
+#[tokio::test]
+async fn test_invalid_exchange_rates_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    for rates in [
+        json!({ "eur": 1.5 }),
+        json!({ "EUR": 0.0 }),
+        json!({ "EUR": -2.0 }),
+        json!({ "USD": 1.0 }),
+    ] {
+        let response = app
+            .put_json("/settings/rates", &json!({ "rates": rates }))
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", rates);
+    }
+
+    let response = app.get("/settings/rates").await;
+    let rates: ExchangeRatesResponse = response.json();
+    assert!(rates.rates.is_empty());
+}
+
+#[tokio::test]
+async fn test_stats_convert_to_default_currency() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_mixed_currency_records(&data_path, &user_id).await;
+    set_rates(&app, json!({ "EUR": 1.5, "GBP": 1.25 })).await;
+
+    let response = app
+        .get(&format!(
+            "/records/stats?start_time={}&end_time={}&convert=true",
+            FEB_START,
+            FEB_START + 3600
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let stats: RecordStats = response.json();
+
+    // 10 USD + 20 EUR * 1.5 + 4 GBP * 1.25; CHF has no rate
+    assert_eq!(stats.currency.as_deref(), Some("USD"));
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.sum, 45.0);
+    assert_eq!(stats.min, Some(5.0));
+    assert_eq!(stats.max, Some(30.0));
+
+    let unconverted = stats.unconverted.unwrap();
+    assert_eq!(unconverted.len(), 1);
+    assert_eq!(unconverted[0].currency, "CHF");
+    assert_eq!(unconverted[0].count, 1);
+    assert_eq!(unconverted[0].sum, 8.0);
+
+    // Without the flag amounts are summed as they are
+    let response = app
+        .get(&format!(
+            "/records/stats?start_time={}&end_time={}",
+            FEB_START,
+            FEB_START + 3600
+        ))
+        .await;
+    let stats: serde_json::Value = response.json();
+    assert_eq!(stats["sum"], 42.0);
+    assert!(stats.get("unconverted").is_none());
+}
+
+#[tokio::test]
+async fn test_summary_convert_to_default_currency() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_mixed_currency_records(&data_path, &user_id).await;
+    set_rates(&app, json!({ "EUR": 1.5, "GBP": 1.25 })).await;
+
+    let response = app
+        .get(&format!(
+            "/records/summary?group_by=month&start_time={}&end_time={}&convert=true",
+            FEB_START,
+            FEB_START + 3600
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let buckets: Vec<SummaryBucket> = response.json();
+
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].period, "2024-02");
+    assert_eq!(buckets[0].currency, "USD");
+    assert_eq!(buckets[0].total, 45.0);
+    assert_eq!(buckets[0].count, 3);
+    assert!(!buckets[0].unconverted);
+
+    assert_eq!(buckets[1].period, "2024-02");
+    assert_eq!(buckets[1].currency, "CHF");
+    assert_eq!(buckets[1].total, 8.0);
+    assert_eq!(buckets[1].count, 1);
+    assert!(buckets[1].unconverted);
+
+    // Once CHF has a rate everything lands in one bucket
+    set_rates(&app, json!({ "CHF": 1.125 })).await;
+    let response = app
+        .get(&format!(
+            "/records/summary?group_by=month&start_time={}&end_time={}&convert=true",
+            FEB_START,
+            FEB_START + 3600
+        ))
+        .await;
+    let buckets: Vec<SummaryBucket> = response.json();
+    assert_eq!(buckets.len(), 1);
+    assert_eq!(buckets[0].total, 54.0);
+    assert_eq!(buckets[0].count, 4);
+}
@@ -0,0 +1,129 @@
+/*!
+ * Excluded Categories Tests
+ *
+ * Covers the `exclude_category_ids` parameter of GET /records and the summary
+ * endpoints: excluded rows disappear from listings, counts and totals, and a
+ * category cannot be included and excluded at the same time.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{CategoryTotal, SummaryBucket};
+use serde_json::Value;
+
+const FEB_START: i64 = 1706745600;
+const MAR_START: i64 = 1709251200;
+
+fn time_range() -> String {
+    format!("start_time={}&end_time={}", FEB_START, MAR_START - 1)
+}
+
+#[tokio::test]
+async fn excluded_categories_are_left_out_of_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let rent = create_test_category_via_api(&app, "Rent").await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+
+    create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, FEB_START).await;
+    create_test_record(&data_path, &user_id, "Rent", 800.0, &rent, FEB_START + 60).await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Train",
+        40.0,
+        &travel,
+        FEB_START + 120,
+    )
+    .await;
+
+    let response = app
+        .get(&format!(
+            "/records?{}&exclude_category_ids={},{}",
+            time_range(),
+            rent,
+            travel
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: Value = response.json();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["records"].as_array().unwrap().len(), 1);
+    assert_eq!(body["records"][0]["name"], "Lunch");
+
+    // Combines with an include list as long as the two do not overlap
+    let response = app
+        .get(&format!(
+            "/records?{}&category_ids={},{}&exclude_category_ids={}&count_only=true",
+            time_range(),
+            food,
+            rent,
+            travel
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: Value = response.json();
+    assert_eq!(body["total_count"], 2);
+}
+
+#[tokio::test]
+async fn including_and_excluding_the_same_category_is_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let rent = create_test_category_via_api(&app, "Rent").await;
+
+    let response = app
+        .get(&format!(
+            "/records?category_ids={},{}&exclude_category_ids={}",
+            food, rent, rent
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().contains(&rent), "{}", response.text());
+
+    let response = app.get("/records?exclude_category_ids=,").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "exclude_category_ids must contain at least one id"
+    );
+}
+
+#[tokio::test]
+async fn excluded_categories_are_left_out_of_summaries() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let rent = create_test_category_via_api(&app, "Rent").await;
+
+    create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, FEB_START).await;
+    create_test_record(&data_path, &user_id, "Dinner", 30.0, &food, FEB_START + 60).await;
+    create_test_record(&data_path, &user_id, "Rent", 800.0, &rent, FEB_START + 120).await;
+
+    let response = app
+        .get(&format!(
+            "/records/summary?group_by=month&{}&exclude_category_ids={}",
+            time_range(),
+            rent
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let buckets: Vec<SummaryBucket> = response.json();
+    assert_eq!(buckets.len(), 1);
+    assert_eq!(buckets[0].total, 42.0);
+    assert_eq!(buckets[0].count, 2);
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-category?{}&exclude_category_ids={}",
+            time_range(),
+            food
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let totals: Vec<CategoryTotal> = response.json();
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].category_id.as_deref(), Some(rent.as_str()));
+    assert_eq!(totals[0].total_amount, 800.0);
+}
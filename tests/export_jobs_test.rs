@@ -0,0 +1,314 @@
+/*!
+ * Export Job Tests
+ *
+ * Drives asynchronous exports (POST /exports) to completion through the HTTP layer,
+ * checking progress reporting, the downloaded file in NDJSON and the statement
+ * formats, cleanup and retention purging, which spares unfinished jobs until a
+ * restart marks them failed and carries on past a user whose database cannot be
+ * opened.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::constants::{ERR_EXPORT_INTERRUPTED, EXPORT_JOB_RETENTION_SECS};
+use my_budget_server::database::{get_user_db, user_db_path};
+use my_budget_server::export_jobs::{create_export_job, export_file_path};
+use my_budget_server::maintenance::{recover_interrupted_export_jobs, run_maintenance};
+use my_budget_server::models::{ExportJob, ExportJobStatus, Record};
+use my_budget_server::test_support::TestApp;
+use std::time::Duration;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+const FIXTURE_ROWS: i64 = 3000;
+
+async fn insert_fixture_records(data_path: &str, user_id: &str, count: i64) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.write().await;
+    let tx = conn.transaction().await.unwrap();
+    for i in 0..count {
+        tx.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES (?, ?, ?, ?, ?)",
+            (
+                uuid::Uuid::new_v4().to_string(),
+                format!("Fixture {}", i),
+                1.0 + i as f64,
+                "fixture",
+                // Several records per second, so batches split inside equal timestamps
+                TEST_BASE_TIMESTAMP + i / 3,
+            ),
+        )
+        .await
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+}
+
+async fn start_export(app: &TestApp) -> ExportJob {
+    let response = app
+        .post_json("/exports", &serde_json::json!({ "format": "ndjson" }))
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::ACCEPTED,
+        "export creation failed: {}",
+        response.text()
+    );
+    response.json()
+}
+
+/// Polls the job until it finishes, returning every progress value observed.
+async fn wait_for_export(app: &TestApp, job_id: &str) -> (ExportJob, Vec<u32>) {
+    let mut observed = Vec::new();
+    for _ in 0..2000 {
+        let response = app.get(&format!("/exports/{}", job_id)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let job: ExportJob = response.json();
+        observed.push(job.progress);
+
+        match job.status {
+            ExportJobStatus::Completed | ExportJobStatus::Failed => return (job, observed),
+            _ => tokio::time::sleep(Duration::from_millis(5)).await,
+        }
+    }
+    panic!("export job {} did not finish in time", job_id);
+}
+
+fn job_file_path(app: &TestApp, user_id: &str, job: &ExportJob) -> std::path::PathBuf {
+    export_file_path(app.data_path(), user_id, &job.id, "ndjson")
+}
+
+#[tokio::test]
+async fn export_job_runs_to_completion_with_monotonic_progress() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, FIXTURE_ROWS).await;
+
+    let job = start_export(&app).await;
+    assert_eq!(job.status, ExportJobStatus::Pending);
+    assert_eq!(job.progress, 0);
+
+    let (job, observed) = wait_for_export(&app, &job.id).await;
+    assert_eq!(job.status, ExportJobStatus::Completed, "{:?}", job.error);
+    assert_eq!(job.total_rows, FIXTURE_ROWS as u32);
+    assert_eq!(job.progress, FIXTURE_ROWS as u32);
+    assert!(job.completed_at.is_some());
+    assert!(
+        observed.windows(2).all(|pair| pair[0] <= pair[1]),
+        "progress went backwards: {:?}",
+        observed
+    );
+
+    let response = app.get(&format!("/exports/{}/download", job.id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.header("content-type"),
+        Some("application/x-ndjson")
+    );
+
+    let records: Vec<Record> = response
+        .text()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), FIXTURE_ROWS as usize);
+    assert!(
+        records
+            .windows(2)
+            .all(|pair| (pair[0].timestamp, &pair[0].id) < (pair[1].timestamp, &pair[1].id))
+    );
+}
+
+#[tokio::test]
+async fn export_job_delete_removes_file() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, 10).await;
+
+    let job = start_export(&app).await;
+    let (job, _) = wait_for_export(&app, &job.id).await;
+    let file_path = job_file_path(&app, &user_id, &job);
+    assert!(file_path.exists());
+
+    let response = app.delete(&format!("/exports/{}", job.id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert!(!file_path.exists());
+
+    let response = app.get(&format!("/exports/{}", job.id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.text(), "Export not found");
+}
+
+#[tokio::test]
+async fn unfinished_export_cannot_be_downloaded_or_deleted() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    // A job that was created but never started stays pending
+    let (job, _) = create_export_job(
+        &user_db,
+        &data_path,
+        &user_id,
+        "ndjson",
+        0,
+        TEST_BASE_TIMESTAMP,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await
+    .unwrap();
+
+    let response = app.get(&format!("/exports/{}/download", job.id)).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.text(), "Export is not ready (status: pending)");
+
+    let response = app.delete(&format!("/exports/{}", job.id)).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn maintenance_purges_expired_export_jobs() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, 10).await;
+
+    let job = start_export(&app).await;
+    let (job, _) = wait_for_export(&app, &job.id).await;
+    let file_path = job_file_path(&app, &user_id, &job);
+
+    // Still within the retention window
+    let purged = run_maintenance(app.main_db(), &data_path, job.created_at + 60)
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+    assert!(file_path.exists());
+
+    let purged = run_maintenance(
+        app.main_db(),
+        &data_path,
+        job.created_at + EXPORT_JOB_RETENTION_SECS + 1,
+    )
+    .await
+    .unwrap();
+    assert_eq!(purged, 1);
+    assert!(!file_path.exists());
+
+    let response = app.get(&format!("/exports/{}", job.id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn unfinished_jobs_purged_only_after_restart_recovery() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let (job, _) = create_export_job(
+        &user_db,
+        &data_path,
+        &user_id,
+        "ndjson",
+        0,
+        TEST_BASE_TIMESTAMP,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await
+    .unwrap();
+    let expired = job.created_at + EXPORT_JOB_RETENTION_SECS + 1;
+
+    // Old enough to purge, yet a job still pending may be about to run
+    let purged = run_maintenance(app.main_db(), &data_path, expired)
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+
+    let failed = recover_interrupted_export_jobs(app.main_db(), &data_path, expired)
+        .await
+        .unwrap();
+    assert_eq!(failed, 1);
+    let response = app.get(&format!("/exports/{}", job.id)).await;
+    let recovered: ExportJob = response.json();
+    assert_eq!(recovered.status, ExportJobStatus::Failed);
+    assert_eq!(recovered.error.as_deref(), Some(ERR_EXPORT_INTERRUPTED));
+
+    let purged = run_maintenance(app.main_db(), &data_path, expired)
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+}
+
+#[tokio::test]
+async fn maintenance_continues_past_failing_user() {
+    let app = TestApp::new().await;
+    // Listed before the real user, with a directory where its database should be
+    app.main_db()
+        .write()
+        .await
+        .execute(
+            "INSERT INTO users (id, name, password_hash) VALUES ('broken', 'broken', 'x')",
+            (),
+        )
+        .await
+        .unwrap();
+    std::fs::create_dir(user_db_path(app.data_path(), "broken")).unwrap();
+    let user = app.register_and_login().await;
+    let data_path = app.data_path().to_string();
+    insert_fixture_records(&data_path, &user.id, 10).await;
+
+    let job = start_export(&app).await;
+    let (job, _) = wait_for_export(&app, &job.id).await;
+
+    let purged = run_maintenance(
+        app.main_db(),
+        &data_path,
+        job.created_at + EXPORT_JOB_RETENTION_SECS + 1,
+    )
+    .await
+    .unwrap();
+    assert_eq!(purged, 1);
+}
+
+#[tokio::test]
+async fn export_job_writes_statement_formats() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, 10).await;
+
+    for (format, content_type) in [("qif", "application/qif"), ("ofx", "application/x-ofx")] {
+        let response = app
+            .post_json("/exports", &serde_json::json!({ "format": format }))
+            .await;
+        assert_eq!(response.status, StatusCode::ACCEPTED, "{}", response.text());
+        let job: ExportJob = response.json();
+        let (job, _) = wait_for_export(&app, &job.id).await;
+        assert_eq!(job.status, ExportJobStatus::Completed, "{:?}", job.error);
+        assert_eq!(job.progress, 10);
+
+        let response = app.get(&format!("/exports/{}/download", job.id)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.header("content-type"), Some(content_type));
+        let file = response.text();
+
+        // Same writer as the synchronous export
+        let expected = app
+            .get(&format!("/records/export?format={}", format))
+            .await
+            .text();
+        match format {
+            "qif" => assert_eq!(file, expected),
+            _ => {
+                // Only the generation time may differ
+                let transactions = |ofx: &str| ofx.matches("<STMTTRN>").count();
+                assert_eq!(transactions(&file), 10);
+                assert_eq!(transactions(&expected), 10);
+                assert!(file.contains("<TRNAMT>-1.00</TRNAMT>"));
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn export_job_rejects_unknown_format() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json("/exports", &serde_json::json!({ "format": "pdf" }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Unsupported export format: pdf");
+}
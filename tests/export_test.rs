@@ -0,0 +1,146 @@
+/*!
+ * Statement Export Tests
+ *
+ * Covers the QIF and OFX writers against the golden files in tests/fixtures,
+ * including a record whose name needs escaping, and the `format=qif` and
+ * `format=ofx` options of GET /records/export.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::export::{StatementEntry, write_ofx, write_qif};
+use my_budget_server::models::{Record, RecordKind};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000; // Nov 14, 2023 22:13:20 UTC
+const ONE_DAY: i64 = 24 * 60 * 60;
+
+fn entry(
+    id: &str,
+    name: &str,
+    amount: f64,
+    kind: RecordKind,
+    timestamp: i64,
+    category_name: Option<&str>,
+) -> StatementEntry {
+    StatementEntry {
+        record: Record {
+            id: id.to_string(),
+            name: name.to_string(),
+            amount,
+            category_id: Some("c".to_string()),
+            timestamp,
+            currency: "EUR".to_string(),
+            kind,
+            payment_method: None,
+            tags: Vec::new(),
+            splits: Vec::new(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            version: 1,
+            starred: false,
+            balance: None,
+            category: None,
+            budget_warnings: Vec::new(),
+            matched_rule: None,
+        },
+        category_name: category_name.map(str::to_string),
+    }
+}
+
+fn fixture_entries() -> Vec<StatementEntry> {
+    vec![
+        entry(
+            "rec-1",
+            "Lunch",
+            12.5,
+            RecordKind::Expense,
+            TEST_BASE_TIMESTAMP,
+            Some("Food"),
+        ),
+        entry(
+            "rec-2",
+            "Salary",
+            3000.0,
+            RecordKind::Income,
+            TEST_BASE_TIMESTAMP + ONE_DAY,
+            Some("Income"),
+        ),
+        // A refund whose category was deleted, with characters both formats must escape
+        entry(
+            "rec-3",
+            "Tom & Jerry's <Café>\nDinner, \"private\" room",
+            -4.25,
+            RecordKind::Expense,
+            TEST_BASE_TIMESTAMP + 2 * ONE_DAY,
+            None,
+        ),
+    ]
+}
+
+#[test]
+fn qif_matches_golden_file() {
+    let qif = write_qif(&fixture_entries());
+    assert_eq!(qif, include_str!("fixtures/records.qif"));
+}
+
+#[test]
+fn ofx_matches_golden_file() {
+    let generated_at = 1700438400; // Nov 20, 2023 00:00:00 UTC
+    let ofx = write_ofx(&fixture_entries(), "EUR", generated_at);
+    assert_eq!(ofx, include_str!("fixtures/records.ofx"));
+}
+
+#[test]
+fn empty_statements_are_still_well_formed() {
+    assert_eq!(write_qif(&[]), "!Type:Bank\n");
+
+    let ofx = write_ofx(&[], "USD", TEST_BASE_TIMESTAMP);
+    assert!(ofx.contains("<DTSTART>20231114221320</DTSTART>"));
+    assert!(!ofx.contains("<STMTTRN>"));
+    assert!(ofx.ends_with("</OFX>\n"));
+}
+
+#[tokio::test]
+async fn export_endpoint_renders_statement_formats() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.5,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let query = format!(
+        "start_time={}&end_time={}",
+        TEST_BASE_TIMESTAMP,
+        TEST_BASE_TIMESTAMP + ONE_DAY
+    );
+
+    let response = app
+        .get(&format!("/records/export?format=qif&{}", query))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("application/qif"));
+    assert_eq!(
+        response.text(),
+        "!Type:Bank\nD11/14/2023\nT-12.50\nPLunch\nLFood\n^\n"
+    );
+
+    let response = app
+        .get(&format!("/records/export?format=ofx&{}", query))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("application/x-ofx"));
+    let ofx = response.text();
+    assert!(ofx.contains("<CURDEF>USD</CURDEF>"));
+    assert!(ofx.contains("<TRNAMT>-12.50</TRNAMT>"));
+
+    let response = app.get("/records/export?format=csv").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
@@ -8,15 +8,29 @@
  * - extract_record_from_row function tests (data type handling, edge cases)
  * - Default behavior validation (time ranges, limits)
  * - Database precision and consistency tests
+ * - Record timestamp and amount sanity bounds
+ * - Amount precision and string amount parsing
+ * - Time bound parsing from unix timestamps and RFC3339 dates
+ * - Relative range shortcuts around month ends, year ends and leap days
  *
  * All tests use isolated temporary databases for complete test isolation.
  */
 
 mod common;
 
+use axum::http::StatusCode;
 use common::*;
+use my_budget_server::amount_format::parse_amount_str;
+use my_budget_server::constants::{
+    MAX_AMOUNT_STRING_LENGTH, MAX_RECORD_AMOUNT, MAX_RECORD_TIMESTAMP_SKEW_SECS,
+    MIN_RECORD_TIMESTAMP,
+};
 use my_budget_server::database::get_user_db;
-use my_budget_server::records::extract_record_from_row;
+use my_budget_server::records::{
+    RECORD_COLUMNS, extract_record_from_row, resolve_relative_range, validate_record_amount,
+    validate_record_timestamp,
+};
+use my_budget_server::timestamp_format::parse_timestamp_str;
 
 // Test data constants - only for widely reused values
 const TEST_BASE_TIMESTAMP: i64 = 1700000000; // Nov 14, 2023 22:13:20 UTC
@@ -47,7 +61,7 @@ async fn extract_record_from_row_success() {
     let conn = user_db.read().await;
     let mut rows = conn
         .query(
-            "SELECT id, name, amount, category_id, timestamp FROM records WHERE id = ?",
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
             [record_id.as_str()],
         )
         .await
@@ -60,7 +74,7 @@ async fn extract_record_from_row_success() {
         assert_eq!(record.id, record_id);
         assert_eq!(record.name, "Test Record");
         assert_eq!(record.amount, 25.50);
-        assert_eq!(record.category_id, "test_category");
+        assert_eq!(record.category_id.as_deref(), Some("test_category"));
         assert_eq!(record.timestamp, timestamp);
     } else {
         panic!(
@@ -94,7 +108,7 @@ async fn extract_record_with_special_characters() {
     let conn = user_db.read().await;
     let mut rows = conn
         .query(
-            "SELECT id, name, amount, category_id, timestamp FROM records WHERE id = ?",
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
             [record_id.as_str()],
         )
         .await
@@ -105,7 +119,7 @@ async fn extract_record_with_special_characters() {
             extract_record_from_row(row).expect("Failed to extract record with special characters");
 
         assert_eq!(record.name, special_name);
-        assert_eq!(record.category_id, special_category);
+        assert_eq!(record.category_id.as_deref(), Some(special_category));
         assert_eq!(record.amount, 99.99);
     } else {
         panic!(
@@ -195,7 +209,7 @@ async fn extract_record_with_long_strings() {
     let conn = user_db.read().await;
     let mut rows = conn
         .query(
-            "SELECT id, name, amount, category_id, timestamp FROM records WHERE id = ?",
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
             [record_id.as_str()],
         )
         .await
@@ -206,7 +220,7 @@ async fn extract_record_with_long_strings() {
             extract_record_from_row(row).expect("Failed to extract record with long strings");
 
         assert_eq!(record.name, long_name);
-        assert_eq!(record.category_id, long_category);
+        assert_eq!(record.category_id.as_deref(), Some(long_category.as_str()));
         assert_eq!(record.name.len(), 200);
         assert_eq!(record.amount, 42.42);
     } else {
@@ -236,7 +250,8 @@ async fn default_time_behavior() {
     )
     .await;
 
-    // Test that get_records with no time parameters returns records (uses default start_time=0, end_time=now)
+    // Test that get_records with no time parameters returns records (uses default start_time=0, end_time=now;
+    // future-dated records additionally need include_future=true)
     let (records, total_count) = get_records_from_db(&data_path, &user_id, None, None, None).await;
 
     assert_eq!(records.len(), 1);
@@ -328,3 +343,195 @@ async fn database_timestamp_precision() {
     assert_eq!(records[1].name, "Record 1"); // Middle (1700000001)
     assert_eq!(records[2].name, "Record 0"); // Oldest (1700000000)
 }
+
+/// Tests the sanity bounds applied to record timestamps on update.
+/// Verifies both limits are inclusive and that values just past them are rejected.
+#[test]
+fn validate_record_timestamp_boundaries() {
+    let now = TEST_BASE_TIMESTAMP;
+
+    assert!(validate_record_timestamp(MIN_RECORD_TIMESTAMP, now).is_ok());
+    assert!(validate_record_timestamp(now, now).is_ok());
+    assert!(validate_record_timestamp(now + MAX_RECORD_TIMESTAMP_SKEW_SECS, now).is_ok());
+
+    let (status, message) = validate_record_timestamp(MIN_RECORD_TIMESTAMP - 1, now).unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(message.contains("earlier"));
+
+    let (status, message) =
+        validate_record_timestamp(now + MAX_RECORD_TIMESTAMP_SKEW_SECS + 1, now).unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(message.contains("ahead"));
+
+    // Far-future values do not overflow the bound
+    assert!(validate_record_timestamp(i64::MAX, i64::MAX).is_ok());
+    assert!(validate_record_timestamp(i64::MAX, now).is_err());
+}
+
+/// Tests the record amount validator against values that would poison aggregates.
+/// Verifies non-finite amounts and amounts beyond the cap are rejected with distinct messages.
+#[test]
+fn validate_record_amount_rejects_non_finite_and_oversized() {
+    assert!(validate_record_amount(MAX_RECORD_AMOUNT).is_ok());
+    assert!(validate_record_amount(-MAX_RECORD_AMOUNT).is_ok());
+    assert!(validate_record_amount(0.01).is_ok());
+
+    for amount in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+        let (status, message) = validate_record_amount(amount).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("finite"), "{}: {}", amount, message);
+    }
+
+    for amount in [MAX_RECORD_AMOUNT + 1.0, -MAX_RECORD_AMOUNT - 1.0, 1e308] {
+        let (status, message) = validate_record_amount(amount).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("exceed"), "{}: {}", amount, message);
+    }
+
+    let (_, message) = validate_record_amount(0.0).unwrap_err();
+    assert!(message.contains("zero"));
+}
+
+/// Tests the amount precision rule together with the strict string parser.
+/// Verifies strings map to the same value as the JSON number and that finer
+/// than cent precision, malformed and overlong strings are rejected.
+#[test]
+fn amount_strings_parse_strictly_and_respect_precision() {
+    for (input, expected) in [("12.3", 12.3), ("12.34", 12.34), ("-7", -7.0)] {
+        let amount = parse_amount_str(input).unwrap();
+        assert_eq!(amount, expected, "{}", input);
+        assert!(validate_record_amount(amount).is_ok(), "{}", input);
+    }
+
+    // Parses fine but carries a third decimal place
+    let amount = parse_amount_str("12.345").unwrap();
+    let (status, message) = validate_record_amount(amount).unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(message.contains("2 decimal places"), "{}", message);
+
+    for input in [
+        "abc", "", "-", "12.", ".5", "1e3", "+1", " 12", "1,000", "NaN",
+    ] {
+        assert!(parse_amount_str(input).is_err(), "{:?}", input);
+    }
+
+    let long = "1".repeat(MAX_AMOUNT_STRING_LENGTH + 1);
+    let message = parse_amount_str(&long).unwrap_err();
+    assert!(message.contains("longer than"), "{}", message);
+    let long_fraction = format!("1.{}", "0".repeat(MAX_AMOUNT_STRING_LENGTH));
+    assert!(parse_amount_str(&long_fraction).is_err());
+}
+
+/// Tests the time bound parser used by the `start_time`/`end_time` query parameters.
+/// Verifies integers and RFC3339 dates (with offsets and before 1970) map to the
+/// same unix seconds and that anything else is rejected with a clear message.
+#[test]
+fn time_bounds_parse_from_integers_and_rfc3339_dates() {
+    for (input, expected) in [
+        ("1714521600", 1714521600),
+        ("-86400", -86400),
+        ("2024-05-01T00:00:00Z", 1714521600),
+        ("2024-05-01T02:00:00+02:00", 1714521600),
+        ("1969-12-31T00:00:00Z", -86400),
+        ("1900-01-01T00:00:00Z", -2208988800),
+    ] {
+        assert_eq!(parse_timestamp_str(input), Ok(expected), "{}", input);
+    }
+
+    for input in [
+        "",
+        "abc",
+        "2024-05-01",
+        "2024-05-01 00:00:00",
+        "2024-13-01T00:00:00Z",
+        "1.5",
+    ] {
+        let message = parse_timestamp_str(input).unwrap_err();
+        assert!(message.contains("RFC3339"), "{:?}: {}", input, message);
+    }
+}
+
+/// Tests the `range` shortcuts of GET /records against an injected clock.
+/// Verifies whole-period ranges, ranges ending now, local boundaries when the
+/// UTC offset moves the date across a month or year end, and leap years.
+#[test]
+fn relative_ranges_resolve_against_the_given_clock() {
+    const DAY: i64 = 24 * 60 * 60;
+    const HOUR: i64 = 60 * 60;
+    let mar_15_noon = 1710504000; // Friday 2024-03-15 12:00 UTC
+    let feb_1 = 1706745600;
+    let mar_1 = 1709251200;
+    let apr_1 = mar_1 + 31 * DAY;
+
+    let resolve = |range: &str, now: i64, tz: i32| resolve_relative_range(range, now, tz).unwrap();
+    assert_eq!(
+        resolve("today", mar_15_noon, 0),
+        (mar_15_noon - 12 * HOUR, mar_15_noon + 12 * HOUR - 1)
+    );
+    // Monday 2024-03-11 to Sunday 2024-03-17
+    assert_eq!(
+        resolve("this_week", mar_15_noon, 0),
+        (1710115200, 1710115200 + 7 * DAY - 1)
+    );
+    assert_eq!(resolve("this_month", mar_15_noon, 0), (mar_1, apr_1 - 1));
+    // February 2024 has 29 days
+    assert_eq!(resolve("last_month", mar_15_noon, 0), (feb_1, mar_1 - 1));
+    assert_eq!(mar_1 - feb_1, 29 * DAY);
+    assert_eq!(
+        resolve("last_30d", mar_15_noon, 0),
+        (mar_15_noon - 30 * DAY, mar_15_noon)
+    );
+    assert_eq!(
+        resolve("year_to_date", mar_15_noon, 0),
+        (1704067200, mar_15_noon)
+    );
+
+    // On the leap day itself
+    let leap_day_evening = 1709229600; // 2024-02-29 18:00 UTC
+    assert_eq!(
+        resolve("this_month", leap_day_evening, 0),
+        (feb_1, mar_1 - 1)
+    );
+    assert_eq!(
+        resolve("today", leap_day_evening, 0),
+        (mar_1 - DAY, mar_1 - 1)
+    );
+
+    // 2024-01-31 23:30 UTC is already February one hour east, still January five hours west
+    let jan_31_late = 1706743800;
+    assert_eq!(
+        resolve("this_month", jan_31_late, 60),
+        (feb_1 - HOUR, mar_1 - HOUR - 1)
+    );
+    assert_eq!(
+        resolve("today", jan_31_late, 60),
+        (feb_1 - HOUR, feb_1 + DAY - HOUR - 1)
+    );
+    let dec_1_2023 = 1701388800;
+    let jan_1_2024 = 1704067200;
+    assert_eq!(
+        resolve("last_month", jan_31_late, -300),
+        (dec_1_2023 + 5 * HOUR, jan_1_2024 + 5 * HOUR - 1)
+    );
+
+    // 2024-12-31 23:00 UTC is New Year two hours east
+    let new_years_eve = 1735686000;
+    let jan_1_2025 = 1735689600;
+    let dec_1_2024 = 1733011200;
+    assert_eq!(
+        resolve("year_to_date", new_years_eve, 120),
+        (jan_1_2025 - 2 * HOUR, new_years_eve)
+    );
+    assert_eq!(
+        resolve("last_month", new_years_eve, 120),
+        (dec_1_2024 - 2 * HOUR, jan_1_2025 - 2 * HOUR - 1)
+    );
+    assert_eq!(
+        resolve("this_month", new_years_eve, 0),
+        (dec_1_2024, jan_1_2025 - 1)
+    );
+
+    let (status, message) = resolve_relative_range("yesterday", mar_15_noon, 0).unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(message.contains("this_month"), "{}", message);
+}
@@ -0,0 +1,186 @@
+/*!
+ * Idempotency Key Tests
+ *
+ * Covers the `Idempotency-Key` header on POST /records: replays return the
+ * originally created record without inserting a row (whatever amount format the
+ * replay asks for), a key reused for another request is a 409, and keys expire
+ * through the maintenance task.
+ */
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use common::*;
+use my_budget_server::constants::IDEMPOTENCY_KEY_TTL_SECS;
+use my_budget_server::maintenance::run_maintenance;
+use my_budget_server::models::Record;
+use my_budget_server::test_support::{TestApp, TestResponse};
+use serde_json::{Value, json};
+
+fn lunch(category_id: &str) -> Value {
+    json!({
+        "name": "Lunch",
+        "amount": 12.5,
+        "category_id": category_id,
+        "tags": ["work"],
+    })
+}
+
+async fn post_with_key(app: &TestApp, key: &str, body: &Value) -> TestResponse {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/records")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("Idempotency-Key", key)
+        .body(Body::from(serde_json::to_vec(body).unwrap()))
+        .unwrap();
+    app.request(request).await
+}
+
+#[tokio::test]
+async fn test_replay_returns_original_record() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let response = post_with_key(&app, "retry-1", &lunch(&category_id)).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let original: Record = response.json();
+
+    for _ in 0..2 {
+        let response = post_with_key(&app, "retry-1", &lunch(&category_id)).await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+        let replayed: Record = response.json();
+        assert_eq!(replayed.id, original.id);
+        assert_eq!(replayed.timestamp, original.timestamp);
+        assert_eq!(replayed.created_at, original.created_at);
+        assert_eq!(replayed.tags, original.tags);
+    }
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 1);
+}
+
+#[tokio::test]
+async fn test_reused_key_with_different_payload_conflicts() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    post_with_key(&app, "retry-1", &lunch(&category_id)).await;
+
+    let mut dinner = lunch(&category_id);
+    dinner["name"] = json!("Dinner");
+    let response = post_with_key(&app, "retry-1", &dinner).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 1);
+}
+
+#[tokio::test]
+async fn test_replay_in_string_mode_matches_split_request() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let household = create_test_category_via_api(&app, "Household").await;
+    let body = json!({
+        "name": "Supermarket",
+        "amount": 50.0,
+        "splits": [
+            { "category_id": food, "amount": 30.0 },
+            { "category_id": household, "amount": 20.0 },
+        ],
+    });
+
+    let response = post_with_key(&app, "retry-1", &body).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let original: Record = response.json();
+
+    // The same request asking for string amounts is still a replay
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/records?amount_format=string")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("Idempotency-Key", "retry-1")
+        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        .unwrap();
+    let response = app.request(request).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let replayed: Value = response.json();
+    assert_eq!(replayed["id"], json!(original.id));
+    assert_eq!(replayed["amount"], json!("50.00"));
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 1);
+}
+
+#[tokio::test]
+async fn test_keys_are_per_user() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let response = post_with_key(&app, "shared-key", &lunch(&category_id)).await;
+    let first: Record = response.json();
+
+    let other = TestApp::new().await;
+    other
+        .register_and_login_as("other_user", "other-password")
+        .await;
+    let other_category_id = create_test_category_via_api(&other, "Food").await;
+    let response = post_with_key(&other, "shared-key", &lunch(&other_category_id)).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let second: Record = response.json();
+    assert_ne!(second.id, first.id);
+}
+
+#[tokio::test]
+async fn test_expired_key_can_be_reused() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let response = post_with_key(&app, "retry-1", &lunch(&category_id)).await;
+    let original: Record = response.json();
+
+    // Maintenance before the TTL keeps the key
+    run_maintenance(app.main_db(), &data_path, original.created_at + 60)
+        .await
+        .unwrap();
+    let response = post_with_key(&app, "retry-1", &lunch(&category_id)).await;
+    assert_eq!(response.json::<Record>().id, original.id);
+
+    run_maintenance(
+        app.main_db(),
+        &data_path,
+        original.created_at + IDEMPOTENCY_KEY_TTL_SECS + 1,
+    )
+    .await
+    .unwrap();
+
+    // The key is free again; the request itself is now a plain duplicate
+    let response = post_with_key(&app, "retry-1", &lunch(&category_id)).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert!(response.text().contains("Duplicate"));
+
+    let mut dinner = lunch(&category_id);
+    dinner["name"] = json!("Dinner");
+    let response = post_with_key(&app, "retry-1", &dinner).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    assert_ne!(response.json::<Record>().id, original.id);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 2);
+}
+
+#[tokio::test]
+async fn test_invalid_key_is_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    for key in ["", "   ", &"k".repeat(256)] {
+        let response = post_with_key(&app, key, &lunch(&category_id)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{:?}", key);
+    }
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert!(records.is_empty());
+}
@@ -0,0 +1,247 @@
+/*!
+ * Import Integration Tests
+ *
+ * Covers POST /import restoring versioned backup documents in merge and replace
+ * modes, including atomicity when an entry is invalid part-way through and
+ * backup categories matching an existing name in another case or composition.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{ImportResponse, Record};
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+fn backup_document(categories: Vec<Value>, records: Vec<Value>) -> Value {
+    json!({ "version": 1, "categories": categories, "records": records })
+}
+
+fn category_entry(id: &str, name: &str) -> Value {
+    json!({ "id": id, "name": name, "is_income": false })
+}
+
+fn record_entry(id: &str, name: &str, amount: f64, category_id: &str) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "amount": amount,
+        "category_id": category_id,
+        "timestamp": TEST_BASE_TIMESTAMP,
+    })
+}
+
+#[tokio::test]
+async fn import_replace_restores_document() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let old_category = create_test_category_via_api(&app, "Old").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Old Record",
+        5.0,
+        &old_category,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let document = backup_document(
+        vec![category_entry("cat-food", "Food")],
+        vec![
+            record_entry("rec-1", "Lunch", 12.5, "cat-food"),
+            record_entry("rec-2", "Dinner", 30.0, "cat-food"),
+        ],
+    );
+    let response = app.post_json("/import?mode=replace", &document).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let summary: ImportResponse = response.json();
+    assert_eq!(summary.categories_created, 1);
+    assert_eq!(summary.records_created, 2);
+    assert_eq!(summary.records_skipped, 0);
+
+    let (records, total_count) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total_count, 2);
+    assert!(
+        records
+            .iter()
+            .all(|r: &Record| r.category_id.as_deref() == Some("cat-food"))
+    );
+}
+
+#[tokio::test]
+async fn import_replace_is_atomic_on_invalid_record() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let original_category = create_test_category_via_api(&app, "Original").await;
+    let original_record = create_test_record(
+        &data_path,
+        &user_id,
+        "Original Record",
+        42.0,
+        &original_category,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    // The second record is malformed (zero amount) and must abort everything
+    let document = backup_document(
+        vec![category_entry("cat-new", "New")],
+        vec![
+            record_entry("rec-ok", "Fine", 1.0, "cat-new"),
+            record_entry("rec-bad", "Broken", 0.0, "cat-new"),
+            record_entry("rec-never", "Never", 2.0, "cat-new"),
+        ],
+    );
+    let response = app.post_json("/import?mode=replace", &document).await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().starts_with("Record 1:"));
+
+    // Original data is untouched and nothing from the document leaked in
+    let (records, total_count) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total_count, 1);
+    assert_eq!(records[0].id, original_record);
+
+    let categories: Value = app.get("/categories").await.json();
+    assert_eq!(categories["total_count"], 1);
+    assert_eq!(
+        categories["categories"][0]["id"],
+        original_category.as_str()
+    );
+}
+
+#[tokio::test]
+async fn import_merge_skips_existing_ids() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let document = backup_document(
+        vec![category_entry("cat-a", "A")],
+        vec![record_entry("rec-a", "First", 10.0, "cat-a")],
+    );
+    let first: ImportResponse = app.post_json("/import?mode=merge", &document).await.json();
+    assert_eq!(first.records_created, 1);
+
+    let document = backup_document(
+        vec![category_entry("cat-a", "A")],
+        vec![
+            record_entry("rec-a", "First", 10.0, "cat-a"),
+            record_entry("rec-b", "Second", 20.0, "cat-a"),
+        ],
+    );
+    let second: ImportResponse = app.post_json("/import?mode=merge", &document).await.json();
+
+    assert_eq!(
+        second,
+        ImportResponse {
+            categories_created: 0,
+            categories_skipped: 1,
+            records_created: 1,
+            records_skipped: 1,
+        }
+    );
+
+    let (_, total_count) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total_count, 2);
+}
+
+#[tokio::test]
+async fn import_merge_maps_categories_with_existing_names() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let cafe = create_test_category_via_api(&app, "Caf\u{e9}").await;
+
+    let mut lunch = record_entry("rec-lunch", "Lunch", 12.0, "cat-food");
+    lunch["splits"] = json!([
+        { "category_id": "cat-food", "amount": 8.0 },
+        { "category_id": "cat-cafe", "amount": 4.0 },
+    ]);
+    let document = backup_document(
+        vec![
+            category_entry("cat-food", "food"),
+            // "Cafe" with a combining accent, NFC-equal to the existing one
+            category_entry("cat-cafe", "Cafe\u{301}"),
+            json!({
+                "id": "cat-fuel",
+                "name": " Fuel ",
+                "is_income": false,
+                "monthly_budget": 50.0,
+                "enforce_budget": true,
+            }),
+        ],
+        vec![lunch, record_entry("rec-coffee", "Coffee", 3.0, "cat-cafe")],
+    );
+    let response = app.post_json("/import?mode=merge", &document).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let summary: ImportResponse = response.json();
+    assert_eq!(
+        summary,
+        ImportResponse {
+            categories_created: 1,
+            categories_skipped: 2,
+            records_created: 2,
+            records_skipped: 0,
+        }
+    );
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let lunch: Record = app.get("/records/rec-lunch").await.json();
+    assert_eq!(lunch.category_id.as_deref(), Some(food.as_str()));
+    let mut split_categories: Vec<&str> = lunch
+        .splits
+        .iter()
+        .map(|s| s.category_id.as_str())
+        .collect();
+    split_categories.sort();
+    let mut expected = vec![food.as_str(), cafe.as_str()];
+    expected.sort();
+    assert_eq!(split_categories, expected);
+    let coffee = records.iter().find(|r| r.id == "rec-coffee").unwrap();
+    assert_eq!(coffee.category_id.as_deref(), Some(cafe.as_str()));
+
+    let fuel: Value = app.get("/categories/cat-fuel").await.json();
+    assert_eq!(fuel["name"], "Fuel");
+    assert_eq!(fuel["enforce_budget"], true);
+}
+
+#[tokio::test]
+async fn import_rejects_unknown_version() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let document = json!({ "version": 99, "categories": [], "records": [] });
+    let response = app.post_json("/import?mode=merge", &document).await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Unsupported backup version: 99");
+}
+
+#[tokio::test]
+async fn import_rejects_unknown_mode() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let document = backup_document(vec![], vec![]);
+
+    let response = app.post_json("/import?mode=overwrite", &document).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.post_json("/import", &document).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn import_rejects_record_with_unknown_category() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let document = backup_document(
+        vec![],
+        vec![record_entry("rec-x", "Lost", 3.0, "missing-category")],
+    );
+    let response = app.post_json("/import?mode=merge", &document).await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Record 0: Category does not exist");
+
+    let (_, total_count) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total_count, 0);
+}
@@ -0,0 +1,103 @@
+/*!
+ * Embedded Category Tests
+ *
+ * Covers `include=category` on GET /records and GET /records/{id}: each record
+ * carries a nested `category: { id, name }` next to the flat `category_id`, the
+ * object is null when the category no longer exists, and it is left out
+ * entirely without the option.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+#[tokio::test]
+async fn records_embed_their_category() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let lunch = create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.0,
+        &food,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let response = app.get("/records?include=category").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: Value = response.json();
+    let record = &body["records"][0];
+    assert_eq!(record["category_id"], json!(food));
+    assert_eq!(record["category"], json!({ "id": food, "name": "Food" }));
+
+    let response = app
+        .get(&format!("/records/{}?include=category", lunch))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let record: Value = response.json();
+    assert_eq!(record["category"]["name"], "Food");
+
+    // Without the option the response keeps its previous shape
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    assert!(body["records"][0].get("category").is_none());
+    let response = app.get(&format!("/records/{}", lunch)).await;
+    let record: Value = response.json();
+    assert_eq!(record["name"], "Lunch");
+    assert!(record.get("category").is_none());
+}
+
+#[tokio::test]
+async fn orphaned_records_embed_a_null_category() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+    let train = create_test_record(
+        &data_path,
+        &user_id,
+        "Train",
+        40.0,
+        &travel,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    {
+        let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+        let conn = user_db.write().await;
+        conn.execute("DELETE FROM categories WHERE id = ?", [travel.as_str()])
+            .await
+            .unwrap();
+    }
+
+    let response = app.get("/records?include=category").await;
+    let body: Value = response.json();
+    let record = &body["records"][0];
+    assert_eq!(record["category_id"], json!(travel));
+    assert!(record["category"].is_null());
+    assert!(record.get("category").is_some());
+
+    let response = app
+        .get(&format!("/records/{}?include=category", train))
+        .await;
+    let record: Value = response.json();
+    assert!(record["category"].is_null());
+    assert!(record.get("category").is_some());
+}
+
+#[tokio::test]
+async fn unknown_include_options_and_missing_records_are_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/records?include=tags").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("tags"));
+
+    let response = app.get("/records/missing?include=category").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,97 @@
+/*!
+ * Login Enumeration Tests
+ *
+ * Checks that /auth/login gives an unknown username the same answer as a wrong
+ * password, that the dummy hash checked on a miss uses the Argon2 parameters of
+ * real hashes, and (best effort) that a miss spends comparable time verifying.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::auth::dummy_password_hash;
+use my_budget_server::rate_limit::RateLimit;
+use my_budget_server::test_support::{TEST_USERNAME, TestApp, TestResponse};
+use password_hash::PasswordHash;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+async fn test_app() -> TestApp {
+    let app = TestApp::with_auth_rate_limit(RateLimit {
+        max_attempts: 1000,
+        window: Duration::from_secs(60),
+    })
+    .await;
+    app.register_and_login().await;
+    app.set_cookie(None);
+    app
+}
+
+async fn login(app: &TestApp, username: &str) -> TestResponse {
+    app.post_json(
+        "/auth/login",
+        &json!({ "username": username, "password": "not-the-password" }),
+    )
+    .await
+}
+
+/// Fastest of a few logins as `username`, to keep scheduling noise out.
+async fn fastest_login(app: &TestApp, username: &str) -> Duration {
+    let mut fastest = Duration::MAX;
+    for _ in 0..3 {
+        let started = Instant::now();
+        login(app, username).await;
+        fastest = fastest.min(started.elapsed());
+    }
+    fastest
+}
+
+#[tokio::test]
+async fn test_unknown_user_answered_like_wrong_password() {
+    let app = test_app().await;
+
+    let wrong_password = login(&app, TEST_USERNAME).await;
+    let unknown_user = login(&app, "no_such_user").await;
+    assert_eq!(wrong_password.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(unknown_user.status, wrong_password.status);
+    assert_eq!(unknown_user.text(), wrong_password.text());
+    assert_eq!(unknown_user.text(), "Invalid credentials");
+}
+
+#[tokio::test]
+async fn test_dummy_hash_matches_real_parameters() {
+    let app = test_app().await;
+    let mut rows = app
+        .main_db()
+        .read()
+        .await
+        .query(
+            "SELECT password_hash FROM users WHERE name = ?",
+            [TEST_USERNAME],
+        )
+        .await
+        .unwrap();
+    let stored: String = rows.next().await.unwrap().unwrap().get(0).unwrap();
+
+    let real = PasswordHash::new(&stored).unwrap();
+    let dummy = PasswordHash::new(dummy_password_hash()).unwrap();
+    assert_eq!(dummy.algorithm, real.algorithm);
+    assert_eq!(dummy.version, real.version);
+    assert_eq!(dummy.params, real.params);
+    assert_ne!(dummy.salt, real.salt);
+}
+
+#[tokio::test]
+async fn test_unknown_user_runs_the_verifier() {
+    let app = test_app().await;
+    // Warm up, so neither side pays for the dummy hash being made
+    login(&app, "no_such_user").await;
+
+    let wrong_password = fastest_login(&app, TEST_USERNAME).await;
+    let unknown_user = fastest_login(&app, "no_such_user").await;
+    // Skipping Argon2 would make the miss orders of magnitude faster
+    assert!(
+        unknown_user * 4 >= wrong_password,
+        "unknown user took {:?}, wrong password {:?}",
+        unknown_user,
+        wrong_password
+    );
+}
@@ -0,0 +1,158 @@
+/*!
+ * Onboarding Status Tests
+ *
+ * Covers GET /onboarding/status and POST /onboarding/complete for fresh, partially
+ * set up, fully set up and dismissed accounts. Records in archived years count.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::OnboardingStatus;
+use my_budget_server::settings::set_setting;
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+// 2024-01-01 00:00:00 UTC
+const JAN_2024: i64 = 1704067200;
+
+async fn fetch_status(app: &TestApp) -> OnboardingStatus {
+    let response = app.get("/onboarding/status").await;
+    assert_eq!(response.status, StatusCode::OK);
+    response.json()
+}
+
+#[tokio::test]
+async fn test_onboarding_fresh_account() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let status = fetch_status(&app).await;
+
+    assert_eq!(
+        status,
+        OnboardingStatus {
+            has_categories: false,
+            has_budgets: false,
+            has_records: false,
+            preferences_set: false,
+            dismissed: false,
+            onboarding_complete: false,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_onboarding_partially_set_up() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_category_via_api(&app, "Food").await;
+
+    let status = fetch_status(&app).await;
+    assert!(status.has_categories);
+    assert!(!status.has_budgets);
+    assert!(!status.has_records);
+    assert!(!status.onboarding_complete);
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    set_setting(&user_db, "currency", "USD").await.unwrap();
+
+    let status = fetch_status(&app).await;
+    assert!(status.preferences_set);
+    assert!(!status.onboarding_complete);
+}
+
+#[tokio::test]
+async fn test_onboarding_complete_with_categories_budgets_and_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.5,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    // Records alone are not enough without a budget
+    let status = fetch_status(&app).await;
+    assert!(status.has_records);
+    assert!(!status.has_budgets);
+    assert!(!status.onboarding_complete);
+
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "monthly_budget": 300.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let status = fetch_status(&app).await;
+
+    assert!(status.has_categories);
+    assert!(status.has_budgets);
+    assert!(status.has_records);
+    assert!(!status.dismissed);
+    assert!(status.onboarding_complete);
+}
+
+#[tokio::test]
+async fn test_onboarding_counts_archived_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.5,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    // Every record moves out of the live table
+    let response = app
+        .post_json("/records/archive", &json!({ "before": JAN_2024 }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let status = fetch_status(&app).await;
+    assert!(status.has_records);
+}
+
+#[tokio::test]
+async fn test_onboarding_dismissed_account() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json("/onboarding/complete", &serde_json::json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let status: OnboardingStatus = response.json();
+    assert!(status.dismissed);
+    assert!(status.onboarding_complete);
+
+    // The wizard flag is not a user preference
+    let status = fetch_status(&app).await;
+    assert!(status.dismissed);
+    assert!(status.onboarding_complete);
+    assert!(!status.preferences_set);
+    assert!(!status.has_categories);
+}
+
+#[tokio::test]
+async fn test_onboarding_requires_login() {
+    let app = TestApp::new().await;
+
+    let response = app.get("/onboarding/status").await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post_json("/onboarding/complete", &serde_json::json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
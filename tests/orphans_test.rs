@@ -0,0 +1,157 @@
+/*!
+ * Orphaned Records Tests
+ *
+ * Covers GET /records/orphans and POST /records/orphans/repair for records whose
+ * category row was deleted behind the API's back: listing them, moving them to
+ * an existing category with counts before and after, and the history and
+ * version bump each repaired record gets.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{OrphanRepairResponse, Record};
+use serde_json::json;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+async fn delete_category_row(data_path: &str, user_id: &str, category_id: &str) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.write().await;
+    conn.execute("DELETE FROM categories WHERE id = ?", [category_id])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn list_and_repair_orphaned_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+    let misc = create_test_category_via_api(&app, "Misc").await;
+
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.0,
+        &food,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    let train = create_test_record(
+        &data_path,
+        &user_id,
+        "Train",
+        40.0,
+        &travel,
+        TEST_BASE_TIMESTAMP + 60,
+    )
+    .await;
+    let hotel = create_test_record(
+        &data_path,
+        &user_id,
+        "Hotel",
+        90.0,
+        &travel,
+        TEST_BASE_TIMESTAMP + 120,
+    )
+    .await;
+    delete_category_row(&data_path, &user_id, &travel).await;
+
+    let response = app.get("/records/orphans").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let orphans: Vec<Record> = response.json();
+    let ids: Vec<&str> = orphans.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec![hotel.as_str(), train.as_str()]);
+
+    let response = app
+        .post_json("/records/orphans/repair", &json!({ "category_id": misc }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let summary: OrphanRepairResponse = response.json();
+    assert_eq!(
+        summary,
+        OrphanRepairResponse {
+            orphans_before: 2,
+            repaired: 2,
+            orphans_after: 0,
+        }
+    );
+
+    let response = app.get("/records/orphans").await;
+    let orphans: Vec<Record> = response.json();
+    assert!(orphans.is_empty());
+
+    let response = app.get(&format!("/records?category_ids={}", misc)).await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 2);
+    assert_eq!(body["records"][0]["version"], 2);
+
+    let response = app.get(&format!("/records/{}/history", train)).await;
+    let history: serde_json::Value = response.json();
+    assert_eq!(history[0]["changes"]["category_id"]["old"], json!(travel));
+    assert_eq!(history[0]["changes"]["category_id"]["new"], json!(misc));
+}
+
+#[tokio::test]
+async fn repair_requires_an_existing_category() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let travel = create_test_category_via_api(&app, "Travel").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Train",
+        40.0,
+        &travel,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    delete_category_row(&data_path, &user_id, &travel).await;
+
+    // The deleted category itself is not a valid target
+    for category_id in [travel.as_str(), ""] {
+        let response = app
+            .post_json(
+                "/records/orphans/repair",
+                &json!({ "category_id": category_id }),
+            )
+            .await;
+        assert_eq!(
+            response.status,
+            StatusCode::BAD_REQUEST,
+            "{:?}",
+            category_id
+        );
+    }
+
+    let response = app.get("/records/orphans").await;
+    let orphans: Vec<Record> = response.json();
+    assert_eq!(orphans.len(), 1);
+}
+
+#[tokio::test]
+async fn repair_without_orphans_changes_nothing() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.0,
+        &food,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let response = app
+        .post_json("/records/orphans/repair", &json!({ "category_id": food }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let summary: OrphanRepairResponse = response.json();
+    assert_eq!(summary.orphans_before, 0);
+    assert_eq!(summary.repaired, 0);
+    assert_eq!(summary.orphans_after, 0);
+}
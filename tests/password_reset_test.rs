@@ -0,0 +1,164 @@
+/*!
+ * Password Reset Tests
+ *
+ * Covers /auth/forgot-password and /auth/reset-password: the same 202 whether
+ * or not an account has the address, a successful reset followed by login, reuse
+ * and expiry of tokens, and that a reset voids every other outstanding token.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::password_reset::purge_expired_reset_tokens;
+use my_budget_server::test_support::{TEST_PASSWORD, TestApp};
+use serde_json::json;
+
+const EMAIL: &str = "owner@example.com";
+const NEW_PASSWORD: &str = "a brand new passphrase";
+
+async fn register_with_email(app: &TestApp) {
+    let response = app
+        .post_json(
+            "/auth/register",
+            &json!({ "username": "account_owner", "password": TEST_PASSWORD, "email": EMAIL }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+}
+
+/// Asks for a reset of the account with `EMAIL` and returns the token sent.
+async fn request_reset(app: &TestApp) -> String {
+    let response = app
+        .post_json("/auth/forgot-password", &json!({ "email": EMAIL }))
+        .await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    let (email, token) = app.sent_reset_tokens().pop().expect("no token sent");
+    assert_eq!(email, EMAIL);
+    token
+}
+
+async fn reset(app: &TestApp, token: &str, password: &str) -> StatusCode {
+    app.post_json(
+        "/auth/reset-password",
+        &json!({ "token": token, "password": password }),
+    )
+    .await
+    .status
+}
+
+async fn login_status(app: &TestApp, password: &str) -> StatusCode {
+    app.post_json(
+        "/auth/login",
+        &json!({ "username": "account_owner", "password": password }),
+    )
+    .await
+    .status
+}
+
+#[tokio::test]
+async fn test_reset_then_login() {
+    let app = TestApp::new().await;
+    register_with_email(&app).await;
+    let token = request_reset(&app).await;
+
+    // A weak password is refused and leaves the token usable
+    assert_eq!(
+        reset(&app, &token, "password").await,
+        StatusCode::BAD_REQUEST
+    );
+
+    assert_eq!(
+        reset(&app, &token, NEW_PASSWORD).await,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        login_status(&app, TEST_PASSWORD).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(login_status(&app, NEW_PASSWORD).await, StatusCode::OK);
+
+    // Single use
+    let response = app
+        .post_json(
+            "/auth/reset-password",
+            &json!({ "token": token, "password": "yet another passphrase" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Invalid or expired reset token");
+    assert_eq!(login_status(&app, NEW_PASSWORD).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_forgot_password_does_not_reveal_accounts() {
+    let app = TestApp::new().await;
+    register_with_email(&app).await;
+    app.register_and_login_as("no_email_user", TEST_PASSWORD)
+        .await;
+
+    let response = app
+        .post_json(
+            "/auth/forgot-password",
+            &json!({ "email": "nobody@example.com" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    assert!(response.body.is_empty());
+    assert!(app.sent_reset_tokens().is_empty());
+
+    let response = app
+        .post_json(
+            "/auth/forgot-password",
+            &json!({ "email": "OWNER@example.com" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    assert!(response.body.is_empty());
+    assert_eq!(app.sent_reset_tokens().len(), 1);
+
+    assert_eq!(
+        reset(&app, "not-a-real-token", NEW_PASSWORD).await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn test_expired_token_rejected() {
+    let app = TestApp::new().await;
+    register_with_email(&app).await;
+    let token = request_reset(&app).await;
+
+    app.main_db()
+        .write()
+        .await
+        .execute("UPDATE password_reset_tokens SET expires_at = 1000", ())
+        .await
+        .unwrap();
+    assert_eq!(
+        reset(&app, &token, NEW_PASSWORD).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(login_status(&app, TEST_PASSWORD).await, StatusCode::OK);
+
+    let purged = purge_expired_reset_tokens(app.main_db(), 2000)
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+}
+
+#[tokio::test]
+async fn test_reset_voids_other_tokens() {
+    let app = TestApp::new().await;
+    register_with_email(&app).await;
+    let first = request_reset(&app).await;
+    let second = request_reset(&app).await;
+    assert_ne!(first, second);
+
+    assert_eq!(
+        reset(&app, &second, NEW_PASSWORD).await,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        reset(&app, &first, "yet another passphrase").await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(login_status(&app, NEW_PASSWORD).await, StatusCode::OK);
+}
@@ -0,0 +1,86 @@
+/*!
+ * Password Strength Tests
+ *
+ * Covers validate_password_strength directly for each rejection reason (too
+ * short, equal to the username, on the common-password list) and checks that
+ * /auth/register refuses a weak password without creating the user.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::auth::validate_password_strength;
+use my_budget_server::constants::MIN_PASSWORD_LENGTH;
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+#[test]
+fn test_short_password_rejected() {
+    let short = "x7#".repeat(MIN_PASSWORD_LENGTH)[..MIN_PASSWORD_LENGTH - 1].to_string();
+    let (status, message) = validate_password_strength(&short, "some_user").unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        message,
+        format!(
+            "Password must be at least {} characters long",
+            MIN_PASSWORD_LENGTH
+        )
+    );
+}
+
+#[test]
+fn test_password_matching_username_rejected() {
+    let (status, message) = validate_password_strength("Budget_Owner", "budget_owner").unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(message, "Password cannot be the same as the username");
+}
+
+#[test]
+fn test_common_password_rejected() {
+    for password in ["password", "12345678", "Qwerty123", "P@ssw0rd"] {
+        let (status, message) = validate_password_strength(password, "some_user").unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", password);
+        assert_eq!(
+            message, "Password is too common, choose a less guessable one",
+            "{}",
+            password
+        );
+    }
+}
+
+#[test]
+fn test_strong_password_accepted() {
+    assert!(validate_password_strength("correct horse battery", "some_user").is_ok());
+    assert!(validate_password_strength("some_user_2024", "some_user").is_ok());
+}
+
+#[tokio::test]
+async fn test_weak_registration_refused() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_json(
+            "/auth/register",
+            &json!({ "username": "weak_user", "password": "123456789" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "Password is too common, choose a less guessable one"
+    );
+
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": "weak_user", "password": "123456789" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post_json(
+            "/auth/register",
+            &json!({ "username": "weak_user", "password": "a less guessable one" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+}
@@ -0,0 +1,203 @@
+/*!
+ * Payment Method Tests
+ *
+ * Covers the optional `payment_method` on records: set on create, changed or
+ * cleared on update, used as a GET /records filter and as an extra grouping of
+ * the category summary, and null for rows written before the column existed.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::constants::MAX_PAYMENT_METHOD_LENGTH;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{CategoryTotal, Record};
+use my_budget_server::records::{summarize_by_category, top_records};
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+use tempfile::tempdir;
+
+async fn create_paid_record(
+    app: &TestApp,
+    name: &str,
+    category_id: &str,
+    payment_method: Option<&str>,
+) -> Record {
+    create_record_via_api(
+        app,
+        &json!({
+            "name": name,
+            "amount": 20.0,
+            "category_id": category_id,
+            "timestamp": recent_timestamp(),
+            "payment_method": payment_method,
+        }),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_create_with_and_without_payment_method() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let record = create_paid_record(&app, "Lunch", &category_id, Some(" Visa ")).await;
+    assert_eq!(record.payment_method.as_deref(), Some("Visa"));
+    let record = create_paid_record(&app, "Dinner", &category_id, None).await;
+    assert_eq!(record.payment_method, None);
+
+    let response = app.get("/records?sort_by=name&order=asc").await;
+    let body: Value = response.json();
+    assert_eq!(body["records"][0]["payment_method"], Value::Null);
+    assert_eq!(body["records"][1]["payment_method"], "Visa");
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Snack",
+                "amount": 2.0,
+                "category_id": category_id,
+                "payment_method": "x".repeat(MAX_PAYMENT_METHOD_LENGTH + 1),
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_update_sets_and_clears_payment_method() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_paid_record(&app, "Lunch", &category_id, None).await;
+    let path = format!("/records/{}", record.id);
+
+    let response = app
+        .put_json(&path, &json!({ "payment_method": "cash" }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json::<Record>().payment_method.as_deref(),
+        Some("cash")
+    );
+
+    // Leaving the field out keeps the current value
+    let response = app.put_json(&path, &json!({ "amount": 21.0 })).await;
+    assert_eq!(
+        response.json::<Record>().payment_method.as_deref(),
+        Some("cash")
+    );
+
+    let response = app.put_json(&path, &json!({ "payment_method": "" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json::<Record>().payment_method, None);
+
+    let response = app.get(&format!("/records/{}/history", record.id)).await;
+    let history: Value = response.json();
+    assert_eq!(history[0]["changes"]["payment_method"]["old"], "cash");
+    assert_eq!(history[0]["changes"]["payment_method"]["new"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_filter_records_by_payment_method() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    create_paid_record(&app, "Lunch", &category_id, Some("cash")).await;
+    create_paid_record(&app, "Dinner", &category_id, Some("Visa")).await;
+    create_paid_record(&app, "Coffee", &category_id, None).await;
+
+    let response = app.get("/records?payment_method=cash").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["records"][0]["name"], "Lunch");
+
+    let response = app.get("/records?payment_method=%20%20").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_category_summary_by_payment_method() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    create_paid_record(&app, "Lunch", &category_id, Some("cash")).await;
+    create_paid_record(&app, "Dinner", &category_id, Some("cash")).await;
+    create_paid_record(&app, "Groceries", &category_id, Some("Visa")).await;
+    create_paid_record(&app, "Coffee", &category_id, None).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let totals = summarize_by_category(&user_db, 0, recent_timestamp(), true, false, &[])
+        .await
+        .unwrap();
+    let groups: Vec<(Option<&str>, f64, u32)> = totals
+        .iter()
+        .map(|t| (t.payment_method.as_deref(), t.total_amount, t.record_count))
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            (Some("cash"), 40.0, 2),
+            (None, 20.0, 1),
+            (Some("Visa"), 20.0, 1)
+        ]
+    );
+
+    // Without the flag the category stays a single group
+    let response = app.get("/records/summary/by-category").await;
+    let totals: Vec<CategoryTotal> = response.json();
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].record_count, 4);
+    let body: Value = app.get("/records/summary/by-category").await.json();
+    assert!(body[0].get("payment_method").is_none());
+
+    let response = app
+        .get("/records/summary/by-category?group_by=payment_method")
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body.as_array().unwrap().len(), 3);
+    assert_eq!(body[0]["payment_method"], "cash");
+
+    let response = app
+        .get("/records/summary/by-category?group_by=currency")
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_existing_rows_read_back_without_payment_method() {
+    let temp_dir = tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let user_id = uuid::Uuid::new_v4().to_string();
+
+    // A user database created before records had a payment method
+    {
+        let path = temp_dir.path().join(format!("user_{}.db", user_id));
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "CREATE TABLE records (id TEXT PRIMARY KEY, name TEXT NOT NULL, amount REAL NOT NULL, category_id TEXT NOT NULL, timestamp INTEGER NOT NULL)",
+            (),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES ('old', 'Legacy', 1.0, 'c', 1690000000)",
+            (),
+        )
+        .await
+        .unwrap();
+    }
+
+    let user_db = get_user_db(data_path, &user_id).await.unwrap();
+    let totals = summarize_by_category(&user_db, 0, 1700000000, true, false, &[])
+        .await
+        .unwrap();
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].payment_method, None);
+
+    let records = top_records(&user_db, 0, 1700000000, 1, None).await.unwrap();
+    assert_eq!(records[0].id, "old");
+    assert_eq!(records[0].payment_method, None);
+}
@@ -0,0 +1,113 @@
+/*!
+ * Category/Month Pivot Tests
+ *
+ * Covers GET /records/pivot: monthly expense totals per category for one UTC year,
+ * records right at the year boundaries, the "unknown" row for deleted categories
+ * and zero-filled rows for categories without expenses.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::PivotTable;
+use serde_json::json;
+
+const DEC_31_2023_LAST_SECOND: i64 = 1704067199;
+const JAN_1_2024: i64 = 1704067200;
+const MAR_15_2024: i64 = 1710460800;
+const DEC_31_2024_LAST_SECOND: i64 = 1735689599;
+const JAN_1_2025: i64 = 1735689600;
+
+fn months(values: &[(usize, f64)]) -> Vec<f64> {
+    let mut months = vec![0.0; 12];
+    for &(month, value) in values {
+        months[month - 1] = value;
+    }
+    months
+}
+
+#[tokio::test]
+async fn pivot_totals_each_category_per_month() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let rent = create_test_category_via_api(&app, "Rent").await;
+
+    let fixtures = [
+        ("New Year's Eve", 99.0, &food, DEC_31_2023_LAST_SECOND),
+        ("Brunch", 20.0, &food, JAN_1_2024),
+        ("Lunch", 12.5, &food, MAR_15_2024),
+        ("Dinner", 7.5, &food, MAR_15_2024 + 3600),
+        ("Rent", 800.0, &rent, DEC_31_2024_LAST_SECOND),
+        ("Next year", 99.0, &rent, JAN_1_2025),
+    ];
+    for (name, amount, category_id, timestamp) in fixtures {
+        create_test_record(&data_path, &user_id, name, amount, category_id, timestamp).await;
+    }
+    create_test_record(&data_path, &user_id, "Gone", 5.0, "deleted", MAR_15_2024).await;
+    // Income and other currencies are not expenses in the pivoted currency
+    for body in [
+        json!({ "name": "Salary", "amount": 3000.0, "category_id": rent, "kind": "income", "timestamp": MAR_15_2024 }),
+        json!({ "name": "Abroad", "amount": 70.0, "category_id": food, "currency": "EUR", "timestamp": MAR_15_2024 }),
+    ] {
+        let response = app.post_json("/records", &body).await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    }
+
+    let response = app.get("/records/pivot?year=2024").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let table: PivotTable = response.json();
+    assert_eq!(table.year, 2024);
+    assert_eq!(table.currency, "USD");
+
+    let names: Vec<&str> = table
+        .rows
+        .iter()
+        .map(|r| r.category_name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Food", "Rent", "unknown"]);
+
+    assert_eq!(table.rows[0].category_id.as_deref(), Some(food.as_str()));
+    assert_eq!(table.rows[0].months, months(&[(1, 20.0), (3, 20.0)]));
+    assert_eq!(table.rows[0].total, 40.0);
+    assert_eq!(table.rows[1].months, months(&[(12, 800.0)]));
+    assert_eq!(table.rows[1].total, 800.0);
+    assert_eq!(table.rows[2].category_id, None);
+    assert_eq!(table.rows[2].months, months(&[(3, 5.0)]));
+
+    let response = app.get("/records/pivot?year=2024&currency=EUR").await;
+    let table: PivotTable = response.json();
+    assert_eq!(table.rows.len(), 1);
+    assert_eq!(table.rows[0].months, months(&[(3, 70.0)]));
+}
+
+#[tokio::test]
+async fn pivot_can_include_categories_without_expenses() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_category_via_api(&app, "Travel").await;
+    create_test_category_via_api(&app, "Books").await;
+    create_test_record(&data_path, &user_id, "Lunch", 12.5, &food, MAR_15_2024).await;
+
+    let response = app.get("/records/pivot?year=2024").await;
+    let table: PivotTable = response.json();
+    assert_eq!(table.rows.len(), 1);
+
+    let response = app.get("/records/pivot?year=2024&include_empty=true").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let table: PivotTable = response.json();
+    let names: Vec<&str> = table
+        .rows
+        .iter()
+        .map(|r| r.category_name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Books", "Food", "Travel"]);
+    assert_eq!(table.rows[0].months, vec![0.0; 12]);
+    assert_eq!(table.rows[0].total, 0.0);
+    assert_eq!(table.rows[1].total, 12.5);
+
+    for query in ["", "?year=abc", "?year=10000"] {
+        let response = app.get(&format!("/records/pivot{}", query)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
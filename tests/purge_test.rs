@@ -0,0 +1,137 @@
+/*!
+ * Record Purge Tests
+ *
+ * Covers DELETE /records: the dry run without `confirm=true`, the guarded delete
+ * of a time range (optionally one category) with its tags, required bounds, and
+ * that records outside the range survive.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::PurgeRecordsResponse;
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+const ONE_DAY: i64 = 24 * 60 * 60;
+
+fn range() -> String {
+    format!(
+        "start_time={}&end_time={}",
+        TEST_BASE_TIMESTAMP + ONE_DAY,
+        TEST_BASE_TIMESTAMP + 3 * ONE_DAY
+    )
+}
+
+async fn remaining_names(app: &TestApp) -> Vec<String> {
+    let response = app.get("/records?sort_by=timestamp&order=asc").await;
+    let body: Value = response.json();
+    body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn dry_run_then_purge_a_time_range() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Junk").await;
+    for day in 0..5 {
+        let response = app
+            .post_json(
+                "/records",
+                &json!({
+                    "name": format!("Day {}", day),
+                    "amount": 10.0,
+                    "category_id": category_id,
+                    "timestamp": TEST_BASE_TIMESTAMP + day * ONE_DAY,
+                    "tags": ["imported"],
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    }
+
+    let response = app.delete(&format!("/records?{}", range())).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let summary: PurgeRecordsResponse = response.json();
+    assert_eq!(
+        summary,
+        PurgeRecordsResponse {
+            count: 3,
+            dry_run: true
+        }
+    );
+    assert_eq!(remaining_names(&app).await.len(), 5);
+
+    let response = app
+        .delete(&format!("/records?{}&confirm=true", range()))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let summary: PurgeRecordsResponse = response.json();
+    assert_eq!(
+        summary,
+        PurgeRecordsResponse {
+            count: 3,
+            dry_run: false
+        }
+    );
+    assert_eq!(remaining_names(&app).await, vec!["Day 0", "Day 4"]);
+
+    // Tags of purged records go with them
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query("SELECT COUNT(*) FROM record_tags", ())
+        .await
+        .unwrap();
+    let tag_count: u32 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(tag_count, 2);
+}
+
+#[tokio::test]
+async fn purge_can_be_limited_to_a_category() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let junk = create_test_category_via_api(&app, "Junk").await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let day = TEST_BASE_TIMESTAMP + 2 * ONE_DAY;
+    create_test_record(&data_path, &user_id, "Junk", 1.0, &junk, day).await;
+    create_test_record(&data_path, &user_id, "Lunch", 12.0, &food, day).await;
+
+    let response = app
+        .delete(&format!(
+            "/records?{}&category_id={}&confirm=true",
+            range(),
+            junk
+        ))
+        .await;
+    let summary: PurgeRecordsResponse = response.json();
+    assert_eq!(summary.count, 1);
+    assert_eq!(remaining_names(&app).await, vec!["Lunch"]);
+}
+
+#[tokio::test]
+async fn purge_requires_both_bounds() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(&data_path, &user_id, "Keep", 1.0, "c", TEST_BASE_TIMESTAMP).await;
+
+    for query in [
+        "confirm=true".to_string(),
+        format!("start_time={}&confirm=true", TEST_BASE_TIMESTAMP),
+        format!("end_time={}&confirm=true", TEST_BASE_TIMESTAMP),
+        format!(
+            "start_time={}&end_time={}&confirm=true",
+            TEST_BASE_TIMESTAMP + 1,
+            TEST_BASE_TIMESTAMP
+        ),
+    ] {
+        let response = app.delete(&format!("/records?{}", query)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+    assert_eq!(remaining_names(&app).await, vec!["Keep"]);
+}
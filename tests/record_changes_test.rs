@@ -0,0 +1,188 @@
+/*!
+ * Record Changes Tests
+ *
+ * Covers the delta sync endpoint GET /records/changes: records created or
+ * updated and ids of records deleted since a watermark each show up exactly
+ * once, untouched records are left out, archived records still count as
+ * records, and `server_time` works as the next `since`.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::RecordChangesResponse;
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+// 2024-01-01 00:00:00 UTC
+const JAN_2024: i64 = 1704067200;
+
+async fn changes_since(app: &TestApp, since: i64) -> RecordChangesResponse {
+    let response = app.get(&format!("/records/changes?since={}", since)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+#[tokio::test]
+async fn delta_contains_each_change_once() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    // Inserted directly, so their updated_at is far in the past
+    let keep = create_test_record(
+        &data_path,
+        &user_id,
+        "Keep",
+        1.0,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    let edit = create_test_record(
+        &data_path,
+        &user_id,
+        "Edit",
+        2.0,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    let drop = create_test_record(
+        &data_path,
+        &user_id,
+        "Drop",
+        3.0,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let initial = changes_since(&app, 0).await;
+    assert_eq!(initial.records.len(), 3);
+    assert!(initial.deleted_ids.is_empty());
+    let since = initial.server_time;
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "New",
+                "amount": 4.0,
+                "category_id": category_id,
+                "timestamp": TEST_BASE_TIMESTAMP,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let created: serde_json::Value = response.json();
+
+    for amount in [20.0, 21.0] {
+        let response = app
+            .put_json(&format!("/records/{}", edit), &json!({ "amount": amount }))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    }
+
+    let response = app.delete(&format!("/records/{}", drop)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let delta = changes_since(&app, since).await;
+    let mut changed: Vec<&str> = delta.records.iter().map(|r| r.id.as_str()).collect();
+    changed.sort();
+    let mut expected = vec![edit.as_str(), created["id"].as_str().unwrap()];
+    expected.sort();
+    assert_eq!(changed, expected);
+    assert!(!changed.contains(&keep.as_str()));
+    let edited = delta.records.iter().find(|r| r.id == edit).unwrap();
+    assert_eq!(edited.amount, 21.0);
+    assert_eq!(delta.deleted_ids, vec![drop]);
+    assert!(delta.server_time >= since);
+}
+
+#[tokio::test]
+async fn purged_and_short_lived_records_are_reported_as_deleted() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let old = create_test_record(
+        &data_path,
+        &user_id,
+        "Old",
+        1.0,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    let since = changes_since(&app, 0).await.server_time;
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Typo",
+                "amount": 4.0,
+                "category_id": category_id,
+                "timestamp": TEST_BASE_TIMESTAMP + 60,
+            }),
+        )
+        .await;
+    let typo: serde_json::Value = response.json();
+    let typo = typo["id"].as_str().unwrap().to_string();
+    let response = app.delete(&format!("/records/{}", typo)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app
+        .delete(&format!(
+            "/records?start_time={}&end_time={}&confirm=true",
+            TEST_BASE_TIMESTAMP, TEST_BASE_TIMESTAMP
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let delta = changes_since(&app, since).await;
+    assert!(delta.records.is_empty());
+    let mut deleted = delta.deleted_ids.clone();
+    deleted.sort();
+    let mut expected = vec![old, typo];
+    expected.sort();
+    assert_eq!(deleted, expected);
+}
+
+#[tokio::test]
+async fn archived_records_are_neither_dropped_nor_deleted() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let archived = create_test_record(
+        &data_path,
+        &user_id,
+        "Archived",
+        1.0,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    let response = app
+        .post_json("/records/archive", &json!({ "before": JAN_2024 }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let changes = changes_since(&app, 0).await;
+    let ids: Vec<&str> = changes.records.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec![archived.as_str()]);
+    assert!(changes.deleted_ids.is_empty());
+}
+
+#[tokio::test]
+async fn since_is_required_and_validated() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/records/changes").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.get("/records/changes?since=last-week").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("since"));
+
+    let response = app.get("/records/changes?since=2024-05-01T00:00:00Z").await;
+    assert_eq!(response.status, StatusCode::OK);
+}
@@ -0,0 +1,227 @@
+/*!
+ * Record History Tests
+ *
+ * Covers the audit trail behind GET /records/{id}/history: one entry per update
+ * or delete listing exactly the changed fields, newest first, history that
+ * outlives later updates and the record itself, and amounts stored as numbers
+ * whatever amount format the request asked for.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{Record, RecordHistoryAction, RecordHistoryEntry};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+async fn create_groceries(app: &TestApp, category_id: &str) -> Record {
+    create_record_via_api(
+        app,
+        &json!({
+            "name": "Groceries",
+            "amount": 42.0,
+            "category_id": category_id,
+            "timestamp": recent_timestamp(),
+            "tags": ["weekly"],
+        }),
+    )
+    .await
+}
+
+async fn get_history(app: &TestApp, record_id: &str) -> Vec<RecordHistoryEntry> {
+    let response = app.get(&format!("/records/{}/history", record_id)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::OK,
+        "history request failed: {}",
+        response.text()
+    );
+    response.json()
+}
+
+#[tokio::test]
+async fn test_new_record_has_empty_history() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_groceries(&app, &category_id).await;
+
+    assert!(get_history(&app, &record.id).await.is_empty());
+
+    let response = app.get("/records/missing/history").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_multi_field_update_produces_single_entry() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let other_category_id = create_test_category_via_api(&app, "Household").await;
+    let record = create_groceries(&app, &category_id).await;
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({
+                "name": "Groceries and soap",
+                "amount": 55.5,
+                "category_id": other_category_id,
+                "tags": ["weekly", "shared"],
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let history = get_history(&app, &record.id).await;
+    assert_eq!(history.len(), 1);
+
+    let entry = &history[0];
+    assert_eq!(entry.record_id, record.id);
+    assert_eq!(entry.action, RecordHistoryAction::Update);
+    assert_eq!(
+        entry.changes.keys().collect::<Vec<_>>(),
+        vec!["amount", "category_id", "name", "tags"]
+    );
+    assert_eq!(entry.changes["name"].old, json!("Groceries"));
+    assert_eq!(entry.changes["name"].new, json!("Groceries and soap"));
+    assert_eq!(entry.changes["amount"].old, json!(42.0));
+    assert_eq!(entry.changes["amount"].new, json!(55.5));
+    assert_eq!(entry.changes["category_id"].new, json!(other_category_id));
+    assert_eq!(entry.changes["tags"].old, json!(["weekly"]));
+    assert_eq!(entry.changes["tags"].new, json!(["shared", "weekly"]));
+}
+
+#[tokio::test]
+async fn test_history_is_newest_first_and_survives_updates() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_groceries(&app, &category_id).await;
+
+    for amount in [43.0, 44.0, 45.0] {
+        let response = app
+            .put_json(
+                &format!("/records/{}", record.id),
+                &json!({ "amount": amount }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+
+    // Re-sending the current values changes nothing and records nothing
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "amount": 45.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let history = get_history(&app, &record.id).await;
+    let amounts: Vec<_> = history
+        .iter()
+        .map(|entry| {
+            (
+                entry.changes["amount"].old.clone(),
+                entry.changes["amount"].new.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        amounts,
+        vec![
+            (json!(44.0), json!(45.0)),
+            (json!(43.0), json!(44.0)),
+            (json!(42.0), json!(43.0)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_delete_keeps_history_with_snapshot() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_groceries(&app, &category_id).await;
+
+    app.put_json(
+        &format!("/records/{}", record.id),
+        &json!({ "name": "Market" }),
+    )
+    .await;
+    let response = app.delete(&format!("/records/{}", record.id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let history = get_history(&app, &record.id).await;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].action, RecordHistoryAction::Delete);
+    assert_eq!(history[1].action, RecordHistoryAction::Update);
+
+    let snapshot = &history[0].changes;
+    assert_eq!(snapshot.len(), 8);
+    assert_eq!(snapshot["name"].old, json!("Market"));
+    assert_eq!(snapshot["starred"].old, json!(false));
+    assert_eq!(snapshot["amount"].old, json!(42.0));
+    assert_eq!(snapshot["tags"].old, json!(["weekly"]));
+    assert!(snapshot.values().all(|change| change.new.is_null()));
+
+    // Deleting again is still a 404 and adds nothing
+    let response = app.delete(&format!("/records/{}", record.id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(get_history(&app, &record.id).await.len(), 2);
+}
+
+#[tokio::test]
+async fn test_split_amounts_stored_as_numbers_in_string_mode() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let household = create_test_category_via_api(&app, "Household").await;
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Supermarket",
+                "amount": 50.0,
+                "timestamp": recent_timestamp(),
+                "splits": [
+                    { "category_id": food, "amount": 30.0 },
+                    { "category_id": household, "amount": 20.0 },
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let record: Record = response.json();
+
+    // The history must not depend on the format the deleting request asked for
+    let response = app
+        .delete(&format!("/records/{}?amount_format=string", record.id))
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let history = get_history(&app, &record.id).await;
+    let splits = history[0].changes["splits"].old.as_array().unwrap();
+    let mut amounts: Vec<f64> = splits
+        .iter()
+        .map(|split| split["amount"].as_f64().unwrap())
+        .collect();
+    amounts.sort_by(f64::total_cmp);
+    assert_eq!(amounts, vec![20.0, 30.0]);
+}
+
+#[tokio::test]
+async fn test_history_is_per_user() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_groceries(&app, &category_id).await;
+    app.put_json(
+        &format!("/records/{}", record.id),
+        &json!({ "amount": 1.0 }),
+    )
+    .await;
+
+    let other = TestApp::new().await;
+    other
+        .register_and_login_as("other_user", "other-password")
+        .await;
+    let response = other.get(&format!("/records/{}/history", record.id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
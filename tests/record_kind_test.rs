@@ -0,0 +1,143 @@
+/*!
+ * Record Kind Tests
+ *
+ * Covers the expense/income kind on records: defaults, validation, switching on
+ * update, filtering GET /records and separate totals in stats and summaries.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{Record, RecordKind, RecordStats, SummaryBucket};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+async fn create_kind_record(
+    app: &TestApp,
+    category_id: &str,
+    name: &str,
+    amount: f64,
+    kind: Option<&str>,
+) -> Record {
+    let mut payload = json!({
+        "name": name,
+        "amount": amount,
+        "category_id": category_id,
+        "timestamp": recent_timestamp(),
+    });
+    if let Some(kind) = kind {
+        payload["kind"] = json!(kind);
+    }
+    create_record_via_api(app, &payload).await
+}
+
+#[tokio::test]
+async fn test_kind_defaults_to_expense() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let record = create_kind_record(&app, &category_id, "Lunch", 12.0, None).await;
+    assert_eq!(record.kind, RecordKind::Expense);
+
+    let record = create_kind_record(&app, &category_id, "Refund", 5.0, Some("income")).await;
+    assert_eq!(record.kind, RecordKind::Income);
+
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    let kinds: Vec<&str> = body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["kind"].as_str().unwrap())
+        .collect();
+    assert!(kinds.contains(&"income") && kinds.contains(&"expense"));
+}
+
+#[tokio::test]
+async fn test_unknown_kind_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Lunch",
+                "amount": 12.0,
+                "category_id": category_id,
+                "timestamp": recent_timestamp(),
+                "kind": "transfer",
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Record kind must be 'expense' or 'income'");
+
+    let record = create_kind_record(&app, &category_id, "Lunch", 12.0, None).await;
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "kind": "Income" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.get("/records?kind=both").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_update_switches_kind() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Work").await;
+    let record = create_kind_record(&app, &category_id, "Bonus", 100.0, None).await;
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "kind": "income" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let updated: Record = response.json();
+    assert_eq!(updated.kind, RecordKind::Income);
+    assert_eq!(updated.amount, 100.0);
+
+    let response = app.get("/records?kind=income").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["records"][0]["id"], record.id);
+}
+
+#[tokio::test]
+async fn test_filter_and_separate_totals() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Mixed").await;
+
+    create_kind_record(&app, &category_id, "Salary", 2000.0, Some("income")).await;
+    create_kind_record(&app, &category_id, "Rent", 800.0, None).await;
+    create_kind_record(&app, &category_id, "Groceries", 150.5, Some("expense")).await;
+
+    let response = app.get("/records?kind=expense").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 2);
+
+    let response = app.get("/records/stats").await;
+    let stats: RecordStats = response.json();
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.income_total, 2000.0);
+    assert_eq!(stats.expense_total, 950.5);
+
+    let response = app.get("/records/stats?kind=income").await;
+    let stats: RecordStats = response.json();
+    assert_eq!(stats.count, 1);
+    assert_eq!(stats.expense_total, 0.0);
+
+    let response = app.get("/records/summary?group_by=month").await;
+    let buckets: Vec<SummaryBucket> = response.json();
+    let income: f64 = buckets.iter().map(|b| b.income_total).sum();
+    let expense: f64 = buckets.iter().map(|b| b.expense_total).sum();
+    assert_eq!(income, 2000.0);
+    assert_eq!(expense, 950.5);
+}
@@ -0,0 +1,101 @@
+/*!
+ * Record Name Autocomplete Tests
+ *
+ * Covers GET /records/names: case-insensitive prefix matching, names differing
+ * only in case collapsing into one suggestion, ordering by most recent use, the
+ * limit, literal matching of LIKE wildcards, and prefix validation.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::test_support::TestApp;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+async fn suggestions(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app.get(&format!("/records/names?{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+#[tokio::test]
+async fn names_match_prefix_ignoring_case_newest_first() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    for (name, offset) in [
+        ("coffee", 0),
+        ("Cola", 100),
+        ("Coffee", 200),
+        ("Concert tickets", 50),
+        ("Lidl", 300),
+        ("Hot cocoa", 400),
+    ] {
+        create_test_record(
+            &data_path,
+            &user_id,
+            name,
+            3.0,
+            "c",
+            TEST_BASE_TIMESTAMP + offset,
+        )
+        .await;
+    }
+
+    // "coffee" and "Coffee" are one suggestion, spelled as last used
+    assert_eq!(
+        suggestions(&app, "prefix=co").await,
+        vec!["Coffee", "Cola", "Concert tickets"]
+    );
+    assert_eq!(
+        suggestions(&app, "prefix=CO&limit=2").await,
+        vec!["Coffee", "Cola"]
+    );
+    assert_eq!(suggestions(&app, "prefix=lidl").await, vec!["Lidl"]);
+    assert!(suggestions(&app, "prefix=xyz").await.is_empty());
+}
+
+#[tokio::test]
+async fn wildcards_in_the_prefix_match_literally() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "50% off",
+        1.0,
+        "c",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "500 club",
+        1.0,
+        "c",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    create_test_record(&data_path, &user_id, "a_b", 1.0, "c", TEST_BASE_TIMESTAMP).await;
+    create_test_record(&data_path, &user_id, "axb", 1.0, "c", TEST_BASE_TIMESTAMP).await;
+
+    assert_eq!(suggestions(&app, "prefix=50%25").await, vec!["50% off"]);
+    assert_eq!(suggestions(&app, "prefix=a_").await, vec!["a_b"]);
+}
+
+#[tokio::test]
+async fn invalid_prefixes_and_limits_are_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    for query in [
+        "".to_string(),
+        "prefix=".to_string(),
+        "prefix=%20".to_string(),
+        format!("prefix={}", "a".repeat(300)),
+        "prefix=co&limit=0".to_string(),
+        "prefix=co&limit=51".to_string(),
+    ] {
+        let response = app.get(&format!("/records/names?{}", query)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
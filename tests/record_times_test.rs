@@ -0,0 +1,196 @@
+/*!
+ * Record Entry Time Tests
+ *
+ * Covers `created_at` and `updated_at` on records: set on create, bumped only by
+ * updates that change something, backfilled for existing databases and carried
+ * through backup imports.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::Record;
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+use tempfile::tempdir;
+
+const OLD_ENTRY_TIME: i64 = 1700000000;
+
+fn now() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp()
+}
+
+async fn create_lunch(app: &TestApp, category_id: &str) -> Record {
+    create_record_via_api(
+        app,
+        &json!({
+            "name": "Lunch",
+            "amount": 12.5,
+            "category_id": category_id,
+            "timestamp": now() - 7200,
+        }),
+    )
+    .await
+}
+
+async fn backdate_entry_times(data_path: &str, user_id: &str, record_id: &str) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    user_db
+        .write()
+        .await
+        .execute(
+            "UPDATE records SET created_at = ?, updated_at = ? WHERE id = ?",
+            (OLD_ENTRY_TIME, OLD_ENTRY_TIME, record_id),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_create_sets_entry_times() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let before = now();
+    let record = create_lunch(&app, &category_id).await;
+    let after = now();
+
+    assert!(record.created_at >= before && record.created_at <= after);
+    assert_eq!(record.updated_at, record.created_at);
+    // Entry time is independent of the backdated transaction time
+    assert!(record.timestamp < record.created_at);
+
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"][0]["created_at"], record.created_at);
+    assert_eq!(body["records"][0]["updated_at"], record.updated_at);
+}
+
+#[tokio::test]
+async fn test_update_bumps_updated_at_only() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_lunch(&app, &category_id).await;
+    backdate_entry_times(&data_path, &user_id, &record.id).await;
+
+    let before = now();
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "amount": 13.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let updated: Record = response.json();
+
+    assert_eq!(updated.created_at, OLD_ENTRY_TIME);
+    assert!(updated.updated_at >= before);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records[0].created_at, OLD_ENTRY_TIME);
+    assert_eq!(records[0].updated_at, updated.updated_at);
+}
+
+#[tokio::test]
+async fn test_noop_update_keeps_updated_at() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let record = create_lunch(&app, &category_id).await;
+    backdate_entry_times(&data_path, &user_id, &record.id).await;
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "amount": record.amount }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let updated: Record = response.json();
+    assert_eq!(updated.updated_at, OLD_ENTRY_TIME);
+}
+
+#[tokio::test]
+async fn test_migration_backfills_entry_times() {
+    let temp_dir = tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let user_id = uuid::Uuid::new_v4().to_string();
+
+    // A user database created before records tracked entry times
+    {
+        let path = temp_dir.path().join(format!("user_{}.db", user_id));
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "CREATE TABLE records (id TEXT PRIMARY KEY, name TEXT NOT NULL, amount REAL NOT NULL, category_id TEXT NOT NULL, timestamp INTEGER NOT NULL)",
+            (),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES ('old', 'Legacy', 1.0, 'c', 1690000000)",
+            (),
+        )
+        .await
+        .unwrap();
+    }
+
+    get_user_db(data_path, &user_id).await.unwrap();
+    let (records, _) = get_records_from_db(data_path, &user_id, None, None, None).await;
+    assert_eq!(records[0].created_at, 1690000000);
+    assert_eq!(records[0].updated_at, 1690000000);
+
+    // Reopening does not run the backfill again
+    let user_db = get_user_db(data_path, &user_id).await.unwrap();
+    user_db
+        .write()
+        .await
+        .execute("UPDATE records SET updated_at = 1695000000", ())
+        .await
+        .unwrap();
+    get_user_db(data_path, &user_id).await.unwrap();
+    let (records, _) = get_records_from_db(data_path, &user_id, None, None, None).await;
+    assert_eq!(records[0].updated_at, 1695000000);
+}
+
+#[tokio::test]
+async fn test_import_keeps_or_stamps_entry_times() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let document = json!({
+        "version": 1,
+        "categories": [{ "id": "cat-food", "name": "Food", "is_income": false }],
+        "records": [
+            {
+                "id": "with-times",
+                "name": "Lunch",
+                "amount": 12.5,
+                "category_id": "cat-food",
+                "timestamp": OLD_ENTRY_TIME,
+                "created_at": OLD_ENTRY_TIME + 10,
+                "updated_at": OLD_ENTRY_TIME + 20,
+            },
+            {
+                "id": "without-times",
+                "name": "Dinner",
+                "amount": 30.0,
+                "category_id": "cat-food",
+                "timestamp": OLD_ENTRY_TIME,
+            },
+        ],
+    });
+
+    let before = now();
+    let response = app.post_json("/import?mode=merge", &document).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let with_times = records.iter().find(|r| r.id == "with-times").unwrap();
+    assert_eq!(with_times.created_at, OLD_ENTRY_TIME + 10);
+    assert_eq!(with_times.updated_at, OLD_ENTRY_TIME + 20);
+
+    let without_times = records.iter().find(|r| r.id == "without-times").unwrap();
+    assert!(without_times.created_at >= before);
+    assert_eq!(without_times.updated_at, without_times.created_at);
+}
@@ -0,0 +1,127 @@
+/*!
+ * Record Version Tests
+ *
+ * Covers optimistic concurrency on PUT /records/{id}: every change bumps the
+ * record's `version`, an `If-Match` carrying a stale version gets a 409 with the
+ * latest record, and updates without the header keep last-write-wins.
+ */
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use common::*;
+use my_budget_server::models::Record;
+use my_budget_server::test_support::{TestApp, TestResponse};
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+async fn put_if_match(app: &TestApp, record_id: &str, version: &str, body: &Value) -> TestResponse {
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri(format!("/records/{}", record_id))
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::IF_MATCH, version)
+        .body(Body::from(serde_json::to_vec(body).unwrap()))
+        .unwrap();
+    app.request(request).await
+}
+
+#[tokio::test]
+async fn test_interleaved_updates_second_gets_conflict() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.0,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    // Both devices load the record before either saves
+    let response = app.get("/records?limit=1").await;
+    let body: Value = response.json();
+    let loaded_version = body["records"][0]["version"].as_i64().unwrap();
+    assert_eq!(loaded_version, 1);
+
+    let response = put_if_match(&app, &record_id, "\"1\"", &json!({ "amount": 15.0 })).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let first: Record = response.json();
+    assert_eq!(first.version, 2);
+
+    let response = put_if_match(&app, &record_id, "\"1\"", &json!({ "name": "Dinner" })).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    let latest: Record = response.json();
+    assert_eq!(latest.version, 2);
+    assert_eq!(latest.amount, 15.0);
+    assert_eq!(latest.name, "Lunch");
+
+    // Retrying against the version it was given goes through
+    let response = put_if_match(&app, &record_id, "2", &json!({ "name": "Dinner" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let merged: Record = response.json();
+    assert_eq!(merged.version, 3);
+    assert_eq!(merged.name, "Dinner");
+    assert_eq!(merged.amount, 15.0);
+}
+
+#[tokio::test]
+async fn test_updates_without_if_match_keep_last_write_wins() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.0,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    let path = format!("/records/{}", record_id);
+
+    let response = app.put_json(&path, &json!({ "amount": 15.0 })).await;
+    assert_eq!(response.json::<Record>().version, 2);
+    let response = app.put_json(&path, &json!({ "amount": 18.0 })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json::<Record>().version, 3);
+
+    // Saving the same values is not a change and keeps the version
+    let response = app.put_json(&path, &json!({ "amount": 18.0 })).await;
+    assert_eq!(response.json::<Record>().version, 3);
+
+    let response = put_if_match(&app, &record_id, "*", &json!({ "amount": 20.0 })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json::<Record>().version, 4);
+}
+
+#[tokio::test]
+async fn test_invalid_if_match_is_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        12.0,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    for version in ["", "abc", "\"1"] {
+        let response = put_if_match(&app, &record_id, version, &json!({ "amount": 15.0 })).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{:?}", version);
+    }
+
+    let response = put_if_match(&app, "missing", "1", &json!({ "amount": 15.0 })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    assert_eq!(body["records"][0]["amount"], 12.0);
+    assert_eq!(body["records"][0]["version"], 1);
+}
@@ -6,9 +6,17 @@
  *
  * Test Categories:
  * - Basic CRUD operations (empty database, record retrieval)
- * - Time-range filtering (start_time, end_time, both)
- * - Pagination and limits (default behavior, custom limits)
+ * - Time-range filtering (start_time, end_time, both, include_future, RFC3339 bounds, range)
+ * - Pagination and limits (default behavior, custom limits, out-of-range limits)
+ * - Optional totals (include_total, count_only, include_sum, X-Total-Count, HEAD)
+ * - Pagination metadata (limit, has_more)
+ * - Cursor pagination (keyset paging, tampered cursors)
+ * - Amount range filtering (min_amount, max_amount, refunds)
+ * - Multi-category filtering (category_ids lists and limits)
+ * - Fetching by record id (ids lists and limits)
  * - Ordering and consistency (timestamp ordering, edge cases)
+ * - Sorting (sort_by/order whitelist, limit interaction)
+ * - Creation timestamps (client-supplied or defaulting to now)
  * - Data integrity (category preservation, amount accuracy)
  *
  * All tests use isolated temporary databases for complete test isolation.
@@ -16,8 +24,16 @@
 
 mod common;
 
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
 use common::*;
+use my_budget_server::constants::{
+    DEFAULT_RECORDS_LIMIT, MAX_LIMIT, MAX_RECORD_AMOUNT, MAX_RECORD_FILTER_IDS,
+};
 use my_budget_server::models::Record;
+use my_budget_server::test_support::TestApp;
 
 // Test data constants - only for widely reused values
 const TEST_BASE_TIMESTAMP: i64 = 1700000000; // Nov 14, 2023 22:13:20 UTC
@@ -58,100 +74,38 @@ async fn create_sample_records(data_path: &str, user_id: &str) {
 }
 
 // Helper functions for update tests
+
+/// Updates a record through `PUT /records/{id}`, returning the error body on failure.
 async fn update_record_in_db(
-    data_path: &str,
-    user_id: &str,
+    app: &TestApp,
     record_id: &str,
     name: Option<&str>,
     amount: Option<f64>,
     category_id: Option<&str>,
     timestamp: Option<i64>,
 ) -> Result<Record, String> {
-    use my_budget_server::database::get_user_db;
-    use my_budget_server::records::extract_record_from_row;
-
-    // Validate that at least one field is being updated
-    if name.is_none() && amount.is_none() && category_id.is_none() && timestamp.is_none() {
-        return Err("At least one field must be provided for update".to_string());
+    let mut payload = serde_json::Map::new();
+    if let Some(name) = name {
+        payload.insert("name".to_string(), name.into());
     }
-
-    // Input validation for provided fields - reuse production validation functions
-    if let Some(name_val) = name {
-        if let Err((_, error_msg)) = my_budget_server::records::validate_record_name(name_val) {
-            return Err(error_msg);
-        }
+    if let Some(amount) = amount {
+        payload.insert("amount".to_string(), amount.into());
     }
-
-    if let Some(amount_val) = amount {
-        if let Err((_, error_msg)) = my_budget_server::records::validate_record_amount(amount_val) {
-            return Err(error_msg);
-        }
+    if let Some(category_id) = category_id {
+        payload.insert("category_id".to_string(), category_id.into());
     }
-
-    if let Some(category_val) = category_id {
-        if let Err((_, error_msg)) = my_budget_server::records::validate_category_id(category_val) {
-            return Err(error_msg);
-        }
+    if let Some(timestamp) = timestamp {
+        payload.insert("timestamp".to_string(), timestamp.into());
     }
 
-    let user_db = get_user_db(data_path, user_id)
-        .await
-        .map_err(|e| format!("Failed to get user database: {}", e))?;
-
-    let conn = user_db.write().await;
-
-    // First, get the existing record
-    let mut existing_rows = conn
-        .query(
-            "SELECT id, name, amount, category_id, timestamp FROM records WHERE id = ?",
-            [record_id],
-        )
-        .await
-        .map_err(|e| format!("Failed to query existing record: {}", e))?;
-
-    let existing_record = if let Some(row) = existing_rows
-        .next()
-        .await
-        .map_err(|e| format!("Failed to read existing record: {}", e))?
-    {
-        extract_record_from_row(row)
-            .map_err(|e| format!("Failed to extract existing record: {}", e.1))?
+    let response = app
+        .put_json(&format!("/records/{}", record_id), &payload)
+        .await;
+    if response.status == StatusCode::OK {
+        Ok(response.json())
     } else {
-        return Err("Record not found".to_string());
-    };
-
-    // Build updated values
-    let updated_name = name.unwrap_or(&existing_record.name);
-    let updated_amount = amount.unwrap_or(existing_record.amount);
-    let updated_category_id = category_id.unwrap_or(&existing_record.category_id);
-    let updated_timestamp = timestamp.unwrap_or(existing_record.timestamp);
-
-    // Update the record
-    let affected_rows = conn
-        .execute(
-            "UPDATE records SET name = ?, amount = ?, category_id = ?, timestamp = ? WHERE id = ?",
-            (
-                updated_name,
-                updated_amount,
-                updated_category_id,
-                updated_timestamp,
-                record_id,
-            ),
-        )
-        .await
-        .map_err(|e| format!("Failed to update record: {}", e))?;
-
-    if affected_rows == 0 {
-        return Err("Record not found or no changes made".to_string());
+        Err(response.text())
     }
-
-    Ok(Record {
-        id: record_id.to_string(),
-        name: updated_name.to_string(),
-        amount: updated_amount,
-        category_id: updated_category_id.to_string(),
-        timestamp: updated_timestamp,
-    })
 }
 
 async fn get_single_record_from_db(
@@ -160,14 +114,14 @@ async fn get_single_record_from_db(
     record_id: &str,
 ) -> Option<Record> {
     use my_budget_server::database::get_user_db;
-    use my_budget_server::records::extract_record_from_row;
+    use my_budget_server::records::{RECORD_COLUMNS, extract_record_from_row};
 
     let user_db = get_user_db(data_path, user_id).await.ok()?;
     let conn = user_db.read().await;
 
     let mut rows = conn
         .query(
-            "SELECT id, name, amount, category_id, timestamp FROM records WHERE id = ?",
+            &format!("SELECT {} FROM records WHERE id = ?", RECORD_COLUMNS),
             [record_id],
         )
         .await
@@ -331,6 +285,278 @@ async fn default_limit() {
     assert_eq!(total_count, 3);
 }
 
+#[tokio::test]
+async fn future_records_need_include_future() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    create_test_record(&data_path, &user_id, "Today", 10.0, "test", now - 60).await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Next rent",
+        900.0,
+        "test",
+        now + 86400,
+    )
+    .await;
+
+    let names = |body: &serde_json::Value| -> Vec<String> {
+        body["records"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // The default window ends now
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(names(&body), vec!["Today"]);
+    assert_eq!(body["total_count"], 1);
+
+    let response = app.get("/records?include_future=true").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(names(&body), vec!["Next rent", "Today"]);
+    assert_eq!(body["total_count"], 2);
+
+    // An explicit end_time still wins over the flag
+    let response = app
+        .get(&format!("/records?include_future=true&end_time={}", now))
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(names(&body), vec!["Today"]);
+}
+
+#[tokio::test]
+async fn range_shortcuts_replace_explicit_bounds() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    create_test_record(&data_path, &user_id, "Recent", 10.0, "test", now - 60).await;
+    create_test_record(&data_path, &user_id, "Old", 20.0, "test", now - 40 * 86400).await;
+
+    let response = app.get("/records?range=last_30d").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["records"][0]["name"], "Recent");
+
+    let response = app
+        .get("/records?range=year_to_date&tz_offset_minutes=-300")
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    for query in [
+        "range=this_month&start_time=0",
+        "range=today&end_time=100",
+        "range=last_week",
+        "range=today&ids=a",
+        "range=today&tz_offset_minutes=900",
+    ] {
+        let response = app.get(&format!("/records?{}", query)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn time_bounds_accept_rfc3339_dates() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    // 2024-05-01T00:00:00Z and a day later
+    create_test_record(&data_path, &user_id, "May 1", 10.0, "test", 1714521600).await;
+    create_test_record(&data_path, &user_id, "May 2", 20.0, "test", 1714608000).await;
+
+    for query in [
+        "start_time=2024-05-01T00:00:00Z&end_time=2024-05-01T23:59:59Z",
+        "start_time=1714521600&end_time=2024-05-01T23:59:59Z",
+        "start_time=2024-05-01T02:00:00%2B02:00&end_time=1714607999",
+    ] {
+        let response = app.get(&format!("/records?{}", query)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", query);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["total_count"], 1, "{}", query);
+        assert_eq!(body["records"][0]["name"], "May 1", "{}", query);
+    }
+
+    // A date before 1970 is a valid negative bound
+    let response = app
+        .get("/records?start_time=1969-07-20T20:17:00Z&end_time=2024-05-03T00:00:00Z")
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 2);
+
+    let response = app.get("/records?end_time=2024-05-32T00:00:00Z").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let message = response.text();
+    assert!(message.contains("end_time"), "{}", message);
+    assert!(message.contains("RFC3339"), "{}", message);
+
+    let response = app.get("/records/summary?start_time=yesterday").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("start_time"));
+}
+
+#[tokio::test]
+async fn fetch_records_by_ids() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let old = create_test_record(&data_path, &user_id, "Old", 10.0, "test", 1000).await;
+    let recent = create_test_record(&data_path, &user_id, "Recent", 20.0, "test", now - 60).await;
+    let planned =
+        create_test_record(&data_path, &user_id, "Planned", 30.0, "test", now + 86400).await;
+    create_test_record(&data_path, &user_id, "Other", 40.0, "test", now - 120).await;
+
+    // Outside the default window and unknown ids do not matter, the order is the usual one
+    let response = app
+        .get(&format!(
+            "/records?ids={},missing,{},{},{}",
+            old, planned, recent, old
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: serde_json::Value = response.json();
+    let ids: Vec<&str> = body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec![planned.as_str(), recent.as_str(), old.as_str()]);
+    assert_eq!(body["total_count"], 3);
+
+    let response = app.get("/records?ids=missing").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 0);
+}
+
+#[tokio::test]
+async fn include_sum_covers_the_whole_filter() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    create_test_record(&data_path, &user_id, "Lunch", 12.5, "food", now - 300).await;
+    create_test_record(&data_path, &user_id, "Dinner", 30.0, "food", now - 200).await;
+    create_test_record(&data_path, &user_id, "Refund", -2.5, "food", now - 100).await;
+    create_test_record(&data_path, &user_id, "Bus", 3.0, "transport", now - 50).await;
+
+    // The sum covers every matching record, not just the one on the page
+    let response = app
+        .get("/records?category_ids=food&include_sum=true&limit=1")
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"].as_array().unwrap().len(), 1);
+    assert_eq!(body["total_count"], 3);
+    assert_eq!(body["total_amount"], 40.0);
+
+    // Also available without the count, and zero when nothing matches
+    let response = app
+        .get("/records?include_sum=true&include_total=false")
+        .await;
+    let body: serde_json::Value = response.json();
+    assert!(body["total_count"].is_null());
+    assert_eq!(body["total_amount"], 43.0);
+
+    let response = app.get("/records?include_sum=true&ids=missing").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_amount"], 0.0);
+
+    // Left out unless requested
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert!(body.get("total_amount").is_none());
+}
+
+#[tokio::test]
+async fn total_count_header_and_head_requests() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+    let query = "/records?category_ids=food,transport&limit=1";
+
+    let response = app.get(query).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 2);
+    assert_eq!(response.header("x-total-count"), Some("2"));
+
+    let request = Request::builder()
+        .method(Method::HEAD)
+        .uri(query)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(request).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("x-total-count"), Some("2"));
+    assert!(response.body.is_empty());
+
+    // No count, no header
+    let response = app.get("/records?include_total=false").await;
+    assert!(response.header("x-total-count").is_none());
+
+    // HEAD validates the filters like GET
+    let request = Request::builder()
+        .method(Method::HEAD)
+        .uri("/records?min_amount=5&max_amount=1")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(request).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn invalid_ids_lists_are_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let too_many: Vec<String> = (0..=MAX_RECORD_FILTER_IDS)
+        .map(|i| format!("id-{}", i))
+        .collect();
+    let response = app
+        .get(&format!("/records?ids={}", too_many.join(",")))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        format!("ids cannot contain more than {} ids", MAX_RECORD_FILTER_IDS)
+    );
+
+    for query in ["ids=", "ids=,%20,"] {
+        let response = app.get(&format!("/records?{}", query)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+        assert_eq!(response.text(), "ids must contain at least one id");
+    }
+
+    for query in ["ids=a&start_time=0", "ids=a&end_time=100"] {
+        let response = app.get(&format!("/records?{}", query)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn out_of_range_limits_are_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let response = app.get(&format!("/records?limit={}", MAX_LIMIT)).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.get(&format!("/records?limit={}", MAX_LIMIT + 1)).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        format!("Limit cannot exceed {}", MAX_LIMIT)
+    );
+
+    // Zero is an error rather than an empty page
+    let response = app.get("/records?limit=0").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Limit must be greater than 0");
+
+    for limit in ["100000000", "99999999999", "-1"] {
+        let response = app.get(&format!("/records?limit={}", limit)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", limit);
+    }
+}
+
 #[tokio::test]
 async fn ordering_consistency() {
     let (data_path, user_id, _temp_dir) = setup_test_environment().await;
@@ -428,8 +654,8 @@ async fn category_preservation() {
     assert_eq!(records.len(), 2);
 
     // Check that categories are preserved correctly
-    assert_eq!(records[0].category_id, "transport");
-    assert_eq!(records[1].category_id, "food");
+    assert_eq!(records[0].category_id.as_deref(), Some("transport"));
+    assert_eq!(records[1].category_id.as_deref(), Some("food"));
 
     // Check that names and amounts match categories
     assert_eq!(records[0].name, "Gas Expense");
@@ -444,7 +670,7 @@ async fn category_preservation() {
 /// Verifies that the name changes while other fields remain unchanged.
 #[tokio::test]
 async fn update_record_single_field_name() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let original_name = "Original Name";
@@ -464,22 +690,18 @@ async fn update_record_single_field_name() {
 
     // Update only the name
     let updated_name = "Updated Name Only";
-    let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        Some(updated_name),
-        None,
-        None,
-        None,
-    )
-    .await
-    .expect("Failed to update record name");
+    let updated_record =
+        update_record_in_db(&app, &record_id, Some(updated_name), None, None, None)
+            .await
+            .expect("Failed to update record name");
 
     // Verify the update
     assert_eq!(updated_record.name, updated_name);
     assert_eq!(updated_record.amount, original_amount);
-    assert_eq!(updated_record.category_id, original_category);
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(original_category)
+    );
     assert_eq!(updated_record.timestamp, original_timestamp);
     assert_eq!(updated_record.id, record_id);
 
@@ -490,7 +712,7 @@ async fn update_record_single_field_name() {
 
     assert_eq!(db_record.name, updated_name);
     assert_eq!(db_record.amount, original_amount);
-    assert_eq!(db_record.category_id, original_category);
+    assert_eq!(db_record.category_id.as_deref(), Some(original_category));
     assert_eq!(db_record.timestamp, original_timestamp);
 }
 
@@ -498,7 +720,7 @@ async fn update_record_single_field_name() {
 /// Verifies that the amount changes while other fields remain unchanged.
 #[tokio::test]
 async fn update_record_single_field_amount() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let original_name = "Test Record";
@@ -518,22 +740,18 @@ async fn update_record_single_field_amount() {
 
     // Update only the amount
     let updated_amount = 99.99;
-    let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        None,
-        Some(updated_amount),
-        None,
-        None,
-    )
-    .await
-    .expect("Failed to update record amount");
+    let updated_record =
+        update_record_in_db(&app, &record_id, None, Some(updated_amount), None, None)
+            .await
+            .expect("Failed to update record amount");
 
     // Verify the update
     assert_eq!(updated_record.name, original_name);
     assert_eq!(updated_record.amount, updated_amount);
-    assert_eq!(updated_record.category_id, original_category);
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(original_category)
+    );
     assert_eq!(updated_record.timestamp, original_timestamp);
     assert_eq!(updated_record.id, record_id);
 
@@ -550,7 +768,7 @@ async fn update_record_single_field_amount() {
 /// Verifies that the category changes while other fields remain unchanged.
 #[tokio::test]
 async fn update_record_single_field_category() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let original_name = "Category Test";
@@ -569,23 +787,19 @@ async fn update_record_single_field_category() {
     .await;
 
     // Update only the category
-    let updated_category = "updated_category";
-    let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        None,
-        None,
-        Some(updated_category),
-        None,
-    )
-    .await
-    .expect("Failed to update record category");
+    let updated_category = create_test_category_via_api(&app, "Updated Category").await;
+    let updated_record =
+        update_record_in_db(&app, &record_id, None, None, Some(&updated_category), None)
+            .await
+            .expect("Failed to update record category");
 
     // Verify the update
     assert_eq!(updated_record.name, original_name);
     assert_eq!(updated_record.amount, original_amount);
-    assert_eq!(updated_record.category_id, updated_category);
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(updated_category.as_str())
+    );
     assert_eq!(updated_record.timestamp, original_timestamp);
     assert_eq!(updated_record.id, record_id);
 
@@ -594,7 +808,10 @@ async fn update_record_single_field_category() {
         .await
         .expect("Failed to retrieve updated record from database");
 
-    assert_eq!(db_record.category_id, updated_category);
+    assert_eq!(
+        db_record.category_id.as_deref(),
+        Some(updated_category.as_str())
+    );
     assert_eq!(db_record.name, original_name);
     assert_eq!(db_record.amount, original_amount);
 }
@@ -603,7 +820,7 @@ async fn update_record_single_field_category() {
 /// Verifies that the timestamp changes while other fields remain unchanged.
 #[tokio::test]
 async fn update_record_single_field_timestamp() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let original_name = "Timestamp Test";
@@ -623,22 +840,18 @@ async fn update_record_single_field_timestamp() {
 
     // Update only the timestamp
     let updated_timestamp = TEST_BASE_TIMESTAMP + 500;
-    let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        None,
-        None,
-        None,
-        Some(updated_timestamp),
-    )
-    .await
-    .expect("Failed to update record timestamp");
+    let updated_record =
+        update_record_in_db(&app, &record_id, None, None, None, Some(updated_timestamp))
+            .await
+            .expect("Failed to update record timestamp");
 
     // Verify the update
     assert_eq!(updated_record.name, original_name);
     assert_eq!(updated_record.amount, original_amount);
-    assert_eq!(updated_record.category_id, original_category);
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(original_category)
+    );
     assert_eq!(updated_record.timestamp, updated_timestamp);
     assert_eq!(updated_record.id, record_id);
 
@@ -650,14 +863,14 @@ async fn update_record_single_field_timestamp() {
     assert_eq!(db_record.timestamp, updated_timestamp);
     assert_eq!(db_record.name, original_name);
     assert_eq!(db_record.amount, original_amount);
-    assert_eq!(db_record.category_id, original_category);
+    assert_eq!(db_record.category_id.as_deref(), Some(original_category));
 }
 
 /// Tests updating multiple fields of a record simultaneously.
 /// Verifies that multiple fields change while unchanged fields remain intact.
 #[tokio::test]
 async fn update_record_multiple_fields() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let original_name = "Multiple Fields Test";
@@ -679,8 +892,7 @@ async fn update_record_multiple_fields() {
     let updated_name = "Updated Multiple Fields";
     let updated_amount = 55.55;
     let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         &record_id,
         Some(updated_name),
         Some(updated_amount),
@@ -693,7 +905,10 @@ async fn update_record_multiple_fields() {
     // Verify the updates
     assert_eq!(updated_record.name, updated_name);
     assert_eq!(updated_record.amount, updated_amount);
-    assert_eq!(updated_record.category_id, original_category); // unchanged
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(original_category)
+    ); // unchanged
     assert_eq!(updated_record.timestamp, original_timestamp); // unchanged
     assert_eq!(updated_record.id, record_id);
 
@@ -704,7 +919,7 @@ async fn update_record_multiple_fields() {
 
     assert_eq!(db_record.name, updated_name);
     assert_eq!(db_record.amount, updated_amount);
-    assert_eq!(db_record.category_id, original_category);
+    assert_eq!(db_record.category_id.as_deref(), Some(original_category));
     assert_eq!(db_record.timestamp, original_timestamp);
 }
 
@@ -712,7 +927,7 @@ async fn update_record_multiple_fields() {
 /// Verifies that all fields change to their new values.
 #[tokio::test]
 async fn update_record_all_fields() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let original_name = "All Fields Test";
@@ -733,16 +948,15 @@ async fn update_record_all_fields() {
     // Update all fields
     let updated_name = "All Fields Updated";
     let updated_amount = 88.88;
-    let updated_category = "updated_all";
+    let updated_category = create_test_category_via_api(&app, "Updated All").await;
     let updated_timestamp = TEST_BASE_TIMESTAMP + 1000;
 
     let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         &record_id,
         Some(updated_name),
         Some(updated_amount),
-        Some(updated_category),
+        Some(&updated_category),
         Some(updated_timestamp),
     )
     .await
@@ -751,7 +965,10 @@ async fn update_record_all_fields() {
     // Verify all updates
     assert_eq!(updated_record.name, updated_name);
     assert_eq!(updated_record.amount, updated_amount);
-    assert_eq!(updated_record.category_id, updated_category);
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(updated_category.as_str())
+    );
     assert_eq!(updated_record.timestamp, updated_timestamp);
     assert_eq!(updated_record.id, record_id);
 
@@ -762,7 +979,10 @@ async fn update_record_all_fields() {
 
     assert_eq!(db_record.name, updated_name);
     assert_eq!(db_record.amount, updated_amount);
-    assert_eq!(db_record.category_id, updated_category);
+    assert_eq!(
+        db_record.category_id.as_deref(),
+        Some(updated_category.as_str())
+    );
     assert_eq!(db_record.timestamp, updated_timestamp);
 }
 
@@ -770,7 +990,7 @@ async fn update_record_all_fields() {
 /// Should fail with appropriate error message.
 #[tokio::test]
 async fn update_record_empty_payload() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -784,8 +1004,7 @@ async fn update_record_empty_payload() {
     .await;
 
     // Try to update with no fields (should fail)
-    let result =
-        update_record_in_db(&data_path, &user_id, &record_id, None, None, None, None).await;
+    let result = update_record_in_db(&app, &record_id, None, None, None, None).await;
 
     // Should fail because no fields were provided
     assert!(result.is_err());
@@ -801,7 +1020,7 @@ async fn update_record_empty_payload() {
 /// Should fail validation as empty names are not allowed.
 #[tokio::test]
 async fn update_record_empty_name() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -816,8 +1035,7 @@ async fn update_record_empty_name() {
 
     // Test empty name (should fail validation)
     let result = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         &record_id,
         Some(""), // Empty name
         None,
@@ -835,7 +1053,7 @@ async fn update_record_empty_name() {
 /// Should fail validation as zero amounts are not allowed.
 #[tokio::test]
 async fn update_record_zero_amount() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -850,8 +1068,7 @@ async fn update_record_zero_amount() {
 
     // Test zero amount (should fail validation)
     let result = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         &record_id,
         None,
         Some(0.0), // Zero amount
@@ -869,7 +1086,7 @@ async fn update_record_zero_amount() {
 /// Should fail validation as empty categories are not allowed.
 #[tokio::test]
 async fn update_record_empty_category() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -884,8 +1101,7 @@ async fn update_record_empty_category() {
 
     // Test empty category (should fail validation)
     let result = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         &record_id,
         None,
         None,
@@ -903,7 +1119,7 @@ async fn update_record_empty_category() {
 /// Should fail validation as whitespace-only names are treated as empty.
 #[tokio::test]
 async fn update_record_whitespace_only_name() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -918,8 +1134,7 @@ async fn update_record_whitespace_only_name() {
 
     // Test whitespace-only name (should fail validation)
     let result = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         &record_id,
         Some("   "), // Whitespace-only name
         None,
@@ -934,8 +1149,7 @@ async fn update_record_whitespace_only_name() {
 
     // Test mixed whitespace (tabs and spaces)
     let result = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         &record_id,
         Some(" \t \n "), // Mixed whitespace
         None,
@@ -953,7 +1167,7 @@ async fn update_record_whitespace_only_name() {
 /// Should succeed as 255 characters is the boundary limit.
 #[tokio::test]
 async fn update_record_max_name_length() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -968,16 +1182,8 @@ async fn update_record_max_name_length() {
 
     // Test 255-character name (should succeed)
     let max_length_name = "a".repeat(255);
-    let result = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        Some(&max_length_name),
-        None,
-        None,
-        None,
-    )
-    .await;
+    let result =
+        update_record_in_db(&app, &record_id, Some(&max_length_name), None, None, None).await;
 
     assert!(result.is_ok());
     let updated_record = result.unwrap();
@@ -989,7 +1195,7 @@ async fn update_record_max_name_length() {
 /// Should fail validation as names cannot exceed 255 characters.
 #[tokio::test]
 async fn update_record_too_long_name() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -1004,16 +1210,8 @@ async fn update_record_too_long_name() {
 
     // Test 256-character name (should fail)
     let too_long_name = "a".repeat(256);
-    let result = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        Some(&too_long_name),
-        None,
-        None,
-        None,
-    )
-    .await;
+    let result =
+        update_record_in_db(&app, &record_id, Some(&too_long_name), None, None, None).await;
 
     assert!(result.is_err());
     let error_message = result.unwrap_err();
@@ -1024,7 +1222,7 @@ async fn update_record_too_long_name() {
 /// Should succeed as negative amounts may represent refunds or corrections.
 #[tokio::test]
 async fn update_record_negative_amount() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record
     let record_id = create_test_record(
@@ -1039,33 +1237,53 @@ async fn update_record_negative_amount() {
 
     // Test negative amount (should succeed)
     let negative_amount = -25.50;
-    let result = update_record_in_db(
+    let result =
+        update_record_in_db(&app, &record_id, None, Some(negative_amount), None, None).await;
+
+    assert!(result.is_ok());
+    let updated_record = result.unwrap();
+    assert_eq!(updated_record.amount, negative_amount);
+}
+
+/// Tests updating a record with out-of-range timestamps.
+/// Negative values and dates far in the future are rejected and leave the record unchanged.
+#[tokio::test]
+async fn update_record_invalid_timestamp() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    let record_id = create_test_record(
         &data_path,
         &user_id,
-        &record_id,
-        None,
-        Some(negative_amount),
-        None,
-        None,
+        "Timestamp Bounds Test",
+        10.00,
+        "test_category",
+        TEST_BASE_TIMESTAMP,
     )
     .await;
 
-    assert!(result.is_ok());
-    let updated_record = result.unwrap();
-    assert_eq!(updated_record.amount, negative_amount);
+    // Year 9999
+    for timestamp in [-5, 253402300799] {
+        let result = update_record_in_db(&app, &record_id, None, None, None, Some(timestamp)).await;
+        let error = result.expect_err("out-of-range timestamp should be rejected");
+        assert!(error.contains("Timestamp"), "{}", error);
+    }
+
+    let db_record = get_single_record_from_db(&data_path, &user_id, &record_id)
+        .await
+        .expect("Failed to retrieve record from database");
+    assert_eq!(db_record.timestamp, TEST_BASE_TIMESTAMP);
 }
 
 /// Tests updating a non-existent record.
 /// Should fail with "Record not found" error.
 #[tokio::test]
 async fn update_record_nonexistent_record() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     // Try to update a record that doesn't exist
     let fake_record_id = "non-existent-record-id";
     let result = update_record_in_db(
-        &data_path,
-        &user_id,
+        &app,
         fake_record_id,
         Some("This should fail"),
         None,
@@ -1084,7 +1302,7 @@ async fn update_record_nonexistent_record() {
 /// Verifies data integrity by checking that unmodified fields remain intact.
 #[tokio::test]
 async fn update_record_preserves_unchanged_fields() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create initial record with specific values
     let original_name = "Preserve Test";
@@ -1104,42 +1322,34 @@ async fn update_record_preserves_unchanged_fields() {
 
     // Update only the name, leaving everything else unchanged
     let updated_name = "Name Only Updated";
-    let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        Some(updated_name),
-        None,
-        None,
-        None,
-    )
-    .await
-    .expect("Failed to update record with preserved fields");
+    let updated_record =
+        update_record_in_db(&app, &record_id, Some(updated_name), None, None, None)
+            .await
+            .expect("Failed to update record with preserved fields");
 
     // Verify name changed but everything else preserved
     assert_eq!(updated_record.name, updated_name);
     assert_eq!(updated_record.amount, original_amount);
-    assert_eq!(updated_record.category_id, original_category);
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(original_category)
+    );
     assert_eq!(updated_record.timestamp, original_timestamp);
 
     // Now update only the amount
     let updated_amount = 999.99;
-    let updated_record = update_record_in_db(
-        &data_path,
-        &user_id,
-        &record_id,
-        None,
-        Some(updated_amount),
-        None,
-        None,
-    )
-    .await
-    .expect("Failed to update record amount with preserved fields");
+    let updated_record =
+        update_record_in_db(&app, &record_id, None, Some(updated_amount), None, None)
+            .await
+            .expect("Failed to update record amount with preserved fields");
 
     // Verify amount changed but name (from previous update) and other fields preserved
     assert_eq!(updated_record.name, updated_name); // From previous update
     assert_eq!(updated_record.amount, updated_amount); // New update
-    assert_eq!(updated_record.category_id, original_category); // Preserved
+    assert_eq!(
+        updated_record.category_id.as_deref(),
+        Some(original_category)
+    ); // Preserved
     assert_eq!(updated_record.timestamp, original_timestamp); // Preserved
 
     // Verify final state in database
@@ -1149,42 +1359,42 @@ async fn update_record_preserves_unchanged_fields() {
 
     assert_eq!(db_record.name, updated_name);
     assert_eq!(db_record.amount, updated_amount);
-    assert_eq!(db_record.category_id, original_category);
+    assert_eq!(db_record.category_id.as_deref(), Some(original_category));
     assert_eq!(db_record.timestamp, original_timestamp);
 }
 
 // Delete Record Tests
 
-/// Helper function to delete a record from the database
-async fn delete_record_in_db(
-    data_path: &str,
-    user_id: &str,
-    record_id: &str,
-) -> Result<(), String> {
-    use my_budget_server::database::get_user_db;
-
-    let user_db = get_user_db(data_path, user_id)
-        .await
-        .map_err(|e| format!("Failed to get user database: {}", e))?;
-
-    let conn = user_db.write().await;
-    let affected_rows = conn
-        .execute("DELETE FROM records WHERE id = ?", [record_id])
-        .await
-        .map_err(|e| format!("Failed to delete record: {}", e))?;
+/// Percent-encodes a path segment so arbitrary ids survive the trip through the URI.
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
 
-    if affected_rows == 0 {
-        return Err("Record not found".to_string());
+/// Deletes a record through `DELETE /records/{id}`, returning the error body on failure.
+async fn delete_record_in_db(app: &TestApp, record_id: &str) -> Result<(), String> {
+    let response = app
+        .delete(&format!("/records/{}", encode_path_segment(record_id)))
+        .await;
+    if response.status == StatusCode::NO_CONTENT {
+        Ok(())
+    } else {
+        Err(response.text())
     }
-
-    Ok(())
 }
 
 /// Tests successful deletion of an existing record.
 /// Verifies that the record is removed and cannot be retrieved afterward.
 #[tokio::test]
 async fn delete_record_success() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create a test record
     let record_id = create_test_record(
@@ -1204,7 +1414,7 @@ async fn delete_record_success() {
     assert_eq!(record_before.name, "Record to Delete");
 
     // Delete the record
-    delete_record_in_db(&data_path, &user_id, &record_id)
+    delete_record_in_db(&app, &record_id)
         .await
         .expect("Failed to delete existing record");
 
@@ -1220,12 +1430,12 @@ async fn delete_record_success() {
 /// Verifies that appropriate error is returned.
 #[tokio::test]
 async fn delete_record_not_found() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     let non_existent_id = "non-existent-record-id";
 
     // Try to delete non-existent record
-    let result = delete_record_in_db(&data_path, &user_id, non_existent_id).await;
+    let result = delete_record_in_db(&app, non_existent_id).await;
     assert!(result.is_err(), "Deleting non-existent record should fail");
     assert_eq!(result.unwrap_err(), "Record not found");
 }
@@ -1234,7 +1444,7 @@ async fn delete_record_not_found() {
 /// Creates multiple records, deletes one, and verifies others remain intact.
 #[tokio::test]
 async fn delete_record_preserves_other_records() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create multiple test records
     let record_id_1 = create_test_record(
@@ -1274,7 +1484,7 @@ async fn delete_record_preserves_other_records() {
     assert_eq!(total_before, 3);
 
     // Delete the middle record
-    delete_record_in_db(&data_path, &user_id, &record_id_2)
+    delete_record_in_db(&app, &record_id_2)
         .await
         .expect("Failed to delete middle record");
 
@@ -1304,7 +1514,7 @@ async fn delete_record_preserves_other_records() {
 /// Verifies that the database becomes empty after all deletions.
 #[tokio::test]
 async fn delete_all_records_sequentially() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, data_path, user_id) = setup_test_app().await;
 
     // Create multiple records
     let record_ids = [
@@ -1345,7 +1555,7 @@ async fn delete_all_records_sequentially() {
 
     // Delete records one by one
     for (i, record_id) in record_ids.iter().enumerate() {
-        delete_record_in_db(&data_path, &user_id, record_id)
+        delete_record_in_db(&app, record_id)
             .await
             .unwrap_or_else(|e| panic!("Failed to delete record {}: {}", i + 1, e));
 
@@ -1367,7 +1577,7 @@ async fn delete_all_records_sequentially() {
 /// Verifies that malformed UUIDs are handled gracefully.
 #[tokio::test]
 async fn delete_record_malformed_uuid() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     let malformed_ids = vec![
         "invalid-uuid",
@@ -1379,7 +1589,7 @@ async fn delete_record_malformed_uuid() {
     ];
 
     for malformed_id in malformed_ids {
-        let result = delete_record_in_db(&data_path, &user_id, malformed_id).await;
+        let result = delete_record_in_db(&app, malformed_id).await;
         assert!(
             result.is_err(),
             "Malformed UUID '{}' should result in error",
@@ -1393,12 +1603,16 @@ async fn delete_record_malformed_uuid() {
 /// Verifies that empty strings are handled gracefully.
 #[tokio::test]
 async fn delete_record_empty_id() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    // An empty id never reaches the handler: "/records/" has no matching route
+    let result = delete_record_in_db(&app, "").await;
+    assert!(result.is_err(), "Empty ID should result in error");
 
-    let empty_ids = vec!["", "   ", "\t", "\n", "  \t\n  "];
+    let empty_ids = vec!["   ", "\t", "\n", "  \t\n  "];
 
     for empty_id in empty_ids {
-        let result = delete_record_in_db(&data_path, &user_id, empty_id).await;
+        let result = delete_record_in_db(&app, empty_id).await;
         assert!(
             result.is_err(),
             "Empty ID '{}' should result in error",
@@ -1412,7 +1626,7 @@ async fn delete_record_empty_id() {
 /// Verifies that special characters are handled safely.
 #[tokio::test]
 async fn delete_record_special_characters() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     let special_char_ids = vec![
         "'; DROP TABLE records; --",
@@ -1425,7 +1639,7 @@ async fn delete_record_special_characters() {
     ];
 
     for special_id in special_char_ids {
-        let result = delete_record_in_db(&data_path, &user_id, special_id).await;
+        let result = delete_record_in_db(&app, special_id).await;
         assert!(
             result.is_err(),
             "Special character ID '{}' should result in error",
@@ -1439,17 +1653,768 @@ async fn delete_record_special_characters() {
 /// Verifies that extremely long strings are handled gracefully.
 #[tokio::test]
 async fn delete_record_very_long_id() {
-    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let (app, _data_path, _user_id) = setup_test_app().await;
 
     // Create a very long string (1000 characters)
     let very_long_id = "a".repeat(1000);
-    let result = delete_record_in_db(&data_path, &user_id, &very_long_id).await;
+    let result = delete_record_in_db(&app, &very_long_id).await;
     assert!(result.is_err(), "Very long ID should result in error");
     assert_eq!(result.unwrap_err(), "Record not found");
 
     // Test with extremely long string (10000 characters)
     let extremely_long_id = "b".repeat(10000);
-    let result = delete_record_in_db(&data_path, &user_id, &extremely_long_id).await;
+    let result = delete_record_in_db(&app, &extremely_long_id).await;
     assert!(result.is_err(), "Extremely long ID should result in error");
     assert_eq!(result.unwrap_err(), "Record not found");
 }
+
+// Export Tests
+
+async fn insert_bulk_records(data_path: &str, user_id: &str, count: i64, base_time: i64) {
+    use my_budget_server::database::get_user_db;
+
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.write().await;
+    let tx = conn.transaction().await.unwrap();
+    for i in 0..count {
+        tx.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES (?, ?, ?, ?, ?)",
+            (
+                uuid::Uuid::new_v4().to_string(),
+                format!("Bulk {}", i),
+                1.0 + (i % 50) as f64,
+                "bulk",
+                base_time + i,
+            ),
+        )
+        .await
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn export_ndjson_streams_in_bounded_chunks() {
+    use futures_util::StreamExt;
+    use my_budget_server::constants::EXPORT_FLUSH_ROWS;
+    use my_budget_server::database::get_user_db;
+    use my_budget_server::records::stream_records_ndjson;
+
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let total: i64 = 3200;
+    insert_bulk_records(&data_path, &user_id, total, TEST_BASE_TIMESTAMP).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let mut stream = Box::pin(stream_records_ndjson(user_db, 0, i64::MAX));
+
+    let mut line_count = 0;
+    let mut chunk_count = 0;
+    let mut last_timestamp = i64::MIN;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.expect("Export chunk should not be an error");
+        let lines: Vec<&[u8]> = chunk
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        // No chunk ever carries more than one flush worth of rows
+        assert!(lines.len() <= EXPORT_FLUSH_ROWS);
+        chunk_count += 1;
+
+        for line in lines {
+            let record: Record = serde_json::from_slice(line).expect("Each line is a Record");
+            assert!(record.timestamp >= last_timestamp);
+            last_timestamp = record.timestamp;
+            line_count += 1;
+        }
+    }
+
+    assert_eq!(line_count, total);
+    assert!(chunk_count >= total as usize / EXPORT_FLUSH_ROWS);
+}
+
+#[tokio::test]
+async fn export_ndjson_respects_time_range() {
+    use futures_util::StreamExt;
+    use my_budget_server::database::get_user_db;
+    use my_budget_server::records::stream_records_ndjson;
+
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    create_sample_records(&data_path, &user_id).await;
+    let (old_time, middle_time, _new_time, _future_time) = get_test_timestamps();
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let chunks: Vec<_> = stream_records_ndjson(user_db, old_time, middle_time)
+        .collect()
+        .await;
+
+    let body: Vec<u8> = chunks
+        .into_iter()
+        .flat_map(|chunk| chunk.unwrap().to_vec())
+        .collect();
+    let records: Vec<Record> = body
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].name, "Old Record");
+    assert_eq!(records[1].name, "Middle Record");
+}
+
+#[tokio::test]
+async fn export_ndjson_stops_when_client_disconnects() {
+    use futures_util::StreamExt;
+    use my_budget_server::database::get_user_db;
+    use my_budget_server::records::stream_records_ndjson;
+
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    insert_bulk_records(&data_path, &user_id, 2000, TEST_BASE_TIMESTAMP).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let mut stream = Box::pin(stream_records_ndjson(user_db, 0, i64::MAX));
+
+    // Read a single chunk and then drop the stream like a disconnecting client would
+    let first = stream.next().await.expect("At least one chunk").unwrap();
+    assert!(!first.is_empty());
+    drop(stream);
+
+    // The database stays usable after the aborted export
+    let (_, total_count) = get_records_from_db(&data_path, &user_id, None, None, Some(1)).await;
+    assert_eq!(total_count, 2000);
+}
+
+#[tokio::test]
+async fn export_ndjson_releases_lock_while_client_stalls() {
+    use futures_util::StreamExt;
+    use my_budget_server::database::get_user_db;
+    use my_budget_server::records::stream_records_ndjson;
+    use std::time::Duration;
+
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    insert_bulk_records(&data_path, &user_id, 5000, TEST_BASE_TIMESTAMP).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let mut stream = Box::pin(stream_records_ndjson(user_db.clone(), 0, i64::MAX));
+    let first = stream.next().await.expect("At least one chunk").unwrap();
+
+    // The reader fills the channel and waits on the client that stopped reading
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let write = tokio::time::timeout(Duration::from_secs(2), user_db.write()).await;
+    assert!(
+        write.is_ok(),
+        "a stalled export held the user database lock"
+    );
+    drop(write);
+
+    let mut lines = first.iter().filter(|b| **b == b'\n').count();
+    while let Some(chunk) = stream.next().await {
+        lines += chunk.unwrap().iter().filter(|b| **b == b'\n').count();
+    }
+    assert_eq!(lines, 5000);
+}
+
+// Sorting tests
+
+async fn get_sorted_names(app: &TestApp, query: &str) -> (Vec<String>, u32) {
+    let response = app.get(&format!("/records?{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: serde_json::Value = response.json();
+    let names = body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap().to_string())
+        .collect();
+    (names, body["total_count"].as_u64().unwrap() as u32)
+}
+
+#[tokio::test]
+async fn sort_by_timestamp() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let (names, _) = get_sorted_names(&app, "sort_by=timestamp&order=asc").await;
+    assert_eq!(names, vec!["Old Record", "Middle Record", "New Record"]);
+
+    // Default stays newest first
+    let (names, _) = get_sorted_names(&app, "").await;
+    assert_eq!(names, vec!["New Record", "Middle Record", "Old Record"]);
+}
+
+#[tokio::test]
+async fn sort_by_amount_ascending_with_limit() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let (names, total_count) = get_sorted_names(&app, "sort_by=amount&order=asc&limit=2").await;
+
+    // Cheapest first, while the total still counts every record in range
+    assert_eq!(names, vec!["Old Record", "New Record"]);
+    assert_eq!(total_count, 3);
+
+    let (names, _) = get_sorted_names(&app, "sort_by=amount").await;
+    assert_eq!(names, vec!["Middle Record", "New Record", "Old Record"]);
+}
+
+#[tokio::test]
+async fn sort_by_name() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let (names, _) = get_sorted_names(&app, "sort_by=name&order=asc").await;
+    assert_eq!(names, vec!["Middle Record", "New Record", "Old Record"]);
+
+    let (names, _) = get_sorted_names(&app, "sort_by=name&order=desc").await;
+    assert_eq!(names, vec!["Old Record", "New Record", "Middle Record"]);
+}
+
+#[tokio::test]
+async fn sort_invalid_values_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/records?sort_by=category_id").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "sort_by must be 'timestamp', 'amount' or 'name', got 'category_id'"
+    );
+
+    let response = app.get("/records?sort_by=amount&order=sideways").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "order must be 'asc' or 'desc', got 'sideways'"
+    );
+}
+
+// Creation timestamp tests
+
+fn now() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp()
+}
+
+#[tokio::test]
+async fn create_record_with_past_timestamp() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Cash").await;
+    let last_week = now() - 7 * 24 * 60 * 60;
+
+    let response = app
+        .post_json(
+            "/records",
+            &serde_json::json!({
+                "name": "Market stall",
+                "amount": 8.0,
+                "category_id": category_id,
+                "timestamp": last_week,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let record: Record = response.json();
+    assert_eq!(record.timestamp, last_week);
+
+    let (names, total_count) = get_sorted_names(
+        &app,
+        &format!("start_time={}&end_time={}", last_week - 60, last_week + 60),
+    )
+    .await;
+    assert_eq!(names, vec!["Market stall"]);
+    assert_eq!(total_count, 1);
+
+    let (names, _) = get_sorted_names(&app, &format!("start_time={}", last_week + 61)).await;
+    assert!(names.is_empty());
+}
+
+#[tokio::test]
+async fn create_record_without_timestamp_uses_now() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Cash").await;
+
+    let before = now();
+    let response = app
+        .post_json(
+            "/records",
+            &serde_json::json!({
+                "name": "Coffee",
+                "amount": 3.0,
+                "category_id": category_id,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let record: Record = response.json();
+    assert!(record.timestamp >= before && record.timestamp <= now());
+}
+
+#[tokio::test]
+async fn create_record_invalid_timestamp_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Cash").await;
+
+    for timestamp in [-1, now() + 7 * 24 * 60 * 60] {
+        let response = app
+            .post_json(
+                "/records",
+                &serde_json::json!({
+                    "name": "Coffee",
+                    "amount": 3.0,
+                    "category_id": category_id,
+                    "timestamp": timestamp,
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", timestamp);
+    }
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert!(records.is_empty());
+}
+
+#[tokio::test]
+async fn create_record_amount_out_of_range_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Cash").await;
+
+    let response = app
+        .post_json(
+            "/records",
+            &serde_json::json!({
+                "name": "Yacht",
+                "amount": MAX_RECORD_AMOUNT * 2.0,
+                "category_id": category_id,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("exceed"));
+
+    // JSON has no infinity; an overflowing literal is the closest a client can send
+    let body = format!(
+        r#"{{"name": "Overflow", "amount": 1e999, "category_id": "{}"}}"#,
+        category_id
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/records")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.request(request).await;
+    assert!(response.status.is_client_error(), "{}", response.status);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert!(records.is_empty());
+}
+
+#[tokio::test]
+async fn update_record_amount_above_cap_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Capped",
+        10.0,
+        "test_category",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let result = update_record_in_db(
+        &app,
+        &record_id,
+        None,
+        Some(-MAX_RECORD_AMOUNT - 1.0),
+        None,
+        None,
+    )
+    .await;
+    let error = result.expect_err("amount above the cap should be rejected");
+    assert!(error.contains("exceed"), "{}", error);
+
+    let db_record = get_single_record_from_db(&data_path, &user_id, &record_id)
+        .await
+        .expect("Failed to retrieve record from database");
+    assert_eq!(db_record.amount, 10.0);
+}
+
+#[tokio::test]
+async fn record_amount_precision_and_string_input() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Cash").await;
+
+    let response = app
+        .post_json(
+            "/records",
+            &serde_json::json!({
+                "name": "Too precise",
+                "amount": 12.345678,
+                "category_id": category_id,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("decimal places"));
+
+    let response = app
+        .post_json(
+            "/records",
+            &serde_json::json!({
+                "name": "Lunch",
+                "amount": "12.34",
+                "category_id": category_id,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let record: Record = response.json();
+    assert_eq!(record.amount, 12.34);
+    let db_record = get_single_record_from_db(&data_path, &user_id, &record.id)
+        .await
+        .expect("Failed to retrieve record from database");
+    assert_eq!(db_record.amount, 12.34);
+
+    let path = format!("/records/{}", record.id);
+    let response = app
+        .put_json(&path, &serde_json::json!({ "amount": "-0.5" }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(response.json::<Record>().amount, -0.5);
+
+    for amount in ["12.345", "1e3", " 12", "abc"] {
+        let response = app
+            .put_json(&path, &serde_json::json!({ "amount": amount }))
+            .await;
+        assert!(response.status.is_client_error(), "{}", amount);
+    }
+    let db_record = get_single_record_from_db(&data_path, &user_id, &record.id)
+        .await
+        .expect("Failed to retrieve record from database");
+    assert_eq!(db_record.amount, -0.5);
+}
+
+// Optional total tests
+
+#[tokio::test]
+async fn include_total_false_omits_count() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let response = app.get("/records?include_total=false&limit=2").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"].as_array().unwrap().len(), 2);
+    assert!(body["total_count"].is_null());
+
+    // Counting stays the default
+    let response = app.get("/records?limit=2").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 3);
+}
+
+#[tokio::test]
+async fn count_only_skips_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+    let (_, middle_time, _, _) = get_test_timestamps();
+
+    let response = app
+        .get(&format!(
+            "/records?count_only=true&start_time={}",
+            middle_time
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"], serde_json::json!([]));
+    assert_eq!(body["total_count"], 2);
+
+    let response = app
+        .get("/records?count_only=true&include_total=false")
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+// Pagination metadata tests
+
+#[tokio::test]
+async fn limited_query_reports_pagination_metadata() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let response = app.get("/records?limit=2").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"].as_array().unwrap().len(), 2);
+    assert_eq!(body["total_count"], 3);
+    assert_eq!(body["limit"], 2);
+    assert_eq!(body["has_more"], true);
+
+    // A page holding exactly the remaining records is the last one
+    let response = app.get("/records?limit=3").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"].as_array().unwrap().len(), 3);
+    assert_eq!(body["has_more"], false);
+
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["limit"], DEFAULT_RECORDS_LIMIT);
+    assert_eq!(body["has_more"], false);
+
+    // Uncounted queries and custom orders still know whether more follow
+    let response = app
+        .get("/records?limit=1&include_total=false&sort_by=amount")
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"].as_array().unwrap().len(), 1);
+    assert_eq!(body["has_more"], true);
+}
+
+#[tokio::test]
+async fn cursor_pages_report_has_more() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let response = app.get("/records?limit=2").await;
+    let body: serde_json::Value = response.json();
+    let cursor = body["next_cursor"].as_str().unwrap();
+
+    let response = app
+        .get(&format!("/records?limit=2&cursor={}", cursor))
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"].as_array().unwrap().len(), 1);
+    assert_eq!(body["limit"], 2);
+    assert_eq!(body["has_more"], false);
+}
+
+// Cursor pagination tests
+
+async fn get_page(app: &TestApp, query: &str) -> (Vec<String>, Option<String>) {
+    let response = app.get(&format!("/records?{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: serde_json::Value = response.json();
+    let ids = body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_str().unwrap().to_string())
+        .collect();
+    (ids, body["next_cursor"].as_str().map(str::to_string))
+}
+
+#[tokio::test]
+async fn cursor_pages_yield_every_record_once() {
+    let (app, data_path, user_id) = setup_test_app().await;
+
+    // Pairs of records share a timestamp, so pages split inside equal timestamps
+    let mut expected = Vec::new();
+    for i in 0..9 {
+        let id = create_test_record(
+            &data_path,
+            &user_id,
+            &format!("Record {}", i),
+            1.0 + i as f64,
+            "food",
+            TEST_BASE_TIMESTAMP + (i / 2) * TEST_TIME_INCREMENT,
+        )
+        .await;
+        expected.push(id);
+    }
+
+    let mut seen = Vec::new();
+    let (ids, mut cursor) = get_page(&app, "limit=3").await;
+    seen.extend(ids);
+
+    // A record arriving mid-way lands before the cursor and does not shift later pages
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Late Record",
+        99.0,
+        "food",
+        TEST_BASE_TIMESTAMP + 10 * TEST_TIME_INCREMENT,
+    )
+    .await;
+
+    for _ in 0..2 {
+        let query = format!("limit=3&cursor={}", cursor.expect("more records follow"));
+        let (ids, next) = get_page(&app, &query).await;
+        assert_eq!(ids.len(), 3);
+        seen.extend(ids);
+        cursor = next;
+    }
+
+    // The last page carries no cursor, so there is no trailing empty page
+    assert!(cursor.is_none());
+
+    let mut sorted_seen = seen.clone();
+    sorted_seen.sort();
+    sorted_seen.dedup();
+    assert_eq!(sorted_seen.len(), seen.len(), "a record was returned twice");
+    expected.sort();
+    assert_eq!(sorted_seen, expected);
+}
+
+#[tokio::test]
+async fn cursor_is_null_on_short_page() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let (ids, cursor) = get_page(&app, "limit=5").await;
+    assert_eq!(ids.len(), 3);
+    assert!(cursor.is_none());
+
+    // Only the default order can be walked with a cursor
+    let (_, cursor) = get_page(&app, "limit=2&sort_by=amount").await;
+    assert!(cursor.is_none());
+}
+
+#[tokio::test]
+async fn tampered_cursor_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    // "not json" and {"timestamp":"x"} in URL-safe base64, plus invalid base64
+    for cursor in ["bm90IGpzb24", "eyJ0aW1lc3RhbXAiOiJ4In0", "!!!"] {
+        let response = app.get(&format!("/records?cursor={}", cursor)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", cursor);
+        assert_eq!(response.text(), "Invalid cursor");
+    }
+
+    let (_, cursor) = get_page(&app, "limit=1").await;
+    let response = app
+        .get(&format!(
+            "/records?sort_by=amount&cursor={}",
+            cursor.unwrap()
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+// Amount range tests
+
+async fn create_amount_range_records(data_path: &str, user_id: &str) {
+    let (old_time, middle_time, new_time, _) = get_test_timestamps();
+    create_sample_records(data_path, user_id).await;
+    create_test_record(data_path, user_id, "Refund", -20.0, "food", middle_time).await;
+    create_test_record(data_path, user_id, "Laptop", 1200.0, "tech", new_time).await;
+    create_test_record(data_path, user_id, "Desk", 150.0, "home", old_time).await;
+}
+
+#[tokio::test]
+async fn amount_range_min_only() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_amount_range_records(&data_path, &user_id).await;
+
+    let (names, total_count) = get_sorted_names(&app, "min_amount=100&sort_by=amount").await;
+    assert_eq!(names, vec!["Laptop", "Desk"]);
+    assert_eq!(total_count, 2);
+}
+
+#[tokio::test]
+async fn amount_range_max_only() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_amount_range_records(&data_path, &user_id).await;
+
+    let (names, total_count) = get_sorted_names(&app, "max_amount=15.25&sort_by=amount").await;
+    assert_eq!(names, vec!["New Record", "Old Record", "Refund"]);
+    assert_eq!(total_count, 3);
+
+    // Negative bounds select refunds
+    let (names, _) = get_sorted_names(&app, "max_amount=-0.01").await;
+    assert_eq!(names, vec!["Refund"]);
+}
+
+#[tokio::test]
+async fn amount_range_both_with_time_range_and_limit() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_amount_range_records(&data_path, &user_id).await;
+    let (_, middle_time, new_time, _) = get_test_timestamps();
+
+    let (names, total_count) = get_sorted_names(
+        &app,
+        &format!(
+            "min_amount=-50&max_amount=1500&start_time={}&end_time={}&limit=2",
+            middle_time, new_time
+        ),
+    )
+    .await;
+    assert_eq!(names.len(), 2);
+    assert_eq!(total_count, 4);
+
+    let (names, _) = get_sorted_names(&app, "min_amount=25.75&max_amount=25.75").await;
+    assert_eq!(names, vec!["Middle Record"]);
+}
+
+#[tokio::test]
+async fn amount_range_inverted_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/records?min_amount=100&max_amount=10").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "min_amount cannot be greater than max_amount"
+    );
+
+    let response = app.get("/records?min_amount=NaN").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+// Multi-category filter tests
+
+#[tokio::test]
+async fn category_ids_single() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let (names, total_count) = get_sorted_names(&app, "category_ids=transport").await;
+    assert_eq!(names, vec!["Middle Record"]);
+    assert_eq!(total_count, 1);
+}
+
+#[tokio::test]
+async fn category_ids_several() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_sample_records(&data_path, &user_id).await;
+
+    let (names, total_count) =
+        get_sorted_names(&app, "category_ids=food,%20entertainment,missing,food").await;
+    assert_eq!(names, vec!["New Record", "Old Record"]);
+    assert_eq!(total_count, 2);
+
+    // Composes with other filters and the limit
+    let (names, total_count) =
+        get_sorted_names(&app, "category_ids=food,transport&min_amount=20&limit=1").await;
+    assert_eq!(names, vec!["Middle Record"]);
+    assert_eq!(total_count, 1);
+}
+
+#[tokio::test]
+async fn category_ids_too_many_or_empty_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let ids: Vec<String> = (0..21).map(|i| format!("cat{}", i)).collect();
+    let response = app
+        .get(&format!("/records?category_ids={}", ids.join(",")))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "category_ids cannot contain more than 20 ids"
+    );
+
+    // Twenty is still fine
+    let response = app
+        .get(&format!("/records?category_ids={}", ids[..20].join(",")))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    for query in ["category_ids=", "category_ids=,%20,"] {
+        let response = app.get(&format!("/records?{}", query)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
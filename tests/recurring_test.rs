@@ -0,0 +1,390 @@
+/*!
+ * Recurring Rule Tests
+ *
+ * Covers CRUD, pause and resume for recurring rules under /recurring, and drives
+ * the scheduler against a fixed clock: due occurrences become records, repeated
+ * passes never post an occurrence twice, month-end rules keep their day,
+ * occurrences in a closed period or an archived category are skipped, and a
+ * failing user does not stop the pass for the others.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::{get_user_db, user_db_path};
+use my_budget_server::models::{RecordKind, RecurringInterval, RecurringRule};
+use my_budget_server::recurring::{
+    materialize_due_rules, next_occurrence, run_recurring_rules, set_recurring_rule_active,
+};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+// 2024-01-31 09:00:00 UTC
+const JAN_31_2024: i64 = 1706691600;
+// 2024-02-29 09:00:00 UTC
+const FEB_29_2024: i64 = 1709197200;
+// 2024-03-01 00:00:00 UTC
+const MAR_1_2024: i64 = 1709251200;
+// 2024-03-31 09:00:00 UTC
+const MAR_31_2024: i64 = 1711875600;
+// 2024-04-30 09:00:00 UTC
+const APR_30_2024: i64 = 1714467600;
+// 2024-04-15 00:00:00 UTC
+const APR_15_2024: i64 = 1713139200;
+
+const DAY: i64 = 24 * 60 * 60;
+
+async fn create_rule_via_api(
+    app: &TestApp,
+    category_id: &str,
+    interval: &str,
+    start_time: i64,
+) -> RecurringRule {
+    let response = app
+        .post_json(
+            "/recurring",
+            &json!({
+                "name": "Rent",
+                "amount": 1200.0,
+                "category_id": category_id,
+                "interval": interval,
+                "start_time": start_time,
+            }),
+        )
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::CREATED,
+        "rule creation failed: {}",
+        response.text()
+    );
+    response.json()
+}
+
+#[tokio::test]
+async fn test_create_and_list_recurring_rules() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+
+    let rule = create_rule_via_api(&app, &category_id, "monthly", JAN_31_2024).await;
+    assert_eq!(rule.name, "Rent");
+    assert_eq!(rule.interval, RecurringInterval::Monthly);
+    assert_eq!(rule.next_run, JAN_31_2024);
+    assert_eq!(rule.currency, "USD");
+    assert_eq!(rule.kind, RecordKind::Expense);
+    assert!(rule.active);
+
+    let response = app.get("/recurring").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let rules: Vec<RecurringRule> = response.json();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].id, rule.id);
+
+    let response = app.get(&format!("/recurring/{}", rule.id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.get("/recurring/missing").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_create_recurring_rule_validation() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+
+    let valid = json!({
+        "name": "Rent",
+        "amount": 1200.0,
+        "category_id": category_id,
+        "interval": "monthly",
+        "start_time": JAN_31_2024,
+    });
+
+    let cases = [
+        ("interval", json!("fortnightly")),
+        ("amount", json!(0.0)),
+        ("category_id", json!("missing")),
+        ("currency", json!("usd")),
+        ("kind", json!("transfer")),
+        ("start_time", json!(0)),
+    ];
+    for (field, value) in cases {
+        let mut payload = valid.clone();
+        payload[field] = value;
+        let response = app.post_json("/recurring", &payload).await;
+        assert_eq!(
+            response.status,
+            StatusCode::BAD_REQUEST,
+            "invalid {} was accepted",
+            field
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_update_and_delete_recurring_rule() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Subscriptions").await;
+    let rule = create_rule_via_api(&app, &category_id, "monthly", JAN_31_2024).await;
+
+    let response = app
+        .put_json(
+            &format!("/recurring/{}", rule.id),
+            &json!({ "amount": 1250.0, "interval": "weekly" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let updated: RecurringRule = response.json();
+    assert_eq!(updated.amount, 1250.0);
+    assert_eq!(updated.interval, RecurringInterval::Weekly);
+    assert_eq!(updated.name, "Rent");
+    assert_eq!(updated.next_run, JAN_31_2024);
+
+    let response = app
+        .put_json(&format!("/recurring/{}", rule.id), &json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // Posted records outlive the rule
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    assert_eq!(
+        materialize_due_rules(&user_db, JAN_31_2024).await.unwrap(),
+        1
+    );
+
+    let response = app.delete(&format!("/recurring/{}", rule.id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    let response = app.delete(&format!("/recurring/{}", rule.id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 1);
+}
+
+#[tokio::test]
+async fn test_materialize_posts_due_occurrences() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+    let rule = create_rule_via_api(&app, &category_id, "monthly", JAN_31_2024).await;
+
+    // Nothing is due before the first occurrence
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    assert_eq!(
+        materialize_due_rules(&user_db, JAN_31_2024 - 1)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // Catches up on every missed month, keeping the 31st where the month allows it
+    assert_eq!(
+        materialize_due_rules(&user_db, APR_15_2024).await.unwrap(),
+        3
+    );
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let mut timestamps: Vec<i64> = records.into_iter().map(|record| record.timestamp).collect();
+    timestamps.sort();
+    assert_eq!(timestamps, vec![JAN_31_2024, FEB_29_2024, MAR_31_2024]);
+
+    let response = app.get(&format!("/recurring/{}", rule.id)).await;
+    let rule: RecurringRule = response.json();
+    assert_eq!(rule.next_run, APR_30_2024);
+
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"][0]["name"], "Rent");
+    assert_eq!(body["records"][0]["category_id"], category_id.as_str());
+}
+
+#[tokio::test]
+async fn test_repeated_passes_do_not_double_post() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+    create_rule_via_api(&app, &category_id, "weekly", JAN_31_2024).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    assert_eq!(
+        materialize_due_rules(&user_db, JAN_31_2024 + DAY)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        materialize_due_rules(&user_db, JAN_31_2024 + DAY)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // A fresh connection, as after a restart, sees the advanced schedule
+    let reopened = get_user_db(&data_path, &user_id).await.unwrap();
+    assert_eq!(
+        materialize_due_rules(&reopened, JAN_31_2024 + DAY)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // Full scheduler pass over every user
+    let posted = run_recurring_rules(app.main_db(), &data_path, JAN_31_2024 + 7 * DAY)
+        .await
+        .unwrap();
+    assert_eq!(posted, 1);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(records.len(), 2);
+}
+
+#[tokio::test]
+async fn test_paused_rule_is_skipped_and_resume_skips_missed() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+    let rule = create_rule_via_api(&app, &category_id, "monthly", JAN_31_2024).await;
+
+    let response = app
+        .post_json(&format!("/recurring/{}/pause", rule.id), &json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let paused: RecurringRule = response.json();
+    assert!(!paused.active);
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    assert_eq!(
+        materialize_due_rules(&user_db, APR_15_2024).await.unwrap(),
+        0
+    );
+
+    // Resuming mid-April does not backfill January through March
+    let resumed = set_recurring_rule_active(&user_db, &rule.id, true, APR_15_2024)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(resumed.active);
+    assert_eq!(resumed.next_run, APR_30_2024);
+
+    assert_eq!(
+        materialize_due_rules(&user_db, APR_15_2024).await.unwrap(),
+        0
+    );
+    assert_eq!(
+        materialize_due_rules(&user_db, APR_30_2024).await.unwrap(),
+        1
+    );
+
+    let response = app.post_json("/recurring/missing/resume", &json!({})).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_occurrences_in_closed_period_or_archived_category_skipped() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+    let rule = create_rule_via_api(&app, &category_id, "monthly", JAN_31_2024).await;
+
+    // January and February are closed; only March is posted
+    let response = app
+        .post_json("/records/close", &json!({ "before": MAR_1_2024 }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    assert_eq!(
+        materialize_due_rules(&user_db, APR_15_2024).await.unwrap(),
+        1
+    );
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    let timestamps: Vec<i64> = records.into_iter().map(|record| record.timestamp).collect();
+    assert_eq!(timestamps, vec![MAR_31_2024]);
+
+    // An archived category takes no new records; the rule still moves on
+    let response = app
+        .put_json(
+            &format!("/categories/{}", category_id),
+            &json!({ "archived": true }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(
+        materialize_due_rules(&user_db, APR_30_2024).await.unwrap(),
+        0
+    );
+    let rule: RecurringRule = app.get(&format!("/recurring/{}", rule.id)).await.json();
+    assert!(rule.next_run > APR_30_2024);
+    let (_, total) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert_eq!(total, 1);
+}
+
+#[tokio::test]
+async fn test_scheduler_pass_continues_past_failing_user() {
+    let app = TestApp::new().await;
+    // Listed before the real user, with a directory where its database should be
+    app.main_db()
+        .write()
+        .await
+        .execute(
+            "INSERT INTO users (id, name, password_hash) VALUES ('broken', 'broken', 'x')",
+            (),
+        )
+        .await
+        .unwrap();
+    std::fs::create_dir(user_db_path(app.data_path(), "broken")).unwrap();
+    app.register_and_login().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+    create_rule_via_api(&app, &category_id, "monthly", JAN_31_2024).await;
+
+    let posted = run_recurring_rules(app.main_db(), app.data_path(), APR_15_2024)
+        .await
+        .unwrap();
+    assert_eq!(posted, 3);
+}
+
+#[tokio::test]
+async fn test_category_with_recurring_rule_cannot_be_deleted() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Housing").await;
+    let rule = create_rule_via_api(&app, &category_id, "monthly", JAN_31_2024).await;
+
+    let response = app.delete(&format!("/categories/{}", category_id)).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    app.delete(&format!("/recurring/{}", rule.id)).await;
+    let response = app.delete(&format!("/categories/{}", category_id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+}
+
+#[test]
+fn test_next_occurrence_intervals() {
+    assert_eq!(
+        next_occurrence(JAN_31_2024, JAN_31_2024, RecurringInterval::Daily),
+        Some(JAN_31_2024 + DAY)
+    );
+    assert_eq!(
+        next_occurrence(JAN_31_2024, JAN_31_2024, RecurringInterval::Weekly),
+        Some(JAN_31_2024 + 7 * DAY)
+    );
+
+    // Clamped to the end of February, then back to the 31st
+    assert_eq!(
+        next_occurrence(JAN_31_2024, JAN_31_2024, RecurringInterval::Monthly),
+        Some(FEB_29_2024)
+    );
+    assert_eq!(
+        next_occurrence(JAN_31_2024, FEB_29_2024, RecurringInterval::Monthly),
+        Some(MAR_31_2024)
+    );
+
+    // A leap day falls back to Feb 28 in common years
+    let feb_28_2025 = FEB_29_2024 + 365 * DAY;
+    assert_eq!(
+        next_occurrence(FEB_29_2024, FEB_29_2024, RecurringInterval::Yearly),
+        Some(feb_28_2025)
+    );
+
+    // December rolls over into the next year
+    let dec_31_2024 = JAN_31_2024 + 335 * DAY;
+    assert_eq!(
+        next_occurrence(JAN_31_2024, dec_31_2024, RecurringInterval::Monthly),
+        Some(dec_31_2024 + 31 * DAY)
+    );
+}
@@ -0,0 +1,156 @@
+/*!
+ * Remember-Me Tests
+ *
+ * Covers the `remember_me` login option: the cookie lifetime each choice gets,
+ * check_session_activity ending short and remembered sessions after their own
+ * idle time on a mocked clock, and requests from a session backdated past its
+ * lifetime being logged out.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::constants::{SESSION_EXPIRY_DAYS, SHORT_SESSION_EXPIRY_HOURS};
+use my_budget_server::models::SessionInfo;
+use my_budget_server::test_support::{TEST_PASSWORD, TEST_USERNAME, TestApp, TestResponse};
+use my_budget_server::user_sessions::{SessionActivity, check_session_activity};
+use serde_json::{Value, json};
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+async fn login(app: &TestApp, remember_me: Option<bool>) -> TestResponse {
+    app.set_cookie(None);
+    let mut body = json!({ "username": TEST_USERNAME, "password": TEST_PASSWORD });
+    if let Some(remember_me) = remember_me {
+        body["remember_me"] = Value::Bool(remember_me);
+    }
+    let response = app.post_json("/auth/login", &body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response
+}
+
+async fn current_session(app: &TestApp) -> SessionInfo {
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    sessions.into_iter().find(|s| s.current).unwrap()
+}
+
+/// Moves the clock forward for the session by pushing its last activity back.
+async fn backdate(app: &TestApp, session_id: &str, secs: i64) {
+    app.main_db()
+        .write()
+        .await
+        .execute(
+            "UPDATE user_sessions SET last_seen_at = last_seen_at - ? WHERE id = ?",
+            (secs, session_id),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_cookie_lifetime_follows_remember_me() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+
+    let response = login(&app, Some(true)).await;
+    let cookie = response.header("set-cookie").unwrap();
+    assert!(
+        cookie.contains(&format!("Max-Age={}", SESSION_EXPIRY_DAYS * DAY)),
+        "{}",
+        cookie
+    );
+    assert!(current_session(&app).await.remember_me);
+
+    for remember_me in [Some(false), None] {
+        let response = login(&app, remember_me).await;
+        let cookie = response.header("set-cookie").unwrap();
+        assert!(
+            cookie.contains(&format!("Max-Age={}", SHORT_SESSION_EXPIRY_HOURS * HOUR)),
+            "{}",
+            cookie
+        );
+        assert!(!current_session(&app).await.remember_me);
+    }
+}
+
+#[tokio::test]
+async fn test_session_activity_on_mocked_clock() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    let db = app.main_db();
+
+    login(&app, Some(false)).await;
+    let short = current_session(&app).await;
+    let start = short.last_seen_at;
+
+    let seen = start + SHORT_SESSION_EXPIRY_HOURS * HOUR - 1;
+    assert_eq!(
+        check_session_activity(db, &short.id, seen).await.unwrap(),
+        SessionActivity::Active {
+            remember_me: false,
+            refreshed: true,
+            username: TEST_USERNAME.to_string(),
+        }
+    );
+    // Idle time counts from the last request, not the login
+    assert_eq!(
+        check_session_activity(db, &short.id, seen + 30)
+            .await
+            .unwrap(),
+        SessionActivity::Active {
+            remember_me: false,
+            refreshed: false,
+            username: TEST_USERNAME.to_string(),
+        }
+    );
+    let later = seen + SHORT_SESSION_EXPIRY_HOURS * HOUR;
+    assert_eq!(
+        check_session_activity(db, &short.id, later).await.unwrap(),
+        SessionActivity::Ended
+    );
+    assert_eq!(
+        check_session_activity(db, &short.id, start).await.unwrap(),
+        SessionActivity::Ended
+    );
+
+    login(&app, Some(true)).await;
+    let long = current_session(&app).await;
+    let start = long.last_seen_at;
+    assert!(matches!(
+        check_session_activity(db, &long.id, start + 20 * DAY)
+            .await
+            .unwrap(),
+        SessionActivity::Active {
+            remember_me: true,
+            ..
+        }
+    ));
+    assert_eq!(
+        check_session_activity(db, &long.id, start + 20 * DAY + SESSION_EXPIRY_DAYS * DAY)
+            .await
+            .unwrap(),
+        SessionActivity::Ended
+    );
+}
+
+#[tokio::test]
+async fn test_idle_short_session_logged_out() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+
+    login(&app, Some(true)).await;
+    let remembered = app.cookie();
+    let long = current_session(&app).await;
+    backdate(&app, &long.id, SHORT_SESSION_EXPIRY_HOURS * HOUR + HOUR).await;
+
+    login(&app, None).await;
+    let short = current_session(&app).await;
+    backdate(&app, &short.id, SHORT_SESSION_EXPIRY_HOURS * HOUR + HOUR).await;
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/records").await.status, StatusCode::UNAUTHORIZED);
+
+    app.set_cookie(remembered);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::OK);
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    assert!(sessions.iter().any(|s| s.id == long.id));
+    assert!(!sessions.iter().any(|s| s.id == short.id));
+}
@@ -0,0 +1,162 @@
+/*!
+ * Record Retention Tests
+ *
+ * Drives the retention purge of the maintenance task with a fake "now": records
+ * dated before the retention horizon are deleted with their tags and history,
+ * archived years included, newer ones stay, and records created at or after
+ * the run's snapshot are never touched.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::maintenance::run_retention_purge;
+use my_budget_server::models::Record;
+use my_budget_server::records::purge_expired_records;
+use serde_json::json;
+
+const ONE_DAY: i64 = 24 * 60 * 60;
+const NOW: i64 = 1700000000;
+
+async fn count(data_path: &str, user_id: &str, table: &str) -> u32 {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(&format!("SELECT COUNT(*) FROM {}", table), ())
+        .await
+        .unwrap();
+    rows.next().await.unwrap().unwrap().get(0).unwrap()
+}
+
+#[tokio::test]
+async fn retention_purge_drops_records_past_the_horizon() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let ancient = create_test_record(
+        &data_path,
+        &user_id,
+        "Ancient",
+        1.0,
+        "c",
+        NOW - 400 * ONE_DAY,
+    )
+    .await;
+    create_test_record(&data_path, &user_id, "Recent", 2.0, "c", NOW - 10 * ONE_DAY).await;
+    {
+        let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+        let conn = user_db.write().await;
+        conn.execute(
+            "INSERT INTO record_tags (record_id, tag) VALUES (?, 'old')",
+            [ancient.as_str()],
+        )
+        .await
+        .unwrap();
+    }
+
+    let purged = run_retention_purge(app.main_db(), &data_path, 365, NOW)
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    let names: Vec<String> =
+        get_records_from_db(&data_path, &user_id, Some(i64::MIN), Some(i64::MAX), None)
+            .await
+            .0
+            .into_iter()
+            .map(|record| record.name)
+            .collect();
+    assert_eq!(names, vec!["Recent"]);
+    assert_eq!(count(&data_path, &user_id, "record_tags").await, 0);
+
+    // A second run has nothing left to do
+    let purged = run_retention_purge(app.main_db(), &data_path, 365, NOW)
+        .await
+        .unwrap();
+    assert_eq!(purged, 0);
+}
+
+#[tokio::test]
+async fn retention_purge_reaches_archived_years_and_history() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+    let response = app
+        .post_json(
+            "/records",
+            &json!({
+                "name": "Ancient",
+                "amount": 1.0,
+                "category_id": category_id,
+                "timestamp": NOW - 400 * ONE_DAY,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let ancient: Record = response.json();
+    let response = app
+        .put_json(
+            &format!("/records/{}", ancient.id),
+            &json!({ "name": "Very ancient" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    assert_eq!(count(&data_path, &user_id, "record_history").await, 1);
+
+    let response = app
+        .post_json(
+            "/records/archive",
+            &json!({ "before": NOW - 365 * ONE_DAY }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    // The purge snapshot lies after the record was entered
+    let later = time::OffsetDateTime::now_utc().unix_timestamp() + 1;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let purged = purge_expired_records(&user_db, NOW - 365 * ONE_DAY, later)
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    let (_, total) =
+        get_records_from_db(&data_path, &user_id, Some(i64::MIN), Some(i64::MAX), None).await;
+    assert_eq!(total, 0);
+    assert_eq!(count(&data_path, &user_id, "record_history").await, 0);
+}
+
+#[tokio::test]
+async fn records_created_during_the_run_are_kept() {
+    let (_app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Ancient",
+        1.0,
+        "c",
+        NOW - 400 * ONE_DAY,
+    )
+    .await;
+    {
+        // Backdated, but entered after the run's snapshot was taken
+        let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+        let conn = user_db.write().await;
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp, created_at) VALUES ('late', 'Late entry', 5.0, 'c', ?, ?)",
+            (NOW - 400 * ONE_DAY, NOW),
+        )
+        .await
+        .unwrap();
+    }
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let purged = purge_expired_records(&user_db, NOW - 365 * ONE_DAY, NOW)
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    let names: Vec<String> =
+        get_records_from_db(&data_path, &user_id, Some(i64::MIN), Some(i64::MAX), None)
+            .await
+            .0
+            .into_iter()
+            .map(|record| record.name)
+            .collect();
+    assert_eq!(names, vec!["Late entry"]);
+}
@@ -0,0 +1,203 @@
+/*!
+ * Record Search Tests
+ *
+ * Covers the `q` parameter of GET /records: full-text matching on record names
+ * combined with the time range and limit, keeping the index in sync with
+ * creates, renames and deletes, backfilling records written before the index
+ * existed, and the LIKE fallback when there is no index.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::{get_user_db, table_exists};
+use my_budget_server::models::GetRecordsQuery;
+use my_budget_server::records::{fts_match_query, list_records};
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+use tempfile::tempdir;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+async fn search(app: &TestApp, query: &str) -> (Vec<String>, u32) {
+    let response = app.get(&format!("/records?{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: Value = response.json();
+    let names = body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap().to_string())
+        .collect();
+    (names, body["total_count"].as_u64().unwrap() as u32)
+}
+
+#[tokio::test]
+async fn search_respects_time_range_and_limit() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let fixtures = [
+        ("Coffee at the station", 0),
+        ("Groceries", 60),
+        ("Iced coffee", 120),
+        ("Coffee beans", 180),
+    ];
+    for (name, offset) in fixtures {
+        create_test_record(
+            &data_path,
+            &user_id,
+            name,
+            5.0,
+            "food",
+            TEST_BASE_TIMESTAMP + offset,
+        )
+        .await;
+    }
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    assert!(
+        table_exists(&*user_db.read().await, "records_fts")
+            .await
+            .unwrap()
+    );
+
+    // Case-insensitive, and words match as prefixes
+    let (names, total) = search(&app, "q=COFF").await;
+    assert_eq!(
+        names,
+        vec!["Coffee beans", "Iced coffee", "Coffee at the station"]
+    );
+    assert_eq!(total, 3);
+
+    let (names, total) = search(&app, "q=coffee&limit=1").await;
+    assert_eq!(names, vec!["Coffee beans"]);
+    assert_eq!(total, 3);
+
+    let (names, _) = search(
+        &app,
+        &format!(
+            "q=coffee&start_time={}&end_time={}",
+            TEST_BASE_TIMESTAMP + 60,
+            TEST_BASE_TIMESTAMP + 150
+        ),
+    )
+    .await;
+    assert_eq!(names, vec!["Iced coffee"]);
+
+    // Every word has to match
+    let (names, _) = search(&app, "q=coffee%20st").await;
+    assert_eq!(names, vec!["Coffee at the station"]);
+
+    let response = app.get("/records?q=%20").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn renaming_a_record_changes_its_searchability() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Pizza night",
+        25.0,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    assert_eq!(search(&app, "q=pizza").await.1, 1);
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", record_id),
+            &json!({ "name": "Sushi night" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    assert_eq!(search(&app, "q=pizza").await.1, 0);
+    assert_eq!(search(&app, "q=sushi").await.0, vec!["Sushi night"]);
+    assert_eq!(search(&app, "q=night").await.1, 1);
+
+    let response = app.delete(&format!("/records/{}", record_id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(search(&app, "q=sushi").await.1, 0);
+}
+
+#[tokio::test]
+async fn search_input_is_matched_literally() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Rock \"n\" roll",
+        10.0,
+        "fun",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    // FTS5 syntax in the input must not break the query
+    for q in ["%22rock", "rock%20OR", "NOT%20rock", "roll*", "(rock"] {
+        let response = app.get(&format!("/records?q={}", q)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", q);
+    }
+    assert_eq!(search(&app, "q=%22rock").await.1, 1);
+
+    assert_eq!(fts_match_query("  iced  coffee "), "\"iced\"* \"coffee\"*");
+    assert_eq!(fts_match_query("say \"hi\""), "\"say\"* \"\"\"hi\"\"\"*");
+}
+
+#[tokio::test]
+async fn existing_records_are_indexed_and_like_is_the_fallback() {
+    let temp_dir = tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let user_id = uuid::Uuid::new_v4().to_string();
+
+    // A user database written before the search index existed
+    {
+        let path = temp_dir.path().join(format!("user_{}.db", user_id));
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "CREATE TABLE records (id TEXT PRIMARY KEY, name TEXT NOT NULL, amount REAL NOT NULL, category_id TEXT NOT NULL, timestamp INTEGER NOT NULL)",
+            (),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES ('old', 'Legacy rent', 900.0, 'c', 1690000000)",
+            (),
+        )
+        .await
+        .unwrap();
+    }
+
+    let user_db = get_user_db(data_path, &user_id).await.unwrap();
+    let query = GetRecordsQuery {
+        q: Some("rent".to_string()),
+        ..Default::default()
+    };
+    let response = list_records(&user_db, &query).await.unwrap();
+    assert_eq!(response.total_count, Some(1));
+
+    // Without the index, search is a substring match
+    {
+        let conn = user_db.write().await;
+        for trigger in [
+            "records_fts_insert",
+            "records_fts_update",
+            "records_fts_delete",
+        ] {
+            conn.execute(&format!("DROP TRIGGER {}", trigger), ())
+                .await
+                .unwrap();
+        }
+        conn.execute("DROP TABLE records_fts", ()).await.unwrap();
+    }
+    let query = GetRecordsQuery {
+        q: Some("ACY RE".to_string()),
+        ..Default::default()
+    };
+    let response = list_records(&user_db, &query).await.unwrap();
+    assert_eq!(response.records[0].id, "old");
+}
@@ -0,0 +1,61 @@
+/*!
+ * Session Fixation Tests
+ *
+ * Checks that login and logout move the session to a new id: the cookie from an
+ * anonymous visit to `/` is replaced on login and no longer reaches the logged-in
+ * session, and the logged-in cookie stops working once logged out.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::test_support::{TEST_PASSWORD, TEST_USERNAME, TestApp};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_login_issues_new_session_id() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    app.set_cookie(None);
+
+    let response = app.get("/").await;
+    assert!(response.text().contains("Visit count: 1"));
+    let anonymous = app.cookie().expect("no cookie from /");
+
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": TEST_USERNAME, "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let set_cookie = response
+        .header("set-cookie")
+        .expect("no new cookie on login");
+    let authenticated = app.cookie().unwrap();
+    assert!(set_cookie.starts_with(&authenticated));
+    assert_ne!(authenticated, anonymous);
+
+    // The logged-in session keeps what the anonymous one held...
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::OK);
+    assert!(app.get("/").await.text().contains("Visit count: 2"));
+
+    // ...while the old id names nothing any more
+    app.set_cookie(Some(anonymous));
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+    assert!(app.get("/").await.text().contains("Visit count: 1"));
+}
+
+#[tokio::test]
+async fn test_logout_issues_new_session_id() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    let authenticated = app.cookie().unwrap();
+
+    let response = app.post_json("/auth/logout", &json!({})).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert!(response.header("set-cookie").is_some());
+    assert_ne!(app.cookie().unwrap(), authenticated);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+
+    app.set_cookie(Some(authenticated));
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+}
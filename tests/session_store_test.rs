@@ -0,0 +1,128 @@
+/*!
+ * SQLite Session Store Tests
+ *
+ * Covers `SqliteStore`: records round-trip through users.db, expired sessions are
+ * not loaded and are purged by maintenance, deleted sessions are gone, id
+ * collisions on create get a new id, and a login survives building a fresh
+ * router over the same database while in-memory sessions do not.
+ */
+
+use axum::http::StatusCode;
+use my_budget_server::database::init_main_db;
+use my_budget_server::maintenance::run_maintenance;
+use my_budget_server::models::PublicUser;
+use my_budget_server::session_store::{SqliteStore, purge_expired_sessions};
+use my_budget_server::test_support::{TEST_USERNAME, TestApp};
+use serde_json::json;
+use tempfile::tempdir;
+use time::{Duration, OffsetDateTime};
+use tower_sessions::SessionStore;
+use tower_sessions::session::{Id, Record};
+
+fn record_expiring_in(duration: Duration) -> Record {
+    Record {
+        id: Id::default(),
+        data: [("user_id".to_string(), json!("someone"))]
+            .into_iter()
+            .collect(),
+        expiry_date: OffsetDateTime::now_utc() + duration,
+    }
+}
+
+#[tokio::test]
+async fn test_round_trip_and_delete() {
+    let temp_dir = tempdir().unwrap();
+    let db = init_main_db(temp_dir.path().to_str().unwrap())
+        .await
+        .unwrap();
+    let store = SqliteStore::new(db);
+
+    let mut record = record_expiring_in(Duration::minutes(30));
+    store.create(&mut record).await.unwrap();
+    assert_eq!(store.load(&record.id).await.unwrap(), Some(record.clone()));
+
+    record.data.insert("username".to_string(), json!("renamed"));
+    store.save(&record).await.unwrap();
+    assert_eq!(store.load(&record.id).await.unwrap(), Some(record.clone()));
+
+    store.delete(&record.id).await.unwrap();
+    assert_eq!(store.load(&record.id).await.unwrap(), None);
+    assert_eq!(store.load(&Id::default()).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_create_id_collision() {
+    let temp_dir = tempdir().unwrap();
+    let db = init_main_db(temp_dir.path().to_str().unwrap())
+        .await
+        .unwrap();
+    let store = SqliteStore::new(db);
+
+    let mut first = record_expiring_in(Duration::minutes(30));
+    store.create(&mut first).await.unwrap();
+    let mut second = record_expiring_in(Duration::minutes(30));
+    second.id = first.id;
+    store.create(&mut second).await.unwrap();
+
+    assert_ne!(first.id, second.id);
+    assert_eq!(store.load(&first.id).await.unwrap(), Some(first));
+}
+
+#[tokio::test]
+async fn test_expired_sessions_ignored_and_purged() {
+    let temp_dir = tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let db = init_main_db(data_path).await.unwrap();
+    let store = SqliteStore::new(db.clone());
+
+    let mut expired = record_expiring_in(Duration::minutes(-1));
+    let mut active = record_expiring_in(Duration::minutes(30));
+    store.create(&mut expired).await.unwrap();
+    store.create(&mut active).await.unwrap();
+    assert_eq!(store.load(&expired.id).await.unwrap(), None);
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    run_maintenance(&db, data_path, now).await.unwrap();
+    assert_eq!(purge_expired_sessions(&db, now).await.unwrap(), 0);
+    assert_eq!(store.load(&active.id).await.unwrap(), Some(active));
+
+    let mut rows = db
+        .read()
+        .await
+        .query("SELECT COUNT(*) FROM sessions", ())
+        .await
+        .unwrap();
+    let count: u32 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn test_login_survives_restart() {
+    let app = TestApp::with_sqlite_sessions().await;
+    let user = app.register_and_login().await;
+
+    let restarted = app.restart().await;
+    restarted.set_cookie(app.cookie());
+    let response = restarted.get("/auth/me").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let me: PublicUser = response.json();
+    assert_eq!(me.id, user.id);
+    assert_eq!(me.username, TEST_USERNAME);
+
+    // Logging out on one server logs out everywhere
+    restarted.post_json("/auth/logout", &json!({})).await;
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_memory_sessions_lost_on_restart() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+
+    let restarted = app.restart().await;
+    restarted.set_cookie(app.cookie());
+    assert_eq!(
+        restarted.get("/auth/me").await.status,
+        StatusCode::UNAUTHORIZED
+    );
+}
@@ -0,0 +1,240 @@
+/*!
+ * Split Record Tests
+ *
+ * Covers records split across categories: POST /records/split rejects splits
+ * that do not add up to the amount, category summaries count each portion under
+ * its own category, and updates and deletes keep the split rows consistent.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{CategoryTotal, Record};
+use my_budget_server::test_support::{TestApp, TestResponse};
+use serde_json::{Value, json};
+
+async fn post_split(app: &TestApp, amount: f64, category_id: &str, splits: Value) -> TestResponse {
+    app.post_json(
+        "/records/split",
+        &json!({
+            "name": "Supermarket",
+            "amount": amount,
+            "category_id": category_id,
+            "timestamp": recent_timestamp(),
+            "splits": splits,
+        }),
+    )
+    .await
+}
+
+async fn count_split_rows(data_path: &str, user_id: &str, record_id: &str) -> u32 {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM record_splits WHERE record_id = ?",
+            [record_id],
+        )
+        .await
+        .unwrap();
+    rows.next().await.unwrap().unwrap().get(0).unwrap()
+}
+
+#[tokio::test]
+async fn test_split_sum_mismatch_is_rejected() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let groceries = create_test_category_via_api(&app, "Groceries").await;
+    let household = create_test_category_via_api(&app, "Household").await;
+
+    let response = post_split(
+        &app,
+        50.0,
+        &groceries,
+        json!([
+            { "category_id": groceries, "amount": 30.0 },
+            { "category_id": household, "amount": 15.0 },
+        ]),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.text().contains("add up"));
+
+    // A single split, a repeated category or an unknown category are rejected too
+    let cases = [
+        json!([{ "category_id": groceries, "amount": 50.0 }]),
+        json!([
+            { "category_id": groceries, "amount": 25.0 },
+            { "category_id": groceries, "amount": 25.0 },
+        ]),
+        json!([
+            { "category_id": groceries, "amount": 25.0 },
+            { "category_id": "missing", "amount": 25.0 },
+        ]),
+    ];
+    for splits in cases {
+        let response = post_split(&app, 50.0, &groceries, splits.clone()).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", splits);
+    }
+
+    let response = post_split(&app, 50.0, &groceries, json!([])).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let (records, _) = get_records_from_db(&data_path, &user_id, None, None, None).await;
+    assert!(records.is_empty());
+}
+
+#[tokio::test]
+async fn test_split_record_round_trip() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let groceries = create_test_category_via_api(&app, "Groceries").await;
+    let household = create_test_category_via_api(&app, "Household").await;
+
+    // Parts are compared in cents, so 0.1 + 0.2 style rounding is accepted
+    let response = post_split(
+        &app,
+        0.3,
+        &groceries,
+        json!([
+            { "category_id": household, "amount": 0.1 },
+            { "category_id": groceries, "amount": 0.2 },
+        ]),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let record: Record = response.json();
+    assert_eq!(record.splits.len(), 2);
+
+    let response = app.get("/records").await;
+    let body: Value = response.json();
+    let splits = body["records"][0]["splits"].as_array().unwrap();
+    let mut categories: Vec<&str> = splits
+        .iter()
+        .map(|split| split["category_id"].as_str().unwrap())
+        .collect();
+    categories.sort_unstable();
+    let mut expected = vec![groceries.as_str(), household.as_str()];
+    expected.sort_unstable();
+    assert_eq!(categories, expected);
+}
+
+#[tokio::test]
+async fn test_category_summary_counts_split_portions() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let groceries = create_test_category_via_api(&app, "Groceries").await;
+    let household = create_test_category_via_api(&app, "Household").await;
+
+    let response = post_split(
+        &app,
+        80.0,
+        &groceries,
+        json!([
+            { "category_id": groceries, "amount": 55.0 },
+            { "category_id": household, "amount": 25.0 },
+        ]),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Soap",
+        10.0,
+        &household,
+        recent_timestamp(),
+    )
+    .await;
+
+    let response = app.get("/records/summary/by-category").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let totals: Vec<CategoryTotal> = response.json();
+    let summary: Vec<(&str, f64, u32)> = totals
+        .iter()
+        .map(|t| (t.category_name.as_str(), t.total_amount, t.record_count))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![("Groceries", 55.0, 1), ("Household", 35.0, 2)]
+    );
+}
+
+#[tokio::test]
+async fn test_update_keeps_splits_consistent() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let groceries = create_test_category_via_api(&app, "Groceries").await;
+    let household = create_test_category_via_api(&app, "Household").await;
+
+    let response = post_split(
+        &app,
+        80.0,
+        &groceries,
+        json!([
+            { "category_id": groceries, "amount": 55.0 },
+            { "category_id": household, "amount": 25.0 },
+        ]),
+    )
+    .await;
+    let record: Record = response.json();
+    let path = format!("/records/{}", record.id);
+
+    // Changing only the amount would leave the splits out of balance
+    let response = app.put_json(&path, &json!({ "amount": 90.0 })).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .put_json(
+            &path,
+            &json!({
+                "amount": 90.0,
+                "splits": [
+                    { "category_id": groceries, "amount": 60.0 },
+                    { "category_id": household, "amount": 30.0 },
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let updated: Record = response.json();
+    assert_eq!(updated.splits[0].amount + updated.splits[1].amount, 90.0);
+
+    // An empty list turns it back into a plain record
+    let response = app.put_json(&path, &json!({ "splits": [] })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json::<Record>().splits.is_empty());
+    assert_eq!(count_split_rows(&data_path, &user_id, &record.id).await, 0);
+
+    let history: Value = app.get(&format!("{}/history", path)).await.json();
+    assert_eq!(history[0]["changes"]["splits"]["new"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_delete_removes_splits() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let groceries = create_test_category_via_api(&app, "Groceries").await;
+    let household = create_test_category_via_api(&app, "Household").await;
+
+    let response = post_split(
+        &app,
+        80.0,
+        &groceries,
+        json!([
+            { "category_id": groceries, "amount": 55.0 },
+            { "category_id": household, "amount": 25.0 },
+        ]),
+    )
+    .await;
+    let record: Record = response.json();
+    assert_eq!(count_split_rows(&data_path, &user_id, &record.id).await, 2);
+
+    // A category holding a split portion cannot be deleted
+    let response = app.delete(&format!("/categories/{}", household)).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let response = app.delete(&format!("/records/{}", record.id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(count_split_rows(&data_path, &user_id, &record.id).await, 0);
+
+    let response = app.delete(&format!("/categories/{}", household)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+}
@@ -0,0 +1,145 @@
+/*!
+ * Starred Records Tests
+ *
+ * Covers the `starred` flag: toggling it through POST/DELETE /records/{id}/star
+ * and through a regular update, the `starred` filter of GET /records, and that
+ * the flag survives updates to other fields.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::Record;
+use my_budget_server::test_support::TestApp;
+use serde_json::{Value, json};
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+async fn star(app: &TestApp, record_id: &str) -> Record {
+    let response = app
+        .post_json(&format!("/records/{}/star", record_id), &json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    response.json()
+}
+
+async fn names(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app.get(&format!("/records?{}", query)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: Value = response.json();
+    body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn star_and_unstar_a_record() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Deposit",
+        500.0,
+        "c",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let record = star(&app, &record_id).await;
+    assert!(record.starred);
+    assert_eq!(record.version, 2);
+
+    // Starring again leaves the record as it is
+    let record = star(&app, &record_id).await;
+    assert!(record.starred);
+    assert_eq!(record.version, 2);
+
+    let response = app.delete(&format!("/records/{}/star", record_id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let record: Record = response.json();
+    assert!(!record.starred);
+    assert_eq!(record.version, 3);
+
+    let response = app.delete("/records/missing/star").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn filter_by_starred() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let deposit = create_test_record(
+        &data_path,
+        &user_id,
+        "Deposit",
+        500.0,
+        "c",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Coffee",
+        3.0,
+        "c",
+        TEST_BASE_TIMESTAMP + 60,
+    )
+    .await;
+    let laptop = create_test_record(
+        &data_path,
+        &user_id,
+        "Laptop",
+        1500.0,
+        "c",
+        TEST_BASE_TIMESTAMP + 120,
+    )
+    .await;
+    star(&app, &deposit).await;
+    star(&app, &laptop).await;
+
+    assert_eq!(names(&app, "starred=true").await, vec!["Laptop", "Deposit"]);
+    assert_eq!(names(&app, "starred=false").await, vec!["Coffee"]);
+    assert_eq!(names(&app, "").await.len(), 3);
+}
+
+#[tokio::test]
+async fn starred_survives_unrelated_updates() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let record_id = create_test_record(
+        &data_path,
+        &user_id,
+        "Deposit",
+        500.0,
+        "c",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    star(&app, &record_id).await;
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", record_id),
+            &json!({ "name": "Security deposit", "amount": 550.0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let record: Record = response.json();
+    assert!(record.starred);
+    assert_eq!(names(&app, "starred=true").await, vec!["Security deposit"]);
+
+    // The flag can also be cleared through a regular update
+    let response = app
+        .put_json(
+            &format!("/records/{}", record_id),
+            &json!({ "starred": false }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let record: Record = response.json();
+    assert!(!record.starred);
+    assert!(names(&app, "starred=true").await.is_empty());
+}
@@ -0,0 +1,153 @@
+/*!
+ * Record Stats Tests
+ *
+ * Checks GET /records/stats aggregates against known fixtures, including the
+ * empty-range shape and the optional category filter.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::RecordStats;
+use my_budget_server::records::compute_record_stats;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+#[tokio::test]
+async fn test_stats_over_known_fixture() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    let amounts = [0.1, 0.2, 10.25, -3.5, 99.99];
+    for (i, amount) in amounts.iter().enumerate() {
+        create_test_record(
+            &data_path,
+            &user_id,
+            &format!("Item {}", i),
+            *amount,
+            "c",
+            TEST_BASE_TIMESTAMP + i as i64,
+        )
+        .await;
+    }
+
+    let stats = compute_record_stats(&user_db, 0, TEST_BASE_TIMESTAMP + 10, None, None, None)
+        .await
+        .unwrap();
+
+    let expected_sum: f64 = amounts.iter().sum();
+    assert_eq!(stats.count, 5);
+    assert!((stats.sum - expected_sum).abs() < 1e-9);
+    assert!((stats.average.unwrap() - expected_sum / 5.0).abs() < 1e-9);
+    assert_eq!(stats.min, Some(-3.5));
+    assert_eq!(stats.max, Some(99.99));
+}
+
+#[tokio::test]
+async fn test_stats_time_range_and_category_filter() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    create_test_record(&data_path, &user_id, "A", 10.0, "food", TEST_BASE_TIMESTAMP).await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "B",
+        20.0,
+        "food",
+        TEST_BASE_TIMESTAMP + 100,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "C",
+        5.0,
+        "rent",
+        TEST_BASE_TIMESTAMP + 100,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "D",
+        40.0,
+        "food",
+        TEST_BASE_TIMESTAMP + 500,
+    )
+    .await;
+
+    let stats = compute_record_stats(
+        &user_db,
+        TEST_BASE_TIMESTAMP,
+        TEST_BASE_TIMESTAMP + 100,
+        Some("food"),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        stats,
+        RecordStats {
+            count: 2,
+            sum: 30.0,
+            income_total: 0.0,
+            expense_total: 30.0,
+            average: Some(15.0),
+            min: Some(10.0),
+            max: Some(20.0),
+            currency: None,
+            unconverted: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_stats_empty_range() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(&data_path, &user_id, "A", 10.0, "food", TEST_BASE_TIMESTAMP).await;
+
+    let response = app
+        .get(&format!(
+            "/records/stats?start_time={}&end_time={}",
+            TEST_BASE_TIMESTAMP + 1,
+            TEST_BASE_TIMESTAMP + 100
+        ))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.text(),
+        r#"{"count":0,"sum":0.0,"income_total":0.0,"expense_total":0.0,"average":null,"min":null,"max":null}"#
+    );
+}
+
+#[tokio::test]
+async fn test_stats_endpoint_uses_default_window() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(&data_path, &user_id, "A", 10.0, "food", TEST_BASE_TIMESTAMP).await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "B",
+        2.5,
+        "rent",
+        TEST_BASE_TIMESTAMP + 1,
+    )
+    .await;
+
+    let response = app.get("/records/stats").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let stats: RecordStats = response.json();
+    assert_eq!(stats.count, 2);
+    assert_eq!(stats.sum, 12.5);
+
+    let response = app.get("/records/stats?category_id=rent").await;
+    let stats: RecordStats = response.json();
+    assert_eq!(stats.count, 1);
+    assert_eq!(stats.max, Some(2.5));
+}
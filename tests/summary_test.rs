@@ -0,0 +1,279 @@
+/*!
+ * Record Summary Tests
+ *
+ * Covers period bucketing for GET /records/summary (month and week boundaries,
+ * records landing exactly on a boundary, chronological order, empty ranges) and
+ * the per-category and per-tag totals of GET /records/summary/by-category and
+ * GET /records/summary/by-tag.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{CategoryTotal, SummaryBucket, TagTotal};
+use my_budget_server::records::{summarize_by_category, summarize_by_tag, summarize_records};
+
+// 2024-01-31 23:59:59 UTC, the last second of January
+const JAN_LAST_SECOND: i64 = 1706745599;
+// 2024-02-01 00:00:00 UTC (a Thursday)
+const FEB_START: i64 = 1706745600;
+// 2024-02-15 12:00:00 UTC
+const FEB_MID: i64 = 1707998400;
+// 2024-03-01 00:00:00 UTC
+const MAR_START: i64 = 1709251200;
+// 2024-02-04 23:59:59 UTC, the last second of a Sunday
+const SUNDAY_LAST_SECOND: i64 = 1707091199;
+// 2024-02-05 00:00:00 UTC, the following Monday
+const MONDAY_START: i64 = 1707091200;
+
+fn periods(buckets: &[SummaryBucket]) -> Vec<&str> {
+    buckets.iter().map(|b| b.period.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_summary_month_boundaries() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    create_test_record(&data_path, &user_id, "Late Jan", 10.0, "c", JAN_LAST_SECOND).await;
+    create_test_record(&data_path, &user_id, "Feb 1st", 20.0, "c", FEB_START).await;
+    create_test_record(&data_path, &user_id, "Mid Feb", 5.5, "c", FEB_MID).await;
+    create_test_record(&data_path, &user_id, "Mar 1st", -3.0, "c", MAR_START).await;
+
+    let buckets = summarize_records(&user_db, "month", 0, MAR_START, &[], 0)
+        .await
+        .unwrap();
+
+    assert_eq!(periods(&buckets), vec!["2024-01", "2024-02", "2024-03"]);
+    assert_eq!(buckets[0].count, 1);
+    assert_eq!(buckets[0].total, 10.0);
+    assert_eq!(buckets[1].count, 2);
+    assert_eq!(buckets[1].total, 25.5);
+    assert_eq!(buckets[2].count, 1);
+    assert_eq!(buckets[2].total, -3.0);
+}
+
+#[tokio::test]
+async fn test_summary_range_bounds_are_inclusive() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    create_test_record(&data_path, &user_id, "Late Jan", 10.0, "c", JAN_LAST_SECOND).await;
+    create_test_record(&data_path, &user_id, "Feb 1st", 20.0, "c", FEB_START).await;
+    create_test_record(&data_path, &user_id, "Mar 1st", 30.0, "c", MAR_START).await;
+
+    // A range starting exactly on the February boundary excludes January only
+    let buckets = summarize_records(&user_db, "month", FEB_START, MAR_START, &[], 0)
+        .await
+        .unwrap();
+    assert_eq!(periods(&buckets), vec!["2024-02", "2024-03"]);
+
+    // Ending one second before March drops the boundary record
+    let buckets = summarize_records(&user_db, "month", FEB_START, MAR_START - 1, &[], 0)
+        .await
+        .unwrap();
+    assert_eq!(periods(&buckets), vec!["2024-02"]);
+}
+
+#[tokio::test]
+async fn test_summary_week_and_day_buckets() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    create_test_record(&data_path, &user_id, "Thursday", 1.0, "c", FEB_START).await;
+    create_test_record(&data_path, &user_id, "Sunday", 2.0, "c", SUNDAY_LAST_SECOND).await;
+    create_test_record(&data_path, &user_id, "Monday", 4.0, "c", MONDAY_START).await;
+
+    // Weeks start on Monday; Sunday's last second still belongs to the earlier week
+    let buckets = summarize_records(&user_db, "week", 0, MONDAY_START, &[], 0)
+        .await
+        .unwrap();
+    assert_eq!(periods(&buckets), vec!["2024-01-29", "2024-02-05"]);
+    assert_eq!(buckets[0].count, 2);
+    assert_eq!(buckets[0].total, 3.0);
+    assert_eq!(buckets[1].total, 4.0);
+
+    let buckets = summarize_records(&user_db, "day", 0, MONDAY_START, &[], 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        periods(&buckets),
+        vec!["2024-02-01", "2024-02-04", "2024-02-05"]
+    );
+}
+
+#[tokio::test]
+async fn test_summary_empty_range_returns_empty_array() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(&data_path, &user_id, "Feb 1st", 20.0, "c", FEB_START).await;
+
+    let response = app
+        .get(&format!(
+            "/records/summary?group_by=month&start_time={}&end_time={}",
+            MAR_START,
+            MAR_START + 86400
+        ))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.text(), "[]");
+}
+
+#[tokio::test]
+async fn test_summary_endpoint() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(&data_path, &user_id, "Late Jan", 10.0, "c", JAN_LAST_SECOND).await;
+    create_test_record(&data_path, &user_id, "Feb 1st", 20.0, "c", FEB_START).await;
+
+    let response = app.get("/records/summary?group_by=month").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let buckets: Vec<SummaryBucket> = response.json();
+    assert_eq!(periods(&buckets), vec!["2024-01", "2024-02"]);
+
+    let response = app.get("/records/summary?group_by=year").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text(),
+        "group_by must be 'day', 'week' or 'month', got 'year'"
+    );
+}
+
+#[tokio::test]
+async fn test_category_summary_orders_by_absolute_total() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let rent = create_test_category_via_api(&app, "Rent").await;
+    let salary = create_test_category_via_api(&app, "Salary").await;
+
+    create_test_record(&data_path, &user_id, "Lunch", -12.5, &food, FEB_START).await;
+    create_test_record(&data_path, &user_id, "Dinner", -30.0, &food, FEB_MID).await;
+    create_test_record(&data_path, &user_id, "Rent", -800.0, &rent, FEB_START).await;
+    create_test_record(&data_path, &user_id, "Pay", 2500.0, &salary, FEB_MID).await;
+
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+    let totals = summarize_by_category(&user_db, FEB_START, MAR_START, false, false, &[])
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = totals.iter().map(|t| t.category_name.as_str()).collect();
+    assert_eq!(names, vec!["Salary", "Rent", "Food"]);
+    assert_eq!(totals[0].category_id.as_deref(), Some(salary.as_str()));
+    assert_eq!(totals[0].total_amount, 2500.0);
+    assert_eq!(totals[2].total_amount, -42.5);
+    assert_eq!(totals[2].record_count, 2);
+}
+
+#[tokio::test]
+async fn test_category_summary_groups_orphaned_records_as_unknown() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+
+    create_test_record(&data_path, &user_id, "Lunch", -10.0, &food, FEB_START).await;
+    create_test_record(&data_path, &user_id, "Gone 1", -5.0, "deleted-1", FEB_START).await;
+    create_test_record(&data_path, &user_id, "Gone 2", -7.0, "deleted-2", FEB_MID).await;
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-category?start_time={}&end_time={}",
+            FEB_START, MAR_START
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let totals: Vec<CategoryTotal> = response.json();
+
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[0].category_id, None);
+    assert_eq!(totals[0].category_name, "unknown");
+    assert_eq!(totals[0].total_amount, -12.0);
+    assert_eq!(totals[0].record_count, 2);
+    assert_eq!(totals[1].category_name, "Food");
+}
+
+#[tokio::test]
+async fn test_category_summary_empty_range() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &user_id, "Lunch", -10.0, &food, FEB_START).await;
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-category?start_time={}&end_time={}",
+            MAR_START,
+            MAR_START + 86400
+        ))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.text(), "[]");
+}
+
+async fn tag_record(data_path: &str, user_id: &str, record_id: &str, tags: &[&str]) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.write().await;
+    for tag in tags {
+        conn.execute(
+            "INSERT INTO record_tags (record_id, tag) VALUES (?, ?)",
+            (record_id, *tag),
+        )
+        .await
+        .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_tag_summary_counts_records_toward_every_tag() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    let flight = create_test_record(&data_path, &user_id, "Flight", -400.0, "c", FEB_START).await;
+    let hotel = create_test_record(&data_path, &user_id, "Hotel", -250.0, "c", FEB_MID).await;
+    let taxi = create_test_record(&data_path, &user_id, "Taxi", -30.0, "c", FEB_MID).await;
+    create_test_record(&data_path, &user_id, "Groceries", -60.0, "c", FEB_MID).await;
+    tag_record(&data_path, &user_id, &flight, &["travel", "vacation"]).await;
+    tag_record(&data_path, &user_id, &hotel, &["vacation"]).await;
+    tag_record(&data_path, &user_id, &taxi, &["travel"]).await;
+
+    let totals = summarize_by_tag(&user_db, FEB_START, MAR_START)
+        .await
+        .unwrap();
+
+    let tags: Vec<Option<&str>> = totals.iter().map(|t| t.tag.as_deref()).collect();
+    assert_eq!(tags, vec![Some("vacation"), Some("travel"), None]);
+    assert_eq!(totals[0].total_amount, -650.0);
+    assert_eq!(totals[0].record_count, 2);
+    assert_eq!(totals[1].total_amount, -430.0);
+    assert_eq!(totals[1].record_count, 2);
+    assert_eq!(totals[2].total_amount, -60.0);
+    assert_eq!(totals[2].record_count, 1);
+    assert_eq!(totals[2].currency, "USD");
+}
+
+#[tokio::test]
+async fn test_tag_summary_endpoint_empty_range() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let lunch = create_test_record(&data_path, &user_id, "Lunch", -10.0, "c", FEB_START).await;
+    tag_record(&data_path, &user_id, &lunch, &["work"]).await;
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-tag?start_time={}&end_time={}",
+            FEB_START, MAR_START
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let totals: Vec<TagTotal> = response.json();
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].tag.as_deref(), Some("work"));
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-tag?start_time={}&end_time={}",
+            MAR_START,
+            MAR_START + 86400
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.text(), "[]");
+}
@@ -0,0 +1,309 @@
+/*!
+ * Sync Integration Tests
+ *
+ * Exercises the chunked GET /sync endpoint through the HTTP layer: continuation
+ * across chunks, the final watermark, records and categories written mid-sync,
+ * archived records, and token validation/expiry, including tokens from before
+ * data was archived or purged.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::SyncResponse;
+use my_budget_server::sync::{SyncEntity, SyncToken, encode_sync_token};
+use serde_json::json;
+use std::collections::HashSet;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+// 2024-01-01 00:00:00 UTC
+const JAN_2024: i64 = 1704067200;
+
+async fn insert_fixture_records(data_path: &str, user_id: &str, count: i64) {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.write().await;
+    let tx = conn.transaction().await.unwrap();
+    for i in 0..count {
+        tx.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES (?, ?, ?, ?, ?)",
+            (
+                uuid::Uuid::new_v4().to_string(),
+                format!("Fixture {}", i),
+                1.0 + i as f64,
+                "fixture",
+                TEST_BASE_TIMESTAMP + i,
+            ),
+        )
+        .await
+        .unwrap();
+    }
+    tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn sync_chunks_cover_every_record_exactly_once() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, 2500).await;
+
+    let mut seen = HashSet::new();
+    let mut chunks = 0;
+    let mut continuation: Option<String> = None;
+
+    loop {
+        let path = match &continuation {
+            Some(token) => format!("/sync?continuation={}", token),
+            None => "/sync".to_string(),
+        };
+        let response = app.get(&path).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+        let chunk: SyncResponse = response.json();
+        chunks += 1;
+        assert!(chunk.records.len() <= 1000);
+        for record in chunk.records {
+            assert!(seen.insert(record.id), "record returned twice");
+        }
+
+        if chunk.complete {
+            assert!(chunk.continuation.is_none());
+            assert!(chunk.server_time.is_some());
+            break;
+        }
+
+        // Intermediate chunks never advance the watermark
+        assert!(chunk.server_time.is_none());
+        continuation = chunk.continuation;
+        assert!(continuation.is_some());
+    }
+
+    assert_eq!(chunks, 3);
+    assert_eq!(seen.len(), 2500);
+}
+
+#[tokio::test]
+async fn sync_delivers_records_written_behind_the_cursor() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, 4).await;
+
+    let chunk: SyncResponse = app.get("/sync?limit=2").await.json();
+    assert_eq!(chunk.records.len(), 2);
+    let mut seen: HashSet<String> = chunk.records.into_iter().map(|r| r.id).collect();
+
+    // An id sorting before every id already handed out
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    {
+        let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+        let conn = user_db.write().await;
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp, created_at, updated_at) VALUES ('0', 'Late', 1.0, 'fixture', ?, ?, ?)",
+            (TEST_BASE_TIMESTAMP, now, now),
+        )
+        .await
+        .unwrap();
+    }
+
+    let mut continuation = chunk.continuation;
+    while let Some(token) = continuation {
+        let response = app
+            .get(&format!("/sync?limit=2&continuation={}", token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let chunk: SyncResponse = response.json();
+        for record in chunk.records {
+            assert!(seen.insert(record.id), "record returned twice");
+        }
+        continuation = chunk.continuation;
+    }
+
+    assert_eq!(seen.len(), 5);
+    assert!(seen.contains("0"));
+}
+
+#[tokio::test]
+async fn sync_includes_archived_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, 5).await;
+    let response = app
+        .post_json("/records/archive", &json!({ "before": JAN_2024 }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let response = app.get("/sync").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let chunk: SyncResponse = response.json();
+    assert!(chunk.complete);
+    assert_eq!(chunk.records.len(), 5);
+}
+
+#[tokio::test]
+async fn sync_includes_categories_before_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Groceries").await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Market",
+        12.5,
+        &category_id,
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let first: SyncResponse = app.get("/sync?limit=1").await.json();
+    assert_eq!(first.categories.len(), 1);
+    assert!(first.records.is_empty());
+    assert!(!first.complete);
+
+    let second: SyncResponse = app
+        .get(&format!(
+            "/sync?limit=1&continuation={}",
+            first.continuation.unwrap()
+        ))
+        .await
+        .json();
+    assert!(second.categories.is_empty());
+    assert_eq!(second.records.len(), 1);
+    assert_eq!(second.records[0].name, "Market");
+}
+
+#[tokio::test]
+async fn sync_delivers_categories_updated_behind_the_cursor() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let mut ids = Vec::new();
+    for name in ["Food", "Rent", "Travel"] {
+        ids.push(create_test_category_via_api(&app, name).await);
+    }
+    {
+        // Spread out in the past, so the walk order is fixed
+        let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+        let conn = user_db.write().await;
+        for (i, id) in ids.iter().enumerate() {
+            conn.execute(
+                "UPDATE categories SET updated_at = ? WHERE id = ?",
+                (TEST_BASE_TIMESTAMP + i as i64, id.as_str()),
+            )
+            .await
+            .unwrap();
+        }
+    }
+
+    let first: SyncResponse = app.get("/sync?limit=2").await.json();
+    let names: Vec<&str> = first.categories.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["Food", "Rent"]);
+
+    // Renamed after it was handed out, so it comes again after the cursor
+    let response = app
+        .put_json(
+            &format!("/categories/{}", ids[0]),
+            &json!({ "name": "Groceries" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let second: SyncResponse = app
+        .get(&format!(
+            "/sync?limit=2&continuation={}",
+            first.continuation.unwrap()
+        ))
+        .await
+        .json();
+    let names: Vec<&str> = second.categories.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["Travel", "Groceries"]);
+}
+
+#[tokio::test]
+async fn sync_restarts_after_data_is_purged() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    insert_fixture_records(&data_path, &user_id, 20).await;
+
+    let first: SyncResponse = app.get("/sync?limit=5").await.json();
+    let continuation = first.continuation.unwrap();
+
+    // Nothing removed yet, so the sync resumes
+    let path = format!("/sync?limit=5&continuation={}", continuation);
+    assert_eq!(app.get(&path).await.status, StatusCode::OK);
+
+    let response = app
+        .delete(&format!(
+            "/records?start_time={}&end_time={}&confirm=true",
+            TEST_BASE_TIMESTAMP,
+            TEST_BASE_TIMESTAMP + 9
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+
+    let response = app.get(&path).await;
+    assert_eq!(response.status, StatusCode::GONE);
+    assert!(response.text().contains("restart sync"));
+
+    // A fresh sync sees what is left
+    let mut seen = 0;
+    let mut chunk: SyncResponse = app.get("/sync?limit=5").await.json();
+    loop {
+        seen += chunk.records.len();
+        let Some(continuation) = chunk.continuation else {
+            break;
+        };
+        let response = app
+            .get(&format!("/sync?limit=5&continuation={}", continuation))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        chunk = response.json();
+    }
+    assert_eq!(seen, 10);
+}
+
+#[tokio::test]
+async fn sync_empty_database_completes_immediately() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let chunk: SyncResponse = app.get("/sync").await.json();
+
+    assert!(chunk.complete);
+    assert!(chunk.records.is_empty());
+    assert!(chunk.categories.is_empty());
+    assert!(chunk.server_time.is_some());
+}
+
+#[tokio::test]
+async fn sync_rejects_tampered_token() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app.get("/sync?continuation=not-a-token").await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.text(), "Invalid continuation token");
+}
+
+#[tokio::test]
+async fn sync_expired_token_requires_restart() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let stale = encode_sync_token(&SyncToken {
+        entity: SyncEntity::Records,
+        last_id: String::new(),
+        last_updated_at: 0,
+        server_time: TEST_BASE_TIMESTAMP,
+        data_horizon: 0,
+    });
+    let response = app.get(&format!("/sync?continuation={}", stale)).await;
+
+    assert_eq!(response.status, StatusCode::GONE);
+    assert!(response.text().contains("restart sync"));
+}
+
+#[tokio::test]
+async fn sync_rejects_out_of_range_chunk_size() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    assert_eq!(
+        app.get("/sync?limit=0").await.status,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        app.get("/sync?limit=100000").await.status,
+        StatusCode::BAD_REQUEST
+    );
+}
@@ -0,0 +1,202 @@
+/*!
+ * Record Tags Tests
+ *
+ * Covers tags on records through the HTTP layer: normalization on create and
+ * update, limits, filtering GET /records by tag, and cleanup on delete.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::constants::{MAX_TAG_LENGTH, MAX_TAGS_PER_RECORD};
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::Record;
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+
+async fn create_tagged_record(
+    app: &TestApp,
+    category_id: &str,
+    name: &str,
+    tags: &[&str],
+) -> Record {
+    create_record_via_api(
+        app,
+        &json!({
+            "name": name,
+            "amount": 12.5,
+            "category_id": category_id,
+            "timestamp": recent_timestamp(),
+            "tags": tags,
+        }),
+    )
+    .await
+}
+
+async fn count_tag_rows(data_path: &str, user_id: &str, record_id: &str) -> u32 {
+    let user_db = get_user_db(data_path, user_id).await.unwrap();
+    let conn = user_db.read().await;
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM record_tags WHERE record_id = ?",
+            [record_id],
+        )
+        .await
+        .unwrap();
+    rows.next().await.unwrap().unwrap().get(0).unwrap()
+}
+
+#[tokio::test]
+async fn test_create_record_with_normalized_tags() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Travel").await;
+
+    let record = create_tagged_record(
+        &app,
+        &category_id,
+        "Hotel",
+        &[" Vacation ", "reimbursable", "VACATION"],
+    )
+    .await;
+
+    assert_eq!(record.tags, vec!["reimbursable", "vacation"]);
+
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(
+        body["records"][0]["tags"],
+        json!(["reimbursable", "vacation"])
+    );
+}
+
+#[tokio::test]
+async fn test_records_without_tags_have_empty_list() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Food").await;
+
+    let record = create_tagged_record(&app, &category_id, "Lunch", &[]).await;
+
+    assert!(record.tags.is_empty());
+    let response = app.get("/records").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["records"][0]["tags"], json!([]));
+}
+
+#[tokio::test]
+async fn test_filter_records_by_tag() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Travel").await;
+
+    create_tagged_record(&app, &category_id, "Hotel", &["vacation", "reimbursable"]).await;
+    create_tagged_record(&app, &category_id, "Flight", &["vacation"]).await;
+    create_tagged_record(&app, &category_id, "Taxi", &["work"]).await;
+
+    // The filter value is normalized like stored tags
+    let response = app.get("/records?tag=%20Vacation").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 2);
+    let mut names: Vec<&str> = body["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["Flight", "Hotel"]);
+
+    let response = app.get("/records?tag=reimbursable&limit=1").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["records"][0]["name"], "Hotel");
+
+    let response = app.get("/records?tag=unused").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 0);
+}
+
+#[tokio::test]
+async fn test_update_replaces_tags() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Travel").await;
+    let record = create_tagged_record(&app, &category_id, "Hotel", &["vacation"]).await;
+
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "tags": ["Work", "reimbursable"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let updated: Record = response.json();
+    assert_eq!(updated.tags, vec!["reimbursable", "work"]);
+
+    // Updating other fields keeps the tags
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "name": "Hotel night" }),
+        )
+        .await;
+    let updated: Record = response.json();
+    assert_eq!(updated.tags, vec!["reimbursable", "work"]);
+
+    let response = app.get("/records?tag=vacation").await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_count"], 0);
+}
+
+#[tokio::test]
+async fn test_delete_record_removes_tags() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Travel").await;
+    let record = create_tagged_record(&app, &category_id, "Hotel", &["vacation", "work"]).await;
+    assert_eq!(count_tag_rows(&data_path, &user_id, &record.id).await, 2);
+
+    let response = app.delete(&format!("/records/{}", record.id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    assert_eq!(count_tag_rows(&data_path, &user_id, &record.id).await, 0);
+}
+
+#[tokio::test]
+async fn test_tag_limits() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+    let category_id = create_test_category_via_api(&app, "Travel").await;
+
+    let too_many: Vec<String> = (0..=MAX_TAGS_PER_RECORD)
+        .map(|i| format!("tag{}", i))
+        .collect();
+    let too_long = "x".repeat(MAX_TAG_LENGTH + 1);
+
+    for (tags, expected) in [
+        (
+            json!(too_many),
+            format!(
+                "A record cannot have more than {} tags",
+                MAX_TAGS_PER_RECORD
+            ),
+        ),
+        (
+            json!([too_long]),
+            format!("Tag must be less than {} characters", MAX_TAG_LENGTH),
+        ),
+        (json!(["  "]), "Tag cannot be empty".to_string()),
+    ] {
+        let response = app
+            .post_json(
+                "/records",
+                &json!({
+                    "name": "Hotel",
+                    "amount": 10.0,
+                    "category_id": category_id,
+                    "timestamp": recent_timestamp(),
+                    "tags": tags,
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.text(), expected);
+    }
+}
@@ -0,0 +1,196 @@
+/*!
+ * Daily Time Series Tests
+ *
+ * Covers GET /records/timeseries: UTC day bucketing (including records exactly
+ * at midnight), zero-filled gaps, the expense and currency filters, and the
+ * bounded time range.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::TimeseriesPoint;
+use my_budget_server::records::daily_timeseries;
+
+// 2024-05-02 23:59:59 UTC
+const MAY_2_LAST_SECOND: i64 = 1714694399;
+// 2024-05-03 00:00:00 UTC
+const MAY_3_MIDNIGHT: i64 = 1714694400;
+// 2024-05-05 09:30:00 UTC
+const MAY_5_MORNING: i64 = 1714901400;
+// 2024-05-01 00:00:00 UTC
+const MAY_1_START: i64 = 1714521600;
+// 2024-05-06 00:00:00 UTC
+const MAY_6_START: i64 = 1714953600;
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn dates(points: &[TimeseriesPoint]) -> Vec<&str> {
+    points.iter().map(|p| p.date.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_midnight_record_belongs_to_new_day() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    create_test_record(&data_path, &user_id, "Late", 10.0, "c", MAY_2_LAST_SECOND).await;
+    create_test_record(&data_path, &user_id, "Midnight", 20.0, "c", MAY_3_MIDNIGHT).await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Later",
+        12.5,
+        "c",
+        MAY_3_MIDNIGHT + 60,
+    )
+    .await;
+
+    let points = daily_timeseries(&user_db, MAY_1_START, MAY_6_START, "USD", false, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        points,
+        vec![
+            TimeseriesPoint {
+                date: "2024-05-02".to_string(),
+                total: 10.0,
+                count: 1,
+            },
+            TimeseriesPoint {
+                date: "2024-05-03".to_string(),
+                total: 32.5,
+                count: 2,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_fill_adds_empty_days() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    create_test_record(&data_path, &user_id, "Midnight", 20.0, "c", MAY_3_MIDNIGHT).await;
+    create_test_record(&data_path, &user_id, "Morning", 5.0, "c", MAY_5_MORNING).await;
+
+    // The window ends exactly at midnight, so May 6th is included
+    let points = daily_timeseries(&user_db, MAY_1_START, MAY_6_START, "USD", true, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        dates(&points),
+        vec![
+            "2024-05-01",
+            "2024-05-02",
+            "2024-05-03",
+            "2024-05-04",
+            "2024-05-05",
+            "2024-05-06"
+        ]
+    );
+    let totals: Vec<(f64, u32)> = points.iter().map(|p| (p.total, p.count)).collect();
+    assert_eq!(
+        totals,
+        vec![(0.0, 0), (0.0, 0), (20.0, 1), (0.0, 0), (5.0, 1), (0.0, 0)]
+    );
+}
+
+#[tokio::test]
+async fn test_only_expenses_in_currency_are_counted() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    create_test_record(&data_path, &user_id, "Lunch", 10.0, "c", MAY_3_MIDNIGHT).await;
+    let salary =
+        create_test_record(&data_path, &user_id, "Salary", 900.0, "c", MAY_3_MIDNIGHT).await;
+    let euro = create_test_record(&data_path, &user_id, "Cafe", 4.0, "c", MAY_3_MIDNIGHT).await;
+    {
+        let conn = user_db.write().await;
+        conn.execute(
+            "UPDATE records SET kind = 'income' WHERE id = ?",
+            [salary.as_str()],
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "UPDATE records SET currency = 'EUR' WHERE id = ?",
+            [euro.as_str()],
+        )
+        .await
+        .unwrap();
+    }
+
+    let points = daily_timeseries(&user_db, MAY_1_START, MAY_6_START, "USD", false, 0)
+        .await
+        .unwrap();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].total, 10.0);
+    assert_eq!(points[0].count, 1);
+
+    let points = daily_timeseries(&user_db, MAY_1_START, MAY_6_START, "EUR", false, 0)
+        .await
+        .unwrap();
+    assert_eq!(points[0].total, 4.0);
+}
+
+#[tokio::test]
+async fn test_timeseries_endpoint() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(&data_path, &user_id, "Midnight", 20.0, "c", MAY_3_MIDNIGHT).await;
+
+    let response = app
+        .get(&format!(
+            "/records/timeseries?start_time={}&end_time={}&fill=true",
+            MAY_1_START,
+            MAY_6_START - 1
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let body: serde_json::Value = response.json();
+    assert_eq!(body.as_array().unwrap().len(), 5);
+    assert_eq!(
+        body[2],
+        serde_json::json!({ "date": "2024-05-03", "total": 20.0, "count": 1 })
+    );
+
+    // Without fill only days with records are returned
+    let response = app
+        .get(&format!(
+            "/records/timeseries?start_time={}&end_time={}",
+            MAY_1_START, MAY_6_START
+        ))
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_timeseries_range_is_bounded() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .get(&format!(
+            "/records/timeseries?start_time={}&end_time={}",
+            MAY_1_START - 800 * DAY,
+            MAY_1_START
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .get(&format!(
+            "/records/timeseries?start_time={}&end_time={}",
+            MAY_6_START, MAY_1_START
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // The default window is the last month, filled day by day
+    let response = app.get("/records/timeseries?fill=true").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body.as_array().unwrap().len(), 31);
+}
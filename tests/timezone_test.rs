@@ -0,0 +1,128 @@
+/*!
+ * Local Time Bucketing Tests
+ *
+ * Covers the `tz_offset_minutes` parameter of the summary, time series and pivot
+ * endpoints: a purchase late in the evening UTC belongs to the next local day
+ * (and here the next month) east of UTC, UTC stays the default, and offsets
+ * outside -840..=840 minutes are rejected.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::models::{PivotTable, SummaryBucket, TimeseriesPoint};
+use my_budget_server::test_support::TestApp;
+
+/// April 30, 2024 23:30 UTC, already May 1 at UTC+2
+const APR_30_2330_UTC: i64 = 1714519800;
+const APR_29_START: i64 = 1714348800;
+const MAY_3_START: i64 = 1714694400;
+
+fn window() -> String {
+    format!("start_time={}&end_time={}", APR_29_START, MAY_3_START)
+}
+
+async fn summary_periods(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app
+        .get(&format!("/records/summary?{}&{}", window(), query))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let buckets: Vec<SummaryBucket> = response.json();
+    buckets.into_iter().map(|b| b.period).collect()
+}
+
+#[tokio::test]
+async fn late_evening_utc_lands_in_the_next_local_day() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &user_id, "Dinner", 42.0, &food, APR_30_2330_UTC).await;
+
+    assert_eq!(
+        summary_periods(&app, "group_by=day").await,
+        vec!["2024-04-30"]
+    );
+    assert_eq!(
+        summary_periods(&app, "group_by=day&tz_offset_minutes=120").await,
+        vec!["2024-05-01"]
+    );
+    assert_eq!(
+        summary_periods(&app, "group_by=month&tz_offset_minutes=120").await,
+        vec!["2024-05"]
+    );
+    assert_eq!(
+        summary_periods(&app, "group_by=day&tz_offset_minutes=-300").await,
+        vec!["2024-04-30"]
+    );
+
+    let response = app
+        .get(&format!(
+            "/records/timeseries?{}&tz_offset_minutes=120",
+            window()
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let points: Vec<TimeseriesPoint> = response.json();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].date, "2024-05-01");
+    assert_eq!(points[0].total, 42.0);
+
+    let response = app.get("/records/pivot?year=2024").await;
+    let table: PivotTable = response.json();
+    assert_eq!(table.rows[0].months[3], 42.0);
+
+    let response = app
+        .get("/records/pivot?year=2024&tz_offset_minutes=120")
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let table: PivotTable = response.json();
+    assert_eq!(table.rows[0].months[3], 0.0);
+    assert_eq!(table.rows[0].months[4], 42.0);
+}
+
+#[tokio::test]
+async fn local_days_are_filled_in_local_time() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &user_id, "Dinner", 42.0, &food, APR_30_2330_UTC).await;
+
+    // The window starts at 22:00 UTC on April 30, midnight at UTC+2
+    let response = app
+        .get(&format!(
+            "/records/timeseries?start_time={}&end_time={}&fill=true&tz_offset_minutes=120",
+            APR_30_2330_UTC - 5400,
+            APR_30_2330_UTC - 5400 + 2 * 24 * 60 * 60 - 1
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let points: Vec<TimeseriesPoint> = response.json();
+    let dates: Vec<&str> = points.iter().map(|p| p.date.as_str()).collect();
+    assert_eq!(dates, vec!["2024-05-01", "2024-05-02"]);
+    assert_eq!(points[0].total, 42.0);
+}
+
+#[tokio::test]
+async fn offsets_out_of_range_are_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    for offset in ["840", "-840"] {
+        let response = app
+            .get(&format!("/records/summary?tz_offset_minutes={}", offset))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", offset);
+    }
+
+    for path in [
+        "/records/summary?tz_offset_minutes=841",
+        "/records/summary?tz_offset_minutes=-841",
+        "/records/timeseries?tz_offset_minutes=900",
+        "/records/pivot?year=2024&tz_offset_minutes=-900",
+    ] {
+        let response = app.get(path).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", path);
+        assert_eq!(
+            response.text(),
+            "tz_offset_minutes must be between -840 and 840"
+        );
+    }
+}
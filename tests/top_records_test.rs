@@ -0,0 +1,191 @@
+/*!
+ * Top Records Tests
+ *
+ * Covers GET /records/top: ordering by absolute amount, the deterministic
+ * tiebreak on timestamp then id, the category filter and validation of `n`.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::constants::MAX_LIMIT;
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::Record;
+use my_budget_server::records::top_records;
+
+const TEST_BASE_TIMESTAMP: i64 = 1700000000;
+
+fn names(records: &[Record]) -> Vec<&str> {
+    records.iter().map(|r| r.name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_largest_absolute_amounts_first() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    let fixtures = [
+        ("Coffee", 3.5),
+        ("Rent", 1200.0),
+        ("Refund", -250.0),
+        ("Groceries", 80.0),
+    ];
+    for (i, (name, amount)) in fixtures.iter().enumerate() {
+        create_test_record(
+            &data_path,
+            &user_id,
+            name,
+            *amount,
+            "c",
+            TEST_BASE_TIMESTAMP + i as i64,
+        )
+        .await;
+    }
+
+    let records = top_records(&user_db, 0, TEST_BASE_TIMESTAMP + 10, 3, None)
+        .await
+        .unwrap();
+    assert_eq!(names(&records), vec!["Rent", "Refund", "Groceries"]);
+
+    // Records outside the range are ignored
+    let records = top_records(
+        &user_db,
+        TEST_BASE_TIMESTAMP + 2,
+        TEST_BASE_TIMESTAMP + 10,
+        3,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(names(&records), vec!["Refund", "Groceries"]);
+}
+
+#[tokio::test]
+async fn test_ties_break_by_timestamp_then_id() {
+    let (data_path, user_id, _temp_dir) = setup_test_environment().await;
+    let user_db = get_user_db(&data_path, &user_id).await.unwrap();
+
+    let older = create_test_record(
+        &data_path,
+        &user_id,
+        "Older",
+        50.0,
+        "c",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    let mut newer = Vec::new();
+    for name in ["Newer A", "Newer B", "Newer C"] {
+        newer.push(
+            create_test_record(
+                &data_path,
+                &user_id,
+                name,
+                -50.0,
+                "c",
+                TEST_BASE_TIMESTAMP + 60,
+            )
+            .await,
+        );
+    }
+    newer.sort_unstable_by(|a, b| b.cmp(a));
+
+    let records = top_records(&user_db, 0, TEST_BASE_TIMESTAMP + 60, 10, None)
+        .await
+        .unwrap();
+    let ids: Vec<&str> = records.iter().map(|r| r.id.as_str()).collect();
+    let mut expected: Vec<&str> = newer.iter().map(String::as_str).collect();
+    expected.push(older.as_str());
+    assert_eq!(ids, expected);
+
+    // Repeated calls are stable
+    let again = top_records(&user_db, 0, TEST_BASE_TIMESTAMP + 60, 10, None)
+        .await
+        .unwrap();
+    let again_ids: Vec<&str> = again.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(again_ids, ids);
+}
+
+#[tokio::test]
+async fn test_top_records_endpoint_with_category_filter() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Rent",
+        1200.0,
+        "home",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Dinner",
+        60.0,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+    create_test_record(
+        &data_path,
+        &user_id,
+        "Lunch",
+        15.0,
+        "food",
+        TEST_BASE_TIMESTAMP,
+    )
+    .await;
+
+    let response = app
+        .get(&format!(
+            "/records/top?start_time={}&end_time={}&n=1",
+            TEST_BASE_TIMESTAMP, TEST_BASE_TIMESTAMP
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let records: Vec<Record> = response.json();
+    assert_eq!(names(&records), vec!["Rent"]);
+
+    let response = app
+        .get(&format!(
+            "/records/top?start_time={}&category_id=food",
+            TEST_BASE_TIMESTAMP
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let records: Vec<Record> = response.json();
+    assert_eq!(names(&records), vec!["Dinner", "Lunch"]);
+}
+
+#[tokio::test]
+async fn test_top_records_n_is_validated() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    for i in 0..12 {
+        create_test_record(
+            &data_path,
+            &user_id,
+            &format!("Item {}", i),
+            (i + 1) as f64,
+            "c",
+            TEST_BASE_TIMESTAMP,
+        )
+        .await;
+    }
+
+    // n defaults to 10
+    let response = app
+        .get(&format!("/records/top?start_time={}", TEST_BASE_TIMESTAMP))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let records: Vec<Record> = response.json();
+    assert_eq!(records.len(), 10);
+    assert_eq!(records[0].name, "Item 11");
+
+    let response = app.get("/records/top?n=0").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.get(&format!("/records/top?n={}", MAX_LIMIT + 1)).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
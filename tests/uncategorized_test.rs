@@ -0,0 +1,214 @@
+/*!
+ * Uncategorized Record Tests
+ *
+ * Covers records without a category: creating them with a null or omitted
+ * category_id, filing them under a category later, the category_id=none filter,
+ * their own bucket in the category summary and the migration that makes
+ * category_id nullable in existing databases.
+ */
+
+mod common;
+
+use axum::http::StatusCode;
+use common::*;
+use my_budget_server::constants::{UNCATEGORIZED_CATEGORY_NAME, UNKNOWN_CATEGORY_NAME};
+use my_budget_server::database::get_user_db;
+use my_budget_server::models::{CategoryTotal, Record};
+use my_budget_server::test_support::TestApp;
+use serde_json::json;
+use tempfile::tempdir;
+
+// 2024-02-01 00:00:00 UTC
+const FEB_START: i64 = 1706745600;
+
+async fn create_uncategorized_record(app: &TestApp, name: &str, amount: f64) -> Record {
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": name, "amount": amount, "timestamp": FEB_START }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    response.json()
+}
+
+#[tokio::test]
+async fn test_create_without_category_then_assign_one() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let record = create_uncategorized_record(&app, "Mystery", 12.0).await;
+    assert_eq!(record.category_id, None);
+
+    // An explicit null is the same as leaving it out
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Cash", "amount": 3.0, "category_id": null }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    let explicit: Record = response.json();
+    assert_eq!(explicit.category_id, None);
+
+    let response = app.get(&format!("/records/{}", record.id)).await;
+    let fetched: serde_json::Value = response.json();
+    assert!(fetched["category_id"].is_null());
+
+    let food = create_test_category_via_api(&app, "Food").await;
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "category_id": food }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let updated: Record = response.json();
+    assert_eq!(updated.category_id.as_deref(), Some(food.as_str()));
+
+    // Updating other fields leaves the category alone
+    let response = app
+        .put_json(
+            &format!("/records/{}", record.id),
+            &json!({ "amount": 15.0 }),
+        )
+        .await;
+    let updated: Record = response.json();
+    assert_eq!(updated.category_id.as_deref(), Some(food.as_str()));
+}
+
+#[tokio::test]
+async fn test_unknown_category_still_rejected() {
+    let (app, _data_path, _user_id) = setup_test_app().await;
+
+    let response = app
+        .post_json(
+            "/records",
+            &json!({ "name": "Lunch", "amount": 8.0, "category_id": "missing" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_filter_uncategorized_records() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    let lunch = create_test_record(&data_path, &user_id, "Lunch", 8.0, &food, FEB_START).await;
+    let mystery = create_uncategorized_record(&app, "Mystery", 12.0).await;
+
+    let response = app.get("/records?start_time=0&category_id=none").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let listed: serde_json::Value = response.json();
+    assert_eq!(listed["total_count"], 1);
+    assert_eq!(listed["records"][0]["id"], mystery.id.as_str());
+
+    let response = app
+        .get(&format!("/records?start_time=0&category_id={}", food))
+        .await;
+    let listed: serde_json::Value = response.json();
+    assert_eq!(listed["total_count"], 1);
+    assert_eq!(listed["records"][0]["id"], lunch.as_str());
+
+    // Excluding a category keeps the uncategorized records
+    let response = app
+        .get(&format!(
+            "/records?start_time=0&exclude_category_ids={}",
+            food
+        ))
+        .await;
+    let listed: serde_json::Value = response.json();
+    assert_eq!(listed["total_count"], 1);
+    assert_eq!(listed["records"][0]["id"], mystery.id.as_str());
+}
+
+#[tokio::test]
+async fn test_category_summary_has_uncategorized_bucket() {
+    let (app, data_path, user_id) = setup_test_app().await;
+    let food = create_test_category_via_api(&app, "Food").await;
+    create_test_record(&data_path, &user_id, "Lunch", 8.0, &food, FEB_START).await;
+    create_uncategorized_record(&app, "Mystery", 12.0).await;
+    // Points at a category that was never created
+    create_test_record(&data_path, &user_id, "Orphan", 5.0, "gone", FEB_START).await;
+
+    let response = app
+        .get(&format!(
+            "/records/summary/by-category?start_time={}&end_time={}",
+            FEB_START,
+            FEB_START + 3600
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let totals: Vec<CategoryTotal> = response.json();
+
+    assert_eq!(totals.len(), 3);
+    assert_eq!(totals[0].category_id, None);
+    assert_eq!(totals[0].category_name, UNCATEGORIZED_CATEGORY_NAME);
+    assert_eq!(totals[0].total_amount, 12.0);
+    assert_eq!(totals[1].category_id.as_deref(), Some(food.as_str()));
+    assert_eq!(totals[1].total_amount, 8.0);
+    assert_eq!(totals[2].category_id, None);
+    assert_eq!(totals[2].category_name, UNKNOWN_CATEGORY_NAME);
+    assert_eq!(totals[2].total_amount, 5.0);
+}
+
+#[tokio::test]
+async fn test_existing_database_allows_null_category() {
+    let temp_dir = tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+    let user_id = uuid::Uuid::new_v4().to_string();
+
+    // A user database created while category_id was required
+    {
+        let path = temp_dir.path().join(format!("user_{}.db", user_id));
+        let db = libsql::Builder::new_local(path).build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute(
+            "CREATE TABLE records (id TEXT PRIMARY KEY, name TEXT NOT NULL, amount REAL NOT NULL, category_id TEXT NOT NULL, timestamp INTEGER NOT NULL)",
+            (),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES ('old', 'Legacy rent', 1.0, 'c', 1700000000)",
+            (),
+        )
+        .await
+        .unwrap();
+    }
+
+    // Opening it twice must be idempotent
+    get_user_db(data_path, &user_id).await.unwrap();
+    let user_db = get_user_db(data_path, &user_id).await.unwrap();
+
+    let conn = user_db.write().await;
+    conn.execute(
+        "INSERT INTO records (id, name, amount, category_id, timestamp) VALUES ('new', 'Loose change', 2.0, NULL, 1700000060)",
+        (),
+    )
+    .await
+    .unwrap();
+
+    let mut rows = conn
+        .query("SELECT id, category_id FROM records ORDER BY id ASC", ())
+        .await
+        .unwrap();
+    let row = rows.next().await.unwrap().unwrap();
+    assert_eq!(row.get::<String>(0).unwrap(), "new");
+    assert_eq!(row.get::<Option<String>>(1).unwrap(), None);
+    let row = rows.next().await.unwrap().unwrap();
+    assert_eq!(row.get::<String>(0).unwrap(), "old");
+    assert_eq!(row.get::<Option<String>>(1).unwrap().as_deref(), Some("c"));
+
+    // The search index and its triggers survive the table rebuild
+    let mut rows = conn
+        .query(
+            "SELECT COUNT(*) FROM records_fts WHERE records_fts MATCH 'legacy OR loose'",
+            (),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        rows.next().await.unwrap().unwrap().get::<i64>(0).unwrap(),
+        2
+    );
+}
@@ -0,0 +1,58 @@
+/*!
+ * User Database Schema Tests
+ *
+ * Covers the schema setup of per-user databases: it runs when a database is
+ * first opened and records its version, later opens skip it, and a database at
+ * an older version is brought up to date again.
+ */
+
+use my_budget_server::database::get_user_db;
+use tempfile::tempdir;
+
+async fn user_version(conn: &libsql::Connection) -> i64 {
+    let mut rows = conn.query("PRAGMA user_version", ()).await.unwrap();
+    rows.next().await.unwrap().unwrap().get(0).unwrap()
+}
+
+async fn index_exists(conn: &libsql::Connection, name: &str) -> bool {
+    let mut rows = conn
+        .query(
+            "SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?",
+            [name],
+        )
+        .await
+        .unwrap();
+    rows.next().await.unwrap().is_some()
+}
+
+#[tokio::test]
+async fn test_schema_setup_runs_once_per_version() {
+    let temp_dir = tempdir().unwrap();
+    let data_path = temp_dir.path().to_str().unwrap();
+
+    let user_db = get_user_db(data_path, "schema-user").await.unwrap();
+    let version = {
+        let conn = user_db.write().await;
+        let version = user_version(&conn).await;
+        assert!(version > 0);
+        assert!(index_exists(&conn, "idx_records_updated_at").await);
+        conn.execute("DROP INDEX idx_records_updated_at", ())
+            .await
+            .unwrap();
+        version
+    };
+
+    // Up to date, so the setup is skipped and the dropped index stays gone
+    let user_db = get_user_db(data_path, "schema-user").await.unwrap();
+    {
+        let conn = user_db.write().await;
+        assert!(!index_exists(&conn, "idx_records_updated_at").await);
+        conn.execute("PRAGMA user_version = 0", ()).await.unwrap();
+    }
+
+    // An older version runs the setup again
+    let user_db = get_user_db(data_path, "schema-user").await.unwrap();
+    let conn = user_db.read().await;
+    assert!(index_exists(&conn, "idx_records_updated_at").await);
+    assert_eq!(user_version(&conn).await, version);
+}
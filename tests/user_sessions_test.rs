@@ -0,0 +1,161 @@
+/*!
+ * Session Listing Tests
+ *
+ * Covers GET /auth/sessions and DELETE /auth/sessions/{id}: each login shows up
+ * with its user agent and truncated address, the requesting session is flagged,
+ * a revoked session is logged out on its next request, revoking the current one
+ * logs out, and sessions of other users cannot be revoked.
+ */
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{Method, Request, StatusCode, header},
+};
+use my_budget_server::models::SessionInfo;
+use my_budget_server::test_support::{TEST_PASSWORD, TEST_USERNAME, TestApp};
+use my_budget_server::user_sessions::truncate_ip;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+
+/// Logs in as the test user on a fresh session, as from another device, and
+/// returns that session's cookie.
+async fn login_from_device(app: &TestApp, user_agent: &str, ip: &str) -> Option<String> {
+    app.set_cookie(None);
+    let body = json!({ "username": TEST_USERNAME, "password": TEST_PASSWORD });
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/auth/login")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::USER_AGENT, user_agent)
+        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        .unwrap();
+    request.extensions_mut().insert(ConnectInfo(SocketAddr::new(
+        ip.parse::<IpAddr>().unwrap(),
+        40000,
+    )));
+    let response = app.request(request).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    app.cookie()
+}
+
+#[test]
+fn test_truncate_ip() {
+    assert_eq!(truncate_ip("203.0.113.57".parse().unwrap()), "203.0.113.0");
+    assert_eq!(
+        truncate_ip("2001:db8:85a3:8d3:1319:8a2e:370:7348".parse().unwrap()),
+        "2001:db8:85a3::"
+    );
+}
+
+#[tokio::test]
+async fn test_sessions_listed_with_current_flag() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    let phone = login_from_device(&app, "Phone Browser", "203.0.113.57").await;
+    let laptop = login_from_device(&app, "Laptop Browser", "2001:db8:85a3::1").await;
+
+    let response = app.get("/auth/sessions").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let sessions: Vec<SessionInfo> = response.json();
+    assert_eq!(sessions.len(), 3);
+
+    let current: Vec<&SessionInfo> = sessions.iter().filter(|s| s.current).collect();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0].user_agent.as_deref(), Some("Laptop Browser"));
+    assert_eq!(current[0].ip.as_deref(), Some("2001:db8:85a3::"));
+
+    let phone_session = sessions
+        .iter()
+        .find(|s| s.user_agent.as_deref() == Some("Phone Browser"))
+        .unwrap();
+    assert_eq!(phone_session.ip.as_deref(), Some("203.0.113.0"));
+    assert!(phone_session.last_seen_at >= phone_session.created_at);
+    // The session cookie's id is never exposed
+    assert!(!response.text().contains(&phone.unwrap()));
+
+    app.set_cookie(laptop);
+    let response = app.get("/auth/sessions").await;
+    assert_eq!(response.json::<Vec<SessionInfo>>().len(), 3);
+}
+
+#[tokio::test]
+async fn test_revoked_session_is_logged_out() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    let phone = login_from_device(&app, "Phone Browser", "203.0.113.57").await;
+    let laptop = login_from_device(&app, "Laptop Browser", "198.51.100.7").await;
+
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    let phone_id = sessions
+        .iter()
+        .find(|s| s.user_agent.as_deref() == Some("Phone Browser"))
+        .unwrap()
+        .id
+        .clone();
+    let response = app.delete(&format!("/auth/sessions/{}", phone_id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    let response = app.delete(&format!("/auth/sessions/{}", phone_id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // The laptop is still in, the phone is out
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::OK);
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    assert_eq!(sessions.len(), 2);
+    app.set_cookie(phone);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/records").await.status, StatusCode::UNAUTHORIZED);
+
+    app.set_cookie(laptop);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_revoking_current_session_logs_out() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    let laptop = login_from_device(&app, "Laptop Browser", "198.51.100.7").await;
+
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    let current = sessions.iter().find(|s| s.current).unwrap().id.clone();
+    let response = app.delete(&format!("/auth/sessions/{}", current)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+
+    // Nor does the old cookie get back in
+    app.set_cookie(laptop);
+    assert_eq!(app.get("/auth/me").await.status, StatusCode::UNAUTHORIZED);
+
+    login_from_device(&app, "Laptop Browser", "198.51.100.7").await;
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    assert_eq!(sessions.len(), 2);
+}
+
+#[tokio::test]
+async fn test_logout_removes_session_and_others_cannot_revoke() {
+    let app = TestApp::new().await;
+    app.register_and_login().await;
+    login_from_device(&app, "Laptop Browser", "198.51.100.7").await;
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    let laptop_id = sessions.iter().find(|s| s.current).unwrap().id.clone();
+
+    app.set_cookie(None);
+    app.register_and_login_as("other_user", TEST_PASSWORD).await;
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    assert_eq!(sessions.len(), 1);
+    let response = app.delete(&format!("/auth/sessions/{}", laptop_id)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.post_json("/auth/logout", &json!({})).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    // Logging back in leaves one row, not the logged-out one beside it
+    let response = app
+        .post_json(
+            "/auth/login",
+            &json!({ "username": "other_user", "password": TEST_PASSWORD }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let sessions: Vec<SessionInfo> = app.get("/auth/sessions").await.json();
+    assert_eq!(sessions.len(), 1);
+}